@@ -0,0 +1,203 @@
+//! Consistent error strings returned across the WIT boundary
+
+use crate::types::HttpResponse;
+use std::fmt::Display;
+
+/// Longest response body excerpt included in error messages
+const MAX_BODY_EXCERPT: usize = 200;
+
+/// Error for a non-success HTTP response, including a short body excerpt
+///
+/// For 429 and 503 responses with a `Retry-After` in seconds, the delay is
+/// included as `HTTP 429, retry after 30s: ...`, so the host can pass it on
+/// to clients.
+///
+/// ```
+/// use ai_messenger_adapter_sdk::error::http_error;
+/// use ai_messenger_adapter_sdk::types::HttpResponse;
+///
+/// let response = HttpResponse {
+///     status_code: 404,
+///     headers: vec![],
+///     body: "model not found".to_string(),
+/// };
+/// assert_eq!(http_error(&response), "HTTP 404: model not found");
+/// ```
+pub fn http_error(response: &HttpResponse) -> String {
+    let status = match retry_after(response) {
+        Some(secs) if matches!(response.status_code, 429 | 503) => {
+            format!("{}, retry after {}s", response.status_code, secs)
+        }
+        _ => response.status_code.to_string(),
+    };
+    let body = response.body.trim();
+
+    if body.is_empty() {
+        return format!("HTTP {}", status);
+    }
+    format!("HTTP {}: {}", status, excerpt(body))
+}
+
+/// Seconds the provider asked to wait before retrying, from `Retry-After`
+///
+/// Only the delay-seconds form is understood, not HTTP dates.
+pub fn retry_after(response: &HttpResponse) -> Option<u64> {
+    response
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+}
+
+/// Error for a body that should have been JSON but couldn't be parsed
+///
+/// Says what arrived instead of passing on the parser's message alone:
+/// empty bodies and HTML error pages are named, and a short excerpt of the
+/// body is included.
+///
+/// ```
+/// use ai_messenger_adapter_sdk::error::invalid_json;
+///
+/// assert_eq!(
+///     invalid_json("response", "<html>Bad Gateway</html>", "expected value"),
+///     "Failed to parse response: expected JSON, got HTML: <html>Bad Gateway</html>"
+/// );
+/// ```
+pub fn invalid_json(what: &str, body: &str, error: impl Display) -> String {
+    let body = body.trim();
+
+    if body.is_empty() {
+        parse_error(what, "empty body")
+    } else if body.starts_with('<') {
+        parse_error(what, format!("expected JSON, got HTML: {}", excerpt(body)))
+    } else if !body.starts_with(['{', '[']) {
+        parse_error(what, format!("expected JSON, got: {}", excerpt(body)))
+    } else {
+        parse_error(what, format!("{} in: {}", error, excerpt(body)))
+    }
+}
+
+/// At most `MAX_BODY_EXCERPT` characters of `body`, marked when cut
+fn excerpt(body: &str) -> String {
+    match body.char_indices().nth(MAX_BODY_EXCERPT) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+/// Error for a payload that could not be parsed
+///
+/// ```
+/// use ai_messenger_adapter_sdk::error::parse_error;
+///
+/// assert_eq!(
+///     parse_error("response", "expected value"),
+///     "Failed to parse response: expected value"
+/// );
+/// ```
+pub fn parse_error(what: &str, error: impl Display) -> String {
+    format!("Failed to parse {}: {}", what, error)
+}
+
+/// Error for a feature the provider doesn't support
+pub fn unsupported(feature: &str) -> String {
+    format!("{} is not supported by this adapter", feature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status_code: u16, body: &str) -> HttpResponse {
+        HttpResponse {
+            status_code,
+            headers: vec![],
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_http_error_without_body() {
+        assert_eq!(http_error(&response(500, "  ")), "HTTP 500");
+    }
+
+    #[test]
+    fn test_http_error_truncates_long_body() {
+        let message = http_error(&response(500, &"x".repeat(1000)));
+
+        assert!(message.ends_with("..."));
+        assert!(message.len() < 1000);
+    }
+
+    #[test]
+    fn test_http_error_with_retry_after() {
+        let mut response = response(429, r#"{"error":"rate limited"}"#);
+        response
+            .headers
+            .push(("Retry-After".to_string(), "30".to_string()));
+
+        assert_eq!(
+            http_error(&response),
+            r#"HTTP 429, retry after 30s: {"error":"rate limited"}"#
+        );
+        assert_eq!(retry_after(&response), Some(30));
+
+        // Only rate limiting and unavailability carry the delay
+        response.status_code = 500;
+        assert_eq!(
+            http_error(&response),
+            r#"HTTP 500: {"error":"rate limited"}"#
+        );
+    }
+
+    #[test]
+    fn test_invalid_json_html_body() {
+        let body = format!(
+            "<!DOCTYPE html><html><body>{}</body></html>",
+            "x".repeat(500)
+        );
+
+        let message = invalid_json("response", &body, "expected value at line 1 column 1");
+
+        assert!(message
+            .starts_with("Failed to parse response: expected JSON, got HTML: <!DOCTYPE html>"));
+        assert!(message.ends_with("..."));
+        assert!(!message.contains("line 1 column 1"));
+    }
+
+    #[test]
+    fn test_invalid_json_empty_body() {
+        assert_eq!(
+            invalid_json("response", " \n", "EOF while parsing a value"),
+            "Failed to parse response: empty body"
+        );
+    }
+
+    #[test]
+    fn test_invalid_json_plain_text_body() {
+        assert_eq!(
+            invalid_json("response", "upstream connect error", "expected value"),
+            "Failed to parse response: expected JSON, got: upstream connect error"
+        );
+    }
+
+    #[test]
+    fn test_invalid_json_truncated_body() {
+        assert_eq!(
+            invalid_json(
+                "response",
+                r#"{"message":{"content":"Hel"#,
+                "EOF while parsing a string at line 1 column 26"
+            ),
+            r#"Failed to parse response: EOF while parsing a string at line 1 column 26 in: {"message":{"content":"Hel"#
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(
+            unsupported("streaming"),
+            "streaming is not supported by this adapter"
+        );
+    }
+}
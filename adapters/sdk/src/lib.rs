@@ -0,0 +1,127 @@
+//! Helpers for writing ai_messenger LLM adapters
+//!
+//! The SDK re-exports the bindings generated from `wit/llm/llm.wit` and
+//! provides the mapping code every adapter needs (roles, finish reasons, usage
+//! and error strings). An adapter only implements [`LlmProvider`] and calls
+//! [`export_adapter!`]:
+//!
+//! ```no_run
+//! use ai_messenger_adapter_sdk::types::{ChatRequest, ChatResponse, HttpConfig, HttpResponse};
+//! use ai_messenger_adapter_sdk::{LlmProvider, error, export_adapter, role};
+//!
+//! struct Echo;
+//!
+//! impl LlmProvider for Echo {
+//!     fn prepare_request(request: ChatRequest) -> Result<HttpConfig, String> {
+//!         let body = request
+//!             .messages
+//!             .iter()
+//!             .map(|message| format!("{}: {}", role::role_to_str(&message.role), message.content))
+//!             .collect::<Vec<_>>()
+//!             .join("\n");
+//!
+//!         Ok(HttpConfig {
+//!             url: "http://localhost:8000/echo".to_string(),
+//!             headers: vec![],
+//!             body,
+//!         })
+//!     }
+//!
+//!     fn parse_response(response: HttpResponse) -> Result<ChatResponse, String> {
+//!         if response.status_code != 200 {
+//!             return Err(error::http_error(&response));
+//!         }
+//!
+//!         Ok(ChatResponse {
+//!             content: response.body,
+//!             model: "echo".to_string(),
+//!             finish_reason: None,
+//!             stop_sequence: None,
+//!             usage: None,
+//!         })
+//!     }
+//! }
+//!
+//! export_adapter!(Echo);
+//! ```
+
+pub mod error;
+pub mod log;
+pub mod role;
+pub mod testing;
+pub mod usage;
+
+/// Bindings generated from the LLM adapter WIT world
+pub mod bindings {
+    wit_bindgen::generate!({
+        world: "llm-adapter",
+        path: "../../wit/llm",
+        additional_derives: [PartialEq],
+        pub_export_macro: true,
+        export_macro_name: "export_llm_adapter",
+    });
+}
+
+pub use bindings::ai_messenger::llm::types;
+
+use bindings::exports::ai_messenger::llm::llm::Guest;
+use types::{ChatRequest, ChatResponse, HttpConfig, HttpResponse, ModelInfo, StreamChunk};
+
+/// Provider-specific part of an LLM adapter
+///
+/// Every implementor is also a WIT `Guest`, so it can be exported with
+/// [`export_adapter!`] directly.
+pub trait LlmProvider {
+    /// Turn a generic chat request into the provider's HTTP request
+    fn prepare_request(request: ChatRequest) -> Result<HttpConfig, String>;
+
+    /// Turn the provider's HTTP response into a generic chat response
+    fn parse_response(response: HttpResponse) -> Result<ChatResponse, String>;
+
+    /// Parse one streaming chunk (unsupported unless overridden)
+    fn parse_stream_chunk(_chunk: String) -> Result<Option<StreamChunk>, String> {
+        Err(error::unsupported("streaming"))
+    }
+
+    /// Build the request for `model`'s metadata (unsupported unless overridden)
+    ///
+    /// When unsupported, the host falls back to values declared in its config.
+    fn prepare_model_info_request(_model: String) -> Result<HttpConfig, String> {
+        Err(error::unsupported("model info"))
+    }
+
+    /// Parse the provider's model metadata response (unsupported unless overridden)
+    fn parse_model_info_response(_response: HttpResponse) -> Result<ModelInfo, String> {
+        Err(error::unsupported("model info"))
+    }
+}
+
+impl<T: LlmProvider> Guest for T {
+    fn prepare_request(request: ChatRequest) -> Result<HttpConfig, String> {
+        <T as LlmProvider>::prepare_request(request)
+    }
+
+    fn parse_response(response: HttpResponse) -> Result<ChatResponse, String> {
+        <T as LlmProvider>::parse_response(response)
+    }
+
+    fn parse_stream_chunk(chunk: String) -> Result<Option<StreamChunk>, String> {
+        <T as LlmProvider>::parse_stream_chunk(chunk)
+    }
+
+    fn prepare_model_info_request(model: String) -> Result<HttpConfig, String> {
+        <T as LlmProvider>::prepare_model_info_request(model)
+    }
+
+    fn parse_model_info_response(response: HttpResponse) -> Result<ModelInfo, String> {
+        <T as LlmProvider>::parse_model_info_response(response)
+    }
+}
+
+/// Export an [`LlmProvider`] implementation as the adapter's WIT world
+#[macro_export]
+macro_rules! export_adapter {
+    ($provider:ident) => {
+        $crate::bindings::export_llm_adapter!($provider with_types_in $crate::bindings);
+    };
+}
@@ -0,0 +1,62 @@
+//! Logging through the host
+//!
+//! Messages end up in the host's logs with target `adapter`, tagged with
+//! the adapter's service, provider and version. The host rate-limits
+//! them, so log what helps diagnose a failure rather than every step.
+//!
+//! Outside of WASM (in unit tests) there is no host; messages are kept
+//! for [`testing::take_logs`](crate::testing::take_logs) instead.
+//!
+//! ```
+//! use ai_messenger_adapter_sdk::log;
+//!
+//! log::debug("request", "Sending request to /api/chat");
+//! ```
+
+pub use crate::bindings::ai_messenger::llm::logging::Level;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::cell::RefCell;
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    pub(crate) static CAPTURED: RefCell<Vec<(Level, String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Log `message` at `level`; `target` names the part of the adapter
+pub fn log(level: Level, target: &str, message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    crate::bindings::ai_messenger::llm::logging::log(level, target, message);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    CAPTURED.with(|captured| {
+        captured
+            .borrow_mut()
+            .push((level, target.to_string(), message.to_string()))
+    });
+}
+
+/// Log at trace level
+pub fn trace(target: &str, message: &str) {
+    log(Level::Trace, target, message);
+}
+
+/// Log at debug level
+pub fn debug(target: &str, message: &str) {
+    log(Level::Debug, target, message);
+}
+
+/// Log at info level
+pub fn info(target: &str, message: &str) {
+    log(Level::Info, target, message);
+}
+
+/// Log at warn level
+pub fn warn(target: &str, message: &str) {
+    log(Level::Warn, target, message);
+}
+
+/// Log at error level
+pub fn error(target: &str, message: &str) {
+    log(Level::Error, target, message);
+}
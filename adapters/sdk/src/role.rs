@@ -0,0 +1,258 @@
+//! Conversions between WIT variants and the strings providers use
+
+use crate::error::unsupported;
+use crate::types::{FinishReason, Role};
+
+/// Provider string for a role
+///
+/// ```
+/// use ai_messenger_adapter_sdk::role::role_to_str;
+/// use ai_messenger_adapter_sdk::types::Role;
+///
+/// assert_eq!(role_to_str(&Role::Assistant), "assistant");
+/// assert_eq!(role_to_str(&Role::Other("critic".to_string())), "critic");
+/// ```
+pub fn role_to_str(role: &Role) -> &str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::Function => "function",
+        Role::Tool => "tool",
+        Role::Other(other) => other,
+    }
+}
+
+/// Role for a provider string, keeping unknown roles as `Role::Other`
+///
+/// ```
+/// use ai_messenger_adapter_sdk::role::role_from_str;
+/// use ai_messenger_adapter_sdk::types::Role;
+///
+/// assert_eq!(role_from_str("user"), Role::User);
+/// assert_eq!(role_from_str("critic"), Role::Other("critic".to_string()));
+/// ```
+pub fn role_from_str(role: &str) -> Role {
+    match role {
+        "system" => Role::System,
+        "user" => Role::User,
+        "assistant" => Role::Assistant,
+        "function" => Role::Function,
+        "tool" => Role::Tool,
+        other => Role::Other(other.to_string()),
+    }
+}
+
+/// Roles accepted by OpenAI-compatible chat APIs such as Ollama's
+pub const CHAT_ROLES: &[&str] = &["system", "user", "assistant", "tool"];
+
+/// Provider string for a role, remapped to one of the `supported` roles
+///
+/// `function` is the older name of `tool`, so each is sent as the other
+/// when the provider only knows that one. Roles without a supported
+/// equivalent, including unknown `Role::Other` roles, are an error rather
+/// than a request the provider would reject.
+///
+/// ```
+/// use ai_messenger_adapter_sdk::role::{CHAT_ROLES, role_to_supported_str};
+/// use ai_messenger_adapter_sdk::types::Role;
+///
+/// assert_eq!(role_to_supported_str(&Role::Function, CHAT_ROLES), Ok("tool"));
+/// assert!(role_to_supported_str(&Role::Other("critic".to_string()), CHAT_ROLES).is_err());
+/// ```
+pub fn role_to_supported_str<'a>(role: &'a Role, supported: &[&str]) -> Result<&'a str, String> {
+    let equivalents: &[&'static str] = match role {
+        Role::Function => &["tool"],
+        Role::Tool => &["function"],
+        _ => &[],
+    };
+
+    let name = role_to_str(role);
+    if supported.contains(&name) {
+        return Ok(name);
+    }
+    equivalents
+        .iter()
+        .find(|equivalent| supported.contains(*equivalent))
+        .copied()
+        .ok_or_else(|| unsupported(&format!("Role '{}'", name)))
+}
+
+/// Finish reason for a provider string
+///
+/// Accepts both `content_filter` (OpenAI style) and `content-filter`, and
+/// Anthropic's `end_turn`, `stop_sequence` and `max_tokens`.
+///
+/// ```
+/// use ai_messenger_adapter_sdk::role::finish_reason_from_str;
+/// use ai_messenger_adapter_sdk::types::FinishReason;
+///
+/// assert_eq!(finish_reason_from_str("length"), FinishReason::Length);
+/// assert_eq!(finish_reason_from_str("content_filter"), FinishReason::ContentFilter);
+/// ```
+pub fn finish_reason_from_str(reason: &str) -> FinishReason {
+    match reason {
+        "stop" | "end_turn" | "stop_sequence" => FinishReason::Stop,
+        "length" | "max_tokens" => FinishReason::Length,
+        "content_filter" | "content-filter" => FinishReason::ContentFilter,
+        "error" => FinishReason::Error,
+        other => FinishReason::Other(other.to_string()),
+    }
+}
+
+/// Finish reason, counting a reply that used up its token limit as cut off
+///
+/// Providers that don't say why they stopped, or report every finished
+/// reply as stopped (Ollama before `done_reason`), end replies cut off by
+/// `max-completion-tokens` (Ollama's `num_predict`) without a hint. A reply
+/// of `completion_tokens` reaching `max_tokens` was most likely cut off.
+///
+/// ```
+/// use ai_messenger_adapter_sdk::role::finish_reason_with_limit;
+/// use ai_messenger_adapter_sdk::types::FinishReason;
+///
+/// // Ollama: {"done": true, "eval_count": 128} with num_predict = 128
+/// assert_eq!(
+///     finish_reason_with_limit(Some(FinishReason::Stop), 128, Some(128)),
+///     Some(FinishReason::Length)
+/// );
+/// ```
+pub fn finish_reason_with_limit(
+    reported: Option<FinishReason>,
+    completion_tokens: u32,
+    max_tokens: Option<u32>,
+) -> Option<FinishReason> {
+    let limit_reached = max_tokens.is_some_and(|max_tokens| completion_tokens >= max_tokens);
+    match reported {
+        None | Some(FinishReason::Stop) if limit_reached => Some(FinishReason::Length),
+        reported => reported,
+    }
+}
+
+/// Provider string for a finish reason
+pub fn finish_reason_to_str(reason: &FinishReason) -> &str {
+    match reason {
+        FinishReason::Stop => "stop",
+        FinishReason::Length => "length",
+        FinishReason::ContentFilter => "content_filter",
+        FinishReason::Error => "error",
+        FinishReason::Other(other) => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_round_trip() {
+        for role in [
+            Role::System,
+            Role::User,
+            Role::Assistant,
+            Role::Function,
+            Role::Tool,
+            Role::Other("narrator".to_string()),
+        ] {
+            assert_eq!(role_from_str(role_to_str(&role)), role);
+        }
+    }
+
+    #[test]
+    fn test_supported_roles_round_trip() {
+        for role in [Role::System, Role::User, Role::Assistant, Role::Tool] {
+            let name = role_to_supported_str(&role, CHAT_ROLES).unwrap();
+            assert_eq!(role_from_str(name), role);
+        }
+    }
+
+    #[test]
+    fn test_function_and_tool_remapped() {
+        assert_eq!(
+            role_to_supported_str(&Role::Function, CHAT_ROLES),
+            Ok("tool")
+        );
+        assert_eq!(
+            role_to_supported_str(&Role::Tool, &["user", "function"]),
+            Ok("function")
+        );
+        // Kept when the provider knows both
+        assert_eq!(
+            role_to_supported_str(&Role::Function, &["function", "tool"]),
+            Ok("function")
+        );
+        assert_eq!(
+            role_to_supported_str(&Role::Tool, &["system", "user", "assistant"]),
+            Err("Role 'tool' is not supported by this adapter".to_string())
+        );
+    }
+
+    #[test]
+    fn test_other_roles_need_provider_support() {
+        let narrator = Role::Other("narrator".to_string());
+
+        assert!(role_to_supported_str(&narrator, CHAT_ROLES).is_err());
+        assert_eq!(
+            role_to_supported_str(&narrator, &["user", "narrator"]),
+            Ok("narrator")
+        );
+    }
+
+    #[test]
+    fn test_finish_reason_round_trip() {
+        for reason in [
+            FinishReason::Stop,
+            FinishReason::Length,
+            FinishReason::ContentFilter,
+            FinishReason::Error,
+            FinishReason::Other("tool_calls".to_string()),
+        ] {
+            assert_eq!(
+                finish_reason_from_str(finish_reason_to_str(&reason)),
+                reason
+            );
+        }
+    }
+
+    #[test]
+    fn test_finish_reason_from_provider_fixtures() {
+        // done_reason of newer Ollama versions, finish_reason of OpenAI,
+        // stop_reason of Anthropic
+        for (reported, expected) in [
+            ("stop", FinishReason::Stop),
+            ("length", FinishReason::Length),
+            ("content_filter", FinishReason::ContentFilter),
+            ("end_turn", FinishReason::Stop),
+            ("stop_sequence", FinishReason::Stop),
+            ("max_tokens", FinishReason::Length),
+            ("error", FinishReason::Error),
+            ("load", FinishReason::Other("load".to_string())),
+        ] {
+            assert_eq!(finish_reason_from_str(reported), expected, "{}", reported);
+        }
+    }
+
+    #[test]
+    fn test_finish_reason_with_limit() {
+        // Older Ollama: {"done": true, "eval_count": 64} for num_predict = 64
+        assert_eq!(
+            finish_reason_with_limit(Some(FinishReason::Stop), 64, Some(64)),
+            Some(FinishReason::Length)
+        );
+        assert_eq!(
+            finish_reason_with_limit(None, 64, Some(64)),
+            Some(FinishReason::Length)
+        );
+        // Below the limit, or without one, the reported reason stands
+        assert_eq!(
+            finish_reason_with_limit(Some(FinishReason::Stop), 63, Some(64)),
+            Some(FinishReason::Stop)
+        );
+        assert_eq!(finish_reason_with_limit(None, 64, None), None);
+        // A specific reason isn't overridden
+        assert_eq!(
+            finish_reason_with_limit(Some(FinishReason::ContentFilter), 64, Some(64)),
+            Some(FinishReason::ContentFilter)
+        );
+    }
+}
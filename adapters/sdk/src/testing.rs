@@ -0,0 +1,182 @@
+//! Test harness for adapter unit tests
+//!
+//! Runs an [`LlmProvider`] natively, so adapters can be tested with plain
+//! `cargo test` without building a WASM component.
+//!
+//! ```
+//! use ai_messenger_adapter_sdk::testing::{chat_request, run_parse_response, run_prepare_request};
+//! use ai_messenger_adapter_sdk::types::{ChatRequest, ChatResponse, HttpConfig, HttpResponse, Role};
+//! use ai_messenger_adapter_sdk::LlmProvider;
+//!
+//! struct Echo;
+//!
+//! impl LlmProvider for Echo {
+//!     fn prepare_request(request: ChatRequest) -> Result<HttpConfig, String> {
+//!         Ok(HttpConfig {
+//!             url: format!("http://localhost/{}", request.model),
+//!             headers: vec![],
+//!             body: request.messages[0].content.clone(),
+//!         })
+//!     }
+//!
+//!     fn parse_response(response: HttpResponse) -> Result<ChatResponse, String> {
+//!         Ok(ChatResponse {
+//!             content: response.body,
+//!             model: "echo".to_string(),
+//!             finish_reason: None,
+//!             stop_sequence: None,
+//!             usage: None,
+//!         })
+//!     }
+//! }
+//!
+//! let config = run_prepare_request::<Echo>(chat_request("m", &[(Role::User, "hi")])).unwrap();
+//! assert_eq!(config.url, "http://localhost/m");
+//!
+//! let response = run_parse_response::<Echo>(200, "hello").unwrap();
+//! assert_eq!(response.content, "hello");
+//! ```
+
+use crate::log::Level;
+use crate::types::{ChatRequest, ChatResponse, HttpConfig, HttpResponse, Message, ModelInfo, Role};
+use crate::LlmProvider;
+use std::path::Path;
+
+/// Build a chat request with the given messages and no optional parameters
+pub fn chat_request(model: &str, messages: &[(Role, &str)]) -> ChatRequest {
+    ChatRequest {
+        messages: messages
+            .iter()
+            .map(|(role, content)| Message {
+                role: role.clone(),
+                content: content.to_string(),
+            })
+            .collect(),
+        model: model.to_string(),
+        max_completion_tokens: None,
+        temperature: None,
+        top_p: None,
+        enable_streaming: None,
+        stop: None,
+        seed: None,
+        user: None,
+        provider_params: None,
+    }
+}
+
+/// Build a JSON HTTP response as the host would pass it to the adapter
+pub fn http_response(status_code: u16, body: &str) -> HttpResponse {
+    HttpResponse {
+        status_code,
+        headers: vec![("content-type".to_string(), "application/json".to_string())],
+        body: body.to_string(),
+    }
+}
+
+/// Run the adapter's `prepare-request` export
+pub fn run_prepare_request<P: LlmProvider>(request: ChatRequest) -> Result<HttpConfig, String> {
+    P::prepare_request(request)
+}
+
+/// Run the adapter's `parse-response` export on a JSON response
+pub fn run_parse_response<P: LlmProvider>(
+    status_code: u16,
+    body: &str,
+) -> Result<ChatResponse, String> {
+    P::parse_response(http_response(status_code, body))
+}
+
+/// Run the adapter's `parse-model-info-response` export on a JSON response
+pub fn run_parse_model_info_response<P: LlmProvider>(
+    status_code: u16,
+    body: &str,
+) -> Result<ModelInfo, String> {
+    P::parse_model_info_response(http_response(status_code, body))
+}
+
+/// Take the messages logged with [`log`](crate::log) on this thread
+///
+/// ```
+/// use ai_messenger_adapter_sdk::log::{self, Level};
+/// use ai_messenger_adapter_sdk::testing::take_logs;
+///
+/// log::warn("parse", "Missing usage");
+/// assert_eq!(
+///     take_logs(),
+///     [(Level::Warn, "parse".to_string(), "Missing usage".to_string())]
+/// );
+/// ```
+pub fn take_logs() -> Vec<(Level, String, String)> {
+    crate::log::CAPTURED.with(|captured| captured.take())
+}
+
+/// Load a fixture file from `dir`
+///
+/// Usually called with `concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures")`.
+///
+/// # Panics
+///
+/// Panics with the fixture path if the file can't be read, so a missing
+/// fixture fails the test with a clear message.
+pub fn load_fixture(dir: impl AsRef<Path>, name: &str) -> String {
+    let path = dir.as_ref().join(name);
+
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to load fixture {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_request() {
+        let request = chat_request(
+            "llama3.2",
+            &[(Role::System, "Be brief"), (Role::User, "Hi")],
+        );
+
+        assert_eq!(request.model, "llama3.2");
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[0].role, Role::System);
+        assert_eq!(request.temperature, None);
+    }
+
+    struct NoModelInfo;
+
+    impl LlmProvider for NoModelInfo {
+        fn prepare_request(_request: ChatRequest) -> Result<HttpConfig, String> {
+            Err("unused".to_string())
+        }
+
+        fn parse_response(_response: HttpResponse) -> Result<ChatResponse, String> {
+            Err("unused".to_string())
+        }
+    }
+
+    #[test]
+    fn test_model_info_unsupported_by_default() {
+        assert!(NoModelInfo::prepare_model_info_request("llama3.2".to_string()).is_err());
+        assert!(run_parse_model_info_response::<NoModelInfo>(200, "{}").is_err());
+    }
+
+    #[test]
+    fn test_load_fixture() {
+        let dir = std::env::temp_dir();
+        let name = format!("ai_messenger_sdk_fixture_{}.json", std::process::id());
+        std::fs::write(dir.join(&name), "{}").unwrap();
+
+        assert_eq!(load_fixture(&dir, &name), "{}");
+
+        std::fs::remove_file(dir.join(&name)).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to load fixture")]
+    fn test_load_missing_fixture() {
+        load_fixture(
+            std::env::temp_dir(),
+            "ai_messenger_sdk_missing_fixture.json",
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! Token usage helpers
+
+use crate::types::Usage;
+
+/// Build usage statistics from prompt and completion token counts
+///
+/// ```
+/// use ai_messenger_adapter_sdk::usage::usage;
+///
+/// let usage = usage(12, 30);
+/// assert_eq!(usage.total_tokens, 42);
+/// ```
+pub fn usage(prompt_tokens: u32, completion_tokens: u32) -> Usage {
+    Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens.saturating_add(completion_tokens),
+    }
+}
+
+/// Add up usage from several responses (e.g. streamed chunks)
+///
+/// ```
+/// use ai_messenger_adapter_sdk::usage::{merge, usage};
+///
+/// let total = merge(&usage(10, 5), &usage(0, 7));
+/// assert_eq!(total, usage(10, 12));
+/// ```
+pub fn merge(a: &Usage, b: &Usage) -> Usage {
+    usage(
+        a.prompt_tokens.saturating_add(b.prompt_tokens),
+        a.completion_tokens.saturating_add(b.completion_tokens),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_saturates() {
+        assert_eq!(usage(u32::MAX, 1).total_tokens, u32::MAX);
+        assert_eq!(
+            merge(&usage(u32::MAX, 0), &usage(1, 0)).prompt_tokens,
+            u32::MAX
+        );
+    }
+}
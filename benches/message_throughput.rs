@@ -0,0 +1,155 @@
+//! Message-handling throughput against an in-process mock LLM adapter
+//!
+//! Each iteration sends `concurrency` requests at once. The mock takes
+//! `PROVIDER_LATENCY` per reply, so if calls to the shared adapter are
+//! serialized (e.g. by the `RwLock` around it) time per iteration grows
+//! with concurrency instead of staying flat.
+//!
+//! Run with `cargo bench --bench message_throughput`.
+
+use ai_messenger::adapter::services::SharedLlm;
+use ai_messenger::adapter::traits::{
+    AdapterService, ChatMessage, Finish, GenerationOptions, LlmAdapter, ModelInfo, ServiceError,
+};
+use ai_messenger::server::AppState;
+use ai_messenger::server::router::build_router;
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::Request;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use futures::future::join_all;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+
+/// Time the mock provider takes to reply
+const PROVIDER_LATENCY: Duration = Duration::from_millis(1);
+
+/// Requests in flight per iteration
+const CONCURRENCY: &[usize] = &[1, 8, 32];
+
+/// LLM adapter replying after `PROVIDER_LATENCY`, without any network
+struct MockLlm;
+
+#[async_trait]
+impl AdapterService for MockLlm {
+    fn service_name(&self) -> &'static str {
+        "llm"
+    }
+
+    fn provider_name(&self) -> &str {
+        "mock"
+    }
+
+    fn version(&self) -> &str {
+        "bench"
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LlmAdapter for MockLlm {
+    async fn send_message(
+        &mut self,
+        _messages: &[ChatMessage],
+        _options: &GenerationOptions,
+    ) -> Result<String, ServiceError> {
+        tokio::time::sleep(PROVIDER_LATENCY).await;
+        Ok("mock reply".to_string())
+    }
+
+    async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+        Err(ServiceError::ServiceUnavailable(
+            "no model info".to_string(),
+        ))
+    }
+
+    async fn stream_message(
+        &mut self,
+        _messages: &[ChatMessage],
+        _options: &GenerationOptions,
+        chunks: mpsc::Sender<String>,
+    ) -> Result<Finish, ServiceError> {
+        tokio::time::sleep(PROVIDER_LATENCY).await;
+        let _ = chunks.send("mock reply".to_string()).await;
+        Ok(Finish::stop())
+    }
+}
+
+fn message_request() -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/message/alice")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+        ))
+        .unwrap()
+}
+
+/// Full request path: router, middleware, handler and adapter lock
+fn bench_router(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let app = build_router("", AppState::with_llm(MockLlm));
+
+    let mut group = c.benchmark_group("message_router");
+    for &concurrency in CONCURRENCY {
+        group.throughput(Throughput::Elements(concurrency as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(concurrency),
+            &concurrency,
+            |b, &concurrency| {
+                b.to_async(&runtime).iter(|| async {
+                    let responses =
+                        join_all((0..concurrency).map(|_| app.clone().oneshot(message_request())))
+                            .await;
+                    for response in responses {
+                        assert!(response.unwrap().status().is_success());
+                    }
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Adapter calls alone, locking the shared adapter the way the handler does
+fn bench_shared_adapter(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let llm: SharedLlm = AppState::with_llm(MockLlm).llm.unwrap();
+    let messages = vec![ChatMessage {
+        role: "user".to_string(),
+        content: "Hi".to_string(),
+        parts: None,
+    }];
+    let options = GenerationOptions::default();
+
+    let mut group = c.benchmark_group("shared_adapter");
+    for &concurrency in CONCURRENCY {
+        group.throughput(Throughput::Elements(concurrency as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(concurrency),
+            &concurrency,
+            |b, &concurrency| {
+                b.to_async(&runtime).iter(|| async {
+                    join_all((0..concurrency).map(|_| async {
+                        let mut llm = llm.write().await;
+                        llm.send_message(&messages, &options).await.unwrap()
+                    }))
+                    .await
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_router, bench_shared_adapter);
+criterion_main!(benches);
@@ -0,0 +1,195 @@
+use crate::adapter::breaker::CircuitBreaker;
+use crate::adapter::traits::ServiceError;
+use crate::config::schema::{BalanceStrategy, CircuitBreakerConfig, EndpointConfig};
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Spreads requests over the endpoints of one provider
+///
+/// Weighted round robin is the smooth variant nginx uses: over any run of
+/// picks each endpoint gets its weight's share, interleaved rather than in
+/// bursts. Least in flight picks the endpoint with the fewest requests in
+/// flight per unit of weight, counting a request until its lease is dropped.
+///
+/// Each endpoint has its own circuit breaker, configured like the
+/// provider's. Endpoints whose circuit is open are skipped until their
+/// cooldown is over; if all are, requests fail fast at the breaker.
+pub struct EndpointBalancer {
+    endpoints: Vec<Endpoint>,
+    provider: String,
+    /// Running scores of the smooth weighted round robin, by endpoint
+    scores: Mutex<Vec<i64>>,
+    strategy: BalanceStrategy,
+}
+
+struct Endpoint {
+    base_url: String,
+    /// Tracks whether the endpoint is up (None if breakers are disabled)
+    breaker: Option<CircuitBreaker>,
+    in_flight: AtomicUsize,
+    weight: u32,
+}
+
+impl Endpoint {
+    fn is_ready(&self) -> bool {
+        self.breaker.as_ref().is_none_or(CircuitBreaker::is_ready)
+    }
+}
+
+/// An endpoint picked for one request, counted as in flight until dropped
+pub struct EndpointLease<'a> {
+    endpoint: &'a Endpoint,
+}
+
+impl EndpointLease<'_> {
+    pub fn base_url(&self) -> &str {
+        &self.endpoint.base_url
+    }
+
+    /// Run `call` against the endpoint, counting its outcome for the endpoint's health
+    pub async fn call<T>(
+        &self,
+        call: impl Future<Output = Result<T, ServiceError>>,
+    ) -> Result<T, ServiceError> {
+        match &self.endpoint.breaker {
+            Some(breaker) => breaker.call(call).await,
+            None => call.await,
+        }
+    }
+}
+
+impl Drop for EndpointLease<'_> {
+    fn drop(&mut self) {
+        self.endpoint.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl EndpointBalancer {
+    /// Balancer for `endpoints` of `provider`, or None if there are none to balance
+    ///
+    /// Endpoints weighted 0 get no requests, but one of them must have a
+    /// weight. `health` configures the breaker of each endpoint.
+    pub fn new(
+        provider: &str,
+        endpoints: &[EndpointConfig],
+        strategy: BalanceStrategy,
+        health: &CircuitBreakerConfig,
+    ) -> Result<Option<Self>, ServiceError> {
+        if endpoints.is_empty() {
+            return Ok(None);
+        }
+        if let Some(endpoint) = endpoints.iter().find(|e| e.base_url.trim().is_empty()) {
+            return Err(ServiceError::InvalidConfig(format!(
+                "Endpoint with weight {} has an empty base_url",
+                endpoint.weight
+            )));
+        }
+        if endpoints.iter().all(|endpoint| endpoint.weight == 0) {
+            return Err(ServiceError::InvalidConfig(
+                "At least one endpoint needs a weight above 0".to_string(),
+            ));
+        }
+
+        Ok(Some(EndpointBalancer {
+            endpoints: endpoints
+                .iter()
+                .map(|endpoint| Endpoint {
+                    base_url: endpoint.base_url.clone(),
+                    breaker: CircuitBreaker::from_config(
+                        &format!("{} at {}", provider, endpoint.base_url),
+                        health,
+                    ),
+                    in_flight: AtomicUsize::new(0),
+                    weight: endpoint.weight,
+                })
+                .collect(),
+            provider: provider.to_string(),
+            scores: Mutex::new(vec![0; endpoints.len()]),
+            strategy,
+        }))
+    }
+
+    /// Pick the endpoint for the next request
+    pub fn pick(&self) -> EndpointLease<'_> {
+        let mut eligible: Vec<bool> = self
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.weight > 0 && endpoint.is_ready())
+            .collect();
+        if !eligible.contains(&true) {
+            // All down: pick as usual and let the breaker fail fast
+            eligible = self.endpoints.iter().map(|e| e.weight > 0).collect();
+        }
+
+        let index = match self.strategy {
+            BalanceStrategy::WeightedRoundRobin => self.next_in_turn(&eligible),
+            BalanceStrategy::LeastInFlight => self.least_in_flight(&eligible),
+        };
+
+        let endpoint = &self.endpoints[index];
+        endpoint.in_flight.fetch_add(1, Ordering::SeqCst);
+        EndpointLease { endpoint }
+    }
+
+    /// Provider whose endpoints are balanced
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    /// Requests in flight to each endpoint, by base URL (for metrics)
+    pub fn in_flight(&self) -> Vec<(&str, usize)> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| {
+                (
+                    endpoint.base_url.as_str(),
+                    endpoint.in_flight.load(Ordering::SeqCst),
+                )
+            })
+            .collect()
+    }
+
+    /// Smooth weighted round robin over the `eligible` endpoints
+    ///
+    /// Skipped endpoints keep their score, so they rejoin where they left off.
+    fn next_in_turn(&self, eligible: &[bool]) -> usize {
+        let mut scores = self.scores.lock().unwrap_or_else(|e| e.into_inner());
+        let mut total = 0;
+
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            if eligible[index] {
+                scores[index] += i64::from(endpoint.weight);
+                total += i64::from(endpoint.weight);
+            }
+        }
+        // The first of the highest scores, so ties go in configured order
+        let best = (0..scores.len())
+            .rev()
+            .filter(|&index| eligible[index])
+            .max_by_key(|&index| scores[index])
+            .unwrap_or(0);
+        scores[best] -= total;
+        best
+    }
+
+    fn least_in_flight(&self, eligible: &[bool]) -> usize {
+        // Compare in_flight / weight without dividing: a/w < b/v  <=>  a*v < b*w
+        let mut best: Option<(usize, u64, u64)> = None;
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            if !eligible[index] {
+                continue;
+            }
+            let in_flight = endpoint.in_flight.load(Ordering::SeqCst) as u64;
+            let weight = u64::from(endpoint.weight);
+            let is_better = best.is_none_or(|(_, best_in_flight, best_weight)| {
+                in_flight * best_weight < best_in_flight * weight
+            });
+            if is_better {
+                best = Some((index, in_flight, weight));
+            }
+        }
+
+        best.map_or(0, |(index, _, _)| index)
+    }
+}
@@ -0,0 +1,250 @@
+use crate::adapter::traits::ServiceError;
+use crate::config::schema::CircuitBreakerConfig;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// State of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Requests go through to the provider
+    Closed,
+    /// Requests fail fast until the cooldown is over
+    Open,
+    /// One request probes the provider; its outcome closes or reopens the circuit
+    HalfOpen,
+}
+
+impl BreakerState {
+    /// Value of the state metric: 0 closed, 1 half-open, 2 open
+    pub fn as_metric(self) -> u8 {
+        match self {
+            BreakerState::Closed => 0,
+            BreakerState::HalfOpen => 1,
+            BreakerState::Open => 2,
+        }
+    }
+}
+
+/// Stops sending requests to a provider that keeps failing
+///
+/// After `failure_threshold` consecutive provider failures the circuit
+/// opens and requests fail with `ServiceUnavailable` right away instead of
+/// each waiting for a timeout. Once `cooldown` has passed, one request is
+/// let through: if it succeeds the circuit closes, otherwise it opens again.
+pub struct CircuitBreaker {
+    cooldown: Duration,
+    failure_threshold: u32,
+    inner: Mutex<BreakerInner>,
+    provider: String,
+}
+
+struct BreakerInner {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// Whether the half-open probe is in flight
+    probing: bool,
+    state: BreakerState,
+}
+
+/// How a call's outcome counts for the circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// The provider answered, even if with a client error
+    Success,
+    /// The provider is down, slow or failing
+    Failure,
+    /// Says nothing about the provider, e.g. our own queue was full
+    Neutral,
+}
+
+impl Outcome {
+    fn of<T>(result: &Result<T, ServiceError>) -> Self {
+        match result {
+            Ok(_) => Outcome::Success,
+            Err(
+                ServiceError::InvalidResponse(_)
+                | ServiceError::ServiceUnavailable(_)
+                | ServiceError::Timeout(_),
+            ) => Outcome::Failure,
+            Err(ServiceError::ProviderError { status, .. }) if *status >= 500 => Outcome::Failure,
+            Err(ServiceError::ProviderError { .. }) => Outcome::Success,
+            Err(_) => Outcome::Neutral,
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Breaker for `provider` opening after `failure_threshold` failures
+    pub fn new(provider: &str, failure_threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            cooldown,
+            failure_threshold: failure_threshold.max(1),
+            inner: Mutex::new(BreakerInner {
+                consecutive_failures: 0,
+                opened_at: None,
+                probing: false,
+                state: BreakerState::Closed,
+            }),
+            provider: provider.to_string(),
+        }
+    }
+
+    /// Breaker configured by `config` (None if it's disabled)
+    pub fn from_config(provider: &str, config: &CircuitBreakerConfig) -> Option<Self> {
+        (config.failure_threshold > 0).then(|| {
+            CircuitBreaker::new(
+                provider,
+                config.failure_threshold,
+                Duration::from_secs(config.cooldown_secs),
+            )
+        })
+    }
+
+    /// Run `call` unless the circuit is open, and count its outcome
+    ///
+    /// A cancelled call doesn't count either way, but frees the probe slot
+    /// so the next request can probe instead.
+    pub async fn call<T>(
+        &self,
+        call: impl Future<Output = Result<T, ServiceError>>,
+    ) -> Result<T, ServiceError> {
+        let attempt = self.admit()?;
+        let result = call.await;
+        attempt.finish(Outcome::of(&result));
+        result
+    }
+
+    /// Current state (an open circuit past its cooldown turns half-open on the next call)
+    pub fn state(&self) -> BreakerState {
+        self.lock().state
+    }
+
+    /// Whether a call would be let through instead of failing fast
+    pub fn is_ready(&self) -> bool {
+        let inner = self.lock();
+        match inner.state {
+            BreakerState::Closed => true,
+            BreakerState::HalfOpen => !inner.probing,
+            BreakerState::Open => {
+                inner.opened_at.map_or(self.cooldown, |at| at.elapsed()) >= self.cooldown
+            }
+        }
+    }
+
+    /// Provider failures since the last success
+    pub fn consecutive_failures(&self) -> u32 {
+        self.lock().consecutive_failures
+    }
+
+    /// Provider the breaker guards
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    fn admit(&self) -> Result<Attempt<'_>, ServiceError> {
+        let mut inner = self.lock();
+
+        if inner.state == BreakerState::Open {
+            let elapsed = inner.opened_at.map_or(self.cooldown, |at| at.elapsed());
+            if elapsed < self.cooldown {
+                return Err(ServiceError::ServiceUnavailable(format!(
+                    "Circuit open for {} after {} consecutive failures, retrying in {}s",
+                    self.provider,
+                    inner.consecutive_failures,
+                    (self.cooldown - elapsed).as_secs().max(1)
+                )));
+            }
+            inner.state = BreakerState::HalfOpen;
+            tracing::info!(
+                "Circuit for {} half-open, probing the provider",
+                self.provider
+            );
+        }
+
+        let probe = inner.state == BreakerState::HalfOpen;
+        if probe {
+            if inner.probing {
+                return Err(ServiceError::ServiceUnavailable(format!(
+                    "Circuit half-open for {}, waiting for the probe request",
+                    self.provider
+                )));
+            }
+            inner.probing = true;
+        }
+
+        Ok(Attempt {
+            breaker: self,
+            finished: false,
+            probe,
+        })
+    }
+
+    fn record(&self, outcome: Outcome, probe: bool) {
+        let mut inner = self.lock();
+        if probe {
+            inner.probing = false;
+        }
+
+        match outcome {
+            Outcome::Neutral => {}
+            Outcome::Success => {
+                if inner.state != BreakerState::Closed {
+                    tracing::info!(
+                        "Circuit for {} closed, the provider recovered",
+                        self.provider
+                    );
+                }
+                inner.consecutive_failures = 0;
+                inner.opened_at = None;
+                inner.state = BreakerState::Closed;
+            }
+            Outcome::Failure => {
+                inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+                let trips = match inner.state {
+                    BreakerState::Closed => inner.consecutive_failures >= self.failure_threshold,
+                    BreakerState::HalfOpen => probe,
+                    BreakerState::Open => false,
+                };
+                if trips {
+                    tracing::warn!(
+                        "Circuit for {} open after {} consecutive failures, failing requests for {}s",
+                        self.provider,
+                        inner.consecutive_failures,
+                        self.cooldown.as_secs()
+                    );
+                    inner.opened_at = Some(Instant::now());
+                    inner.state = BreakerState::Open;
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BreakerInner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A call admitted by the breaker, counted once it finishes
+struct Attempt<'a> {
+    breaker: &'a CircuitBreaker,
+    finished: bool,
+    probe: bool,
+}
+
+impl Attempt<'_> {
+    fn finish(mut self, outcome: Outcome) {
+        self.finished = true;
+        self.breaker.record(outcome, self.probe);
+    }
+}
+
+impl Drop for Attempt<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.breaker.record(Outcome::Neutral, self.probe);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Encryption of stored values at rest
+//!
+//! With `encrypt = true` under `[storage]`, every value goes through the
+//! `[adapters.crypto]` adapter on its way into the storage adapter and back.
+//! Keys stay readable, so prefix listing and key migration keep working;
+//! only what's stored under them is encrypted. Each value is sealed for
+//! its logical key, so a value moved to another key fails to decrypt.
+
+use crate::adapter::manifest::AdapterManifest;
+use crate::adapter::services::SharedCrypto;
+use crate::adapter::traits::{AdapterService, KeyPage, ServiceError, StorageAdapter};
+use crate::config::Config;
+use async_trait::async_trait;
+
+/// Storage adapter encrypting values with a crypto adapter
+///
+/// The wrapped adapter only ever sees ciphertext. It goes around
+/// `EncodedKeys`, so values are sealed for the logical key, which stays
+/// the same when `migrate_keys` moves them to canonical keys. Values
+/// stored before encryption was turned on can't be read through it.
+pub struct EncryptedValues {
+    crypto: SharedCrypto,
+    inner: Box<dyn StorageAdapter>,
+}
+
+impl EncryptedValues {
+    pub fn new(inner: Box<dyn StorageAdapter>, crypto: SharedCrypto) -> Self {
+        EncryptedValues { crypto, inner }
+    }
+}
+
+/// Crypto adapter to encrypt stored values with, if `[storage] encrypt` is on
+///
+/// `crypto` is the loaded `[adapters.crypto]` adapter. Without one,
+/// encryption can't be on: storage must not quietly fall back to plaintext.
+pub fn storage_crypto(
+    config: &Config,
+    crypto: Option<&SharedCrypto>,
+) -> Result<Option<SharedCrypto>, ServiceError> {
+    if !config.storage.encrypt {
+        return Ok(None);
+    }
+    match crypto {
+        Some(crypto) => Ok(Some(crypto.clone())),
+        None => Err(ServiceError::InvalidConfig(
+            "[storage] encrypt needs a loaded [adapters.crypto] adapter".to_string(),
+        )),
+    }
+}
+
+#[async_trait]
+impl AdapterService for EncryptedValues {
+    fn service_name(&self) -> &'static str {
+        self.inner.service_name()
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn version(&self) -> &str {
+        self.inner.version()
+    }
+
+    fn manifest(&self) -> Option<&AdapterManifest> {
+        self.inner.manifest()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        self.inner.shutdown().await
+    }
+}
+
+#[async_trait]
+impl StorageAdapter for EncryptedValues {
+    async fn store(&mut self, key: &str, data: &[u8]) -> Result<(), ServiceError> {
+        let sealed = self
+            .crypto
+            .read()
+            .await
+            .encrypt(key.as_bytes(), data)
+            .await?;
+        self.inner.store(key, &sealed).await
+    }
+
+    async fn retrieve(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        let sealed = self.inner.retrieve(key).await?;
+        self.crypto
+            .read()
+            .await
+            .decrypt(key.as_bytes(), &sealed)
+            .await
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), ServiceError> {
+        self.inner.delete(key).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, ServiceError> {
+        self.inner.exists(key).await
+    }
+
+    async fn list_keys_paginated(
+        &self,
+        prefix: Option<&str>,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<KeyPage, ServiceError> {
+        self.inner.list_keys_paginated(prefix, cursor, limit).await
+    }
+}
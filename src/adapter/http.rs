@@ -0,0 +1,241 @@
+use crate::adapter::traits::ServiceError;
+use crate::config::defaults::{
+    DEFAULT_ADAPTER_CONNECT_TIMEOUT_SECS, DEFAULT_ADAPTER_HTTP_TIMEOUT_SECS,
+    DEFAULT_ADAPTER_POOL_IDLE_TIMEOUT_SECS, DEFAULT_ADAPTER_TCP_KEEPALIVE_SECS,
+    DEFAULT_HTTP_POOL_MAX_IDLE_PER_HOST, MAX_ADAPTER_REQUEST_HEADER_BYTES,
+    MAX_ADAPTER_REQUEST_HEADERS,
+};
+use crate::config::schema::HttpConfig;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Timeouts and keep-alive settings for the adapter HTTP client
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpClientSettings {
+    /// Limit on establishing a TCP/TLS connection
+    pub connect_timeout: Duration,
+    /// How long idle pooled connections are kept for reuse
+    pub pool_idle_timeout: Duration,
+    /// Idle connections pooled per host
+    pub pool_max_idle_per_host: usize,
+    /// Interval of TCP keep-alive probes on open connections
+    pub tcp_keepalive: Duration,
+    /// Limit on a whole request, including reading the response body
+    pub timeout: Duration,
+}
+
+impl Default for HttpClientSettings {
+    fn default() -> Self {
+        HttpClientSettings {
+            connect_timeout: Duration::from_secs(DEFAULT_ADAPTER_CONNECT_TIMEOUT_SECS),
+            pool_idle_timeout: Duration::from_secs(DEFAULT_ADAPTER_POOL_IDLE_TIMEOUT_SECS),
+            pool_max_idle_per_host: DEFAULT_HTTP_POOL_MAX_IDLE_PER_HOST,
+            tcp_keepalive: Duration::from_secs(DEFAULT_ADAPTER_TCP_KEEPALIVE_SECS),
+            timeout: Duration::from_secs(DEFAULT_ADAPTER_HTTP_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl HttpClientSettings {
+    /// Default settings with the pooling and keep-alive of `[http]`
+    pub fn from_config(config: &HttpConfig) -> Self {
+        HttpClientSettings {
+            pool_idle_timeout: Duration::from_secs(config.pool_idle_timeout_secs),
+            pool_max_idle_per_host: config.pool_max_idle_per_host,
+            tcp_keepalive: Duration::from_secs(config.tcp_keepalive_secs),
+            ..HttpClientSettings::default()
+        }
+    }
+}
+
+/// Build an HTTP client for provider requests
+///
+/// Clones of the returned client share one connection pool, so build it
+/// once and hand out clones rather than creating a client per request.
+pub fn build_client(settings: &HttpClientSettings) -> Result<reqwest::Client, ServiceError> {
+    reqwest::Client::builder()
+        .connect_timeout(settings.connect_timeout)
+        .pool_idle_timeout(settings.pool_idle_timeout)
+        .pool_max_idle_per_host(settings.pool_max_idle_per_host)
+        .tcp_keepalive(settings.tcp_keepalive)
+        .timeout(settings.timeout)
+        .build()
+        .map_err(|e| {
+            ServiceError::InitializationFailed(format!("HTTP client creation failed: {e}"))
+        })
+}
+
+/// Process-wide HTTP client and the settings it was built with
+static SHARED_CLIENT: OnceLock<(HttpClientSettings, reqwest::Client)> = OnceLock::new();
+
+/// Process-wide HTTP client
+///
+/// Built on first use; every caller gets a clone sharing the same pool, so
+/// connections and TLS sessions are reused across adapters and requests.
+/// It has the `[http]` settings if `configure_shared_client` came first,
+/// default settings otherwise.
+pub fn shared_client() -> Result<reqwest::Client, ServiceError> {
+    shared_client_with(HttpClientSettings::default).map(|(_, client)| client.clone())
+}
+
+/// Build the shared client with the settings of `[http]`
+///
+/// Only the shared client gets them; clients from `build_client` keep the
+/// settings they're given. Call it before anything uses `shared_client`:
+/// once built, the client keeps its settings, and a different `[http]`
+/// is only warned about.
+pub fn configure_shared_client(config: &HttpConfig) -> Result<(), ServiceError> {
+    let settings = HttpClientSettings::from_config(config);
+    let (current, _) = shared_client_with(|| settings.clone())?;
+    if *current != settings {
+        tracing::warn!("The HTTP client is already in use; [http] settings apply after a restart");
+    }
+
+    Ok(())
+}
+
+fn shared_client_with(
+    settings: impl FnOnce() -> HttpClientSettings,
+) -> Result<&'static (HttpClientSettings, reqwest::Client), ServiceError> {
+    if let Some(shared) = SHARED_CLIENT.get() {
+        return Ok(shared);
+    }
+
+    let settings = settings();
+    let client = build_client(&settings)?;
+    Ok(SHARED_CLIENT.get_or_init(|| (settings, client)))
+}
+
+/// HTTP request prepared by an adapter (mirrors the WIT `http-config`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderRequest {
+    pub body: String,
+    pub headers: Vec<(String, String)>,
+    pub url: String,
+}
+
+/// Provider response handed back to the adapter (mirrors the WIT `http-response`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderResponse {
+    pub body: String,
+    pub headers: Vec<(String, String)>,
+    pub status_code: u16,
+}
+
+/// Running total of the response bytes read from a provider
+///
+/// Counts every chunk of an exchange, so a streamed response is limited
+/// as a whole rather than per chunk.
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseSizeLimit {
+    limit: usize,
+    read: usize,
+}
+
+impl ResponseSizeLimit {
+    pub fn new(limit: usize) -> Self {
+        ResponseSizeLimit { limit, read: 0 }
+    }
+
+    /// Count `len` more bytes, failing once the total exceeds the limit
+    pub fn add(&mut self, len: usize) -> Result<(), ServiceError> {
+        self.read = self.read.saturating_add(len);
+        if self.read > self.limit {
+            return Err(ServiceError::ResponseTooLarge { limit: self.limit });
+        }
+        Ok(())
+    }
+}
+
+/// Check that an adapter's request headers are within reasonable bounds
+fn check_headers(headers: &[(String, String)]) -> Result<(), ServiceError> {
+    if headers.len() > MAX_ADAPTER_REQUEST_HEADERS {
+        return Err(ServiceError::ExecutionError(format!(
+            "Adapter set {} request headers, more than the limit of {}",
+            headers.len(),
+            MAX_ADAPTER_REQUEST_HEADERS
+        )));
+    }
+
+    let size: usize = headers
+        .iter()
+        .map(|(name, value)| name.len() + value.len())
+        .sum();
+    if size > MAX_ADAPTER_REQUEST_HEADER_BYTES {
+        return Err(ServiceError::ExecutionError(format!(
+            "Adapter set {} bytes of request headers, more than the limit of {}",
+            size, MAX_ADAPTER_REQUEST_HEADER_BYTES
+        )));
+    }
+
+    Ok(())
+}
+
+/// Send an adapter-prepared request to the provider
+///
+/// Error statuses are returned as responses, since interpreting them is the
+/// adapter's job. Only transport failures are errors, bodies larger than
+/// `max_response_bytes` (those are read no further than the limit) and
+/// bodies that aren't UTF-8, which adapters couldn't receive as a string.
+pub async fn send(
+    client: &reqwest::Client,
+    request: &ProviderRequest,
+    max_response_bytes: usize,
+) -> Result<ProviderResponse, ServiceError> {
+    check_headers(&request.headers)?;
+
+    let mut builder = client.post(&request.url).body(request.body.clone());
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+
+    let transport_error = |e: reqwest::Error| {
+        if e.is_timeout() {
+            ServiceError::Timeout(format!("Request to {} timed out: {e}", request.url))
+        } else {
+            ServiceError::ServiceUnavailable(format!("Request to {} failed: {e}", request.url))
+        }
+    };
+    let mut response = builder.send().await.map_err(transport_error)?;
+
+    let status_code = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+
+    // Refuse announced oversized bodies before reading any of them
+    if let Some(len) = response.content_length() {
+        ResponseSizeLimit::new(max_response_bytes)
+            .add(usize::try_from(len).unwrap_or(usize::MAX))?;
+    }
+    let mut limit = ResponseSizeLimit::new(max_response_bytes);
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(transport_error)? {
+        limit.add(chunk.len())?;
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(ProviderResponse {
+        body: decode_body(body, &request.url)?,
+        headers,
+        status_code,
+    })
+}
+
+/// Response body as text for the adapter, without a byte order mark
+fn decode_body(body: Vec<u8>, url: &str) -> Result<String, ServiceError> {
+    let body = String::from_utf8(body).map_err(|e| {
+        ServiceError::InvalidResponse(format!(
+            "Response from {} is not valid UTF-8 (invalid byte at offset {})",
+            url,
+            e.utf8_error().valid_up_to()
+        ))
+    })?;
+
+    Ok(match body.strip_prefix('\u{feff}') {
+        Some(stripped) => stripped.to_string(),
+        None => body,
+    })
+}
@@ -0,0 +1,222 @@
+//! Canonical encoding of storage keys
+//!
+//! Storage adapters only ever see encoded keys, so every backend gets the
+//! same safe ASCII regardless of what the logical key contains.
+//!
+//! Version 1 of the encoding:
+//!
+//! - A key is a sequence of segments separated by `/`. The separators are
+//!   kept, so namespaces like `conversation/` stay visible to backends and
+//!   prefix listing keeps working.
+//! - Within a segment, ASCII letters, digits, `-` and `_` are kept as is.
+//! - Every other byte of the segment's UTF-8 encoding, including `%` and
+//!   `.`, is written as `%XX` with uppercase hex digits.
+//!
+//! Keys made only of the kept characters (like all keys written so far by
+//! the server) encode to themselves. Encoding is injective, so distinct
+//! logical keys never collide. Keys stored before the encoding existed can
+//! be rewritten with `ai_messenger data migrate-keys`.
+
+use crate::adapter::manifest::AdapterManifest;
+use crate::adapter::traits::{AdapterService, KeyPage, ServiceError, StorageAdapter};
+use async_trait::async_trait;
+
+/// Version of the key encoding described in this module
+pub const KEY_ENCODING_VERSION: u32 = 1;
+
+/// Separator between key segments, kept by the encoding
+const SEPARATOR: char = '/';
+
+/// Encoder and decoder for the canonical storage key format
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeyCodec;
+
+impl KeyCodec {
+    /// Encode a logical key, keeping `/` as the segment separator
+    pub fn encode(key: &str) -> String {
+        key.split(SEPARATOR)
+            .map(Self::encode_segment)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Encode a single segment; a `/` inside it is escaped as `%2F`
+    pub fn encode_segment(segment: &str) -> String {
+        let mut encoded = String::with_capacity(segment.len());
+        for byte in segment.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+                encoded.push(byte as char);
+            } else {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        encoded
+    }
+
+    /// Decode an encoded key back to the logical key
+    pub fn decode(encoded: &str) -> Result<String, ServiceError> {
+        let invalid = || ServiceError::ExecutionError(format!("Invalid encoded key: {encoded}"));
+
+        let mut bytes = Vec::with_capacity(encoded.len());
+        let mut input = encoded.bytes();
+        while let Some(byte) = input.next() {
+            if byte == b'%' {
+                let high = input.next().and_then(hex_value).ok_or_else(invalid)?;
+                let low = input.next().and_then(hex_value).ok_or_else(invalid)?;
+                bytes.push((high << 4) | low);
+            } else if byte.is_ascii() {
+                bytes.push(byte);
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| invalid())
+    }
+
+    /// Whether `key` is exactly what encoding its decoded form produces
+    pub fn is_canonical(key: &str) -> bool {
+        Self::decode(key).is_ok_and(|decoded| Self::encode(&decoded) == key)
+    }
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+/// Storage adapter applying `KeyCodec` to every key
+///
+/// Callers use logical keys; the wrapped adapter only receives encoded
+/// ones. Listed keys are decoded again, skipping keys that were stored
+/// without the encoding.
+pub struct EncodedKeys {
+    inner: Box<dyn StorageAdapter>,
+}
+
+impl EncodedKeys {
+    pub fn new(inner: Box<dyn StorageAdapter>) -> Self {
+        EncodedKeys { inner }
+    }
+}
+
+#[async_trait]
+impl AdapterService for EncodedKeys {
+    fn service_name(&self) -> &'static str {
+        self.inner.service_name()
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn version(&self) -> &str {
+        self.inner.version()
+    }
+
+    fn manifest(&self) -> Option<&AdapterManifest> {
+        self.inner.manifest()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        self.inner.shutdown().await
+    }
+}
+
+#[async_trait]
+impl StorageAdapter for EncodedKeys {
+    async fn store(&mut self, key: &str, data: &[u8]) -> Result<(), ServiceError> {
+        self.inner.store(&KeyCodec::encode(key), data).await
+    }
+
+    async fn retrieve(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        self.inner.retrieve(&KeyCodec::encode(key)).await
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), ServiceError> {
+        self.inner.delete(&KeyCodec::encode(key)).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, ServiceError> {
+        self.inner.exists(&KeyCodec::encode(key)).await
+    }
+
+    /// Pages can hold fewer than `limit` keys, since keys without the
+    /// encoding are skipped. The cursor is the inner adapter's.
+    async fn list_keys_paginated(
+        &self,
+        prefix: Option<&str>,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<KeyPage, ServiceError> {
+        let prefix = prefix.map(KeyCodec::encode);
+        let page = self
+            .inner
+            .list_keys_paginated(prefix.as_deref(), cursor, limit)
+            .await?;
+
+        let keys = page
+            .keys
+            .into_iter()
+            .filter_map(|key| {
+                if !KeyCodec::is_canonical(&key) {
+                    tracing::warn!(
+                        "Skipping storage key '{}' without the key encoding, run `ai_messenger data migrate-keys`",
+                        key
+                    );
+                    return None;
+                }
+                KeyCodec::decode(&key).ok()
+            })
+            .collect();
+
+        Ok(KeyPage {
+            keys,
+            next_cursor: page.next_cursor,
+        })
+    }
+}
+
+/// Outcome of `migrate_keys`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyMigration {
+    /// Keys already in canonical form
+    pub unchanged: usize,
+    /// Keys moved to their canonical form
+    pub migrated: usize,
+    /// Keys left alone because their canonical form was already taken
+    pub conflicts: Vec<String>,
+}
+
+/// Move keys stored before the encoding to their canonical form
+///
+/// Works on the raw adapter, not on `EncodedKeys`. A stored key is taken
+/// as a logical key unless it already is canonical, so running the
+/// migration again changes nothing.
+pub async fn migrate_keys(storage: &mut dyn StorageAdapter) -> Result<KeyMigration, ServiceError> {
+    let mut migration = KeyMigration::default();
+
+    for key in storage.list_keys(None).await? {
+        if KeyCodec::is_canonical(&key) {
+            migration.unchanged += 1;
+            continue;
+        }
+
+        let encoded = KeyCodec::encode(&key);
+        if storage.exists(&encoded).await? {
+            migration.conflicts.push(key);
+            continue;
+        }
+
+        // Store before deleting, so an interrupted migration loses nothing
+        let data = storage.retrieve(&key).await?;
+        storage.store(&encoded, &data).await?;
+        storage.delete(&key).await?;
+        migration.migrated += 1;
+    }
+
+    Ok(migration)
+}
@@ -0,0 +1,83 @@
+use crate::adapter::traits::ServiceError;
+use crate::config::defaults::DEFAULT_RETRY_AFTER_SECS;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Bounds concurrent calls to an adapter, queueing a limited number of extras
+pub struct ConcurrencyLimiter {
+    max_concurrent: usize,
+    max_queued: usize,
+    provider: String,
+    queued: AtomicUsize,
+    semaphore: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter for `provider` allowing `max_concurrent` calls and `max_queued` waiters
+    pub fn new(provider: &str, max_concurrent: usize, max_queued: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+
+        ConcurrencyLimiter {
+            max_concurrent,
+            max_queued,
+            provider: provider.to_string(),
+            queued: AtomicUsize::new(0),
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+        }
+    }
+
+    /// Wait for a slot, or fail with `Overloaded` if the queue is full
+    ///
+    /// The slot is released when the permit is dropped, including when the
+    /// caller is cancelled while waiting or while the call is in flight.
+    pub async fn acquire(&self) -> Result<OwnedSemaphorePermit, ServiceError> {
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= self.max_queued {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(ServiceError::Overloaded {
+                retry_after_secs: DEFAULT_RETRY_AFTER_SECS,
+            });
+        }
+
+        let _waiting = QueueSlot(&self.queued);
+
+        self.semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| ServiceError::ServiceUnavailable("Adapter is shutting down".to_string()))
+    }
+
+    /// Provider the limiter guards
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    /// Number of calls currently holding a slot (for metrics)
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrent - self.semaphore.available_permits()
+    }
+
+    /// Number of calls waiting for a slot (for metrics)
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// Configured maximum of concurrent calls
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+}
+
+/// Leaves the wait queue when dropped
+struct QueueSlot<'a>(&'a AtomicUsize);
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
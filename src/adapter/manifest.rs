@@ -0,0 +1,496 @@
+use crate::adapter::traits::ServiceError;
+use crate::config::schema::{ServiceAdapterConfig, StrictConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Manifest file installed next to `adapter.wasm`
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+/// Capability of adapters returning embeddings of text
+pub const CAPABILITY_EMBEDDINGS: &str = "embeddings";
+
+/// Capability of adapters accepting image parts in messages
+pub const CAPABILITY_IMAGES: &str = "images";
+
+/// Capability of adapters classifying content for `[moderation.adapter]`
+pub const CAPABILITY_MODERATION: &str = "moderation";
+
+/// Capability of adapters sending replies in chunks as they're generated
+pub const CAPABILITY_STREAMING: &str = "streaming";
+
+/// Capability of adapters calling tools
+pub const CAPABILITY_TOOLS: &str = "tools";
+
+/// Keys of `[adapters.<service>.config]` read by the host, not the adapter
+const HOST_CONFIG_KEYS: [&str; 2] = ["defaults", "model_info"];
+
+/// What an adapter declares about itself in its `manifest.toml`
+///
+/// The manifest is optional; adapters without one declare nothing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdapterManifest {
+    /// Optional features the adapter supports, e.g. `images`
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Keys accepted in `[adapters.<service>.config]`, by name
+    ///
+    /// Without it, the config isn't checked beyond `required_config`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_schema: Option<BTreeMap<String, ConfigField>>,
+    /// Keys that must be set, and not empty, in `[adapters.<service>.config]`
+    #[serde(default)]
+    pub required_config: Vec<String>,
+}
+
+impl AdapterManifest {
+    /// Path of the manifest belonging to `module_path`
+    pub fn path_for_module(module_path: &Path) -> PathBuf {
+        module_path.with_file_name(MANIFEST_FILE)
+    }
+
+    /// Read the manifest next to `module_path`, as `strict` asks
+    ///
+    /// A missing manifest declares nothing. A manifest that can't be read or
+    /// doesn't describe an adapter fails loading with `strict`; otherwise
+    /// it's logged and the adapter is loaded as if it had none.
+    pub fn load_for_module(module_path: &Path, strict: bool) -> Result<Self, ServiceError> {
+        match Self::read_for_module(module_path) {
+            Ok(manifest) => Ok(manifest.unwrap_or_default()),
+            Err(e) if strict => Err(e),
+            Err(e) => {
+                tracing::warn!("{}; loading the adapter without it", e);
+                Ok(AdapterManifest::default())
+            }
+        }
+    }
+
+    /// Read and validate the manifest next to `module_path`
+    ///
+    /// Returns `Ok(None)` if there is none, so callers can tell an adapter
+    /// without a manifest from one with a broken manifest.
+    pub fn read_for_module(module_path: &Path) -> Result<Option<Self>, ServiceError> {
+        let path = Self::path_for_module(module_path);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(ServiceError::InitializationFailed(format!(
+                    "Failed to read adapter manifest {}: {}",
+                    path.display(),
+                    e
+                )));
+            }
+        };
+
+        let invalid = |problem: String| {
+            ServiceError::InitializationFailed(format!(
+                "Invalid adapter manifest {}: {}",
+                path.display(),
+                problem
+            ))
+        };
+        let manifest: AdapterManifest =
+            toml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+        manifest.validate().map_err(invalid)?;
+
+        Ok(Some(manifest))
+    }
+
+    /// Check what the types alone don't: names are set and required keys declared
+    fn validate(&self) -> Result<(), String> {
+        if self.capabilities.iter().any(|name| name.trim().is_empty()) {
+            return Err("`capabilities` contains an empty name".to_string());
+        }
+        for key in &self.required_config {
+            if key.trim().is_empty() {
+                return Err("`required_config` contains an empty key".to_string());
+            }
+            if let Some(schema) = &self.config_schema
+                && !schema.contains_key(key)
+            {
+                return Err(format!(
+                    "`required_config` lists `{}`, which `config_schema` doesn't declare",
+                    key
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the adapter declares `capability`
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities
+            .iter()
+            .any(|declared| declared == capability)
+    }
+
+    /// Check that `config` sets every key in `required_config`
+    ///
+    /// Runs before the module is loaded, so a forgotten `api_key` fails at
+    /// startup instead of as a 401 on the first request. Keys holding an
+    /// empty string, array or table count as missing. Secret placeholders
+    /// are checked as written, before they're resolved.
+    pub fn check_config(
+        &self,
+        service: &str,
+        config: &ServiceAdapterConfig,
+    ) -> Result<(), ServiceError> {
+        for key in &self.required_config {
+            let problem = match config.config.get(key) {
+                None => "is missing",
+                Some(value) if is_empty(value) => "is empty",
+                Some(_) => continue,
+            };
+
+            return Err(ServiceError::InvalidConfig(format!(
+                "{} adapter {}@{} requires `{}` in [adapters.{}.config], but it {}",
+                service, config.provider, config.version, key, service, problem
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check `config` against `config_schema` as `strict` asks
+    ///
+    /// Unknown keys, typically typos such as `baseurl`, and values of the
+    /// wrong type fail with `strict`, are logged with `warn` and ignored
+    /// with `off`.
+    pub fn check_config_schema(
+        &self,
+        service: &str,
+        config: &ServiceAdapterConfig,
+        strict: StrictConfig,
+    ) -> Result<(), ServiceError> {
+        if strict == StrictConfig::Off {
+            return Ok(());
+        }
+
+        let problems = self.config_problems(service, config);
+        if problems.is_empty() {
+            return Ok(());
+        }
+        if strict == StrictConfig::Strict {
+            return Err(ServiceError::InvalidConfig(problems.join("; ")));
+        }
+        for problem in problems {
+            tracing::warn!("{}", problem);
+        }
+        Ok(())
+    }
+
+    /// Keys of `config` that `config_schema` doesn't declare or declares with another type
+    fn config_problems(&self, service: &str, config: &ServiceAdapterConfig) -> Vec<String> {
+        let (Some(schema), Some(table)) = (&self.config_schema, config.config.as_table()) else {
+            return Vec::new();
+        };
+
+        let adapter = format!("{} adapter {}@{}", service, config.provider, config.version);
+        table
+            .iter()
+            .filter(|(key, _)| !HOST_CONFIG_KEYS.contains(&key.as_str()))
+            .filter_map(|(key, value)| match schema.get(key) {
+                None => Some(format!(
+                    "{} doesn't support `{}` in [adapters.{}.config]",
+                    adapter, key, service
+                )),
+                Some(field) if !field.value_type.matches(value) => Some(format!(
+                    "{} expects `{}` in [adapters.{}.config] to be {}, not {}",
+                    adapter,
+                    key,
+                    service,
+                    field.value_type.as_str(),
+                    value.type_str()
+                )),
+                Some(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// A key an adapter accepts in its config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigField {
+    /// What the key sets, for config forms and `adapter list`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub value_type: ConfigType,
+}
+
+/// Type of a config value, as TOML names it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigType {
+    Array,
+    Boolean,
+    /// Integers are accepted too
+    Float,
+    Integer,
+    String,
+    Table,
+}
+
+impl ConfigType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConfigType::Array => "array",
+            ConfigType::Boolean => "boolean",
+            ConfigType::Float => "float",
+            ConfigType::Integer => "integer",
+            ConfigType::String => "string",
+            ConfigType::Table => "table",
+        }
+    }
+
+    fn matches(self, value: &toml::Value) -> bool {
+        matches!(
+            (self, value),
+            (ConfigType::Array, toml::Value::Array(_))
+                | (ConfigType::Boolean, toml::Value::Boolean(_))
+                | (
+                    ConfigType::Float,
+                    toml::Value::Float(_) | toml::Value::Integer(_)
+                )
+                | (ConfigType::Integer, toml::Value::Integer(_))
+                | (ConfigType::String, toml::Value::String(_))
+                | (ConfigType::Table, toml::Value::Table(_))
+        )
+    }
+}
+
+fn is_empty(value: &toml::Value) -> bool {
+    match value {
+        toml::Value::String(string) => string.trim().is_empty(),
+        toml::Value::Array(array) => array.is_empty(),
+        toml::Value::Table(table) => table.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::traits::Capabilities;
+
+    fn manifest(required: &[&str]) -> AdapterManifest {
+        AdapterManifest {
+            required_config: required.iter().map(|key| key.to_string()).collect(),
+            ..AdapterManifest::default()
+        }
+    }
+
+    #[test]
+    fn test_check_config_names_missing_key() {
+        let config = ServiceAdapterConfig::new("openai").with_setting("model", "gpt-4o");
+
+        let error = manifest(&["model", "api_key"])
+            .check_config("llm", &config)
+            .unwrap_err();
+
+        assert!(matches!(error, ServiceError::InvalidConfig(_)));
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration: llm adapter openai@latest requires `api_key` in \
+             [adapters.llm.config], but it is missing"
+        );
+    }
+
+    #[test]
+    fn test_check_config_rejects_empty_values() {
+        for value in [
+            toml::Value::String("  ".to_string()),
+            toml::Value::Array(vec![]),
+            toml::Value::Table(toml::Table::new()),
+        ] {
+            let config = ServiceAdapterConfig::new("openai").with_setting("api_key", value);
+
+            let error = manifest(&["api_key"])
+                .check_config("llm", &config)
+                .unwrap_err();
+            assert!(error.to_string().ends_with("but it is empty"));
+        }
+    }
+
+    #[test]
+    fn test_check_config_accepts_set_keys() {
+        let config = ServiceAdapterConfig::new("openai")
+            .with_setting("api_key", "${ENV:OPENAI_API_KEY}")
+            .with_setting("max_retries", 0);
+
+        assert!(
+            manifest(&["api_key", "max_retries"])
+                .check_config("llm", &config)
+                .is_ok()
+        );
+        assert!(manifest(&[]).check_config("llm", &config).is_ok());
+    }
+
+    fn schema_manifest() -> AdapterManifest {
+        toml::from_str(
+            r#"
+[config_schema.base_url]
+type = "string"
+description = "URL of the provider's API"
+
+[config_schema.timeout_secs]
+type = "integer"
+
+[config_schema.temperature]
+type = "float"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_config_schema_accepts_declared_keys() {
+        let config = ServiceAdapterConfig::new("ollama")
+            .with_setting("base_url", "http://localhost:11434")
+            .with_setting("temperature", 1)
+            .with_setting("timeout_secs", 30)
+            .with_setting("model_info", toml::Table::new());
+
+        for strict in [StrictConfig::Strict, StrictConfig::Warn] {
+            assert!(
+                schema_manifest()
+                    .check_config_schema("llm", &config, strict)
+                    .is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_config_schema_unknown_key() {
+        let config = ServiceAdapterConfig::new("ollama")
+            .with_setting("baseurl", "http://localhost:11434")
+            .with_setting("timeout_secs", "30");
+
+        let error = schema_manifest()
+            .check_config_schema("llm", &config, StrictConfig::Strict)
+            .unwrap_err();
+        assert!(matches!(error, ServiceError::InvalidConfig(_)));
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration: llm adapter ollama@latest doesn't support `baseurl` in \
+             [adapters.llm.config]; llm adapter ollama@latest expects `timeout_secs` in \
+             [adapters.llm.config] to be integer, not string"
+        );
+
+        // Only logged in warn mode, not checked at all when off
+        for strict in [StrictConfig::Warn, StrictConfig::Off] {
+            assert!(
+                schema_manifest()
+                    .check_config_schema("llm", &config, strict)
+                    .is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_config_schema_without_schema() {
+        let config = ServiceAdapterConfig::new("ollama").with_setting("baseurl", "anything");
+
+        assert!(
+            manifest(&[])
+                .check_config_schema("llm", &config, StrictConfig::Strict)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_load_for_module() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let module_path = temp_dir.path().join("adapter.wasm");
+
+        // No manifest declares nothing
+        assert_eq!(
+            AdapterManifest::read_for_module(&module_path).unwrap(),
+            None
+        );
+        assert_eq!(
+            AdapterManifest::load_for_module(&module_path, true).unwrap(),
+            AdapterManifest::default()
+        );
+
+        std::fs::write(
+            temp_dir.path().join(MANIFEST_FILE),
+            "required_config = [\"api_key\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            AdapterManifest::load_for_module(&module_path, true).unwrap(),
+            manifest(&["api_key"])
+        );
+
+        std::fs::write(
+            temp_dir.path().join(MANIFEST_FILE),
+            "capabilities = [\"images\"]\n",
+        )
+        .unwrap();
+        let manifest = AdapterManifest::load_for_module(&module_path, true).unwrap();
+        assert!(manifest.has_capability(CAPABILITY_IMAGES));
+        assert!(!manifest.has_capability("audio"));
+    }
+
+    #[test]
+    fn test_capabilities_from_manifest() {
+        let manifest: AdapterManifest =
+            toml::from_str("capabilities = [\"streaming\", \"tools\", \"audio\"]\n").unwrap();
+
+        assert_eq!(
+            Capabilities::from_manifest(&manifest),
+            Capabilities {
+                streaming: true,
+                tools: true,
+                ..Capabilities::default()
+            }
+        );
+        assert_eq!(
+            Capabilities::from_manifest(&AdapterManifest::default()),
+            Capabilities::default()
+        );
+    }
+
+    #[test]
+    fn test_load_for_module_invalid_manifest() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let module_path = temp_dir.path().join("adapter.wasm");
+
+        for (content, problem) in [
+            (
+                "required_config = 1",
+                "invalid type: integer `1`, expected a sequence",
+            ),
+            (
+                "capabilities = [\"\"]",
+                "`capabilities` contains an empty name",
+            ),
+            (
+                "required_config = [\"api_key\"]\n[config_schema.model]\ntype = \"string\"\n",
+                "`required_config` lists `api_key`, which `config_schema` doesn't declare",
+            ),
+            (
+                "[config_schema.model]\ntype = \"text\"\n",
+                "unknown variant `text`",
+            ),
+        ] {
+            std::fs::write(temp_dir.path().join(MANIFEST_FILE), content).unwrap();
+
+            // Strict loading fails with the specific problem
+            let error = AdapterManifest::load_for_module(&module_path, true).unwrap_err();
+            assert!(matches!(error, ServiceError::InitializationFailed(_)));
+            let message = error.to_string();
+            assert!(message.contains("Invalid adapter manifest"), "{}", message);
+            assert!(message.contains(problem), "{}", message);
+
+            // Lenient loading goes on without the manifest
+            assert_eq!(
+                AdapterManifest::load_for_module(&module_path, false).unwrap(),
+                AdapterManifest::default()
+            );
+        }
+    }
+}
@@ -3,14 +3,25 @@
 // This module provides the public interface for the WASM adapter system,
 // enabling config-driven loading and management of service adapters.
 
+pub mod balancer;
+pub mod breaker;
+pub mod encryption;
+pub mod http;
+pub mod keys;
+pub mod limiter;
+pub mod manifest;
 pub mod runtime;
 pub mod services;
+pub mod stream;
 pub mod traits;
 
 #[cfg(test)]
 mod tests;
 
 // Re-export key types for public API
-pub use runtime::WasmRuntime;
 pub use services::AdapterRegistry;
-pub use traits::{AdapterService, ServiceError};
+#[allow(unused_imports)] // The binary imports these from their modules
+pub use {
+    runtime::WasmRuntime,
+    traits::{AdapterService, ServiceError},
+};
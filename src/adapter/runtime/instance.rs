@@ -1,21 +1,83 @@
-use crate::adapter::traits::ServiceError;
-use wasmtime::{Store, component::Component};
+use super::logging::{self, GuestLogger};
+use crate::adapter::http::{ProviderRequest, ProviderResponse};
+use crate::adapter::traits::{ModelInfo, ServiceError};
+use wasmtime::component::{Component, Linker};
+use wasmtime::{Engine, Store};
+
+/// Fuel each function call starts with
+pub const CALL_FUEL: u64 = 100_000;
 
 /// WASM instance wrapper providing lifecycle management
 pub struct WasmInstance {
     store: Store<InstanceState>,
-    #[allow(dead_code)] // Used in future WIT bindings implementation
     component: Component,
+    service: String,
     provider_name: String,
     version: String,
     is_ready: bool,
+    /// Fuel used by the last function call, until taken
+    fuel_consumed: Option<u64>,
+}
+
+/// Everything needed to create more instances of a loaded adapter
+///
+/// Compiled components are shared, so this is cheap to keep around.
+#[derive(Clone)]
+pub struct InstanceTemplate {
+    component: Component,
+    config_json: String,
+    engine: Engine,
+    provider_name: String,
+    service: String,
+    version: String,
+}
+
+impl InstanceTemplate {
+    /// Create and initialize a fresh instance with its own store
+    pub async fn instantiate(&self) -> Result<WasmInstance, ServiceError> {
+        let mut instance = WasmInstance::new(
+            &self.engine,
+            self.component.clone(),
+            self.service.clone(),
+            self.provider_name.clone(),
+            self.version.clone(),
+            self.config_json.clone(),
+        )?;
+        instance.initialize().await?;
+
+        Ok(instance)
+    }
+
+    /// Get provider name
+    pub fn provider_name(&self) -> &str {
+        &self.provider_name
+    }
+
+    /// Get version
+    pub fn version(&self) -> &str {
+        &self.version
+    }
 }
 
 /// State shared with WASM instances
-#[derive(Default)]
 pub struct InstanceState {
     pub config_json: String,
     pub is_initialized: bool,
+    /// Target of the guest's `logging` import calls
+    pub logger: GuestLogger,
+}
+
+/// Linker providing the host's imports to adapter components
+///
+/// Without it, adapters importing `ai-messenger:llm/logging` fail to
+/// instantiate.
+pub fn host_linker(engine: &Engine) -> Result<Linker<InstanceState>, ServiceError> {
+    let mut linker = Linker::new(engine);
+    logging::add_to_linker(&mut linker).map_err(|e| {
+        ServiceError::InitializationFailed(format!("Linking host imports failed: {e}"))
+    })?;
+
+    Ok(linker)
 }
 
 impl WasmInstance {
@@ -23,6 +85,7 @@ impl WasmInstance {
     pub fn new(
         engine: &wasmtime::Engine,
         component: Component,
+        service: String,
         provider_name: String,
         version: String,
         config_json: String,
@@ -30,6 +93,7 @@ impl WasmInstance {
         let state = InstanceState {
             config_json,
             is_initialized: false,
+            logger: GuestLogger::new(&service, &provider_name, &version),
         };
 
         let store = Store::new(engine, state);
@@ -37,9 +101,11 @@ impl WasmInstance {
         Ok(WasmInstance {
             store,
             component,
+            service,
             provider_name,
             version,
             is_ready: false,
+            fuel_consumed: None,
         })
     }
 
@@ -50,8 +116,9 @@ impl WasmInstance {
             .set_fuel(1_000_000)
             .map_err(|e| ServiceError::InitializationFailed(format!("Fuel setting failed: {e}")))?;
 
-        // TODO: Instantiate component and call initialization function
-        // This will be implemented once we have WIT bindings
+        let _linker = host_linker(self.store.engine())?;
+        // TODO: Instantiate the component with the linker and call its
+        // initialization function once we have WIT bindings
 
         self.store.data_mut().is_initialized = true;
         self.is_ready = true;
@@ -60,6 +127,7 @@ impl WasmInstance {
     }
 
     /// Execute a function call on the WASM instance
+    #[allow(dead_code)] // Called once components are instantiated via WIT bindings
     pub async fn call_function(
         &mut self,
         _function_name: &str,
@@ -73,20 +141,61 @@ impl WasmInstance {
 
         // Add fuel for execution
         self.store
-            .set_fuel(100_000)
+            .set_fuel(CALL_FUEL)
             .map_err(|e| ServiceError::ExecutionError(format!("Fuel setting failed: {e}")))?;
 
         // TODO: Implement actual function calling via WIT bindings
         // For now, return placeholder
+        self.fuel_consumed = self
+            .remaining_fuel()
+            .map(|remaining| CALL_FUEL.saturating_sub(remaining));
         Ok(b"placeholder_response".to_vec())
     }
 
+    /// Call the adapter's `prepare-model-info-request` export
+    pub async fn prepare_model_info_request(
+        &mut self,
+        _model: &str,
+    ) -> Result<ProviderRequest, ServiceError> {
+        // TODO: Call the export via WIT bindings
+        Err(self.unbound_export("prepare-model-info-request"))
+    }
+
+    /// Call the adapter's `parse-model-info-response` export
+    pub async fn parse_model_info_response(
+        &mut self,
+        _response: ProviderResponse,
+    ) -> Result<ModelInfo, ServiceError> {
+        // TODO: Call the export via WIT bindings
+        Err(self.unbound_export("parse-model-info-response"))
+    }
+
+    /// Error for exports the host can't call until WIT bindings exist
+    pub fn unbound_export(&self, function: &str) -> ServiceError {
+        ServiceError::NotImplemented(format!(
+            "`{function}` can't be called: host WIT bindings are not implemented"
+        ))
+    }
+
+    /// Template for creating more instances of the same adapter
+    pub fn template(&self) -> InstanceTemplate {
+        InstanceTemplate {
+            component: self.component.clone(),
+            config_json: self.store.data().config_json.clone(),
+            engine: self.store.engine().clone(),
+            provider_name: self.provider_name.clone(),
+            service: self.service.clone(),
+            version: self.version.clone(),
+        }
+    }
+
     /// Get provider name
     pub fn provider_name(&self) -> &str {
         &self.provider_name
     }
 
     /// Get version
+    #[allow(dead_code)] // Library API; the pool's template reports the version
     pub fn version(&self) -> &str {
         &self.version
     }
@@ -104,7 +213,16 @@ impl WasmInstance {
     }
 
     /// Get remaining fuel (for monitoring)
+    #[allow(dead_code)] // Read for `WasmTrap` once the guest is called via WIT bindings
     pub fn remaining_fuel(&self) -> Option<u64> {
         self.store.get_fuel().ok()
     }
+
+    /// Fuel used by the last function call, if not taken yet
+    ///
+    /// Taking it means a call that doesn't go through the instance can't
+    /// report the fuel of an earlier one.
+    pub fn take_fuel_consumed(&mut self) -> Option<u64> {
+        self.fuel_consumed.take()
+    }
 }
@@ -1,6 +1,7 @@
 use crate::adapter::runtime::instance::WasmInstance;
 use crate::adapter::traits::ServiceError;
-use std::path::Path;
+use std::ffi::OsStr;
+use std::path::{self, Path};
 use thiserror::Error;
 use wasmtime::{Engine, component::Component};
 
@@ -11,6 +12,7 @@ pub enum LoaderError {
     #[error("Failed to compile WASM module: {0}")]
     CompilationError(String),
     #[error("Invalid WASM component: {0}")]
+    #[allow(dead_code)] // Reported by `validate_component` once it checks exports
     InvalidComponent(String),
 }
 
@@ -34,6 +36,7 @@ impl<'a> ModuleLoader<'a> {
     /// Load and compile WASM component from file
     pub async fn load_module(
         &self,
+        service: &str,
         module_path: &Path,
         config_json: &str,
     ) -> Result<WasmInstance, ServiceError> {
@@ -55,12 +58,13 @@ impl<'a> ModuleLoader<'a> {
             .map_err(|e| LoaderError::CompilationError(e.to_string()))?;
 
         // Extract metadata from file path
-        let (provider_name, version) = self.extract_metadata(module_path)?;
+        let (provider_name, version) = Self::extract_metadata(module_path)?;
 
         // Create instance
         let mut instance = WasmInstance::new(
             self.engine,
             component,
+            service.to_string(),
             provider_name,
             version,
             config_json.to_string(),
@@ -74,31 +78,47 @@ impl<'a> ModuleLoader<'a> {
 
     /// Extract provider name and version from module path
     /// Expected path: data/adapters/{service}/{provider}/{version}/adapter.wasm
-    fn extract_metadata(&self, module_path: &Path) -> Result<(String, String), ServiceError> {
+    ///
+    /// Works on path components rather than the path string, so it doesn't
+    /// depend on the platform's separator. The last `adapters` directory
+    /// wins, in case the data directory itself lives under one.
+    pub(crate) fn extract_metadata(module_path: &Path) -> Result<(String, String), ServiceError> {
         const PROVIDER_OFFSET: usize = 2;
         const VERSION_OFFSET: usize = 3;
 
-        let path_str = module_path.to_string_lossy();
-        let parts: Vec<&str> = path_str.split('/').collect();
+        let parts: Vec<&OsStr> = module_path
+            .components()
+            .filter_map(|component| match component {
+                path::Component::Normal(part) => Some(part),
+                _ => None,
+            })
+            .collect();
 
         // Find the adapters directory and extract provider/version
-        if let Some(adapters_index) = parts.iter().position(|&part| part == "adapters") {
-            let provider = parts.get(adapters_index + PROVIDER_OFFSET).ok_or_else(|| {
-                ServiceError::InvalidConfig("Cannot extract provider from path".to_string())
+        let adapters_index = parts
+            .iter()
+            .rposition(|&part| part == "adapters")
+            .ok_or_else(|| {
+                ServiceError::InvalidConfig("Invalid adapter path format".to_string())
             })?;
-            let version = parts.get(adapters_index + VERSION_OFFSET).ok_or_else(|| {
-                ServiceError::InvalidConfig("Cannot extract version from path".to_string())
-            })?;
-
-            Ok((provider.to_string(), version.to_string()))
-        } else {
-            Err(ServiceError::InvalidConfig(
-                "Invalid adapter path format".to_string(),
-            ))
-        }
+        let part = |offset: usize, name: &str| {
+            parts
+                .get(adapters_index + offset)
+                .and_then(|part| part.to_str())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    ServiceError::InvalidConfig(format!("Cannot extract {} from path", name))
+                })
+        };
+
+        Ok((
+            part(PROVIDER_OFFSET, "provider")?,
+            part(VERSION_OFFSET, "version")?,
+        ))
     }
 
     /// Validate WASM component exports (future enhancement)
+    #[allow(dead_code)] // TODO: Call when loading once WIT bindings exist
     pub async fn validate_component(&self, _component: &Component) -> Result<(), ServiceError> {
         // TODO: Validate that component exports expected WIT interface
         // This will be implemented once we have proper WIT bindings
@@ -0,0 +1,255 @@
+//! Host side of the WIT `logging` import
+//!
+//! Guest log calls become tracing events with target `adapter`, inside a
+//! span carrying the adapter's service, provider and version, so
+//! `RUST_LOG=adapter=debug` shows them interleaved with host logs.
+
+use std::time::{Duration, Instant};
+use tracing::Span;
+use wasmtime::component::{ComponentType, Lift, Linker, Lower};
+
+use super::instance::InstanceState;
+
+/// Name of the WIT interface providing the import
+pub const LOGGING_INTERFACE: &str = "ai-messenger:llm/logging@0.0.1-alpha";
+
+/// Log messages an instance may emit per `RATE_WINDOW`; the rest are dropped
+pub const MAX_GUEST_LOGS_PER_WINDOW: u32 = 50;
+
+/// Window the guest log rate limit applies to
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Severity of a guest log message (the WIT `level` enum)
+#[derive(ComponentType, Lift, Lower, Debug, Clone, Copy, PartialEq)]
+#[component(enum)]
+#[repr(u8)]
+#[allow(dead_code)] // Lifted from guest calls, which the compiler doesn't see
+pub enum GuestLogLevel {
+    #[component(name = "trace")]
+    Trace,
+    #[component(name = "debug")]
+    Debug,
+    #[component(name = "info")]
+    Info,
+    #[component(name = "warn")]
+    Warn,
+    #[component(name = "error")]
+    Error,
+}
+
+/// Forwards an instance's log calls into host tracing, with a rate limit
+pub struct GuestLogger {
+    dropped: u64,
+    logged: u32,
+    span: Span,
+    window_start: Instant,
+}
+
+impl GuestLogger {
+    pub fn new(service: &str, provider: &str, version: &str) -> Self {
+        GuestLogger {
+            dropped: 0,
+            logged: 0,
+            span: tracing::info_span!(target: "adapter", "adapter", service, provider, version),
+            window_start: Instant::now(),
+        }
+    }
+
+    /// Log a message from the guest, unless it exceeds the rate limit
+    pub fn log(&mut self, level: GuestLogLevel, target: &str, message: &str) {
+        self.log_at(Instant::now(), level, target, message);
+    }
+
+    /// Messages dropped by the rate limit since the instance was created
+    #[allow(dead_code)] // Used in tests
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn log_at(&mut self, now: Instant, level: GuestLogLevel, target: &str, message: &str) {
+        let _entered = self.span.enter();
+
+        if now.duration_since(self.window_start) >= RATE_WINDOW {
+            if self.logged > MAX_GUEST_LOGS_PER_WINDOW {
+                tracing::warn!(
+                    target: "adapter",
+                    "Dropped {} log messages over the limit of {} per {:?}",
+                    self.logged - MAX_GUEST_LOGS_PER_WINDOW,
+                    MAX_GUEST_LOGS_PER_WINDOW,
+                    RATE_WINDOW
+                );
+            }
+            self.window_start = now;
+            self.logged = 0;
+        }
+
+        self.logged = self.logged.saturating_add(1);
+        if self.logged > MAX_GUEST_LOGS_PER_WINDOW {
+            self.dropped += 1;
+            return;
+        }
+
+        match level {
+            GuestLogLevel::Trace => {
+                tracing::trace!(target: "adapter", guest_target = target, "{}", message)
+            }
+            GuestLogLevel::Debug => {
+                tracing::debug!(target: "adapter", guest_target = target, "{}", message)
+            }
+            GuestLogLevel::Info => {
+                tracing::info!(target: "adapter", guest_target = target, "{}", message)
+            }
+            GuestLogLevel::Warn => {
+                tracing::warn!(target: "adapter", guest_target = target, "{}", message)
+            }
+            GuestLogLevel::Error => {
+                tracing::error!(target: "adapter", guest_target = target, "{}", message)
+            }
+        }
+    }
+}
+
+/// Provide the `logging` import to components linked with `linker`
+pub fn add_to_linker(linker: &mut Linker<InstanceState>) -> wasmtime::Result<()> {
+    linker.instance(LOGGING_INTERFACE)?.func_wrap(
+        "log",
+        |mut store, (level, target, message): (GuestLogLevel, String, String)| {
+            store.data_mut().logger.log(level, &target, &message);
+            Ok(())
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::{Layer, registry::LookupSpan};
+
+    /// Records "LEVEL target span: message" for every event
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<String>>>);
+
+    struct MessageVisitor(String);
+
+    impl Visit for MessageVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                self.0 = format!("{:?}", value);
+            }
+        }
+    }
+
+    impl<S: tracing::Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+        fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+            let mut visitor = MessageVisitor(String::new());
+            event.record(&mut visitor);
+            let span = ctx.event_span(event).map_or("-", |span| span.name());
+
+            self.0.lock().unwrap().push(format!(
+                "{} {} {}: {}",
+                event.metadata().level(),
+                event.metadata().target(),
+                span,
+                visitor.0
+            ));
+        }
+    }
+
+    fn captured(f: impl FnOnce()) -> Vec<String> {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, f);
+
+        capture.0.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn test_guest_logs_forwarded_to_tracing() {
+        let lines = captured(|| {
+            let mut logger = GuestLogger::new("llm", "ollama", "1.0.0");
+            logger.log(GuestLogLevel::Info, "request", "Preparing request");
+            logger.log(GuestLogLevel::Error, "", "Unexpected response");
+        });
+
+        assert_eq!(
+            lines,
+            [
+                "INFO adapter adapter: Preparing request",
+                "ERROR adapter adapter: Unexpected response",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_guest_logs_rate_limited() {
+        let lines = captured(|| {
+            let mut logger = GuestLogger::new("llm", "ollama", "1.0.0");
+            let start = Instant::now();
+            for i in 0..MAX_GUEST_LOGS_PER_WINDOW + 10 {
+                logger.log_at(start, GuestLogLevel::Info, "", &format!("line {i}"));
+            }
+            assert_eq!(logger.dropped(), 10);
+
+            // The next window reports the drops and logs again
+            logger.log_at(start + RATE_WINDOW, GuestLogLevel::Info, "", "after");
+            assert_eq!(logger.dropped(), 10);
+        });
+
+        assert_eq!(lines.len(), MAX_GUEST_LOGS_PER_WINDOW as usize + 2);
+        assert!(lines[lines.len() - 2].starts_with("WARN adapter adapter: Dropped 10"));
+        assert_eq!(lines[lines.len() - 1], "INFO adapter adapter: after");
+    }
+
+    #[test]
+    fn test_add_to_linker() {
+        let engine = wasmtime::Engine::default();
+        let mut linker = Linker::<InstanceState>::new(&engine);
+
+        assert!(add_to_linker(&mut linker).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_logging_import_linked_for_components() {
+        let mut config = wasmtime::Config::new();
+        config.async_support(true);
+        let engine = wasmtime::Engine::new(&config).unwrap();
+        let component = wasmtime::component::Component::new(
+            &engine,
+            r#"(component
+                (type $level (enum "trace" "debug" "info" "warn" "error"))
+                (import "ai-messenger:llm/logging@0.0.1-alpha" (instance
+                    (export "level" (type $l (eq $level)))
+                    (export "log" (func
+                        (param "level" $l) (param "target" string) (param "message" string)))
+                ))
+            )"#,
+        )
+        .unwrap();
+        let state = || InstanceState {
+            config_json: "{}".to_string(),
+            is_initialized: false,
+            logger: GuestLogger::new("llm", "ollama", "1.0.0"),
+        };
+
+        let unlinked = Linker::<InstanceState>::new(&engine);
+        let mut store = wasmtime::Store::new(&engine, state());
+        assert!(
+            unlinked
+                .instantiate_async(&mut store, &component)
+                .await
+                .is_err()
+        );
+
+        let linker = crate::adapter::runtime::instance::host_linker(&engine).unwrap();
+        let mut store = wasmtime::Store::new(&engine, state());
+        assert!(
+            linker
+                .instantiate_async(&mut store, &component)
+                .await
+                .is_ok()
+        );
+    }
+}
@@ -2,18 +2,26 @@
 
 pub mod instance;
 pub mod loader;
+pub mod logging;
+pub mod pool;
 
 pub use instance::WasmInstance;
-pub use loader::{LoaderError, ModuleLoader};
+pub use loader::ModuleLoader;
+pub use pool::InstancePool;
 
 use crate::adapter::traits::ServiceError;
 use std::collections::HashMap;
+use std::sync::Arc;
 use wasmtime::{Config, Engine};
 
 /// Central WASM runtime managing all adapter instances
+///
+/// The runtime lock only guards the pool map; calls check out an instance
+/// from their provider's pool, so providers (and, with a pool size above 1,
+/// requests to one provider) execute concurrently.
 pub struct WasmRuntime {
     engine: Engine,
-    instances: HashMap<String, WasmInstance>,
+    pools: HashMap<String, Arc<InstancePool>>,
 }
 
 impl WasmRuntime {
@@ -32,54 +40,73 @@ impl WasmRuntime {
 
         Ok(WasmRuntime {
             engine,
-            instances: HashMap::new(),
+            pools: HashMap::new(),
         })
     }
 
     /// Load a WASM adapter module for a specific service
+    ///
+    /// Up to `pool_size` instances are created on demand for concurrent calls.
     pub async fn load_adapter(
         &mut self,
         service: &str,
         module_path: &std::path::Path,
         config_json: &str,
+        pool_size: usize,
     ) -> Result<(), ServiceError> {
         let loader = ModuleLoader::new(&self.engine);
-        let instance = loader.load_module(module_path, config_json).await?;
+        let instance = loader
+            .load_module(service, module_path, config_json)
+            .await?;
 
-        let instance_key = format!("{}_{}", service, instance.provider_name());
-        self.instances.insert(instance_key, instance);
+        self.add_instance(service, instance, pool_size);
 
         Ok(())
     }
 
-    /// Get adapter instance by service and provider
-    pub fn get_instance(&self, service: &str, provider: &str) -> Option<&WasmInstance> {
-        let key = format!("{}_{}", service, provider);
-        self.instances.get(&key)
+    /// Register an instance for a service, replacing any pool for the same provider
+    pub fn add_instance(&mut self, service: &str, instance: WasmInstance, pool_size: usize) {
+        let pool_key = format!("{}_{}", service, instance.provider_name());
+        self.pools
+            .insert(pool_key, Arc::new(InstancePool::new(instance, pool_size)));
     }
 
-    /// Get mutable adapter instance by service and provider
-    pub fn get_instance_mut(&mut self, service: &str, provider: &str) -> Option<&mut WasmInstance> {
+    /// Get the instance pool of an adapter by service and provider
+    ///
+    /// Returns a handle, so the runtime lock can be released before an
+    /// instance is checked out for a call.
+    pub fn get_pool(&self, service: &str, provider: &str) -> Option<Arc<InstancePool>> {
         let key = format!("{}_{}", service, provider);
-        self.instances.get_mut(&key)
+        self.pools.get(&key).cloned()
+    }
+
+    /// Engine that instances must be created with
+    #[allow(dead_code)] // Used when building instances outside the loader (tests)
+    pub fn engine(&self) -> &Engine {
+        &self.engine
     }
 
     /// Shutdown all instances gracefully
+    ///
+    /// Instances checked out by an in-flight call are cleaned up when the
+    /// call returns them.
     pub async fn shutdown(&mut self) -> Result<(), ServiceError> {
-        for (_, instance) in self.instances.drain() {
-            instance.shutdown().await?;
+        for (_, pool) in self.pools.drain() {
+            pool.shutdown().await?;
         }
         Ok(())
     }
 
     /// List all loaded adapters
+    #[allow(dead_code)] // Library API; the registry lists adapters for the server
     pub fn list_adapters(&self) -> Vec<(&str, &str, &str)> {
-        self.instances
+        self.pools
             .iter()
-            .map(|(key, instance)| {
+            .map(|(key, pool)| {
                 let parts: Vec<&str> = key.split('_').collect();
                 let service = parts.first().unwrap_or(&"unknown");
-                (*service, instance.provider_name(), instance.version())
+                let template = pool.template();
+                (*service, template.provider_name(), template.version())
             })
             .collect()
     }
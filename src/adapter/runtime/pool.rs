@@ -0,0 +1,139 @@
+use crate::adapter::runtime::instance::{InstanceTemplate, WasmInstance};
+use crate::adapter::traits::ServiceError;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Pool of instances of one adapter, so calls to a provider can run in parallel
+///
+/// Calls need exclusive access to an instance, so a single instance would
+/// serialize all requests to its provider. Instances are created lazily up
+/// to `max_size`. Each instance has its own store and linear memory, so the
+/// pool's memory use grows with the number of instances created.
+pub struct InstancePool {
+    created: AtomicUsize,
+    idle: Mutex<Vec<WasmInstance>>,
+    max_size: usize,
+    permits: Arc<Semaphore>,
+    template: InstanceTemplate,
+}
+
+impl InstancePool {
+    /// Create a pool seeded with an initialized instance
+    ///
+    /// `max_size` is clamped to at least 1.
+    pub fn new(instance: WasmInstance, max_size: usize) -> Self {
+        let max_size = max_size.max(1);
+
+        InstancePool {
+            created: AtomicUsize::new(1),
+            template: instance.template(),
+            idle: Mutex::new(vec![instance]),
+            max_size,
+            permits: Arc::new(Semaphore::new(max_size)),
+        }
+    }
+
+    /// Check out an instance, waiting if all `max_size` instances are busy
+    ///
+    /// The instance returns to the pool when the guard is dropped.
+    pub async fn checkout(self: &Arc<Self>) -> Result<PooledInstance, ServiceError> {
+        let permit =
+            self.permits.clone().acquire_owned().await.map_err(|_| {
+                ServiceError::ServiceUnavailable("Instance pool closed".to_string())
+            })?;
+
+        let idle = self
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+
+        let instance = match idle {
+            Some(instance) => instance,
+            None => {
+                let instance = self.template.instantiate().await?;
+                let created = self.created.fetch_add(1, Ordering::SeqCst) + 1;
+                tracing::debug!(
+                    "Created instance {}/{} of {}",
+                    created,
+                    self.max_size,
+                    instance.provider_name()
+                );
+                instance
+            }
+        };
+
+        Ok(PooledInstance {
+            instance: Some(instance),
+            pool: self.clone(),
+            _permit: permit,
+        })
+    }
+
+    /// Number of instances created so far
+    #[allow(dead_code)] // Used in tests and by embedders
+    pub fn size(&self) -> usize {
+        self.created.load(Ordering::SeqCst)
+    }
+
+    /// Maximum number of instances
+    #[allow(dead_code)] // Used in tests and by embedders
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Template the pool creates instances from
+    pub fn template(&self) -> &InstanceTemplate {
+        &self.template
+    }
+
+    /// Shut down idle instances and reject further checkouts
+    ///
+    /// Checked-out instances are dropped when their guards are.
+    pub async fn shutdown(&self) -> Result<(), ServiceError> {
+        self.permits.close();
+
+        let idle = std::mem::take(&mut *self.idle.lock().unwrap_or_else(PoisonError::into_inner));
+        for instance in idle {
+            instance.shutdown().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Instance checked out of an `InstancePool`
+pub struct PooledInstance {
+    instance: Option<WasmInstance>,
+    pool: Arc<InstancePool>,
+    // Released after the instance is back in the pool
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Deref for PooledInstance {
+    type Target = WasmInstance;
+
+    fn deref(&self) -> &WasmInstance {
+        self.instance.as_ref().expect("instance present until drop")
+    }
+}
+
+impl DerefMut for PooledInstance {
+    fn deref_mut(&mut self) -> &mut WasmInstance {
+        self.instance.as_mut().expect("instance present until drop")
+    }
+}
+
+impl Drop for PooledInstance {
+    fn drop(&mut self) {
+        if let Some(instance) = self.instance.take() {
+            self.pool
+                .idle
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(instance);
+        }
+    }
+}
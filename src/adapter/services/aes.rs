@@ -0,0 +1,238 @@
+use crate::adapter::traits::{AdapterService, CryptoAdapter, ServiceError};
+use crate::config::schema::ServiceAdapterConfig;
+use crate::config::secrets::resolve_secrets;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use async_trait::async_trait;
+
+/// Provider name selecting the built-in AES-GCM encryption
+pub const AES_GCM_PROVIDER: &str = "aes-gcm";
+
+/// Length of the key in bytes (AES-256)
+const KEY_LEN: usize = 32;
+
+/// Length of the random nonce stored ahead of each ciphertext
+const NONCE_LEN: usize = 12;
+
+/// Length of the authentication tag ending each ciphertext
+const TAG_LEN: usize = 16;
+
+/// Check that `config` selects a crypto provider the host can run
+///
+/// WASM crypto adapters (`wit/crypto/crypto.wit`) can't be called until the
+/// host has WIT bindings for them, and encrypted storage would load, then
+/// fail on every value. So only the built-in provider is accepted for now.
+pub fn check_crypto_provider(config: &ServiceAdapterConfig) -> Result<(), ServiceError> {
+    if config.provider == AES_GCM_PROVIDER {
+        return Ok(());
+    }
+    Err(ServiceError::InvalidConfig(format!(
+        "Crypto provider '{}' isn't supported: only the built-in '{}' provider can encrypt yet",
+        config.provider, AES_GCM_PROVIDER
+    )))
+}
+
+/// Crypto adapter encrypting with AES-256-GCM on the host
+///
+/// Every `encrypt` draws a fresh random nonce and returns it followed by
+/// the ciphertext and its tag, so equal data never encrypts the same way
+/// and altered data fails to decrypt. The context is the associated data,
+/// so a ciphertext moved to another context fails to decrypt as well.
+pub struct AesGcmCrypto {
+    cipher: Aes256Gcm,
+}
+
+impl AesGcmCrypto {
+    /// Build the cipher from `key` in `[adapters.crypto.config]`
+    ///
+    /// The key is 64 hex digits (32 bytes), usually given as a
+    /// `${ENV:..}` or `${FILE:..}` secret rather than written in the config.
+    pub fn from_config(config: &ServiceAdapterConfig) -> Result<Self, ServiceError> {
+        let resolved = resolve_secrets(&config.config, "crypto")
+            .map_err(|e| ServiceError::InvalidConfig(e.to_string()))?;
+        let key = match resolved.get("key") {
+            Some(toml::Value::String(key)) => key.clone(),
+            Some(_) => {
+                return Err(ServiceError::InvalidConfig(
+                    "AES-GCM `key` must be a string".to_string(),
+                ));
+            }
+            None => {
+                return Err(ServiceError::InvalidConfig(
+                    "AES-GCM encryption needs a `key` in [adapters.crypto.config]".to_string(),
+                ));
+            }
+        };
+
+        Self::new(&decode_key(&key)?)
+    }
+
+    /// Build the cipher from a raw 32-byte key
+    pub fn new(key: &[u8]) -> Result<Self, ServiceError> {
+        let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| {
+            ServiceError::InvalidConfig(format!("AES-GCM keys are {} bytes", KEY_LEN))
+        })?;
+        Ok(AesGcmCrypto { cipher })
+    }
+}
+
+#[async_trait]
+impl AdapterService for AesGcmCrypto {
+    fn service_name(&self) -> &'static str {
+        "crypto"
+    }
+
+    fn provider_name(&self) -> &str {
+        AES_GCM_PROVIDER
+    }
+
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CryptoAdapter for AesGcmCrypto {
+    async fn encrypt(&self, context: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, ServiceError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: plaintext,
+            aad: context,
+        };
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|_| ServiceError::ExecutionError("AES-GCM encryption failed".to_string()))?;
+
+        let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    async fn decrypt(&self, context: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, ServiceError> {
+        if ciphertext.len() < NONCE_LEN + TAG_LEN {
+            return Err(ServiceError::ExecutionError(format!(
+                "Encrypted data of {} bytes is too short for AES-GCM",
+                ciphertext.len()
+            )));
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: context,
+        };
+
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| {
+                ServiceError::ExecutionError(
+                    "AES-GCM decryption failed: the data was altered or sealed with another key or context"
+                        .to_string(),
+                )
+            })
+    }
+}
+
+/// Bytes of a key written as hex digits, without naming the key in errors
+fn decode_key(key: &str) -> Result<Vec<u8>, ServiceError> {
+    let invalid = || {
+        ServiceError::InvalidConfig(format!(
+            "AES-GCM `key` must be {} hex digits ({} bytes)",
+            KEY_LEN * 2,
+            KEY_LEN
+        ))
+    };
+
+    let key = key.trim();
+    if key.len() != KEY_LEN * 2 || !key.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    (0..key.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&key[i..i + 2], 16).map_err(|_| invalid()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    fn crypto(config: &str) -> Result<AesGcmCrypto, ServiceError> {
+        let config: ServiceAdapterConfig =
+            toml::from_str(&format!("provider = \"aes-gcm\"\n[config]\n{}", config)).unwrap();
+        AesGcmCrypto::from_config(&config)
+    }
+
+    #[tokio::test]
+    async fn test_aes_gcm_round_trip() {
+        let crypto = crypto(&format!("key = \"{KEY}\"")).unwrap();
+
+        let sealed = crypto.encrypt(b"a", b"Hello, world").await.unwrap();
+        assert_eq!(sealed.len(), NONCE_LEN + 12 + TAG_LEN);
+        assert!(!sealed.windows(5).any(|window| window == b"Hello"));
+        assert_eq!(
+            crypto.decrypt(b"a", &sealed).await.unwrap(),
+            b"Hello, world"
+        );
+
+        // A fresh nonce each time
+        let again = crypto.encrypt(b"a", b"Hello, world").await.unwrap();
+        assert_ne!(sealed, again);
+        assert_eq!(crypto.decrypt(b"a", &again).await.unwrap(), b"Hello, world");
+
+        let empty = crypto.encrypt(b"", b"").await.unwrap();
+        assert_eq!(crypto.decrypt(b"", &empty).await.unwrap(), b"");
+    }
+
+    #[tokio::test]
+    async fn test_aes_gcm_rejects_altered_data_other_keys_and_contexts() {
+        let crypto = crypto(&format!("key = \"{KEY}\"")).unwrap();
+        let sealed = crypto.encrypt(b"a", b"secret").await.unwrap();
+
+        let mut altered = sealed.clone();
+        *altered.last_mut().unwrap() ^= 1;
+        assert!(crypto.decrypt(b"a", &altered).await.is_err());
+        assert!(crypto.decrypt(b"a", &sealed[..NONCE_LEN]).await.is_err());
+        assert!(crypto.decrypt(b"a", b"secret").await.is_err());
+
+        // Sealed for another context, such as another storage key
+        assert!(crypto.decrypt(b"b", &sealed).await.is_err());
+
+        let other = AesGcmCrypto::new(&[7; KEY_LEN]).unwrap();
+        assert!(other.decrypt(b"a", &sealed).await.is_err());
+    }
+
+    #[test]
+    fn test_aes_gcm_key_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let key_path = temp_dir.path().join("storage.key");
+        std::fs::write(&key_path, format!("{}\n", KEY.to_uppercase())).unwrap();
+        assert!(crypto(&format!("key = \"${{FILE:{}}}\"", key_path.display())).is_ok());
+
+        let not_hex = format!("key = \"{}zz\"", &KEY[2..]);
+        for config in [
+            "",
+            "key = 42",
+            "key = \"00ff\"",
+            not_hex.as_str(),
+            "key = \"${ENV:AI_MESSENGER_TEST_AES_GCM_MISSING}\"",
+        ] {
+            assert!(
+                matches!(crypto(config), Err(ServiceError::InvalidConfig(_))),
+                "{}",
+                config
+            );
+        }
+    }
+}
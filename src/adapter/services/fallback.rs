@@ -0,0 +1,331 @@
+use crate::adapter::balancer::EndpointBalancer;
+use crate::adapter::breaker::CircuitBreaker;
+use crate::adapter::limiter::ConcurrencyLimiter;
+use crate::adapter::manifest::AdapterManifest;
+use crate::adapter::runtime::WasmRuntime;
+use crate::adapter::services::AdapterLoadOptions;
+use crate::adapter::services::llm::LlmAdapterWrapper;
+use crate::adapter::services::mock::{MOCK_PROVIDER, MockLlm};
+use crate::adapter::traits::{
+    AdapterService, Capabilities, ChatMessage, Completion, Finish, GenerationOptions, LlmAdapter,
+    ModelInfo, ServiceError,
+};
+use crate::config::schema::{AdapterConfig, ServiceAdapterConfig};
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{RwLock, mpsc};
+
+/// LLM adapters tried in order until one answers
+///
+/// Built from `[adapters.llm]` and its `[[adapters.llm.fallback]]` entries.
+/// The provider, model and version reported are those of the adapter that
+/// served the last request, so responses and the usage log name the
+/// provider that actually answered.
+pub struct FallbackLlm {
+    adapters: Vec<Box<dyn LlmAdapter>>,
+    /// Index of the adapter that served the last request
+    served: usize,
+}
+
+impl FallbackLlm {
+    /// Chain of `adapters`, the first being the primary
+    pub fn new(adapters: Vec<Box<dyn LlmAdapter>>) -> Result<Self, ServiceError> {
+        if adapters.is_empty() {
+            return Err(ServiceError::InvalidConfig(
+                "A fallback chain needs at least one LLM adapter".to_string(),
+            ));
+        }
+
+        Ok(FallbackLlm {
+            adapters,
+            served: 0,
+        })
+    }
+
+    /// Load the adapter configured in `config` and those of its `fallback` entries
+    ///
+    /// All of them but the built-in `mock` are loaded into `runtime`, which
+    /// keeps one adapter per provider, so a provider can only appear once in
+    /// the chain.
+    pub async fn load(
+        runtime: &Arc<RwLock<WasmRuntime>>,
+        http_client: &reqwest::Client,
+        config: &ServiceAdapterConfig,
+        data_dir: &Path,
+        adapters: &AdapterConfig,
+    ) -> Result<Self, ServiceError> {
+        let entries: Vec<_> = std::iter::once(config).chain(&config.fallback).collect();
+        let mut providers = HashSet::new();
+        for entry in &entries {
+            if !providers.insert(entry.provider.as_str()) {
+                return Err(ServiceError::InvalidConfig(format!(
+                    "LLM provider '{}' appears more than once in [adapters.llm] and its fallback",
+                    entry.provider
+                )));
+            }
+        }
+        if let Some(nested) = config
+            .fallback
+            .iter()
+            .find(|entry| !entry.fallback.is_empty())
+        {
+            return Err(ServiceError::InvalidConfig(format!(
+                "Fallback LLM provider '{}' can't have a fallback of its own",
+                nested.provider
+            )));
+        }
+
+        let mut chain: Vec<Box<dyn LlmAdapter>> = Vec::new();
+        for entry in entries {
+            if entry.provider == MOCK_PROVIDER {
+                chain.push(Box::new(MockLlm::from_config(entry)?));
+                continue;
+            }
+            let adapter = LlmAdapterWrapper::new(
+                runtime,
+                http_client,
+                entry,
+                data_dir,
+                "llm",
+                AdapterLoadOptions::from_config(adapters),
+            )
+            .await?;
+            chain.push(Box::new(adapter));
+        }
+
+        FallbackLlm::new(chain)
+    }
+
+    /// Providers of the chain, in the order they're tried
+    #[allow(dead_code)] // Used in tests and by embedders
+    pub fn providers(&self) -> Vec<&str> {
+        self.adapters
+            .iter()
+            .map(|adapter| adapter.provider_name())
+            .collect()
+    }
+
+    fn current(&self) -> &dyn LlmAdapter {
+        &*self.adapters[self.served]
+    }
+
+    /// Adapters able to take `messages`, by index
+    ///
+    /// Images only go to adapters accepting them.
+    fn candidates(&self, messages: &[ChatMessage]) -> Vec<usize> {
+        let has_images = messages
+            .iter()
+            .flat_map(|message| message.parts.iter().flatten())
+            .any(|part| part.is_image());
+
+        (0..self.adapters.len())
+            .filter(|&index| !has_images || self.adapters[index].supports_images())
+            .collect()
+    }
+
+    /// Log a failed attempt and whether the next provider is tried
+    fn failed(&self, index: usize, error: &ServiceError, is_last: bool) {
+        let provider = self.adapters[index].provider_name();
+        if is_last || !fails_over(error) {
+            tracing::debug!("LLM provider '{}' failed: {}", provider, error);
+        } else {
+            tracing::warn!(
+                "LLM provider '{}' failed, trying the next one: {}",
+                provider,
+                error
+            );
+        }
+    }
+}
+
+/// Whether another provider might succeed where one failed with `error`
+///
+/// Requests the provider rejected as malformed or too large would be
+/// rejected the same way by the next one, so they're returned as they are.
+fn fails_over(error: &ServiceError) -> bool {
+    !matches!(
+        error,
+        ServiceError::ProviderError {
+            status: 400 | 413 | 422,
+            ..
+        }
+    )
+}
+
+/// Error for a request every provider in `errors` failed
+fn all_failed(mut errors: Vec<(String, ServiceError)>) -> ServiceError {
+    if errors.len() == 1 {
+        return errors.remove(0).1;
+    }
+
+    let failures = errors
+        .iter()
+        .map(|(provider, error)| format!("{}: {}", provider, error))
+        .collect::<Vec<_>>()
+        .join("; ");
+    ServiceError::ServiceUnavailable(format!("All LLM providers failed ({})", failures))
+}
+
+#[async_trait]
+impl AdapterService for FallbackLlm {
+    fn service_name(&self) -> &'static str {
+        "llm"
+    }
+
+    fn provider_name(&self) -> &str {
+        self.current().provider_name()
+    }
+
+    fn version(&self) -> &str {
+        self.current().version()
+    }
+
+    fn manifest(&self) -> Option<&AdapterManifest> {
+        self.adapters[0].manifest()
+    }
+
+    /// What any adapter of the chain supports, e.g. images if one accepts them
+    fn capabilities(&self) -> Capabilities {
+        self.adapters
+            .iter()
+            .map(|adapter| adapter.capabilities())
+            .fold(Capabilities::default(), Capabilities::union)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.adapters.iter().any(|adapter| adapter.is_ready())
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        for adapter in &mut self.adapters {
+            adapter.shutdown().await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LlmAdapter for FallbackLlm {
+    fn model(&self) -> Option<&str> {
+        self.current().model()
+    }
+
+    /// Moderate with the first adapter of the chain that can
+    async fn moderate(&mut self, text: &str) -> Result<Option<String>, ServiceError> {
+        match self
+            .adapters
+            .iter_mut()
+            .find(|adapter| adapter.supports_moderation())
+        {
+            Some(adapter) => adapter.moderate(text).await,
+            None => self.adapters[0].moderate(text).await,
+        }
+    }
+
+    /// Fuel of the adapter that served the last request
+    fn fuel_consumed(&self) -> Option<u64> {
+        self.current().fuel_consumed()
+    }
+
+    /// Breaker of the primary adapter
+    fn circuit_breaker(&self) -> Option<&Arc<CircuitBreaker>> {
+        self.adapters[0].circuit_breaker()
+    }
+
+    /// Limiter of the primary adapter
+    fn concurrency_limiter(&self) -> Option<&Arc<ConcurrencyLimiter>> {
+        self.adapters[0].concurrency_limiter()
+    }
+
+    /// Endpoints of the primary adapter
+    fn endpoint_balancer(&self) -> Option<&Arc<EndpointBalancer>> {
+        self.adapters[0].endpoint_balancer()
+    }
+
+    async fn send_message(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<String, ServiceError> {
+        self.complete(messages, options)
+            .await
+            .map(|completion| completion.content)
+    }
+
+    async fn complete(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<Completion, ServiceError> {
+        let candidates = self.candidates(messages);
+        let mut errors = Vec::new();
+
+        for (attempt, &index) in candidates.iter().enumerate() {
+            self.served = index;
+            let error = match self.adapters[index].complete(messages, options).await {
+                Ok(completion) => return Ok(completion),
+                Err(error) => error,
+            };
+
+            self.failed(index, &error, attempt + 1 == candidates.len());
+            if !fails_over(&error) {
+                return Err(error);
+            }
+            errors.push((self.adapters[index].provider_name().to_string(), error));
+        }
+
+        Err(all_failed(errors))
+    }
+
+    async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+        self.current().get_model_info().await
+    }
+
+    /// Stream from the first provider that doesn't fail before its first chunk
+    ///
+    /// Once a provider has sent part of the reply, its failure ends the
+    /// stream; starting over with another provider would repeat the reply.
+    async fn stream_message(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+        chunks: mpsc::Sender<String>,
+    ) -> Result<Finish, ServiceError> {
+        let candidates = self.candidates(messages);
+        let mut errors = Vec::new();
+
+        for (attempt, &index) in candidates.iter().enumerate() {
+            self.served = index;
+            let (sender, mut receiver) = mpsc::channel(chunks.max_capacity());
+            let client = &chunks;
+            let forward = async move {
+                let mut forwarded = false;
+                while let Some(chunk) = receiver.recv().await {
+                    forwarded = true;
+                    if client.send(chunk).await.is_err() {
+                        // The client is gone; dropping `receiver` stops the adapter
+                        break;
+                    }
+                }
+                forwarded
+            };
+            let stream = self.adapters[index].stream_message(messages, options, sender);
+            let (result, forwarded) = tokio::join!(stream, forward);
+
+            let error = match result {
+                Ok(finish) => return Ok(finish),
+                Err(error) => error,
+            };
+            let is_last = attempt + 1 == candidates.len() || forwarded || chunks.is_closed();
+            self.failed(index, &error, is_last);
+            if is_last || !fails_over(&error) {
+                return Err(error);
+            }
+            errors.push((self.adapters[index].provider_name().to_string(), error));
+        }
+
+        Err(all_failed(errors))
+    }
+}
@@ -1,14 +1,144 @@
+use crate::adapter::balancer::EndpointBalancer;
+use crate::adapter::breaker::CircuitBreaker;
+use crate::adapter::http;
+use crate::adapter::limiter::ConcurrencyLimiter;
+use crate::adapter::manifest::{AdapterManifest, CAPABILITY_MODERATION};
 use crate::adapter::runtime::WasmRuntime;
-use crate::adapter::traits::{AdapterService, LlmAdapter, ModelInfo, ServiceError};
+use crate::adapter::services::{AdapterLoadOptions, load_wasm_adapter};
+use crate::adapter::traits::{
+    AdapterService, ChatMessage, Completion, GenerationOptions, LlmAdapter, ModelInfo, ServiceError,
+};
+use crate::config::defaults::DEFAULT_ADAPTER_MAX_QUEUED;
 use crate::config::schema::ServiceAdapterConfig;
 use async_trait::async_trait;
+use serde::Deserialize;
 use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Model metadata declared under `[adapters.llm.config.model_info]`
+///
+/// Only used for values the provider can't report itself.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclaredModelInfo {
+    pub context_length: Option<u32>,
+    pub name: Option<String>,
+    pub parameters: Option<String>,
+}
+
+impl DeclaredModelInfo {
+    /// Read the declared values from an adapter's provider config
+    pub fn from_config(config: &toml::Value) -> Result<Self, ServiceError> {
+        match config.get("model_info") {
+            Some(model_info) => model_info.clone().try_into().map_err(|e| {
+                ServiceError::InvalidConfig(format!("Invalid [model_info] table: {e}"))
+            }),
+            None => Ok(DeclaredModelInfo::default()),
+        }
+    }
+
+    /// Fill in what the provider didn't report with declared values
+    ///
+    /// Without a provider report, the declared values are all there is;
+    /// unknown fields stay `None` rather than being guessed.
+    pub fn complete(&self, reported: Option<ModelInfo>, model: &str, version: &str) -> ModelInfo {
+        match reported {
+            Some(info) => ModelInfo {
+                context_length: info.context_length.or(self.context_length),
+                parameters: info.parameters.or_else(|| self.parameters.clone()),
+                ..info
+            },
+            None => ModelInfo {
+                name: self.name.clone().unwrap_or_else(|| model.to_string()),
+                version: version.to_string(),
+                context_length: self.context_length,
+                parameters: self.parameters.clone(),
+            },
+        }
+    }
+}
+
+/// Chat request handed to the adapter's `prepare-request` export
+/// (mirrors the WIT `chat-request`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatRequest {
+    pub enable_streaming: Option<bool>,
+    pub max_completion_tokens: Option<u32>,
+    pub messages: Vec<ChatMessage>,
+    pub model: String,
+    pub provider_params: Option<String>,
+    pub seed: Option<u32>,
+    pub stop: Option<Vec<String>>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub user: Option<String>,
+}
+
+impl ChatRequest {
+    /// Request for `messages` with the sampling fields taken from `options`
+    ///
+    /// The WIT `seed` is unsigned; a negative seed asks for a random one,
+    /// which is what leaving it unset does too. Larger seeds are rejected
+    /// by `GenerationOptions::validate`.
+    pub fn new(model: &str, messages: &[ChatMessage], options: &GenerationOptions) -> Self {
+        ChatRequest {
+            enable_streaming: None,
+            max_completion_tokens: options.max_tokens,
+            messages: messages.to_vec(),
+            model: model.to_string(),
+            provider_params: content_parts_params(messages),
+            seed: options.seed.and_then(|seed| u32::try_from(seed).ok()),
+            stop: options.stop.clone(),
+            temperature: options.temperature,
+            top_p: options.top_p,
+            user: None,
+        }
+    }
+
+    /// Send the request to `base_url` instead of the adapter's configured one
+    ///
+    /// Adapters read it as `base_url` in `provider_params`, next to any
+    /// other parameters.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        let mut params: serde_json::Map<_, _> = self
+            .provider_params
+            .as_deref()
+            .and_then(|params| serde_json::from_str(params).ok())
+            .unwrap_or_default();
+        params.insert("base_url".to_string(), base_url.into());
+        self.provider_params = Some(serde_json::Value::Object(params).to_string());
+        self
+    }
+}
+
+/// `provider_params` carrying the parts of multi-part messages
+///
+/// The WIT `chat-message` only has text content, so adapters supporting
+/// images read them from `content_parts`: one entry per message, `null`
+/// for plain-text ones.
+fn content_parts_params(messages: &[ChatMessage]) -> Option<String> {
+    if messages.iter().all(|message| message.parts.is_none()) {
+        return None;
+    }
+    let parts: Vec<_> = messages.iter().map(|message| &message.parts).collect();
+    Some(serde_json::json!({ "content_parts": parts }).to_string())
+}
+
 /// LLM adapter wrapper providing typed interface to WASM instances
 pub struct LlmAdapterWrapper {
     runtime: Arc<RwLock<WasmRuntime>>,
+    /// Picks one of `[[adapters.llm.endpoints]]` per request, if configured
+    balancer: Option<Arc<EndpointBalancer>>,
+    breaker: Option<Arc<CircuitBreaker>>,
+    declared_model_info: DeclaredModelInfo,
+    /// Fuel the adapter used for the last reply, if it reported any
+    fuel_consumed: Option<u64>,
+    http_client: reqwest::Client,
+    limiter: Option<Arc<ConcurrencyLimiter>>,
+    manifest: AdapterManifest,
+    max_response_bytes: usize,
+    model: Option<String>,
     provider: String,
     version: String,
     service_name: String,
@@ -16,32 +146,148 @@ pub struct LlmAdapterWrapper {
 
 impl LlmAdapterWrapper {
     /// Create new LLM adapter wrapper
+    ///
+    /// `http_client` is cloned, not rebuilt, so all requests to the provider
+    /// reuse its connection pool.
     pub async fn new(
         runtime: &Arc<RwLock<WasmRuntime>>,
+        http_client: &reqwest::Client,
         config: &ServiceAdapterConfig,
         data_dir: &Path,
         service_name: &str,
+        options: AdapterLoadOptions,
     ) -> Result<Self, ServiceError> {
-        let module_path = config.module_path(data_dir, service_name);
-        let config_json = config
-            .config_as_json()
-            .map_err(|e| ServiceError::InvalidConfig(e.to_string()))?;
-
-        // Load the WASM module
-        {
-            let mut runtime_guard = runtime.write().await;
-            runtime_guard
-                .load_adapter(service_name, &module_path, &config_json)
-                .await?;
-        }
+        let declared_model_info = DeclaredModelInfo::from_config(&config.config)?;
+        let balancer = EndpointBalancer::new(
+            &config.provider,
+            &config.endpoints,
+            config.balance,
+            &config.circuit_breaker,
+        )?
+        .map(Arc::new);
+
+        let manifest = load_wasm_adapter(runtime, config, data_dir, service_name, options).await?;
+
+        let limiter = config.max_concurrent.map(|max_concurrent| {
+            Arc::new(ConcurrencyLimiter::new(
+                &config.provider,
+                max_concurrent,
+                config.max_queued.unwrap_or(DEFAULT_ADAPTER_MAX_QUEUED),
+            ))
+        });
 
         Ok(LlmAdapterWrapper {
             runtime: runtime.clone(),
+            balancer,
+            breaker: CircuitBreaker::from_config(&config.provider, &config.circuit_breaker)
+                .map(Arc::new),
+            declared_model_info,
+            fuel_consumed: None,
+            http_client: http_client.clone(),
+            limiter,
+            manifest,
+            max_response_bytes: config.http.max_response_bytes,
+            model: config
+                .config
+                .get("default_model")
+                .and_then(toml::Value::as_str)
+                .map(str::to_string),
             provider: config.provider.clone(),
             version: config.version.clone(),
             service_name: service_name.to_string(),
         })
     }
+
+    /// Ask the provider for model metadata through the adapter
+    ///
+    /// The adapter prepares the request and parses the response; the host
+    /// only performs the HTTP round trip.
+    async fn query_model_info(&self) -> Result<ModelInfo, ServiceError> {
+        let model = self.model.as_deref().ok_or_else(|| {
+            ServiceError::InvalidConfig("No model configured to query".to_string())
+        })?;
+
+        let pool = self
+            .runtime
+            .read()
+            .await
+            .get_pool(&self.service_name, &self.provider)
+            .ok_or_else(|| {
+                ServiceError::ServiceUnavailable("LLM adapter instance not found".to_string())
+            })?;
+
+        let mut instance = pool.checkout().await?;
+        if !instance.is_ready() {
+            return Err(ServiceError::ServiceUnavailable(
+                "LLM adapter not ready".to_string(),
+            ));
+        }
+
+        let request = instance.prepare_model_info_request(model).await?;
+        let response = http::send(&self.http_client, &request, self.max_response_bytes).await?;
+        instance.parse_model_info_response(response).await
+    }
+
+    /// Generate a reply through the adapter, with the fuel it used
+    ///
+    /// The finish reason and usage are those the adapter's `parse-response`
+    /// reports, not assumed. Answers `NotImplemented` until the host can
+    /// call the adapter.
+    async fn generate(
+        &self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<(Completion, Option<u64>), ServiceError> {
+        // Queue behind other in-flight calls; the permit is held until we return
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire().await?),
+            None => None,
+        };
+
+        let pool = self
+            .runtime
+            .read()
+            .await
+            .get_pool(&self.service_name, &self.provider);
+
+        if let Some(pool) = pool {
+            let mut instance = pool.checkout().await?;
+            if !instance.is_ready() {
+                return Err(ServiceError::ServiceUnavailable(
+                    "LLM adapter not ready".to_string(),
+                ));
+            }
+            // Pooled instances may still hold the fuel of an earlier call
+            instance.take_fuel_consumed();
+
+            let model = self.model.as_deref().unwrap_or(&self.provider);
+            let mut request = ChatRequest::new(model, messages, options);
+            // Held until the reply is in, so least-in-flight sees this request
+            let endpoint = self.balancer.as_ref().map(|balancer| balancer.pick());
+            if let Some(endpoint) = &endpoint {
+                request = request.with_base_url(endpoint.base_url());
+            }
+
+            let call = async {
+                // TODO: Pass `request` to `prepare-request` via WIT bindings,
+                // and take the finish reason and usage from `parse-response`.
+                // Until then, fail rather than answer without the request's
+                // sampling options, stop sequences and images
+                tracing::debug!("Can't generate with request {:?}", request);
+                Err(instance.unbound_export("prepare-request"))
+            };
+            let completion: Completion = match &endpoint {
+                Some(endpoint) => endpoint.call(call).await,
+                None => call.await,
+            }?;
+
+            Ok((completion, instance.take_fuel_consumed()))
+        } else {
+            Err(ServiceError::ServiceUnavailable(
+                "LLM adapter instance not found".to_string(),
+            ))
+        }
+    }
 }
 
 #[async_trait]
@@ -58,6 +304,10 @@ impl AdapterService for LlmAdapterWrapper {
         &self.version
     }
 
+    fn manifest(&self) -> Option<&AdapterManifest> {
+        Some(&self.manifest)
+    }
+
     fn is_ready(&self) -> bool {
         // TODO: Check actual WASM instance readiness
         true
@@ -69,51 +319,93 @@ impl AdapterService for LlmAdapterWrapper {
     }
 }
 
-#[async_trait]
 #[async_trait]
 impl LlmAdapter for LlmAdapterWrapper {
-    async fn send_message(&mut self, message: &str) -> Result<String, ServiceError> {
-        let runtime = self.runtime.read().await;
-
-        if let Some(instance) = runtime.get_instance(&self.service_name, &self.provider) {
-            if !instance.is_ready() {
-                return Err(ServiceError::ServiceUnavailable(
-                    "LLM adapter not ready".to_string(),
-                ));
-            }
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
 
-            // TODO: Call actual WASM function via WIT bindings
-            // For now, return placeholder response
-            Ok(format!("LLM response to: {}", message))
-        } else {
-            Err(ServiceError::ServiceUnavailable(
-                "LLM adapter instance not found".to_string(),
-            ))
+    async fn moderate(&mut self, text: &str) -> Result<Option<String>, ServiceError> {
+        if !self.supports_moderation() {
+            return Err(ServiceError::ServiceUnavailable(format!(
+                "The {} adapter doesn't support moderation; its manifest must declare \"{}\"",
+                self.provider, CAPABILITY_MODERATION
+            )));
         }
+
+        // TODO: Add moderation requests to the WIT interface. Until then,
+        // checks fail rather than let everything through unchecked
+        tracing::debug!("Can't moderate {} bytes", text.len());
+        Err(ServiceError::NotImplemented(
+            "`moderate` can't be called: the WIT interface has no moderation request yet"
+                .to_string(),
+        ))
     }
 
-    async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
-        let runtime = self.runtime.read().await;
+    fn circuit_breaker(&self) -> Option<&Arc<CircuitBreaker>> {
+        self.breaker.as_ref()
+    }
 
-        if let Some(instance) = runtime.get_instance(&self.service_name, &self.provider) {
-            if !instance.is_ready() {
-                return Err(ServiceError::ServiceUnavailable(
-                    "LLM adapter not ready".to_string(),
-                ));
+    fn concurrency_limiter(&self) -> Option<&Arc<ConcurrencyLimiter>> {
+        self.limiter.as_ref()
+    }
+
+    fn endpoint_balancer(&self) -> Option<&Arc<EndpointBalancer>> {
+        self.balancer.as_ref()
+    }
+
+    async fn send_message(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<String, ServiceError> {
+        let completion = self.complete(messages, options).await?;
+        Ok(completion.content)
+    }
+
+    /// Generate a reply, failing fast while the provider's circuit is open
+    ///
+    /// Also used by the default `stream_message`, so streams end with the
+    /// finish reason the adapter reported.
+    async fn complete(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<Completion, ServiceError> {
+        self.fuel_consumed = None;
+        let (completion, fuel_consumed) = match self.breaker.clone() {
+            Some(breaker) => breaker.call(self.generate(messages, options)).await,
+            None => self.generate(messages, options).await,
+        }?;
+        self.fuel_consumed = fuel_consumed;
+
+        Ok(completion)
+    }
+
+    fn fuel_consumed(&self) -> Option<u64> {
+        self.fuel_consumed
+    }
+
+    /// Model metadata from the provider, completed with declared values
+    ///
+    /// Falls back to `[adapters.llm.config.model_info]` (and the configured
+    /// model name) when the provider can't be asked.
+    async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+        let reported = match self.query_model_info().await {
+            Ok(info) => Some(info),
+            Err(e) => {
+                tracing::debug!(
+                    "{} can't report model info, using config values: {}",
+                    self.provider,
+                    e
+                );
+                None
             }
+        };
 
-            // TODO: Call actual WASM function to get model info
-            // For now, return placeholder info
-            Ok(ModelInfo {
-                name: format!("{}_model", self.provider),
-                version: self.version.clone(),
-                context_length: Some(4096),
-                parameters: Some("7B".to_string()),
-            })
-        } else {
-            Err(ServiceError::ServiceUnavailable(
-                "LLM adapter instance not found".to_string(),
-            ))
-        }
+        let model = self.model.as_deref().unwrap_or(&self.provider);
+        Ok(self
+            .declared_model_info
+            .complete(reported, model, &self.version))
     }
 }
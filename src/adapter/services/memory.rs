@@ -0,0 +1,77 @@
+use crate::adapter::traits::{AdapterService, KeyPage, ServiceError, StorageAdapter};
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// Provider name selecting storage that only lives in memory
+pub const MEMORY_PROVIDER: &str = "memory";
+
+/// Storage adapter keeping entries in memory until the process exits
+///
+/// Nothing is written to disk, which makes it the storage of ephemeral
+/// mode (and of tests).
+#[derive(Default)]
+pub struct MemoryStorage {
+    pub entries: HashMap<String, Vec<u8>>,
+}
+
+#[async_trait]
+impl AdapterService for MemoryStorage {
+    fn service_name(&self) -> &'static str {
+        "storage"
+    }
+
+    fn provider_name(&self) -> &str {
+        MEMORY_PROVIDER
+    }
+
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageAdapter for MemoryStorage {
+    async fn store(&mut self, key: &str, data: &[u8]) -> Result<(), ServiceError> {
+        self.entries.insert(key.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    async fn retrieve(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        self.entries
+            .get(key)
+            .cloned()
+            .ok_or_else(|| ServiceError::ExecutionError(format!("Missing key: {key}")))
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), ServiceError> {
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, ServiceError> {
+        Ok(self.entries.contains_key(key))
+    }
+
+    async fn list_keys_paginated(
+        &self,
+        prefix: Option<&str>,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<KeyPage, ServiceError> {
+        let keys = self
+            .entries
+            .keys()
+            .filter(|key| prefix.is_none_or(|p| key.starts_with(p)))
+            .cloned();
+
+        KeyPage::from_keys(keys, cursor, limit)
+    }
+}
@@ -0,0 +1,499 @@
+use crate::adapter::services::llm::DeclaredModelInfo;
+use crate::adapter::traits::{
+    AdapterService, Capabilities, ChatMessage, Completion, Finish, GenerationOptions, LlmAdapter,
+    ModelInfo, ServiceError, Usage, until_closed,
+};
+use crate::config::schema::ServiceAdapterConfig;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Provider name selecting the built-in scripted LLM
+pub const MOCK_PROVIDER: &str = "mock";
+
+/// Characters counted as one token in the reported usage
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Settings of the mock under `[adapters.llm.config]`
+///
+/// Other keys of the table, such as `defaults` and `model_info`, are left
+/// to the host.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct MockConfig {
+    /// Wait between streamed chunks, in milliseconds
+    #[serde(default)]
+    pub chunk_latency_ms: u64,
+    /// Wait before each reply starts, in milliseconds
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Model reported with replies (default: "mock")
+    pub model: Option<String>,
+    /// Start over with the first response once all were given, instead of echoing
+    #[serde(default)]
+    pub repeat: bool,
+    /// Replies given in order, one per request
+    #[serde(default)]
+    pub responses: Vec<MockResponse>,
+}
+
+/// Entry of `responses`: the text of a reply, or a table scripting it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum MockResponse {
+    Text(String),
+    Step(MockStep),
+}
+
+/// Scripted reply with its chunks, latency or failure
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockStep {
+    /// Chunks the reply is streamed in, as they are
+    #[serde(default)]
+    pub chunks: Vec<String>,
+    /// Fail with this provider message, after sending any `chunks`
+    pub error: Option<String>,
+    /// Wait before this reply starts instead of `latency_ms`
+    pub latency_ms: Option<u64>,
+    /// Text of the reply, streamed word by word
+    pub reply: Option<String>,
+    /// `Retry-After` of the failure, in seconds
+    pub retry_after_secs: Option<u64>,
+    /// HTTP status of the failure (default: 500)
+    pub status: Option<u16>,
+}
+
+/// Reply resolved from a `responses` entry
+#[derive(Debug, Clone, PartialEq)]
+struct Scripted {
+    /// None echoes the last user message
+    chunks: Option<Vec<String>>,
+    /// Status, message and `Retry-After` of the failure ending the reply
+    error: Option<(u16, String, Option<u64>)>,
+    latency: Duration,
+}
+
+impl Scripted {
+    fn from_response(response: &MockResponse, latency: Duration) -> Result<Self, ServiceError> {
+        let step = match response {
+            MockResponse::Text(text) => {
+                return Ok(Scripted {
+                    chunks: Some(words(text)),
+                    error: None,
+                    latency,
+                });
+            }
+            MockResponse::Step(step) => step,
+        };
+
+        let chunks = match (&step.reply, step.chunks.is_empty()) {
+            (Some(_), false) => {
+                return Err(ServiceError::InvalidConfig(
+                    "A mock response can't have both `reply` and `chunks`".to_string(),
+                ));
+            }
+            (Some(reply), true) => Some(words(reply)),
+            (None, false) => Some(step.chunks.clone()),
+            // A failure without chunks sends nothing before it
+            (None, true) if step.error.is_some() => Some(Vec::new()),
+            (None, true) => None,
+        };
+        let error = match &step.error {
+            Some(message) => {
+                let status = step.status.unwrap_or(500);
+                if !(400..=599).contains(&status) {
+                    return Err(ServiceError::InvalidConfig(format!(
+                        "Mock response status {} isn't an HTTP error status",
+                        status
+                    )));
+                }
+                if step.reply.is_some() {
+                    return Err(ServiceError::InvalidConfig(
+                        "A failing mock response can't have a `reply`; \
+                         use `chunks` for text sent before the failure"
+                            .to_string(),
+                    ));
+                }
+                Some((status, message.clone(), step.retry_after_secs))
+            }
+            None if step.status.is_some() || step.retry_after_secs.is_some() => {
+                return Err(ServiceError::InvalidConfig(
+                    "`status` and `retry_after_secs` of a mock response need an `error`"
+                        .to_string(),
+                ));
+            }
+            None => None,
+        };
+
+        Ok(Scripted {
+            chunks,
+            error,
+            latency: step.latency_ms.map_or(latency, Duration::from_millis),
+        })
+    }
+
+    fn error(&self) -> Option<ServiceError> {
+        self.error.as_ref().map(
+            |(status, message, retry_after_secs)| ServiceError::ProviderError {
+                status: *status,
+                message: message.clone(),
+                retry_after_secs: *retry_after_secs,
+            },
+        )
+    }
+}
+
+/// LLM adapter answering from a script, without a provider
+///
+/// Replies come from `responses` in order, each after `latency_ms`; once
+/// they're used up (or without any), the last user message is echoed.
+/// Streamed replies arrive in chunks `chunk_latency_ms` apart. That makes
+/// streaming, failures and timeouts reproducible in tests and lets the
+/// server run locally without a model.
+#[derive(Debug)]
+pub struct MockLlm {
+    chunk_latency: Duration,
+    declared: DeclaredModelInfo,
+    latency: Duration,
+    model: String,
+    next: usize,
+    repeat: bool,
+    responses: Vec<Scripted>,
+}
+
+impl MockLlm {
+    /// Build the mock from `[adapters.llm.config]`
+    pub fn from_config(config: &ServiceAdapterConfig) -> Result<Self, ServiceError> {
+        let mock: MockConfig =
+            config.config.clone().try_into().map_err(|e| {
+                ServiceError::InvalidConfig(format!("Invalid mock LLM config: {e}"))
+            })?;
+        let declared = DeclaredModelInfo::from_config(&config.config)?;
+
+        Self::new(mock, declared)
+    }
+
+    /// Build the mock from its settings
+    pub fn new(config: MockConfig, declared: DeclaredModelInfo) -> Result<Self, ServiceError> {
+        let latency = Duration::from_millis(config.latency_ms);
+        let responses = config
+            .responses
+            .iter()
+            .map(|response| Scripted::from_response(response, latency))
+            .collect::<Result<Vec<_>, ServiceError>>()?;
+
+        Ok(MockLlm {
+            chunk_latency: Duration::from_millis(config.chunk_latency_ms),
+            declared,
+            latency,
+            model: config.model.unwrap_or_else(|| MOCK_PROVIDER.to_string()),
+            next: 0,
+            repeat: config.repeat,
+            responses,
+        })
+    }
+
+    /// The next reply of the script, with its chunks filled in
+    fn next_reply(&mut self, messages: &[ChatMessage]) -> (Vec<String>, Scripted) {
+        let scripted = match self.responses.len() {
+            0 => None,
+            len if self.repeat => Some(self.responses[self.next % len].clone()),
+            _ => self.responses.get(self.next).cloned(),
+        };
+        self.next += 1;
+
+        let scripted = scripted.unwrap_or(Scripted {
+            chunks: None,
+            error: None,
+            latency: self.latency,
+        });
+        let chunks = scripted.chunks.clone().unwrap_or_else(|| {
+            let last_user = messages.iter().rev().find(|message| message.role == "user");
+            words(last_user.map_or("", |message| message.content.as_str()))
+        });
+        (chunks, scripted)
+    }
+}
+
+#[async_trait]
+impl AdapterService for MockLlm {
+    fn service_name(&self) -> &'static str {
+        "llm"
+    }
+
+    fn provider_name(&self) -> &str {
+        MOCK_PROVIDER
+    }
+
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            streaming: true,
+            ..Capabilities::default()
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LlmAdapter for MockLlm {
+    async fn send_message(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<String, ServiceError> {
+        Ok(self.complete(messages, options).await?.content)
+    }
+
+    async fn complete(
+        &mut self,
+        messages: &[ChatMessage],
+        _options: &GenerationOptions,
+    ) -> Result<Completion, ServiceError> {
+        let (chunks, scripted) = self.next_reply(messages);
+        tokio::time::sleep(scripted.latency).await;
+        if let Some(error) = scripted.error() {
+            return Err(error);
+        }
+
+        let content = chunks.concat();
+        let prompt_tokens: u32 = messages
+            .iter()
+            .map(|message| estimate_tokens(&message.content))
+            .sum();
+        let completion_tokens = estimate_tokens(&content);
+        Ok(Completion {
+            content,
+            finish: Finish::stop(),
+            usage: Some(Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens.saturating_add(completion_tokens),
+            }),
+        })
+    }
+
+    async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+        Ok(self.declared.complete(None, &self.model, self.version()))
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    async fn stream_message(
+        &mut self,
+        messages: &[ChatMessage],
+        _options: &GenerationOptions,
+        chunks: mpsc::Sender<String>,
+    ) -> Result<Finish, ServiceError> {
+        let chunk_latency = self.chunk_latency;
+        let (reply, scripted) = self.next_reply(messages);
+
+        if until_closed(&chunks, tokio::time::sleep(scripted.latency))
+            .await
+            .is_none()
+        {
+            return Ok(Finish::default());
+        }
+        for (index, chunk) in reply.into_iter().enumerate() {
+            if index > 0
+                && until_closed(&chunks, tokio::time::sleep(chunk_latency))
+                    .await
+                    .is_none()
+            {
+                return Ok(Finish::default());
+            }
+            if chunks.send(chunk).await.is_err() {
+                return Ok(Finish::default());
+            }
+        }
+
+        match scripted.error() {
+            Some(error) => Err(error),
+            None => Ok(Finish::stop()),
+        }
+    }
+}
+
+/// `text` split after each space, so the chunks add up to it
+fn words(text: &str) -> Vec<String> {
+    text.split_inclusive(' ').map(str::to_string).collect()
+}
+
+/// Rough token count of `text`, for the usage the mock reports
+fn estimate_tokens(text: &str) -> u32 {
+    u32::try_from(text.chars().count().div_ceil(CHARS_PER_TOKEN)).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn mock(config: &str) -> MockLlm {
+        let config: ServiceAdapterConfig =
+            toml::from_str(&format!("provider = \"mock\"\n[config]\n{}", config)).unwrap();
+        MockLlm::from_config(&config).unwrap()
+    }
+
+    fn user(content: &str) -> Vec<ChatMessage> {
+        vec![ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+            parts: None,
+        }]
+    }
+
+    async fn reply(llm: &mut MockLlm, content: &str) -> Result<String, ServiceError> {
+        llm.send_message(&user(content), &GenerationOptions::default())
+            .await
+    }
+
+    /// Stream a reply, collecting its chunks and how it ended
+    async fn stream(
+        llm: &mut MockLlm,
+        content: &str,
+    ) -> (Vec<String>, Result<Finish, ServiceError>) {
+        let (sender, mut receiver) = mpsc::channel(16);
+        let finish = llm
+            .stream_message(&user(content), &GenerationOptions::default(), sender)
+            .await;
+        let mut chunks = Vec::new();
+        while let Some(chunk) = receiver.recv().await {
+            chunks.push(chunk);
+        }
+        (chunks, finish)
+    }
+
+    #[tokio::test]
+    async fn test_mock_follows_script_then_echoes() {
+        let mut llm = mock(
+            r#"responses = ["First reply", { chunks = ["Sec", "ond"] }, { error = "overloaded", status = 503, retry_after_secs = 2 }]"#,
+        );
+
+        assert_eq!(reply(&mut llm, "Hi").await.unwrap(), "First reply");
+        assert_eq!(reply(&mut llm, "Hi").await.unwrap(), "Second");
+        match reply(&mut llm, "Hi").await {
+            Err(ServiceError::ProviderError {
+                status,
+                message,
+                retry_after_secs,
+            }) => {
+                assert_eq!(status, 503);
+                assert_eq!(message, "overloaded");
+                assert_eq!(retry_after_secs, Some(2));
+            }
+            other => panic!("expected a provider error, got {:?}", other),
+        }
+        assert_eq!(reply(&mut llm, "Echo me").await.unwrap(), "Echo me");
+
+        let mut repeating = mock("repeat = true\nresponses = [\"one\", \"two\"]");
+        for expected in ["one", "two", "one"] {
+            assert_eq!(reply(&mut repeating, "Hi").await.unwrap(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_reports_usage_and_model() {
+        let mut llm = mock("model = \"test-model\"\n[config.model_info]\ncontext_length = 4096");
+
+        let completion = llm
+            .complete(&user("Hello there"), &GenerationOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(completion.content, "Hello there");
+        assert_eq!(completion.finish, Finish::stop());
+        assert_eq!(
+            completion.usage,
+            Some(Usage {
+                prompt_tokens: 3,
+                completion_tokens: 3,
+                total_tokens: 6,
+            })
+        );
+
+        assert_eq!(llm.model(), Some("test-model"));
+        let info = llm.get_model_info().await.unwrap();
+        assert_eq!(info.name, "test-model");
+        assert_eq!(info.context_length, Some(4096));
+        assert!(llm.capabilities().streaming);
+    }
+
+    #[tokio::test]
+    async fn test_mock_streams_with_latency() {
+        let mut llm = mock(concat!(
+            "latency_ms = 100\nchunk_latency_ms = 10\n",
+            r#"responses = ["one two three", { chunks = ["partial"], error = "stream broke", status = 502 }]"#,
+        ));
+
+        let start = Instant::now();
+        let (chunks, finish) = stream(&mut llm, "Hi").await;
+        assert_eq!(chunks, ["one ", "two ", "three"]);
+        assert_eq!(finish.unwrap(), Finish::stop());
+        assert!(start.elapsed() >= Duration::from_millis(120));
+
+        let (chunks, finish) = stream(&mut llm, "Hi").await;
+        assert_eq!(chunks, ["partial"]);
+        assert!(matches!(
+            finish,
+            Err(ServiceError::ProviderError { status: 502, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mock_stream_stops_when_receiver_is_dropped() {
+        let mut llm = mock("chunk_latency_ms = 1000");
+        let (sender, mut receiver) = mpsc::channel(16);
+        let messages = user("one two three");
+        let options = GenerationOptions::default();
+
+        let streaming = llm.stream_message(&messages, &options, sender);
+        let reader = async move {
+            let first = receiver.recv().await;
+            drop(receiver);
+            first
+        };
+        let start = Instant::now();
+        let (finish, first) = tokio::join!(streaming, reader);
+
+        assert_eq!(first.as_deref(), Some("one "));
+        assert_eq!(finish.unwrap(), Finish::default());
+        assert!(start.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_mock_rejects_invalid_scripts() {
+        for script in [
+            r#"responses = [{ reply = "a", chunks = ["a"] }]"#,
+            r#"responses = [{ reply = "a", error = "failed" }]"#,
+            r#"responses = [{ error = "failed", status = 200 }]"#,
+            r#"responses = [{ reply = "a", status = 503 }]"#,
+            r#"responses = [{ reply = "a", delay = 5 }]"#,
+            r#"responses = [42]"#,
+            r#"latency_ms = "slow""#,
+        ] {
+            let config: ServiceAdapterConfig =
+                toml::from_str(&format!("provider = \"mock\"\n[config]\n{}", script)).unwrap();
+            assert!(
+                matches!(
+                    MockLlm::from_config(&config),
+                    Err(ServiceError::InvalidConfig(_))
+                ),
+                "{}",
+                script
+            );
+        }
+    }
+}
@@ -1,36 +1,183 @@
 // Service-specific adapter implementations
 
+pub mod aes;
+pub mod fallback;
+pub mod image;
 pub mod llm;
+pub mod memory;
+pub mod mock;
+mod providers;
+pub mod silence;
+pub mod sqlite;
 pub mod storage;
-// Future services:
-// pub mod stt;
-// pub mod tts;
+pub mod stt;
+pub mod tts;
 
+use crate::adapter::encryption::{EncryptedValues, storage_crypto};
+use crate::adapter::http;
+use crate::adapter::keys::EncodedKeys;
+use crate::adapter::manifest::AdapterManifest;
 use crate::adapter::runtime::WasmRuntime;
-use crate::adapter::services::{llm::LlmAdapterWrapper, storage::StorageAdapterWrapper};
-use crate::adapter::traits::{AdapterService, ServiceError};
-use crate::config::schema::Config;
-use std::collections::HashMap;
-use std::path::Path;
+use crate::adapter::services::aes::{AES_GCM_PROVIDER, AesGcmCrypto, check_crypto_provider};
+use crate::adapter::services::memory::{MEMORY_PROVIDER, MemoryStorage};
+use crate::adapter::services::mock::{MOCK_PROVIDER, MockLlm};
+use crate::adapter::services::providers::Providers;
+use crate::adapter::services::silence::{SILENCE_PROVIDER, SilenceTts};
+use crate::adapter::services::sqlite::{SQLITE_PROVIDER, SqliteStorage};
+use crate::adapter::traits::{
+    AdapterService, CryptoAdapter, ImageAdapter, LlmAdapter, ServiceError, StorageAdapter,
+    SttAdapter, TtsAdapter,
+};
+use crate::config::defaults::DEFAULT_ADAPTER_POOL_SIZE;
+use crate::config::schema::{AdapterConfig, Config, ServiceAdapterConfig, StrictConfig};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Crypto adapter shared between its users (encrypted storage)
+pub type SharedCrypto = Arc<RwLock<dyn CryptoAdapter>>;
+
+/// Image adapter shared between its users
+pub type SharedImage = Arc<RwLock<dyn ImageAdapter>>;
+
+/// LLM adapter shared between its users (request handlers, `Messenger`)
+pub type SharedLlm = Arc<RwLock<dyn LlmAdapter>>;
+
+/// Storage adapter shared between its users
+pub type SharedStorage = Arc<RwLock<dyn StorageAdapter>>;
+
+/// STT adapter shared between its users
+pub type SharedStt = Arc<RwLock<dyn SttAdapter>>;
+
+/// TTS adapter shared between its users
+pub type SharedTts = Arc<RwLock<dyn TtsAdapter>>;
+
+/// Why a configured adapter isn't loaded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AdapterLoadFailure {
+    pub at: DateTime<Utc>,
+    /// WASM module it was loaded from (None for built-in providers)
+    pub attempted_path: Option<PathBuf>,
+    pub error: String,
+    pub provider: String,
+    pub version: String,
+}
+
+impl AdapterLoadFailure {
+    /// Failure of loading `config` as the adapter for `service`
+    pub fn new(
+        service: &str,
+        config: &ServiceAdapterConfig,
+        data_dir: &Path,
+        error: &ServiceError,
+    ) -> Self {
+        AdapterLoadFailure {
+            at: Utc::now(),
+            attempted_path: (!is_built_in(service, &config.provider))
+                .then(|| config.module_path(data_dir, service)),
+            error: error.to_string(),
+            provider: config.provider.clone(),
+            version: config.version.clone(),
+        }
+    }
+}
+
+/// How strictly a WASM adapter is checked against its manifest when loading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdapterLoadOptions {
+    /// What to do when the adapter's config doesn't match the manifest's schema
+    pub strict_config: StrictConfig,
+    /// Fail when the manifest is invalid, instead of ignoring it
+    pub strict_manifest: bool,
+}
+
+impl AdapterLoadOptions {
+    /// Options set under `[adapters]`
+    pub fn from_config(adapters: &AdapterConfig) -> Self {
+        AdapterLoadOptions {
+            strict_config: adapters.strict_config,
+            strict_manifest: adapters.strict_manifest,
+        }
+    }
+}
+
+/// Load the WASM module configured for `service_name` into `runtime`
+///
+/// The module's manifest is checked against `config` first and returned.
+pub(crate) async fn load_wasm_adapter(
+    runtime: &Arc<RwLock<WasmRuntime>>,
+    config: &ServiceAdapterConfig,
+    data_dir: &Path,
+    service_name: &str,
+    options: AdapterLoadOptions,
+) -> Result<AdapterManifest, ServiceError> {
+    let module_path = config.module_path(data_dir, service_name);
+    let manifest = AdapterManifest::load_for_module(&module_path, options.strict_manifest)?;
+    manifest.check_config(service_name, config)?;
+    manifest.check_config_schema(service_name, config, options.strict_config)?;
+    // Log the raw config so secret placeholders, not secrets, end up in logs
+    if let Ok(raw_json) = config.config_as_json() {
+        tracing::debug!("Loading {} adapter with config {}", service_name, raw_json);
+    }
+    let config_json = config
+        .resolved_config_json(service_name)
+        .map_err(|e| ServiceError::InvalidConfig(e.to_string()))?;
+
+    runtime
+        .write()
+        .await
+        .load_adapter(
+            service_name,
+            &module_path,
+            &config_json,
+            config.pool_size.unwrap_or(DEFAULT_ADAPTER_POOL_SIZE),
+        )
+        .await?;
+
+    Ok(manifest)
+}
+
 /// Central registry managing all service adapters
+///
+/// Adapters are either loaded from the config (WASM modules, the built-in
+/// `sqlite` storage, `aes-gcm` encryption and the `mock` LLM and `silence`
+/// TTS stubs) or registered directly as native Rust implementations; both
+/// are used the same way.
 pub struct AdapterRegistry {
     runtime: Arc<RwLock<WasmRuntime>>,
-    llm_adapters: HashMap<String, LlmAdapterWrapper>,
-    storage_adapters: HashMap<String, StorageAdapterWrapper>,
+    crypto_adapters: Providers<SharedCrypto>,
+    /// Configured adapters that failed to load, by service
+    failed: HashMap<String, AdapterLoadFailure>,
+    http_client: reqwest::Client,
+    image_adapters: Providers<SharedImage>,
+    llm_adapters: Providers<SharedLlm>,
+    /// Manifests of the WASM adapters, by service and provider
+    manifests: HashMap<(String, String), AdapterManifest>,
+    storage_adapters: Providers<SharedStorage>,
+    stt_adapters: Providers<SharedStt>,
+    tts_adapters: Providers<SharedTts>,
 }
 
 impl AdapterRegistry {
     /// Create new adapter registry
     pub async fn new() -> Result<Self, ServiceError> {
         let runtime = WasmRuntime::new()?;
+        let http_client = http::shared_client()?;
 
         Ok(AdapterRegistry {
             runtime: Arc::new(RwLock::new(runtime)),
-            llm_adapters: HashMap::new(),
-            storage_adapters: HashMap::new(),
+            crypto_adapters: Providers::new("crypto", "crypto"),
+            failed: HashMap::new(),
+            http_client,
+            image_adapters: Providers::new("image", "image"),
+            llm_adapters: Providers::new("llm", "LLM"),
+            manifests: HashMap::new(),
+            storage_adapters: Providers::new("storage", "storage"),
+            stt_adapters: Providers::new("stt", "STT"),
+            tts_adapters: Providers::new("tts", "TTS"),
         })
     }
 
@@ -40,100 +187,467 @@ impl AdapterRegistry {
         config: &Config,
         data_dir: &Path,
     ) -> Result<(), ServiceError> {
-        for (service_name, service_config) in &config.adapters.services {
-            match service_name.as_str() {
-                "llm" => {
-                    let adapter = llm::LlmAdapterWrapper::new(
-                        &self.runtime,
-                        service_config,
-                        data_dir,
-                        service_name,
-                    )
-                    .await?;
+        for (provider, services) in shared_providers(config) {
+            tracing::warn!(
+                "Provider '{}' is configured for several services ({}); each gets its own adapter",
+                provider,
+                services.join(", ")
+            );
+        }
 
-                    self.llm_adapters
-                        .insert(service_config.provider.clone(), adapter);
-                }
-                "storage" => {
-                    let adapter = storage::StorageAdapterWrapper::new(
-                        &self.runtime,
-                        service_config,
-                        data_dir,
-                        service_name,
-                    )
-                    .await?;
+        // Crypto first, since encrypted storage needs it
+        let mut services: Vec<_> = config.adapters.services.iter().collect();
+        services.sort_by_key(|(service_name, _)| service_name.as_str() != "crypto");
 
-                    self.storage_adapters
-                        .insert(service_config.provider.clone(), adapter);
+        for (service_name, service_config) in services {
+            let loaded = self
+                .load_service(service_name, service_config, config, data_dir)
+                .await;
+            match loaded {
+                Ok(()) => {
+                    self.failed.remove(service_name);
                 }
-                _ => {
-                    tracing::warn!("Unknown service type: {}", service_name);
+                Err(e) if config.adapters.fail_fast => return Err(e),
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to load {} adapter '{}', continuing without it: {}",
+                        service_name,
+                        service_config.provider,
+                        e
+                    );
+                    let failure =
+                        AdapterLoadFailure::new(service_name, service_config, data_dir, &e);
+                    self.failed.insert(service_name.clone(), failure);
                 }
             }
         }
 
+        self.check_default_providers(config)
+    }
+
+    /// Fail when a `default_provider` in `config` names an LLM or storage
+    /// provider that is neither registered nor configured
+    pub fn check_default_providers(&self, config: &Config) -> Result<(), ServiceError> {
+        self.llm_adapters.check_default(config)?;
+        self.storage_adapters.check_default(config)
+    }
+
+    /// Load and register the adapter configured for `service_name`
+    async fn load_service(
+        &mut self,
+        service_name: &str,
+        service_config: &ServiceAdapterConfig,
+        config: &Config,
+        data_dir: &Path,
+    ) -> Result<(), ServiceError> {
+        match service_name {
+            "crypto" => {
+                check_crypto_provider(service_config)?;
+                self.register_crypto_adapter(
+                    AES_GCM_PROVIDER,
+                    AesGcmCrypto::from_config(service_config)?,
+                );
+            }
+            "image" => {
+                let adapter = image::ImageAdapterWrapper::new(
+                    &self.runtime,
+                    service_config,
+                    data_dir,
+                    service_name,
+                    AdapterLoadOptions::from_config(&config.adapters),
+                )
+                .await?;
+
+                self.add_manifest(service_name, &service_config.provider, &adapter);
+                self.register_image_adapter(&service_config.provider, adapter);
+            }
+            "llm" if !service_config.fallback.is_empty() => {
+                let chain = fallback::FallbackLlm::load(
+                    &self.runtime,
+                    &self.http_client,
+                    service_config,
+                    data_dir,
+                    &config.adapters,
+                )
+                .await?;
+
+                self.add_manifest(service_name, &service_config.provider, &chain);
+                self.register_llm_adapter(&service_config.provider, chain);
+            }
+            "llm" if service_config.provider == MOCK_PROVIDER => {
+                self.register_llm_adapter(MOCK_PROVIDER, MockLlm::from_config(service_config)?);
+            }
+            "llm" => {
+                let adapter = llm::LlmAdapterWrapper::new(
+                    &self.runtime,
+                    &self.http_client,
+                    service_config,
+                    data_dir,
+                    service_name,
+                    AdapterLoadOptions::from_config(&config.adapters),
+                )
+                .await?;
+
+                self.add_manifest(service_name, &service_config.provider, &adapter);
+                self.register_llm_adapter(&service_config.provider, adapter);
+            }
+            "storage" if service_config.provider == MEMORY_PROVIDER => {
+                self.register_configured_storage(
+                    config,
+                    MEMORY_PROVIDER,
+                    MemoryStorage::default(),
+                )?;
+            }
+            "storage" if service_config.provider == SQLITE_PROVIDER => {
+                let adapter = SqliteStorage::from_config(service_config, data_dir).await?;
+
+                self.register_configured_storage(config, &service_config.provider, adapter)?;
+            }
+            "storage" => {
+                let adapter = storage::StorageAdapterWrapper::new(
+                    &self.runtime,
+                    service_config,
+                    data_dir,
+                    service_name,
+                    AdapterLoadOptions::from_config(&config.adapters),
+                )
+                .await?;
+
+                self.add_manifest(service_name, &service_config.provider, &adapter);
+                self.register_configured_storage(config, &service_config.provider, adapter)?;
+            }
+            "stt" => {
+                let adapter = stt::SttAdapterWrapper::new(
+                    &self.runtime,
+                    service_config,
+                    data_dir,
+                    service_name,
+                    AdapterLoadOptions::from_config(&config.adapters),
+                )
+                .await?;
+
+                self.add_manifest(service_name, &service_config.provider, &adapter);
+                self.register_stt_adapter(&service_config.provider, adapter);
+            }
+            "tts" if service_config.provider == SILENCE_PROVIDER => {
+                self.register_tts_adapter(SILENCE_PROVIDER, SilenceTts);
+            }
+            "tts" => {
+                let adapter = tts::TtsAdapterWrapper::new(
+                    &self.runtime,
+                    service_config,
+                    data_dir,
+                    service_name,
+                    AdapterLoadOptions::from_config(&config.adapters),
+                )
+                .await?;
+
+                self.add_manifest(service_name, &service_config.provider, &adapter);
+                self.register_tts_adapter(&service_config.provider, adapter);
+            }
+            _ => {
+                tracing::warn!("Unknown service type: {}", service_name);
+            }
+        }
+
         Ok(())
     }
 
-    /// Get LLM adapter by provider name
-    pub fn get_llm_adapter(&self, provider: &str) -> Option<&LlmAdapterWrapper> {
-        self.llm_adapters.get(provider)
+    fn add_manifest(&mut self, service: &str, provider: &str, adapter: &impl AdapterService) {
+        if let Some(manifest) = adapter.manifest() {
+            self.manifests.insert(
+                (service.to_string(), provider.to_string()),
+                manifest.clone(),
+            );
+        }
     }
 
-    /// Get mutable LLM adapter by provider name
-    pub fn get_llm_adapter_mut(&mut self, provider: &str) -> Option<&mut LlmAdapterWrapper> {
-        self.llm_adapters.get_mut(provider)
+    /// Register a crypto adapter under `provider`
+    ///
+    /// Replaces an adapter already registered under `provider`.
+    pub fn register_crypto_adapter<C: CryptoAdapter + 'static>(
+        &mut self,
+        provider: &str,
+        adapter: C,
+    ) {
+        let adapter: SharedCrypto = Arc::new(RwLock::new(adapter));
+        self.crypto_adapters.insert(provider, adapter);
     }
 
-    /// Get storage adapter by provider name
-    pub fn get_storage_adapter(&self, provider: &str) -> Option<&StorageAdapterWrapper> {
-        self.storage_adapters.get(provider)
+    /// Register an image adapter under `provider`
+    ///
+    /// Replaces an adapter already registered under `provider`.
+    pub fn register_image_adapter<I: ImageAdapter + 'static>(
+        &mut self,
+        provider: &str,
+        adapter: I,
+    ) {
+        let adapter: SharedImage = Arc::new(RwLock::new(adapter));
+        self.image_adapters.insert(provider, adapter);
+    }
+
+    /// Register an LLM adapter under `provider`
+    ///
+    /// Any `LlmAdapter` works, so embedders can plug in native Rust
+    /// implementations (in-process models, test doubles) without a WASM
+    /// module. Replaces an adapter already registered under `provider`.
+    pub fn register_llm_adapter<L: LlmAdapter + 'static>(&mut self, provider: &str, adapter: L) {
+        let adapter: SharedLlm = Arc::new(RwLock::new(adapter));
+        self.llm_adapters.insert(provider, adapter);
+    }
+
+    /// Register a storage adapter under `provider`
+    ///
+    /// Keys are encoded with `KeyCodec` before they reach the adapter, as
+    /// for storage loaded from the config. Replaces an adapter already
+    /// registered under `provider`.
+    pub fn register_storage_adapter<S: StorageAdapter + 'static>(
+        &mut self,
+        provider: &str,
+        adapter: S,
+    ) {
+        let adapter: SharedStorage = Arc::new(RwLock::new(EncodedKeys::new(Box::new(adapter))));
+        self.insert_storage_adapter(provider, adapter);
     }
 
-    /// Get mutable storage adapter by provider name
-    pub fn get_storage_adapter_mut(
+    fn insert_storage_adapter(&mut self, provider: &str, adapter: SharedStorage) {
+        self.storage_adapters.insert(provider, adapter);
+    }
+
+    /// Register a storage adapter loaded for `config`
+    ///
+    /// With `[storage] encrypt`, its values are encrypted with the crypto
+    /// adapter of `config`, which must be registered by then, for their
+    /// logical keys (see `EncryptedValues`).
+    fn register_configured_storage<S: StorageAdapter + 'static>(
         &mut self,
+        config: &Config,
         provider: &str,
-    ) -> Option<&mut StorageAdapterWrapper> {
-        self.storage_adapters.get_mut(provider)
+        adapter: S,
+    ) -> Result<(), ServiceError> {
+        match storage_crypto(config, self.crypto_adapter_for(config))? {
+            Some(crypto) => {
+                let storage = EncodedKeys::new(Box::new(adapter));
+                let adapter: SharedStorage =
+                    Arc::new(RwLock::new(EncryptedValues::new(Box::new(storage), crypto)));
+                self.insert_storage_adapter(provider, adapter);
+            }
+            None => self.register_storage_adapter(provider, adapter),
+        }
+        Ok(())
+    }
+
+    /// Register an STT adapter under `provider`
+    ///
+    /// Replaces an adapter already registered under `provider`.
+    pub fn register_stt_adapter<T: SttAdapter + 'static>(&mut self, provider: &str, adapter: T) {
+        let adapter: SharedStt = Arc::new(RwLock::new(adapter));
+        self.stt_adapters.insert(provider, adapter);
+    }
+
+    /// Register a TTS adapter under `provider`
+    ///
+    /// Replaces an adapter already registered under `provider`.
+    pub fn register_tts_adapter<T: TtsAdapter + 'static>(&mut self, provider: &str, adapter: T) {
+        let adapter: SharedTts = Arc::new(RwLock::new(adapter));
+        self.tts_adapters.insert(provider, adapter);
+    }
+}
+
+/// Lookups by provider and defaults set in code, for embedders
+///
+/// The server picks adapters with `*_adapter_for` instead.
+#[allow(dead_code)] // Library API; the binary only uses the config-driven lookups
+impl AdapterRegistry {
+    /// HTTP client shared by all adapters
+    pub fn http_client(&self) -> &reqwest::Client {
+        &self.http_client
+    }
+
+    /// Get crypto adapter by provider name
+    pub fn get_crypto_adapter(&self, provider: &str) -> Option<&SharedCrypto> {
+        self.crypto_adapters.get(provider)
+    }
+
+    /// Get image adapter by provider name
+    pub fn get_image_adapter(&self, provider: &str) -> Option<&SharedImage> {
+        self.image_adapters.get(provider)
+    }
+
+    /// Get LLM adapter by provider name
+    pub fn get_llm_adapter(&self, provider: &str) -> Option<&SharedLlm> {
+        self.llm_adapters.get(provider)
+    }
+
+    /// Get storage adapter by provider name
+    pub fn get_storage_adapter(&self, provider: &str) -> Option<&SharedStorage> {
+        self.storage_adapters.get(provider)
+    }
+
+    /// Get STT adapter by provider name
+    pub fn get_stt_adapter(&self, provider: &str) -> Option<&SharedStt> {
+        self.stt_adapters.get(provider)
+    }
+
+    /// Get TTS adapter by provider name
+    pub fn get_tts_adapter(&self, provider: &str) -> Option<&SharedTts> {
+        self.tts_adapters.get(provider)
+    }
+
+    /// Manifest of the WASM adapter loaded for `service` from `provider`
+    ///
+    /// Native and built-in adapters have none.
+    pub fn get_manifest(&self, service: &str, provider: &str) -> Option<&AdapterManifest> {
+        self.manifests
+            .get(&(service.to_string(), provider.to_string()))
+    }
+
+    /// Make the adapter registered under `provider` the default crypto adapter
+    pub fn set_default_crypto_adapter(&mut self, provider: &str) -> Result<(), ServiceError> {
+        self.crypto_adapters.set_default(provider)
+    }
+
+    /// Make the adapter registered under `provider` the default image adapter
+    pub fn set_default_image_adapter(&mut self, provider: &str) -> Result<(), ServiceError> {
+        self.image_adapters.set_default(provider)
+    }
+
+    /// Make the adapter registered under `provider` the default LLM adapter
+    pub fn set_default_llm_adapter(&mut self, provider: &str) -> Result<(), ServiceError> {
+        self.llm_adapters.set_default(provider)
+    }
+
+    /// Make the adapter registered under `provider` the default storage adapter
+    pub fn set_default_storage_adapter(&mut self, provider: &str) -> Result<(), ServiceError> {
+        self.storage_adapters.set_default(provider)
+    }
+
+    /// Make the adapter registered under `provider` the default STT adapter
+    pub fn set_default_stt_adapter(&mut self, provider: &str) -> Result<(), ServiceError> {
+        self.stt_adapters.set_default(provider)
+    }
+
+    /// Make the adapter registered under `provider` the default TTS adapter
+    pub fn set_default_tts_adapter(&mut self, provider: &str) -> Result<(), ServiceError> {
+        self.tts_adapters.set_default(provider)
+    }
+
+    /// Get the default crypto adapter, chosen like `get_default_llm_adapter`
+    pub fn get_default_crypto_adapter(&self) -> Option<&SharedCrypto> {
+        self.crypto_adapters.default_adapter()
+    }
+
+    /// Get the default image adapter, chosen like `get_default_llm_adapter`
+    pub fn get_default_image_adapter(&self) -> Option<&SharedImage> {
+        self.image_adapters.default_adapter()
+    }
+
+    /// Get the default LLM adapter
+    ///
+    /// That's the one set with `set_default_llm_adapter`, or else the first
+    /// registered, with a warning if there are several to choose from.
+    pub fn get_default_llm_adapter(&self) -> Option<&SharedLlm> {
+        self.llm_adapters.default_adapter()
+    }
+
+    /// Get the default storage adapter, chosen like `get_default_llm_adapter`
+    pub fn get_default_storage_adapter(&self) -> Option<&SharedStorage> {
+        self.storage_adapters.default_adapter()
+    }
+
+    /// Get the default STT adapter, chosen like `get_default_llm_adapter`
+    pub fn get_default_stt_adapter(&self) -> Option<&SharedStt> {
+        self.stt_adapters.default_adapter()
+    }
+
+    /// Get the default TTS adapter, chosen like `get_default_llm_adapter`
+    pub fn get_default_tts_adapter(&self) -> Option<&SharedTts> {
+        self.tts_adapters.default_adapter()
+    }
+}
+
+impl AdapterRegistry {
+    /// Crypto adapter to use with `config`, chosen like `llm_adapter_for`
+    pub fn crypto_adapter_for(&self, config: &Config) -> Option<&SharedCrypto> {
+        self.crypto_adapters.adapter_for(config)
+    }
+
+    /// Image adapter to use with `config`, chosen like `llm_adapter_for`
+    pub fn image_adapter_for(&self, config: &Config) -> Option<&SharedImage> {
+        self.image_adapters.adapter_for(config)
+    }
+
+    /// LLM adapter to use with `config`
+    ///
+    /// The provider configured under `[adapters.llm]` if it is registered,
+    /// otherwise its `default_provider` or else the default one, so
+    /// registered adapters also work with a config that names a different
+    /// provider.
+    pub fn llm_adapter_for(&self, config: &Config) -> Option<&SharedLlm> {
+        self.llm_adapters.adapter_for(config)
     }
 
-    /// Get default LLM adapter (first available)
-    pub fn get_default_llm_adapter(&self) -> Option<&LlmAdapterWrapper> {
-        self.llm_adapters.values().next()
+    /// Storage adapter to use with `config`, chosen like `llm_adapter_for`
+    pub fn storage_adapter_for(&self, config: &Config) -> Option<&SharedStorage> {
+        self.storage_adapters.adapter_for(config)
     }
 
-    /// Get default storage adapter (first available)
-    pub fn get_default_storage_adapter(&self) -> Option<&StorageAdapterWrapper> {
-        self.storage_adapters.values().next()
+    /// STT adapter to use with `config`, chosen like `llm_adapter_for`
+    pub fn stt_adapter_for(&self, config: &Config) -> Option<&SharedStt> {
+        self.stt_adapters.adapter_for(config)
     }
-    /// List all loaded adapters
+
+    /// TTS adapter to use with `config`, chosen like `llm_adapter_for`
+    pub fn tts_adapter_for(&self, config: &Config) -> Option<&SharedTts> {
+        self.tts_adapters.adapter_for(config)
+    }
+
+    /// List all loaded adapters, then those that failed to load
+    ///
+    /// Failed adapters have the status `failed: <error>`.
+    #[allow(dead_code)] // Used in tests and by embedders
     pub async fn list_adapters(&self) -> Vec<(String, String, String, String)> {
-        let runtime = self.runtime.read().await;
-        runtime
-            .list_adapters()
-            .into_iter()
-            .map(|(service, provider, version)| {
-                (
-                    service.to_string(),
-                    provider.to_string(),
-                    version.to_string(),
-                    "ready".to_string(),
-                )
-            })
-            .collect()
+        let mut adapters = Vec::new();
+
+        adapters.extend(self.crypto_adapters.entries().await);
+        adapters.extend(self.image_adapters.entries().await);
+        adapters.extend(self.llm_adapters.entries().await);
+        adapters.extend(self.storage_adapters.entries().await);
+        adapters.extend(self.stt_adapters.entries().await);
+        adapters.extend(self.tts_adapters.entries().await);
+
+        let mut failed: Vec<_> = self.failed.iter().collect();
+        failed.sort_by_key(|(service, _)| service.as_str());
+        adapters.extend(failed.into_iter().map(|(service, failure)| {
+            (
+                service.clone(),
+                failure.provider.clone(),
+                failure.version.clone(),
+                format!("failed: {}", failure.error),
+            )
+        }));
+
+        adapters
+    }
+
+    /// Configured adapters that failed to load, by service
+    ///
+    /// Empty unless `[adapters] fail_fast` is off and a service failed.
+    pub fn failed_adapters(&self) -> &HashMap<String, AdapterLoadFailure> {
+        &self.failed
     }
 
     /// Graceful shutdown of all adapters
     pub async fn shutdown(&mut self) -> Result<(), ServiceError> {
         // Shutdown service adapters
-        for (_, mut adapter) in self.llm_adapters.drain() {
-            adapter.shutdown().await?;
-        }
-
-        for (_, mut adapter) in self.storage_adapters.drain() {
-            adapter.shutdown().await?;
-        }
+        self.crypto_adapters.shutdown().await?;
+        self.image_adapters.shutdown().await?;
+        self.llm_adapters.shutdown().await?;
+        self.storage_adapters.shutdown().await?;
+        self.stt_adapters.shutdown().await?;
+        self.tts_adapters.shutdown().await?;
+        self.manifests.clear();
+        self.failed.clear();
 
         // Shutdown runtime
         let mut runtime = self.runtime.write().await;
@@ -142,3 +656,37 @@ impl AdapterRegistry {
         Ok(())
     }
 }
+
+/// Whether `provider` is built into the host for `service`, needing no WASM module
+pub fn is_built_in(service: &str, provider: &str) -> bool {
+    match service {
+        "crypto" => provider == AES_GCM_PROVIDER,
+        "llm" => provider == MOCK_PROVIDER,
+        "storage" => [MEMORY_PROVIDER, SQLITE_PROVIDER].contains(&provider),
+        "tts" => provider == SILENCE_PROVIDER,
+        _ => false,
+    }
+}
+
+/// Providers configured for more than one service, with those services
+///
+/// That works, as adapters and manifests are kept per service, but is more
+/// likely a copy-paste slip than intended.
+pub(crate) fn shared_providers(config: &Config) -> Vec<(String, Vec<String>)> {
+    let mut services_by_provider: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (service, service_config) in &config.adapters.services {
+        services_by_provider
+            .entry(&service_config.provider)
+            .or_default()
+            .push(service.clone());
+    }
+
+    services_by_provider
+        .into_iter()
+        .filter(|(_, services)| services.len() > 1)
+        .map(|(provider, mut services)| {
+            services.sort();
+            (provider.to_string(), services)
+        })
+        .collect()
+}
@@ -0,0 +1,149 @@
+use crate::adapter::traits::{AdapterService, ServiceError};
+use crate::config::schema::Config;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Adapters of one service by provider, in registration order
+///
+/// Keeps the default adapter the same across runs when several are
+/// registered, which a `HashMap` wouldn't.
+pub(crate) struct Providers<T> {
+    adapters: Vec<(String, T)>,
+    /// Provider set with `set_default`
+    default: Option<String>,
+    /// Service name in messages, e.g. "LLM"
+    label: &'static str,
+    /// Section of the service under `[adapters]`, e.g. "llm"
+    service: &'static str,
+}
+
+impl<T> Providers<T> {
+    pub(crate) fn new(service: &'static str, label: &'static str) -> Self {
+        Providers {
+            adapters: Vec::new(),
+            default: None,
+            label,
+            service,
+        }
+    }
+
+    pub(crate) fn get(&self, provider: &str) -> Option<&T> {
+        self.adapters
+            .iter()
+            .find(|(name, _)| name == provider)
+            .map(|(_, adapter)| adapter)
+    }
+
+    /// Add or replace the adapter for `provider`
+    ///
+    /// A replaced adapter keeps its position.
+    pub(crate) fn insert(&mut self, provider: &str, adapter: T) {
+        match self.adapters.iter_mut().find(|(name, _)| name == provider) {
+            Some(entry) => {
+                tracing::warn!(
+                    "Replaced {} adapter for provider '{}'",
+                    self.label,
+                    provider
+                );
+                entry.1 = adapter;
+            }
+            None => self.adapters.push((provider.to_string(), adapter)),
+        }
+    }
+
+    pub(crate) fn set_default(&mut self, provider: &str) -> Result<(), ServiceError> {
+        if self.get(provider).is_none() {
+            return Err(ServiceError::InvalidConfig(format!(
+                "Default {} provider '{}' isn't registered",
+                self.label, provider
+            )));
+        }
+        self.default = Some(provider.to_string());
+        Ok(())
+    }
+
+    /// The default adapter, or else the first registered one
+    pub(crate) fn default_adapter(&self) -> Option<&T> {
+        if let Some(adapter) = self.default.as_deref().and_then(|name| self.get(name)) {
+            return Some(adapter);
+        }
+        let (provider, adapter) = self.adapters.first()?;
+        if self.adapters.len() > 1 {
+            tracing::warn!(
+                "No default {} provider set, using '{}', the first registered",
+                self.label,
+                provider
+            );
+        }
+        Some(adapter)
+    }
+
+    /// Adapter to use with `config`
+    ///
+    /// The provider configured for the service if it is registered,
+    /// otherwise its `default_provider`, otherwise the default one.
+    pub(crate) fn adapter_for(&self, config: &Config) -> Option<&T> {
+        let Some(service_config) = config.adapters.get_service(self.service) else {
+            return self.default_adapter();
+        };
+        if let Some(adapter) = self.get(&service_config.provider) {
+            return Some(adapter);
+        }
+        match &service_config.default_provider {
+            Some(provider) => self.get(provider),
+            None => self.default_adapter(),
+        }
+    }
+
+    /// Fail unless the `default_provider` in `config` names a provider that
+    /// is registered or configured for the service
+    ///
+    /// A configured provider that failed to load is reported as such.
+    pub(crate) fn check_default(&self, config: &Config) -> Result<(), ServiceError> {
+        let Some(service_config) = config.adapters.get_service(self.service) else {
+            return Ok(());
+        };
+        match &service_config.default_provider {
+            Some(provider)
+                if *provider != service_config.provider && self.get(provider).is_none() =>
+            {
+                Err(ServiceError::InvalidConfig(format!(
+                    "adapters.{}.default_provider '{}' isn't a registered {} provider",
+                    self.service, provider, self.label
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<A: AdapterService + ?Sized> Providers<Arc<RwLock<A>>> {
+    /// Service, provider, version and status of each adapter, for `list_adapters`
+    pub(crate) async fn entries(&self) -> Vec<(String, String, String, String)> {
+        let mut entries = Vec::with_capacity(self.adapters.len());
+        for (_, adapter) in &self.adapters {
+            let adapter = adapter.read().await;
+            let status = if adapter.is_ready() {
+                "ready"
+            } else {
+                "not ready"
+            };
+            entries.push((
+                adapter.service_name().to_string(),
+                adapter.provider_name().to_string(),
+                adapter.version().to_string(),
+                status.to_string(),
+            ));
+        }
+        entries
+    }
+
+    /// Remove all adapters, shutting each down
+    pub(crate) async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        self.default = None;
+        for (_, adapter) in self.adapters.drain(..) {
+            adapter.write().await.shutdown().await?;
+        }
+        Ok(())
+    }
+}
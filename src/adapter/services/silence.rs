@@ -0,0 +1,175 @@
+use crate::adapter::traits::{AdapterService, ServiceError, Speech, TtsAdapter};
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+/// Provider name selecting the built-in TTS stub
+pub const SILENCE_PROVIDER: &str = "silence";
+
+/// Samples per second of the audio
+const SAMPLE_RATE: u32 = 8000;
+
+/// Audio length per character of text
+const MILLIS_PER_CHAR: u32 = 50;
+
+/// Longest clip produced, so long texts don't make huge responses
+const MAX_MILLIS: u32 = 30_000;
+
+/// Bytes of silence per streamed chunk
+const CHUNK_BYTES: usize = 16 * 1024;
+
+/// MIME type of the clips
+const WAV_MIME_TYPE: &str = "audio/wav";
+
+/// TTS adapter answering every text with silence
+///
+/// Produces a WAV clip about as long as speaking the text would take,
+/// which is enough to exercise clients and the speech endpoint without a
+/// provider. Any voice is accepted.
+#[derive(Debug, Default)]
+pub struct SilenceTts;
+
+#[async_trait]
+impl AdapterService for SilenceTts {
+    fn service_name(&self) -> &'static str {
+        "tts"
+    }
+
+    fn provider_name(&self) -> &str {
+        SILENCE_PROVIDER
+    }
+
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TtsAdapter for SilenceTts {
+    async fn synthesize(
+        &mut self,
+        text: &str,
+        _voice: Option<&str>,
+    ) -> Result<Speech, ServiceError> {
+        let samples = samples_for(text);
+        let mut audio = wav_header(samples);
+        audio.resize(audio.len() + samples as usize * 2, 0);
+
+        Ok(Speech {
+            audio,
+            mime_type: WAV_MIME_TYPE.to_string(),
+        })
+    }
+
+    /// Send the WAV header, then the silence in chunks
+    async fn stream_speech(
+        &mut self,
+        text: &str,
+        _voice: Option<&str>,
+        chunks: mpsc::Sender<Speech>,
+    ) -> Result<(), ServiceError> {
+        let samples = samples_for(text);
+        let mut remaining = samples as usize * 2;
+        let mut audio = wav_header(samples);
+
+        loop {
+            let speech = Speech {
+                audio,
+                mime_type: WAV_MIME_TYPE.to_string(),
+            };
+            if chunks.send(speech).await.is_err() || remaining == 0 {
+                break;
+            }
+            let len = remaining.min(CHUNK_BYTES);
+            remaining -= len;
+            audio = vec![0; len];
+        }
+
+        Ok(())
+    }
+}
+
+/// Silent 16-bit samples lasting about as long as speaking `text`
+fn samples_for(text: &str) -> u32 {
+    let chars = u32::try_from(text.chars().count()).unwrap_or(u32::MAX);
+    let millis = chars.saturating_mul(MILLIS_PER_CHAR).min(MAX_MILLIS);
+    SAMPLE_RATE * millis / 1000
+}
+
+/// Header of a WAV file of `samples` 16-bit mono samples
+fn wav_header(samples: u32) -> Vec<u8> {
+    let data_len = samples * 2;
+    let mut wav = Vec::with_capacity(44);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    // Format chunk: PCM, 1 channel, 16 bits per sample
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    // Data chunk
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_silence_lasts_as_long_as_the_text() {
+        let speech = SilenceTts.synthesize("Hello", None).await.unwrap();
+
+        assert_eq!(speech.mime_type, "audio/wav");
+        assert_eq!(&speech.audio[..4], b"RIFF");
+        assert_eq!(&speech.audio[8..12], b"WAVE");
+        // 5 characters are 250ms, 2000 samples of 2 bytes
+        assert_eq!(speech.audio.len(), 44 + 4000);
+        assert!(speech.audio[44..].iter().all(|&byte| byte == 0));
+
+        let long = SilenceTts
+            .synthesize(&"a".repeat(10_000), Some("any"))
+            .await
+            .unwrap();
+        assert_eq!(long.audio.len(), 44 + 30 * 8000 * 2);
+    }
+
+    #[tokio::test]
+    async fn test_silence_streams_the_same_clip() {
+        let text = "a".repeat(1000);
+        let (chunks, mut receiver) = mpsc::channel::<Speech>(4);
+        let streamed = tokio::spawn(async move {
+            let mut audio = Vec::new();
+            let mut count = 0;
+            while let Some(chunk) = receiver.recv().await {
+                assert_eq!(chunk.mime_type, "audio/wav");
+                audio.extend(chunk.audio);
+                count += 1;
+            }
+            (audio, count)
+        });
+
+        SilenceTts.stream_speech(&text, None, chunks).await.unwrap();
+        let (audio, count) = streamed.await.unwrap();
+
+        let speech = SilenceTts.synthesize(&text, None).await.unwrap();
+        assert_eq!(audio, speech.audio);
+        // The header, then 50 seconds capped at 30 in 16 KiB chunks
+        assert_eq!(count, 1 + (30 * 8000 * 2usize).div_ceil(CHUNK_BYTES));
+    }
+}
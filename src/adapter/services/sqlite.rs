@@ -0,0 +1,223 @@
+use crate::adapter::traits::{AdapterService, KeyPage, ServiceError, StorageAdapter};
+use crate::config::defaults::DEFAULT_SQLITE_FILE;
+use crate::config::schema::ServiceAdapterConfig;
+use async_trait::async_trait;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Provider name selecting the host-backed SQLite storage
+pub const SQLITE_PROVIDER: &str = "sqlite";
+
+/// Schema created on first use
+///
+/// `case_sensitive_like` makes `LIKE` match keys exactly as stored and lets
+/// prefix queries use the primary key index.
+const SCHEMA: &str = "
+    PRAGMA case_sensitive_like = ON;
+    PRAGMA journal_mode = WAL;
+    CREATE TABLE IF NOT EXISTS entries (
+        key TEXT PRIMARY KEY NOT NULL,
+        value BLOB NOT NULL
+    ) WITHOUT ROWID;
+";
+
+/// Storage adapter backed by a SQLite file on the host
+///
+/// WASM adapters can't open files, so unlike other providers this one runs
+/// natively. Queries run on the blocking thread pool.
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    /// Open (or create) the database configured for a storage adapter
+    ///
+    /// `path` in the adapter config is relative to the data directory and
+    /// defaults to `storage.sqlite3`.
+    pub async fn from_config(
+        config: &ServiceAdapterConfig,
+        data_dir: &Path,
+    ) -> Result<Self, ServiceError> {
+        let path = match config.config.get("path") {
+            Some(toml::Value::String(path)) => {
+                crate::config::expand_required_path(path, Some(data_dir))
+            }
+            Some(_) => {
+                return Err(ServiceError::InvalidConfig(
+                    "SQLite storage `path` must be a string".to_string(),
+                ));
+            }
+            None => data_dir.join(DEFAULT_SQLITE_FILE),
+        };
+
+        Self::open(path).await
+    }
+
+    /// Open (or create) the database at `path`, creating the schema if needed
+    pub async fn open(path: PathBuf) -> Result<Self, ServiceError> {
+        let opened = path.clone();
+        let connection = tokio::task::spawn_blocking(move || {
+            let connection = Connection::open(&opened)?;
+            connection.execute_batch(SCHEMA)?;
+            Ok::<_, rusqlite::Error>(connection)
+        })
+        .await
+        .map_err(task_error)?
+        .map_err(|e| {
+            ServiceError::InitializationFailed(format!(
+                "Failed to open SQLite storage {}: {e}",
+                path.display()
+            ))
+        })?;
+
+        tracing::debug!("Opened SQLite storage at {}", path.display());
+
+        Ok(SqliteStorage {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Run a query on the blocking thread pool
+    async fn with_connection<T, F>(&self, query: F) -> Result<T, ServiceError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let connection = connection.lock().map_err(|_| {
+                ServiceError::ExecutionError("SQLite connection poisoned".to_string())
+            })?;
+            query(&connection).map_err(|e| ServiceError::ExecutionError(format!("SQLite: {e}")))
+        })
+        .await
+        .map_err(task_error)?
+    }
+}
+
+#[async_trait]
+impl AdapterService for SqliteStorage {
+    fn service_name(&self) -> &'static str {
+        "storage"
+    }
+
+    fn provider_name(&self) -> &str {
+        SQLITE_PROVIDER
+    }
+
+    fn version(&self) -> &str {
+        rusqlite::version()
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        // The connection is closed when the last handle is dropped
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageAdapter for SqliteStorage {
+    async fn store(&mut self, key: &str, data: &[u8]) -> Result<(), ServiceError> {
+        let (key, data) = (key.to_string(), data.to_vec());
+
+        self.with_connection(move |connection| {
+            connection
+                .execute(
+                    "INSERT INTO entries (key, value) VALUES (?1, ?2)
+                     ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                    params![key, data],
+                )
+                .map(|_| ())
+        })
+        .await
+    }
+
+    async fn retrieve(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        let owned_key = key.to_string();
+
+        self.with_connection(move |connection| {
+            connection
+                .query_row(
+                    "SELECT value FROM entries WHERE key = ?1",
+                    [owned_key],
+                    |row| row.get(0),
+                )
+                .optional()
+        })
+        .await?
+        .ok_or_else(|| ServiceError::ExecutionError(format!("Missing key: {key}")))
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), ServiceError> {
+        let key = key.to_string();
+
+        self.with_connection(move |connection| {
+            connection
+                .execute("DELETE FROM entries WHERE key = ?1", [key])
+                .map(|_| ())
+        })
+        .await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, ServiceError> {
+        let key = key.to_string();
+
+        self.with_connection(move |connection| {
+            connection
+                .query_row("SELECT 1 FROM entries WHERE key = ?1", [key], |_| Ok(()))
+                .optional()
+                .map(|row| row.is_some())
+        })
+        .await
+    }
+
+    async fn list_keys_paginated(
+        &self,
+        prefix: Option<&str>,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<KeyPage, ServiceError> {
+        let pattern = format!("{}%", escape_like(prefix.unwrap_or("")));
+        let after = cursor.map(KeyPage::decode_cursor).transpose()?;
+        // One extra row tells whether there's another page
+        let fetch = i64::try_from(limit.max(1))
+            .unwrap_or(i64::MAX)
+            .saturating_add(1);
+
+        let keys: Vec<String> = self
+            .with_connection(move |connection| {
+                let mut statement = connection.prepare(
+                    "SELECT key FROM entries
+                     WHERE key LIKE ?1 ESCAPE '\\' AND (?2 IS NULL OR key > ?2)
+                     ORDER BY key LIMIT ?3",
+                )?;
+                let keys = statement.query_map(params![pattern, after, fetch], |row| row.get(0))?;
+                keys.collect()
+            })
+            .await?;
+
+        Ok(KeyPage::from_sorted(keys, limit))
+    }
+}
+
+/// Escape `LIKE` wildcards so a prefix matches literally
+fn escape_like(prefix: &str) -> String {
+    let mut escaped = String::with_capacity(prefix.len());
+    for c in prefix.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn task_error(error: tokio::task::JoinError) -> ServiceError {
+    ServiceError::ExecutionError(format!("SQLite task failed: {error}"))
+}
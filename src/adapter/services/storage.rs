@@ -1,5 +1,7 @@
+use crate::adapter::manifest::AdapterManifest;
 use crate::adapter::runtime::WasmRuntime;
-use crate::adapter::traits::{AdapterService, ServiceError, StorageAdapter};
+use crate::adapter::services::{AdapterLoadOptions, load_wasm_adapter};
+use crate::adapter::traits::{AdapterService, KeyPage, ServiceError, StorageAdapter};
 use crate::config::schema::ServiceAdapterConfig;
 use async_trait::async_trait;
 use std::path::Path;
@@ -9,6 +11,7 @@ use tokio::sync::RwLock;
 /// Storage adapter wrapper providing typed interface to WASM instances
 pub struct StorageAdapterWrapper {
     runtime: Arc<RwLock<WasmRuntime>>,
+    manifest: AdapterManifest,
     provider: String,
     version: String,
     service_name: String,
@@ -21,22 +24,13 @@ impl StorageAdapterWrapper {
         config: &ServiceAdapterConfig,
         data_dir: &Path,
         service_name: &str,
+        options: AdapterLoadOptions,
     ) -> Result<Self, ServiceError> {
-        let module_path = config.module_path(data_dir, service_name);
-        let config_json = config
-            .config_as_json()
-            .map_err(|e| ServiceError::InvalidConfig(e.to_string()))?;
-
-        // Load the WASM module
-        {
-            let mut runtime_guard = runtime.write().await;
-            runtime_guard
-                .load_adapter(service_name, &module_path, &config_json)
-                .await?;
-        }
+        let manifest = load_wasm_adapter(runtime, config, data_dir, service_name, options).await?;
 
         Ok(StorageAdapterWrapper {
             runtime: runtime.clone(),
+            manifest,
             provider: config.provider.clone(),
             version: config.version.clone(),
             service_name: service_name.to_string(),
@@ -58,6 +52,10 @@ impl AdapterService for StorageAdapterWrapper {
         &self.version
     }
 
+    fn manifest(&self) -> Option<&AdapterManifest> {
+        Some(&self.manifest)
+    }
+
     fn is_ready(&self) -> bool {
         // TODO: Check actual WASM instance readiness
         true
@@ -73,9 +71,14 @@ impl AdapterService for StorageAdapterWrapper {
 #[async_trait]
 impl StorageAdapter for StorageAdapterWrapper {
     async fn store(&mut self, key: &str, data: &[u8]) -> Result<(), ServiceError> {
-        let runtime = self.runtime.read().await;
-
-        if let Some(instance) = runtime.get_instance(&self.service_name, &self.provider) {
+        let pool = self
+            .runtime
+            .read()
+            .await
+            .get_pool(&self.service_name, &self.provider);
+
+        if let Some(pool) = pool {
+            let instance = pool.checkout().await?;
             if !instance.is_ready() {
                 return Err(ServiceError::ServiceUnavailable(
                     "Storage adapter not ready".to_string(),
@@ -94,9 +97,14 @@ impl StorageAdapter for StorageAdapterWrapper {
     }
 
     async fn retrieve(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
-        let runtime = self.runtime.read().await;
-
-        if let Some(instance) = runtime.get_instance(&self.service_name, &self.provider) {
+        let pool = self
+            .runtime
+            .read()
+            .await
+            .get_pool(&self.service_name, &self.provider);
+
+        if let Some(pool) = pool {
+            let instance = pool.checkout().await?;
             if !instance.is_ready() {
                 return Err(ServiceError::ServiceUnavailable(
                     "Storage adapter not ready".to_string(),
@@ -114,9 +122,14 @@ impl StorageAdapter for StorageAdapterWrapper {
     }
 
     async fn delete(&mut self, key: &str) -> Result<(), ServiceError> {
-        let runtime = self.runtime.read().await;
-
-        if let Some(instance) = runtime.get_instance(&self.service_name, &self.provider) {
+        let pool = self
+            .runtime
+            .read()
+            .await
+            .get_pool(&self.service_name, &self.provider);
+
+        if let Some(pool) = pool {
+            let instance = pool.checkout().await?;
             if !instance.is_ready() {
                 return Err(ServiceError::ServiceUnavailable(
                     "Storage adapter not ready".to_string(),
@@ -134,9 +147,14 @@ impl StorageAdapter for StorageAdapterWrapper {
     }
 
     async fn exists(&self, key: &str) -> Result<bool, ServiceError> {
-        let runtime = self.runtime.read().await;
-
-        if let Some(instance) = runtime.get_instance(&self.service_name, &self.provider) {
+        let pool = self
+            .runtime
+            .read()
+            .await
+            .get_pool(&self.service_name, &self.provider);
+
+        if let Some(pool) = pool {
+            let instance = pool.checkout().await?;
             if !instance.is_ready() {
                 return Err(ServiceError::ServiceUnavailable(
                     "Storage adapter not ready".to_string(),
@@ -153,10 +171,20 @@ impl StorageAdapter for StorageAdapterWrapper {
         }
     }
 
-    async fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, ServiceError> {
-        let runtime = self.runtime.read().await;
-
-        if let Some(instance) = runtime.get_instance(&self.service_name, &self.provider) {
+    async fn list_keys_paginated(
+        &self,
+        prefix: Option<&str>,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<KeyPage, ServiceError> {
+        let pool = self
+            .runtime
+            .read()
+            .await
+            .get_pool(&self.service_name, &self.provider);
+
+        if let Some(pool) = pool {
+            let instance = pool.checkout().await?;
             if !instance.is_ready() {
                 return Err(ServiceError::ServiceUnavailable(
                     "Storage adapter not ready".to_string(),
@@ -170,7 +198,7 @@ impl StorageAdapter for StorageAdapterWrapper {
                 None => vec!["key1".to_string(), "key2".to_string()],
             };
 
-            Ok(keys)
+            KeyPage::from_keys(keys, cursor, limit)
         } else {
             Err(ServiceError::ServiceUnavailable(
                 "Storage adapter instance not found".to_string(),
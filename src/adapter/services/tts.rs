@@ -0,0 +1,105 @@
+use crate::adapter::manifest::AdapterManifest;
+use crate::adapter::runtime::WasmRuntime;
+use crate::adapter::services::{AdapterLoadOptions, load_wasm_adapter};
+use crate::adapter::traits::{AdapterService, ServiceError, Speech, TtsAdapter};
+use crate::config::schema::ServiceAdapterConfig;
+use async_trait::async_trait;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// TTS adapter wrapper providing typed interface to WASM instances
+pub struct TtsAdapterWrapper {
+    runtime: Arc<RwLock<WasmRuntime>>,
+    manifest: AdapterManifest,
+    provider: String,
+    version: String,
+    service_name: String,
+}
+
+impl TtsAdapterWrapper {
+    /// Create new TTS adapter wrapper
+    pub async fn new(
+        runtime: &Arc<RwLock<WasmRuntime>>,
+        config: &ServiceAdapterConfig,
+        data_dir: &Path,
+        service_name: &str,
+        options: AdapterLoadOptions,
+    ) -> Result<Self, ServiceError> {
+        let manifest = load_wasm_adapter(runtime, config, data_dir, service_name, options).await?;
+
+        Ok(TtsAdapterWrapper {
+            runtime: runtime.clone(),
+            manifest,
+            provider: config.provider.clone(),
+            version: config.version.clone(),
+            service_name: service_name.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl AdapterService for TtsAdapterWrapper {
+    fn service_name(&self) -> &'static str {
+        "tts"
+    }
+
+    fn provider_name(&self) -> &str {
+        &self.provider
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn manifest(&self) -> Option<&AdapterManifest> {
+        Some(&self.manifest)
+    }
+
+    fn is_ready(&self) -> bool {
+        // TODO: Check actual WASM instance readiness
+        true
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        // The runtime handles instance cleanup
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TtsAdapter for TtsAdapterWrapper {
+    async fn synthesize(
+        &mut self,
+        text: &str,
+        voice: Option<&str>,
+    ) -> Result<Speech, ServiceError> {
+        let pool = self
+            .runtime
+            .read()
+            .await
+            .get_pool(&self.service_name, &self.provider);
+
+        if let Some(pool) = pool {
+            let instance = pool.checkout().await?;
+            if !instance.is_ready() {
+                return Err(ServiceError::ServiceUnavailable(
+                    "TTS adapter not ready".to_string(),
+                ));
+            }
+
+            // TODO: Pass the request to `prepare-request` and the provider's
+            // answer to `parse-response` via WIT bindings
+            tracing::debug!(
+                "Synthesizing {} bytes of text with voice {:?}",
+                text.len(),
+                voice
+            );
+            Err(instance.unbound_export("prepare-request"))
+        } else {
+            Err(ServiceError::ServiceUnavailable(
+                "TTS adapter instance not found".to_string(),
+            ))
+        }
+    }
+}
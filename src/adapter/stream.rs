@@ -0,0 +1,79 @@
+use crate::adapter::services::SharedLlm;
+use crate::adapter::traits::{ChatMessage, Finish, GenerationOptions, ServiceError};
+use futures::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Reply of an LLM adapter, chunk by chunk as the adapter produces them
+///
+/// Yields the chunks in order, then an error if generation failed after
+/// (or before) them. Dropping the stream cancels generation and the
+/// upstream request, like a client disconnecting from a streamed response.
+pub struct ChunkStream {
+    chunks: mpsc::Receiver<String>,
+    generation: Option<JoinHandle<Result<Finish, ServiceError>>>,
+}
+
+/// Stream the reply of `llm` to `messages` outside of an HTTP request
+///
+/// Generation runs in its own task holding the adapter, feeding a channel
+/// of `buffer` chunks, so a consumer reading slower than the adapter
+/// holds generation back.
+pub fn stream_reply(
+    llm: SharedLlm,
+    messages: Vec<ChatMessage>,
+    options: GenerationOptions,
+    buffer: usize,
+) -> ChunkStream {
+    let (chunks, receiver) = mpsc::channel(buffer.max(1));
+    let generation = tokio::spawn(async move {
+        let mut llm = llm.write().await;
+        llm.stream_message(&messages, &options, chunks).await
+    });
+
+    ChunkStream {
+        chunks: receiver,
+        generation: Some(generation),
+    }
+}
+
+impl Stream for ChunkStream {
+    type Item = Result<String, ServiceError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // The channel closes once the adapter is done sending
+        if let Some(chunk) = ready!(this.chunks.poll_recv(cx)) {
+            return Poll::Ready(Some(Ok(chunk)));
+        }
+
+        let Some(generation) = this.generation.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let result = ready!(Pin::new(generation).poll(cx));
+        this.generation = None;
+
+        Poll::Ready(match result {
+            Ok(Ok(_)) => None,
+            Ok(Err(error)) => Some(Err(error)),
+            Err(error) => Some(Err(ServiceError::ExecutionError(format!(
+                "Reply generation failed: {}",
+                error
+            )))),
+        })
+    }
+}
+
+impl Drop for ChunkStream {
+    fn drop(&mut self) {
+        // Dropping the receiver already asks the adapter to stop; aborting
+        // also covers adapters still waiting for the lock
+        if let Some(generation) = &self.generation {
+            generation.abort();
+        }
+    }
+}
@@ -1,7 +1,97 @@
 #[cfg(test)]
 mod adapter_tests {
-    use crate::adapter::traits::ModelInfo;
-    use crate::adapter::{AdapterRegistry, ServiceError, WasmRuntime};
+    use crate::adapter::balancer::EndpointBalancer;
+    use crate::adapter::breaker::{BreakerState, CircuitBreaker};
+    use crate::adapter::encryption::EncryptedValues;
+    use crate::adapter::http::{
+        HttpClientSettings, ProviderRequest, ResponseSizeLimit, build_client, send, shared_client,
+    };
+    use crate::adapter::keys::{EncodedKeys, KeyCodec, migrate_keys};
+    use crate::adapter::limiter::ConcurrencyLimiter;
+    use crate::adapter::manifest::AdapterManifest;
+    use crate::adapter::runtime::{InstancePool, ModuleLoader, WasmInstance};
+    use crate::adapter::services::SharedCrypto;
+    use crate::adapter::services::aes::AesGcmCrypto;
+    use crate::adapter::services::fallback::FallbackLlm;
+    use crate::adapter::services::llm::{ChatRequest, DeclaredModelInfo};
+    use crate::adapter::services::sqlite::SqliteStorage;
+    use crate::adapter::stream::stream_reply;
+    use crate::adapter::traits::StorageAdapter;
+    use crate::adapter::traits::{
+        Capabilities, ChatMessage, ContentPart, Finish, FinishReason, GenerationOptions, ImageSize,
+        KeyPage, LlmAdapter, ModelInfo, until_closed,
+    };
+    use crate::adapter::{AdapterRegistry, AdapterService, ServiceError, WasmRuntime};
+    use crate::config::defaults::{
+        DEFAULT_ADAPTER_MAX_RESPONSE_BYTES, MAX_ADAPTER_REQUEST_HEADER_BYTES,
+        MAX_ADAPTER_REQUEST_HEADERS,
+    };
+    use crate::config::schema::{
+        AdapterConfig, BalanceStrategy, CircuitBreakerConfig, EndpointConfig, ServiceAdapterConfig,
+    };
+    use crate::routes::test_support::{FnLlm, MemoryStorage};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use tokio::sync::mpsc;
+
+    /// Sets its flag when dropped, to observe cancelled futures
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// LLM adapter whose upstream request never finishes
+    struct SlowLlm {
+        cancelled: Arc<AtomicBool>,
+        completed: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl AdapterService for SlowLlm {
+        fn service_name(&self) -> &'static str {
+            "llm"
+        }
+
+        fn provider_name(&self) -> &str {
+            "slow"
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl LlmAdapter for SlowLlm {
+        async fn send_message(
+            &mut self,
+            _messages: &[ChatMessage],
+            _options: &GenerationOptions,
+        ) -> Result<String, ServiceError> {
+            let _guard = DropFlag(self.cancelled.clone());
+            std::future::pending::<()>().await;
+            self.completed.store(true, Ordering::SeqCst);
+            Ok("done".to_string())
+        }
+
+        async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+            Err(ServiceError::ServiceUnavailable("test".to_string()))
+        }
+    }
 
     #[tokio::test]
     async fn test_adapter_registry_creation() {
@@ -15,6 +105,116 @@ mod adapter_tests {
         assert!(runtime.is_ok());
     }
 
+    /// Ready instance of an empty component for `provider`
+    async fn empty_instance(runtime: &WasmRuntime, provider: &str) -> WasmInstance {
+        let component = wasmtime::component::Component::new(runtime.engine(), "(component)")
+            .expect("empty component should compile");
+        let mut instance = WasmInstance::new(
+            runtime.engine(),
+            component,
+            "llm".to_string(),
+            provider.to_string(),
+            "1.0.0".to_string(),
+            "{}".to_string(),
+        )
+        .unwrap();
+        instance.initialize().await.unwrap();
+        instance
+    }
+
+    #[tokio::test]
+    async fn test_function_call_reports_consumed_fuel_once() {
+        let runtime = WasmRuntime::new().unwrap();
+        let mut instance = empty_instance(&runtime, "ollama").await;
+        assert_eq!(instance.take_fuel_consumed(), None);
+
+        instance.call_function("generate", b"{}").await.unwrap();
+
+        let consumed = instance.take_fuel_consumed().unwrap();
+        assert!(consumed <= crate::adapter::runtime::instance::CALL_FUEL);
+        assert_eq!(instance.take_fuel_consumed(), None);
+    }
+
+    #[tokio::test]
+    async fn test_providers_do_not_block_each_other() {
+        let mut runtime = WasmRuntime::new().unwrap();
+        let ollama = empty_instance(&runtime, "ollama").await;
+        let openai = empty_instance(&runtime, "openai").await;
+        runtime.add_instance("llm", ollama, 1);
+        runtime.add_instance("llm", openai, 1);
+        let runtime = Arc::new(tokio::sync::RwLock::new(runtime));
+
+        // Simulate a long call holding the ollama instance
+        let ollama = runtime.read().await.get_pool("llm", "ollama").unwrap();
+        let _busy = ollama.checkout().await.unwrap();
+
+        // Lookups and calls on another provider proceed meanwhile
+        let openai = tokio::time::timeout(std::time::Duration::from_millis(100), async {
+            let openai = runtime.read().await.get_pool("llm", "openai").unwrap();
+            let instance = openai.checkout().await.unwrap();
+            instance.provider_name().to_string()
+        })
+        .await
+        .expect("openai should not wait for ollama");
+        assert_eq!(openai, "openai");
+
+        // The runtime map itself isn't held by the busy call
+        assert!(runtime.try_write().is_ok());
+
+        // While the busy instance still blocks callers of the same provider
+        let waiting =
+            tokio::time::timeout(std::time::Duration::from_millis(10), ollama.checkout()).await;
+        assert!(waiting.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_instance_pool_grows_lazily_up_to_max() {
+        let runtime = WasmRuntime::new().unwrap();
+        let pool = Arc::new(InstancePool::new(
+            empty_instance(&runtime, "ollama").await,
+            2,
+        ));
+        assert_eq!(pool.size(), 1);
+        assert_eq!(pool.max_size(), 2);
+
+        // Sequential calls reuse the seed instance
+        drop(pool.checkout().await.unwrap());
+        drop(pool.checkout().await.unwrap());
+        assert_eq!(pool.size(), 1);
+
+        // Concurrent calls get their own instances, up to the cap
+        let first = pool.checkout().await.unwrap();
+        let second = pool.checkout().await.unwrap();
+        assert_eq!(pool.size(), 2);
+        assert!(first.is_ready() && second.is_ready());
+
+        let waiting =
+            tokio::time::timeout(std::time::Duration::from_millis(10), pool.checkout()).await;
+        assert!(waiting.is_err());
+
+        // Returned instances are reused rather than recreated
+        drop(first);
+        let third = pool.checkout().await.unwrap();
+        assert_eq!(third.provider_name(), "ollama");
+        assert_eq!(pool.size(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_instance_pool_shutdown_rejects_checkouts() {
+        let runtime = WasmRuntime::new().unwrap();
+        let pool = Arc::new(InstancePool::new(
+            empty_instance(&runtime, "ollama").await,
+            1,
+        ));
+
+        pool.shutdown().await.unwrap();
+
+        assert!(matches!(
+            pool.checkout().await,
+            Err(ServiceError::ServiceUnavailable(_))
+        ));
+    }
+
     #[test]
     fn test_service_error_types() {
         let error = ServiceError::InitializationFailed("test".to_string());
@@ -24,6 +224,38 @@ mod adapter_tests {
         assert!(error.to_string().contains("exec"));
     }
 
+    #[test]
+    fn test_service_error_from_wasm_trap() {
+        let error = wasmtime::Error::new(wasmtime::Trap::OutOfFuel);
+
+        let service_error = ServiceError::from_wasm_error(error, "prepare-request", Some(0));
+
+        match &service_error {
+            ServiceError::WasmTrap {
+                function,
+                remaining_fuel,
+                trap,
+            } => {
+                assert_eq!(function, "prepare-request");
+                assert_eq!(*remaining_fuel, Some(0));
+                assert_eq!(*trap, wasmtime::Trap::OutOfFuel);
+            }
+            other => panic!("Expected WasmTrap, got {:?}", other),
+        }
+        assert!(service_error.to_string().contains("prepare-request"));
+    }
+
+    #[test]
+    fn test_service_error_from_wasm_non_trap() {
+        let error = wasmtime::Error::msg("type mismatch");
+
+        let service_error = ServiceError::from_wasm_error(error, "parse-response", None);
+
+        assert!(matches!(service_error, ServiceError::ExecutionError(_)));
+        assert!(service_error.to_string().contains("type mismatch"));
+        assert!(service_error.to_string().contains("parse-response"));
+    }
+
     #[test]
     fn test_model_info_display() {
         let model_info = ModelInfo {
@@ -48,4 +280,2504 @@ mod adapter_tests {
         assert!(registry.get_llm_adapter("ollama").is_none());
         assert!(registry.get_storage_adapter("json").is_none());
     }
+
+    #[tokio::test]
+    async fn test_registry_rejects_missing_required_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters]
+fail_fast = true
+
+[adapters.llm]
+provider = "openai"
+version = "1.0.0"
+config = { model = "gpt-4o" }
+"#,
+        )
+        .unwrap();
+        let module_path = config
+            .adapters
+            .get_service("llm")
+            .unwrap()
+            .module_path(temp_dir.path(), "llm");
+        std::fs::create_dir_all(module_path.parent().unwrap()).unwrap();
+        std::fs::write(
+            AdapterManifest::path_for_module(&module_path),
+            "required_config = [\"api_key\"]\n",
+        )
+        .unwrap();
+
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        let error = registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap_err();
+
+        // Rejected before the (missing) module is read
+        assert!(matches!(error, ServiceError::InvalidConfig(_)));
+        assert!(error.to_string().contains("requires `api_key`"));
+    }
+
+    #[tokio::test]
+    async fn test_registry_reports_failed_adapters() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "openai"
+version = "1.0.0"
+
+[adapters.storage]
+provider = "memory"
+"#,
+        )
+        .unwrap();
+
+        // The missing LLM module doesn't keep storage from loading
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+
+        let storage = registry.storage_adapter_for(&config).unwrap().clone();
+        storage.write().await.store("key", b"data").await.unwrap();
+        assert_eq!(storage.read().await.retrieve("key").await.unwrap(), b"data");
+        assert!(registry.llm_adapter_for(&config).is_none());
+
+        let failure = &registry.failed_adapters()["llm"];
+        assert_eq!(failure.provider, "openai");
+        assert_eq!(
+            failure.attempted_path,
+            Some(
+                temp_dir
+                    .path()
+                    .join("adapters/llm/openai/1.0.0/adapter.wasm")
+            )
+        );
+        let adapters = registry.list_adapters().await;
+        assert_eq!(adapters.len(), 2);
+        let (service, provider, version, status) = &adapters[1];
+        assert_eq!(
+            (service.as_str(), provider.as_str(), version.as_str()),
+            ("llm", "openai", "1.0.0")
+        );
+        assert_eq!(status, &format!("failed: {}", failure.error));
+
+        // With fail_fast, the same config doesn't load at all
+        let mut config = config;
+        config.adapters.fail_fast = true;
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        assert!(
+            registry
+                .initialize_from_config(&config, temp_dir.path())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registry_keeps_manifests_per_service() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "local"
+version = "1.0.0"
+
+[adapters.storage]
+provider = "local"
+version = "1.0.0"
+"#,
+        )
+        .unwrap();
+        for (service, capabilities) in [("llm", "[\"images\"]"), ("storage", "[]")] {
+            let module_path = config
+                .adapters
+                .get_service(service)
+                .unwrap()
+                .module_path(temp_dir.path(), service);
+            std::fs::create_dir_all(module_path.parent().unwrap()).unwrap();
+            std::fs::write(&module_path, "(component)").unwrap();
+            std::fs::write(
+                AdapterManifest::path_for_module(&module_path),
+                format!("capabilities = {capabilities}\n"),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            crate::adapter::services::shared_providers(&config),
+            vec![(
+                "local".to_string(),
+                vec!["llm".to_string(), "storage".to_string()]
+            )]
+        );
+
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+
+        let llm = registry.get_manifest("llm", "local").unwrap();
+        assert_eq!(llm.capabilities, vec!["images".to_string()]);
+        let storage = registry.get_manifest("storage", "local").unwrap();
+        assert!(storage.capabilities.is_empty());
+        assert!(registry.get_llm_adapter("local").is_some());
+        assert!(registry.get_storage_adapter("local").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_registry_loads_stt_adapter() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters.stt]
+provider = "whisper"
+version = "1.0.0"
+"#,
+        )
+        .unwrap();
+        let module_path = config
+            .adapters
+            .get_service("stt")
+            .unwrap()
+            .module_path(temp_dir.path(), "stt");
+        std::fs::create_dir_all(module_path.parent().unwrap()).unwrap();
+        std::fs::write(&module_path, "(component)").unwrap();
+        std::fs::write(
+            AdapterManifest::path_for_module(&module_path),
+            "capabilities = []\n",
+        )
+        .unwrap();
+
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+
+        assert!(registry.get_manifest("stt", "whisper").is_some());
+        let stt = registry.stt_adapter_for(&config).unwrap().clone();
+        {
+            let stt = stt.read().await;
+            assert_eq!(stt.service_name(), "stt");
+            assert_eq!(stt.provider_name(), "whisper");
+            assert_eq!(stt.version(), "1.0.0");
+        }
+        // The instance is there, but can't be called without WIT bindings yet
+        let error = stt
+            .write()
+            .await
+            .transcribe(b"RIFF", "audio/wav")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ServiceError::NotImplemented(_)));
+
+        let adapters = registry.list_adapters().await;
+        assert_eq!(
+            adapters,
+            [(
+                "stt".to_string(),
+                "whisper".to_string(),
+                "1.0.0".to_string(),
+                "ready".to_string()
+            )]
+        );
+        assert!(registry.shutdown().await.is_ok());
+        assert!(registry.get_default_stt_adapter().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_registry_loads_image_adapter() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters.image]
+provider = "openai-images"
+version = "1.0.0"
+"#,
+        )
+        .unwrap();
+        let module_path = config
+            .adapters
+            .get_service("image")
+            .unwrap()
+            .module_path(temp_dir.path(), "image");
+        std::fs::create_dir_all(module_path.parent().unwrap()).unwrap();
+        std::fs::write(&module_path, "(component)").unwrap();
+        std::fs::write(
+            AdapterManifest::path_for_module(&module_path),
+            "capabilities = []\n",
+        )
+        .unwrap();
+
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+
+        assert!(registry.get_manifest("image", "openai-images").is_some());
+        let image = registry.image_adapter_for(&config).unwrap().clone();
+        {
+            let image = image.read().await;
+            assert_eq!(image.service_name(), "image");
+            assert_eq!(image.provider_name(), "openai-images");
+            assert_eq!(image.version(), "1.0.0");
+        }
+        // The instance is there, but can't be called without WIT bindings yet
+        let error = image
+            .write()
+            .await
+            .generate("A red fox", ImageSize::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ServiceError::NotImplemented(_)));
+
+        let adapters = registry.list_adapters().await;
+        assert_eq!(
+            adapters,
+            [(
+                "image".to_string(),
+                "openai-images".to_string(),
+                "1.0.0".to_string(),
+                "ready".to_string()
+            )]
+        );
+        assert!(registry.shutdown().await.is_ok());
+        assert!(registry.get_default_image_adapter().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wasm_llm_not_implemented() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "ollama"
+version = "1.0.0"
+"#,
+        )
+        .unwrap();
+        let module_path = config
+            .adapters
+            .get_service("llm")
+            .unwrap()
+            .module_path(temp_dir.path(), "llm");
+        std::fs::create_dir_all(module_path.parent().unwrap()).unwrap();
+        std::fs::write(&module_path, "(component)").unwrap();
+        std::fs::write(
+            AdapterManifest::path_for_module(&module_path),
+            "capabilities = []\n",
+        )
+        .unwrap();
+
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+        let llm = registry.llm_adapter_for(&config).unwrap().clone();
+        let messages = [ChatMessage {
+            role: "user".to_string(),
+            content: "Hello".to_string(),
+            parts: None,
+        }];
+        let options = GenerationOptions {
+            seed: Some(42),
+            stop: Some(vec!["END".to_string()]),
+            ..Default::default()
+        };
+        let mut llm = llm.write().await;
+
+        // Without bindings the request can't reach the adapter, so it isn't
+        // answered without its options
+        let error = llm.complete(&messages, &options).await.unwrap_err();
+        assert!(matches!(error, ServiceError::NotImplemented(_)));
+
+        let (chunks, mut receiver) = mpsc::channel(1);
+        let error = llm
+            .stream_message(&messages, &options, chunks)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ServiceError::NotImplemented(_)));
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_registry_loads_mock_llm() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "mock"
+
+[adapters.llm.config]
+responses = ["Scripted reply"]
+"#,
+        )
+        .unwrap();
+
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+
+        // Built in: no module or manifest is looked for
+        assert!(registry.get_manifest("llm", "mock").is_none());
+        assert!(registry.failed_adapters().is_empty());
+        let llm = registry.llm_adapter_for(&config).unwrap().clone();
+        let messages = [ChatMessage {
+            role: "user".to_string(),
+            content: "Hello".to_string(),
+            parts: None,
+        }];
+        let mut llm = llm.write().await;
+        assert_eq!(llm.provider_name(), "mock");
+        assert!(llm.capabilities().streaming);
+        for expected in ["Scripted reply", "Hello"] {
+            let reply = llm
+                .send_message(&messages, &GenerationOptions::default())
+                .await
+                .unwrap();
+            assert_eq!(reply, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_encrypts_storage() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[storage]
+encrypt = true
+
+[adapters.crypto]
+provider = "aes-gcm"
+
+[adapters.crypto.config]
+key = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+
+[adapters.storage]
+provider = "memory"
+"#,
+        )
+        .unwrap();
+
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+        assert!(registry.failed_adapters().is_empty());
+        assert!(registry.get_manifest("crypto", "aes-gcm").is_none());
+
+        let crypto = registry.crypto_adapter_for(&config).unwrap().clone();
+        let storage = registry.storage_adapter_for(&config).unwrap().clone();
+        let mut storage = storage.write().await;
+        storage.store("conversation/a", b"hallo").await.unwrap();
+        assert_eq!(storage.retrieve("conversation/a").await.unwrap(), b"hallo");
+
+        let sealed = crypto
+            .read()
+            .await
+            .encrypt(b"conversation/a", b"hallo")
+            .await
+            .unwrap();
+        assert_eq!(
+            crypto
+                .read()
+                .await
+                .decrypt(b"conversation/a", &sealed)
+                .await
+                .unwrap(),
+            b"hallo"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registry_rejects_wasm_crypto_adapters() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[storage]
+encrypt = true
+
+[adapters.crypto]
+provider = "vault"
+
+[adapters.storage]
+provider = "memory"
+"#,
+        )
+        .unwrap();
+
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+
+        // Rejected up front rather than failing on every stored value
+        assert!(registry.crypto_adapter_for(&config).is_none());
+        assert!(registry.storage_adapter_for(&config).is_none());
+        let failure = &registry.failed_adapters()["crypto"];
+        assert!(failure.error.contains("'vault'"), "{}", failure.error);
+    }
+
+    #[tokio::test]
+    async fn test_registry_rejects_encryption_without_crypto() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[storage]
+encrypt = true
+
+[adapters.storage]
+provider = "memory"
+"#,
+        )
+        .unwrap();
+
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry
+            .initialize_from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+
+        // Storage isn't loaded rather than storing plaintext
+        assert!(registry.storage_adapter_for(&config).is_none());
+        let failure = &registry.failed_adapters()["storage"];
+        assert!(
+            failure.error.contains("[adapters.crypto]"),
+            "{}",
+            failure.error
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registry_native_adapters() {
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry.register_llm_adapter("echo", FnLlm::new("echo", |_| "Hi".to_string()));
+        registry.register_storage_adapter("memory", MemoryStorage::default());
+
+        let llm = registry.get_llm_adapter("echo").unwrap().clone();
+        let reply = llm
+            .write()
+            .await
+            .send_message(&[], &GenerationOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(reply, "Hi");
+
+        // Registered storage gets encoded keys like configured storage
+        let storage = registry.get_storage_adapter("memory").unwrap().clone();
+        storage.write().await.store("a b", b"data").await.unwrap();
+        assert_eq!(
+            storage.read().await.list_keys(None).await.unwrap(),
+            vec!["a b".to_string()]
+        );
+
+        let mut adapters: Vec<String> = registry
+            .list_adapters()
+            .await
+            .into_iter()
+            .map(|(service, provider, version, status)| {
+                format!("{service} {provider} {version} {status}")
+            })
+            .collect();
+        adapters.sort();
+        assert_eq!(
+            adapters,
+            [
+                "llm echo test ready".to_string(),
+                format!("storage memory {} ready", env!("CARGO_PKG_VERSION"))
+            ]
+        );
+
+        assert!(registry.shutdown().await.is_ok());
+        assert!(registry.get_default_llm_adapter().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_registry_adapter_for_config() {
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry.register_llm_adapter("echo", FnLlm::new("echo", |_| String::new()));
+
+        // Falls back to the registered adapter when the configured one is missing
+        let config = crate::config::Config::default();
+        let llm = registry.llm_adapter_for(&config).unwrap();
+        assert_eq!(llm.read().await.provider_name(), "echo");
+        assert!(registry.storage_adapter_for(&config).is_none());
+
+        registry.register_llm_adapter("ollama", FnLlm::new("ollama", |_| String::new()));
+        let llm = registry.llm_adapter_for(&config).unwrap();
+        assert_eq!(llm.read().await.provider_name(), "ollama");
+    }
+
+    #[tokio::test]
+    async fn test_registry_default_adapter() {
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry.register_llm_adapter("zeta", FnLlm::new("zeta", |_| String::new()));
+        registry.register_llm_adapter("alpha", FnLlm::new("alpha", |_| String::new()));
+        // Replacing an adapter keeps its position
+        registry.register_llm_adapter("zeta", FnLlm::new("zeta", |_| String::new()));
+
+        // Without a default, the first registered wins on every run
+        let llm = registry.get_default_llm_adapter().unwrap();
+        assert_eq!(llm.read().await.provider_name(), "zeta");
+
+        registry.set_default_llm_adapter("alpha").unwrap();
+        let llm = registry.get_default_llm_adapter().unwrap();
+        assert_eq!(llm.read().await.provider_name(), "alpha");
+
+        let error = registry.set_default_llm_adapter("ollama").unwrap_err();
+        assert!(matches!(error, ServiceError::InvalidConfig(_)));
+        assert!(
+            registry
+                .set_default_storage_adapter("memory")
+                .unwrap_err()
+                .to_string()
+                .contains("storage provider 'memory'")
+        );
+
+        let providers: Vec<String> = registry
+            .list_adapters()
+            .await
+            .into_iter()
+            .map(|(_, provider, _, _)| provider)
+            .collect();
+        assert_eq!(providers, ["zeta", "alpha"]);
+    }
+
+    #[tokio::test]
+    async fn test_registry_configured_default_provider() {
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry.register_llm_adapter("zeta", FnLlm::new("zeta", |_| String::new()));
+        registry.register_llm_adapter("alpha", FnLlm::new("alpha", |_| String::new()));
+
+        // The configured `ollama` isn't registered, so the default applies
+        let mut config = crate::config::Config::default();
+        config
+            .adapters
+            .services
+            .get_mut("llm")
+            .unwrap()
+            .default_provider = Some("alpha".to_string());
+        registry.check_default_providers(&config).unwrap();
+        let llm = registry.llm_adapter_for(&config).unwrap();
+        assert_eq!(llm.read().await.provider_name(), "alpha");
+
+        config
+            .adapters
+            .services
+            .get_mut("llm")
+            .unwrap()
+            .default_provider = Some("beta".to_string());
+        let error = registry.check_default_providers(&config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration: adapters.llm.default_provider 'beta' isn't a registered LLM provider"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_until_closed_completes() {
+        let (tx, _rx) = mpsc::channel::<String>(1);
+
+        assert_eq!(until_closed(&tx, async { 42 }).await, Some(42));
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_cancelled_on_receiver_drop() {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let completed = Arc::new(AtomicBool::new(false));
+        let mut adapter = SlowLlm {
+            cancelled: cancelled.clone(),
+            completed: completed.clone(),
+        };
+
+        let (tx, rx) = mpsc::channel(1);
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: "hello".to_string(),
+            parts: None,
+        }];
+        let stream = tokio::spawn(async move {
+            adapter
+                .stream_message(&messages, &GenerationOptions::default(), tx)
+                .await
+        });
+
+        // Client disconnects mid-request
+        tokio::task::yield_now().await;
+        drop(rx);
+
+        let result = tokio::time::timeout(std::time::Duration::from_secs(5), stream)
+            .await
+            .expect("stream should stop once the receiver is dropped")
+            .unwrap();
+
+        assert!(result.is_ok());
+        assert!(cancelled.load(Ordering::SeqCst));
+        assert!(!completed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_stream_reply_yields_chunks() {
+        let llm = Arc::new(tokio::sync::RwLock::new(FnLlm::new("echo", |messages| {
+            format!("echo: {}", messages[0].content)
+        })));
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: "hello".to_string(),
+            parts: None,
+        }];
+
+        let chunks: Vec<_> = stream_reply(llm, messages, GenerationOptions::default(), 4)
+            .collect()
+            .await;
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].as_ref().unwrap(), "echo: hello");
+    }
+
+    #[tokio::test]
+    async fn test_stream_reply_cancelled_on_drop() {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let completed = Arc::new(AtomicBool::new(false));
+        let llm = Arc::new(tokio::sync::RwLock::new(SlowLlm {
+            cancelled: cancelled.clone(),
+            completed: completed.clone(),
+        }));
+
+        let stream = stream_reply(llm.clone(), Vec::new(), GenerationOptions::default(), 1);
+        tokio::task::yield_now().await;
+        drop(stream);
+
+        // The adapter is released once generation stopped
+        let released = tokio::time::timeout(std::time::Duration::from_secs(5), llm.write())
+            .await
+            .expect("generation should stop once the stream is dropped");
+        drop(released);
+
+        assert!(cancelled.load(Ordering::SeqCst));
+        assert!(!completed.load(Ordering::SeqCst));
+    }
+
+    /// Provider backend that succeeds or times out on demand, counting calls
+    struct MockBackend {
+        calls: AtomicUsize,
+    }
+
+    impl MockBackend {
+        fn new() -> Self {
+            MockBackend {
+                calls: AtomicUsize::new(0),
+            }
+        }
+
+        async fn reply(&self, fail: bool) -> Result<String, ServiceError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if fail {
+                Err(ServiceError::Timeout("provider didn't answer".to_string()))
+            } else {
+                Ok("hello".to_string())
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_after_consecutive_failures() {
+        let backend = MockBackend::new();
+        let breaker = CircuitBreaker::new("ollama", 3, std::time::Duration::from_secs(60));
+
+        // A success in between resets the count
+        for fail in [true, true, false, true, true] {
+            let _ = breaker.call(backend.reply(fail)).await;
+        }
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.consecutive_failures(), 2);
+
+        assert!(breaker.call(backend.reply(true)).await.is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert_eq!(backend.calls(), 6);
+
+        // Open: fails fast without reaching the provider
+        let error = breaker.call(backend.reply(false)).await.unwrap_err();
+        assert!(matches!(error, ServiceError::ServiceUnavailable(_)));
+        assert!(error.to_string().contains("Circuit open for ollama"));
+        assert_eq!(backend.calls(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_half_open_probe() {
+        let backend = MockBackend::new();
+        let cooldown = std::time::Duration::from_millis(20);
+        let breaker = CircuitBreaker::new("ollama", 1, cooldown);
+
+        let _ = breaker.call(backend.reply(true)).await;
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        // A failed probe opens the circuit again
+        tokio::time::sleep(cooldown * 2).await;
+        assert!(breaker.call(backend.reply(true)).await.is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert_eq!(backend.calls(), 2);
+        assert!(breaker.call(backend.reply(false)).await.is_err());
+        assert_eq!(backend.calls(), 2);
+
+        // A successful probe closes it
+        tokio::time::sleep(cooldown * 2).await;
+        assert_eq!(breaker.call(backend.reply(false)).await.unwrap(), "hello");
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.consecutive_failures(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_admits_one_probe_at_a_time() {
+        let cooldown = std::time::Duration::from_millis(20);
+        let breaker = CircuitBreaker::new("ollama", 1, cooldown);
+        let _ = breaker
+            .call(async { Err::<(), _>(ServiceError::Timeout("down".to_string())) })
+            .await;
+        tokio::time::sleep(cooldown * 2).await;
+
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let probe = breaker.call(async {
+            let _ = released.await;
+            Ok::<_, ServiceError>(())
+        });
+        let others = async {
+            tokio::task::yield_now().await;
+            let other = breaker.call(async { Ok::<_, ServiceError>(()) }).await;
+            assert!(other.unwrap_err().to_string().contains("half-open"));
+            assert_eq!(breaker.state(), BreakerState::HalfOpen);
+            release.send(()).unwrap();
+        };
+        let (probe, ()) = tokio::join!(probe, others);
+
+        assert!(probe.is_ok());
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_cancelled_probe_frees_slot() {
+        let cooldown = std::time::Duration::from_millis(20);
+        let breaker = CircuitBreaker::new("ollama", 1, cooldown);
+        let _ = breaker
+            .call(async { Err::<(), _>(ServiceError::Timeout("down".to_string())) })
+            .await;
+        tokio::time::sleep(cooldown * 2).await;
+
+        // The probe never finishes
+        let probe = breaker.call(std::future::pending::<Result<(), ServiceError>>());
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(10), probe)
+                .await
+                .is_err()
+        );
+
+        assert!(
+            breaker
+                .call(async { Ok::<_, ServiceError>(()) })
+                .await
+                .is_ok()
+        );
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_ignores_client_and_local_errors() {
+        let breaker = CircuitBreaker::new("ollama", 1, std::time::Duration::from_secs(60));
+
+        let _ = breaker
+            .call(async {
+                Err::<(), _>(ServiceError::ProviderError {
+                    status: 404,
+                    message: "model not found".to_string(),
+                    retry_after_secs: None,
+                })
+            })
+            .await;
+        let _ = breaker
+            .call(async {
+                Err::<(), _>(ServiceError::Overloaded {
+                    retry_after_secs: 1,
+                })
+            })
+            .await;
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        let _ = breaker
+            .call(async {
+                Err::<(), _>(ServiceError::ProviderError {
+                    status: 502,
+                    message: "bad gateway".to_string(),
+                    retry_after_secs: None,
+                })
+            })
+            .await;
+        assert_eq!(breaker.state(), BreakerState::Open);
+    }
+
+    #[test]
+    fn test_breaker_from_config() {
+        let mut config = crate::config::schema::CircuitBreakerConfig::default();
+        assert!(CircuitBreaker::from_config("ollama", &config).is_some());
+
+        config.failure_threshold = 0;
+        assert!(CircuitBreaker::from_config("ollama", &config).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_limiter_queues_then_rejects() {
+        let limiter = Arc::new(ConcurrencyLimiter::new("ollama", 1, 1));
+
+        let first = limiter.acquire().await.unwrap();
+        assert_eq!(limiter.in_flight(), 1);
+
+        // Second call waits in the queue
+        let waiting = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire().await.map(drop) })
+        };
+        while limiter.queued() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // Third call exceeds the queue bound
+        match limiter.acquire().await {
+            Err(ServiceError::Overloaded { retry_after_secs }) => assert!(retry_after_secs > 0),
+            other => panic!("Expected Overloaded, got {:?}", other.map(drop)),
+        }
+
+        // Releasing the slot lets the queued call through
+        drop(first);
+        waiting.await.unwrap().unwrap();
+        assert_eq!(limiter.queued(), 0);
+        assert_eq!(limiter.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_limiter_cancelled_waiter_leaves_queue() {
+        let limiter = ConcurrencyLimiter::new("ollama", 1, 1);
+        let _held = limiter.acquire().await.unwrap();
+
+        let result =
+            tokio::time::timeout(std::time::Duration::from_millis(10), limiter.acquire()).await;
+
+        assert!(result.is_err());
+        assert_eq!(limiter.queued(), 0);
+        assert_eq!(limiter.max_concurrent(), 1);
+    }
+
+    #[test]
+    fn test_http_client_settings_from_config() {
+        let config: crate::config::Config = toml::from_str(
+            "[http]\npool_idle_timeout_secs = 5\npool_max_idle_per_host = 0\ntcp_keepalive_secs = 1\n",
+        )
+        .unwrap();
+
+        let settings = HttpClientSettings::from_config(&config.http);
+        assert_eq!(
+            settings,
+            HttpClientSettings {
+                pool_idle_timeout: std::time::Duration::from_secs(5),
+                pool_max_idle_per_host: 0,
+                tcp_keepalive: std::time::Duration::from_secs(1),
+                ..HttpClientSettings::default()
+            }
+        );
+        assert!(build_client(&settings).is_ok());
+
+        // Without [http], the shared client gets the defaults
+        let defaults = crate::config::Config::default();
+        assert_eq!(
+            HttpClientSettings::from_config(&defaults.http),
+            HttpClientSettings::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_client_honors_timeout() {
+        // Server that accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                connections.push(socket);
+            }
+        });
+
+        let settings = HttpClientSettings {
+            timeout: std::time::Duration::from_millis(50),
+            ..HttpClientSettings::default()
+        };
+        let client = build_client(&settings).unwrap();
+        let error = client
+            .get(format!("http://{}/", addr))
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(error.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn test_send_reports_timeout() {
+        // Server that accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                connections.push(socket);
+            }
+        });
+
+        let client = build_client(&HttpClientSettings {
+            timeout: std::time::Duration::from_millis(50),
+            ..HttpClientSettings::default()
+        })
+        .unwrap();
+        let request = ProviderRequest {
+            body: String::new(),
+            headers: vec![],
+            url: format!("http://{}/api/chat", addr),
+        };
+        let error = send(&client, &request, DEFAULT_ADAPTER_MAX_RESPONSE_BYTES)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ServiceError::Timeout(_)));
+    }
+
+    /// Read one HTTP/1.1 request (head and Content-Length body) from `socket`
+    async fn read_request(
+        socket: &mut tokio::net::TcpStream,
+        buffer: &mut Vec<u8>,
+    ) -> Option<String> {
+        use tokio::io::AsyncReadExt;
+
+        loop {
+            if let Some(head_end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&buffer[..head_end]).to_lowercase();
+                let body_len = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map_or(0, |len| len.trim().parse::<usize>().unwrap());
+                let request_len = head_end + 4 + body_len;
+
+                if buffer.len() >= request_len {
+                    let request: Vec<u8> = buffer.drain(..request_len).collect();
+                    return Some(String::from_utf8(request).unwrap());
+                }
+            }
+
+            let mut chunk = [0u8; 1024];
+            match socket.read(&mut chunk).await {
+                Ok(0) | Err(_) => return None,
+                Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+
+    /// Server answering "ok" to every request, reporting each request and
+    /// the number of the connection it arrived on
+    async fn recording_server() -> (
+        std::net::SocketAddr,
+        mpsc::UnboundedReceiver<(usize, String)>,
+    ) {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut connection_id = 0;
+            while let Ok((mut socket, _)) = listener.accept().await {
+                connection_id += 1;
+                let requests_tx = requests_tx.clone();
+                tokio::spawn(async move {
+                    let mut buffer = Vec::new();
+                    while let Some(request) = read_request(&mut socket, &mut buffer).await {
+                        requests_tx.send((connection_id, request)).unwrap();
+                        socket
+                            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        (addr, requests_rx)
+    }
+
+    #[tokio::test]
+    async fn test_registries_share_one_client() {
+        let (addr, mut requests_rx) = recording_server().await;
+
+        // Adapters get clones of their registry's client
+        for _ in 0..2 {
+            let registry = AdapterRegistry::new().await.unwrap();
+            let response = registry
+                .http_client()
+                .get(format!("http://{}/api/tags", addr))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.text().await.unwrap(), "ok");
+        }
+
+        // A client per registry would have opened a second connection
+        let (first_connection, _) = requests_rx.recv().await.unwrap();
+        let (second_connection, _) = requests_rx.recv().await.unwrap();
+        assert_eq!(first_connection, second_connection);
+    }
+
+    #[tokio::test]
+    async fn test_shared_client_reuses_connection() {
+        let (addr, mut requests_rx) = recording_server().await;
+
+        let body = r#"{"model":"llama3.2","messages":[]}"#;
+        for _ in 0..2 {
+            let client = shared_client().unwrap();
+            let response = client
+                .post(format!("http://{}/api/chat", addr))
+                .header("authorization", "Bearer sk-test")
+                .header("content-type", "application/json")
+                .body(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.text().await.unwrap(), "ok");
+        }
+
+        let (first_connection, first) = requests_rx.recv().await.unwrap();
+        let (second_connection, second) = requests_rx.recv().await.unwrap();
+
+        // Both requests went over one pooled connection
+        assert_eq!(first_connection, second_connection);
+
+        // Headers and body are passed through unchanged
+        assert_eq!(first, second);
+        assert!(first.starts_with("POST /api/chat HTTP/1.1\r\n"));
+        assert!(first.contains("authorization: Bearer sk-test\r\n"));
+        assert!(first.contains("content-type: application/json\r\n"));
+        assert!(first.ends_with(&format!("\r\n\r\n{}", body)));
+    }
+
+    #[tokio::test]
+    async fn test_send_passes_request_and_error_response_through() {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, mut request_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = Vec::new();
+            let request = read_request(&mut socket, &mut buffer).await.unwrap();
+            request_tx.send(request).unwrap();
+            socket
+                .write_all(
+                    b"HTTP/1.1 404 Not Found\r\ncontent-length: 15\r\nx-request-id: 7\r\n\r\nmodel not found",
+                )
+                .await
+                .unwrap();
+        });
+
+        let request = ProviderRequest {
+            body: r#"{"model":"llama3.2"}"#.to_string(),
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            url: format!("http://{}/api/show", addr),
+        };
+        let response = send(
+            &shared_client().unwrap(),
+            &request,
+            DEFAULT_ADAPTER_MAX_RESPONSE_BYTES,
+        )
+        .await
+        .unwrap();
+
+        // Error statuses are for the adapter to interpret
+        assert_eq!(response.status_code, 404);
+        assert_eq!(response.body, "model not found");
+        assert!(
+            response
+                .headers
+                .contains(&("x-request-id".to_string(), "7".to_string()))
+        );
+
+        let received = request_rx.recv().await.unwrap();
+        assert!(received.starts_with("POST /api/show HTTP/1.1\r\n"));
+        assert!(received.contains("content-type: application/json\r\n"));
+        assert!(received.ends_with(r#"{"model":"llama3.2"}"#));
+    }
+
+    /// Server answering one request with `head`, then `chunks` copies of a 4 KiB chunk
+    ///
+    /// Stops writing once the client hangs up.
+    async fn serve_chunks(head: &'static str, chunks: usize) -> std::net::SocketAddr {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            read_request(&mut socket, &mut Vec::new()).await.unwrap();
+            if socket.write_all(head.as_bytes()).await.is_err() {
+                return;
+            }
+            let chunk = format!("1000\r\n{}\r\n", "x".repeat(4096));
+            for _ in 0..chunks {
+                if socket.write_all(chunk.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+            let _ = socket.write_all(b"0\r\n\r\n").await;
+        });
+        addr
+    }
+
+    fn provider_request(addr: std::net::SocketAddr) -> ProviderRequest {
+        ProviderRequest {
+            body: String::new(),
+            headers: vec![],
+            url: format!("http://{}/api/chat", addr),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_rejects_non_utf8_body() {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            read_request(&mut socket, &mut Vec::new()).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\nok\xff\xfe")
+                .await
+                .unwrap();
+        });
+
+        let error = send(
+            &shared_client().unwrap(),
+            &provider_request(addr),
+            DEFAULT_ADAPTER_MAX_RESPONSE_BYTES,
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(error, ServiceError::InvalidResponse(_)));
+        assert!(error.to_string().contains("not valid UTF-8"));
+        assert!(error.to_string().contains("offset 2"));
+    }
+
+    #[tokio::test]
+    async fn test_send_strips_byte_order_mark() {
+        let addr = serve_chunks("HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\n\u{feff}{}", 0).await;
+
+        let response = send(
+            &shared_client().unwrap(),
+            &provider_request(addr),
+            DEFAULT_ADAPTER_MAX_RESPONSE_BYTES,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.body, "{}");
+    }
+
+    #[tokio::test]
+    async fn test_send_aborts_oversized_stream() {
+        // 64 MiB if read to the end
+        let addr = serve_chunks(
+            "HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n",
+            16 * 1024,
+        )
+        .await;
+
+        let error = send(&shared_client().unwrap(), &provider_request(addr), 10_000)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            ServiceError::ResponseTooLarge { limit: 10_000 }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Provider response exceeds the limit of 10000 bytes"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_rejects_announced_oversized_body() {
+        let addr = serve_chunks("HTTP/1.1 200 OK\r\ncontent-length: 1000000000\r\n\r\n", 0).await;
+
+        let error = send(&shared_client().unwrap(), &provider_request(addr), 10_000)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ServiceError::ResponseTooLarge { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_send_reads_body_within_limit() {
+        let addr = serve_chunks("HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n", 2).await;
+
+        let response = send(&shared_client().unwrap(), &provider_request(addr), 8192)
+            .await
+            .unwrap();
+
+        assert_eq!(response.body.len(), 8192);
+    }
+
+    #[tokio::test]
+    async fn test_send_rejects_excessive_headers() {
+        // Nothing listens: the request is rejected before it is sent
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let header = |i: usize| (format!("x-header-{i}"), "value".to_string());
+
+        let mut request = provider_request(addr);
+        request.headers = (0..MAX_ADAPTER_REQUEST_HEADERS + 1).map(header).collect();
+        let error = send(&shared_client().unwrap(), &request, 1024)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ServiceError::ExecutionError(_)));
+        assert!(error.to_string().contains("request headers"));
+
+        request.headers = vec![(
+            "x-large".to_string(),
+            "v".repeat(MAX_ADAPTER_REQUEST_HEADER_BYTES),
+        )];
+        let error = send(&shared_client().unwrap(), &request, 1024)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("bytes of request headers"));
+    }
+
+    #[test]
+    fn test_response_size_limit_is_cumulative() {
+        let mut limit = ResponseSizeLimit::new(10);
+
+        assert!(limit.add(6).is_ok());
+        assert!(limit.add(4).is_ok());
+        assert!(matches!(
+            limit.add(1),
+            Err(ServiceError::ResponseTooLarge { limit: 10 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_reports_unreachable_provider() {
+        // Bind and drop to get a port nothing listens on
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let request = ProviderRequest {
+            body: String::new(),
+            headers: vec![],
+            url: format!("http://{}/api/show", addr),
+        };
+        let error = send(
+            &shared_client().unwrap(),
+            &request,
+            DEFAULT_ADAPTER_MAX_RESPONSE_BYTES,
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(error, ServiceError::ServiceUnavailable(_)));
+    }
+
+    #[test]
+    fn test_declared_model_info_from_config() {
+        let config: toml::Value = toml::from_str(
+            r#"
+default_model = "llama3.2"
+
+[model_info]
+context_length = 131072
+parameters = "3.2B"
+"#,
+        )
+        .unwrap();
+
+        let declared = DeclaredModelInfo::from_config(&config).unwrap();
+        assert_eq!(declared.context_length, Some(131072));
+        assert_eq!(declared.parameters.as_deref(), Some("3.2B"));
+        assert_eq!(declared.name, None);
+
+        // Absent table means nothing declared
+        let empty = toml::Value::Table(toml::Table::new());
+        assert_eq!(
+            DeclaredModelInfo::from_config(&empty).unwrap(),
+            DeclaredModelInfo::default()
+        );
+
+        // Typos are reported rather than silently ignored
+        let invalid: toml::Value = toml::from_str(
+            "[model_info]
+context = 1",
+        )
+        .unwrap();
+        assert!(matches!(
+            DeclaredModelInfo::from_config(&invalid),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_declared_model_info_only_fills_gaps() {
+        let declared = DeclaredModelInfo {
+            context_length: Some(4096),
+            name: Some("declared".to_string()),
+            parameters: Some("7B".to_string()),
+        };
+
+        let reported = ModelInfo {
+            name: "llama3.2:latest".to_string(),
+            version: "1.0.0".to_string(),
+            context_length: Some(131072),
+            parameters: None,
+        };
+        let info = declared.complete(Some(reported), "llama3.2", "1.0.0");
+
+        assert_eq!(info.name, "llama3.2:latest");
+        assert_eq!(info.context_length, Some(131072));
+        assert_eq!(info.parameters.as_deref(), Some("7B"));
+    }
+
+    #[test]
+    fn test_model_info_without_provider_or_declaration() {
+        let info = DeclaredModelInfo::default().complete(None, "llama3.2", "1.0.0");
+
+        // Nothing is made up
+        assert_eq!(info.name, "llama3.2");
+        assert_eq!(info.version, "1.0.0");
+        assert_eq!(info.context_length, None);
+        assert_eq!(info.parameters, None);
+    }
+
+    #[tokio::test]
+    async fn test_instance_model_info_exports_not_bound() {
+        let engine = wasmtime::Engine::default();
+        let component = wasmtime::component::Component::new(&engine, "(component)").unwrap();
+        let mut instance = WasmInstance::new(
+            &engine,
+            component,
+            "llm".to_string(),
+            "ollama".to_string(),
+            "1.0.0".to_string(),
+            "{}".to_string(),
+        )
+        .unwrap();
+
+        let error = instance
+            .prepare_model_info_request("llama3.2")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("prepare-model-info-request"));
+    }
+
+    #[test]
+    fn test_chat_request_forwards_sampling_options() {
+        let messages = [ChatMessage {
+            role: "user".to_string(),
+            content: "Hi".to_string(),
+            parts: None,
+        }];
+        let options = GenerationOptions {
+            max_tokens: Some(128),
+            seed: Some(42),
+            stop: Some(vec!["END".to_string()]),
+            temperature: Some(0.2),
+            top_p: Some(0.9),
+        };
+
+        let request = ChatRequest::new("llama3.2", &messages, &options);
+
+        assert_eq!(request.model, "llama3.2");
+        assert_eq!(request.messages, messages);
+        assert_eq!(request.seed, Some(42));
+        assert_eq!(request.stop, Some(vec!["END".to_string()]));
+        assert_eq!(request.temperature, Some(0.2));
+        assert_eq!(request.top_p, Some(0.9));
+        assert_eq!(request.max_completion_tokens, Some(128));
+        assert_eq!(request.provider_params, None);
+
+        // A negative seed means a random one, like no seed at all
+        let random = GenerationOptions {
+            seed: Some(-1),
+            ..Default::default()
+        };
+        assert_eq!(ChatRequest::new("m", &messages, &random).seed, None);
+    }
+
+    #[test]
+    fn test_chat_request_passes_content_parts() {
+        let image = ContentPart::ImageUrl {
+            url: "https://example.com/cat.png".to_string(),
+        };
+        let messages = [
+            ChatMessage {
+                role: "system".to_string(),
+                content: "Describe images.".to_string(),
+                parts: None,
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: "What's this?".to_string(),
+                parts: Some(vec![
+                    ContentPart::Text {
+                        text: "What's this?".to_string(),
+                    },
+                    image,
+                ]),
+            },
+        ];
+
+        let request = ChatRequest::new("llava", &messages, &GenerationOptions::default());
+
+        let params: serde_json::Value =
+            serde_json::from_str(&request.provider_params.unwrap()).unwrap();
+        assert_eq!(
+            params,
+            serde_json::json!({
+                "content_parts": [
+                    null,
+                    [
+                        {"type": "text", "text": "What's this?"},
+                        {"type": "image_url", "url": "https://example.com/cat.png"}
+                    ]
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_generation_options_validation() {
+        let valid = GenerationOptions {
+            max_tokens: Some(256),
+            seed: Some(-1),
+            stop: Some(vec!["\n\n".to_string(); 4]),
+            temperature: Some(2.0),
+            top_p: Some(0.0),
+        };
+        assert!(valid.validate().is_ok());
+        assert!(GenerationOptions::default().validate().is_ok());
+        let largest_seed = GenerationOptions {
+            seed: Some(i64::from(u32::MAX)),
+            ..Default::default()
+        };
+        assert!(largest_seed.validate().is_ok());
+
+        let invalid = [
+            GenerationOptions {
+                temperature: Some(2.1),
+                ..Default::default()
+            },
+            GenerationOptions {
+                temperature: Some(-0.1),
+                ..Default::default()
+            },
+            GenerationOptions {
+                top_p: Some(1.5),
+                ..Default::default()
+            },
+            GenerationOptions {
+                max_tokens: Some(0),
+                ..Default::default()
+            },
+            GenerationOptions {
+                seed: Some(i64::from(u32::MAX) + 1),
+                ..Default::default()
+            },
+            GenerationOptions {
+                stop: Some(vec!["x".to_string(); 5]),
+                ..Default::default()
+            },
+            GenerationOptions {
+                stop: Some(vec![String::new()]),
+                ..Default::default()
+            },
+            GenerationOptions {
+                temperature: Some(f32::NAN),
+                ..Default::default()
+            },
+        ];
+        for options in invalid {
+            assert!(
+                options.validate().is_err(),
+                "{:?} should be invalid",
+                options
+            );
+        }
+    }
+
+    #[test]
+    fn test_generation_options_with_defaults() {
+        let defaults = GenerationOptions {
+            max_tokens: Some(512),
+            temperature: Some(0.7),
+            ..Default::default()
+        };
+        let request = GenerationOptions {
+            temperature: Some(0.2),
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        let effective = request.with_defaults(&defaults);
+
+        assert_eq!(effective.max_tokens, Some(512));
+        assert_eq!(effective.seed, Some(42));
+        assert_eq!(effective.temperature, Some(0.2));
+        assert_eq!(effective.top_p, None);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_storage_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("storage.sqlite3");
+        let mut storage = SqliteStorage::open(path.clone()).await.unwrap();
+
+        assert!(!storage.exists("sender/default/profile").await.unwrap());
+        assert!(storage.retrieve("sender/default/profile").await.is_err());
+
+        storage
+            .store("sender/default/profile", b"v1")
+            .await
+            .unwrap();
+        storage
+            .store("sender/default/profile", b"v2")
+            .await
+            .unwrap();
+        storage.store("sender/work/profile", b"work").await.unwrap();
+        storage.store("conversation/abc", b"").await.unwrap();
+
+        assert!(storage.exists("sender/default/profile").await.unwrap());
+        assert_eq!(
+            storage.retrieve("sender/default/profile").await.unwrap(),
+            b"v2"
+        );
+        assert_eq!(storage.retrieve("conversation/abc").await.unwrap(), b"");
+
+        assert_eq!(
+            storage.list_keys(Some("sender/")).await.unwrap(),
+            vec!["sender/default/profile", "sender/work/profile"]
+        );
+        assert_eq!(storage.list_keys(None).await.unwrap().len(), 3);
+
+        storage.delete("sender/work/profile").await.unwrap();
+        assert!(!storage.exists("sender/work/profile").await.unwrap());
+        assert_eq!(
+            storage.list_keys(Some("sender/")).await.unwrap(),
+            vec!["sender/default/profile"]
+        );
+
+        // Data survives reopening the file
+        drop(storage);
+        let storage = SqliteStorage::open(path).await.unwrap();
+        assert_eq!(
+            storage.retrieve("sender/default/profile").await.unwrap(),
+            b"v2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_list_keys_prefix_is_literal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut storage = SqliteStorage::open(temp_dir.path().join("storage.sqlite3"))
+            .await
+            .unwrap();
+
+        storage.store("a_b/1", b"").await.unwrap();
+        storage.store("axb/1", b"").await.unwrap();
+        storage.store("100%/1", b"").await.unwrap();
+        storage.store("1000/1", b"").await.unwrap();
+        storage.store("A_b/1", b"").await.unwrap();
+
+        assert_eq!(storage.list_keys(Some("a_b")).await.unwrap(), vec!["a_b/1"]);
+        assert_eq!(
+            storage.list_keys(Some("100%")).await.unwrap(),
+            vec!["100%/1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_list_keys_paginated() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut storage = SqliteStorage::open(temp_dir.path().join("storage.sqlite3"))
+            .await
+            .unwrap();
+
+        for key in ["conv/c", "conv/a", "conv/e", "conv/b", "conv/d", "sender/x"] {
+            storage.store(key, b"").await.unwrap();
+        }
+
+        let mut pages = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = storage
+                .list_keys_paginated(Some("conv/"), cursor.as_deref(), 2)
+                .await
+                .unwrap();
+            pages.push(page.keys);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        assert_eq!(
+            pages,
+            vec![
+                vec!["conv/a", "conv/b"],
+                vec!["conv/c", "conv/d"],
+                vec!["conv/e"]
+            ]
+        );
+
+        // A page that ends exactly at the last key has no cursor
+        let page = storage
+            .list_keys_paginated(Some("conv/"), None, 5)
+            .await
+            .unwrap();
+        assert_eq!(page.keys.len(), 5);
+        assert_eq!(page.next_cursor, None);
+
+        // Limits beyond what SQLite takes still return every key
+        for limit in [usize::MAX, i64::MAX as usize] {
+            let page = storage
+                .list_keys_paginated(Some("conv/"), None, limit)
+                .await
+                .unwrap();
+            assert_eq!(page.keys.len(), 5);
+            assert_eq!(page.next_cursor, None);
+        }
+
+        // list_keys collects every page
+        assert_eq!(storage.list_keys(Some("conv/")).await.unwrap().len(), 5);
+
+        assert!(
+            storage
+                .list_keys_paginated(None, Some("not hex"), 2)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_extract_metadata_from_unix_path() {
+        let path = Path::new("/home/me/.ai_messenger/adapters/llm/ollama/0.1.0/adapter.wasm");
+
+        assert_eq!(
+            ModuleLoader::extract_metadata(path).unwrap(),
+            ("ollama".to_string(), "0.1.0".to_string())
+        );
+
+        // A data directory below another `adapters` directory still works
+        let nested = Path::new("adapters/data/./adapters/llm/openai/latest/adapter.wasm");
+        assert_eq!(
+            ModuleLoader::extract_metadata(nested).unwrap(),
+            ("openai".to_string(), "latest".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_metadata_rejects_other_layouts() {
+        for path in [
+            "/data/llm/ollama/0.1.0/adapter.wasm",
+            "/data/adapters/llm/ollama",
+        ] {
+            let error = ModuleLoader::extract_metadata(Path::new(path)).unwrap_err();
+            assert!(matches!(error, ServiceError::InvalidConfig(_)), "{}", path);
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_extract_metadata_from_windows_path() {
+        for path in [
+            r"C:\Users\me\.ai_messenger\adapters\llm\ollama\0.1.0\adapter.wasm",
+            r"\\?\C:\data\adapters\llm\ollama\0.1.0\adapter.wasm",
+            r"C:/data\adapters/llm\ollama/0.1.0\adapter.wasm",
+        ] {
+            assert_eq!(
+                ModuleLoader::extract_metadata(Path::new(path)).unwrap(),
+                ("ollama".to_string(), "0.1.0".to_string()),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_key_page_cursor_round_trip() {
+        for key in ["conversation/abc", "conversation/gr%C3%BC", ""] {
+            let cursor = KeyPage::cursor_after(key);
+            assert!(cursor.chars().all(|c| c.is_ascii_hexdigit()));
+            assert_eq!(KeyPage::decode_cursor(&cursor).unwrap(), key);
+        }
+
+        assert!(KeyPage::decode_cursor("abc").is_err());
+        assert!(KeyPage::decode_cursor("zz").is_err());
+        assert!(KeyPage::decode_cursor("ff").is_err());
+    }
+
+    #[test]
+    fn test_key_page_from_keys() {
+        let keys = ["c", "a", "b"].map(str::to_string);
+
+        let first = KeyPage::from_keys(keys.clone(), None, 2).unwrap();
+        assert_eq!(first.keys, vec!["a", "b"]);
+
+        let rest = KeyPage::from_keys(keys, first.next_cursor.as_deref(), 2).unwrap();
+        assert_eq!(rest.keys, vec!["c"]);
+        assert_eq!(rest.next_cursor, None);
+    }
+
+    #[test]
+    fn test_key_codec_round_trips_unicode() {
+        for key in [
+            "conversation/grüße",
+            "conversation/日本語",
+            "sender/🦀/profile",
+            "conversation/a b.c%d",
+        ] {
+            let encoded = KeyCodec::encode(key);
+
+            assert!(encoded.is_ascii(), "{encoded}");
+            assert_eq!(KeyCodec::decode(&encoded).unwrap(), key);
+        }
+
+        assert_eq!(
+            KeyCodec::encode("conversation/grüße"),
+            "conversation/gr%C3%BC%C3%9Fe"
+        );
+    }
+
+    #[test]
+    fn test_key_codec_keeps_namespaces() {
+        // Keys written before the encoding existed are unchanged
+        assert_eq!(
+            KeyCodec::encode("sender/default/profile"),
+            "sender/default/profile"
+        );
+        assert_eq!(
+            KeyCodec::encode("conversation/abc-123"),
+            "conversation/abc-123"
+        );
+
+        // A slash inside a segment is escaped, separators are kept
+        let key = format!("conversation/{}", KeyCodec::encode_segment("a/b"));
+        assert_eq!(key, "conversation/a%2Fb");
+        assert_eq!(KeyCodec::decode(&key).unwrap(), "conversation/a/b");
+
+        // Dot segments can't escape a filesystem directory
+        assert_eq!(KeyCodec::encode("../x"), "%2E%2E/x");
+    }
+
+    #[test]
+    fn test_key_codec_collision_free() {
+        let keys = [
+            "a/b", "a%2Fb", "a%252Fb", "a.b", "a%2Eb", "a_b", "a-b", "A/b", "a//b", "a/b/", "",
+            "ü", "%C3%BC", "u\u{308}",
+        ];
+
+        let encoded: std::collections::HashSet<String> =
+            keys.iter().map(|key| KeyCodec::encode(key)).collect();
+        assert_eq!(encoded.len(), keys.len());
+
+        for key in keys {
+            assert_eq!(KeyCodec::decode(&KeyCodec::encode(key)).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn test_key_codec_rejects_invalid_keys() {
+        assert!(KeyCodec::decode("100%").is_err());
+        assert!(KeyCodec::decode("%G1").is_err());
+        assert!(KeyCodec::decode("grüße").is_err());
+        assert!(KeyCodec::decode("%FF").is_err());
+
+        assert!(KeyCodec::is_canonical("conversation/gr%C3%BC%C3%9Fe"));
+        assert!(!KeyCodec::is_canonical("conversation/gr%c3%bc%c3%9fe"));
+        assert!(!KeyCodec::is_canonical("a.b"));
+    }
+
+    #[tokio::test]
+    async fn test_encoded_keys_storage() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("storage.sqlite3");
+        let mut storage =
+            EncodedKeys::new(Box::new(SqliteStorage::open(path.clone()).await.unwrap()));
+
+        storage.store("conversation/grüße", b"hallo").await.unwrap();
+        storage.store("conversation/a.b", b"dot").await.unwrap();
+        storage
+            .store("sender/default/profile", b"{}")
+            .await
+            .unwrap();
+
+        assert!(storage.exists("conversation/grüße").await.unwrap());
+        assert_eq!(
+            storage.retrieve("conversation/grüße").await.unwrap(),
+            b"hallo"
+        );
+
+        // Listing decodes the keys again
+        let mut keys = storage.list_keys(Some("conversation/")).await.unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["conversation/a.b", "conversation/grüße"]);
+
+        // The backend only ever saw safe ASCII keys
+        drop(storage);
+        let raw = SqliteStorage::open(path).await.unwrap();
+        let raw_keys = raw.list_keys(None).await.unwrap();
+        assert_eq!(raw_keys.len(), 3);
+        assert!(raw_keys.iter().all(|key| KeyCodec::is_canonical(key)));
+        assert!(raw_keys.contains(&"conversation/a%2Eb".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_values_storage() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("storage.sqlite3");
+        let crypto: SharedCrypto = Arc::new(tokio::sync::RwLock::new(
+            AesGcmCrypto::new(&[1; 32]).unwrap(),
+        ));
+        let mut storage = EncryptedValues::new(
+            Box::new(SqliteStorage::open(path.clone()).await.unwrap()),
+            crypto.clone(),
+        );
+
+        storage.store("conversation/a", b"hallo").await.unwrap();
+        storage.store("conversation/b", b"").await.unwrap();
+        assert_eq!(storage.retrieve("conversation/a").await.unwrap(), b"hallo");
+        assert_eq!(storage.retrieve("conversation/b").await.unwrap(), b"");
+        assert_eq!(
+            storage
+                .list_keys(Some("conversation/"))
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // The backend only ever saw ciphertext, under readable keys
+        drop(storage);
+        let mut raw = SqliteStorage::open(path).await.unwrap();
+        let sealed = raw.retrieve("conversation/a").await.unwrap();
+        assert_ne!(sealed, b"hallo");
+        assert_eq!(
+            crypto
+                .read()
+                .await
+                .decrypt(b"conversation/a", &sealed)
+                .await
+                .unwrap(),
+            b"hallo"
+        );
+
+        // A value moved under another key doesn't decrypt there
+        raw.store("conversation/b", &sealed).await.unwrap();
+        // Nor do values stored in plaintext
+        raw.store("conversation/plain", b"hallo").await.unwrap();
+        let storage = EncryptedValues::new(Box::new(raw), crypto);
+        assert!(storage.retrieve("conversation/b").await.is_err());
+        assert!(storage.retrieve("conversation/plain").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_keys() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut storage = SqliteStorage::open(temp_dir.path().join("storage.sqlite3"))
+            .await
+            .unwrap();
+
+        storage
+            .store("conversation/grüße", b"legacy")
+            .await
+            .unwrap();
+        storage.store("conversation/a.b", b"dot").await.unwrap();
+        storage.store("conversation/a%2Eb", b"taken").await.unwrap();
+        storage
+            .store("sender/default/profile", b"{}")
+            .await
+            .unwrap();
+
+        let migration = migrate_keys(&mut storage).await.unwrap();
+        assert_eq!(migration.migrated, 1);
+        assert_eq!(migration.unchanged, 2);
+        assert_eq!(migration.conflicts, vec!["conversation/a.b"]);
+
+        assert_eq!(
+            storage
+                .retrieve("conversation/gr%C3%BC%C3%9Fe")
+                .await
+                .unwrap(),
+            b"legacy"
+        );
+        assert!(!storage.exists("conversation/grüße").await.unwrap());
+        // Conflicting keys are left for the user to resolve
+        assert_eq!(
+            storage.retrieve("conversation/a%2Eb").await.unwrap(),
+            b"taken"
+        );
+
+        // Running again changes nothing
+        let migration = migrate_keys(&mut storage).await.unwrap();
+        assert_eq!(migration.migrated, 0);
+        assert_eq!(migration.conflicts, vec!["conversation/a.b"]);
+    }
+
+    #[test]
+    fn test_finish_reason_names() {
+        // finish_reason of OpenAI, done_reason of Ollama, stop_reason of Anthropic
+        for (reported, expected, name) in [
+            ("stop", FinishReason::Stop, "stop"),
+            ("end_turn", FinishReason::Stop, "stop"),
+            ("stop_sequence", FinishReason::Stop, "stop"),
+            ("length", FinishReason::Length, "length"),
+            ("max_tokens", FinishReason::Length, "length"),
+            (
+                "content_filter",
+                FinishReason::ContentFilter,
+                "content_filter",
+            ),
+            (
+                "content-filter",
+                FinishReason::ContentFilter,
+                "content_filter",
+            ),
+            ("error", FinishReason::Error, "error"),
+            (
+                "tool_calls",
+                FinishReason::Other("tool_calls".to_string()),
+                "tool_calls",
+            ),
+        ] {
+            let reason = FinishReason::from(reported);
+            assert_eq!(reason, expected, "{}", reported);
+            assert_eq!(serde_json::to_value(&reason).unwrap(), name);
+            assert_eq!(
+                serde_json::from_value::<FinishReason>(name.into()).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_stream_reports_finish() {
+        let mut llm = FnLlm::new("echo", |_| "hi".to_string());
+        let (chunks, mut receiver) = mpsc::channel(1);
+
+        let finish = llm
+            .stream_message(&user_message(), &GenerationOptions::default(), chunks)
+            .await
+            .unwrap();
+
+        assert_eq!(receiver.recv().await.unwrap(), "hi");
+        assert_eq!(finish, Finish::stop());
+    }
+
+    /// LLM adapter failing with a fixed error until it has no errors left
+    struct FlakyLlm {
+        calls: Arc<AtomicUsize>,
+        errors: Vec<ServiceError>,
+        images: bool,
+        provider: &'static str,
+    }
+
+    impl FlakyLlm {
+        fn new(provider: &'static str, errors: Vec<ServiceError>) -> (Self, Arc<AtomicUsize>) {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let llm = FlakyLlm {
+                calls: calls.clone(),
+                errors,
+                images: false,
+                provider,
+            };
+            (llm, calls)
+        }
+    }
+
+    #[async_trait]
+    impl AdapterService for FlakyLlm {
+        fn service_name(&self) -> &'static str {
+            "llm"
+        }
+
+        fn provider_name(&self) -> &str {
+            self.provider
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                images: self.images,
+                ..Capabilities::default()
+            }
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl LlmAdapter for FlakyLlm {
+        async fn send_message(
+            &mut self,
+            _messages: &[ChatMessage],
+            _options: &GenerationOptions,
+        ) -> Result<String, ServiceError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.errors.pop() {
+                Some(error) => Err(error),
+                None => Ok(format!("reply from {}", self.provider)),
+            }
+        }
+
+        async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+            Err(ServiceError::ServiceUnavailable("test".to_string()))
+        }
+    }
+
+    fn down() -> ServiceError {
+        ServiceError::ServiceUnavailable("connection refused".to_string())
+    }
+
+    fn user_message() -> Vec<ChatMessage> {
+        vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Hi".to_string(),
+            parts: None,
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_fallback_primary_fails_secondary_succeeds() {
+        let (primary, primary_calls) = FlakyLlm::new("primary", vec![down()]);
+        let (secondary, secondary_calls) = FlakyLlm::new("secondary", vec![]);
+        let mut chain = FallbackLlm::new(vec![Box::new(primary), Box::new(secondary)]).unwrap();
+        assert_eq!(chain.providers(), vec!["primary", "secondary"]);
+
+        let reply = chain
+            .send_message(&user_message(), &GenerationOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(reply, "reply from secondary");
+        // The provider that answered is reported
+        assert_eq!(chain.provider_name(), "secondary");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(secondary_calls.load(Ordering::SeqCst), 1);
+
+        // The primary is tried first again on the next request
+        let reply = chain
+            .send_message(&user_message(), &GenerationOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(reply, "reply from primary");
+        assert_eq!(chain.provider_name(), "primary");
+        assert_eq!(secondary_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_aggregates_errors() {
+        let (primary, _) = FlakyLlm::new("primary", vec![down()]);
+        let (secondary, _) = FlakyLlm::new(
+            "secondary",
+            vec![ServiceError::Timeout("no answer".to_string())],
+        );
+        let mut chain = FallbackLlm::new(vec![Box::new(primary), Box::new(secondary)]).unwrap();
+
+        let error = chain
+            .send_message(&user_message(), &GenerationOptions::default())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ServiceError::ServiceUnavailable(_)));
+        assert_eq!(
+            error.to_string(),
+            "Service unavailable: All LLM providers failed (primary: Service unavailable: \
+             connection refused; secondary: Request timed out: no answer)"
+        );
+        assert!(FallbackLlm::new(vec![]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fallback_keeps_rejected_requests() {
+        let rejected = ServiceError::ProviderError {
+            status: 400,
+            message: "context too long".to_string(),
+            retry_after_secs: None,
+        };
+        let (primary, _) = FlakyLlm::new("primary", vec![rejected]);
+        let (secondary, secondary_calls) = FlakyLlm::new("secondary", vec![]);
+        let mut chain = FallbackLlm::new(vec![Box::new(primary), Box::new(secondary)]).unwrap();
+
+        let error = chain
+            .send_message(&user_message(), &GenerationOptions::default())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            ServiceError::ProviderError { status: 400, .. }
+        ));
+        assert_eq!(secondary_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_skips_adapters_without_images() {
+        let (primary, primary_calls) = FlakyLlm::new("primary", vec![]);
+        let (mut secondary, _) = FlakyLlm::new("secondary", vec![]);
+        secondary.images = true;
+        let mut chain = FallbackLlm::new(vec![Box::new(primary), Box::new(secondary)]).unwrap();
+        assert!(chain.supports_images());
+        assert_eq!(
+            chain.capabilities(),
+            Capabilities {
+                images: true,
+                ..Capabilities::default()
+            }
+        );
+
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: "What's this?".to_string(),
+            parts: Some(vec![ContentPart::ImageUrl {
+                url: "https://example.com/cat.png".to_string(),
+            }]),
+        }];
+        let reply = chain
+            .send_message(&messages, &GenerationOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(reply, "reply from secondary");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_stream_fails_over_before_first_chunk() {
+        let (primary, _) = FlakyLlm::new("primary", vec![down()]);
+        let (secondary, _) = FlakyLlm::new("secondary", vec![]);
+        let mut chain = FallbackLlm::new(vec![Box::new(primary), Box::new(secondary)]).unwrap();
+        let (chunks, mut receiver) = mpsc::channel(4);
+
+        chain
+            .stream_message(&user_message(), &GenerationOptions::default(), chunks)
+            .await
+            .unwrap();
+
+        assert_eq!(receiver.recv().await.unwrap(), "reply from secondary");
+        assert!(receiver.recv().await.is_none());
+        assert_eq!(chain.provider_name(), "secondary");
+    }
+
+    #[test]
+    fn test_fallback_config() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "ollama"
+
+[[adapters.llm.fallback]]
+provider = "openai"
+version = "1.2.0"
+
+[adapters.llm.fallback.config]
+api_key = "${ENV:OPENAI_API_KEY}"
+"#,
+        )
+        .unwrap();
+
+        let llm = config.adapters.get_service("llm").unwrap();
+        assert_eq!(llm.fallback.len(), 1);
+        assert_eq!(llm.fallback[0].provider, "openai");
+        assert_eq!(llm.fallback[0].version, "1.2.0");
+        assert!(llm.fallback[0].config.get("api_key").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_fallback_load_rejects_repeated_provider() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let runtime = Arc::new(tokio::sync::RwLock::new(WasmRuntime::new().unwrap()));
+        let mut config = ServiceAdapterConfig::new("ollama");
+        config.fallback.push(ServiceAdapterConfig::new("ollama"));
+
+        let result = FallbackLlm::load(
+            &runtime,
+            &shared_client().unwrap(),
+            &config,
+            temp_dir.path(),
+            &AdapterConfig::default(),
+        )
+        .await;
+
+        let Err(error) = result else {
+            panic!("a repeated provider should be rejected");
+        };
+        assert!(matches!(error, ServiceError::InvalidConfig(_)));
+    }
+
+    fn endpoints(weights: &[u32]) -> Vec<EndpointConfig> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(index, &weight)| EndpointConfig {
+                base_url: format!("http://llm-{}:11434", index),
+                weight,
+            })
+            .collect()
+    }
+
+    /// Balancer over `endpoints` whose breakers open after one failure
+    fn balancer(endpoints: &[EndpointConfig], strategy: BalanceStrategy) -> EndpointBalancer {
+        let health = CircuitBreakerConfig {
+            cooldown_secs: 60,
+            failure_threshold: 1,
+        };
+        EndpointBalancer::new("ollama", endpoints, strategy, &health)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_weighted_round_robin_follows_weights() {
+        let balancer = balancer(&endpoints(&[3, 1, 0]), BalanceStrategy::default());
+
+        let picks: Vec<String> = (0..400)
+            .map(|_| balancer.pick().base_url().to_string())
+            .collect();
+        let count = |url: &str| picks.iter().filter(|pick| *pick == url).count();
+        assert_eq!(count("http://llm-0:11434"), 300);
+        assert_eq!(count("http://llm-1:11434"), 100);
+        assert_eq!(count("http://llm-2:11434"), 0);
+        // Interleaved: never more than 3 picks of the heavier endpoint in a row
+        assert!(
+            picks
+                .windows(4)
+                .all(|run| run.iter().any(|pick| pick == "http://llm-1:11434"))
+        );
+        assert!(
+            balancer
+                .in_flight()
+                .iter()
+                .all(|(_, in_flight)| *in_flight == 0)
+        );
+    }
+
+    #[test]
+    fn test_least_in_flight_follows_weights() {
+        let balancer = balancer(&endpoints(&[2, 1]), BalanceStrategy::LeastInFlight);
+
+        // Held leases: the first endpoint takes twice as many
+        let leases: Vec<_> = (0..30).map(|_| balancer.pick()).collect();
+        let held = balancer.in_flight();
+        assert_eq!(held[0].1, 20);
+        assert_eq!(held[1].1, 10);
+
+        drop(leases);
+        assert_eq!(balancer.in_flight()[0].1, 0);
+        // With nothing in flight, ties go to the first endpoint
+        assert_eq!(balancer.pick().base_url(), "http://llm-0:11434");
+    }
+
+    #[test]
+    fn test_least_in_flight_prefers_idle_endpoint() {
+        let balancer = balancer(&endpoints(&[1, 1]), BalanceStrategy::LeastInFlight);
+
+        let busy = balancer.pick();
+        assert_eq!(busy.base_url(), "http://llm-0:11434");
+        assert_eq!(balancer.pick().base_url(), "http://llm-1:11434");
+        drop(busy);
+        assert_eq!(balancer.pick().base_url(), "http://llm-0:11434");
+    }
+
+    #[test]
+    fn test_endpoint_balancer_validation() {
+        let health = CircuitBreakerConfig::default();
+        let none =
+            EndpointBalancer::new("ollama", &[], BalanceStrategy::default(), &health).unwrap();
+        assert!(none.is_none());
+
+        let all_zero = EndpointBalancer::new(
+            "ollama",
+            &endpoints(&[0, 0]),
+            BalanceStrategy::default(),
+            &health,
+        );
+        assert!(matches!(all_zero, Err(ServiceError::InvalidConfig(_))));
+
+        let mut blank = endpoints(&[1]);
+        blank[0].base_url = " ".to_string();
+        let blank =
+            EndpointBalancer::new("ollama", &blank, BalanceStrategy::LeastInFlight, &health);
+        assert!(matches!(blank, Err(ServiceError::InvalidConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_balancer_skips_unhealthy_endpoints() {
+        for strategy in [
+            BalanceStrategy::WeightedRoundRobin,
+            BalanceStrategy::LeastInFlight,
+        ] {
+            let balancer = balancer(&endpoints(&[1, 1, 1]), strategy);
+
+            let down = balancer.pick();
+            assert_eq!(down.base_url(), "http://llm-0:11434");
+            let result: Result<(), _> = down
+                .call(async { Err(ServiceError::Timeout("no answer".to_string())) })
+                .await;
+            assert!(result.is_err());
+            drop(down);
+
+            // The others take turns until the breaker's cooldown is over
+            let leases: Vec<_> = (0..6).map(|_| balancer.pick()).collect();
+            let picks: Vec<_> = leases.iter().map(|lease| lease.base_url()).collect();
+            assert!(!picks.contains(&"http://llm-0:11434"));
+            assert!(picks.contains(&"http://llm-1:11434"));
+            assert!(picks.contains(&"http://llm-2:11434"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_balancer_fails_fast_when_all_endpoints_are_down() {
+        let balancer = balancer(&endpoints(&[1]), BalanceStrategy::default());
+        let fail = || async { Err::<(), _>(ServiceError::ServiceUnavailable("down".to_string())) };
+
+        assert!(balancer.pick().call(fail()).await.is_err());
+
+        // Still picked, but the call doesn't reach the endpoint
+        let reached = AtomicBool::new(false);
+        let result = balancer
+            .pick()
+            .call(async {
+                reached.store(true, Ordering::SeqCst);
+                Ok(())
+            })
+            .await;
+        assert!(matches!(result, Err(ServiceError::ServiceUnavailable(_))));
+        assert!(!reached.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_requests_rotate_across_listed_hosts() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "ollama"
+endpoints = ["http://gpu-1:11434", "http://gpu-2:11434", "http://gpu-3:11434"]
+"#,
+        )
+        .unwrap();
+        let llm = config.adapters.get_service("llm").unwrap();
+        assert!(llm.endpoints.iter().all(|endpoint| endpoint.weight == 1));
+
+        let balancer = balancer(&llm.endpoints, llm.balance);
+        let picks: Vec<String> = (0..6)
+            .map(|_| balancer.pick().base_url().to_string())
+            .collect();
+        assert_eq!(
+            picks,
+            [
+                "http://gpu-1:11434",
+                "http://gpu-2:11434",
+                "http://gpu-3:11434",
+                "http://gpu-1:11434",
+                "http://gpu-2:11434",
+                "http://gpu-3:11434",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_endpoints_config() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "ollama"
+balance = "least_in_flight"
+
+[[adapters.llm.endpoints]]
+base_url = "http://gpu-1:11434"
+weight = 3
+
+[[adapters.llm.endpoints]]
+base_url = "http://gpu-2:11434"
+"#,
+        )
+        .unwrap();
+
+        let llm = config.adapters.get_service("llm").unwrap();
+        assert_eq!(llm.balance, BalanceStrategy::LeastInFlight);
+        assert_eq!(llm.endpoints.len(), 2);
+        assert_eq!(llm.endpoints[0].weight, 3);
+        assert_eq!(llm.endpoints[1].base_url, "http://gpu-2:11434");
+        assert_eq!(llm.endpoints[1].weight, 1);
+        assert_eq!(
+            ServiceAdapterConfig::new("ollama").balance,
+            BalanceStrategy::WeightedRoundRobin
+        );
+    }
+
+    #[test]
+    fn test_chat_request_with_base_url() {
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Hi".to_string(),
+            parts: Some(vec![ContentPart::Text {
+                text: "Hi".to_string(),
+            }]),
+        }];
+        let options = GenerationOptions::default();
+
+        let plain = ChatRequest::new("llama3", &messages[..0], &options)
+            .with_base_url("http://gpu-1:11434");
+        assert_eq!(
+            plain.provider_params.as_deref(),
+            Some(r#"{"base_url":"http://gpu-1:11434"}"#)
+        );
+
+        // Merged with the parameters already there
+        let request =
+            ChatRequest::new("llava", &messages, &options).with_base_url("http://gpu-1:11434");
+        let params: serde_json::Value =
+            serde_json::from_str(request.provider_params.as_deref().unwrap()).unwrap();
+        assert_eq!(params["base_url"], "http://gpu-1:11434");
+        assert_eq!(params["content_parts"][0][0]["text"], "Hi");
+    }
 }
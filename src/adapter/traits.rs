@@ -1,6 +1,19 @@
+use crate::adapter::balancer::EndpointBalancer;
+use crate::adapter::breaker::CircuitBreaker;
+use crate::adapter::limiter::ConcurrencyLimiter;
+use crate::adapter::manifest::{
+    AdapterManifest, CAPABILITY_EMBEDDINGS, CAPABILITY_IMAGES, CAPABILITY_MODERATION,
+    CAPABILITY_STREAMING, CAPABILITY_TOOLS,
+};
+use ai_messenger_adapter_sdk::role::finish_reason_from_str;
+use ai_messenger_adapter_sdk::types;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::mpsc;
 
 /// Common error type for all adapter operations
 #[derive(Error, Debug)]
@@ -11,8 +24,64 @@ pub enum ServiceError {
     ExecutionError(String),
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
+    #[error("Not implemented: {0}")]
+    NotImplemented(String),
     #[error("Service unavailable: {0}")]
     ServiceUnavailable(String),
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+    #[error("Provider returned HTTP {status}: {message}")]
+    ProviderError {
+        /// Status of the provider's response
+        status: u16,
+        /// The provider's description of the error, e.g. "model not found"
+        message: String,
+        /// Delay the provider asked for before retrying (its `Retry-After`)
+        retry_after_secs: Option<u64>,
+    },
+    #[error("Provider sent an invalid response: {0}")]
+    InvalidResponse(String),
+    #[error("Provider response exceeds the limit of {limit} bytes")]
+    ResponseTooLarge {
+        /// `max_response_bytes` of the adapter
+        limit: usize,
+    },
+    #[error("Service overloaded, retry after {retry_after_secs}s")]
+    Overloaded {
+        /// Suggested delay before retrying (for a `Retry-After` header)
+        retry_after_secs: u64,
+    },
+    #[error("WASM trap in `{function}`: {trap}")]
+    WasmTrap {
+        /// Adapter function that was being called
+        function: String,
+        /// Fuel left in the store when the trap occurred
+        remaining_fuel: Option<u64>,
+        /// Trap code reported by wasmtime
+        trap: wasmtime::Trap,
+    },
+}
+
+impl ServiceError {
+    /// Convert a wasmtime error into a service error, preserving trap details
+    ///
+    /// Traps become `WasmTrap` so callers can inspect the trap code and fuel;
+    /// all other failures fall back to `ExecutionError`.
+    #[allow(dead_code)] // Used once the guest is called via WIT bindings
+    pub fn from_wasm_error(
+        error: wasmtime::Error,
+        function: &str,
+        remaining_fuel: Option<u64>,
+    ) -> Self {
+        match error.downcast_ref::<wasmtime::Trap>() {
+            Some(trap) => ServiceError::WasmTrap {
+                function: function.to_string(),
+                remaining_fuel,
+                trap: *trap,
+            },
+            None => ServiceError::ExecutionError(format!("`{function}` failed: {error:#}")),
+        }
+    }
 }
 
 /// Base trait for all service adapters
@@ -27,23 +96,208 @@ pub trait AdapterService: Send + Sync {
     /// Adapter version
     fn version(&self) -> &str;
 
+    /// Manifest installed with the adapter's WASM module
+    ///
+    /// Native and built-in adapters have none.
+    fn manifest(&self) -> Option<&AdapterManifest> {
+        None
+    }
+
+    /// Optional features the adapter supports
+    ///
+    /// Read from the manifest by default, so adapters without one support
+    /// none; native adapters override it.
+    fn capabilities(&self) -> Capabilities {
+        self.manifest()
+            .map(Capabilities::from_manifest)
+            .unwrap_or_default()
+    }
+
     /// Check if the adapter is ready to handle requests
     fn is_ready(&self) -> bool;
 
     /// Graceful shutdown of the adapter
     async fn shutdown(&mut self) -> Result<(), ServiceError>;
 }
+
+/// Optional features of an adapter, as declared in its manifest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    /// Returns embeddings of text
+    pub embeddings: bool,
+    /// Accepts image parts in messages
+    pub images: bool,
+    /// Classifies content for `[moderation.adapter]`
+    pub moderation: bool,
+    /// Sends replies in chunks as they're generated; without it, a
+    /// streamed reply would arrive as one chunk at the end
+    pub streaming: bool,
+    /// Calls tools
+    pub tools: bool,
+}
+
+impl Capabilities {
+    /// Capabilities `manifest` declares; unknown names are ignored
+    pub fn from_manifest(manifest: &AdapterManifest) -> Self {
+        Capabilities {
+            embeddings: manifest.has_capability(CAPABILITY_EMBEDDINGS),
+            images: manifest.has_capability(CAPABILITY_IMAGES),
+            moderation: manifest.has_capability(CAPABILITY_MODERATION),
+            streaming: manifest.has_capability(CAPABILITY_STREAMING),
+            tools: manifest.has_capability(CAPABILITY_TOOLS),
+        }
+    }
+
+    /// Manifest names of the supported capabilities, alphabetically
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.embeddings, CAPABILITY_EMBEDDINGS),
+            (self.images, CAPABILITY_IMAGES),
+            (self.moderation, CAPABILITY_MODERATION),
+            (self.streaming, CAPABILITY_STREAMING),
+            (self.tools, CAPABILITY_TOOLS),
+        ]
+        .into_iter()
+        .filter_map(|(supported, name)| supported.then_some(name))
+        .collect()
+    }
+
+    /// Capabilities supported by either `self` or `other`
+    pub fn union(self, other: Capabilities) -> Self {
+        Capabilities {
+            embeddings: self.embeddings || other.embeddings,
+            images: self.images || other.images,
+            moderation: self.moderation || other.moderation,
+            streaming: self.streaming || other.streaming,
+            tools: self.tools || other.tools,
+        }
+    }
+}
+
 /// Trait for LLM service adapters
 #[async_trait]
 pub trait LlmAdapter: AdapterService {
-    /// Send a message and get response
-    async fn send_message(&mut self, message: &str) -> Result<String, ServiceError>;
+    /// Send a conversation and get the response
+    async fn send_message(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<String, ServiceError>;
+
+    /// Send a conversation and get the response with its token usage
+    ///
+    /// The default wraps `send_message`, reports no usage and assumes the
+    /// reply ended naturally; adapters whose provider returns token counts
+    /// or finish reasons should override it.
+    async fn complete(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<Completion, ServiceError> {
+        let content = self.send_message(messages, options).await?;
+        Ok(Completion {
+            content,
+            finish: Finish::stop(),
+            usage: None,
+        })
+    }
 
     /// Get model information
+    #[allow(dead_code)] // Library API; no endpoint reports model info yet
     async fn get_model_info(&self) -> Result<ModelInfo, ServiceError>;
 
-    // Stream message response (future enhancement)
-    // async fn stream_message(&mut self, message: &str) -> Result<impl Stream<Item = String>, ServiceError>;
+    /// Model requests are sent to, if configured (None means the provider default)
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether the adapter accepts image parts in messages
+    ///
+    /// Adapters declare this with the `images` capability in their manifest.
+    fn supports_images(&self) -> bool {
+        self.capabilities().images
+    }
+
+    /// Whether the adapter can classify content with `moderate`
+    ///
+    /// Adapters declare this with the `moderation` capability in their manifest.
+    fn supports_moderation(&self) -> bool {
+        self.capabilities().moderation
+    }
+
+    /// Check `text` against the provider's content policy
+    ///
+    /// Returns the category it falls under, or None if it's allowed.
+    async fn moderate(&mut self, _text: &str) -> Result<Option<String>, ServiceError> {
+        Err(ServiceError::ServiceUnavailable(format!(
+            "The {} adapter doesn't support moderation",
+            self.provider_name()
+        )))
+    }
+
+    /// Fuel the adapter's WASM instance used for the last reply
+    ///
+    /// None if the adapter doesn't run in WASM or the call didn't report it.
+    fn fuel_consumed(&self) -> Option<u64> {
+        None
+    }
+
+    /// Breaker guarding calls to the provider, if one is configured
+    fn circuit_breaker(&self) -> Option<&Arc<CircuitBreaker>> {
+        None
+    }
+
+    /// Limiter bounding concurrent calls to the provider, if one is configured
+    fn concurrency_limiter(&self) -> Option<&Arc<ConcurrencyLimiter>> {
+        None
+    }
+
+    /// Balancer spreading calls over the provider's endpoints, if several are configured
+    fn endpoint_balancer(&self) -> Option<&Arc<EndpointBalancer>> {
+        None
+    }
+
+    /// Stream a message response as chunks sent to `chunks`
+    ///
+    /// Returns how generation ended, usually reported with the last chunk.
+    /// Dropping the receiver cancels the stream: implementations must stop
+    /// and abort any upstream request rather than generate to completion.
+    /// The default sends the whole `complete` response as one chunk.
+    async fn stream_message(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+        chunks: mpsc::Sender<String>,
+    ) -> Result<Finish, ServiceError> {
+        let upstream = self.complete(messages, options);
+        let Some(response) = until_closed(&chunks, upstream).await else {
+            return Ok(Finish::default());
+        };
+        let completion = response?;
+
+        // A receiver dropped after the response arrived is not an error
+        let _ = chunks.send(completion.content).await;
+
+        Ok(completion.finish)
+    }
+}
+
+/// Drive `upstream` until it completes or the receiver of `chunks` is dropped
+///
+/// Returns `None` if the receiver went away first; `upstream` is dropped at
+/// that point, which aborts any request it owns.
+pub async fn until_closed<T, F: Future>(
+    chunks: &mpsc::Sender<T>,
+    upstream: F,
+) -> Option<F::Output> {
+    tokio::select! {
+        biased;
+        _ = chunks.closed() => {
+            tracing::debug!("Stream receiver dropped, cancelling upstream request");
+            None
+        }
+        output = upstream => Some(output),
+    }
 }
 
 /// Trait for storage service adapters
@@ -61,8 +315,453 @@ pub trait StorageAdapter: AdapterService {
     /// Check if key exists
     async fn exists(&self, key: &str) -> Result<bool, ServiceError>;
 
+    /// List up to `limit` keys with optional prefix, in key order
+    ///
+    /// Pass the previous page's `next_cursor` as `cursor` to continue after
+    /// it. Cursors are opaque to callers; see `KeyPage::cursor_after`.
+    async fn list_keys_paginated(
+        &self,
+        prefix: Option<&str>,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<KeyPage, ServiceError>;
+
     /// List all keys with optional prefix
-    async fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, ServiceError>;
+    ///
+    /// Collects every page of `list_keys_paginated`, so it's only meant for
+    /// prefixes with few keys.
+    async fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, ServiceError> {
+        let mut keys = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = self
+                .list_keys_paginated(prefix, cursor.as_deref(), LIST_KEYS_PAGE_SIZE)
+                .await?;
+            keys.extend(page.keys);
+
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(keys),
+            }
+        }
+    }
+}
+
+/// Page size `StorageAdapter::list_keys` fetches keys in
+pub const LIST_KEYS_PAGE_SIZE: usize = 1000;
+
+/// One page of keys from `StorageAdapter::list_keys_paginated`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyPage {
+    pub keys: Vec<String>,
+    /// Cursor for the next page (None on the last page)
+    pub next_cursor: Option<String>,
+}
+
+impl KeyPage {
+    /// Page from the keys following the cursor, in order
+    ///
+    /// `keys` may hold more than `limit` keys (adapters fetch one extra to
+    /// see whether there's another page); the rest is cut off.
+    pub fn from_sorted(mut keys: Vec<String>, limit: usize) -> Self {
+        let limit = limit.max(1);
+        let next_cursor = (keys.len() > limit).then(|| {
+            keys.truncate(limit);
+            KeyPage::cursor_after(&keys[limit - 1])
+        });
+
+        KeyPage { keys, next_cursor }
+    }
+
+    /// Page of `keys` for adapters without native cursoring
+    ///
+    /// Sorts `keys` and skips those up to and including the cursor.
+    pub fn from_keys(
+        keys: impl IntoIterator<Item = String>,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<Self, ServiceError> {
+        let after = cursor.map(KeyPage::decode_cursor).transpose()?;
+        let mut keys: Vec<String> = keys
+            .into_iter()
+            .filter(|key| after.as_ref().is_none_or(|after| key > after))
+            .collect();
+        keys.sort();
+
+        Ok(KeyPage::from_sorted(keys, limit))
+    }
+
+    /// Cursor continuing after `key`
+    ///
+    /// The key is hex encoded, so cursors are safe in query strings and
+    /// don't invite clients to build their own.
+    pub fn cursor_after(key: &str) -> String {
+        key.bytes().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Key a cursor continues after
+    pub fn decode_cursor(cursor: &str) -> Result<String, ServiceError> {
+        let invalid = || ServiceError::ExecutionError(format!("Invalid cursor: {cursor}"));
+        if !cursor.len().is_multiple_of(2) {
+            return Err(invalid());
+        }
+
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| {
+                cursor
+                    .get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+        String::from_utf8(bytes).map_err(|_| invalid())
+    }
+}
+
+/// Trait for text-to-speech service adapters
+#[async_trait]
+pub trait TtsAdapter: AdapterService {
+    /// Speak `text`, in `voice` if given (None means the provider default)
+    async fn synthesize(&mut self, text: &str, voice: Option<&str>)
+    -> Result<Speech, ServiceError>;
+
+    /// Speak `text`, sending the audio to `chunks` as it's produced
+    ///
+    /// All chunks carry the same MIME type, so the receiver can answer with
+    /// it once the first arrives. Dropping the receiver cancels synthesis,
+    /// as for `LlmAdapter::stream_message`. The default sends the whole
+    /// `synthesize` clip as one chunk.
+    async fn stream_speech(
+        &mut self,
+        text: &str,
+        voice: Option<&str>,
+        chunks: mpsc::Sender<Speech>,
+    ) -> Result<(), ServiceError> {
+        let upstream = self.synthesize(text, voice);
+        let Some(speech) = until_closed(&chunks, upstream).await else {
+            return Ok(());
+        };
+        // A receiver dropped after synthesis is not an error
+        let _ = chunks.send(speech?).await;
+        Ok(())
+    }
+}
+
+/// Audio synthesized by a TTS adapter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Speech {
+    pub audio: Vec<u8>,
+    /// Encoding of `audio`, e.g. "audio/mpeg"
+    pub mime_type: String,
+}
+
+/// Trait for speech-to-text service adapters
+#[async_trait]
+pub trait SttAdapter: AdapterService {
+    /// Transcribe `audio`, encoded as `mime_type` (e.g. "audio/wav")
+    async fn transcribe(&mut self, audio: &[u8], mime_type: &str) -> Result<String, ServiceError>;
+}
+
+/// Trait for crypto service adapters, encrypting data at rest
+#[async_trait]
+pub trait CryptoAdapter: AdapterService {
+    /// Encrypt `plaintext` into bytes `decrypt` turns back into it
+    ///
+    /// `context` (e.g. the storage key) isn't encrypted but authenticated:
+    /// decrypting only succeeds with the same context.
+    async fn encrypt(&self, context: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, ServiceError>;
+
+    /// Decrypt what `encrypt` produced for `context`
+    ///
+    /// Fails if the data was altered, encrypted with another key or for
+    /// another context.
+    async fn decrypt(&self, context: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, ServiceError>;
+}
+
+/// Trait for image generation service adapters
+#[async_trait]
+pub trait ImageAdapter: AdapterService {
+    /// Generate an image of `size` as described by `prompt`
+    async fn generate(
+        &mut self,
+        prompt: &str,
+        size: ImageSize,
+    ) -> Result<GeneratedImage, ServiceError>;
+}
+
+/// Image generated by an image adapter
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedImage {
+    pub data: Vec<u8>,
+    /// Encoding of `data`, e.g. "image/png"
+    pub mime_type: String,
+}
+
+/// Size of a generated image in pixels, written `<width>x<height>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for ImageSize {
+    fn default() -> Self {
+        ImageSize {
+            width: 1024,
+            height: 1024,
+        }
+    }
+}
+
+impl std::str::FromStr for ImageSize {
+    type Err = String;
+
+    /// Parse `1024x768`, ignoring surrounding whitespace and the case of the `x`
+    fn from_str(size: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid image size {size:?}, expected e.g. \"1024x1024\"");
+        let (width, height) = size.trim().split_once(['x', 'X']).ok_or_else(invalid)?;
+        let side = |side: &str| side.trim().parse::<u32>().ok().filter(|side| *side > 0);
+
+        Ok(ImageSize {
+            width: side(width).ok_or_else(invalid)?,
+            height: side(height).ok_or_else(invalid)?,
+        })
+    }
+}
+
+impl fmt::Display for ImageSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Message in a conversation sent to LLM adapters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    /// Text of the message (for multi-part messages, its text parts)
+    pub content: String,
+    /// All parts of a multi-part message, images included
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parts: Option<Vec<ContentPart>>,
+}
+
+/// Part of a multi-part message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text {
+        text: String,
+    },
+    /// Inline image as base64
+    Image {
+        data: String,
+        mime: String,
+    },
+    ImageUrl {
+        url: String,
+    },
+}
+
+impl ContentPart {
+    /// Whether this part is an image, inline or linked
+    pub fn is_image(&self) -> bool {
+        matches!(
+            self,
+            ContentPart::Image { .. } | ContentPart::ImageUrl { .. }
+        )
+    }
+}
+
+/// Token usage reported by a provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// Reply to an LLM request
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub content: String,
+    /// How generation ended
+    pub finish: Finish,
+    /// Token usage, if the provider reported it
+    pub usage: Option<Usage>,
+}
+
+/// How generation of a reply ended, as far as the provider reports it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Finish {
+    /// Why generation ended (None if the provider didn't say)
+    pub reason: Option<FinishReason>,
+    /// Stop sequence that ended generation, if the provider reports it
+    pub stop_sequence: Option<String>,
+}
+
+impl Finish {
+    /// A reply that ended naturally
+    ///
+    /// Assumed for adapters that only return the text of their reply.
+    pub fn stop() -> Self {
+        Finish {
+            reason: Some(FinishReason::Stop),
+            stop_sequence: None,
+        }
+    }
+}
+
+/// Why the provider stopped generating (`finish-reason` in the WIT)
+///
+/// Serialized as `stop`, `length`, `content_filter`, `error` or the
+/// provider's own name for other reasons.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FinishReason {
+    /// The reply ended naturally or at a stop sequence
+    Stop,
+    /// The reply was cut off by the token limit
+    Length,
+    /// The provider withheld content
+    ContentFilter,
+    /// The provider failed after generating part of the reply
+    Error,
+    /// Reasons not covered above, as the provider names them
+    Other(String),
+}
+
+impl FinishReason {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FinishReason::Stop => "stop",
+            FinishReason::Length => "length",
+            FinishReason::ContentFilter => "content_filter",
+            FinishReason::Error => "error",
+            FinishReason::Other(reason) => reason,
+        }
+    }
+}
+
+impl From<&str> for FinishReason {
+    /// Map a provider's name for a finish reason like adapters do
+    fn from(reason: &str) -> Self {
+        finish_reason_from_str(reason).into()
+    }
+}
+
+impl From<types::FinishReason> for FinishReason {
+    fn from(reason: types::FinishReason) -> Self {
+        match reason {
+            types::FinishReason::Stop => FinishReason::Stop,
+            types::FinishReason::Length => FinishReason::Length,
+            types::FinishReason::ContentFilter => FinishReason::ContentFilter,
+            types::FinishReason::Error => FinishReason::Error,
+            types::FinishReason::Other(reason) => FinishReason::Other(reason),
+        }
+    }
+}
+
+impl fmt::Display for FinishReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for FinishReason {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for FinishReason {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let reason = String::deserialize(deserializer)?;
+        Ok(FinishReason::from(reason.as_str()))
+    }
+}
+
+/// Sampling parameters for an LLM request (unset values use provider defaults)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenerationOptions {
+    /// Maximum number of completion tokens (`max-completion-tokens` in the WIT)
+    #[serde(
+        default,
+        alias = "max_completion_tokens",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_tokens: Option<u32>,
+    /// Random seed for reproducible output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// Sequences that end generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    /// Sampling temperature (0.0 to 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling probability mass (0.0 to 1.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+}
+
+impl GenerationOptions {
+    /// Maximum number of stop sequences per request
+    pub const MAX_STOP_SEQUENCES: usize = 4;
+
+    /// Fill values that aren't set here from `defaults`
+    pub fn with_defaults(self, defaults: &GenerationOptions) -> Self {
+        GenerationOptions {
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            seed: self.seed.or(defaults.seed),
+            stop: self.stop.or_else(|| defaults.stop.clone()),
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+        }
+    }
+
+    /// Check that all values are in range, naming the first invalid field
+    pub fn validate(&self) -> Result<(), String> {
+        if self
+            .temperature
+            .is_some_and(|temperature| !(0.0..=2.0).contains(&temperature))
+        {
+            return Err("temperature must be between 0 and 2".to_string());
+        }
+
+        if self
+            .top_p
+            .is_some_and(|top_p| !(0.0..=1.0).contains(&top_p))
+        {
+            return Err("top_p must be between 0 and 1".to_string());
+        }
+
+        if self.max_tokens == Some(0) {
+            return Err("max_tokens must be greater than 0".to_string());
+        }
+
+        // Negative seeds ask for a random one; larger ones don't fit the WIT `seed`
+        if self.seed.is_some_and(|seed| seed > i64::from(u32::MAX)) {
+            return Err(format!("seed must be at most {}", u32::MAX));
+        }
+
+        if let Some(stop) = &self.stop {
+            if stop.len() > Self::MAX_STOP_SEQUENCES {
+                return Err(format!(
+                    "stop accepts at most {} sequences",
+                    Self::MAX_STOP_SEQUENCES
+                ));
+            }
+
+            if stop.iter().any(String::is_empty) {
+                return Err("stop sequences must not be empty".to_string());
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Model information returned by LLM adapters
@@ -20,8 +20,12 @@ pub fn build() -> Command {
                 .help("Print version")
                 .action(ArgAction::Version),
         )
+        .subcommand(super::commands::adapter::command())
         .subcommand(super::commands::cache::command())
+        .subcommand(super::commands::chat::command())
+        .subcommand(super::commands::config::command())
         .subcommand(super::commands::data::command())
+        .subcommand(super::commands::doctor::command())
         .subcommand(
             Command::new("help")
                 .about("Print this message or the help of the given command")
@@ -32,7 +36,8 @@ pub fn build() -> Command {
                         .num_args(0..=1),
                 ),
         )
-        .subcommand(super::commands::serve::command());
+        .subcommand(super::commands::serve::command())
+        .subcommand(super::commands::usage::command());
 
     let cmd = super::options::help::apply(cmd);
     super::options::version::apply(cmd)
@@ -68,11 +73,16 @@ mod tests {
         let subcommand_names: Vec<&str> = cmd.get_subcommands().map(|sub| sub.get_name()).collect();
 
         // Should have all expected subcommands in alphabetical order
+        assert!(subcommand_names.contains(&"adapter"));
         assert!(subcommand_names.contains(&"cache"));
+        assert!(subcommand_names.contains(&"chat"));
+        assert!(subcommand_names.contains(&"config"));
         assert!(subcommand_names.contains(&"data"));
+        assert!(subcommand_names.contains(&"doctor"));
         assert!(subcommand_names.contains(&"serve"));
         assert!(subcommand_names.contains(&"help"));
-        assert_eq!(subcommand_names.len(), 4);
+        assert!(subcommand_names.contains(&"usage"));
+        assert_eq!(subcommand_names.len(), 9);
     }
 
     #[test]
@@ -81,8 +91,13 @@ mod tests {
 
         let subcommand_names: Vec<&str> = cmd.get_subcommands().map(|sub| sub.get_name()).collect();
 
-        // Should be in alphabetical order: cache, data, help, serve
-        assert_eq!(subcommand_names, vec!["cache", "data", "help", "serve"]);
+        // Should be in alphabetical order: adapter, cache, chat, config, data, doctor, help, serve, usage
+        assert_eq!(
+            subcommand_names,
+            vec![
+                "adapter", "cache", "chat", "config", "data", "doctor", "help", "serve", "usage"
+            ]
+        );
     }
 
     #[test]
@@ -185,7 +200,7 @@ mod tests {
     #[test]
     fn test_subcommand_parsing() {
         // Test each subcommand can be parsed individually
-        for subcommand_name in ["serve", "cache", "data", "help"] {
+        for subcommand_name in ["serve", "cache", "data", "doctor", "help"] {
             let cmd = build();
             let matches = cmd
                 .try_get_matches_from(["ai_messenger", subcommand_name])
@@ -252,8 +267,8 @@ mod tests {
     fn test_subcommand_count() {
         let cmd = build();
 
-        // Should have exactly 4 subcommands
-        assert_eq!(cmd.get_subcommands().count(), 4);
+        // Should have exactly 9 subcommands
+        assert_eq!(cmd.get_subcommands().count(), 9);
     }
 
     #[test]
@@ -0,0 +1,351 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde::Serialize;
+use std::path::Path;
+
+use super::shared::{DEFAULT_OUTPUT, OUTPUT_VALUES};
+use crate::adapter::manifest::AdapterManifest;
+use crate::adapter::services::is_built_in;
+use crate::adapter::traits::Capabilities;
+use crate::config::Config;
+
+pub fn command() -> Command {
+    let list = Command::new("list")
+        .about("List the configured adapters and the config keys they accept")
+        .disable_help_flag(true)
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Path to configuration file")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .short('l')
+                .value_name("LEVEL")
+                .help("Set the logging level")
+                .value_parser(crate::cli::options::logging::LOG_LEVEL_VALUES)
+                .default_value(crate::cli::options::logging::DEFAULT_LOG_LEVEL)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .value_name("FORMAT")
+                .help("Output format (json for scripts)")
+                .value_parser(OUTPUT_VALUES)
+                .default_value(DEFAULT_OUTPUT)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .short('V')
+                .help("Enable verbose output (sets log-level to debug)")
+                .action(ArgAction::SetTrue),
+        );
+
+    let cmd = Command::new("adapter")
+        .about("Inspect the configured adapters")
+        .disable_help_flag(true)
+        .disable_help_subcommand(true)
+        .subcommand_required(true)
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        )
+        .subcommand(crate::cli::options::help::apply(list));
+
+    // Apply consistent help styling
+    crate::cli::options::help::apply(cmd)
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", sub_m)) => run_list(sub_m),
+        _ => unreachable!("adapter requires a subcommand"),
+    }
+}
+
+/// A configured adapter and what its manifest declares
+#[derive(Debug, Serialize)]
+struct AdapterEntry {
+    #[serde(flatten)]
+    manifest: AdapterManifest,
+    provider: String,
+    service: String,
+    /// `built-in`, `installed`, `invalid manifest` or `missing`
+    status: &'static str,
+    /// Known capabilities the manifest declares
+    supports: Capabilities,
+    version: String,
+}
+
+fn run_list(matches: &ArgMatches) -> Result<()> {
+    let config_file = matches.get_one::<String>("config").cloned();
+    let log_level = crate::cli::options::logging::extract_log_level(matches);
+    let output = matches
+        .get_one::<String>("output")
+        .map_or(DEFAULT_OUTPUT, String::as_str);
+
+    // Initialize logging with the requested level
+    if let Err(e) = crate::utils::init_logging(&log_level) {
+        eprintln!("Failed to initialize logging: {}", e);
+        // Continue without logging rather than fail
+    }
+
+    let (config, config_dir) = if log_level == "debug" {
+        crate::config::load_config(config_file, None)?
+    } else {
+        crate::config::load_config_silent(config_file, None)?
+    };
+    let data_dir = crate::config::data_dir(&config, config_dir.as_deref());
+
+    let entries = adapter_entries(&config, &data_dir);
+    match output {
+        "json" => println!("{}", serde_json::json!({ "adapters": entries })),
+        _ => print!("{}", format_entries(&entries)),
+    }
+
+    Ok(())
+}
+
+/// Configured adapters by service, with the manifests installed next to their modules
+///
+/// Reads the manifests without loading any module, so it works while the
+/// server is running and for adapters that fail to load.
+fn adapter_entries(config: &Config, data_dir: &Path) -> Vec<AdapterEntry> {
+    let mut services: Vec<_> = config.adapters.services.iter().collect();
+    services.sort_by_key(|(service, _)| service.as_str());
+
+    let mut entries = Vec::new();
+    for (service, adapter) in services {
+        let builtin = is_built_in(service, &adapter.provider);
+        let module_path = adapter.module_path(data_dir, service);
+        let (manifest, status) = if builtin {
+            (AdapterManifest::default(), "built-in")
+        } else if module_path.exists() {
+            match AdapterManifest::read_for_module(&module_path) {
+                Ok(manifest) => (manifest.unwrap_or_default(), "installed"),
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    (AdapterManifest::default(), "invalid manifest")
+                }
+            }
+        } else {
+            (AdapterManifest::default(), "missing")
+        };
+
+        entries.push(AdapterEntry {
+            supports: Capabilities::from_manifest(&manifest),
+            manifest,
+            provider: adapter.provider.clone(),
+            service: service.clone(),
+            status,
+            version: adapter.version.clone(),
+        });
+    }
+
+    entries
+}
+
+/// Render `entries` as a table, followed by the config keys each adapter accepts
+fn format_entries(entries: &[AdapterEntry]) -> String {
+    if entries.is_empty() {
+        return "No adapters configured\n".to_string();
+    }
+
+    let mut rows = vec![[
+        "SERVICE".to_string(),
+        "PROVIDER".to_string(),
+        "VERSION".to_string(),
+        "CAPABILITIES".to_string(),
+        "STATUS".to_string(),
+    ]];
+    for entry in entries {
+        let capabilities = entry.supports.names();
+        rows.push([
+            entry.service.clone(),
+            entry.provider.clone(),
+            entry.version.clone(),
+            if capabilities.is_empty() {
+                "-".to_string()
+            } else {
+                capabilities.join(",")
+            },
+            entry.status.to_string(),
+        ]);
+    }
+    let mut out = format_table(&rows);
+
+    for entry in entries {
+        let Some(schema) = &entry.manifest.config_schema else {
+            continue;
+        };
+        out.push_str(&format!("\n[adapters.{}.config]\n", entry.service));
+        let rows: Vec<[String; 3]> = schema
+            .iter()
+            .map(|(key, field)| {
+                [
+                    key.clone(),
+                    field.value_type.as_str().to_string(),
+                    field.description.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        for line in format_table(&rows).lines() {
+            out.push_str(&format!("  {}\n", line));
+        }
+    }
+
+    out
+}
+
+/// Left-aligned columns separated by two spaces
+fn format_table<const N: usize>(rows: &[[String; N]]) -> String {
+    let widths: Vec<usize> = (0..N)
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:<width$}", cell))
+                .collect::<Vec<_>>()
+                .join("  ");
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_structure() {
+        let cmd = command();
+
+        assert_eq!(cmd.get_name(), "adapter");
+        assert!(cmd.is_subcommand_required_set());
+        let names: Vec<&str> = cmd.get_subcommands().map(|sub| sub.get_name()).collect();
+        assert_eq!(names, vec!["list"]);
+    }
+
+    #[test]
+    fn test_subcommand_required() {
+        let result = command().try_get_matches_from(["adapter"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_adapter_entries_read_manifests() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config: Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "ollama"
+version = "1.0.0"
+
+[adapters.storage]
+provider = "sqlite"
+"#,
+        )
+        .unwrap();
+        let module_path = config
+            .adapters
+            .get_service("llm")
+            .unwrap()
+            .module_path(temp_dir.path(), "llm");
+        std::fs::create_dir_all(module_path.parent().unwrap()).unwrap();
+        std::fs::write(&module_path, b"").unwrap();
+        std::fs::write(
+            AdapterManifest::path_for_module(&module_path),
+            "capabilities = [\"streaming\", \"images\"]\n\n\
+             [config_schema.base_url]\ntype = \"string\"\ndescription = \"Server URL\"\n",
+        )
+        .unwrap();
+
+        let entries = adapter_entries(&config, temp_dir.path());
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].status, "installed");
+        assert_eq!(entries[1].status, "built-in");
+        let json = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(json["config_schema"]["base_url"]["type"], "string");
+        assert_eq!(json["provider"], "ollama");
+        assert_eq!(json["supports"]["streaming"], true);
+        assert_eq!(json["supports"]["tools"], false);
+
+        let text = format_entries(&entries);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("SERVICE"));
+        assert!(lines[1].starts_with("llm"));
+        assert!(lines[1].ends_with("installed"));
+        assert!(lines[1].contains("  images,streaming  "));
+        assert!(lines[2].contains("  -  "));
+        assert_eq!(lines[4], "[adapters.llm.config]");
+        assert_eq!(lines[5], "  base_url  string  Server URL");
+    }
+
+    #[test]
+    fn test_adapter_entries_invalid_manifest() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = Config::default();
+        let module_path = config
+            .adapters
+            .get_service("llm")
+            .unwrap()
+            .module_path(temp_dir.path(), "llm");
+        std::fs::create_dir_all(module_path.parent().unwrap()).unwrap();
+        std::fs::write(&module_path, b"").unwrap();
+        std::fs::write(
+            AdapterManifest::path_for_module(&module_path),
+            "required_config = \"api_key\"\n",
+        )
+        .unwrap();
+
+        let entries = adapter_entries(&config, temp_dir.path());
+
+        assert_eq!(entries[0].status, "invalid manifest");
+        assert!(entries[0].manifest.required_config.is_empty());
+    }
+
+    #[test]
+    fn test_adapter_entries_missing_module() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = Config::default();
+
+        let entries = adapter_entries(&config, temp_dir.path());
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, "missing");
+        assert!(entries[0].manifest.config_schema.is_none());
+        assert_eq!(format_entries(&[]), "No adapters configured\n");
+    }
+
+    #[tokio::test]
+    async fn test_run_with_nonexistent_config() {
+        let matches = command()
+            .try_get_matches_from(["adapter", "list", "--config", "/nonexistent/config.toml"])
+            .unwrap();
+
+        assert!(run(&matches).await.is_err());
+    }
+}
@@ -1,12 +1,172 @@
+use crate::utils::cache::{self, CleanOptions};
 use anyhow::Result;
-use clap::{ArgMatches, Command};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
 
 pub fn command() -> Command {
+    let clean = Command::new("clean")
+        .about("Remove cached files from the cache directory")
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Path to configuration file")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Only list what would be removed")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("older-than")
+                .long("older-than")
+                .value_name("AGE")
+                .help("Only remove files older than AGE (e.g. 12h, 7d, 2w)")
+                .value_parser(cache::parse_age)
+                .num_args(1),
+        )
+        .arg(super::shared::profile_arg());
+
+    let clear = Command::new("clear")
+        .about("Remove everything in the cache directory")
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Path to configuration file")
+                .num_args(1),
+        )
+        .arg(super::shared::profile_arg())
+        .arg(
+            Arg::new("yes")
+                .long("yes")
+                .short('y')
+                .help("Don't ask for confirmation")
+                .action(ArgAction::SetTrue),
+        );
+
     super::shared::create_path_command("cache", "Show the cache directory path")
+        .subcommand(clean)
+        .subcommand(clear)
 }
 
 pub async fn run(matches: &ArgMatches) -> Result<()> {
-    super::shared::run_path_command(matches, crate::config::cache_dir).await
+    match matches.subcommand() {
+        Some(("clean", sub_matches)) => run_clean(matches, sub_matches),
+        Some(("clear", sub_matches)) => run_clear(matches, sub_matches),
+        _ => super::shared::run_path_command(matches, crate::config::cache_dir).await,
+    }
+}
+
+/// Remove our files from the cache directory and print what was freed
+///
+/// Running servers can do the same with `DELETE /v1/admin/cache`.
+fn run_clean(matches: &ArgMatches, sub_matches: &ArgMatches) -> Result<()> {
+    let config_file = sub_matches
+        .get_one::<String>("config")
+        .or_else(|| matches.get_one::<String>("config"))
+        .cloned();
+    let profile = sub_matches
+        .get_one::<String>("profile")
+        .or_else(|| matches.get_one::<String>("profile"))
+        .map(String::as_str);
+    let log_level = crate::cli::options::logging::extract_log_level(matches);
+
+    if let Err(e) = crate::utils::init_logging(&log_level) {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+
+    let (config, config_dir) = crate::config::load_config_silent(config_file, profile)?;
+    let cache_dir = crate::config::cache_dir(&config, config_dir.as_deref());
+    let options = CleanOptions {
+        dry_run: sub_matches.get_flag("dry-run"),
+        older_than: sub_matches.get_one::<Duration>("older-than").copied(),
+    };
+
+    let report = cache::clean(&cache_dir, &options)?;
+
+    let verb = if options.dry_run {
+        "Would remove"
+    } else {
+        "Removed"
+    };
+    for path in &report.removed {
+        println!("{} {}", verb, cache_dir.join(path).display());
+    }
+    println!(
+        "{} {} files, {} bytes from {}",
+        verb,
+        report.removed.len(),
+        report.freed_bytes,
+        cache_dir.display()
+    );
+
+    Ok(())
+}
+
+/// Remove everything in the cache directory once the user confirms
+///
+/// Without a terminal to ask on, `--yes` is required.
+fn run_clear(matches: &ArgMatches, sub_matches: &ArgMatches) -> Result<()> {
+    let config_file = sub_matches
+        .get_one::<String>("config")
+        .or_else(|| matches.get_one::<String>("config"))
+        .cloned();
+    let profile = sub_matches
+        .get_one::<String>("profile")
+        .or_else(|| matches.get_one::<String>("profile"))
+        .map(String::as_str);
+    let log_level = crate::cli::options::logging::extract_log_level(matches);
+
+    if let Err(e) = crate::utils::init_logging(&log_level) {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+
+    let (config, config_dir) = crate::config::load_config_silent(config_file, profile)?;
+    let cache_dir = crate::config::cache_dir(&config, config_dir.as_deref());
+    if !cache_dir.exists() {
+        println!("Nothing to clear: {} does not exist", cache_dir.display());
+        return Ok(());
+    }
+    // Refuse dangerous directories before asking
+    let cache_dir = cache::check_cache_dir(&cache_dir)?;
+
+    if !sub_matches.get_flag("yes") {
+        if !std::io::stdin().is_terminal() {
+            anyhow::bail!(
+                "Refusing to clear {} without confirmation; pass --yes",
+                cache_dir.display()
+            );
+        }
+        if !confirm_clear(&mut std::io::stdin().lock(), &cache_dir)? {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    let report = cache::clear(&cache_dir)?;
+    println!(
+        "Removed {} files, {} bytes from {}",
+        report.removed.len(),
+        report.freed_bytes,
+        cache_dir.display()
+    );
+
+    Ok(())
+}
+
+/// Ask whether to clear `cache_dir`; only "y" or "yes" confirm
+fn confirm_clear(input: &mut impl BufRead, cache_dir: &Path) -> Result<bool> {
+    eprint!("Remove everything in {}? [y/N] ", cache_dir.display());
+    std::io::stderr().flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
 }
 
 #[cfg(test)]
@@ -79,6 +239,102 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_clean_subcommand_parsing() {
+        let matches = command()
+            .try_get_matches_from(["cache", "clean", "--older-than", "7d", "--dry-run"])
+            .unwrap();
+        let (name, sub_matches) = matches.subcommand().unwrap();
+
+        assert_eq!(name, "clean");
+        assert!(sub_matches.get_flag("dry-run"));
+        assert_eq!(
+            sub_matches.get_one::<Duration>("older-than"),
+            Some(&Duration::from_secs(7 * 86400))
+        );
+
+        // Ages need a unit
+        assert!(
+            command()
+                .try_get_matches_from(["cache", "clean", "--older-than", "7"])
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clean_with_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_dir = temp_dir.path().join("cache");
+        std::fs::create_dir_all(cache_dir.join("tmp")).unwrap();
+        std::fs::write(cache_dir.join("tmp/leftover"), b"x").unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+        std::fs::write(&config_file, "[storage]\ncache_dir = \"cache\"\n").unwrap();
+
+        let matches = command()
+            .try_get_matches_from(["cache", "clean", "--config", config_file.to_str().unwrap()])
+            .unwrap();
+        run(&matches).await.unwrap();
+
+        assert!(!cache_dir.join("tmp/leftover").exists());
+    }
+
+    #[test]
+    fn test_clear_subcommand_parsing() {
+        let matches = command()
+            .try_get_matches_from(["cache", "clear", "-y"])
+            .unwrap();
+        let (name, sub_matches) = matches.subcommand().unwrap();
+
+        assert_eq!(name, "clear");
+        assert!(sub_matches.get_flag("yes"));
+    }
+
+    #[tokio::test]
+    async fn test_clear_with_yes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_dir = temp_dir.path().join("cache");
+        std::fs::create_dir_all(cache_dir.join("tmp")).unwrap();
+        std::fs::write(cache_dir.join("tmp/leftover"), b"x").unwrap();
+        std::fs::write(cache_dir.join("foreign.txt"), b"y").unwrap();
+        let config_file = temp_dir.path().join("config.toml");
+        std::fs::write(&config_file, "[storage]\ncache_dir = \"cache\"\n").unwrap();
+
+        let matches = command()
+            .try_get_matches_from([
+                "cache",
+                "clear",
+                "--yes",
+                "--config",
+                config_file.to_str().unwrap(),
+            ])
+            .unwrap();
+        run(&matches).await.unwrap();
+
+        assert!(cache_dir.exists());
+        assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_confirm_clear() {
+        let cache_dir = Path::new("/tmp/cache");
+
+        for (answer, confirmed) in [
+            ("y\n", true),
+            ("YES\n", true),
+            ("n\n", false),
+            ("\n", false),
+            ("", false),
+        ] {
+            let mut input = std::io::Cursor::new(answer);
+            assert_eq!(
+                confirm_clear(&mut input, cache_dir).unwrap(),
+                confirmed,
+                "{:?}",
+                answer
+            );
+        }
+    }
+
     #[test]
     fn test_command_short_help_flag() {
         test_utils::test_path_command_help_flags("cache");
@@ -92,7 +348,7 @@ mod tests {
         assert_eq!(cmd.get_name(), "cache");
         assert!(cmd.is_disable_help_flag_set());
 
-        // Should have exactly 4 arguments: config, help, log-level, verbose
-        assert_eq!(cmd.get_arguments().count(), 4);
+        // Should have exactly 6 arguments: config, help, log-level, output, profile, verbose
+        assert_eq!(cmd.get_arguments().count(), 6);
     }
 }
@@ -0,0 +1,432 @@
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use futures::{Stream, StreamExt};
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use crate::adapter::AdapterRegistry;
+use crate::adapter::services::SharedLlm;
+use crate::adapter::stream::stream_reply;
+use crate::adapter::traits::{ChatMessage, GenerationOptions, ServiceError};
+
+/// Log level of the chat command, so logs don't interleave with replies
+const DEFAULT_CHAT_LOG_LEVEL: &str = "warn";
+
+/// Lines that end the session, besides end of input
+const EXIT_COMMANDS: [&str; 2] = ["/exit", "/quit"];
+
+/// Frames of the spinner shown until the first chunk arrives
+const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Time between spinner frames
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn command() -> Command {
+    let cmd = Command::new("chat")
+        .about("Chat with the configured LLM adapter in the terminal")
+        .disable_help_flag(true)
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Path to configuration file")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .short('l')
+                .value_name("LEVEL")
+                .help("Set the logging level")
+                .value_parser(crate::cli::options::logging::LOG_LEVEL_VALUES)
+                .default_value(DEFAULT_CHAT_LOG_LEVEL)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .help("Print chunks received, elapsed time and model after each reply")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .short('V')
+                .help("Enable verbose output (sets log-level to debug)")
+                .action(ArgAction::SetTrue),
+        );
+
+    // Apply consistent help styling
+    crate::cli::options::help::apply(cmd)
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let config_file = matches.get_one::<String>("config").cloned();
+    let log_level = crate::cli::options::logging::extract_log_level(matches);
+    let stats = matches.get_flag("stats");
+
+    // Initialize logging with the requested level
+    if let Err(e) = crate::utils::init_logging(&log_level) {
+        eprintln!("Failed to initialize logging: {}", e);
+        // Continue without logging rather than fail
+    }
+
+    let (config, config_dir) = if log_level == "debug" {
+        crate::config::load_config(config_file, None)?
+    } else {
+        crate::config::load_config_silent(config_file, None)?
+    };
+    let data_dir = crate::config::data_dir(&config, config_dir.as_deref());
+
+    crate::adapter::http::configure_shared_client(&config.http)?;
+    let mut registry = AdapterRegistry::new().await?;
+    registry
+        .initialize_from_config(&config, &data_dir)
+        .await
+        .context("Failed to load adapters")?;
+    let llm = match registry.llm_adapter_for(&config) {
+        Some(llm) => llm.clone(),
+        // Failures are recorded rather than returned; report the cause
+        None => match registry.failed_adapters().get("llm") {
+            Some(failure) => anyhow::bail!(
+                "LLM adapter '{}' failed to load: {}",
+                failure.provider,
+                failure.error
+            ),
+            None => anyhow::bail!("No LLM adapter configured"),
+        },
+    };
+
+    let session = Session {
+        buffer: config.server.stream_buffer,
+        llm,
+        options: crate::server::state::generation_defaults(&config),
+        stats,
+    };
+    let result = session.repl().await;
+
+    registry.shutdown().await?;
+    result
+}
+
+/// An interactive conversation with one LLM adapter
+struct Session {
+    buffer: usize,
+    llm: SharedLlm,
+    options: GenerationOptions,
+    stats: bool,
+}
+
+impl Session {
+    /// Read prompts until end of input, `/exit` or Ctrl-C at the prompt
+    ///
+    /// Ctrl-C while a reply is generated cancels it and returns to the prompt.
+    async fn repl(&self) -> Result<()> {
+        let spinner = std::io::stdout().is_terminal();
+        let mut lines = stdin_lines();
+        let mut conversation: Vec<ChatMessage> = Vec::new();
+
+        println!("Type {} or press Ctrl-D to leave.", EXIT_COMMANDS[0]);
+        loop {
+            print!("> ");
+            std::io::stdout().flush()?;
+
+            let line = tokio::select! {
+                line = lines.recv() => line.transpose()?,
+                _ = tokio::signal::ctrl_c() => None,
+            };
+            let Some(line) = line else {
+                println!();
+                return Ok(());
+            };
+            let prompt = line.trim();
+            if prompt.is_empty() {
+                continue;
+            }
+            if EXIT_COMMANDS.contains(&prompt) {
+                return Ok(());
+            }
+
+            conversation.push(ChatMessage {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+                parts: None,
+            });
+
+            let started = Instant::now();
+            let chunks = stream_reply(
+                self.llm.clone(),
+                conversation.clone(),
+                self.options.clone(),
+                self.buffer,
+            );
+            let cancel = async {
+                let _ = tokio::signal::ctrl_c().await;
+            };
+            let rendered = render_reply(chunks, &mut std::io::stdout(), cancel, spinner).await;
+            println!();
+
+            match rendered {
+                Ok(reply) if reply.cancelled => {
+                    // Leave the cancelled prompt out of the conversation
+                    conversation.pop();
+                    eprintln!("(cancelled)");
+                }
+                Ok(reply) => {
+                    if self.stats {
+                        let llm = self.llm.read().await;
+                        let model = llm.model().unwrap_or("default model");
+                        eprintln!(
+                            "[{}]",
+                            format_stats(
+                                reply.chunks,
+                                started.elapsed(),
+                                llm.provider_name(),
+                                model
+                            )
+                        );
+                    }
+                    conversation.push(ChatMessage {
+                        role: "assistant".to_string(),
+                        content: reply.text,
+                        parts: None,
+                    });
+                }
+                Err(e) => {
+                    conversation.pop();
+                    eprintln!("Error: {:#}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Lines of stdin, read on their own thread
+///
+/// A read pending on tokio's stdin would hold up runtime shutdown until
+/// the next line is entered, e.g. after Ctrl-C at the prompt.
+fn stdin_lines() -> mpsc::UnboundedReceiver<std::io::Result<String>> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+/// A reply as far as it was rendered
+#[derive(Debug, Default, PartialEq)]
+struct RenderedReply {
+    /// Chunks received, about one token each for streaming adapters
+    chunks: usize,
+    /// Whether rendering stopped because `cancel` completed
+    cancelled: bool,
+    text: String,
+}
+
+/// Write each chunk of `chunks` to `out` as it arrives
+///
+/// Until the first chunk, a spinner is drawn in its place if `spinner` is
+/// set. When `cancel` completes first, `chunks` is dropped, which cancels
+/// generation, and the partial reply is returned.
+async fn render_reply<S, W, C>(
+    chunks: S,
+    out: &mut W,
+    cancel: C,
+    spinner: bool,
+) -> Result<RenderedReply>
+where
+    S: Stream<Item = Result<String, ServiceError>> + Unpin,
+    W: Write,
+    C: Future<Output = ()>,
+{
+    let mut chunks = chunks;
+    let mut reply = RenderedReply::default();
+    let mut ticker = tokio::time::interval(SPINNER_INTERVAL);
+    let mut frame = 0;
+    tokio::pin!(cancel);
+
+    loop {
+        let spinning = spinner && reply.chunks == 0;
+        tokio::select! {
+            biased;
+            _ = &mut cancel => {
+                reply.cancelled = true;
+                break;
+            }
+            chunk = chunks.next() => {
+                let Some(chunk) = chunk else {
+                    break;
+                };
+                if spinning && frame > 0 {
+                    // Clear the spinner before the first chunk takes its place
+                    write!(out, "\r \r")?;
+                }
+                let chunk = chunk?;
+                write!(out, "{}", chunk)?;
+                out.flush()?;
+                reply.chunks += 1;
+                reply.text.push_str(&chunk);
+            }
+            _ = ticker.tick(), if spinning => {
+                write!(out, "\r{}", SPINNER_FRAMES[frame % SPINNER_FRAMES.len()])?;
+                out.flush()?;
+                frame += 1;
+            }
+        }
+    }
+
+    if spinner && reply.chunks == 0 && frame > 0 {
+        write!(out, "\r \r")?;
+        out.flush()?;
+    }
+
+    Ok(reply)
+}
+
+/// One-line summary of a reply for `--stats`
+///
+/// Streams don't report token usage, so the chunks received are counted;
+/// they're only about one token each.
+fn format_stats(chunks: usize, elapsed: Duration, provider: &str, model: &str) -> String {
+    format!(
+        "{} chunks in {:.2}s, {}/{}",
+        chunks,
+        elapsed.as_secs_f64(),
+        provider,
+        model
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    /// Writer remembering what had been written at each flush
+    #[derive(Default)]
+    struct Flushes {
+        pending: Vec<u8>,
+        flushed: Vec<String>,
+    }
+
+    impl Write for Flushes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.pending.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            let pending = std::mem::take(&mut self.pending);
+            self.flushed.push(String::from_utf8(pending).unwrap());
+            Ok(())
+        }
+    }
+
+    fn scripted(chunks: &[&str]) -> impl Stream<Item = Result<String, ServiceError>> + Unpin {
+        stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok(chunk.to_string()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_render_reply_flushes_each_chunk() {
+        let mut out = Flushes::default();
+
+        let reply = render_reply(
+            scripted(&["Hel", "lo", " world"]),
+            &mut out,
+            std::future::pending(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(out.flushed, ["Hel", "lo", " world"]);
+        assert_eq!(
+            reply,
+            RenderedReply {
+                chunks: 3,
+                cancelled: false,
+                text: "Hello world".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_reply_reports_failure_after_partial_output() {
+        let mut out = Flushes::default();
+        let chunks = scripted(&["Hel"]).chain(stream::iter([Err(ServiceError::ExecutionError(
+            "provider went away".to_string(),
+        ))]));
+
+        let error = render_reply(chunks, &mut out, std::future::pending(), false)
+            .await
+            .unwrap_err();
+
+        assert_eq!(out.flushed, ["Hel"]);
+        assert!(error.to_string().contains("provider went away"));
+    }
+
+    #[tokio::test]
+    async fn test_render_reply_cancel_drops_stream() {
+        let (sender, receiver) = mpsc::channel::<Result<String, ServiceError>>(4);
+        sender.send(Ok("Hel".to_string())).await.unwrap();
+        let chunks = Box::pin(stream::unfold(receiver, |mut receiver| async move {
+            let chunk = receiver.recv().await?;
+            Some((chunk, receiver))
+        }));
+        let mut out = Flushes::default();
+
+        // Cancel once the first chunk is out, with the rest still pending
+        let cancel = tokio::time::sleep(Duration::from_millis(20));
+        let reply = render_reply(chunks, &mut out, cancel, false).await.unwrap();
+
+        assert!(reply.cancelled);
+        assert_eq!(reply.text, "Hel");
+        assert_eq!(out.flushed, ["Hel"]);
+        // The upstream side sees the stream went away
+        assert!(sender.is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_render_reply_clears_spinner() {
+        let chunks = Box::pin(stream::once(async {
+            tokio::time::sleep(SPINNER_INTERVAL * 2).await;
+            Ok::<_, ServiceError>("Hi".to_string())
+        }));
+        let mut out = Flushes::default();
+
+        render_reply(chunks, &mut out, std::future::pending(), true)
+            .await
+            .unwrap();
+
+        let written = out.flushed.concat();
+        assert!(written.starts_with('\r'));
+        assert!(written.ends_with("\r \rHi"));
+    }
+
+    #[test]
+    fn test_format_stats() {
+        assert_eq!(
+            format_stats(42, Duration::from_millis(1500), "ollama", "llama3.2"),
+            "42 chunks in 1.50s, ollama/llama3.2"
+        );
+    }
+}
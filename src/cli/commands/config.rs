@@ -0,0 +1,254 @@
+use crate::server::runtime_info::{ResolvedRuntimeInfo, config_toml};
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+
+pub fn command() -> Command {
+    let init = Command::new("init")
+        .about("Write a commented config file to start from")
+        .disable_help_flag(true)
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .help("Overwrite an existing config file")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        )
+        .arg(
+            Arg::new("path")
+                .long("path")
+                .value_name("FILE")
+                .help("Where to write the config file (default: the platform config location)")
+                .value_parser(clap::value_parser!(PathBuf))
+                .num_args(1),
+        );
+
+    let schema = Command::new("schema")
+        .about("Print the JSON Schema of the config file format")
+        .disable_help_flag(true)
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        );
+
+    let show = Command::new("show")
+        .about("Print the resolved runtime parameters as JSON, secrets redacted")
+        .disable_help_flag(true)
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Path to configuration file")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("json for the runtime parameters, toml for the effective config")
+                .value_parser(["json", "toml"])
+                .default_value("json")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        )
+        .arg(super::shared::profile_arg())
+        .arg(
+            Arg::new("show-secrets")
+                .long("show-secrets")
+                .help("Show secrets in the TOML output instead of redacting them")
+                .action(ArgAction::SetTrue),
+        );
+
+    let cmd = Command::new("config")
+        .about("Manage the configuration file")
+        .disable_help_flag(true)
+        .disable_help_subcommand(true)
+        .subcommand_required(true)
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        )
+        .subcommand(crate::cli::options::help::apply(init))
+        .subcommand(crate::cli::options::help::apply(schema))
+        .subcommand(crate::cli::options::help::apply(show));
+
+    // Apply consistent help styling
+    crate::cli::options::help::apply(cmd)
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("init", sub_m)) => run_init(sub_m),
+        Some(("schema", _)) => run_schema(),
+        Some(("show", sub_m)) => run_show(sub_m),
+        _ => unreachable!("config requires a subcommand"),
+    }
+}
+
+/// Write the config template and tell the user where it went
+fn run_init(matches: &ArgMatches) -> Result<()> {
+    let path = matches
+        .get_one::<PathBuf>("path")
+        .cloned()
+        .unwrap_or_else(crate::config::defaults::platform_config_file);
+
+    crate::config::creation::create_config_template(&path, matches.get_flag("force"))?;
+    println!("Created config file: {}", path.display());
+
+    Ok(())
+}
+
+/// Print the config JSON Schema, e.g. for editors or CI validation
+fn run_schema() -> Result<()> {
+    let schema = crate::config::Config::json_schema();
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+
+    Ok(())
+}
+
+/// Print what `serve` would run with, like `serve --print-config`
+///
+/// Command line overrides of `serve` don't apply and no adapters are
+/// loaded, so `loaded` is null in JSON.
+fn run_show(matches: &ArgMatches) -> Result<()> {
+    let config_file = matches.get_one::<String>("config").cloned();
+    let profile = matches.get_one::<String>("profile").map(String::as_str);
+    let show_secrets = matches.get_flag("show-secrets");
+    let (config, config_dir) = crate::config::load_config_silent(config_file, profile)?;
+
+    if matches.get_one::<String>("format").map(String::as_str) == Some("toml") {
+        print!("{}", config_toml(&config, show_secrets)?);
+        return Ok(());
+    }
+    // The runtime parameters never include secrets
+    if show_secrets {
+        anyhow::bail!("--show-secrets only applies to --format toml");
+    }
+    let info = ResolvedRuntimeInfo::from_config(&config, config_dir.as_deref());
+    println!("{}", serde_json::to_string_pretty(&info)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_structure() {
+        let cmd = command();
+
+        assert_eq!(cmd.get_name(), "config");
+        assert!(cmd.is_subcommand_required_set());
+        assert!(cmd.find_subcommand("init").is_some());
+        assert!(cmd.find_subcommand("schema").is_some());
+        assert!(cmd.find_subcommand("show").is_some());
+
+        // A subcommand is required
+        assert!(command().try_get_matches_from(["config"]).is_err());
+    }
+
+    #[test]
+    fn test_init_parsing() {
+        let matches = command()
+            .try_get_matches_from(["config", "init", "--path", "my.toml", "--force"])
+            .unwrap();
+        let (name, sub_matches) = matches.subcommand().unwrap();
+
+        assert_eq!(name, "init");
+        assert!(sub_matches.get_flag("force"));
+        assert_eq!(
+            sub_matches.get_one::<PathBuf>("path"),
+            Some(&PathBuf::from("my.toml"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_init() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let path_arg = path.to_str().unwrap();
+
+        let matches = command()
+            .try_get_matches_from(["config", "init", "--path", path_arg])
+            .unwrap();
+        run(&matches).await.unwrap();
+        let (config, _) = crate::config::load_config(Some(path_arg.to_string()), None).unwrap();
+        assert_eq!(config.server.port, 8080);
+
+        // Running it again would lose edits
+        assert!(run(&matches).await.is_err());
+
+        let matches = command()
+            .try_get_matches_from(["config", "init", "--path", path_arg, "--force"])
+            .unwrap();
+        assert!(run(&matches).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_run_schema() {
+        let matches = command()
+            .try_get_matches_from(["config", "schema"])
+            .unwrap();
+
+        assert!(run(&matches).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_run_show() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, "[server]\nport = 9000\n").unwrap();
+
+        let matches = command()
+            .try_get_matches_from(["config", "show", "--config", path.to_str().unwrap()])
+            .unwrap();
+        assert!(run(&matches).await.is_ok());
+
+        let matches = command()
+            .try_get_matches_from([
+                "config",
+                "show",
+                "--config",
+                path.to_str().unwrap(),
+                "--format",
+                "toml",
+            ])
+            .unwrap();
+        assert!(run(&matches).await.is_ok());
+
+        let matches = command()
+            .try_get_matches_from([
+                "config",
+                "show",
+                "--config",
+                path.to_str().unwrap(),
+                "--show-secrets",
+            ])
+            .unwrap();
+        assert!(run(&matches).await.is_err());
+
+        let matches = command()
+            .try_get_matches_from(["config", "show", "--config", "/nonexistent/config.toml"])
+            .unwrap();
+        assert!(run(&matches).await.is_err());
+    }
+}
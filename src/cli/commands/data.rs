@@ -1,12 +1,63 @@
+use crate::adapter::keys::{KEY_ENCODING_VERSION, migrate_keys};
 use anyhow::Result;
-use clap::{ArgMatches, Command};
+use clap::{Arg, ArgMatches, Command};
 
 pub fn command() -> Command {
-    super::shared::create_path_command("data", "Show the data directory path")
+    super::shared::create_path_command("data", "Show the data directory path").subcommand(
+        Command::new("migrate-keys")
+            .about("Rewrite stored keys into the current storage key encoding")
+            .arg(
+                Arg::new("config")
+                    .long("config")
+                    .value_name("FILE")
+                    .help("Path to configuration file")
+                    .num_args(1),
+            )
+            .arg(super::shared::profile_arg()),
+    )
 }
 
 pub async fn run(matches: &ArgMatches) -> Result<()> {
-    super::shared::run_path_command(matches, crate::config::data_dir).await
+    match matches.subcommand() {
+        Some(("migrate-keys", sub_matches)) => run_migrate_keys(matches, sub_matches).await,
+        _ => super::shared::run_path_command(matches, crate::config::data_dir).await,
+    }
+}
+
+/// Move keys written before the key encoding to their encoded form
+///
+/// Safe to run repeatedly: keys that are already encoded are left alone.
+async fn run_migrate_keys(matches: &ArgMatches, sub_matches: &ArgMatches) -> Result<()> {
+    let config_file = sub_matches
+        .get_one::<String>("config")
+        .or_else(|| matches.get_one::<String>("config"))
+        .cloned();
+    let profile = sub_matches
+        .get_one::<String>("profile")
+        .or_else(|| matches.get_one::<String>("profile"))
+        .map(String::as_str);
+    let log_level = crate::cli::options::logging::extract_log_level(matches);
+
+    if let Err(e) = crate::utils::init_logging(&log_level) {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+
+    let (config, config_dir) = crate::config::load_config_silent(config_file, profile)?;
+    let data_dir = crate::config::data_dir(&config, config_dir.as_deref());
+
+    let mut storage = crate::server::state::load_raw_storage(&config, &data_dir).await?;
+    let migration = migrate_keys(&mut *storage).await?;
+    storage.shutdown().await?;
+
+    println!(
+        "Key encoding v{}: {} keys migrated, {} already encoded",
+        KEY_ENCODING_VERSION, migration.migrated, migration.unchanged
+    );
+    for key in &migration.conflicts {
+        eprintln!("Skipped '{}': its encoded key is already in use", key);
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -70,8 +121,8 @@ mod tests {
         assert_eq!(cmd.get_name(), "data");
         assert!(cmd.is_disable_help_flag_set());
 
-        // Should have exactly 4 arguments: config, help, log-level, verbose
-        assert_eq!(cmd.get_arguments().count(), 4);
+        // Should have exactly 6 arguments: config, help, log-level, output, profile, verbose
+        assert_eq!(cmd.get_arguments().count(), 6);
     }
 
     #[test]
@@ -113,4 +164,68 @@ mod tests {
         let result = run(&matches).await;
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_command_has_migrate_keys_subcommand() {
+        let matches = command()
+            .try_get_matches_from(["data", "migrate-keys", "--config", "/tmp/config.toml"])
+            .unwrap();
+
+        let (name, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(name, "migrate-keys");
+        assert_eq!(
+            sub_matches.get_one::<String>("config").unwrap(),
+            "/tmp/config.toml"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migrate_keys_rewrites_legacy_keys() {
+        use crate::adapter::services::sqlite::SqliteStorage;
+        use crate::adapter::traits::StorageAdapter;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            format!(
+                "[storage]\ndata_dir = {:?}\n\n[adapters.storage]\nprovider = \"sqlite\"\n",
+                temp_dir.path()
+            ),
+        )
+        .unwrap();
+
+        let database = temp_dir.path().join("storage.sqlite3");
+        let mut storage = SqliteStorage::open(database.clone()).await.unwrap();
+        storage
+            .store("conversation/grüße", b"legacy")
+            .await
+            .unwrap();
+        storage
+            .store("sender/default/profile", b"{}")
+            .await
+            .unwrap();
+        drop(storage);
+
+        let config_arg = config_path.to_string_lossy().to_string();
+        let matches = command()
+            .try_get_matches_from(["data", "migrate-keys", "--config", &config_arg])
+            .unwrap();
+        run(&matches).await.unwrap();
+
+        let storage = SqliteStorage::open(database).await.unwrap();
+        let mut keys = storage.list_keys(None).await.unwrap();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec!["conversation/gr%C3%BC%C3%9Fe", "sender/default/profile"]
+        );
+        assert_eq!(
+            storage
+                .retrieve("conversation/gr%C3%BC%C3%9Fe")
+                .await
+                .unwrap(),
+            b"legacy"
+        );
+    }
 }
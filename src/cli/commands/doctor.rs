@@ -0,0 +1,632 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde::Serialize;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::{Config, discovery, schema::ServiceAdapterConfig};
+
+/// Default Ollama endpoint used when the LLM adapter config has no `base_url`
+const DEFAULT_LLM_BASE_URL: &str = "http://localhost:11434";
+
+/// Timeout for the LLM endpoint reachability probe
+const ENDPOINT_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Name of the probe file used to test directory writability
+const WRITE_PROBE_FILE: &str = ".ai_messenger_doctor";
+
+pub fn command() -> Command {
+    let cmd = Command::new("doctor")
+        .about("Diagnose common setup problems")
+        .disable_help_flag(true)
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Path to configuration file")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print results as JSON")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .short('l')
+                .value_name("LEVEL")
+                .help("Set the logging level")
+                .value_parser(crate::cli::options::logging::LOG_LEVEL_VALUES)
+                .default_value(crate::cli::options::logging::DEFAULT_LOG_LEVEL)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .short('V')
+                .help("Enable verbose output (sets log-level to debug)")
+                .action(ArgAction::SetTrue),
+        );
+
+    // Apply consistent help styling
+    crate::cli::options::help::apply(cmd)
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    let config_file = matches.get_one::<String>("config").cloned();
+    let json = matches.get_flag("json");
+    let log_level = crate::cli::options::logging::extract_log_level(matches);
+
+    // Initialize logging with the requested level
+    if let Err(e) = crate::utils::init_logging(&log_level) {
+        eprintln!("Failed to initialize logging: {}", e);
+        // Continue without logging rather than fail
+    }
+
+    let report = run_checks(config_file.as_deref());
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    if !report.passed {
+        let failed = report
+            .checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Fail)
+            .count();
+        anyhow::bail!("{} check(s) failed", failed);
+    }
+
+    Ok(())
+}
+
+/// Outcome of a single diagnostic check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Fail,
+    Pass,
+    Warn,
+}
+
+/// Result of a single diagnostic check
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    pub message: String,
+    pub name: String,
+    pub status: CheckStatus,
+}
+
+impl CheckResult {
+    fn pass(name: &str, message: impl Into<String>) -> Self {
+        CheckResult {
+            hint: None,
+            message: message.into(),
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+        }
+    }
+
+    fn warn(name: &str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        CheckResult {
+            hint: Some(hint.into()),
+            message: message.into(),
+            name: name.to_string(),
+            status: CheckStatus::Warn,
+        }
+    }
+
+    fn fail(name: &str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        CheckResult {
+            hint: Some(hint.into()),
+            message: message.into(),
+            name: name.to_string(),
+            status: CheckStatus::Fail,
+        }
+    }
+}
+
+/// Full diagnostic report
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<CheckResult>,
+    pub passed: bool,
+}
+
+/// Run all checks in order and collect the results
+pub fn run_checks(config_file: Option<&str>) -> DoctorReport {
+    let mut checks = Vec::new();
+
+    let (config_check, config, config_dir) = check_config(config_file);
+    checks.push(config_check);
+
+    let data_dir = crate::config::data_dir(&config, config_dir.as_deref());
+    let cache_dir = crate::config::cache_dir(&config, config_dir.as_deref());
+    checks.push(check_dir_writable("data_dir", &data_dir));
+    checks.push(check_dir_writable("cache_dir", &cache_dir));
+
+    // Sort services for deterministic output
+    let mut services: Vec<_> = config.adapters.services.iter().collect();
+    services.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (service, adapter_config) in services {
+        checks.push(check_adapter_module(service, adapter_config, &data_dir));
+    }
+
+    if let Some(llm_config) = config.adapters.get_service("llm") {
+        checks.push(check_endpoint_reachable(&llm_base_url(llm_config)));
+    }
+
+    checks.push(check_port_available(
+        &config.server.host,
+        config.server.port,
+    ));
+
+    let passed = checks.iter().all(|check| check.status != CheckStatus::Fail);
+
+    DoctorReport { checks, passed }
+}
+
+/// Check that a config file is discoverable and parseable
+///
+/// Returns the check result together with the effective config and its directory,
+/// so that later checks can run against the same configuration.
+pub fn check_config(config_file: Option<&str>) -> (CheckResult, Config, Option<PathBuf>) {
+    const NAME: &str = "config";
+    let profile = crate::config::profiles::selected_profile(None);
+
+    if let Some(path) = config_file {
+        return match discovery::load_from_file(path, profile.as_deref()) {
+            Ok((config, config_dir)) => (
+                CheckResult::pass(NAME, format!("Loaded {}", path)),
+                config,
+                Some(config_dir),
+            ),
+            Err(e) => (
+                CheckResult::fail(
+                    NAME,
+                    format!("{:#}", e),
+                    "Check that the file passed to --config exists and is valid TOML",
+                ),
+                Config::default(),
+                None,
+            ),
+        };
+    }
+
+    for (_, path, exists) in discovery::list_config_locations() {
+        if !exists {
+            continue;
+        }
+
+        // The first existing file wins, just like the fallback chain
+        return match discovery::load_from_file(&path, profile.as_deref()) {
+            Ok((config, config_dir)) => (
+                CheckResult::pass(NAME, format!("Loaded {}", path.display())),
+                config,
+                Some(config_dir),
+            ),
+            Err(e) => (
+                CheckResult::fail(
+                    NAME,
+                    format!("{:#}", e),
+                    format!("Fix the TOML syntax in {}", path.display()),
+                ),
+                Config::default(),
+                None,
+            ),
+        };
+    }
+
+    (
+        CheckResult::warn(
+            NAME,
+            "No config file found, using defaults",
+            "Copy example_config.toml to ./ai_messenger.toml to customize settings",
+        ),
+        Config::default(),
+        None,
+    )
+}
+
+/// Check that a directory exists and is writable
+pub fn check_dir_writable(name: &str, path: &Path) -> CheckResult {
+    if !path.exists() {
+        return CheckResult::warn(
+            name,
+            format!("{} does not exist yet", path.display()),
+            format!("Create it with: mkdir -p {}", path.display()),
+        );
+    }
+
+    if !path.is_dir() {
+        return CheckResult::fail(
+            name,
+            format!("{} is not a directory", path.display()),
+            "Point the setting at a directory instead of a file",
+        );
+    }
+
+    let probe = path.join(WRITE_PROBE_FILE);
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            CheckResult::pass(name, format!("{} is writable", path.display()))
+        }
+        Err(e) => CheckResult::fail(
+            name,
+            format!("{} is not writable: {}", path.display(), e),
+            "Fix the directory permissions or configure another location",
+        ),
+    }
+}
+
+/// Check that an adapter module exists and compiles as a WASM component
+pub fn check_adapter_module(
+    service: &str,
+    adapter_config: &ServiceAdapterConfig,
+    data_dir: &Path,
+) -> CheckResult {
+    let name = format!("adapter.{}", service);
+    let module_path = adapter_config.module_path(data_dir, service);
+
+    if !module_path.exists() {
+        return CheckResult::fail(
+            &name,
+            format!(
+                "{}@{} not found at {}",
+                adapter_config.provider,
+                adapter_config.version,
+                module_path.display()
+            ),
+            format!(
+                "Install the {} adapter to {}",
+                adapter_config.provider,
+                module_path.display()
+            ),
+        );
+    }
+
+    let mut wasm_config = wasmtime::Config::new();
+    wasm_config.wasm_component_model(true);
+
+    let compiled = wasmtime::Engine::new(&wasm_config)
+        .and_then(|engine| wasmtime::component::Component::from_file(&engine, &module_path));
+
+    match compiled {
+        Ok(_) => CheckResult::pass(
+            &name,
+            format!(
+                "{}@{} compiled from {}",
+                adapter_config.provider,
+                adapter_config.version,
+                module_path.display()
+            ),
+        ),
+        Err(e) => CheckResult::fail(
+            &name,
+            format!("{} failed to compile: {}", module_path.display(), e),
+            "Reinstall the adapter; it must be a valid WASM component",
+        ),
+    }
+}
+
+/// Check that the LLM endpoint accepts TCP connections
+pub fn check_endpoint_reachable(base_url: &str) -> CheckResult {
+    const NAME: &str = "llm_endpoint";
+
+    let url = match reqwest::Url::parse(base_url) {
+        Ok(url) => url,
+        Err(e) => {
+            return CheckResult::fail(
+                NAME,
+                format!("Invalid base_url {}: {}", base_url, e),
+                "Set adapters.llm.config.base_url to a valid URL",
+            );
+        }
+    };
+
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return CheckResult::fail(
+            NAME,
+            format!("base_url {} has no host or port", base_url),
+            "Set adapters.llm.config.base_url to a URL like http://localhost:11434",
+        );
+    };
+
+    let addrs: Vec<_> = match (host, port).to_socket_addrs() {
+        Ok(addrs) => addrs.collect(),
+        Err(e) => {
+            return CheckResult::fail(
+                NAME,
+                format!("Cannot resolve {}: {}", host, e),
+                "Check the host name in adapters.llm.config.base_url",
+            );
+        }
+    };
+
+    let reachable = addrs
+        .iter()
+        .any(|addr| TcpStream::connect_timeout(addr, ENDPOINT_CONNECT_TIMEOUT).is_ok());
+
+    if reachable {
+        CheckResult::pass(NAME, format!("{} is reachable", base_url))
+    } else {
+        CheckResult::fail(
+            NAME,
+            format!("{} is not reachable", base_url),
+            "Start the LLM provider (e.g. `ollama serve`) or fix adapters.llm.config.base_url",
+        )
+    }
+}
+
+/// Check that the server port can be bound
+pub fn check_port_available(host: &str, port: u16) -> CheckResult {
+    const NAME: &str = "server_port";
+
+    match TcpListener::bind((host, port)) {
+        Ok(_) => CheckResult::pass(NAME, format!("{}:{} is available", host, port)),
+        Err(e) => CheckResult::fail(
+            NAME,
+            format!("Cannot bind {}:{}: {}", host, port, e),
+            "Stop the process using the port or pass --port to serve",
+        ),
+    }
+}
+
+/// Extract the LLM base URL from adapter config, falling back to the Ollama default
+fn llm_base_url(llm_config: &ServiceAdapterConfig) -> String {
+    llm_config
+        .config
+        .get("base_url")
+        .and_then(|value| value.as_str())
+        .unwrap_or(DEFAULT_LLM_BASE_URL)
+        .to_string()
+}
+
+/// Print a human-readable report
+fn print_report(report: &DoctorReport) {
+    for check in &report.checks {
+        let icon = match check.status {
+            CheckStatus::Fail => "❌",
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️ ",
+        };
+        println!("{} {}: {}", icon, check.name, check.message);
+
+        if let Some(hint) = &check.hint {
+            println!("   hint: {}", hint);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_command_creation() {
+        let cmd = command();
+
+        assert_eq!(cmd.get_name(), "doctor");
+        let about_str = format!("{}", cmd.get_about().unwrap());
+        assert_eq!(about_str, "Diagnose common setup problems");
+    }
+
+    #[test]
+    fn test_command_has_required_args() {
+        let cmd = command();
+
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "config"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "help"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "json"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "log-level"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "verbose"));
+        assert_eq!(cmd.get_arguments().count(), 5);
+    }
+
+    #[test]
+    fn test_command_json_flag() {
+        let cmd = command();
+        let matches = cmd.try_get_matches_from(["doctor", "--json"]).unwrap();
+
+        assert!(matches.get_flag("json"));
+    }
+
+    #[test]
+    fn test_check_config_with_valid_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(&config_path, "[server]\nport = 4000\n").unwrap();
+
+        let (check, config, config_dir) = check_config(Some(&config_path.to_string_lossy()));
+
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert_eq!(config.server.port, 4000);
+        assert!(config_dir.is_some());
+    }
+
+    #[test]
+    fn test_check_config_with_invalid_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("invalid.toml");
+        fs::write(&config_path, "[server\nhost = \"broken\n").unwrap();
+
+        let (check, config, config_dir) = check_config(Some(&config_path.to_string_lossy()));
+
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.hint.is_some());
+        assert_eq!(config.server.port, 8080);
+        assert!(config_dir.is_none());
+    }
+
+    #[test]
+    fn test_check_config_with_missing_file() {
+        let (check, _, _) = check_config(Some("/nonexistent/config.toml"));
+
+        assert_eq!(check.status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_check_dir_writable_existing() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let check = check_dir_writable("data_dir", temp_dir.path());
+
+        assert_eq!(check.status, CheckStatus::Pass);
+        // Probe file should be cleaned up
+        assert!(!temp_dir.path().join(WRITE_PROBE_FILE).exists());
+    }
+
+    #[test]
+    fn test_check_dir_writable_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing");
+
+        let check = check_dir_writable("data_dir", &missing);
+
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.hint.unwrap().contains("mkdir -p"));
+    }
+
+    #[test]
+    fn test_check_dir_writable_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("file");
+        fs::write(&file_path, "not a dir").unwrap();
+
+        let check = check_dir_writable("data_dir", &file_path);
+
+        assert_eq!(check.status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_check_adapter_module_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let adapter_config = Config::default().adapters.services["llm"].clone();
+
+        let check = check_adapter_module("llm", &adapter_config, temp_dir.path());
+
+        assert_eq!(check.name, "adapter.llm");
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.message.contains("ollama@latest"));
+    }
+
+    #[test]
+    fn test_check_adapter_module_invalid() {
+        let temp_dir = TempDir::new().unwrap();
+        let adapter_config = Config::default().adapters.services["llm"].clone();
+        let module_path = adapter_config.module_path(temp_dir.path(), "llm");
+        fs::create_dir_all(module_path.parent().unwrap()).unwrap();
+        fs::write(&module_path, b"not wasm").unwrap();
+
+        let check = check_adapter_module("llm", &adapter_config, temp_dir.path());
+
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.message.contains("failed to compile"));
+    }
+
+    #[test]
+    fn test_check_endpoint_reachable_bound() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let check = check_endpoint_reachable(&format!("http://127.0.0.1:{}", port));
+
+        assert_eq!(check.status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_check_endpoint_reachable_unbound() {
+        // Bind and drop to get a port that is very likely free
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let check = check_endpoint_reachable(&format!("http://127.0.0.1:{}", port));
+
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.hint.is_some());
+    }
+
+    #[test]
+    fn test_check_endpoint_reachable_invalid_url() {
+        let check = check_endpoint_reachable("not a url");
+
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.message.contains("Invalid base_url"));
+    }
+
+    #[test]
+    fn test_check_port_available_free() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let check = check_port_available("127.0.0.1", port);
+
+        assert_eq!(check.status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_check_port_available_in_use() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let check = check_port_available("127.0.0.1", port);
+
+        assert_eq!(check.status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_llm_base_url_default_and_override() {
+        let mut adapter_config = Config::default().adapters.services["llm"].clone();
+        assert_eq!(llm_base_url(&adapter_config), DEFAULT_LLM_BASE_URL);
+
+        let mut table = toml::Table::new();
+        table.insert(
+            "base_url".to_string(),
+            toml::Value::String("http://example.com:1234".to_string()),
+        );
+        adapter_config.config = toml::Value::Table(table);
+        assert_eq!(llm_base_url(&adapter_config), "http://example.com:1234");
+    }
+
+    #[test]
+    fn test_report_serializes_to_json() {
+        let report = DoctorReport {
+            checks: vec![
+                CheckResult::pass("config", "ok"),
+                CheckResult::fail("server_port", "in use", "free it"),
+            ],
+            passed: false,
+        };
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["passed"], false);
+        assert_eq!(json["checks"][0]["status"], "pass");
+        assert!(json["checks"][0].get("hint").is_none());
+        assert_eq!(json["checks"][1]["status"], "fail");
+        assert_eq!(json["checks"][1]["hint"], "free it");
+    }
+}
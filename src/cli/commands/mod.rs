@@ -1,4 +1,9 @@
+pub mod adapter;
 pub mod cache;
+pub mod chat;
+pub mod config;
 pub mod data;
+pub mod doctor;
 pub mod serve;
 pub mod shared;
+pub mod usage;
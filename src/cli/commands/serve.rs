@@ -1,12 +1,32 @@
-use crate::config::defaults::{DEFAULT_SERVER_HOST, DEFAULT_SERVER_PORT, DEFAULT_SERVER_PORT_STR};
+use crate::config::Config;
+use crate::config::defaults::{
+    DEFAULT_CREATE_DIRS, DEFAULT_SERVER_HOST, DEFAULT_SERVER_PORT, DEFAULT_SERVER_PORT_STR,
+};
+use crate::utils::LogFormat;
 use anyhow::Result;
 use clap::parser::ValueSource;
 use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+
+/// Formats of --print-config
+pub const PRINT_CONFIG_VALUES: [&str; 2] = ["json", "toml"];
+
+/// Format of --print-config without a value
+pub const DEFAULT_PRINT_CONFIG: &str = "toml";
+
+/// Environment variable of the deprecated --no-config-write
+pub const NO_CONFIG_WRITE_ENV: &str = "AI_MESSENGER_NO_CONFIG_WRITE";
 
 pub fn command() -> Command {
     let cmd = Command::new("serve")
         .about("Start the ai_messenger service")
         .disable_help_flag(true)
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .help("Load the config and adapters, then exit without serving")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("config")
                 .long("config")
@@ -14,6 +34,26 @@ pub fn command() -> Command {
                 .help("Path to configuration file")
                 .num_args(1),
         )
+        .arg(
+            Arg::new("create-dirs")
+                .long("create-dirs")
+                .value_name("BOOL")
+                .help(format!(
+                    "Create missing data and cache directories on startup (default: {})",
+                    DEFAULT_CREATE_DIRS
+                ))
+                .value_parser(clap::value_parser!(bool))
+                .default_value(if DEFAULT_CREATE_DIRS { "true" } else { "false" })
+                .default_missing_value("true")
+                .num_args(0..=1),
+        )
+        .arg(
+            Arg::new("ephemeral")
+                .long("ephemeral")
+                .help("Keep everything in memory: no stored history, config or data files")
+                .conflicts_with("init")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("help")
                 .long("help")
@@ -29,6 +69,21 @@ pub fn command() -> Command {
                 .default_value(DEFAULT_SERVER_HOST)
                 .num_args(1),
         )
+        .arg(
+            Arg::new("init")
+                .long("init")
+                .help("Create a default config file when none is found")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("log-format")
+                .long("log-format")
+                .value_name("FORMAT")
+                .help("Log output format (json for log collectors)")
+                .value_parser(crate::cli::options::logging::LOG_FORMAT_VALUES)
+                .default_value(crate::cli::options::logging::DEFAULT_LOG_FORMAT)
+                .num_args(1),
+        )
         .arg(
             Arg::new("log-level")
                 .long("log-level")
@@ -39,6 +94,15 @@ pub fn command() -> Command {
                 .default_value(crate::cli::options::logging::DEFAULT_LOG_LEVEL)
                 .num_args(1),
         )
+        .arg(
+            Arg::new("no-config-write")
+                .long("no-config-write")
+                .env(NO_CONFIG_WRITE_ENV)
+                .help("Deprecated: no config file is created unless --init is passed")
+                .hide(true)
+                .conflicts_with("init")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("port")
                 .long("port")
@@ -47,12 +111,46 @@ pub fn command() -> Command {
                 .default_value(DEFAULT_SERVER_PORT_STR)
                 .num_args(1),
         )
+        .arg(
+            Arg::new("print-config")
+                .long("print-config")
+                .value_name("FORMAT")
+                .help(
+                    "Print the effective config as TOML and exit, \
+                     or the resolved runtime parameters as JSON before binding",
+                )
+                .value_parser(PRINT_CONFIG_VALUES)
+                .default_missing_value(DEFAULT_PRINT_CONFIG)
+                .num_args(0..=1),
+        )
+        .arg(super::shared::profile_arg())
+        .arg(
+            Arg::new("quiet")
+                .long("quiet")
+                .short('q')
+                .help("Don't show the startup banner")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("show-secrets")
+                .long("show-secrets")
+                .help("Show secrets in --print-config output instead of redacting them")
+                .requires("print-config")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("verbose")
                 .long("verbose")
                 .short('V')
                 .help("Enable verbose output (sets log-level to debug)")
                 .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .visible_alias("watch-config")
+                .help("Reload adapters when the config file changes")
+                .action(ArgAction::SetTrue),
         );
 
     // Apply consistent help styling
@@ -60,90 +158,214 @@ pub fn command() -> Command {
 }
 
 pub async fn run(m: &ArgMatches) -> Result<()> {
-    let serve_config = extract_config(m);
+    let overrides = extract_overrides(m);
+    let log_format = overrides.log_format;
+    let quiet = overrides.quiet;
+    let check = overrides.check;
+    let ephemeral = overrides.ephemeral;
+    let print_config = overrides.print_config;
+    let show_secrets = overrides.show_secrets;
+    let watch = overrides.watch;
+    let config_file = overrides.config_file.clone();
+
+    // Log files come from the config, so log to the console while loading it
+    let bootstrap_logger = crate::utils::console_subscriber(&overrides.log_level, log_format);
+    let (serve_config, config, config_dir) =
+        tracing::subscriber::with_default(bootstrap_logger, || load_serve_config(overrides))?;
+
+    if print_config == Some(PrintConfig::Toml) {
+        let config = effective_config(config, &serve_config, ephemeral);
+        print!(
+            "{}",
+            crate::server::runtime_info::config_toml(&config, show_secrets)?
+        );
+        return Ok(());
+    }
 
     // Initialize logging as early as possible
-    if let Err(e) = crate::utils::init_logging(&serve_config.log_level) {
-        eprintln!("Failed to initialize logging: {}", e);
-        // Continue without logging rather than fail
+    let log_files = config
+        .logging
+        .log_files(config_dir.as_deref(), &serve_config.log_level);
+    if let Err(e) =
+        crate::utils::init_logging_with_files(&serve_config.log_level, log_format, &log_files)
+    {
+        eprintln!("Failed to initialize file logging: {:#}", e);
+        // Continue with console logging rather than fail
+        let _ = crate::utils::init_logging_with_format(&serve_config.log_level, log_format);
     }
 
     tracing::info!("Starting ai_messenger server");
+    if let Some(profile) = &config.profile {
+        tracing::info!("Using config profile '{}'", profile);
+    }
     tracing::debug!("Log level set to: {}", serve_config.log_level);
+    for log_file in &log_files {
+        tracing::debug!(
+            "Logging to {} at {}",
+            log_file.path.display(),
+            log_file.level
+        );
+    }
 
-    // Load configuration
-    let (config, config_dir) = crate::config::load_config(serve_config.config_file.clone())?;
-
-    // Use config values, with CLI overrides taking precedence
-    let host = serve_config.host;
-    let port = serve_config.port;
-    let log_level = serve_config.log_level;
-
-    tracing::info!("Server will bind to {}:{}", host, port);
+    tracing::info!(
+        "Server will bind to {}:{}",
+        serve_config.host,
+        serve_config.port
+    );
+
+    // Resolved after loading, which may have created a default config file
+    let watch_file = if watch {
+        let watch_file = crate::config::config_file_path(config_file.as_deref());
+        if watch_file.is_none() {
+            tracing::warn!("No config file to watch, using built-in defaults");
+        }
+        watch_file
+    } else {
+        None
+    };
 
     // Start the server (server will handle its own logging based on log_level)
     let startup_config = crate::server::startup::ServerStartupConfig {
+        check,
         config,
         config_dir,
-        host,
-        log_level,
-        port,
+        create_dirs: serve_config.create_dirs,
+        ephemeral,
+        host: serve_config.host,
+        log_level: serve_config.log_level,
+        port: serve_config.port,
+        print_config: print_config == Some(PrintConfig::Json),
+        quiet,
+        watch_file,
     };
     crate::server::start(startup_config).await?;
 
     Ok(())
 }
 
+/// Options given explicitly on the command line
+///
+/// `None` means the option wasn't passed and the config file (or its
+/// built-in default) decides.
 #[derive(Debug)]
-pub struct ServeConfig {
+pub struct ServeOverrides {
+    /// Exit after loading instead of serving
+    pub check: bool,
     pub config_file: Option<String>,
+    pub create_dirs: Option<bool>,
+    /// Keep everything in memory
+    pub ephemeral: bool,
+    pub host: Option<String>,
+    /// Create a default config file if none is found
+    pub init: bool,
+    pub log_format: LogFormat,
+    pub log_level: String,
+    pub port: Option<u16>,
+    pub print_config: Option<PrintConfig>,
+    /// Config profile to apply (`AI_MESSENGER_PROFILE` if None)
+    pub profile: Option<String>,
+    pub quiet: bool,
+    /// Don't redact secrets in the printed config
+    pub show_secrets: bool,
+    pub watch: bool,
+}
+
+/// What --print-config prints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrintConfig {
+    /// `ResolvedRuntimeInfo`, before binding
+    Json,
+    /// The effective `Config`, instead of serving
+    Toml,
+}
+
+/// Effective serve settings after applying CLI precedence to the config
+#[derive(Debug)]
+pub struct ServeConfig {
+    pub create_dirs: bool,
     pub host: String,
     pub log_level: String,
     pub port: u16,
 }
 
-/// Extract configuration from CLI arguments with proper precedence:
-/// CLI explicit > Config file > Default values
-fn extract_config(matches: &ArgMatches) -> ServeConfig {
-    // Load config file first to get potential values
-    let config_file = matches.get_one::<String>("config").cloned();
-    let (config, _) = crate::config::load_config_silent(config_file.clone()).unwrap_or_default();
-
-    let log_level = crate::cli::options::logging::extract_log_level(matches);
+/// Extract the options explicitly set on the command line
+///
+/// Doesn't touch the config file, so it is loaded exactly once, in `run()`.
+fn extract_overrides(matches: &ArgMatches) -> ServeOverrides {
+    let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    let port = explicit("port").then(|| {
+        matches
+            .get_one::<String>("port")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_SERVER_PORT)
+    });
+
+    ServeOverrides {
+        check: matches.get_flag("check"),
+        config_file: matches.get_one::<String>("config").cloned(),
+        create_dirs: explicit("create-dirs")
+            .then(|| *matches.get_one::<bool>("create-dirs").unwrap()),
+        ephemeral: matches.get_flag("ephemeral"),
+        host: explicit("host").then(|| matches.get_one::<String>("host").unwrap().clone()),
+        init: matches.get_flag("init"),
+        log_format: crate::cli::options::logging::extract_log_format(matches),
+        log_level: crate::cli::options::logging::extract_log_level(matches),
+        port,
+        print_config: matches.get_one::<String>("print-config").map(|format| {
+            match format.as_str() {
+                "json" => PrintConfig::Json,
+                _ => PrintConfig::Toml,
+            }
+        }),
+        profile: matches.get_one::<String>("profile").cloned(),
+        quiet: matches.get_flag("quiet"),
+        show_secrets: matches.get_flag("show-secrets"),
+        watch: matches.get_flag("watch"),
+    }
+}
 
-    // Host precedence: CLI explicit > Config file > Default
-    let host = match matches.value_source("host") {
-        Some(ValueSource::CommandLine) => {
-            // User explicitly set --host
-            matches.get_one::<String>("host").unwrap().clone()
-        }
-        _ => {
-            // Use config file value, which already has built-in default
-            config.server.host.clone()
-        }
+/// Load the configuration once and apply CLI precedence to it
+///
+/// An explicitly passed --config that can't be read or parsed is an error,
+/// never a silent fallback to defaults. Without a config file, in-memory
+/// defaults are used, unless --init asks for a default file to be created.
+fn load_serve_config(overrides: ServeOverrides) -> Result<(ServeConfig, Config, Option<PathBuf>)> {
+    let load = if overrides.init {
+        crate::config::load_or_init_config
+    } else {
+        crate::config::load_config
     };
+    let (config, config_dir) = load(overrides.config_file.clone(), overrides.profile.as_deref())?;
+    let serve_config = resolve_config(overrides, &config);
 
-    // Port precedence: CLI explicit > Config file > Default
-    let port = match matches.value_source("port") {
-        Some(ValueSource::CommandLine) => {
-            // User explicitly set --port
-            matches
-                .get_one::<String>("port")
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(DEFAULT_SERVER_PORT)
-        }
-        _ => {
-            // Use config file value, which already has built-in default
-            config.server.port
-        }
-    };
+    Ok((serve_config, config, config_dir))
+}
 
+/// Apply precedence: CLI explicit > Config file > Default values
+///
+/// The loaded config already carries built-in defaults for unset values.
+fn resolve_config(overrides: ServeOverrides, config: &Config) -> ServeConfig {
     ServeConfig {
-        config_file,
-        host,
-        log_level,
-        port,
+        create_dirs: overrides
+            .create_dirs
+            .or(config.storage.create_dirs)
+            .unwrap_or(DEFAULT_CREATE_DIRS),
+        host: overrides.host.unwrap_or_else(|| config.server.host.clone()),
+        log_level: overrides.log_level,
+        port: overrides.port.unwrap_or(config.server.port),
+    }
+}
+
+/// `config` with the command line overrides applied, as the server would run it
+fn effective_config(mut config: Config, serve_config: &ServeConfig, ephemeral: bool) -> Config {
+    config.server.host = serve_config.host.clone();
+    config.server.port = serve_config.port;
+    config.storage.create_dirs = Some(serve_config.create_dirs);
+    if ephemeral {
+        crate::server::startup::make_ephemeral(&mut config);
     }
+    config
 }
 
 #[cfg(test)]
@@ -165,12 +387,32 @@ mod tests {
         let cmd = command();
 
         // Should have all expected arguments
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "check"));
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "config"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "create-dirs"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "ephemeral"));
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "help"));
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "host"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "init"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "log-format"));
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "log-level"));
+        assert!(
+            cmd.get_arguments()
+                .any(|arg| arg.get_id() == "no-config-write")
+        );
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "port"));
+        assert!(
+            cmd.get_arguments()
+                .any(|arg| arg.get_id() == "print-config")
+        );
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "profile"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "quiet"));
+        assert!(
+            cmd.get_arguments()
+                .any(|arg| arg.get_id() == "show-secrets")
+        );
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "verbose"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "watch"));
     }
 
     #[test]
@@ -197,137 +439,240 @@ mod tests {
         assert_eq!(port_default, DEFAULT_SERVER_PORT_STR);
     }
 
+    /// Extract the CLI overrides from serve arguments
+    fn overrides_for(args: &[&str]) -> ServeOverrides {
+        extract_overrides(&command().try_get_matches_from(args).unwrap())
+    }
+
+    /// Write a config file and return its directory (kept alive) and path
+    fn config_file(content: &str) -> (tempfile::TempDir, String) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, content).unwrap();
+        let config_arg = config_path.to_string_lossy().to_string();
+
+        (temp_dir, config_arg)
+    }
+
+    /// Config with server values that differ from the defaults
+    fn custom_config() -> Config {
+        let mut config = Config::default();
+        config.server.host = "192.168.1.1".to_string();
+        config.server.port = 9000;
+        config
+    }
+
     #[test]
-    fn test_extract_config_defaults() {
-        let cmd = command();
-        let matches = cmd.try_get_matches_from(["serve"]).unwrap();
+    fn test_extract_overrides_defaults() {
+        let overrides = overrides_for(&["serve"]);
+
+        assert!(!overrides.check);
+        assert_eq!(overrides.config_file, None);
+        assert_eq!(overrides.create_dirs, None);
+        assert!(!overrides.ephemeral);
+        assert_eq!(overrides.host, None);
+        assert!(!overrides.init);
+        assert_eq!(overrides.log_format, LogFormat::Pretty);
+        assert_eq!(overrides.log_level, "info");
+        assert_eq!(overrides.port, None);
+        assert_eq!(overrides.print_config, None);
+        assert_eq!(overrides.profile, None);
+        assert!(!overrides.quiet);
+        assert!(!overrides.show_secrets);
+        assert!(!overrides.watch);
+    }
+
+    #[test]
+    fn test_extract_overrides_quiet() {
+        assert!(overrides_for(&["serve", "--quiet"]).quiet);
+        assert!(overrides_for(&["serve", "-q"]).quiet);
+    }
 
-        let config = extract_config(&matches);
+    #[test]
+    fn test_extract_overrides_print_config() {
+        let overrides = overrides_for(&["serve", "--print-config", "json", "--check"]);
+
+        assert!(overrides.check);
+        assert_eq!(overrides.print_config, Some(PrintConfig::Json));
+
+        // Without a format, the effective config is printed as TOML
+        let overrides = overrides_for(&["serve", "--print-config", "--show-secrets"]);
+        assert_eq!(overrides.print_config, Some(PrintConfig::Toml));
+        assert!(overrides.show_secrets);
 
-        assert_eq!(config.config_file, None);
-        assert_eq!(config.host, DEFAULT_SERVER_HOST);
-        assert_eq!(config.log_level, "info");
-        assert_eq!(config.port, DEFAULT_SERVER_PORT);
+        assert!(
+            command()
+                .try_get_matches_from(["serve", "--print-config", "yaml"])
+                .is_err()
+        );
+        // Nothing is printed to show secrets in
+        assert!(
+            command()
+                .try_get_matches_from(["serve", "--show-secrets"])
+                .is_err()
+        );
     }
 
     #[test]
-    fn test_extract_config_with_custom_values() {
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from([
-                "serve",
-                "--host",
-                "0.0.0.0",
-                "--port",
-                "3000",
-                "--config",
-                "/custom/config.toml",
-                "--log-level",
-                "debug",
-            ])
-            .unwrap();
+    fn test_extract_overrides_watch() {
+        let overrides = overrides_for(&["serve", "--watch"]);
+
+        assert!(overrides.watch);
 
-        let config = extract_config(&matches);
+        let overrides = overrides_for(&["serve", "--watch-config"]);
+        assert!(overrides.watch);
+    }
 
-        assert_eq!(config.config_file, Some("/custom/config.toml".to_string()));
-        assert_eq!(config.host, "0.0.0.0");
-        assert_eq!(config.log_level, "debug");
-        assert_eq!(config.port, 3000);
+    #[test]
+    fn test_extract_overrides_init() {
+        assert!(overrides_for(&["serve", "--init"]).init);
+
+        // The deprecated opposite is still accepted, but not together with it
+        assert!(!overrides_for(&["serve", "--no-config-write"]).init);
+        assert!(
+            command()
+                .try_get_matches_from(["serve", "--init", "--no-config-write"])
+                .is_err()
+        );
     }
 
     #[test]
-    fn test_extract_config_invalid_port() {
+    fn test_extract_overrides_ephemeral() {
+        assert!(overrides_for(&["serve", "--ephemeral"]).ephemeral);
+
+        // Creating a config file is exactly what ephemeral mode avoids
+        assert!(
+            command()
+                .try_get_matches_from(["serve", "--ephemeral", "--init"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_no_config_write_env() {
         let cmd = command();
-        let matches = cmd
-            .try_get_matches_from(["serve", "--port", "invalid"])
+        let arg = cmd
+            .get_arguments()
+            .find(|arg| arg.get_id() == "no-config-write")
             .unwrap();
+        assert_eq!(
+            arg.get_env(),
+            Some(std::ffi::OsStr::new(NO_CONFIG_WRITE_ENV))
+        );
+    }
 
-        let config = extract_config(&matches);
+    #[test]
+    fn test_init_still_requires_explicit_config() {
+        let mut overrides = overrides_for(&["serve", "--init"]);
+        overrides.config_file = Some("/nonexistent/config.toml".to_string());
 
-        // Should fallback to DEFAULT_SERVER_PORT for invalid port
-        assert_eq!(config.port, DEFAULT_SERVER_PORT);
+        assert!(load_serve_config(overrides).is_err());
     }
 
     #[test]
-    fn test_extract_config_verbose_flag() {
-        let cmd = command();
-        let matches = cmd.try_get_matches_from(["serve", "--verbose"]).unwrap();
+    fn test_extract_overrides_log_format() {
+        let overrides = overrides_for(&["serve", "--log-format", "json"]);
 
-        let config = extract_config(&matches);
+        assert_eq!(overrides.log_format, LogFormat::Json);
+    }
 
-        assert_eq!(config.log_level, "debug"); // --verbose sets log-level to debug
+    #[test]
+    fn test_extract_overrides_with_custom_values() {
+        let overrides = overrides_for(&[
+            "serve",
+            "--host",
+            "0.0.0.0",
+            "--port",
+            "3000",
+            "--config",
+            "/custom/config.toml",
+            "--log-level",
+            "debug",
+        ]);
+
+        assert_eq!(
+            overrides.config_file,
+            Some("/custom/config.toml".to_string())
+        );
+        assert_eq!(overrides.host, Some("0.0.0.0".to_string()));
+        assert_eq!(overrides.log_level, "debug");
+        assert_eq!(overrides.port, Some(3000));
     }
 
     #[test]
-    fn test_extract_config_log_level() {
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from(["serve", "--log-level", "warn"])
-            .unwrap();
+    fn test_extract_overrides_does_not_load_config() {
+        // A missing file isn't touched until run() loads it
+        let overrides = overrides_for(&["serve", "--config", "/nonexistent/path/config.toml"]);
 
-        let config = extract_config(&matches);
+        assert_eq!(
+            overrides.config_file,
+            Some("/nonexistent/path/config.toml".to_string())
+        );
+        assert_eq!(overrides.host, None);
+        assert_eq!(overrides.port, None);
+    }
 
-        assert_eq!(config.log_level, "warn");
+    #[test]
+    fn test_extract_overrides_invalid_port() {
+        let overrides = overrides_for(&["serve", "--port", "invalid"]);
+
+        // Should fallback to DEFAULT_SERVER_PORT for invalid port
+        assert_eq!(overrides.port, Some(DEFAULT_SERVER_PORT));
     }
 
     #[test]
-    fn test_extract_config_verbose_overrides_log_level() {
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from(["serve", "--log-level", "warn", "--verbose"])
-            .unwrap();
+    fn test_extract_overrides_verbose_flag() {
+        let overrides = overrides_for(&["serve", "--verbose"]);
 
-        let config = extract_config(&matches);
+        assert_eq!(overrides.log_level, "debug"); // --verbose sets log-level to debug
+    }
+
+    #[test]
+    fn test_extract_overrides_log_level() {
+        let overrides = overrides_for(&["serve", "--log-level", "warn"]);
 
-        assert_eq!(config.log_level, "debug"); // --verbose overrides --log-level
+        assert_eq!(overrides.log_level, "warn");
     }
 
     #[test]
-    fn test_host_precedence_cli_over_config() {
-        // Test that explicit CLI --host overrides config file
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from(["serve", "--host", "192.168.1.100"])
-            .unwrap();
-        let config = extract_config(&matches);
-        assert_eq!(config.host, "192.168.1.100"); // CLI explicit wins
+    fn test_extract_overrides_verbose_overrides_log_level() {
+        let overrides = overrides_for(&["serve", "--log-level", "warn", "--verbose"]);
+
+        assert_eq!(overrides.log_level, "debug"); // --verbose overrides --log-level
     }
 
     #[test]
-    fn test_port_precedence_cli_over_config() {
-        // Test that explicit CLI --port overrides config file
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from(["serve", "--port", "9999"])
-            .unwrap();
-        let config = extract_config(&matches);
-        assert_eq!(config.port, 9999); // CLI explicit wins
+    fn test_host_precedence_cli_over_config() {
+        let overrides = overrides_for(&["serve", "--host", "192.168.1.100"]);
+        let serve_config = resolve_config(overrides, &custom_config());
+
+        assert_eq!(serve_config.host, "192.168.1.100"); // CLI explicit wins
+        assert_eq!(serve_config.port, 9000); // From config
     }
 
     #[test]
-    fn test_host_fallback_to_default_when_no_cli() {
-        // Test that when no CLI host is provided, we fall back to config/default
-        let cmd = command();
-        let matches = cmd.try_get_matches_from(["serve"]).unwrap();
-        let config = extract_config(&matches);
-        // Should use config file value or default (127.0.0.1)
-        assert_eq!(config.host, "127.0.0.1"); // Config default
+    fn test_port_precedence_cli_over_config() {
+        let overrides = overrides_for(&["serve", "--port", "9999"]);
+        let serve_config = resolve_config(overrides, &custom_config());
+
+        assert_eq!(serve_config.host, "192.168.1.1"); // From config
+        assert_eq!(serve_config.port, 9999); // CLI explicit wins
     }
 
     #[test]
-    fn test_port_fallback_to_default_when_no_cli() {
-        // Test that when no CLI port is provided, we fall back to config/default
-        let cmd = command();
-        let matches = cmd.try_get_matches_from(["serve"]).unwrap();
-        let config = extract_config(&matches);
-        // Should use config file value or default (8080)
-        assert_eq!(config.port, 8080); // Config default
+    fn test_precedence_defaults_when_no_config() {
+        let serve_config = resolve_config(overrides_for(&["serve"]), &Config::default());
+
+        assert!(serve_config.create_dirs);
+        assert_eq!(serve_config.host, DEFAULT_SERVER_HOST);
+        assert_eq!(serve_config.log_level, "info");
+        assert_eq!(serve_config.port, DEFAULT_SERVER_PORT);
     }
 
     #[test]
     fn test_serve_config_debug() {
         let config = ServeConfig {
-            config_file: Some("test.toml".to_string()),
+            create_dirs: true,
             host: "localhost".to_string(),
             log_level: "debug".to_string(),
             port: DEFAULT_SERVER_PORT,
@@ -337,7 +682,6 @@ mod tests {
         let debug_str = format!("{:?}", config);
         assert!(debug_str.contains("debug"));
         assert!(debug_str.contains("localhost"));
-        assert!(debug_str.contains("test.toml"));
         assert!(debug_str.contains(&DEFAULT_SERVER_PORT.to_string()));
     }
 
@@ -368,168 +712,146 @@ mod tests {
 
     #[test]
     fn test_precedence_with_config_file() {
-        // This test simulates the scenario where we have both config file AND CLI args
-        // and verifies CLI wins over config file values
-        use std::fs;
-        use tempfile::TempDir;
-
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("test_precedence.toml");
-
-        // Create a config file with specific values
-        let config_content = r#"
-[server]
-host = "192.168.1.1"
-port = 9000
-"#;
-        fs::write(&config_path, config_content).unwrap();
-
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from([
-                "serve",
-                "--config",
-                &config_path.to_string_lossy(),
-                "--host",
-                "127.0.0.1",
-                "--port",
-                "8080",
-            ])
-            .unwrap();
-
-        let config = extract_config(&matches);
+        let (_temp_dir, config_arg) =
+            config_file("[server]\nhost = \"192.168.1.1\"\nport = 9000\n");
+
+        let overrides = overrides_for(&[
+            "serve",
+            "--config",
+            &config_arg,
+            "--host",
+            "127.0.0.1",
+            "--port",
+            "8080",
+        ]);
+        let (serve_config, config, config_dir) = load_serve_config(overrides).unwrap();
 
         // CLI values should override config file values
-        assert_eq!(config.host, "127.0.0.1"); // CLI override
-        assert_eq!(config.port, 8080); // CLI override
-    }
+        assert_eq!(serve_config.host, "127.0.0.1");
+        assert_eq!(serve_config.port, 8080);
 
-    #[test]
-    fn test_precedence_config_file_over_defaults() {
-        // Test that config file values are used when no CLI args are provided
-        use std::fs;
-        use tempfile::TempDir;
-
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("test_config_defaults.toml");
-
-        // Create a config file with custom values
-        let config_content = r#"
-[server]
-host = "0.0.0.0"
-port = 3000
-"#;
-        fs::write(&config_path, config_content).unwrap();
-
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from(["serve", "--config", &config_path.to_string_lossy()])
-            .unwrap();
-
-        let config = extract_config(&matches);
-
-        // Config file values should be used (not defaults)
-        assert_eq!(config.host, "0.0.0.0"); // From config file
-        assert_eq!(config.port, 3000); // From config file
+        // The loaded config is the one handed to the server
+        assert_eq!(config.server.host, "192.168.1.1");
+        assert!(config_dir.is_some());
     }
 
     #[test]
-    fn test_precedence_defaults_when_no_config() {
-        // Test that defaults are used when no CLI args AND no config file
-        let cmd = command();
-        let matches = cmd.try_get_matches_from(["serve"]).unwrap();
+    fn test_precedence_config_file_over_defaults() {
+        let (_temp_dir, config_arg) = config_file("[server]\nhost = \"0.0.0.0\"\nport = 3000\n");
 
-        let config = extract_config(&matches);
+        let overrides = overrides_for(&["serve", "--config", &config_arg]);
+        let (serve_config, config, _) = load_serve_config(overrides).unwrap();
 
-        // Should get defaults since no CLI args and no config file
-        assert_eq!(config.host, DEFAULT_SERVER_HOST);
-        assert_eq!(config.port, DEFAULT_SERVER_PORT);
+        // Host and port come from the same load as the config passed on
+        assert_eq!(serve_config.host, "0.0.0.0");
+        assert_eq!(serve_config.port, 3000);
+        assert_eq!(config.server.host, serve_config.host);
+        assert_eq!(config.server.port, serve_config.port);
     }
 
     #[test]
     fn test_precedence_partial_config_file() {
-        // Test mixed scenario: config file has only some values
-        use std::fs;
-        use tempfile::TempDir;
-
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("test_partial.toml");
-
         // Config file only has host, no port
-        let config_content = r#"
-[server]
-host = "0.0.0.0"
-# port is missing - should use default
-"#;
-        fs::write(&config_path, config_content).unwrap();
-
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from(["serve", "--config", &config_path.to_string_lossy()])
-            .unwrap();
+        let (_temp_dir, config_arg) = config_file("[server]\nhost = \"0.0.0.0\"\n");
 
-        let config = extract_config(&matches);
+        let overrides = overrides_for(&["serve", "--config", &config_arg]);
+        let (serve_config, _, _) = load_serve_config(overrides).unwrap();
 
         // Host from config, port from default
-        assert_eq!(config.host, "0.0.0.0"); // From config
-        assert_eq!(config.port, DEFAULT_SERVER_PORT); // Serde default
+        assert_eq!(serve_config.host, "0.0.0.0");
+        assert_eq!(serve_config.port, DEFAULT_SERVER_PORT);
     }
 
     #[test]
-    fn test_precedence_invalid_config_file() {
-        // Test that we fall back to defaults when config file is invalid
-        use std::fs;
-        use tempfile::TempDir;
+    fn test_profile_with_cli_precedence() {
+        let (_temp_dir, config_arg) = config_file(
+            "[server]\nport = 9000\n\n[profiles.prod.server]\nhost = \"0.0.0.0\"\nport = 80\n",
+        );
 
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("invalid.toml");
+        let overrides = overrides_for(&[
+            "serve",
+            "--config",
+            &config_arg,
+            "--profile",
+            "prod",
+            "--port",
+            "3000",
+        ]);
+        assert_eq!(overrides.profile.as_deref(), Some("prod"));
+        let (serve_config, config, _) = load_serve_config(overrides).unwrap();
+
+        // The profile overrides the file, the command line overrides the profile
+        assert_eq!(config.profile.as_deref(), Some("prod"));
+        assert_eq!(serve_config.host, "0.0.0.0");
+        assert_eq!(serve_config.port, 3000);
+
+        let overrides = overrides_for(&["serve", "--config", &config_arg, "--profile", "staging"]);
+        let error = load_serve_config(overrides).unwrap_err();
+        assert!(format!("{:#}", error).contains("available: prod"));
+    }
 
-        // Create invalid TOML
-        let config_content = r#"
-[server
-host = "broken
-"#;
-        fs::write(&config_path, config_content).unwrap();
+    #[test]
+    fn test_effective_config_applies_overrides() {
+        let (_temp_dir, config_arg) =
+            config_file("[server]\nhost = \"192.168.1.1\"\nport = 9000\n");
+
+        let overrides = overrides_for(&[
+            "serve",
+            "--config",
+            &config_arg,
+            "--port",
+            "3000",
+            "--create-dirs",
+            "false",
+        ]);
+        let (serve_config, config, _) = load_serve_config(overrides).unwrap();
+        let config = effective_config(config, &serve_config, false);
+
+        assert_eq!(config.server.host, "192.168.1.1");
+        assert_eq!(config.server.port, 3000);
+        assert_eq!(config.storage.create_dirs, Some(false));
+
+        let config = effective_config(Config::default(), &serve_config, true);
+        let storage = config.adapters.get_service("storage").unwrap();
+        assert_eq!(storage.provider, "memory");
+        assert!(!config.usage_log.enabled);
+    }
 
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from(["serve", "--config", &config_path.to_string_lossy()])
-            .unwrap();
+    #[test]
+    fn test_invalid_config_file_is_an_error() {
+        let (_temp_dir, config_arg) = config_file("[server\nhost = \"broken\n");
 
-        let config = extract_config(&matches);
+        let overrides = overrides_for(&["serve", "--config", &config_arg, "--port", "3000"]);
+        let error = load_serve_config(overrides).unwrap_err();
 
-        // Should fallback to defaults when config is invalid
-        assert_eq!(config.host, DEFAULT_SERVER_HOST);
-        assert_eq!(config.port, DEFAULT_SERVER_PORT);
+        assert!(format!("{:#}", error).contains("Failed to parse config file"));
     }
 
     #[test]
-    fn test_precedence_nonexistent_config_file() {
-        // Test that we fall back to defaults when specified config file doesn't exist
-        let cmd = command();
-        let matches = cmd
-            .try_get_matches_from(["serve", "--config", "/nonexistent/path/config.toml"])
-            .unwrap();
-
-        let config = extract_config(&matches);
-
-        // Should fallback to defaults when config file doesn't exist
-        assert_eq!(config.host, DEFAULT_SERVER_HOST);
-        assert_eq!(config.port, DEFAULT_SERVER_PORT);
+    fn test_nonexistent_config_file_is_an_error() {
+        // A typo'd --config path must not fall back to defaults
+        let overrides = overrides_for(&["serve", "--config", "/nonexistent/path/config.toml"]);
+        let error = load_serve_config(overrides).unwrap_err();
+
+        let message = format!("{:#}", error);
+        assert!(message.contains("Failed to read config file"));
+        assert!(message.contains("/nonexistent/path/config.toml"));
     }
 
-    #[tokio::test]
-    async fn test_run_function_exists() {
-        let cmd = command();
-        let matches = cmd.try_get_matches_from(["serve"]).unwrap();
+    #[test]
+    fn test_create_dirs_precedence() {
+        let (_temp_dir, config_arg) = config_file("[storage]\ncreate_dirs = false\n");
+
+        // Config file disables it
+        let overrides = overrides_for(&["serve", "--config", &config_arg]);
+        assert!(!load_serve_config(overrides).unwrap().0.create_dirs);
 
-        // NOTE: This test only verifies the function signature exists
-        // Actually calling run() would start a real server, which is not appropriate for unit tests
-        // Integration tests should be used for testing the actual server startup
+        // Bare flag re-enables it
+        let overrides = overrides_for(&["serve", "--config", &config_arg, "--create-dirs"]);
+        assert!(load_serve_config(overrides).unwrap().0.create_dirs);
 
-        // Test that we can extract config without errors
-        let config = extract_config(&matches);
-        assert_eq!(config.host, DEFAULT_SERVER_HOST);
-        assert_eq!(config.port, DEFAULT_SERVER_PORT);
+        // Explicit value wins over default
+        let overrides = overrides_for(&["serve", "--create-dirs=false"]);
+        assert!(!resolve_config(overrides, &Config::default()).create_dirs);
     }
 }
@@ -2,6 +2,24 @@ use anyhow::Result;
 use clap::{Arg, ArgAction, ArgMatches, Command};
 use std::path::{Path, PathBuf};
 
+/// Output format of path commands when --output isn't given
+pub const DEFAULT_OUTPUT: &str = "text";
+
+/// Formats of --output: the bare path, or `{"path": "..."}` for scripts
+pub const OUTPUT_VALUES: [&str; 2] = ["text", "json"];
+
+/// --profile, selecting a `[profiles.NAME]` section of the config file
+pub fn profile_arg() -> Arg {
+    Arg::new("profile")
+        .long("profile")
+        .value_name("NAME")
+        .help(format!(
+            "Config profile to apply over the base sections (default: ${})",
+            crate::config::profiles::PROFILE_ENV
+        ))
+        .num_args(1)
+}
+
 /// Create a generic path command (for cache, data, etc.)
 pub fn create_path_command(name: &'static str, about: &'static str) -> Command {
     let cmd = Command::new(name)
@@ -31,6 +49,17 @@ pub fn create_path_command(name: &'static str, about: &'static str) -> Command {
                 .default_value(crate::cli::options::logging::DEFAULT_LOG_LEVEL)
                 .num_args(1),
         )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .value_name("FORMAT")
+                .help("Output format (json for scripts)")
+                .value_parser(OUTPUT_VALUES)
+                .default_value(DEFAULT_OUTPUT)
+                .num_args(1),
+        )
+        .arg(profile_arg())
         .arg(
             Arg::new("verbose")
                 .long("verbose")
@@ -49,6 +78,7 @@ where
     F: Fn(&crate::config::Config, Option<&Path>) -> PathBuf,
 {
     let config_file = matches.get_one::<String>("config").cloned();
+    let profile = matches.get_one::<String>("profile").map(String::as_str);
     let log_level = crate::cli::options::logging::extract_log_level(matches);
 
     // Initialize logging with the requested level
@@ -59,18 +89,29 @@ where
 
     // Load configuration using same logic as serve (but silent for non-debug)
     let (config, config_dir) = if log_level == "debug" {
-        crate::config::load_config(config_file)?
+        crate::config::load_config(config_file, profile)?
     } else {
-        crate::config::load_config_silent(config_file)?
+        crate::config::load_config_silent(config_file, profile)?
     };
 
     // Get the path using the provided function and print it
     let path = path_fn(&config, config_dir.as_deref());
-    println!("{}", path.display());
+    let output = matches
+        .get_one::<String>("output")
+        .map_or(DEFAULT_OUTPUT, String::as_str);
+    println!("{}", format_path(&path, output));
 
     Ok(())
 }
 
+/// Render `path` in the `--output` format
+fn format_path(path: &Path, output: &str) -> String {
+    match output {
+        "json" => serde_json::json!({ "path": path.to_string_lossy() }).to_string(),
+        _ => path.display().to_string(),
+    }
+}
+
 #[cfg(test)]
 pub mod test_utils {
     use super::*;
@@ -84,12 +125,13 @@ pub mod test_utils {
         let about_str = format!("{}", cmd.get_about().unwrap());
         assert_eq!(about_str, about);
         assert!(cmd.is_disable_help_flag_set());
-        assert_eq!(cmd.get_arguments().count(), 4); // config, help, log-level, verbose
+        assert_eq!(cmd.get_arguments().count(), 6); // config, help, log-level, output, profile, verbose
 
         // Should have all expected arguments
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "config"));
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "help"));
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "log-level"));
+        assert!(cmd.get_arguments().any(|arg| arg.get_id() == "profile"));
         assert!(cmd.get_arguments().any(|arg| arg.get_id() == "verbose"));
     }
 
@@ -180,8 +222,8 @@ pub mod test_utils {
         assert_eq!(cmd.get_about().unwrap().to_string(), "Test command");
         assert!(cmd.is_disable_help_flag_set());
 
-        // Should have exactly 4 arguments
-        assert_eq!(cmd.get_arguments().count(), 4);
+        // Should have exactly 6 arguments
+        assert_eq!(cmd.get_arguments().count(), 6);
 
         // Verify all required arguments exist
         let arg_names: Vec<_> = cmd
@@ -191,9 +233,40 @@ pub mod test_utils {
         assert!(arg_names.contains(&"config"));
         assert!(arg_names.contains(&"help"));
         assert!(arg_names.contains(&"log-level"));
+        assert!(arg_names.contains(&"output"));
+        assert!(arg_names.contains(&"profile"));
         assert!(arg_names.contains(&"verbose"));
     }
 
+    #[test]
+    fn test_create_path_command_output() {
+        let cmd = create_path_command("test", "Test command");
+        let matches = cmd.try_get_matches_from(["test"]).unwrap();
+        assert_eq!(matches.get_one::<String>("output").unwrap(), "text");
+
+        let cmd = create_path_command("test", "Test command");
+        let matches = cmd
+            .try_get_matches_from(["test", "--output", "json"])
+            .unwrap();
+        assert_eq!(matches.get_one::<String>("output").unwrap(), "json");
+
+        let cmd = create_path_command("test", "Test command");
+        assert!(cmd.try_get_matches_from(["test", "-o", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn test_format_path() {
+        let path = Path::new("/home/me/my data/\"quoted\"");
+
+        assert_eq!(format_path(path, "text"), "/home/me/my data/\"quoted\"");
+
+        let json: serde_json::Value = serde_json::from_str(&format_path(path, "json")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "path": "/home/me/my data/\"quoted\"" })
+        );
+    }
+
     #[test]
     fn test_create_path_command_log_level_validation() {
         let cmd = create_path_command("test", "Test command");
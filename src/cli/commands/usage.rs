@@ -0,0 +1,284 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+use crate::server::usage_log::{self, UsageReport};
+
+/// Format of `--from` and `--to`
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub fn command() -> Command {
+    let report = Command::new("report")
+        .about("Total the tokens used per provider and model")
+        .disable_help_flag(true)
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Path to configuration file")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("from")
+                .long("from")
+                .value_name("YYYY-MM-DD")
+                .help("First day to include (UTC)")
+                .value_parser(parse_date)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .short('l')
+                .value_name("LEVEL")
+                .help("Set the logging level")
+                .value_parser(crate::cli::options::logging::LOG_LEVEL_VALUES)
+                .default_value(crate::cli::options::logging::DEFAULT_LOG_LEVEL)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("to")
+                .long("to")
+                .value_name("YYYY-MM-DD")
+                .help("Last day to include (UTC)")
+                .value_parser(parse_date)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .short('V')
+                .help("Enable verbose output (sets log-level to debug)")
+                .action(ArgAction::SetTrue),
+        );
+
+    let cmd = Command::new("usage")
+        .about("Inspect the LLM usage ledger")
+        .disable_help_flag(true)
+        .disable_help_subcommand(true)
+        .subcommand_required(true)
+        .arg(
+            Arg::new("help")
+                .long("help")
+                .short('h')
+                .help("Print help")
+                .action(ArgAction::Help),
+        )
+        .subcommand(crate::cli::options::help::apply(report));
+
+    // Apply consistent help styling
+    crate::cli::options::help::apply(cmd)
+}
+
+pub async fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("report", sub_m)) => run_report(sub_m),
+        _ => unreachable!("usage requires a subcommand"),
+    }
+}
+
+fn run_report(matches: &ArgMatches) -> Result<()> {
+    let config_file = matches.get_one::<String>("config").cloned();
+    let from = matches.get_one::<NaiveDate>("from").copied();
+    let to = matches.get_one::<NaiveDate>("to").copied();
+    let log_level = crate::cli::options::logging::extract_log_level(matches);
+
+    // Initialize logging with the requested level
+    if let Err(e) = crate::utils::init_logging(&log_level) {
+        eprintln!("Failed to initialize logging: {}", e);
+        // Continue without logging rather than fail
+    }
+
+    let (config, config_dir) = if log_level == "debug" {
+        crate::config::load_config(config_file, None)?
+    } else {
+        crate::config::load_config_silent(config_file, None)?
+    };
+    let data_dir = crate::config::data_dir(&config, config_dir.as_deref());
+
+    let files = usage_log::ledger_files(&config.usage_log, &data_dir);
+    if files.is_empty() {
+        println!(
+            "No usage recorded in {}",
+            config.usage_log.ledger_path(&data_dir).display()
+        );
+        return Ok(());
+    }
+
+    let report = usage_log::summarize(&files, from, to).context("Failed to read usage ledger")?;
+    print!("{}", format_report(&report));
+
+    Ok(())
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, DATE_FORMAT)
+        .map_err(|_| format!("expected a date like 2025-01-31, got '{}'", value))
+}
+
+/// Render `report` as a table with a total row
+fn format_report(report: &UsageReport) -> String {
+    if report.is_empty() {
+        return "No usage in the selected range\n".to_string();
+    }
+
+    let mut rows = vec![[
+        "PROVIDER".to_string(),
+        "MODEL".to_string(),
+        "CALLS".to_string(),
+        "FAILED".to_string(),
+        "PROMPT".to_string(),
+        "COMPLETION".to_string(),
+        "TOTAL".to_string(),
+    ]];
+    let mut total = usage_log::UsageTotals::default();
+    for ((provider, model), totals) in report {
+        rows.push([
+            provider.clone(),
+            model.clone(),
+            totals.calls.to_string(),
+            totals.failed.to_string(),
+            totals.prompt_tokens.to_string(),
+            totals.completion_tokens.to_string(),
+            totals.total_tokens.to_string(),
+        ]);
+        total.calls += totals.calls;
+        total.failed += totals.failed;
+        total.prompt_tokens += totals.prompt_tokens;
+        total.completion_tokens += totals.completion_tokens;
+        total.total_tokens += totals.total_tokens;
+    }
+    rows.push([
+        "total".to_string(),
+        String::new(),
+        total.calls.to_string(),
+        total.failed.to_string(),
+        total.prompt_tokens.to_string(),
+        total.completion_tokens.to_string(),
+        total.total_tokens.to_string(),
+    ]);
+
+    let widths: Vec<usize> = (0..7)
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(column, (cell, &width))| {
+                    // Names left-aligned, numbers right-aligned
+                    if column < 2 {
+                        format!("{:<width$}", cell)
+                    } else {
+                        format!("{:>width$}", cell)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+
+    #[test]
+    fn test_command_structure() {
+        let cmd = command();
+
+        assert_eq!(cmd.get_name(), "usage");
+        assert!(cmd.is_subcommand_required_set());
+        let names: Vec<&str> = cmd.get_subcommands().map(|sub| sub.get_name()).collect();
+        assert_eq!(names, vec!["report"]);
+    }
+
+    #[test]
+    fn test_report_requires_valid_dates() {
+        let matches = command()
+            .try_get_matches_from([
+                "usage",
+                "report",
+                "--from",
+                "2025-01-01",
+                "--to",
+                "2025-01-31",
+            ])
+            .unwrap();
+        let (_, report) = matches.subcommand().unwrap();
+        assert_eq!(
+            report.get_one::<NaiveDate>("from"),
+            NaiveDate::from_ymd_opt(2025, 1, 1).as_ref()
+        );
+
+        let result = command().try_get_matches_from(["usage", "report", "--from", "01/31/2025"]);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn test_subcommand_required() {
+        let result = command().try_get_matches_from(["usage"]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_format_report() {
+        let mut report = UsageReport::new();
+        report.insert(
+            ("ollama".to_string(), "llama3.2".to_string()),
+            usage_log::UsageTotals {
+                calls: 2,
+                completion_tokens: 30,
+                failed: 0,
+                prompt_tokens: 12,
+                total_tokens: 42,
+            },
+        );
+        report.insert(
+            ("openai".to_string(), "gpt-4o".to_string()),
+            usage_log::UsageTotals {
+                calls: 1,
+                completion_tokens: 5,
+                failed: 1,
+                prompt_tokens: 3,
+                total_tokens: 8,
+            },
+        );
+
+        let table = format_report(&report);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("PROVIDER"));
+        assert!(lines[1].starts_with("ollama"));
+        assert!(lines[1].ends_with("42"));
+        assert!(lines[3].starts_with("total"));
+        assert!(lines[3].ends_with("50"));
+        assert_eq!(
+            format_report(&UsageReport::new()),
+            "No usage in the selected range\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_with_nonexistent_config() {
+        let matches = command()
+            .try_get_matches_from(["usage", "report", "--config", "/nonexistent/config.toml"])
+            .unwrap();
+
+        assert!(run(&matches).await.is_err());
+    }
+}
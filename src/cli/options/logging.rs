@@ -1,11 +1,26 @@
+use crate::utils::logger::LogFormat;
 use clap::ArgMatches;
 
+/// Default log format for commands that support --log-format
+pub const DEFAULT_LOG_FORMAT: &str = "pretty";
+
 /// Default log level for all commands
 pub const DEFAULT_LOG_LEVEL: &str = "info";
 
+/// Valid log format values
+pub const LOG_FORMAT_VALUES: [&str; 2] = ["pretty", "json"];
+
 /// Valid log level values for all commands (aligned with tracing levels)
 pub const LOG_LEVEL_VALUES: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
 
+/// Extract log format from matches (values are restricted by clap)
+pub fn extract_log_format(matches: &ArgMatches) -> LogFormat {
+    matches
+        .get_one::<String>("log-format")
+        .and_then(|format| format.parse().ok())
+        .unwrap_or_default()
+}
+
 /// Extract log level from matches, with --verbose override
 pub fn extract_log_level(matches: &ArgMatches) -> String {
     if matches.get_flag("verbose") {
@@ -22,6 +37,13 @@ mod tests {
 
     fn create_test_command() -> Command {
         Command::new("test")
+            .arg(
+                Arg::new("log-format")
+                    .long("log-format")
+                    .value_parser(LOG_FORMAT_VALUES)
+                    .default_value(DEFAULT_LOG_FORMAT)
+                    .num_args(1),
+            )
             .arg(
                 Arg::new("log-level")
                     .long("log-level")
@@ -53,6 +75,25 @@ mod tests {
         assert!(LOG_LEVEL_VALUES.contains(&"off"));
     }
 
+    #[test]
+    fn test_extract_log_format() {
+        let matches = create_test_command()
+            .try_get_matches_from(["test"])
+            .unwrap();
+        assert_eq!(extract_log_format(&matches), LogFormat::Pretty);
+
+        let matches = create_test_command()
+            .try_get_matches_from(["test", "--log-format", "json"])
+            .unwrap();
+        assert_eq!(extract_log_format(&matches), LogFormat::Json);
+
+        assert!(
+            create_test_command()
+                .try_get_matches_from(["test", "--log-format", "xml"])
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_extract_log_level_default() {
         let cmd = create_test_command();
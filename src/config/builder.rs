@@ -0,0 +1,206 @@
+use super::schema::{AdapterValidationError, Config, ServiceAdapterConfig};
+use std::path::PathBuf;
+
+/// Chainable construction of a `Config` without a TOML file
+///
+/// Starts from the defaults, like a config file that only sets what it
+/// needs to. `build` checks the result before handing it out.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ai_messenger::config::schema::ServiceAdapterConfig;
+/// use ai_messenger::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     // Your app controls logging
+///     tracing_subscriber::fmt::init();
+///
+///     // Initialize ai_messenger (without touching global logging)
+///     ai_messenger::init()?;
+///
+///     let config = Config::builder()
+///         .host("0.0.0.0")
+///         .port(3000)
+///         .base_path("api")
+///         .data_dir("~/.my_app/data")
+///         .adapter(
+///             "llm",
+///             ServiceAdapterConfig::new("ollama").with_setting("default_model", "llama3.2"),
+///         )
+///         .build()?;
+///
+///     let mut messenger = Messenger::from_config(config).await?;
+///     let response = messenger.send_message("assistant", &[Message::user("Hello!")]).await?;
+///     println!("{}", response.message.content);
+///
+///     messenger.shutdown().await
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+#[allow(dead_code)] // Library API for embedders
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+#[allow(dead_code)] // Library API for embedders
+impl ConfigBuilder {
+    /// Builder starting from the default config
+    pub fn new() -> Self {
+        ConfigBuilder::default()
+    }
+
+    /// Server bind address
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.config.server.host = host.into();
+        self
+    }
+
+    /// Server port
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.server.port = port;
+        self
+    }
+
+    /// Base path prefix for all routes (e.g. "api")
+    pub fn base_path(mut self, base_path: impl Into<String>) -> Self {
+        self.config.server.base_path = base_path.into();
+        self
+    }
+
+    /// Data directory, expanded like `storage.data_dir` in a config file
+    pub fn data_dir(mut self, data_dir: impl Into<PathBuf>) -> Self {
+        self.config.storage.data_dir = Some(data_dir.into());
+        self
+    }
+
+    /// Cache directory, expanded like `storage.cache_dir` in a config file
+    pub fn cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.config.storage.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    /// Add the adapter for `service` (e.g. "llm"), replacing any previous one
+    pub fn adapter(mut self, service: impl Into<String>, adapter: ServiceAdapterConfig) -> Self {
+        self.config
+            .adapters
+            .services
+            .insert(service.into(), adapter);
+        self
+    }
+
+    /// Remove all adapters, including the default LLM adapter
+    pub fn without_adapters(mut self) -> Self {
+        self.config.adapters.services.clear();
+        self
+    }
+
+    /// Check the config and return it
+    pub fn build(self) -> Result<Config, ConfigBuildError> {
+        check(&self.config)?;
+        Ok(self.config)
+    }
+}
+
+/// Reject configs with empty required values
+pub(crate) fn check(config: &Config) -> Result<(), ConfigBuildError> {
+    if config.server.host.trim().is_empty() {
+        return Err(ConfigBuildError::Empty("server.host".to_string()));
+    }
+    for (service, adapter) in &config.adapters.services {
+        if service.trim().is_empty() {
+            return Err(ConfigBuildError::Empty("adapter service name".to_string()));
+        }
+        if adapter.provider.trim().is_empty() {
+            return Err(ConfigBuildError::Empty(format!(
+                "adapters.{}.provider",
+                service
+            )));
+        }
+        if adapter.version.trim().is_empty() {
+            return Err(ConfigBuildError::Empty(format!(
+                "adapters.{}.version",
+                service
+            )));
+        }
+    }
+    config.adapters.check()?;
+
+    Ok(())
+}
+
+/// Reason `ConfigBuilder::build` or `Config::from_env` rejected a config
+#[derive(Debug, PartialEq, thiserror::Error)]
+#[allow(dead_code)] // Library API for embedders
+pub enum ConfigBuildError {
+    #[error(transparent)]
+    Adapter(#[from] AdapterValidationError),
+    #[error("{0} must not be empty")]
+    Empty(String),
+    #[error("{variable} has an invalid value: {value:?}")]
+    InvalidEnv { value: String, variable: String },
+    #[error("{0} must be set")]
+    MissingEnv(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults_match_config_defaults() {
+        let built = ConfigBuilder::new().build().unwrap();
+        let default = Config::default();
+
+        assert_eq!(built.server.host, default.server.host);
+        assert_eq!(built.server.port, default.server.port);
+        let mut built_services: Vec<_> = built.adapters.services.keys().collect();
+        let mut default_services: Vec<_> = default.adapters.services.keys().collect();
+        built_services.sort();
+        default_services.sort();
+        assert_eq!(built_services, default_services);
+    }
+
+    #[test]
+    fn test_builder_sets_fields() {
+        let config = Config::builder()
+            .host("0.0.0.0")
+            .port(3000)
+            .base_path("api")
+            .data_dir("/tmp/data")
+            .cache_dir("/tmp/cache")
+            .without_adapters()
+            .adapter(
+                "storage",
+                ServiceAdapterConfig::new("sqlite").with_setting("path", "db.sqlite3"),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.port, 3000);
+        assert_eq!(config.server.base_path, "api");
+        assert_eq!(config.storage.data_dir, Some("/tmp/data".into()));
+        assert_eq!(config.storage.cache_dir, Some("/tmp/cache".into()));
+        assert!(config.adapters.get_service("llm").is_none());
+
+        let storage = config.adapters.get_service("storage").unwrap();
+        assert_eq!(storage.provider, "sqlite");
+        assert_eq!(storage.config["path"].as_str(), Some("db.sqlite3"));
+    }
+
+    #[test]
+    fn test_builder_rejects_empty_values() {
+        assert_eq!(
+            Config::builder().host(" ").build().unwrap_err(),
+            ConfigBuildError::Empty("server.host".to_string())
+        );
+
+        let error = Config::builder()
+            .adapter("llm", ServiceAdapterConfig::new(""))
+            .build()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "adapters.llm.provider must not be empty");
+    }
+}
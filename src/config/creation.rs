@@ -21,7 +21,7 @@ pub fn create_default_config_file<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
         .context("Failed to serialize default config to TOML")?;
 
     // Write config file
-    fs::write(path, toml_content)
+    write_private_file(path, toml_content.as_bytes())
         .with_context(|| format!("Failed to write default config file: {}", path.display()))?;
 
     tracing::info!("Created default config file: {}", path.display());
@@ -34,6 +34,38 @@ pub fn create_default_config_file<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
         .to_path_buf())
 }
 
+/// Commented starting config written by `config init`
+pub const CONFIG_TEMPLATE: &str = include_str!("template.toml");
+
+/// Write `CONFIG_TEMPLATE` to `path`
+/// Returns the directory containing the created config file
+///
+/// An existing file is only replaced if `force` is set.
+pub fn create_config_template<P: AsRef<Path>>(path: P, force: bool) -> Result<PathBuf> {
+    let path = path.as_ref();
+    if path.exists() && !force {
+        anyhow::bail!(
+            "Config file already exists: {} (use --force to overwrite it)",
+            path.display()
+        );
+    }
+
+    // Create parent directory if it doesn't exist
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory: {}", parent.display()))?;
+    }
+
+    write_private_file(path, CONFIG_TEMPLATE.as_bytes())
+        .with_context(|| format!("Failed to write config file: {}", path.display()))?;
+
+    let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    Ok(canonical_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf())
+}
+
 /// Create a config file with custom content
 /// Returns the directory containing the created config file
 #[allow(dead_code)]
@@ -51,7 +83,7 @@ pub fn create_config_file<P: AsRef<Path>>(path: P, config: &Config) -> Result<Pa
         toml::to_string_pretty(config).context("Failed to serialize config to TOML")?;
 
     // Write config file
-    fs::write(path, toml_content)
+    write_private_file(path, toml_content.as_bytes())
         .with_context(|| format!("Failed to write config file: {}", path.display()))?;
 
     tracing::info!("Created config file: {}", path.display());
@@ -64,6 +96,114 @@ pub fn create_config_file<P: AsRef<Path>>(path: P, config: &Config) -> Result<Pa
         .to_path_buf())
 }
 
+/// Replace `path` with `content` atomically, readable only by the owner on Unix
+///
+/// The content goes to a temporary file next to `path` that's renamed into
+/// place once written, so a crash leaves either the old or the new file,
+/// never a truncated one.
+fn write_private_file(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "Path has no file name")
+    })?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result =
+        write_new_private_file(&temp_path, content).and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Write `content` to a new file at `path` and flush it to disk
+fn write_new_private_file(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// Create the data directory and its `adapters/` subtree if they are missing
+///
+/// New directories are private to the current user on Unix. Returns the
+/// directories that were actually created.
+pub fn create_data_dirs<P: AsRef<Path>>(data_dir: P) -> Result<Vec<PathBuf>> {
+    let data_dir = data_dir.as_ref();
+    let mut created = Vec::new();
+
+    for dir in [data_dir.to_path_buf(), data_dir.join("adapters")] {
+        if dir.is_dir() {
+            continue;
+        }
+
+        create_private_dir(&dir).map_err(|e| create_dir_error("data", "data_dir", &dir, e))?;
+
+        tracing::info!("Created data directory: {}", dir.display());
+        created.push(dir);
+    }
+
+    Ok(created)
+}
+
+/// Create the cache directory if it is missing
+///
+/// Like the data directory, it's private to the current user on Unix.
+/// Returns whether the directory was created.
+pub fn create_cache_dir<P: AsRef<Path>>(cache_dir: P) -> Result<bool> {
+    let cache_dir = cache_dir.as_ref();
+    if cache_dir.is_dir() {
+        return Ok(false);
+    }
+
+    create_private_dir(cache_dir)
+        .map_err(|e| create_dir_error("cache", "cache_dir", cache_dir, e))?;
+
+    tracing::info!("Created cache directory: {}", cache_dir.display());
+    Ok(true)
+}
+
+/// Error for a `kind` directory that couldn't be created, with a hint on
+/// permission errors pointing at the `storage.<setting>` to change
+fn create_dir_error(kind: &str, setting: &str, dir: &Path, error: std::io::Error) -> anyhow::Error {
+    let context = format!("Failed to create {} directory: {}", kind, dir.display());
+    if error.kind() == std::io::ErrorKind::PermissionDenied {
+        return anyhow::anyhow!(
+            "{}: permission denied. Set storage.{} to a directory you can write to, \
+             or create it yourself and start with --create-dirs=false",
+            context,
+            setting
+        );
+    }
+    anyhow::Error::new(error).context(context)
+}
+
+/// Create a directory (and missing parents) readable only by the owner
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+
+    builder.create(path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,6 +251,55 @@ mod tests {
         assert!(nested_path.parent().unwrap().exists());
     }
 
+    #[test]
+    fn test_config_template_is_valid_default_config() {
+        let config: Config = toml::from_str(CONFIG_TEMPLATE).unwrap();
+        crate::config::builder::check(&config).unwrap();
+
+        // The template only spells out defaults
+        let defaults = Config::default();
+        assert_eq!(config.server.host, defaults.server.host);
+        assert_eq!(config.server.port, defaults.server.port);
+        assert_eq!(config.server.base_path, defaults.server.base_path);
+        assert_eq!(
+            config.adapters.services["llm"].provider,
+            defaults.adapters.services["llm"].provider
+        );
+        assert_eq!(
+            config.adapters.services["llm"].version,
+            defaults.adapters.services["llm"].version
+        );
+        assert!(!config.adapters.services.contains_key("storage"));
+    }
+
+    #[test]
+    fn test_create_config_template() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("nested").join("config.toml");
+
+        let config_dir = create_config_template(&config_path, false).unwrap();
+
+        assert_eq!(
+            config_dir,
+            config_path.parent().unwrap().canonicalize().unwrap()
+        );
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), CONFIG_TEMPLATE);
+    }
+
+    #[test]
+    fn test_create_config_template_needs_force_to_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(&config_path, "# mine").unwrap();
+
+        let error = create_config_template(&config_path, false).unwrap_err();
+        assert!(error.to_string().contains("--force"));
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), "# mine");
+
+        create_config_template(&config_path, true).unwrap();
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), CONFIG_TEMPLATE);
+    }
+
     #[test]
     fn test_create_config_file_with_custom_content() {
         let temp_dir = TempDir::new().unwrap();
@@ -154,4 +343,130 @@ mod tests {
         assert!(nested_path.exists());
         assert!(nested_path.parent().unwrap().exists());
     }
+
+    #[test]
+    fn test_create_config_file_replaces_atomically() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(&config_path, "# old config").unwrap();
+
+        let mut config = Config::default();
+        config.server.port = 9001;
+        create_config_file(&config_path, &config).unwrap();
+
+        let parsed: Config = toml::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(parsed.server.port, 9001);
+
+        // No temporary file is left behind
+        let entries: Vec<_> = fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_failed_write_keeps_existing_target() {
+        let temp_dir = TempDir::new().unwrap();
+        // A directory can't be replaced by a file
+        let config_path = temp_dir.path().join("config.toml");
+        fs::create_dir(&config_path).unwrap();
+        fs::write(config_path.join("keep"), "").unwrap();
+
+        assert!(create_config_file(&config_path, &Config::default()).is_err());
+
+        assert!(config_path.join("keep").exists());
+        let entries: Vec<_> = fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_config_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let default_path = temp_dir.path().join("default.toml");
+        let custom_path = temp_dir.path().join("custom.toml");
+        // Replacing a world-readable file makes it private too
+        fs::write(&custom_path, "").unwrap();
+        fs::set_permissions(&custom_path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        create_default_config_file(&default_path).unwrap();
+        create_config_file(&custom_path, &Config::default()).unwrap();
+
+        for path in [default_path, custom_path] {
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn test_create_data_dirs() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("nested").join("data");
+
+        let created = create_data_dirs(&data_dir).unwrap();
+
+        assert_eq!(created, vec![data_dir.clone(), data_dir.join("adapters")]);
+        assert!(data_dir.join("adapters").is_dir());
+
+        // Second run is a no-op
+        assert!(create_data_dirs(&data_dir).unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_data_dirs_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("data");
+
+        create_data_dirs(&data_dir).unwrap();
+
+        let mode = fs::metadata(data_dir.join("adapters"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o077, 0);
+    }
+
+    #[test]
+    fn test_create_cache_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_dir = temp_dir.path().join("nested").join("cache");
+
+        assert!(create_cache_dir(&cache_dir).unwrap());
+        assert!(cache_dir.is_dir());
+
+        // Second run is a no-op
+        assert!(!create_cache_dir(&cache_dir).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_dirs_permission_denied() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let read_only = temp_dir.path().join("read_only");
+        fs::create_dir(&read_only).unwrap();
+        fs::set_permissions(&read_only, fs::Permissions::from_mode(0o500)).unwrap();
+        // Root ignores directory permissions
+        if fs::write(read_only.join("probe"), "").is_ok() {
+            return;
+        }
+
+        let error = create_data_dirs(read_only.join("data")).unwrap_err();
+        assert!(error.to_string().contains("storage.data_dir"), "{}", error);
+        let error = create_cache_dir(read_only.join("cache")).unwrap_err();
+        assert!(error.to_string().contains("storage.cache_dir"), "{}", error);
+    }
+
+    #[test]
+    fn test_create_data_dirs_existing_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("data");
+        fs::write(&data_dir, "not a directory").unwrap();
+
+        assert!(create_data_dirs(&data_dir).is_err());
+    }
 }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::ffi::OsString;
 use std::path::PathBuf;
 use toml::Table;
 
@@ -30,12 +31,312 @@ pub fn default_base_path() -> String {
     DEFAULT_SERVER_BASE_PATH.to_string()
 }
 
+/// API versions mounted when `[server] api_versions` isn't set
+pub const DEFAULT_API_VERSIONS: [&str; 1] = ["v1"];
+
+/// Get default API versions (for serde defaults)
+pub fn default_api_versions() -> Vec<crate::config::schema::ApiVersionConfig> {
+    DEFAULT_API_VERSIONS
+        .into_iter()
+        .map(crate::config::schema::ApiVersionConfig::stable)
+        .collect()
+}
+
+/// Time limit for a response to start, for routes without their own limit
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 15;
+
+/// Time limit for /v1/message responses to start (LLM calls can be slow)
+pub const DEFAULT_MESSAGE_TIMEOUT_SECS: u64 = 120;
+
+/// Get default request timeout (for serde defaults)
+pub fn default_request_timeout_secs() -> u64 {
+    DEFAULT_REQUEST_TIMEOUT_SECS
+}
+
+/// Get default message timeout (for serde defaults)
+pub fn default_message_timeout_secs() -> u64 {
+    DEFAULT_MESSAGE_TIMEOUT_SECS
+}
+
+/// Chunks buffered between the LLM adapter and a slow streaming client
+pub const DEFAULT_STREAM_BUFFER: usize = 16;
+
+/// Get default streaming buffer size (for serde defaults)
+pub fn default_stream_buffer() -> usize {
+    DEFAULT_STREAM_BUFFER
+}
+
+/// Largest request body accepted, in bytes
+pub const DEFAULT_MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// Get default request body limit (for serde defaults)
+pub fn default_max_body_bytes() -> usize {
+    DEFAULT_MAX_BODY_BYTES
+}
+
+/// Largest decoded image accepted in message content, in bytes
+///
+/// Base64 grows it by a third, which still fits the default body limit.
+pub const DEFAULT_MAX_IMAGE_BYTES: usize = 3 * 1024 * 1024;
+
+/// Get default image size limit (for serde defaults)
+pub fn default_max_image_bytes() -> usize {
+    DEFAULT_MAX_IMAGE_BYTES
+}
+
+/// Largest audio file accepted for transcription, in bytes
+///
+/// Leaves room for the multipart framing within the default body limit.
+pub const DEFAULT_MAX_AUDIO_BYTES: usize = 3 * 1024 * 1024;
+
+/// Get default audio size limit (for serde defaults)
+pub fn default_max_audio_bytes() -> usize {
+    DEFAULT_MAX_AUDIO_BYTES
+}
+
+/// Conversations a search reads before it stops and reports `truncated`
+pub const DEFAULT_MAX_SEARCH_SCANNED: usize = 1000;
+
+/// Get default search scan limit (for serde defaults)
+pub fn default_max_search_scanned() -> usize {
+    DEFAULT_MAX_SEARCH_SCANNED
+}
+
+/// Responses kept for replaying requests with an idempotency key
+pub const DEFAULT_IDEMPOTENCY_MAX_ENTRIES: usize = 1000;
+
+/// Seconds a response to a request with an idempotency key can be replayed
+pub const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Get default idempotency cache size (for serde defaults)
+pub fn default_idempotency_max_entries() -> usize {
+    DEFAULT_IDEMPOTENCY_MAX_ENTRIES
+}
+
+/// Get default idempotency window (for serde defaults)
+pub fn default_idempotency_ttl_secs() -> u64 {
+    DEFAULT_IDEMPOTENCY_TTL_SECS
+}
+
+/// Background jobs run at the same time
+pub const DEFAULT_JOBS_CONCURRENCY: usize = 4;
+
+/// Runs of a failing background job before it's dropped
+pub const DEFAULT_JOBS_MAX_ATTEMPTS: u32 = 5;
+
+/// Seconds between checks for due background jobs
+pub const DEFAULT_JOBS_POLL_INTERVAL_SECS: u64 = 5;
+
+/// Seconds before a failed background job is first retried
+pub const DEFAULT_JOBS_RETRY_BASE_SECS: u64 = 10;
+
+/// Longest wait between retries of a background job, in seconds
+pub const DEFAULT_JOBS_RETRY_MAX_SECS: u64 = 60 * 60;
+
+/// Get default background job concurrency (for serde defaults)
+pub fn default_jobs_concurrency() -> usize {
+    DEFAULT_JOBS_CONCURRENCY
+}
+
+/// Background jobs run unless disabled (for serde defaults)
+pub fn default_jobs_enabled() -> bool {
+    true
+}
+
+/// Get default background job attempts (for serde defaults)
+pub fn default_jobs_max_attempts() -> u32 {
+    DEFAULT_JOBS_MAX_ATTEMPTS
+}
+
+/// Get default background job poll interval (for serde defaults)
+pub fn default_jobs_poll_interval_secs() -> u64 {
+    DEFAULT_JOBS_POLL_INTERVAL_SECS
+}
+
+/// Get default first retry delay of background jobs (for serde defaults)
+pub fn default_jobs_retry_base_secs() -> u64 {
+    DEFAULT_JOBS_RETRY_BASE_SECS
+}
+
+/// Get default longest retry delay of background jobs (for serde defaults)
+pub fn default_jobs_retry_max_secs() -> u64 {
+    DEFAULT_JOBS_RETRY_MAX_SECS
+}
+
+/// Paths that don't require an API key when auth is enabled
+pub const DEFAULT_AUTH_EXEMPT_PATHS: &[&str] = &["/"];
+
+/// Get default auth-exempt paths (for serde defaults)
+pub fn default_auth_exempt() -> Vec<String> {
+    DEFAULT_AUTH_EXEMPT_PATHS
+        .iter()
+        .map(|path| path.to_string())
+        .collect()
+}
+
+/// Header and JSON field names the access log never shows the values of
+pub const DEFAULT_ACCESS_LOG_REDACT: &[&str] = &[
+    "api_key",
+    "authorization",
+    "cookie",
+    "password",
+    "proxy-authorization",
+    "set-cookie",
+    "token",
+    "x-api-key",
+];
+
+/// Get default access log redactions (for serde defaults)
+pub fn default_access_log_redact() -> Vec<String> {
+    DEFAULT_ACCESS_LOG_REDACT
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Directory of the usage ledger, relative to the data directory
+pub const DEFAULT_USAGE_LOG_DIR: &str = "usage";
+
+/// File name of the current usage ledger
+pub const DEFAULT_USAGE_LOG_FILE: &str = "ledger.jsonl";
+
+/// Size at which the usage ledger is rotated
+pub const DEFAULT_USAGE_LOG_MAX_SIZE_MB: u64 = 10;
+
+/// Rotated usage ledgers kept besides the current one
+pub const DEFAULT_USAGE_LOG_MAX_FILES: usize = 5;
+
+/// Get default for recording failed calls in the usage ledger (for serde defaults)
+pub fn default_usage_log_include_failed() -> bool {
+    true
+}
+
+/// Get default usage ledger size limit (for serde defaults)
+pub fn default_usage_log_max_size_mb() -> u64 {
+    DEFAULT_USAGE_LOG_MAX_SIZE_MB
+}
+
+/// Get default number of rotated usage ledgers (for serde defaults)
+pub fn default_usage_log_max_files() -> usize {
+    DEFAULT_USAGE_LOG_MAX_FILES
+}
+
+/// Stored messages sent with each message of a conversation with `[conversations.memory]`
+pub const DEFAULT_MEMORY_WINDOW_MESSAGES: usize = 20;
+
+/// Estimated tokens of unsummarized messages at which a conversation is summarized
+pub const DEFAULT_MEMORY_SUMMARY_TRIGGER_TOKENS: u64 = 4000;
+
+/// Get default number of stored messages sent (for serde defaults)
+pub fn default_memory_window_messages() -> usize {
+    DEFAULT_MEMORY_WINDOW_MESSAGES
+}
+
+/// Get default summary threshold (for serde defaults)
+pub fn default_memory_summary_trigger_tokens() -> u64 {
+    DEFAULT_MEMORY_SUMMARY_TRIGGER_TOKENS
+}
+
+/// Whether `serve` creates missing data directories on startup
+pub const DEFAULT_CREATE_DIRS: bool = true;
+
 /// Default adapter provider for LLM service
 pub const DEFAULT_LLM_PROVIDER: &str = "ollama";
 
 /// Default adapter version for all adapters
 pub const DEFAULT_ADAPTER_VERSION: &str = "latest";
 
+/// Database file of the SQLite storage provider, relative to the data directory
+pub const DEFAULT_SQLITE_FILE: &str = "storage.sqlite3";
+
+/// Default number of WASM instances per adapter (one store's memory each)
+pub const DEFAULT_ADAPTER_POOL_SIZE: usize = 1;
+
+/// Default bound on requests waiting for a concurrency-limited adapter
+pub const DEFAULT_ADAPTER_MAX_QUEUED: usize = 64;
+
+/// Timeout for adapter requests to providers (LLM responses can be slow)
+pub const DEFAULT_ADAPTER_HTTP_TIMEOUT_SECS: u64 = 300;
+
+/// Largest provider response body an adapter reads, in bytes
+pub const DEFAULT_ADAPTER_MAX_RESPONSE_BYTES: usize = 8 * 1024 * 1024;
+
+/// Get default provider response limit (for serde defaults)
+pub fn default_adapter_max_response_bytes() -> usize {
+    DEFAULT_ADAPTER_MAX_RESPONSE_BYTES
+}
+
+/// Most headers an adapter may set on a provider request
+pub const MAX_ADAPTER_REQUEST_HEADERS: usize = 64;
+
+/// Largest total size of the headers an adapter sets, names and values
+pub const MAX_ADAPTER_REQUEST_HEADER_BYTES: usize = 16 * 1024;
+
+/// Timeout for adapters connecting to providers
+pub const DEFAULT_ADAPTER_CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// How long idle provider connections stay pooled for reuse
+pub const DEFAULT_ADAPTER_POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+
+/// TCP keep-alive interval for provider connections
+pub const DEFAULT_ADAPTER_TCP_KEEPALIVE_SECS: u64 = 60;
+
+/// Idle provider connections pooled per host
+///
+/// Enough for bursts of concurrent requests to one provider (e.g. a local
+/// Ollama) to find a warm connection, without holding sockets open forever.
+pub const DEFAULT_HTTP_POOL_MAX_IDLE_PER_HOST: usize = 32;
+
+/// Get default pool idle timeout (for serde defaults)
+pub fn default_http_pool_idle_timeout_secs() -> u64 {
+    DEFAULT_ADAPTER_POOL_IDLE_TIMEOUT_SECS
+}
+
+/// Get default idle connections per host (for serde defaults)
+pub fn default_http_pool_max_idle_per_host() -> usize {
+    DEFAULT_HTTP_POOL_MAX_IDLE_PER_HOST
+}
+
+/// Get default TCP keep-alive interval (for serde defaults)
+pub fn default_http_tcp_keepalive_secs() -> u64 {
+    DEFAULT_ADAPTER_TCP_KEEPALIVE_SECS
+}
+
+/// Seconds clients are told to wait when an adapter's queue is full
+pub const DEFAULT_RETRY_AFTER_SECS: u64 = 1;
+
+/// Consecutive provider failures that open an adapter's circuit
+pub const DEFAULT_CIRCUIT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Seconds an open circuit fails requests fast before probing the provider
+pub const DEFAULT_CIRCUIT_COOLDOWN_SECS: u64 = 30;
+
+/// Get default circuit breaker failure threshold (for serde defaults)
+pub fn default_circuit_failure_threshold() -> u32 {
+    DEFAULT_CIRCUIT_FAILURE_THRESHOLD
+}
+
+/// Get default circuit breaker cooldown (for serde defaults)
+pub fn default_circuit_cooldown_secs() -> u64 {
+    DEFAULT_CIRCUIT_COOLDOWN_SECS
+}
+
+/// Rotated log files kept besides the current one
+pub const DEFAULT_LOG_MAX_FILES: usize = 7;
+
+/// Size limit per log file for size-based rotation
+pub const DEFAULT_LOG_MAX_SIZE_MB: u64 = 10;
+
+/// Get default number of rotated log files (for serde defaults)
+pub fn default_log_max_files() -> usize {
+    DEFAULT_LOG_MAX_FILES
+}
+
+/// Get default log file size limit (for serde defaults)
+pub fn default_log_max_size_mb() -> u64 {
+    DEFAULT_LOG_MAX_SIZE_MB
+}
+
 /// Get default LLM provider as String (for serde defaults)
 pub fn default_llm_provider() -> String {
     DEFAULT_LLM_PROVIDER.to_string()
@@ -46,6 +347,16 @@ pub fn default_adapter_version() -> String {
     DEFAULT_ADAPTER_VERSION.to_string()
 }
 
+/// Share of requests an endpoint gets unless weighted (for serde defaults)
+pub fn default_endpoint_weight() -> u32 {
+    1
+}
+
+/// Invalid adapter manifests fail loading unless disabled (for serde defaults)
+pub fn default_strict_manifest() -> bool {
+    true
+}
+
 /// Get default adapter services HashMap (for serde defaults)
 pub fn default_adapter_services() -> HashMap<String, crate::config::schema::ServiceAdapterConfig> {
     let mut services = HashMap::new();
@@ -57,6 +368,15 @@ pub fn default_adapter_services() -> HashMap<String, crate::config::schema::Serv
             provider: default_llm_provider(),
             version: default_adapter_version(),
             config: toml::Value::Table(Table::new()),
+            balance: Default::default(),
+            circuit_breaker: Default::default(),
+            default_provider: None,
+            endpoints: Vec::new(),
+            fallback: Vec::new(),
+            http: Default::default(),
+            max_concurrent: None,
+            max_queued: None,
+            pool_size: None,
         },
     );
 
@@ -75,19 +395,59 @@ where
     }
 }
 
+/// Directory set in an XDG base directory variable, given its `value`
+///
+/// Only used on Linux and the BSDs; macOS and Windows have their own
+/// conventions. Relative paths are ignored, as the XDG spec demands.
+fn xdg_dir(value: Option<OsString>) -> Option<PathBuf> {
+    if cfg!(any(target_os = "macos", windows)) {
+        return None;
+    }
+
+    value.map(PathBuf::from).filter(|path| path.is_absolute())
+}
+
 /// Get the default data directory using platform-specific paths
+///
+/// `XDG_DATA_HOME` takes precedence where XDG applies.
 pub fn default_data_dir() -> PathBuf {
-    platform_dir_with_fallback(dirs::data_dir, "./ai_messenger")
+    data_dir_from_vars(|name| std::env::var_os(name))
+}
+
+/// `default_data_dir` reading variables with `var` instead of from the process environment
+fn data_dir_from_vars(var: impl Fn(&str) -> Option<OsString>) -> PathBuf {
+    platform_dir_with_fallback(
+        || xdg_dir(var("XDG_DATA_HOME")).or_else(dirs::data_dir),
+        "./ai_messenger",
+    )
 }
 
 /// Get the default cache directory using platform-specific paths
+///
+/// `XDG_CACHE_HOME` takes precedence where XDG applies.
 pub fn default_cache_dir() -> PathBuf {
-    platform_dir_with_fallback(dirs::cache_dir, "./ai_messenger/cache")
+    cache_dir_from_vars(|name| std::env::var_os(name))
+}
+
+/// `default_cache_dir` reading variables with `var` instead of from the process environment
+fn cache_dir_from_vars(var: impl Fn(&str) -> Option<OsString>) -> PathBuf {
+    platform_dir_with_fallback(
+        || xdg_dir(var("XDG_CACHE_HOME")).or_else(dirs::cache_dir),
+        "./ai_messenger/cache",
+    )
 }
 
 /// Get the default config directory using platform-specific paths
+///
+/// `XDG_CONFIG_HOME` takes precedence where XDG applies.
 pub fn default_config_dir() -> PathBuf {
-    dirs::config_local_dir()
+    config_dir_from_vars(|name| std::env::var_os(name))
+}
+
+/// `default_config_dir` reading variables with `var` instead of from the process environment
+fn config_dir_from_vars(var: impl Fn(&str) -> Option<OsString>) -> PathBuf {
+    xdg_dir(var("XDG_CONFIG_HOME"))
+        .or_else(dirs::config_local_dir)
         .unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")))
 }
 
@@ -113,6 +473,7 @@ pub fn local_config_file() -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
 
     #[test]
     fn test_default_data_dir() {
@@ -137,12 +498,52 @@ mod tests {
         assert!(cache_dir.is_absolute() || cache_dir.starts_with("./ai_messenger"));
     }
 
+    /// Variable lookup answering only `name`, with `value`
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn only(name: &'static str, value: impl Into<OsString>) -> impl Fn(&str) -> Option<OsString> {
+        let value = value.into();
+        move |var| (var == name).then(|| value.clone())
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_xdg_data_home() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            data_dir_from_vars(only("XDG_DATA_HOME", temp_dir.path())),
+            temp_dir.path().join(APP_DOMAIN)
+        );
+
+        // Relative values are invalid per the XDG spec
+        assert!(
+            !data_dir_from_vars(only("XDG_DATA_HOME", "relative/data")).starts_with("relative")
+        );
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_xdg_config_and_cache_home() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = temp_dir.path().join("config");
+        let cache = temp_dir.path().join("cache");
+
+        assert_eq!(
+            config_dir_from_vars(only("XDG_CONFIG_HOME", &config)),
+            config
+        );
+        assert_eq!(
+            cache_dir_from_vars(only("XDG_CACHE_HOME", &cache)),
+            cache.join(APP_DOMAIN)
+        );
+    }
+
     #[test]
     fn test_default_config_dir() {
         let config_dir = default_config_dir();
 
         // Should be absolute or fallback to current dir
-        assert!(config_dir.is_absolute() || config_dir == PathBuf::from("."));
+        assert!(config_dir.is_absolute() || config_dir == Path::new("."));
     }
 
     #[test]
@@ -4,17 +4,30 @@ use std::path::{Path, PathBuf};
 
 use super::creation::create_default_config_file;
 use super::defaults;
+use super::profiles::apply_profile;
 use super::schema::Config;
 
 /// Load configuration from a specific file (must exist)
 /// Returns the config and the directory containing the config file
-pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<(Config, PathBuf)> {
+///
+/// `profile` names a `[profiles.<name>]` section to apply over the base
+/// sections, see `profiles::apply_profile`.
+pub fn load_from_file<P: AsRef<Path>>(path: P, profile: Option<&str>) -> Result<(Config, PathBuf)> {
     let path = path.as_ref();
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read config file: {}", path.display()))?;
 
-    let config: Config = toml::from_str(&content)
+    let mut table: toml::Table = toml::from_str(&content)
         .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+    apply_profile(&mut table, profile).with_context(|| format!("In {}", path.display()))?;
+    let mut config: Config = toml::Value::Table(table)
+        .try_into()
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+    config.profile = profile.map(str::to_string);
+    config
+        .adapters
+        .check()
+        .with_context(|| format!("Invalid config file: {}", path.display()))?;
 
     // Get the directory containing the config file for relative path resolution
     let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
@@ -28,81 +41,123 @@ pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<(Config, PathBuf)> {
     Ok((config, config_dir))
 }
 
-/// Load configuration using fallback chain (silent version)
-/// Returns the config and the directory containing the config file (if found)
-pub fn load_with_fallback_silent() -> Result<(Config, Option<PathBuf>)> {
-    let fallback_paths = [
+/// Config files tried, in order, when no file is given explicitly
+fn fallback_paths() -> [PathBuf; 3] {
+    [
         defaults::local_config_file(),    // ./ai_messenger.toml
         defaults::home_config_file(),     // ~/.ai_messenger.toml
         defaults::platform_config_file(), // ~/Library/Preferences/com.christiangrete.ai_messenger.toml
-    ];
+    ]
+}
 
-    for path in &fallback_paths {
-        if path.exists()
-            && let Ok((config, config_dir)) = load_from_file(path)
-        {
-            return Ok((config, Some(config_dir)));
+/// Load the first of `paths` that exists and parses
+///
+/// Broken files are skipped, with a warning unless `silent`. The first
+/// file that parses is the config file: if `profile` can't be applied to
+/// it, e.g. because it doesn't define it, that's an error rather than a
+/// reason to look further.
+fn load_first(
+    paths: &[PathBuf],
+    silent: bool,
+    profile: Option<&str>,
+) -> Result<Option<(Config, PathBuf)>> {
+    for path in paths {
+        if !path.exists() {
+            continue;
+        }
+        match load_from_file(path, profile) {
+            Ok(loaded) => return Ok(Some(loaded)),
+            Err(e) if profile.is_some() && load_from_file(path, None).is_ok() => return Err(e),
+            Err(e) if !silent => tracing::warn!("Skipping config file: {:#}", e),
+            Err(_) => {}
         }
     }
+    Ok(None)
+}
 
-    // No config file found, use defaults (silent)
-    Ok((Config::default(), None))
+/// Error for a `profile` selected when no config file defines it
+fn check_no_profile(profile: Option<&str>) -> Result<()> {
+    match profile {
+        Some(profile) => anyhow::bail!(
+            "Config profile '{}' selected, but no config file was loaded to take it from",
+            profile
+        ),
+        None => Ok(()),
+    }
 }
 
-/// Load configuration using fallback chain
+/// Load configuration using fallback chain (silent version)
 /// Returns the config and the directory containing the config file (if found)
-pub fn load_with_fallback() -> Result<(Config, Option<PathBuf>)> {
-    let fallback_paths = [
-        defaults::local_config_file(),    // ./ai_messenger.toml
-        defaults::home_config_file(),     // ~/.ai_messenger.toml
-        defaults::platform_config_file(), // ~/Library/Preferences/com.christiangrete.ai_messenger.toml
-    ];
-
-    for path in &fallback_paths {
-        if path.exists() {
-            match load_from_file(path) {
-                Ok((config, config_dir)) => {
-                    // Debug logging already handled in load_from_file
-                    return Ok((config, Some(config_dir)));
-                }
-                Err(_e) => {
-                    // Continue with next fallback
-                }
-            }
+pub fn load_with_fallback_silent(profile: Option<&str>) -> Result<(Config, Option<PathBuf>)> {
+    match load_first(&fallback_paths(), true, profile)? {
+        Some((config, config_dir)) => Ok((config, Some(config_dir))),
+        // No config file found, use defaults (silent)
+        None => {
+            check_no_profile(profile)?;
+            Ok((Config::default(), None))
         }
     }
+}
 
-    // No config file found, create default config at platform-specific location
-    let platform_config_path = defaults::platform_config_file();
+/// Load configuration using fallback chain
+/// Returns the config and the directory containing the config file (if found)
+///
+/// Like the silent version, nothing is written when no file is found;
+/// skipped files and the fallback to built-in defaults are logged.
+pub fn load_with_fallback(profile: Option<&str>) -> Result<(Config, Option<PathBuf>)> {
+    if let Some((config, config_dir)) = load_first(&fallback_paths(), false, profile)? {
+        return Ok((config, Some(config_dir)));
+    }
+    check_no_profile(profile)?;
 
-    match create_default_config_file(&platform_config_path) {
-        Ok(config_dir) => {
-            tracing::debug!(
-                "Created and loaded default config from: {}",
-                platform_config_path.display()
-            );
-            Ok((Config::default(), Some(config_dir)))
-        }
-        Err(e) => {
-            // If we can't write to platform location, fall back to memory defaults
-            tracing::warn!(
-                "Failed to create default config file: {}, using memory defaults",
-                e
-            );
-            Ok((Config::default(), None))
-        }
+    tracing::info!(
+        "No config file found, using built-in defaults (serve --init creates {})",
+        defaults::platform_config_file().display()
+    );
+    Ok((Config::default(), None))
+}
+
+/// Load configuration using fallback chain, creating a default config
+/// file at the platform location if none is found
+/// Returns the config and the directory containing the config file
+pub fn load_with_fallback_or_create(profile: Option<&str>) -> Result<(Config, Option<PathBuf>)> {
+    load_or_create(
+        &fallback_paths(),
+        &defaults::platform_config_file(),
+        profile,
+    )
+}
+
+/// Load the first of `paths`, or create a default config file at `create_at`
+///
+/// Creating the file was asked for, so failing to is an error rather than
+/// a fallback to defaults. A broken file at `create_at` isn't overwritten.
+/// The created file defines no profiles, so none may be selected.
+fn load_or_create(
+    paths: &[PathBuf],
+    create_at: &Path,
+    profile: Option<&str>,
+) -> Result<(Config, Option<PathBuf>)> {
+    if let Some((config, config_dir)) = load_first(paths, false, profile)? {
+        return Ok((config, Some(config_dir)));
+    }
+    if create_at.exists() {
+        let (config, config_dir) = load_from_file(create_at, profile)?;
+        return Ok((config, Some(config_dir)));
     }
+    check_no_profile(profile)?;
+
+    let config_dir = create_default_config_file(create_at)?;
+    Ok((Config::default(), Some(config_dir)))
 }
 
 /// Check if a config file exists and is readable
-#[cfg(test)]
 pub fn config_exists<P: AsRef<Path>>(path: P) -> bool {
     let path = path.as_ref();
     path.exists() && path.is_file()
 }
 
 /// Get all potential config file locations for debugging
-#[cfg(test)]
 pub fn list_config_locations() -> Vec<(String, PathBuf, bool)> {
     let paths = [
         ("Local", defaults::local_config_file()),
@@ -142,7 +197,7 @@ data_dir = "/test/data"
         fs::write(&config_path, config_content).unwrap();
 
         let (config, _config_dir) =
-            load_from_file(&config_path).expect("Should load config successfully");
+            load_from_file(&config_path, None).expect("Should load config successfully");
 
         assert_eq!(config.server.host, "0.0.0.0");
         assert_eq!(config.server.port, 3000);
@@ -153,7 +208,7 @@ data_dir = "/test/data"
     fn test_load_from_file_not_found() {
         let non_existent_path = "/this/path/does/not/exist.toml";
 
-        let result = load_from_file(non_existent_path);
+        let result = load_from_file(non_existent_path, None);
 
         assert!(result.is_err());
         assert!(
@@ -176,7 +231,7 @@ host = "broken
 
         fs::write(&config_path, invalid_content).unwrap();
 
-        let result = load_from_file(&config_path);
+        let result = load_from_file(&config_path, None);
 
         assert!(result.is_err());
         assert!(
@@ -191,7 +246,7 @@ host = "broken
     fn test_load_with_fallback_silent_defaults() {
         // When no config files exist, should return defaults silently
         let (config, _config_dir) =
-            load_with_fallback_silent().expect("Should return default config");
+            load_with_fallback_silent(None).expect("Should return default config");
 
         // Should have default values
         assert_eq!(config.server.host, "127.0.0.1");
@@ -202,7 +257,7 @@ host = "broken
     #[test]
     fn test_load_with_fallback_defaults() {
         // When no config files exist, should return defaults with message
-        let (config, _config_dir) = load_with_fallback().expect("Should return default config");
+        let (config, _config_dir) = load_with_fallback(None).expect("Should return default config");
 
         // Should have default values
         assert_eq!(config.server.host, "127.0.0.1");
@@ -210,6 +265,106 @@ host = "broken
         assert_eq!(config.storage.data_dir, None);
     }
 
+    #[test]
+    fn test_load_first_skips_missing_and_broken_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing.toml");
+        let broken = temp_dir.path().join("broken.toml");
+        let valid = temp_dir.path().join("valid.toml");
+        fs::write(&broken, "[server").unwrap();
+        fs::write(&valid, "[server]\nport = 9000\n").unwrap();
+
+        let (config, _) = load_first(&[missing.clone(), broken, valid], false, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.server.port, 9000);
+
+        // Nothing to load writes nothing
+        assert!(
+            load_first(std::slice::from_ref(&missing), false, None)
+                .unwrap()
+                .is_none()
+        );
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn test_load_or_create_creates_only_when_nothing_found() {
+        let temp_dir = TempDir::new().unwrap();
+        let existing = temp_dir.path().join("existing.toml");
+        let create_at = temp_dir.path().join("platform/config.toml");
+        fs::write(&existing, "[server]\nport = 9000\n").unwrap();
+
+        let (config, _) =
+            load_or_create(std::slice::from_ref(&existing), &create_at, None).unwrap();
+        assert_eq!(config.server.port, 9000);
+        assert!(!create_at.exists());
+
+        fs::remove_file(&existing).unwrap();
+        let (config, config_dir) =
+            load_or_create(std::slice::from_ref(&existing), &create_at, None).unwrap();
+        assert_eq!(config.server.port, 8080);
+        assert!(create_at.is_file());
+        assert!(config_dir.is_some());
+    }
+
+    #[test]
+    fn test_load_or_create_keeps_broken_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let create_at = temp_dir.path().join("config.toml");
+        fs::write(&create_at, "[server").unwrap();
+
+        let error = load_or_create(std::slice::from_ref(&create_at), &create_at, None).unwrap_err();
+
+        assert!(error.to_string().contains("Failed to parse config file"));
+        assert_eq!(fs::read_to_string(&create_at).unwrap(), "[server");
+    }
+
+    #[test]
+    fn test_load_from_file_with_profile() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            "[server]\nport = 8080\n\n[profiles.prod.server]\nport = 80\n",
+        )
+        .unwrap();
+
+        let (config, _) = load_from_file(&config_path, Some("prod")).unwrap();
+        assert_eq!(config.server.port, 80);
+        assert_eq!(config.profile.as_deref(), Some("prod"));
+
+        let (config, _) = load_from_file(&config_path, None).unwrap();
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.profile, None);
+
+        let error = load_from_file(&config_path, Some("dev")).unwrap_err();
+        assert!(format!("{:#}", error).contains("available: prod"));
+    }
+
+    #[test]
+    fn test_load_first_stops_at_a_file_without_the_profile() {
+        let temp_dir = TempDir::new().unwrap();
+        let local = temp_dir.path().join("local.toml");
+        let home = temp_dir.path().join("home.toml");
+        fs::write(&local, "[server]\nport = 9000\n").unwrap();
+        fs::write(&home, "[profiles.prod.server]\nport = 80\n").unwrap();
+
+        let error = load_first(&[local, home], false, Some("prod")).unwrap_err();
+        assert!(format!("{:#}", error).contains("defines no profiles"));
+    }
+
+    #[test]
+    fn test_load_or_create_with_profile_creates_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let create_at = temp_dir.path().join("config.toml");
+
+        // The created file wouldn't define the profile
+        let error = load_or_create(&[], &create_at, Some("prod")).unwrap_err();
+        assert!(error.to_string().contains("'prod'"));
+        assert!(!create_at.exists());
+    }
+
     #[test]
     fn test_config_exists() {
         let temp_dir = TempDir::new().unwrap();
@@ -288,7 +443,7 @@ port = 9999
         fs::write(&config_path, config_content).unwrap();
 
         let (config, _config_dir) =
-            load_from_file(&config_path).expect("Should load minimal config");
+            load_from_file(&config_path, None).expect("Should load minimal config");
 
         // Should have custom port but default host
         assert_eq!(config.server.host, "127.0.0.1");
@@ -304,7 +459,8 @@ port = 9999
         // Completely empty config file
         fs::write(&config_path, "").unwrap();
 
-        let (config, _config_dir) = load_from_file(&config_path).expect("Should load empty config");
+        let (config, _config_dir) =
+            load_from_file(&config_path, None).expect("Should load empty config");
 
         // Should use all defaults
         assert_eq!(config.server.host, "127.0.0.1");
@@ -338,11 +494,11 @@ port = 9000
         .unwrap();
 
         // First config should fail, but we should handle it gracefully
-        let result = load_from_file(&first_config);
+        let result = load_from_file(&first_config, None);
         assert!(result.is_err());
 
         // Second config should work
-        let result = load_from_file(&second_config);
+        let result = load_from_file(&second_config, None);
         assert!(result.is_ok());
         let (config, _) = result.unwrap();
         assert_eq!(config.server.host, "192.168.1.1");
@@ -366,7 +522,7 @@ host = "localhost"
         .unwrap();
 
         // This should work even if canonicalize has issues
-        let result = load_from_file(&config_path);
+        let result = load_from_file(&config_path, None);
         assert!(result.is_ok());
 
         let (config, config_dir) = result.unwrap();
@@ -383,7 +539,7 @@ host = "localhost"
         // Since we can't easily create the exact fallback scenarios,
         // we test the basic error recovery behavior
 
-        let result = load_with_fallback();
+        let result = load_with_fallback(None);
         assert!(result.is_ok());
     }
 }
@@ -0,0 +1,249 @@
+use super::builder::{ConfigBuildError, check};
+use super::defaults::default_auth_exempt;
+use super::schema::{AuthConfig, Config, ServiceAdapterConfig};
+use std::collections::BTreeMap;
+use std::collections::hash_map::Entry;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Prefix shared by all config environment variables
+pub const ENV_PREFIX: &str = "AI_MESSENGER_";
+
+/// Build a `Config` from `AI_MESSENGER_*` environment variables
+///
+/// Unset variables keep their defaults, so an empty environment gives
+/// `Config::default()`. Variables map to fields as follows:
+///
+/// | Variable                                     | Field                                 |
+/// |----------------------------------------------|---------------------------------------|
+/// | `AI_MESSENGER_HOST`                          | `server.host`                         |
+/// | `AI_MESSENGER_PORT`                          | `server.port`                         |
+/// | `AI_MESSENGER_BASE_PATH`                     | `server.base_path`                    |
+/// | `AI_MESSENGER_MAX_BODY_BYTES`                | `server.max_body_bytes`               |
+/// | `AI_MESSENGER_REQUEST_TIMEOUT_SECS`          | `server.request_timeout_secs`         |
+/// | `AI_MESSENGER_MESSAGE_TIMEOUT_SECS`          | `server.message_timeout_secs`         |
+/// | `AI_MESSENGER_API_KEYS` (comma-separated)    | `server.auth.keys`                    |
+/// | `AI_MESSENGER_DATA_DIR`                      | `storage.data_dir`                    |
+/// | `AI_MESSENGER_CACHE_DIR`                     | `storage.cache_dir`                   |
+/// | `AI_MESSENGER_CREATE_DIRS`                   | `storage.create_dirs`                 |
+/// | `AI_MESSENGER_MAX_TOKENS_PER_CONVERSATION`   | `limits.max_tokens_per_conversation`  |
+/// | `AI_MESSENGER_LOG_FILE`                      | `logging.log_file`                    |
+/// | `AI_MESSENGER_LOG_FILE_LEVEL`                | `logging.file_level`                  |
+/// | `AI_MESSENGER_ERROR_LOG`                     | `logging.error_log`                   |
+/// | `AI_MESSENGER_ADAPTERS_<SERVICE>_PROVIDER`   | `adapters.<service>.provider`         |
+/// | `AI_MESSENGER_ADAPTERS_<SERVICE>_VERSION`    | `adapters.<service>.version`          |
+///
+/// `<SERVICE>` is lowercased, so `AI_MESSENGER_ADAPTERS_LLM_PROVIDER`
+/// configures `adapters.llm`. A service that isn't in the defaults needs its
+/// `_PROVIDER` variable. Other `AI_MESSENGER_*` variables are ignored.
+#[allow(dead_code)] // Library API for embedders
+pub fn config_from_env() -> Result<Config, ConfigBuildError> {
+    config_from_vars(std::env::vars())
+}
+
+/// `config_from_env` reading from `vars` instead of the process environment
+pub fn config_from_vars(
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Config, ConfigBuildError> {
+    // Sorted, so the same environment always fails on the same variable
+    let vars: BTreeMap<String, String> = vars
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.strip_prefix(ENV_PREFIX)?.to_string(), value)))
+        .collect();
+    let mut config = Config::default();
+
+    for (name, value) in &vars {
+        match name.as_str() {
+            "HOST" => config.server.host = value.clone(),
+            "PORT" => config.server.port = parse(name, value)?,
+            "BASE_PATH" => config.server.base_path = value.clone(),
+            "MAX_BODY_BYTES" => config.server.max_body_bytes = parse(name, value)?,
+            "REQUEST_TIMEOUT_SECS" => config.server.request_timeout_secs = parse(name, value)?,
+            "MESSAGE_TIMEOUT_SECS" => config.server.message_timeout_secs = parse(name, value)?,
+            "API_KEYS" => {
+                let keys = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|key| !key.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                config.server.auth = Some(AuthConfig {
+                    exempt: default_auth_exempt(),
+                    keys,
+                    keys_file: None,
+                });
+            }
+            "DATA_DIR" => config.storage.data_dir = Some(PathBuf::from(value)),
+            "CACHE_DIR" => config.storage.cache_dir = Some(PathBuf::from(value)),
+            "CREATE_DIRS" => config.storage.create_dirs = Some(parse_bool(name, value)?),
+            "MAX_TOKENS_PER_CONVERSATION" => {
+                config.limits.max_tokens_per_conversation = Some(parse(name, value)?)
+            }
+            "LOG_FILE" => config.logging.log_file = Some(PathBuf::from(value)),
+            "LOG_FILE_LEVEL" => config.logging.file_level = Some(value.clone()),
+            "ERROR_LOG" => config.logging.error_log = Some(PathBuf::from(value)),
+            _ => {
+                if let Some(service) = name.strip_prefix("ADAPTERS_") {
+                    set_adapter_field(&mut config, &vars, service, value)?;
+                }
+            }
+        }
+    }
+
+    check(&config)?;
+    Ok(config)
+}
+
+/// Apply `AI_MESSENGER_ADAPTERS_<SERVICE>_<FIELD>`
+///
+/// A service that isn't configured yet needs its `_PROVIDER` variable,
+/// rather than getting a provider of another service.
+fn set_adapter_field(
+    config: &mut Config,
+    vars: &BTreeMap<String, String>,
+    service_field: &str,
+    value: &str,
+) -> Result<(), ConfigBuildError> {
+    let (service, field) = match service_field.rsplit_once('_') {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+    if !matches!(field, "PROVIDER" | "VERSION") {
+        return Ok(());
+    }
+
+    let services = &mut config.adapters.services;
+    let adapter = match services.entry(service.to_lowercase()) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let provider_var = format!("ADAPTERS_{}_PROVIDER", service);
+            let provider = vars.get(&provider_var).ok_or_else(|| {
+                ConfigBuildError::MissingEnv(format!("{ENV_PREFIX}{provider_var}"))
+            })?;
+            entry.insert(ServiceAdapterConfig::new(provider.clone()))
+        }
+    };
+    match field {
+        "PROVIDER" => adapter.provider = value.to_string(),
+        _ => adapter.version = value.to_string(),
+    }
+    Ok(())
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, ConfigBuildError> {
+    value.trim().parse().map_err(|_| invalid(name, value))
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool, ConfigBuildError> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(invalid(name, value)),
+    }
+}
+
+fn invalid(name: &str, value: &str) -> ConfigBuildError {
+    ConfigBuildError::InvalidEnv {
+        value: value.to_string(),
+        variable: format!("{}{}", ENV_PREFIX, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_environment_gives_defaults() {
+        let config = config_from_vars(vars(&[("PATH", "/usr/bin")])).unwrap();
+        let default = Config::default();
+
+        assert_eq!(config.server.host, default.server.host);
+        assert_eq!(config.server.port, default.server.port);
+        assert!(config.server.auth.is_none());
+        assert_eq!(config.storage.data_dir, None);
+        assert_eq!(
+            config.adapters.services.len(),
+            default.adapters.services.len()
+        );
+    }
+
+    #[test]
+    fn test_variables_map_to_fields() {
+        let config = config_from_vars(vars(&[
+            ("AI_MESSENGER_HOST", "0.0.0.0"),
+            ("AI_MESSENGER_PORT", "3000"),
+            ("AI_MESSENGER_BASE_PATH", "api"),
+            ("AI_MESSENGER_MESSAGE_TIMEOUT_SECS", "60"),
+            ("AI_MESSENGER_API_KEYS", "key-a, key-b,"),
+            ("AI_MESSENGER_DATA_DIR", "/srv/data"),
+            ("AI_MESSENGER_CREATE_DIRS", "false"),
+            ("AI_MESSENGER_MAX_TOKENS_PER_CONVERSATION", "10000"),
+            ("AI_MESSENGER_LOG_FILE", "/var/log/ai_messenger.log"),
+            ("AI_MESSENGER_ADAPTERS_LLM_VERSION", "1.2.0"),
+            ("AI_MESSENGER_ADAPTERS_STORAGE_PROVIDER", "sqlite"),
+            ("AI_MESSENGER_NO_CONFIG_WRITE", "1"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.port, 3000);
+        assert_eq!(config.server.base_path, "api");
+        assert_eq!(config.server.message_timeout_secs, 60);
+        assert_eq!(config.server.auth.unwrap().keys, ["key-a", "key-b"]);
+        assert_eq!(config.storage.data_dir, Some("/srv/data".into()));
+        assert_eq!(config.storage.create_dirs, Some(false));
+        assert_eq!(config.limits.max_tokens_per_conversation, Some(10000));
+        assert_eq!(
+            config.logging.log_file,
+            Some("/var/log/ai_messenger.log".into())
+        );
+
+        let llm = config.adapters.get_service("llm").unwrap();
+        assert_eq!(llm.provider, "ollama");
+        assert_eq!(llm.version, "1.2.0");
+        let storage = config.adapters.get_service("storage").unwrap();
+        assert_eq!(storage.provider, "sqlite");
+    }
+
+    #[test]
+    fn test_unconfigured_service_needs_provider() {
+        let error =
+            config_from_vars(vars(&[("AI_MESSENGER_ADAPTERS_STT_VERSION", "1.0.0")])).unwrap_err();
+        assert_eq!(
+            error,
+            ConfigBuildError::MissingEnv("AI_MESSENGER_ADAPTERS_STT_PROVIDER".to_string())
+        );
+
+        let config = config_from_vars(vars(&[
+            ("AI_MESSENGER_ADAPTERS_STT_VERSION", "1.0.0"),
+            ("AI_MESSENGER_ADAPTERS_STT_PROVIDER", "whisper"),
+        ]))
+        .unwrap();
+        let stt = config.adapters.get_service("stt").unwrap();
+        assert_eq!(stt.provider, "whisper");
+        assert_eq!(stt.version, "1.0.0");
+    }
+
+    #[test]
+    fn test_invalid_values_rejected() {
+        let error = config_from_vars(vars(&[("AI_MESSENGER_PORT", "eighty")])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "AI_MESSENGER_PORT has an invalid value: \"eighty\""
+        );
+
+        assert!(config_from_vars(vars(&[("AI_MESSENGER_CREATE_DIRS", "maybe")])).is_err());
+        assert_eq!(
+            config_from_vars(vars(&[("AI_MESSENGER_HOST", "")])).unwrap_err(),
+            ConfigBuildError::Empty("server.host".to_string())
+        );
+    }
+}
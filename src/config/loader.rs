@@ -1,18 +1,39 @@
 use anyhow::Result;
 use std::path::PathBuf;
 
-use super::{discovery, schema::Config};
+use super::{discovery, profiles::selected_profile, schema::Config};
 
 /// Load configuration from file or defaults
 /// Returns the config and the directory containing the config file (if found)
-pub fn load_config(config_file_override: Option<String>) -> Result<(Config, Option<PathBuf>)> {
+///
+/// `profile` is the one passed with --profile, falling back to
+/// `AI_MESSENGER_PROFILE`; see `profiles::apply_profile`.
+pub fn load_config(
+    config_file_override: Option<String>,
+    profile: Option<&str>,
+) -> Result<(Config, Option<PathBuf>)> {
+    let profile = selected_profile(profile);
     if let Some(config_path) = config_file_override {
         // --config flag was provided - file MUST exist
-        let (config, config_dir) = discovery::load_from_file(&config_path)?;
+        let (config, config_dir) = discovery::load_from_file(&config_path, profile.as_deref())?;
         Ok((config, Some(config_dir)))
     } else {
         // Try fallback chain
-        discovery::load_with_fallback()
+        discovery::load_with_fallback(profile.as_deref())
+    }
+}
+
+/// Load configuration from file, creating a default config file if none is found
+/// Returns the config and the directory containing the config file
+///
+/// An explicit `config_file_override` must exist; it's never created.
+pub fn load_or_init_config(
+    config_file_override: Option<String>,
+    profile: Option<&str>,
+) -> Result<(Config, Option<PathBuf>)> {
+    match config_file_override {
+        Some(_) => load_config(config_file_override, profile),
+        None => discovery::load_with_fallback_or_create(selected_profile(profile).as_deref()),
     }
 }
 
@@ -20,14 +41,27 @@ pub fn load_config(config_file_override: Option<String>) -> Result<(Config, Opti
 /// Returns the config and the directory containing the config file (if found)
 pub fn load_config_silent(
     config_file_override: Option<String>,
+    profile: Option<&str>,
 ) -> Result<(Config, Option<PathBuf>)> {
+    let profile = selected_profile(profile);
     if let Some(config_path) = config_file_override {
         // --config flag was provided - file MUST exist
-        let (config, config_dir) = discovery::load_from_file(&config_path)?;
+        let (config, config_dir) = discovery::load_from_file(&config_path, profile.as_deref())?;
         Ok((config, Some(config_dir)))
     } else {
         // Try fallback chain (silent)
-        discovery::load_with_fallback_silent()
+        discovery::load_with_fallback_silent(profile.as_deref())
+    }
+}
+
+/// Path of the config file `load_config` reads (None when using defaults)
+pub fn config_file_path(config_file_override: Option<&str>) -> Option<PathBuf> {
+    match config_file_override {
+        Some(config_path) => Some(PathBuf::from(config_path)),
+        None => discovery::list_config_locations()
+            .into_iter()
+            .find(|(_, _, exists)| *exists)
+            .map(|(_, path, _)| path),
     }
 }
 
@@ -53,8 +87,9 @@ data_dir = "/override/data"
 
         fs::write(&config_path, config_content).unwrap();
 
-        let (config, config_dir) = load_config(Some(config_path.to_string_lossy().to_string()))
-            .expect("Should load config from override path");
+        let (config, config_dir) =
+            load_config(Some(config_path.to_string_lossy().to_string()), None)
+                .expect("Should load config from override path");
 
         assert_eq!(config.server.host, "0.0.0.0");
         assert_eq!(config.server.port, 4000);
@@ -66,7 +101,7 @@ data_dir = "/override/data"
     fn test_load_config_override_not_found() {
         let non_existent = "/this/does/not/exist.toml";
 
-        let result = load_config(Some(non_existent.to_string()));
+        let result = load_config(Some(non_existent.to_string()), None);
 
         assert!(result.is_err());
     }
@@ -74,7 +109,7 @@ data_dir = "/override/data"
     #[test]
     fn test_load_config_fallback() {
         // No override, should use fallback chain (which returns defaults when no files exist)
-        let (config, _config_dir) = load_config(None).expect("Should return default config");
+        let (config, _config_dir) = load_config(None, None).expect("Should return default config");
 
         assert_eq!(config.server.host, "127.0.0.1");
         assert_eq!(config.server.port, 8080);
@@ -93,7 +128,7 @@ port = 5000
         fs::write(&config_path, config_content).unwrap();
 
         let (config, config_dir) =
-            load_config_silent(Some(config_path.to_string_lossy().to_string()))
+            load_config_silent(Some(config_path.to_string_lossy().to_string()), None)
                 .expect("Should load config silently");
 
         assert_eq!(config.server.port, 5000);
@@ -104,9 +139,17 @@ port = 5000
     fn test_load_config_silent_fallback() {
         // No override, should use silent fallback (defaults)
         let (config, _config_dir) =
-            load_config_silent(None).expect("Should return default config silently");
+            load_config_silent(None, None).expect("Should return default config silently");
 
         assert_eq!(config.server.host, "127.0.0.1");
         assert_eq!(config.server.port, 8080);
     }
+
+    #[test]
+    fn test_config_file_path_with_override() {
+        assert_eq!(
+            config_file_path(Some("/custom/config.toml")),
+            Some(PathBuf::from("/custom/config.toml"))
+        );
+    }
 }
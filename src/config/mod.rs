@@ -1,13 +1,17 @@
+pub mod builder;
 pub mod creation;
 pub mod defaults;
 pub mod discovery;
+pub mod env;
 pub mod future_example;
 pub mod loader;
 pub mod path_expansion;
 pub mod paths;
+pub mod profiles;
 pub mod schema;
+pub mod secrets;
 
 // Re-exports for convenience
-pub use loader::{load_config, load_config_silent};
+pub use loader::{config_file_path, load_config, load_config_silent, load_or_init_config};
 pub use paths::{cache_dir, data_dir, expand_optional_path, expand_required_path};
 pub use schema::Config;
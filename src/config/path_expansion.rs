@@ -1,53 +1,49 @@
 use std::env;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+
+/// Placeholders replaced with the home directory anywhere in a path
+///
+/// `$HOME` is handled separately, since it only matches as a whole token.
+const HOME_PLACEHOLDERS: &[&str] = &["${HOME}", "%USERPROFILE%"];
 
 /// Expand path with support for home directory and config-relative paths
 ///
 /// Supports:
 /// - `~` at the beginning of the path (replaced with home directory)
-/// - `$HOME` anywhere in the path (replaced with home directory)
+/// - `$HOME`, `${HOME}` and `%USERPROFILE%` anywhere in the path (replaced
+///   with home directory)
 /// - `./` and `../` at the beginning (relative to config directory)
 /// - Other relative paths without leading slash (relative to config directory)
 ///
+/// Windows absolute paths (`C:\...`, `C:/...` or UNC `\\server\share`) are
+/// never joined onto the config directory, on any platform.
+///
 /// If config_dir is None, only home expansion is performed.
 pub fn expand_path<P: AsRef<Path>>(path: P, config_dir: Option<&Path>) -> PathBuf {
     let path = path.as_ref();
     let path_str = path.to_string_lossy();
 
     // First priority: Home directory expansion
-    if path_str.starts_with("~") || path_str.contains("$HOME") {
+    if path_str.starts_with("~") || has_home_variable(&path_str) {
         return expand_home(path);
     }
 
     // Second priority: Config-relative paths (if config_dir is available)
     if let Some(config_dir) = config_dir {
         // Check if it's a relative path (not absolute)
-        if !path.is_absolute() {
+        if !path.is_absolute() && !is_windows_absolute(&path_str) {
             // Relative paths: ./foo, ../foo, foo/bar (but not ~/foo which was handled above)
             let joined = config_dir.join(path);
 
-            // Try to canonicalize (works only if path exists), otherwise clean manually
-            if let Ok(canonical) = joined.canonicalize() {
-                return canonical;
+            // Try to canonicalize (works only if path exists), otherwise clean manually.
+            // Windows canonical paths are verbatim (\\?\C:\...), which many
+            // programs don't accept, so they're always cleaned manually.
+            let canonical = if cfg!(windows) {
+                None
             } else {
-                // If canonicalize fails (path doesn't exist), clean the path manually
-                // This resolves .. and . components without requiring the path to exist
-                let mut components = Vec::new();
-                for component in joined.components() {
-                    match component {
-                        std::path::Component::ParentDir => {
-                            components.pop();
-                        }
-                        std::path::Component::CurDir => {
-                            // Skip current directory components
-                        }
-                        _ => {
-                            components.push(component);
-                        }
-                    }
-                }
-                return components.iter().collect();
-            }
+                joined.canonicalize().ok()
+            };
+            return canonical.unwrap_or_else(|| normalize(&joined));
         }
     }
 
@@ -55,11 +51,57 @@ pub fn expand_path<P: AsRef<Path>>(path: P, config_dir: Option<&Path>) -> PathBu
     path.to_path_buf()
 }
 
+/// Resolve `.` and `..` components without requiring the path to exist
+///
+/// Works on `std::path` components, so separators are whatever the
+/// platform accepts. `..` never goes above the root (or drive); leading
+/// `..` of a relative path are kept.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::Prefix(_) | Component::RootDir) => {}
+                _ => normalized.push(Component::ParentDir),
+            },
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Whether `path` is an absolute Windows path: `C:\`, `C:/` or UNC `\\server`
+///
+/// `Path::is_absolute` only knows these on Windows, but configs are
+/// shared between platforms.
+fn is_windows_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    let drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+
+    drive || path.starts_with(r"\\")
+}
+
+/// Whether `path` contains a home directory variable
+fn has_home_variable(path: &str) -> bool {
+    path.contains("$HOME")
+        || HOME_PLACEHOLDERS
+            .iter()
+            .any(|placeholder| path.contains(placeholder))
+}
+
 /// Expand home directory placeholders in a path
 ///
 /// Supports:
 /// - `~` at the beginning of the path (replaced with home directory)
-/// - `$HOME` anywhere in the path (replaced with home directory)
+/// - `$HOME`, `${HOME}` and `%USERPROFILE%` anywhere in the path (replaced
+///   with home directory)
 ///
 /// If the home directory cannot be determined, the path is returned unchanged.
 pub fn expand_home<P: AsRef<Path>>(path: P) -> PathBuf {
@@ -81,10 +123,14 @@ pub fn expand_home<P: AsRef<Path>>(path: P) -> PathBuf {
     let expanded = if path_str.starts_with("~") {
         // Replace ~ at the beginning with home directory
         path_str.replacen("~", &home_dir, 1)
-    } else if path_str.contains("$HOME") {
+    } else if has_home_variable(&path_str) {
         // Replace $HOME anywhere in the path, but only whole tokens
         // We need to be careful not to replace partial matches like $HOMECOMING
-        replace_home_variable(&path_str, &home_dir)
+        let mut expanded = replace_home_variable(&path_str, &home_dir);
+        for placeholder in HOME_PLACEHOLDERS {
+            expanded = expanded.replace(placeholder, &home_dir);
+        }
+        expanded
     } else {
         // No expansion needed
         return path.to_path_buf();
@@ -323,7 +369,7 @@ mod tests {
         let expanded = expand_home(path);
 
         assert!(!expanded.to_string_lossy().starts_with("~"));
-        // Should preserve the backslashes as literal characters on Unix
+        // Backslashes are literal characters on Unix and separators on Windows
         assert!(expanded.to_string_lossy().contains("Documents"));
     }
 
@@ -432,6 +478,107 @@ mod tests {
         assert_eq!(result, std::path::PathBuf::from("/absolute/path"));
     }
 
+    /// Absolute path from `parts` below the platform's root
+    fn absolute(parts: &[&str]) -> PathBuf {
+        let root = if cfg!(windows) { r"C:\" } else { "/" };
+        let mut path = PathBuf::from(root);
+        path.extend(parts);
+        path
+    }
+
+    #[test]
+    fn test_expand_path_parent_dir_stays_below_root() {
+        let config_dir = absolute(&["config", "dir"]);
+
+        let result = expand_path("../../../../data", Some(&config_dir));
+        assert_eq!(result, absolute(&["data"]));
+
+        let result = expand_path("./a/./b/../c", Some(&config_dir));
+        assert_eq!(result, absolute(&["config", "dir", "a", "c"]));
+    }
+
+    #[test]
+    fn test_normalize_relative_path() {
+        let path: PathBuf = ["..", "a", ".", "b", "..", "c"].iter().collect();
+
+        // Leading ".." of a relative path have nothing to cancel out
+        let expected: PathBuf = ["..", "a", "c"].iter().collect();
+        assert_eq!(normalize(&path), expected);
+    }
+
+    #[test]
+    fn test_expand_path_windows_absolute_not_joined() {
+        let config_dir = absolute(&["config", "dir"]);
+
+        for path in [r"C:\data\app", "C:/data/app", r"\\server\share\app"] {
+            assert_eq!(
+                expand_path(path, Some(&config_dir)),
+                PathBuf::from(path),
+                "{}",
+                path
+            );
+        }
+
+        // A drive-relative path isn't absolute
+        assert!(!is_windows_absolute("C:data"));
+    }
+
+    #[test]
+    fn test_expand_home_braced_and_userprofile() {
+        let Some(home) = env::var("HOME")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(dirs::home_dir)
+        else {
+            return;
+        };
+
+        assert_eq!(
+            expand_home("${HOME}/app"),
+            PathBuf::from(format!("{}/app", home.display()))
+        );
+        assert_eq!(
+            expand_path("%USERPROFILE%/app", Some(&absolute(&["config"]))),
+            PathBuf::from(format!("{}/app", home.display()))
+        );
+        // Lowercase or unterminated forms aren't variables
+        assert_eq!(
+            expand_home("%userprofile%/app"),
+            PathBuf::from("%userprofile%/app")
+        );
+        assert_eq!(
+            expand_home("%USERPROFILE/app"),
+            PathBuf::from("%USERPROFILE/app")
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_expand_path_windows_backslashes() {
+        let config_dir = PathBuf::from(r"C:\config\dir");
+
+        let result = expand_path(r"..\data\app.db", Some(&config_dir));
+        assert_eq!(result, PathBuf::from(r"C:\config\data\app.db"));
+
+        let result = expand_path(r".\logs/server.log", Some(&config_dir));
+        assert_eq!(result, PathBuf::from(r"C:\config\dir\logs\server.log"));
+
+        let result = expand_path(r"D:\data", Some(&config_dir));
+        assert_eq!(result, PathBuf::from(r"D:\data"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_expand_home_windows() {
+        let home = expand_home("~");
+
+        let result = expand_home(r"%USERPROFILE%\AppData\app");
+        assert_eq!(result, home.join("AppData").join("app"));
+
+        let result = expand_home(r"~\Documents");
+        assert_eq!(result, home.join("Documents"));
+    }
+
     #[test]
     fn test_expand_path_no_config_dir() {
         // Without config_dir, only home expansion should work
@@ -61,7 +61,6 @@ where
 ///
 /// let expanded_path = expand_required_path(&required_path, config_dir.as_deref());
 /// ```
-#[allow(dead_code)]
 pub fn expand_required_path<P: AsRef<std::path::Path>>(
     path: P,
     config_dir: Option<&std::path::Path>,
@@ -81,6 +80,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: Some("/custom/data".into()),
                 cache_dir: None,
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -105,6 +106,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: None,
                 cache_dir: Some("/custom/cache".into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -129,6 +132,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: Some("/custom/data".into()),
                 cache_dir: Some("/custom/cache".into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -147,6 +152,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: Some("~/custom/data".into()),
                 cache_dir: None,
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -165,6 +172,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: None,
                 cache_dir: Some("$HOME/.cache/ai_messenger".into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -187,6 +196,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: Some("/absolute/path/data".into()),
                 cache_dir: None,
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -203,6 +214,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: Some("~/data".into()),
                 cache_dir: Some("$HOME/cache".into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -226,6 +239,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: Some("./relative/data".into()),
                 cache_dir: Some("relative/cache".into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -244,6 +259,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: Some("$HOME/.local/share/app/data".into()),
                 cache_dir: Some("~/Library/Caches/app".into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -265,6 +282,8 @@ mod tests {
             storage: schema::StorageConfig {
                 data_dir: Some("~/Documents/测试应用/数据".into()),
                 cache_dir: Some("$HOME/Cache/äöü-app".into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -353,6 +372,8 @@ mod tests {
             storage: StorageConfig {
                 data_dir: Some("./relative/to/config".into()),
                 cache_dir: Some("../another/relative".into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -400,6 +421,8 @@ mod tests {
             storage: StorageConfig {
                 data_dir: Some(long_path.clone().into()),
                 cache_dir: Some(long_path.into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
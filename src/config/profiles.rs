@@ -0,0 +1,190 @@
+use anyhow::Result;
+use toml::Table;
+
+/// Environment variable selecting a profile when `--profile` isn't passed
+pub const PROFILE_ENV: &str = "AI_MESSENGER_PROFILE";
+
+/// Table of a config file holding the profiles, by name
+const PROFILES_TABLE: &str = "profiles";
+
+/// Profile to apply: `cli` if given, otherwise `AI_MESSENGER_PROFILE` if set
+pub fn selected_profile(cli: Option<&str>) -> Option<String> {
+    match cli {
+        Some(profile) => Some(profile.to_string()),
+        None => std::env::var(PROFILE_ENV)
+            .ok()
+            .filter(|profile| !profile.trim().is_empty()),
+    }
+}
+
+/// Take the `[profiles]` out of a config file's `table`, applying `profile`
+///
+/// The tables of `[profiles.<profile>]` are merged into the base ones at
+/// any depth: values set in the profile replace the base values, tables
+/// and keys missing from the base are added. Arrays are replaced as a
+/// whole. Naming a profile the file doesn't define is an error.
+pub fn apply_profile(table: &mut Table, profile: Option<&str>) -> Result<()> {
+    let profiles = match table.remove(PROFILES_TABLE) {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => anyhow::bail!("[{}] must be a table of profiles", PROFILES_TABLE),
+        None => Table::new(),
+    };
+    let Some(profile) = profile else {
+        return Ok(());
+    };
+
+    match profiles.get(profile) {
+        Some(toml::Value::Table(overrides)) => {
+            merge_tables(table, overrides.clone());
+            Ok(())
+        }
+        Some(_) => anyhow::bail!("Config profile '{}' must be a table", profile),
+        None if profiles.is_empty() => {
+            anyhow::bail!(
+                "Unknown config profile '{}': the config file defines no profiles",
+                profile
+            )
+        }
+        None => {
+            let mut available: Vec<_> = profiles.keys().map(String::as_str).collect();
+            available.sort_unstable();
+            anyhow::bail!(
+                "Unknown config profile '{}' (available: {})",
+                profile,
+                available.join(", ")
+            )
+        }
+    }
+}
+
+/// Merge `overrides` into `base`, recursing into tables present in both
+fn merge_tables(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(override_table)) => {
+                merge_tables(base_table, override_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+[server]
+host = "127.0.0.1"
+port = 8080
+
+[storage]
+data_dir = "./data"
+
+[adapters.llm]
+provider = "ollama"
+
+[adapters.llm.config]
+base_url = "http://localhost:11434"
+model = "llama3.2"
+
+[profiles.prod.server]
+host = "0.0.0.0"
+
+[profiles.prod.storage]
+data_dir = "/var/lib/ai_messenger"
+
+[profiles.prod.adapters.llm.config]
+base_url = "http://gpu-1:11434"
+
+[profiles.prod.adapters.storage]
+provider = "sqlite"
+
+[profiles.dev.server]
+port = 3000
+"#;
+
+    fn table() -> Table {
+        toml::from_str(CONFIG).unwrap()
+    }
+
+    #[test]
+    fn test_scalar_override() {
+        let mut table = table();
+        apply_profile(&mut table, Some("prod")).unwrap();
+
+        assert_eq!(table["server"]["host"].as_str(), Some("0.0.0.0"));
+        // Keys the profile doesn't set keep their base values
+        assert_eq!(table["server"]["port"].as_integer(), Some(8080));
+        assert_eq!(
+            table["storage"]["data_dir"].as_str(),
+            Some("/var/lib/ai_messenger")
+        );
+        assert_eq!(
+            table["adapters"]["llm"]["config"]["base_url"].as_str(),
+            Some("http://gpu-1:11434")
+        );
+        assert_eq!(
+            table["adapters"]["llm"]["config"]["model"].as_str(),
+            Some("llama3.2")
+        );
+        assert!(table.get("profiles").is_none());
+    }
+
+    #[test]
+    fn test_table_addition() {
+        let mut table = table();
+        apply_profile(&mut table, Some("prod")).unwrap();
+
+        assert_eq!(
+            table["adapters"]["storage"]["provider"].as_str(),
+            Some("sqlite")
+        );
+        assert_eq!(
+            table["adapters"]["llm"]["provider"].as_str(),
+            Some("ollama")
+        );
+    }
+
+    #[test]
+    fn test_arrays_are_replaced() {
+        let mut base: Table = toml::from_str("[server.auth]\nkeys = [\"a\", \"b\"]\n").unwrap();
+        let overrides: Table = toml::from_str("[server.auth]\nkeys = [\"c\"]\n").unwrap();
+        merge_tables(&mut base, overrides);
+
+        let keys = base["server"]["auth"]["keys"].as_array().unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].as_str(), Some("c"));
+    }
+
+    #[test]
+    fn test_without_profile() {
+        let mut table = table();
+        apply_profile(&mut table, None).unwrap();
+
+        // The profiles are dropped, the base is unchanged
+        assert!(table.get("profiles").is_none());
+        assert_eq!(table["server"]["host"].as_str(), Some("127.0.0.1"));
+    }
+
+    #[test]
+    fn test_unknown_profile() {
+        let error = apply_profile(&mut table(), Some("staging")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown config profile 'staging' (available: dev, prod)"
+        );
+
+        let mut no_profiles: Table = toml::from_str("[server]\nport = 9000\n").unwrap();
+        let error = apply_profile(&mut no_profiles, Some("prod")).unwrap_err();
+        assert!(error.to_string().contains("defines no profiles"));
+    }
+
+    #[test]
+    fn test_selected_profile() {
+        // The command line wins over the environment
+        assert_eq!(selected_profile(Some("dev")).as_deref(), Some("dev"));
+    }
+}
@@ -1,68 +1,868 @@
-use serde::{Deserialize, Serialize};
+use super::secrets::{SecretError, resolve_secrets};
+use crate::utils::log_file::{LogFile, LogRotation};
+use schemars::JsonSchema;
+use schemars::r#gen::SchemaGenerator;
+use schemars::schema::{ArrayValidation, InstanceType, Schema, SchemaObject, SubschemaValidation};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use toml::Table;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 pub struct Config {
     #[serde(default)]
     pub adapters: AdapterConfig,
+    /// Stored conversations
+    #[serde(default)]
+    pub conversations: ConversationsConfig,
+    /// Connection pooling of the HTTP client adapters share
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// Policy checks on messages and replies (off by default)
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    /// Profile of the config file applied when loading, see `config::profiles`
+    #[serde(skip)]
+    pub profile: Option<String>,
     #[serde(default)]
     pub server: ServerConfig,
     #[serde(default)]
     pub storage: StorageConfig,
+    #[serde(default)]
+    pub usage_log: UsageLogConfig,
+}
+
+impl Config {
+    /// Start building a config in code, see `ConfigBuilder`
+    #[allow(dead_code)] // Library API for embedders
+    pub fn builder() -> super::builder::ConfigBuilder {
+        super::builder::ConfigBuilder::new()
+    }
+
+    /// JSON Schema of the config file format, for editors and validation
+    ///
+    /// Generated from these types, so field docs become descriptions and
+    /// serde defaults become defaults.
+    pub fn json_schema() -> serde_json::Value {
+        let schema = schemars::schema_for!(Config);
+        serde_json::to_value(schema).expect("JSON schemas serialize to JSON")
+    }
+
+    /// Build a config from `AI_MESSENGER_*` environment variables
+    ///
+    /// Starts from the defaults, like an empty config file. See
+    /// `config::env::config_from_env` for the variables and their fields.
+    #[allow(dead_code)] // Library API for embedders
+    pub fn from_env() -> Result<Config, super::builder::ConfigBuildError> {
+        super::env::config_from_env()
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ServerConfig {
+    /// Request/response logging (off by default)
+    #[serde(default)]
+    pub access_log: AccessLogConfig,
+    /// API versions mounted under the base path, e.g. `["v1"]`
+    #[serde(default = "crate::config::defaults::default_api_versions")]
+    #[schemars(schema_with = "api_versions_schema")]
+    pub api_versions: Vec<ApiVersionConfig>,
+    /// API key authentication (the API is open when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthConfig>,
     #[serde(default = "crate::config::defaults::default_base_path")]
     pub base_path: String,
+    /// Diagnostics for adapter development (all off by default)
+    #[serde(default)]
+    pub debug: DebugConfig,
+    /// Title new conversations with the LLM in the background (off by default)
+    #[serde(default)]
+    pub generate_titles: bool,
     #[serde(default = "crate::config::defaults::default_host")]
     pub host: String,
+    /// Replaying retried message requests (`Idempotency-Key` header)
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    /// Background jobs, persisted in the storage adapter
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    /// Largest request body accepted, in bytes (larger ones get a 413)
+    #[serde(default = "crate::config::defaults::default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Seconds /v1/message may take to start its response
+    #[serde(default = "crate::config::defaults::default_message_timeout_secs")]
+    pub message_timeout_secs: u64,
     #[serde(default = "crate::config::defaults::default_port")]
     pub port: u16,
+    /// Seconds other routes may take to start their response
+    #[serde(default = "crate::config::defaults::default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Chunks buffered for a streaming client before generation waits on it
+    #[serde(default = "crate::config::defaults::default_stream_buffer")]
+    pub stream_buffer: usize,
+    /// POST each completed message to a URL (off when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhooks: Option<WebhooksConfig>,
+}
+
+/// How much of each request the access log records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogMode {
+    /// No access log
+    #[default]
+    Off,
+    /// Method, path, status and latency at info level
+    Basic,
+    /// Also request and response headers at debug level
+    Verbose,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AccessLogConfig {
+    /// Also log JSON request and response bodies at debug level
+    #[serde(default)]
+    pub bodies: bool,
+    #[serde(default)]
+    pub mode: AccessLogMode,
+    /// Header and JSON field names whose values are never logged
+    #[serde(default = "crate::config::defaults::default_access_log_redact")]
+    pub redact: Vec<String>,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        AccessLogConfig {
+            bodies: false,
+            mode: AccessLogMode::default(),
+            redact: crate::config::defaults::default_access_log_redact(),
+        }
+    }
+}
+
+/// Diagnostics for developing adapters, not meant for production
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DebugConfig {
+    /// Report the WASM fuel an LLM call used in an `X-Adapter-Fuel-Consumed` header
+    #[serde(default)]
+    pub expose_fuel: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct IdempotencyConfig {
+    /// Responses kept for replaying (0 disables idempotency keys)
+    #[serde(default = "crate::config::defaults::default_idempotency_max_entries")]
+    pub max_entries: usize,
+    /// Seconds a response can be replayed for
+    #[serde(default = "crate::config::defaults::default_idempotency_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        IdempotencyConfig {
+            max_entries: crate::config::defaults::default_idempotency_max_entries(),
+            ttl_secs: crate::config::defaults::default_idempotency_ttl_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct JobsConfig {
+    /// Jobs run at the same time
+    #[serde(default = "crate::config::defaults::default_jobs_concurrency")]
+    pub concurrency: usize,
+    /// Run background jobs (they need a storage adapter)
+    #[serde(default = "crate::config::defaults::default_jobs_enabled")]
+    pub enabled: bool,
+    /// Runs of a failing job before it's dropped
+    #[serde(default = "crate::config::defaults::default_jobs_max_attempts")]
+    pub max_attempts: u32,
+    /// Seconds between checks for due jobs
+    #[serde(default = "crate::config::defaults::default_jobs_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// Seconds before the first retry; each further retry waits twice as long
+    #[serde(default = "crate::config::defaults::default_jobs_retry_base_secs")]
+    pub retry_base_secs: u64,
+    /// Longest wait between retries, in seconds
+    #[serde(default = "crate::config::defaults::default_jobs_retry_max_secs")]
+    pub retry_max_secs: u64,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        JobsConfig {
+            concurrency: crate::config::defaults::default_jobs_concurrency(),
+            enabled: crate::config::defaults::default_jobs_enabled(),
+            max_attempts: crate::config::defaults::default_jobs_max_attempts(),
+            poll_interval_secs: crate::config::defaults::default_jobs_poll_interval_secs(),
+            retry_base_secs: crate::config::defaults::default_jobs_retry_base_secs(),
+            retry_max_secs: crate::config::defaults::default_jobs_retry_max_secs(),
+        }
+    }
+}
+
+/// API version to mount, with its lifecycle
+///
+/// Written as the version alone (`"v1"`) or as a table to deprecate it.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ApiVersionConfig {
+    /// Add `Deprecation` headers to its responses
+    #[serde(default)]
+    pub deprecated: bool,
+    /// Date it goes away, as YYYY-MM-DD, announced in `Sunset` headers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunset: Option<String>,
+    /// Name of the version, which is also its path segment, e.g. "v1"
+    pub version: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+impl ApiVersionConfig {
+    /// `version`, neither deprecated nor sunset
+    pub fn stable(version: &str) -> Self {
+        ApiVersionConfig {
+            deprecated: false,
+            sunset: None,
+            version: version.to_string(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ApiVersionConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Entry {
+            Version(String),
+            Table {
+                #[serde(default)]
+                deprecated: bool,
+                sunset: Option<String>,
+                version: String,
+            },
+        }
+
+        Ok(match Entry::deserialize(deserializer)? {
+            Entry::Version(version) => ApiVersionConfig::stable(&version),
+            Entry::Table {
+                deprecated,
+                sunset,
+                version,
+            } => ApiVersionConfig {
+                deprecated,
+                sunset,
+                version,
+            },
+        })
+    }
+}
+
+/// Where completed messages are reported, delivered as background jobs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WebhooksConfig {
+    /// Key signing each delivery with HMAC-SHA256
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// URL receiving a POST for each completed message
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 pub struct StorageConfig {
     /// Optional override for data directory
     pub data_dir: Option<PathBuf>,
     /// Optional override for cache directory
     pub cache_dir: Option<PathBuf>,
+    /// Create missing data and cache directories on server startup
+    ///
+    /// When unset, `serve` creates them and library embedders are expected
+    /// to manage their own directories.
+    pub create_dirs: Option<bool>,
+    /// Encrypt stored values with the `[adapters.crypto]` adapter
+    #[serde(default)]
+    pub encrypt: bool,
+}
+
+/// Connection pooling and keep-alive of the shared adapter HTTP client
+///
+/// Read once, when the client is first built; changes need a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HttpConfig {
+    /// How long an idle connection stays pooled for reuse
+    #[serde(
+        alias = "pool_idle_timeout",
+        default = "crate::config::defaults::default_http_pool_idle_timeout_secs"
+    )]
+    pub pool_idle_timeout_secs: u64,
+    /// Idle connections kept per provider host (0 disables pooling)
+    #[serde(default = "crate::config::defaults::default_http_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+    /// Interval of TCP keep-alive probes on open connections
+    #[serde(
+        alias = "tcp_keepalive",
+        default = "crate::config::defaults::default_http_tcp_keepalive_secs"
+    )]
+    pub tcp_keepalive_secs: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            pool_idle_timeout_secs: crate::config::defaults::default_http_pool_idle_timeout_secs(),
+            pool_max_idle_per_host: crate::config::defaults::default_http_pool_max_idle_per_host(),
+            tcp_keepalive_secs: crate::config::defaults::default_http_tcp_keepalive_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LimitsConfig {
+    /// Largest audio file accepted by `/v1/transcriptions`
+    #[serde(default = "crate::config::defaults::default_max_audio_bytes")]
+    pub max_audio_bytes: usize,
+    /// Largest decoded image accepted in message content
+    #[serde(default = "crate::config::defaults::default_max_image_bytes")]
+    pub max_image_bytes: usize,
+    /// Conversations one search reads at most before reporting `truncated`
+    #[serde(default = "crate::config::defaults::default_max_search_scanned")]
+    pub max_search_scanned: usize,
+    /// Tokens a conversation may use before new messages are rejected
+    /// (unlimited when unset; conversations may set their own budget)
+    pub max_tokens_per_conversation: Option<u64>,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            max_audio_bytes: crate::config::defaults::default_max_audio_bytes(),
+            max_image_bytes: crate::config::defaults::default_max_image_bytes(),
+            max_search_scanned: crate::config::defaults::default_max_search_scanned(),
+            max_tokens_per_conversation: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LoggingConfig {
+    /// File receiving only errors (supports ~ and $HOME, relative to the config file)
+    pub error_log: Option<PathBuf>,
+    /// Level or filter directive for `log_file` (default: the console level)
+    pub file_level: Option<String>,
+    /// File receiving all logs at `file_level` (file logging is off when unset)
+    pub log_file: Option<PathBuf>,
+    /// Rotated files kept besides the current one
+    #[serde(default = "crate::config::defaults::default_log_max_files")]
+    pub max_files: usize,
+    /// Size limit per file when `rotation = "size"`
+    #[serde(default = "crate::config::defaults::default_log_max_size_mb")]
+    pub max_size_mb: u64,
+    /// When to start a new file: daily, hourly, size or never
+    #[serde(default)]
+    pub rotation: LogRotation,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            error_log: None,
+            file_level: None,
+            log_file: None,
+            max_files: crate::config::defaults::default_log_max_files(),
+            max_size_mb: crate::config::defaults::default_log_max_size_mb(),
+            rotation: LogRotation::default(),
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// Log files to write, with paths expanded against `config_dir`
+    ///
+    /// `console_level` is used for `log_file` when `file_level` is unset.
+    pub fn log_files(&self, config_dir: Option<&Path>, console_level: &str) -> Vec<LogFile> {
+        let log_file = |path: &PathBuf, level: &str| LogFile {
+            level: level.to_string(),
+            max_files: self.max_files,
+            max_size_bytes: self.max_size_mb.saturating_mul(1024 * 1024),
+            path: crate::config::expand_required_path(path, config_dir),
+            rotation: self.rotation,
+        };
+
+        let file_level = self.file_level.as_deref().unwrap_or(console_level);
+        self.log_file
+            .iter()
+            .map(|path| log_file(path, file_level))
+            .chain(self.error_log.iter().map(|path| log_file(path, "error")))
+            .collect()
+    }
+}
+
+/// Stored conversations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ConversationsConfig {
+    /// Send the stored history with new messages, condensed as configured
+    /// (off by default: only the messages of the request are sent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<ConversationMemoryConfig>,
+}
+
+/// How much of a stored conversation is sent to the LLM
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ConversationMemoryConfig {
+    #[serde(default)]
+    pub strategy: MemoryStrategy,
+    /// Estimated tokens of unsummarized messages at which a summary is
+    /// written (`summary` only)
+    #[serde(default = "crate::config::defaults::default_memory_summary_trigger_tokens")]
+    pub summary_trigger_tokens: u64,
+    /// LLM provider writing summaries, `[adapters.llm]` or one of its
+    /// fallbacks (default: the adapter answering messages)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary_provider: Option<String>,
+    /// Most recent stored messages sent with each message; with `summary`,
+    /// the messages kept out of the summary
+    #[serde(default = "crate::config::defaults::default_memory_window_messages")]
+    pub window_messages: usize,
+}
+
+impl Default for ConversationMemoryConfig {
+    fn default() -> Self {
+        ConversationMemoryConfig {
+            strategy: MemoryStrategy::default(),
+            summary_trigger_tokens: crate::config::defaults::default_memory_summary_trigger_tokens(
+            ),
+            summary_provider: None,
+            window_messages: crate::config::defaults::default_memory_window_messages(),
+        }
+    }
+}
+
+/// What happens to stored messages that don't fit the window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryStrategy {
+    /// They're left out
+    #[default]
+    Window,
+    /// The LLM summarizes them in the background, and the summary is sent
+    /// in their place
+    Summary,
+}
+
+/// Policy checks on what is sent to the LLM and on what it replies
+///
+/// Without rules and without `[moderation.adapter]`, nothing is checked.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ModerationConfig {
+    /// Also have the LLM adapter classify content (its manifest must declare `moderation`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<ModerationAdapterConfig>,
+    /// Blocklist rules, checked in order
+    #[serde(default)]
+    pub rules: Vec<ModerationRule>,
+}
+
+/// How content flagged by the LLM adapter is handled
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ModerationAdapterConfig {
+    #[serde(default)]
+    pub action: ModerationAction,
+    #[serde(default)]
+    pub direction: ModerationDirection,
+}
+
+/// Blocklist rule matched against message and reply text
+///
+/// A rule has `keywords`, a `pattern` or both; it matches if any of them does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ModerationRule {
+    #[serde(default)]
+    pub action: ModerationAction,
+    #[serde(default)]
+    pub direction: ModerationDirection,
+    /// Name of the rule in logs, metrics and responses
+    pub id: String,
+    /// Words matched as whole words, ignoring case
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Regular expression (Rust `regex` syntax)
+    pub pattern: Option<String>,
+}
+
+/// What happens to content matching a moderation rule
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationAction {
+    /// Reject the request with a 422; blocked messages never reach the LLM
+    #[default]
+    Block,
+    /// Let it through, reporting the match in the response and conversation
+    Flag,
+    /// Replace the matching text with `[REDACTED]`
+    Redact,
+}
+
+impl ModerationAction {
+    /// Name used in the config, e.g. "block"
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModerationAction::Block => "block",
+            ModerationAction::Flag => "flag",
+            ModerationAction::Redact => "redact",
+        }
+    }
+}
+
+/// Which content a moderation rule applies to
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationDirection {
+    /// User messages and LLM replies
+    #[default]
+    Both,
+    /// User messages, before they're sent to the LLM
+    Input,
+    /// LLM replies, before they're returned or stored
+    Output,
+}
+
+impl ModerationDirection {
+    /// Name used in the config, e.g. "input"
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModerationDirection::Both => "both",
+            ModerationDirection::Input => "input",
+            ModerationDirection::Output => "output",
+        }
+    }
+
+    /// Whether rules for `self` apply to content going in `direction`
+    pub fn covers(self, direction: ModerationDirection) -> bool {
+        self == ModerationDirection::Both || self == direction
+    }
+}
+
+/// Ledger of LLM calls, one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UsageLogConfig {
+    /// Write the ledger (off by default)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Also record calls that failed
+    #[serde(default = "crate::config::defaults::default_usage_log_include_failed")]
+    pub include_failed: bool,
+    /// Rotated files kept besides the current one
+    #[serde(default = "crate::config::defaults::default_usage_log_max_files")]
+    pub max_files: usize,
+    /// Size at which the ledger is rotated
+    #[serde(default = "crate::config::defaults::default_usage_log_max_size_mb")]
+    pub max_size_mb: u64,
+    /// Ledger file, relative to the data directory (default: usage/ledger.jsonl)
+    pub path: Option<PathBuf>,
+}
+
+impl Default for UsageLogConfig {
+    fn default() -> Self {
+        UsageLogConfig {
+            enabled: false,
+            include_failed: crate::config::defaults::default_usage_log_include_failed(),
+            max_files: crate::config::defaults::default_usage_log_max_files(),
+            max_size_mb: crate::config::defaults::default_usage_log_max_size_mb(),
+            path: None,
+        }
+    }
+}
+
+impl UsageLogConfig {
+    /// Path of the current ledger file
+    pub fn ledger_path(&self, data_dir: &Path) -> PathBuf {
+        match &self.path {
+            Some(path) => crate::config::expand_required_path(path, Some(data_dir)),
+            None => data_dir
+                .join(crate::config::defaults::DEFAULT_USAGE_LOG_DIR)
+                .join(crate::config::defaults::DEFAULT_USAGE_LOG_FILE),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AuthConfig {
+    /// Request paths that don't require a key (e.g. "/" for health checks)
+    #[serde(default = "crate::config::defaults::default_auth_exempt")]
+    pub exempt: Vec<String>,
+    /// Accepted API keys
+    #[serde(default)]
+    pub keys: Vec<String>,
+    /// File with one accepted API key per line (relative to the config file)
+    pub keys_file: Option<PathBuf>,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
         ServerConfig {
+            access_log: AccessLogConfig::default(),
+            api_versions: crate::config::defaults::default_api_versions(),
+            auth: None,
             base_path: crate::config::defaults::default_base_path(),
+            debug: DebugConfig::default(),
+            generate_titles: false,
             host: crate::config::defaults::default_host(),
+            idempotency: IdempotencyConfig::default(),
+            jobs: JobsConfig::default(),
+            max_body_bytes: crate::config::defaults::default_max_body_bytes(),
+            message_timeout_secs: crate::config::defaults::default_message_timeout_secs(),
             port: crate::config::defaults::default_port(),
+            request_timeout_secs: crate::config::defaults::default_request_timeout_secs(),
+            stream_buffer: crate::config::defaults::default_stream_buffer(),
+            webhooks: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+// Only for the schema: unknown keys are services, and flattening `services`
+// into a closed object is what makes them `additionalProperties`
+#[schemars(deny_unknown_fields)]
 pub struct AdapterConfig {
     #[serde(flatten, default = "crate::config::defaults::default_adapter_services")]
     pub services: HashMap<String, ServiceAdapterConfig>,
+    /// Abort startup when any adapter fails to load, instead of starting
+    /// without it and reporting the failure
+    #[serde(default)]
+    pub fail_fast: bool,
+    /// What to do when an adapter's config doesn't match its manifest's schema
+    #[serde(default)]
+    #[schemars(schema_with = "strict_config_schema")]
+    pub strict_config: StrictConfig,
+    /// Fail loading an adapter whose manifest is invalid, instead of ignoring the manifest
+    #[serde(default = "crate::config::defaults::default_strict_manifest")]
+    pub strict_manifest: bool,
 }
 
 impl Default for AdapterConfig {
     fn default() -> Self {
         AdapterConfig {
             services: crate::config::defaults::default_adapter_services(),
+            fail_fast: false,
+            strict_config: StrictConfig::default(),
+            strict_manifest: crate::config::defaults::default_strict_manifest(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Checking of `[adapters.<service>.config]` against the adapter's manifest
+///
+/// Written as `strict_config = true`, `"warn"` or `"off"` in `[adapters]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrictConfig {
+    /// Unknown keys and type mismatches fail loading the adapter
+    Strict,
+    /// They are logged as warnings
+    #[default]
+    Warn,
+    /// The config isn't checked
+    Off,
+}
+
+impl<'de> Deserialize<'de> for StrictConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Setting {
+            Enabled(bool),
+            Mode(String),
+        }
+
+        match Setting::deserialize(deserializer)? {
+            Setting::Enabled(true) => Ok(StrictConfig::Strict),
+            Setting::Enabled(false) => Ok(StrictConfig::Off),
+            Setting::Mode(mode) => match mode.as_str() {
+                "strict" => Ok(StrictConfig::Strict),
+                "warn" => Ok(StrictConfig::Warn),
+                "off" => Ok(StrictConfig::Off),
+                _ => Err(serde::de::Error::custom(format!(
+                    "invalid strict_config '{}', expected true, \"warn\" or \"off\"",
+                    mode
+                ))),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ServiceAdapterConfig {
     #[serde(default = "crate::config::defaults::default_llm_provider")]
     pub provider: String,
     #[serde(default = "crate::config::defaults::default_adapter_version")]
     pub version: String,
+    /// Provider-specific settings, passed to the adapter as they are
     #[serde(default = "default_toml_value")]
+    #[schemars(schema_with = "open_object_schema")]
     pub config: toml::Value,
+    /// How requests are spread over `endpoints`
+    #[serde(default)]
+    pub balance: BalanceStrategy,
+    /// When to stop sending requests to a failing provider
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Registered provider used when `provider` isn't registered, e.g. one
+    /// registered in code (`llm` and `storage` only)
+    ///
+    /// Without it, that's the first provider registered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_provider: Option<String>,
+    /// Instances of the provider to spread requests over (`llm` only)
+    ///
+    /// Base URLs, or tables with a `base_url` and a `weight`. Without any,
+    /// the adapter uses the `base_url` in its config.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(schema_with = "endpoints_schema")]
+    pub endpoints: Vec<EndpointConfig>,
+    /// Providers tried in order when this one fails (`llm` only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<ServiceAdapterConfig>,
+    /// Limits on the adapter's HTTP exchanges with its provider
+    #[serde(default)]
+    pub http: AdapterHttpConfig,
+    /// Maximum requests in flight to this adapter (unlimited if unset)
+    pub max_concurrent: Option<usize>,
+    /// Maximum requests waiting for a slot before new ones are rejected
+    pub max_queued: Option<usize>,
+    /// Maximum WASM instances for concurrent calls (default: 1)
+    pub pool_size: Option<usize>,
+}
+
+/// `[[adapters.<service>.endpoints]]`: one instance of the provider
+///
+/// Also written as just the base URL, for an endpoint of the default weight.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct EndpointConfig {
+    /// Base URL of the instance, passed to the adapter instead of its configured one
+    pub base_url: String,
+    /// Share of requests relative to the other endpoints (0 takes none)
+    #[serde(default = "crate::config::defaults::default_endpoint_weight")]
+    pub weight: u32,
+}
+
+impl<'de> Deserialize<'de> for EndpointConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Entry {
+            BaseUrl(String),
+            Table {
+                base_url: String,
+                #[serde(default = "crate::config::defaults::default_endpoint_weight")]
+                weight: u32,
+            },
+        }
+
+        Ok(match Entry::deserialize(deserializer)? {
+            Entry::BaseUrl(base_url) => EndpointConfig {
+                base_url,
+                weight: crate::config::defaults::default_endpoint_weight(),
+            },
+            Entry::Table { base_url, weight } => EndpointConfig { base_url, weight },
+        })
+    }
+}
+
+/// How requests are spread over an adapter's endpoints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceStrategy {
+    /// In turn, each endpoint getting its weight's share
+    #[default]
+    WeightedRoundRobin,
+    /// To the endpoint with the fewest requests in flight for its weight
+    LeastInFlight,
+}
+
+/// `[adapters.<service>.circuit_breaker]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CircuitBreakerConfig {
+    /// Seconds requests fail fast once the circuit opened, before one is let
+    /// through to probe the provider
+    #[serde(default = "crate::config::defaults::default_circuit_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// Consecutive provider failures that open the circuit (0 disables it)
+    #[serde(default = "crate::config::defaults::default_circuit_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            cooldown_secs: crate::config::defaults::default_circuit_cooldown_secs(),
+            failure_threshold: crate::config::defaults::default_circuit_failure_threshold(),
+        }
+    }
+}
+
+/// `[adapters.<service>.http]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AdapterHttpConfig {
+    /// Largest response body read from the provider; larger ones fail the
+    /// request instead of being buffered (for streams, the total of all chunks)
+    #[serde(default = "crate::config::defaults::default_adapter_max_response_bytes")]
+    pub max_response_bytes: usize,
+}
+
+impl Default for AdapterHttpConfig {
+    fn default() -> Self {
+        AdapterHttpConfig {
+            max_response_bytes: crate::config::defaults::default_adapter_max_response_bytes(),
+        }
+    }
+}
+
+/// Adapter version selecting the highest installed one
+pub const LATEST_VERSION: &str = "latest";
+
+/// Highest semver version with an `adapter.wasm` installed in `provider_dir`
+///
+/// Releases win over pre-releases, so a `2.0.0-beta` is only picked when
+/// no release is installed. Directories that aren't semver versions, or
+/// have no module yet, are ignored.
+fn latest_version(provider_dir: &Path) -> Option<String> {
+    std::fs::read_dir(provider_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("adapter.wasm").is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| Some((parse_version(&name)?, name)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, name)| name)
+}
+
+/// Sort key of a `MAJOR.MINOR.PATCH[-PRE]` version: release first, then numbers
+fn parse_version(version: &str) -> Option<(bool, u64, u64, u64, String)> {
+    let (numbers, pre) = match version.split_once('-') {
+        Some((numbers, pre)) => (numbers, Some(pre)),
+        None => (version, None),
+    };
+
+    let mut parts = numbers.split('.').map(|part| part.parse::<u64>().ok());
+    let (major, minor, patch) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || pre == Some("") {
+        return None;
+    }
+
+    Some((
+        pre.is_none(),
+        major,
+        minor,
+        patch,
+        pre.unwrap_or_default().to_string(),
+    ))
 }
 
 /// Default TOML value for serde
@@ -70,24 +870,141 @@ fn default_toml_value() -> toml::Value {
     toml::Value::Table(Table::new())
 }
 
+/// Schema of `ServiceAdapterConfig::config`: any table, adapters define its keys
+fn open_object_schema(_: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        ..SchemaObject::default()
+    }
+    .into()
+}
+
+/// `true`, `false` or one of the `StrictConfig` names
+fn strict_config_schema(_: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        enum_values: Some(vec![
+            true.into(),
+            false.into(),
+            "strict".into(),
+            "warn".into(),
+            "off".into(),
+        ]),
+        ..SchemaObject::default()
+    }
+    .into()
+}
+
+/// Schema of `endpoints`: base URLs or `EndpointConfig` tables
+fn endpoints_schema(generator: &mut SchemaGenerator) -> Schema {
+    strings_or_tables_schema::<EndpointConfig>(generator)
+}
+
+/// Schema of `api_versions`: version names or `ApiVersionConfig` tables
+fn api_versions_schema(generator: &mut SchemaGenerator) -> Schema {
+    strings_or_tables_schema::<ApiVersionConfig>(generator)
+}
+
+/// Schema of a list whose entries are strings or `T` tables
+fn strings_or_tables_schema<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    let entry = SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(vec![
+                generator.subschema_for::<String>(),
+                generator.subschema_for::<T>(),
+            ]),
+            ..SubschemaValidation::default()
+        })),
+        ..SchemaObject::default()
+    };
+    SchemaObject {
+        instance_type: Some(InstanceType::Array.into()),
+        array: Some(Box::new(ArrayValidation {
+            items: Some(Schema::from(entry).into()),
+            ..ArrayValidation::default()
+        })),
+        ..SchemaObject::default()
+    }
+    .into()
+}
+
 impl ServiceAdapterConfig {
+    /// Adapter for `provider` with the default version and no settings
+    #[allow(dead_code)] // Used when building configs in code
+    pub fn new(provider: impl Into<String>) -> Self {
+        ServiceAdapterConfig {
+            provider: provider.into(),
+            version: crate::config::defaults::default_adapter_version(),
+            config: default_toml_value(),
+            balance: BalanceStrategy::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            default_provider: None,
+            endpoints: Vec::new(),
+            fallback: Vec::new(),
+            http: AdapterHttpConfig::default(),
+            max_concurrent: None,
+            max_queued: None,
+            pool_size: None,
+        }
+    }
+
+    /// Set a provider-specific setting (an entry of the `config` table)
+    #[allow(dead_code)] // Used when building configs in code
+    pub fn with_setting(mut self, key: &str, value: impl Into<toml::Value>) -> Self {
+        if let toml::Value::Table(table) = &mut self.config {
+            table.insert(key.to_string(), value.into());
+        }
+        self
+    }
+
     /// Generate the default module path for this adapter
+    ///
+    /// Version `latest` uses a `latest` directory if there is one, otherwise
+    /// the highest installed version of the provider (see `latest_version`).
     #[allow(dead_code)]
     pub fn module_path(&self, data_dir: &Path, service: &str) -> PathBuf {
-        data_dir
-            .join("adapters")
-            .join(service)
-            .join(&self.provider)
-            .join(&self.version)
-            .join("adapter.wasm")
+        let provider_dir = data_dir.join("adapters").join(service).join(&self.provider);
+        let version = self.resolve_version(&provider_dir);
+
+        provider_dir.join(version).join("adapter.wasm")
+    }
+
+    /// Concrete version directory for `version` under `provider_dir`
+    fn resolve_version(&self, provider_dir: &Path) -> String {
+        if self.version != LATEST_VERSION || provider_dir.join(LATEST_VERSION).is_dir() {
+            return self.version.clone();
+        }
+
+        match latest_version(provider_dir) {
+            Some(version) => {
+                tracing::info!(
+                    "Resolved {}@{} to version {}",
+                    self.provider,
+                    LATEST_VERSION,
+                    version
+                );
+                version
+            }
+            None => self.version.clone(),
+        }
     }
 
     /// Get the provider config as JSON string for WASM
+    ///
+    /// Secret placeholders are left as written, so this is safe to log.
     #[allow(dead_code)]
-    pub fn config_as_json(&self) -> Result<String, toml::ser::Error> {
+    pub fn config_as_json(&self) -> Result<String, ConfigJsonError> {
         // Convert TOML value to JSON string for WASM interface
-        let json_value = toml_to_json_value(&self.config);
-        Ok(serde_json::to_string(&json_value).unwrap_or_else(|_| "{}".to_string()))
+        let json_value = toml_to_json_value(&self.config, "config")?;
+        Ok(serde_json::to_string(&json_value)?)
+    }
+
+    /// Get the provider config as JSON with `${ENV:..}`/`${FILE:..}` secrets resolved
+    ///
+    /// Only for handing to the adapter at load time; never log or persist it.
+    pub fn resolved_config_json(&self, service: &str) -> Result<String, ConfigJsonError> {
+        let resolved = resolve_secrets(&self.config, service)?;
+        let json_value = toml_to_json_value(&resolved, "config")?;
+        Ok(serde_json::to_string(&json_value)?)
     }
 }
 
@@ -98,6 +1015,29 @@ impl AdapterConfig {
         self.services.get(service)
     }
 
+    /// Check settings that only some services support
+    pub fn check(&self) -> Result<(), AdapterValidationError> {
+        // Sorted, so the same config always fails on the same service
+        let mut services: Vec<_> = self.services.iter().collect();
+        services.sort_by_key(|(service, _)| service.as_str());
+
+        for (service, config) in services {
+            if config.default_provider.is_some() && !matches!(service.as_str(), "llm" | "storage") {
+                return Err(AdapterValidationError::Unsupported {
+                    service: service.clone(),
+                    setting: "default_provider",
+                });
+            }
+            if !config.fallback.is_empty() && service != "llm" {
+                return Err(AdapterValidationError::Unsupported {
+                    service: service.clone(),
+                    setting: "fallback",
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Validate all configured adapters
     #[allow(dead_code)]
     pub fn validate(&self, data_dir: &Path) -> Result<(), AdapterValidationError> {
@@ -117,9 +1057,14 @@ impl AdapterConfig {
     }
 }
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, PartialEq, thiserror::Error)]
 #[allow(dead_code)]
 pub enum AdapterValidationError {
+    #[error("adapters.{service}.{setting} isn't supported for the {service} service")]
+    Unsupported {
+        service: String,
+        setting: &'static str,
+    },
     #[error("Adapter module not found for {service} ({provider}@{version}): {path:?}")]
     ModuleNotFound {
         service: String,
@@ -129,33 +1074,171 @@ pub enum AdapterValidationError {
     },
 }
 
+/// Errors converting an adapter's `config` table to JSON
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigJsonError {
+    #[error("{path} = {value} has no JSON representation")]
+    NonFiniteFloat { path: String, value: f64 },
+    #[error(transparent)]
+    Secret(#[from] SecretError),
+    #[error("Failed to serialize adapter config: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
 /// Convert TOML value to JSON-compatible value
+///
+/// `path` names the value in errors, e.g. `config.options.temperature`.
 #[allow(dead_code)]
-fn toml_to_json_value(toml_val: &toml::Value) -> serde_json::Value {
-    match toml_val {
+fn toml_to_json_value(
+    toml_val: &toml::Value,
+    path: &str,
+) -> Result<serde_json::Value, ConfigJsonError> {
+    Ok(match toml_val {
         toml::Value::String(s) => serde_json::Value::String(s.clone()),
         toml::Value::Integer(i) => serde_json::Value::Number(serde_json::Number::from(*i)),
-        toml::Value::Float(f) => serde_json::Value::Number(
-            serde_json::Number::from_f64(*f).unwrap_or_else(|| serde_json::Number::from(0)),
-        ),
+        // TOML allows nan and inf, JSON doesn't
+        toml::Value::Float(f) => match serde_json::Number::from_f64(*f) {
+            Some(number) => serde_json::Value::Number(number),
+            None => {
+                return Err(ConfigJsonError::NonFiniteFloat {
+                    path: path.to_string(),
+                    value: *f,
+                });
+            }
+        },
         toml::Value::Boolean(b) => serde_json::Value::Bool(*b),
-        toml::Value::Array(arr) => {
-            serde_json::Value::Array(arr.iter().map(toml_to_json_value).collect())
-        }
+        toml::Value::Array(arr) => serde_json::Value::Array(
+            arr.iter()
+                .enumerate()
+                .map(|(i, v)| toml_to_json_value(v, &format!("{}[{}]", path, i)))
+                .collect::<Result<_, _>>()?,
+        ),
         toml::Value::Table(table) => serde_json::Value::Object(
             table
                 .iter()
-                .map(|(k, v)| (k.clone(), toml_to_json_value(v)))
-                .collect(),
+                .map(|(k, v)| {
+                    Ok((
+                        k.clone(),
+                        toml_to_json_value(v, &format!("{}.{}", path, k))?,
+                    ))
+                })
+                .collect::<Result<_, ConfigJsonError>>()?,
         ),
         toml::Value::Datetime(dt) => serde_json::Value::String(dt.to_string()),
-    }
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_json_schema() {
+        let schema = Config::json_schema();
+        let definitions = &schema["definitions"];
+
+        for section in [
+            "adapters",
+            "limits",
+            "logging",
+            "moderation",
+            "server",
+            "storage",
+            "usage_log",
+        ] {
+            assert!(schema["properties"][section].is_object(), "{}", section);
+        }
+        assert_eq!(
+            definitions["ServerConfig"]["properties"]["port"]["default"],
+            8080
+        );
+
+        // Services are keys of [adapters], their config is any table
+        let service = &definitions["AdapterConfig"]["additionalProperties"];
+        assert_eq!(service["$ref"], "#/definitions/ServiceAdapterConfig");
+        let adapter_config = &definitions["ServiceAdapterConfig"]["properties"]["config"];
+        assert_eq!(adapter_config["type"], "object");
+        assert!(adapter_config.get("properties").is_none());
+        assert!(adapter_config.get("additionalProperties").is_none());
+    }
+
+    #[test]
+    fn test_strict_config_setting() {
+        for (setting, expected) in [
+            ("true", StrictConfig::Strict),
+            ("false", StrictConfig::Off),
+            ("\"warn\"", StrictConfig::Warn),
+            ("\"off\"", StrictConfig::Off),
+        ] {
+            let config: Config =
+                toml::from_str(&format!("[adapters]\nstrict_config = {}\n", setting)).unwrap();
+            assert_eq!(config.adapters.strict_config, expected, "{}", setting);
+            // Not mistaken for a service
+            assert!(config.adapters.get_service("strict_config").is_none());
+        }
+
+        assert_eq!(Config::default().adapters.strict_config, StrictConfig::Warn);
+        assert!(toml::from_str::<Config>("[adapters]\nstrict_config = \"loud\"\n").is_err());
+    }
+
+    #[test]
+    fn test_fail_fast_setting() {
+        assert!(!Config::default().adapters.fail_fast);
+
+        let config: Config = toml::from_str("[adapters]\nfail_fast = true\n").unwrap();
+        assert!(config.adapters.fail_fast);
+        assert!(config.adapters.get_service("fail_fast").is_none());
+    }
+
+    #[test]
+    fn test_strict_manifest_setting() {
+        assert!(Config::default().adapters.strict_manifest);
+
+        let config: Config = toml::from_str("[adapters]\nstrict_manifest = false\n").unwrap();
+        assert!(!config.adapters.strict_manifest);
+        assert!(config.adapters.get_service("strict_manifest").is_none());
+    }
+
+    #[test]
+    fn test_default_provider_setting() {
+        let config: Config =
+            toml::from_str("[adapters.llm]\nprovider = \"ollama\"\ndefault_provider = \"echo\"\n")
+                .unwrap();
+        let llm = config.adapters.get_service("llm").unwrap();
+        assert_eq!(llm.default_provider.as_deref(), Some("echo"));
+        config.adapters.check().unwrap();
+
+        let config: Config =
+            toml::from_str("[adapters.tts]\nprovider = \"silence\"\ndefault_provider = \"echo\"\n")
+                .unwrap();
+        assert_eq!(
+            config.adapters.check().unwrap_err().to_string(),
+            "adapters.tts.default_provider isn't supported for the tts service"
+        );
+    }
+
+    #[test]
+    fn test_fallback_setting() {
+        let config: Config = toml::from_str(
+            "[adapters.llm]\nprovider = \"openai\"\n[[adapters.llm.fallback]]\nprovider = \"ollama\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.adapters.get_service("llm").unwrap().fallback.len(),
+            1
+        );
+        config.adapters.check().unwrap();
+
+        let config: Config = toml::from_str(
+            "[adapters.tts]\nprovider = \"openai\"\n[[adapters.tts.fallback]]\nprovider = \"silence\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.adapters.check().unwrap_err().to_string(),
+            "adapters.tts.fallback isn't supported for the tts service"
+        );
+    }
+
     #[test]
     fn test_config_default() {
         let config = Config::default();
@@ -165,6 +1248,7 @@ mod tests {
         assert_eq!(config.server.port, 8080);
         assert_eq!(config.storage.data_dir, None);
         assert_eq!(config.storage.cache_dir, None);
+        assert_eq!(config.storage.create_dirs, None);
 
         // Test adapter defaults
         assert_eq!(config.adapters.services.len(), 1);
@@ -200,6 +1284,7 @@ port = 3000
 [storage]
 data_dir = "/custom/data"
 cache_dir = "/custom/cache"
+create_dirs = false
 "#;
 
         let config: Config = toml::from_str(toml_content).expect("Failed to parse TOML");
@@ -209,6 +1294,7 @@ cache_dir = "/custom/cache"
         assert_eq!(config.server.port, 3000);
         assert_eq!(config.storage.data_dir, Some("/custom/data".into()));
         assert_eq!(config.storage.cache_dir, Some("/custom/cache".into()));
+        assert_eq!(config.storage.create_dirs, Some(false));
     }
 
     #[test]
@@ -224,10 +1310,17 @@ port = 9000
         assert_eq!(config.server.base_path, "");
         assert_eq!(config.server.host, "127.0.0.1");
         assert_eq!(config.server.port, 9000);
+        assert_eq!(config.server.stream_buffer, 16);
         assert_eq!(config.storage.data_dir, None);
         assert_eq!(config.storage.cache_dir, None);
     }
 
+    #[test]
+    fn test_config_stream_buffer() {
+        let config: Config = toml::from_str("[server]\nstream_buffer = 4\n").unwrap();
+        assert_eq!(config.server.stream_buffer, 4);
+    }
+
     #[test]
     fn test_config_empty() {
         let toml_content = "";
@@ -261,6 +1354,240 @@ data_dir = "/my/data"
         assert_eq!(config.storage.cache_dir, None);
     }
 
+    #[test]
+    fn test_config_auth() {
+        let toml_content = r#"
+[server.auth]
+keys = ["secret"]
+keys_file = "keys.txt"
+"#;
+
+        let config: Config = toml::from_str(toml_content).expect("Failed to parse auth TOML");
+        let auth = config.server.auth.expect("Auth block should be present");
+
+        assert_eq!(auth.keys, vec!["secret".to_string()]);
+        assert_eq!(auth.keys_file, Some("keys.txt".into()));
+        // Health check is exempt by default
+        assert_eq!(auth.exempt, vec!["/".to_string()]);
+
+        // Without the block the API stays open
+        assert!(Config::default().server.auth.is_none());
+    }
+
+    #[test]
+    fn test_config_access_log() {
+        let config: Config = toml::from_str(
+            r#"
+[server.access_log]
+mode = "verbose"
+bodies = true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.server.access_log.mode, AccessLogMode::Verbose);
+        assert!(config.server.access_log.bodies);
+        assert!(
+            config
+                .server
+                .access_log
+                .redact
+                .contains(&"authorization".to_string())
+        );
+
+        let default = Config::default();
+        assert_eq!(default.server.access_log.mode, AccessLogMode::Off);
+        assert!(toml::from_str::<Config>("[server.access_log]\nmode = \"loud\"\n").is_err());
+    }
+
+    #[test]
+    fn test_config_jobs() {
+        let config: Config = toml::from_str(
+            r#"
+[server]
+generate_titles = true
+
+[server.jobs]
+concurrency = 1
+retry_max_secs = 60
+"#,
+        )
+        .unwrap();
+
+        assert!(config.server.generate_titles);
+        let jobs = &config.server.jobs;
+        assert!(jobs.enabled);
+        assert_eq!(jobs.concurrency, 1);
+        assert_eq!(jobs.max_attempts, 5);
+        assert_eq!(jobs.retry_max_secs, 60);
+
+        let default = Config::default();
+        assert!(!default.server.generate_titles);
+        assert_eq!(default.server.jobs, JobsConfig::default());
+    }
+
+    #[test]
+    fn test_config_api_versions() {
+        let config: Config = toml::from_str(
+            r#"
+[server]
+api_versions = [
+    { version = "v1", deprecated = true, sunset = "2027-01-01" },
+    "v2",
+]
+"#,
+        )
+        .unwrap();
+
+        let versions = &config.server.api_versions;
+        assert_eq!(versions.len(), 2);
+        assert!(versions[0].deprecated);
+        assert_eq!(versions[0].sunset.as_deref(), Some("2027-01-01"));
+        assert_eq!(versions[1], ApiVersionConfig::stable("v2"));
+
+        assert_eq!(
+            Config::default().server.api_versions,
+            [ApiVersionConfig::stable("v1")]
+        );
+
+        // Printed configs parse back
+        let toml = toml::to_string(&config).unwrap();
+        let parsed: Config = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed.server.api_versions, config.server.api_versions);
+    }
+
+    #[test]
+    fn test_config_webhooks() {
+        let config: Config = toml::from_str(
+            r#"
+[server.webhooks]
+url = "https://example.com/hooks/ai"
+secret = "s3cret"
+"#,
+        )
+        .unwrap();
+
+        let webhooks = config.server.webhooks.unwrap();
+        assert_eq!(webhooks.url, "https://example.com/hooks/ai");
+        assert_eq!(webhooks.secret.as_deref(), Some("s3cret"));
+
+        assert!(Config::default().server.webhooks.is_none());
+        assert!(toml::from_str::<Config>("[server.webhooks]\nsecret = \"s3cret\"\n").is_err());
+    }
+
+    #[test]
+    fn test_config_debug() {
+        assert!(!Config::default().server.debug.expose_fuel);
+
+        let config: Config = toml::from_str("[server.debug]\nexpose_fuel = true\n").unwrap();
+        assert!(config.server.debug.expose_fuel);
+    }
+
+    #[test]
+    fn test_config_conversation_memory() {
+        assert!(Config::default().conversations.memory.is_none());
+
+        let config: Config = toml::from_str(
+            "[conversations.memory]\nstrategy = \"summary\"\nsummary_provider = \"openai\"\n",
+        )
+        .unwrap();
+        let memory = config.conversations.memory.unwrap();
+        assert_eq!(memory.strategy, MemoryStrategy::Summary);
+        assert_eq!(memory.summary_provider.as_deref(), Some("openai"));
+        assert_eq!(memory.window_messages, 20);
+        assert_eq!(memory.summary_trigger_tokens, 4000);
+
+        let config: Config = toml::from_str("[conversations.memory]\n").unwrap();
+        assert_eq!(
+            config.conversations.memory,
+            Some(ConversationMemoryConfig::default())
+        );
+        assert!(toml::from_str::<Config>("[conversations.memory]\nstrategy = \"drop\"\n").is_err());
+    }
+
+    #[test]
+    fn test_config_http() {
+        let defaults = Config::default().http;
+        assert_eq!(defaults.pool_idle_timeout_secs, 90);
+        assert_eq!(defaults.pool_max_idle_per_host, 32);
+        assert_eq!(defaults.tcp_keepalive_secs, 60);
+
+        let config: Config = toml::from_str(
+            "[http]\npool_idle_timeout_secs = 30\npool_max_idle_per_host = 4\ntcp_keepalive = 15\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.http,
+            HttpConfig {
+                pool_idle_timeout_secs: 30,
+                pool_max_idle_per_host: 4,
+                tcp_keepalive_secs: 15,
+            }
+        );
+    }
+
+    #[test]
+    fn test_config_moderation() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.moderation, ModerationConfig::default());
+
+        let toml_content = r#"
+[[moderation.rules]]
+id = "credentials"
+keywords = ["password"]
+action = "redact"
+
+[[moderation.rules]]
+id = "cards"
+pattern = "\\d{4}-\\d{4}"
+direction = "output"
+
+[moderation.adapter]
+action = "flag"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let rules = &config.moderation.rules;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].action, ModerationAction::Redact);
+        assert_eq!(rules[0].direction, ModerationDirection::Both);
+        assert_eq!(rules[1].action, ModerationAction::Block);
+        assert_eq!(rules[1].direction, ModerationDirection::Output);
+        assert_eq!(rules[1].pattern.as_deref(), Some("\\d{4}-\\d{4}"));
+        let adapter = config.moderation.adapter.as_ref().unwrap();
+        assert_eq!(adapter.action, ModerationAction::Flag);
+        assert_eq!(adapter.direction, ModerationDirection::Both);
+    }
+
+    #[test]
+    fn test_config_usage_log() {
+        let config: Config = toml::from_str(
+            r#"
+[usage_log]
+enabled = true
+path = "ledgers/usage.jsonl"
+max_size_mb = 1
+"#,
+        )
+        .unwrap();
+
+        let usage_log = &config.usage_log;
+        assert!(usage_log.enabled);
+        assert!(usage_log.include_failed);
+        assert_eq!(usage_log.max_size_mb, 1);
+        assert_eq!(usage_log.max_files, 5);
+        assert_eq!(
+            usage_log.ledger_path(Path::new("/data")),
+            Path::new("/data/ledgers/usage.jsonl")
+        );
+
+        let default = UsageLogConfig::default();
+        assert!(!default.enabled);
+        assert_eq!(
+            default.ledger_path(Path::new("/data")),
+            Path::new("/data/usage/ledger.jsonl")
+        );
+    }
+
     #[test]
     fn test_config_invalid_toml() {
         let invalid_toml = r#"
@@ -283,17 +1610,70 @@ port = "not_a_number"
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_logging_config_parsing() {
+        let config: Config = toml::from_str(
+            r#"
+[logging]
+log_file = "logs/app.log"
+error_log = "~/logs/error.log"
+file_level = "debug"
+rotation = "size"
+max_size_mb = 5
+max_files = 3
+"#,
+        )
+        .unwrap();
+
+        let files = config
+            .logging
+            .log_files(Some(Path::new("/etc/ai_messenger")), "info");
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            files[0].path,
+            PathBuf::from("/etc/ai_messenger/logs/app.log")
+        );
+        assert_eq!(files[0].level, "debug");
+        assert_eq!(files[0].rotation, LogRotation::Size);
+        assert_eq!(files[0].max_size_bytes, 5 * 1024 * 1024);
+        assert_eq!(files[0].max_files, 3);
+        assert_eq!(files[1].level, "error");
+        assert!(!files[1].path.to_string_lossy().contains('~'));
+        assert!(files[1].path.ends_with("logs/error.log"));
+    }
+
+    #[test]
+    fn test_logging_config_defaults() {
+        let config = Config::default();
+
+        // File logging is opt-in
+        assert!(config.logging.log_files(None, "info").is_empty());
+        assert_eq!(config.logging.rotation, LogRotation::Daily);
+
+        // The log file follows the console level unless set
+        let logging = LoggingConfig {
+            log_file: Some("/var/log/app.log".into()),
+            ..LoggingConfig::default()
+        };
+        assert_eq!(logging.log_files(None, "warn")[0].level, "warn");
+    }
+
     #[test]
     fn test_config_serialization_roundtrip() {
         let original = Config {
             server: ServerConfig {
+                auth: None,
                 base_path: "api".to_string(),
                 host: "0.0.0.0".to_string(),
                 port: 3000,
+                ..ServerConfig::default()
             },
             storage: StorageConfig {
                 data_dir: Some("/test/data".into()),
                 cache_dir: Some("/test/cache".into()),
+                create_dirs: None,
+                encrypt: false,
             },
             ..Config::default()
         };
@@ -408,12 +1788,74 @@ port = 3000
         }
     }
 
+    #[test]
+    fn test_adapter_concurrency_limits() {
+        let toml_content = r#"
+[adapters.llm]
+provider = "ollama"
+max_concurrent = 2
+max_queued = 8
+"#;
+
+        let config: Config = toml::from_str(toml_content).expect("Failed to parse TOML");
+        let llm = config.adapters.get_service("llm").unwrap();
+
+        assert_eq!(llm.max_concurrent, Some(2));
+        assert_eq!(llm.max_queued, Some(8));
+
+        // Limits must not leak into the provider config passed to WASM
+        assert_eq!(llm.config_as_json().unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_resolved_config_json_keeps_placeholder_in_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let key_file = temp_dir.path().join("api_key");
+        std::fs::write(&key_file, "sk-resolved").unwrap();
+        let placeholder = format!("${{FILE:{}}}", key_file.display());
+        let toml_content = format!(
+            r#"
+[adapters.llm]
+provider = "openai"
+
+[adapters.llm.config]
+api_key = "{}"
+"#,
+            placeholder
+        );
+
+        let config: Config = toml::from_str(&toml_content).expect("Failed to parse TOML");
+        let llm = config.adapters.get_service("llm").unwrap();
+
+        let resolved = llm.resolved_config_json("llm").unwrap();
+        assert!(resolved.contains("sk-resolved"));
+
+        // Raw JSON, debug output and serialized config keep the placeholder
+        for output in [
+            llm.config_as_json().unwrap(),
+            format!("{:?}", config),
+            toml::to_string(&config).unwrap(),
+        ] {
+            assert!(output.contains(&placeholder));
+            assert!(!output.contains("sk-resolved"));
+        }
+    }
+
     #[test]
     fn test_adapter_module_path_generation() {
         let adapter = ServiceAdapterConfig {
             provider: "ollama".to_string(),
             version: "1.0.0".to_string(),
             config: toml::Value::Table(Table::new()),
+            balance: BalanceStrategy::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            default_provider: None,
+            endpoints: Vec::new(),
+            fallback: Vec::new(),
+            http: AdapterHttpConfig::default(),
+            max_concurrent: None,
+            max_queued: None,
+            pool_size: None,
         };
 
         let data_dir = std::path::Path::new("/data");
@@ -423,6 +1865,80 @@ port = 3000
         assert_eq!(module_path, expected);
     }
 
+    /// Provider directory with an `adapter.wasm` in each of `versions`
+    fn provider_dir(versions: &[&str]) -> tempfile::TempDir {
+        let data_dir = tempfile::tempdir().unwrap();
+        for version in versions {
+            let dir = data_dir.path().join("adapters/llm/ollama").join(version);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("adapter.wasm"), b"").unwrap();
+        }
+        data_dir
+    }
+
+    #[test]
+    fn test_latest_resolves_to_highest_installed_version() {
+        let data_dir = provider_dir(&["0.9.0", "1.10.0", "1.2.0", "2.0.0-beta", "notes"]);
+        // Half-installed versions are skipped
+        std::fs::create_dir_all(data_dir.path().join("adapters/llm/ollama/3.0.0")).unwrap();
+
+        let module_path = ServiceAdapterConfig::new("ollama").module_path(data_dir.path(), "llm");
+
+        assert_eq!(
+            module_path,
+            data_dir
+                .path()
+                .join("adapters/llm/ollama/1.10.0/adapter.wasm")
+        );
+    }
+
+    #[test]
+    fn test_latest_prefers_latest_directory() {
+        let data_dir = provider_dir(&["1.0.0", "latest"]);
+
+        let module_path = ServiceAdapterConfig::new("ollama").module_path(data_dir.path(), "llm");
+
+        assert_eq!(
+            module_path,
+            data_dir
+                .path()
+                .join("adapters/llm/ollama/latest/adapter.wasm")
+        );
+    }
+
+    #[test]
+    fn test_latest_without_versions_keeps_latest_path() {
+        let data_dir = provider_dir(&["2.0.0-rc.1", "2.0.0-beta"]);
+        let config = ServiceAdapterConfig::new("ollama");
+
+        // Only pre-releases: the highest one
+        assert_eq!(
+            config.module_path(data_dir.path(), "llm"),
+            data_dir
+                .path()
+                .join("adapters/llm/ollama/2.0.0-rc.1/adapter.wasm")
+        );
+        // Nothing installed: the path the error message points at
+        assert_eq!(
+            config.module_path(data_dir.path(), "storage"),
+            data_dir
+                .path()
+                .join("adapters/storage/ollama/latest/adapter.wasm")
+        );
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert!(parse_version("1.2.3").is_some());
+        assert!(parse_version("1.2.3-beta.1").is_some());
+        assert!(parse_version("1.2").is_none());
+        assert!(parse_version("1.2.3.4").is_none());
+        assert!(parse_version("1.2.3-").is_none());
+        assert!(parse_version("v1.2.3").is_none());
+        assert!(parse_version("1.2.3") > parse_version("1.2.3-rc.1"));
+        assert!(parse_version("1.10.0") > parse_version("1.9.9"));
+    }
+
     #[test]
     fn test_adapter_config_as_json() {
         let mut config_table = Table::new();
@@ -437,6 +1953,15 @@ port = 3000
             provider: "test".to_string(),
             version: "1.0".to_string(),
             config: toml::Value::Table(config_table),
+            balance: BalanceStrategy::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            default_provider: None,
+            endpoints: Vec::new(),
+            fallback: Vec::new(),
+            http: AdapterHttpConfig::default(),
+            max_concurrent: None,
+            max_queued: None,
+            pool_size: None,
         };
 
         let json_result = adapter.config_as_json().expect("Failed to convert to JSON");
@@ -464,8 +1989,36 @@ port = 3000
         ];
 
         for (toml_val, expected_json) in test_cases {
-            let result = toml_to_json_value(&toml_val);
+            let result = toml_to_json_value(&toml_val, "config").unwrap();
             assert_eq!(result, expected_json);
         }
     }
+
+    #[test]
+    fn test_non_finite_float_fails_conversion() {
+        let adapter = ServiceAdapterConfig::new("ollama").with_setting(
+            "options",
+            toml::Value::Table(Table::from_iter([(
+                "temperature".to_string(),
+                toml::Value::Float(f64::NAN),
+            )])),
+        );
+
+        let error = adapter.config_as_json().unwrap_err();
+        assert!(matches!(error, ConfigJsonError::NonFiniteFloat { .. }));
+        assert!(
+            error.to_string().contains("config.options.temperature"),
+            "{}",
+            error
+        );
+
+        let error = adapter.resolved_config_json("llm").unwrap_err();
+        assert!(matches!(error, ConfigJsonError::NonFiniteFloat { .. }));
+
+        // Also inside arrays
+        let adapter =
+            ServiceAdapterConfig::new("ollama").with_setting("stops", vec![f64::INFINITY]);
+        let error = adapter.config_as_json().unwrap_err();
+        assert!(error.to_string().contains("config.stops[0]"), "{}", error);
+    }
 }
@@ -0,0 +1,203 @@
+use std::env;
+use std::path::PathBuf;
+use thiserror::Error;
+
+use super::path_expansion;
+
+/// Errors resolving secret placeholders in adapter config
+///
+/// Messages name the placeholder source, never the secret value.
+#[derive(Error, Debug)]
+pub enum SecretError {
+    #[error("Adapter '{adapter}' needs environment variable {variable}, which is not set")]
+    MissingEnv { adapter: String, variable: String },
+    #[error("Adapter '{adapter}' needs secret file {}: {source}", path.display())]
+    UnreadableFile {
+        adapter: String,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// A secret reference parsed from a config string value
+#[derive(Debug, PartialEq)]
+enum Placeholder<'a> {
+    /// `${ENV:NAME}`
+    Env(&'a str),
+    /// `${FILE:path}`
+    File(&'a str),
+}
+
+/// Replace `${ENV:NAME}` and `${FILE:path}` string values with their secrets
+///
+/// Only whole string values are substituted. File contents are trimmed and
+/// file paths support home directory expansion. `adapter` is used in errors.
+pub fn resolve_secrets(value: &toml::Value, adapter: &str) -> Result<toml::Value, SecretError> {
+    resolve_secrets_from_vars(value, adapter, &|name| env::var(name).ok())
+}
+
+/// `resolve_secrets` reading `${ENV:...}` with `var` instead of from the process environment
+fn resolve_secrets_from_vars(
+    value: &toml::Value,
+    adapter: &str,
+    var: &dyn Fn(&str) -> Option<String>,
+) -> Result<toml::Value, SecretError> {
+    match value {
+        toml::Value::String(s) => match parse_placeholder(s) {
+            Some(placeholder) => {
+                resolve_placeholder(placeholder, adapter, var).map(toml::Value::String)
+            }
+            None => Ok(value.clone()),
+        },
+        toml::Value::Array(items) => items
+            .iter()
+            .map(|item| resolve_secrets_from_vars(item, adapter, var))
+            .collect::<Result<_, _>>()
+            .map(toml::Value::Array),
+        toml::Value::Table(table) => table
+            .iter()
+            .map(|(key, item)| {
+                resolve_secrets_from_vars(item, adapter, var).map(|item| (key.clone(), item))
+            })
+            .collect::<Result<_, _>>()
+            .map(toml::Value::Table),
+        _ => Ok(value.clone()),
+    }
+}
+
+/// Parse a string value that consists of exactly one placeholder
+fn parse_placeholder(value: &str) -> Option<Placeholder<'_>> {
+    let inner = value.strip_prefix("${")?.strip_suffix('}')?;
+
+    if let Some(variable) = inner.strip_prefix("ENV:") {
+        return Some(Placeholder::Env(variable));
+    }
+
+    inner.strip_prefix("FILE:").map(Placeholder::File)
+}
+
+/// Look up the secret a placeholder refers to
+fn resolve_placeholder(
+    placeholder: Placeholder<'_>,
+    adapter: &str,
+    var: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, SecretError> {
+    match placeholder {
+        Placeholder::Env(variable) => var(variable).ok_or_else(|| SecretError::MissingEnv {
+            adapter: adapter.to_string(),
+            variable: variable.to_string(),
+        }),
+        Placeholder::File(path) => {
+            let path = path_expansion::expand_path(path, None);
+
+            std::fs::read_to_string(&path)
+                .map(|content| content.trim().to_string())
+                .map_err(|source| SecretError::UnreadableFile {
+                    adapter: adapter.to_string(),
+                    path,
+                    source,
+                })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn table(entries: &[(&str, &str)]) -> toml::Value {
+        toml::Value::Table(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), toml::Value::String(value.to_string())))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_parse_placeholder() {
+        assert_eq!(
+            parse_placeholder("${ENV:OPENAI_API_KEY}"),
+            Some(Placeholder::Env("OPENAI_API_KEY"))
+        );
+        assert_eq!(
+            parse_placeholder("${FILE:~/secrets/openai}"),
+            Some(Placeholder::File("~/secrets/openai"))
+        );
+        assert_eq!(parse_placeholder("plain"), None);
+        assert_eq!(parse_placeholder("prefix ${ENV:X}"), None);
+        assert_eq!(parse_placeholder("${OTHER:X}"), None);
+    }
+
+    #[test]
+    fn test_env_substitution() {
+        let var = |name: &str| {
+            (name == "AI_MESSENGER_TEST_SECRET_ENV").then(|| "sk-from-env".to_string())
+        };
+
+        let config = table(&[("api_key", "${ENV:AI_MESSENGER_TEST_SECRET_ENV}")]);
+        let resolved = resolve_secrets_from_vars(&config, "llm", &var).unwrap();
+
+        assert_eq!(resolved["api_key"].as_str(), Some("sk-from-env"));
+        // The original config keeps the placeholder
+        assert_eq!(
+            config["api_key"].as_str(),
+            Some("${ENV:AI_MESSENGER_TEST_SECRET_ENV}")
+        );
+    }
+
+    #[test]
+    fn test_file_substitution() {
+        let temp_dir = TempDir::new().unwrap();
+        let secret_path = temp_dir.path().join("openai");
+        std::fs::write(&secret_path, "sk-from-file\n").unwrap();
+
+        let placeholder = format!("${{FILE:{}}}", secret_path.display());
+        let config = toml::Value::Table(
+            [(
+                "nested".to_string(),
+                toml::Value::Array(vec![table(&[("api_key", &placeholder)])]),
+            )]
+            .into_iter()
+            .collect(),
+        );
+
+        let resolved = resolve_secrets(&config, "llm").unwrap();
+
+        assert_eq!(
+            resolved["nested"][0]["api_key"].as_str(),
+            Some("sk-from-file")
+        );
+    }
+
+    #[test]
+    fn test_missing_env_names_variable_and_adapter() {
+        let config = table(&[("api_key", "${ENV:AI_MESSENGER_TEST_UNSET_VARIABLE}")]);
+
+        let error = resolve_secrets(&config, "llm").unwrap_err().to_string();
+
+        assert!(error.contains("AI_MESSENGER_TEST_UNSET_VARIABLE"));
+        assert!(error.contains("'llm'"));
+    }
+
+    #[test]
+    fn test_missing_file_names_path_and_adapter() {
+        let config = table(&[("api_key", "${FILE:/nonexistent/ai_messenger/secret}")]);
+
+        let error = resolve_secrets(&config, "storage").unwrap_err().to_string();
+
+        assert!(error.contains("/nonexistent/ai_messenger/secret"));
+        assert!(error.contains("'storage'"));
+    }
+
+    #[test]
+    fn test_non_placeholder_values_unchanged() {
+        let mut config = table(&[("base_url", "http://localhost:11434")]);
+        if let toml::Value::Table(entries) = &mut config {
+            entries.insert("timeout".to_string(), toml::Value::Integer(30));
+        }
+
+        assert_eq!(resolve_secrets(&config, "llm").unwrap(), config);
+    }
+}
@@ -59,9 +59,9 @@
 // Re-export shared modules that are part of the public API
 pub mod adapter;
 pub mod config;
+pub mod routes;
+pub mod server;
 pub mod utils;
-// TODO: Uncomment when implemented
-// pub mod server;
 
 // Library-specific modules (organized in src/library/)
 mod library;
@@ -75,8 +75,7 @@ pub use library::{
 };
 
 // Re-export high-level API functions at crate root
-// TODO: Uncomment when implemented
-// pub use library::api::*;
+pub use library::api::*;
 
 #[cfg(test)]
 mod tests {
@@ -3,26 +3,329 @@
 //! This module provides clean, easy-to-use wrapper functions around
 //! the core ai_messenger functionality.
 
-// Placeholder for future API functions
-#[doc(hidden)]
-pub struct _PlaceholderForFutureAPI;
-
-// TODO: Add high-level API functions when server is implemented
-//
-// Example future API:
-//
-// /// Start an ai_messenger server with the given configuration
-// pub async fn start_server(config: Config) -> Result<()> {
-//     crate::server::start_with_config(config).await
-// }
-//
-// /// Create a default server configuration
-// pub fn default_config() -> Config {
-//     Config::default()
-// }
-//
-// /// Send a message to an AI adapter
-// pub async fn send_message(message: &str, recipient: &str) -> Result<String> {
-//     // Implementation would go here
-//     todo!()
-// }
+use crate::adapter::services::{AdapterRegistry, SharedLlm};
+use crate::adapter::stream::stream_reply;
+use crate::adapter::traits::{ChatMessage, GenerationOptions};
+use crate::config::Config;
+use crate::library::error::{Error, Result};
+use crate::library::types::{ChatResponse, Message};
+use futures::{Stream, StreamExt};
+use std::path::{Path, PathBuf};
+
+/// Load a config file, returning it with the directory it's in
+///
+/// Unlike the CLI, there's no fallback to default locations: the file
+/// must exist. A missing or unreadable file is an `Error::Io`, invalid
+/// contents are an `Error::ConfigParse`.
+pub fn load_config_file(path: impl AsRef<Path>) -> Result<(Config, PathBuf)> {
+    crate::config::discovery::load_from_file(path, None).map_err(Error::from_config_load)
+}
+
+/// Handle for sending messages without running the HTTP server
+///
+/// Loads the adapters from a [`Config`] once and reuses them for every
+/// message, like the server does.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ai_messenger::prelude::*;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     // Your app controls logging
+///     tracing_subscriber::fmt::init();
+///
+///     let mut messenger = Messenger::from_config(Config::default()).await?;
+///
+///     let messages = [Message::user("Hello!")];
+///     let response = messenger.send_message("assistant", &messages).await?;
+///     println!("{}: {}", response.model, response.message.content);
+///
+///     messenger.shutdown().await
+/// }
+/// ```
+///
+/// # Native adapters
+///
+/// Any [`LlmAdapter`](crate::adapter::traits::LlmAdapter) implementation can
+/// be registered in an [`AdapterRegistry`] and used without a WASM module,
+/// e.g. an in-process model or a test double:
+///
+/// ```rust
+/// use ai_messenger::adapter::AdapterRegistry;
+/// use ai_messenger::prelude::*;
+/// # use ai_messenger::adapter::traits::{
+/// #     AdapterService, ChatMessage, GenerationOptions, LlmAdapter, ModelInfo,
+/// # };
+/// # use ai_messenger::adapter::ServiceError;
+/// # struct MyModel;
+/// # #[async_trait::async_trait]
+/// # impl AdapterService for MyModel {
+/// #     fn service_name(&self) -> &'static str { "llm" }
+/// #     fn provider_name(&self) -> &str { "my-model" }
+/// #     fn version(&self) -> &str { "1.0.0" }
+/// #     fn is_ready(&self) -> bool { true }
+/// #     async fn shutdown(&mut self) -> std::result::Result<(), ServiceError> { Ok(()) }
+/// # }
+/// # #[async_trait::async_trait]
+/// # impl LlmAdapter for MyModel {
+/// #     async fn send_message(
+/// #         &mut self,
+/// #         _messages: &[ChatMessage],
+/// #         _options: &GenerationOptions,
+/// #     ) -> std::result::Result<String, ServiceError> { Ok("Hi!".to_string()) }
+/// #     async fn get_model_info(&self) -> std::result::Result<ModelInfo, ServiceError> {
+/// #         Ok(ModelInfo {
+/// #             name: "my-model".to_string(),
+/// #             version: "1.0.0".to_string(),
+/// #             context_length: None,
+/// #             parameters: None,
+/// #         })
+/// #     }
+/// # }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let mut registry = AdapterRegistry::new().await?;
+///     registry.register_llm_adapter("my-model", MyModel);
+///
+///     let mut messenger = Messenger::with_registry(&Config::default(), registry);
+///     let response = messenger.send_message("assistant", &[Message::user("Hello!")]).await?;
+///     assert_eq!(response.model, "my-model");
+///
+///     messenger.shutdown().await
+/// }
+/// ```
+///
+/// The same registry can be served over HTTP with
+/// [`start_with_registry`](crate::server::startup::start_with_registry).
+pub struct Messenger {
+    generation_defaults: GenerationOptions,
+    llm: Option<SharedLlm>,
+    registry: AdapterRegistry,
+    stream_buffer: usize,
+}
+
+impl Messenger {
+    /// Load the adapters configured in `config`
+    ///
+    /// Unlike the server, an adapter that fails to load is an error, so
+    /// problems show up here rather than on the first message.
+    pub async fn from_config(config: Config) -> Result<Self> {
+        Self::load(config, None).await
+    }
+
+    /// Load a config file and the adapters configured in it
+    ///
+    /// Relative paths in the file are resolved against its directory, as
+    /// when the server is started with `--config`.
+    pub async fn from_config_file(path: impl AsRef<Path>) -> Result<Self> {
+        let (config, config_dir) = load_config_file(path)?;
+        Self::load(config, Some(&config_dir)).await
+    }
+
+    async fn load(config: Config, config_dir: Option<&Path>) -> Result<Self> {
+        let data_dir = crate::config::data_dir(&config, config_dir);
+
+        crate::adapter::http::configure_shared_client(&config.http).map_err(Error::AdapterLoad)?;
+        let mut registry = AdapterRegistry::new().await.map_err(Error::AdapterLoad)?;
+        registry
+            .initialize_from_config(&config, &data_dir)
+            .await
+            .map_err(Error::AdapterLoad)?;
+
+        Ok(Self::with_registry(&config, registry))
+    }
+
+    /// Use the adapters in `registry` instead of loading them from `config`
+    ///
+    /// Replies come from the LLM adapter `registry.llm_adapter_for(config)`
+    /// picks; sampling defaults still come from `config`.
+    pub fn with_registry(config: &Config, registry: AdapterRegistry) -> Self {
+        Messenger {
+            generation_defaults: crate::server::state::generation_defaults(config),
+            llm: registry.llm_adapter_for(config).cloned(),
+            registry,
+            stream_buffer: config.server.stream_buffer,
+        }
+    }
+
+    /// Send a conversation to `recipient` and return the reply
+    ///
+    /// Sampling parameters come from `[adapters.llm.config.defaults]`.
+    /// Recipients aren't resolved yet, so every recipient is answered by
+    /// the configured LLM adapter, as on the HTTP API.
+    pub async fn send_message(
+        &mut self,
+        recipient: &str,
+        messages: &[Message],
+    ) -> Result<ChatResponse> {
+        let mut llm = self
+            .llm
+            .as_ref()
+            .ok_or(Error::NoAdapter("llm"))?
+            .write()
+            .await;
+
+        tracing::debug!(
+            "Sending {} messages to {} via {}",
+            messages.len(),
+            recipient,
+            llm.provider_name()
+        );
+
+        let messages: Vec<ChatMessage> = messages.iter().cloned().map(Into::into).collect();
+        let completion = llm.complete(&messages, &self.generation_defaults).await?;
+
+        Ok(ChatResponse {
+            message: Message::assistant(completion.content),
+            model: llm.provider_name().to_string(),
+            usage: completion.usage,
+        })
+    }
+
+    /// Send a conversation to `recipient` and stream the reply as it's generated
+    ///
+    /// Yields the chunks of the reply in order, ending with an error if the
+    /// adapter fails partway. Adapters that can't stream send the whole
+    /// reply as one chunk. Dropping the stream cancels generation.
+    pub fn stream_message(
+        &self,
+        recipient: &str,
+        messages: &[Message],
+    ) -> Result<impl Stream<Item = Result<String>> + Send + Unpin + use<>> {
+        let llm = self.llm.clone().ok_or(Error::NoAdapter("llm"))?;
+
+        tracing::debug!(
+            "Streaming a reply to {} messages to {}",
+            messages.len(),
+            recipient
+        );
+
+        let messages: Vec<ChatMessage> = messages.iter().cloned().map(Into::into).collect();
+        let chunks = stream_reply(
+            llm,
+            messages,
+            self.generation_defaults.clone(),
+            self.stream_buffer,
+        );
+
+        Ok(chunks.map(|chunk| chunk.map_err(Error::from)))
+    }
+
+    /// Shut down all adapters
+    pub async fn shutdown(mut self) -> Result<()> {
+        self.registry.shutdown().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::FnLlm;
+
+    fn config_without_adapters() -> Config {
+        let mut config = Config::default();
+        config.adapters.services.clear();
+        config
+    }
+
+    #[tokio::test]
+    async fn test_from_config_without_adapters() {
+        let messenger = Messenger::from_config(config_without_adapters())
+            .await
+            .unwrap();
+
+        assert!(messenger.llm.is_none());
+        assert!(messenger.shutdown().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_message_without_llm_adapter() {
+        let mut messenger = Messenger::from_config(config_without_adapters())
+            .await
+            .unwrap();
+
+        let messages = [Message::user("Hi")];
+        let error = messenger
+            .send_message("assistant", &messages)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::NoAdapter("llm")));
+        assert_eq!(error.to_string(), "No llm adapter configured");
+    }
+
+    #[tokio::test]
+    async fn test_send_message_with_registered_adapter() {
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry.register_llm_adapter(
+            "echo",
+            FnLlm::new("echo", |messages| {
+                format!("echo: {}", messages.last().unwrap().content)
+            }),
+        );
+        // The default config names ollama, which isn't registered
+        let mut messenger = Messenger::with_registry(&Config::default(), registry);
+
+        let response = messenger
+            .send_message("assistant", &[Message::user("Hi")])
+            .await
+            .unwrap();
+
+        assert_eq!(response.message, Message::assistant("echo: Hi"));
+        assert_eq!(response.model, "echo");
+        assert!(messenger.shutdown().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_stream_message_with_registered_adapter() {
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry.register_llm_adapter("echo", FnLlm::new("echo", |_| "Hi there".to_string()));
+        let messenger = Messenger::with_registry(&Config::default(), registry);
+
+        let chunks: Vec<String> = messenger
+            .stream_message("assistant", &[Message::user("Hi")])
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(chunks.concat(), "Hi there");
+        assert!(matches!(
+            Messenger::from_config(config_without_adapters())
+                .await
+                .unwrap()
+                .stream_message("assistant", &[]),
+            Err(Error::NoAdapter("llm"))
+        ));
+    }
+
+    #[test]
+    fn test_load_config_file_errors() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+
+        let error = load_config_file(&path).unwrap_err();
+        assert!(matches!(error, Error::Io(ref e) if e.kind() == std::io::ErrorKind::NotFound));
+
+        std::fs::write(&path, "[server\nport = ").unwrap();
+        assert!(matches!(
+            load_config_file(&path),
+            Err(Error::ConfigParse(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_config_file_returns_config_dir() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, "[server]\nport = 4000\n").unwrap();
+
+        let (config, config_dir) = load_config_file(&path).unwrap();
+        assert_eq!(config.server.port, 4000);
+        assert_eq!(config_dir, temp_dir.path().canonicalize().unwrap());
+    }
+}
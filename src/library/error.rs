@@ -1,14 +1,148 @@
 /// Error types for the ai_messenger library.
-pub use anyhow::{Error, Result};
-
-// TODO: Add custom error types as needed when we implement server layer
-// #[derive(Debug, thiserror::Error)]
-// pub enum AdapterError {
-//     #[error("WASM module failed to load: {0}")]
-//     WasmLoadError(String),
-//     #[error("Configuration error: {0}")]
-//     ConfigError(String),
-//     #[error("Network error: {0}")]
-//     NetworkError(String),
-//     // ...
-// }
+use crate::adapter::traits::ServiceError;
+use crate::config::builder::ConfigBuildError;
+use thiserror::Error;
+
+/// Result type of the public library API
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors returned by the public library API
+///
+/// Internal code keeps using `anyhow` and `ServiceError`; failures are
+/// mapped to these variants where they cross into the library API, so
+/// embedders can branch on the kind of failure. Wrapped errors are
+/// available through `std::error::Error::source`.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Library setup failed for a reason without a more specific variant
+    #[error("Configuration error: {0}")]
+    Config(String),
+    /// Config file isn't valid TOML or doesn't match the config schema
+    #[error("Failed to parse config: {0}")]
+    ConfigParse(#[from] toml::de::Error),
+    /// Config parsed, but contains invalid settings
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(#[from] ConfigBuildError),
+    /// Adapter couldn't be loaded or initialized
+    #[error("Failed to load adapter: {0}")]
+    AdapterLoad(#[source] ServiceError),
+    /// Adapter failed while handling a request
+    #[error("Adapter error: {0}")]
+    Adapter(#[from] ServiceError),
+    /// No adapter is configured for the service
+    #[error("No {0} adapter configured")]
+    NoAdapter(&'static str),
+    /// Reading a file failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    /// Map an error from loading a config file
+    ///
+    /// Failures to read the file keep their `io::ErrorKind` and a message
+    /// naming the file, TOML errors become `ConfigParse`, anything else is
+    /// a `Config` error with the whole context chain.
+    pub(crate) fn from_config_load(error: anyhow::Error) -> Self {
+        if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+            return Error::Io(std::io::Error::new(io_error.kind(), format!("{:#}", error)));
+        }
+
+        match error.downcast::<toml::de::Error>() {
+            Ok(toml_error) => Error::ConfigParse(toml_error),
+            Err(error) => Error::Config(format!("{:#}", error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_service_error_converts_to_adapter_error() {
+        let error: Error = ServiceError::ServiceUnavailable("down".to_string()).into();
+
+        assert!(matches!(error, Error::Adapter(_)));
+        assert_eq!(
+            error.to_string(),
+            "Adapter error: Service unavailable: down"
+        );
+    }
+
+    #[test]
+    fn test_config_load_keeps_io_error_kind() {
+        let error = anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .context("Failed to read config file: missing.toml");
+
+        match Error::from_config_load(error) {
+            Error::Io(io_error) => {
+                assert_eq!(io_error.kind(), std::io::ErrorKind::NotFound);
+                assert!(io_error.to_string().contains("missing.toml"));
+            }
+            other => panic!("Expected Io error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_config_load_parse_error_is_config_parse_error() {
+        let toml_error = toml::from_str::<toml::Table>("[server").unwrap_err();
+        let error = anyhow::Error::new(toml_error).context("Failed to parse config file: a.toml");
+
+        let error = Error::from_config_load(error);
+        assert!(matches!(error, Error::ConfigParse(_)));
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_config_load_other_error_is_config_error() {
+        let error = anyhow::anyhow!("unexpected").context("Failed to load config: a.toml");
+
+        match Error::from_config_load(error) {
+            Error::Config(message) => {
+                assert_eq!(message, "Failed to load config: a.toml: unexpected")
+            }
+            other => panic!("Expected Config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_conversions_keep_source() {
+        fn parse() -> Result<toml::Table> {
+            Ok(toml::from_str("[server")?)
+        }
+        fn build() -> Result<crate::config::Config> {
+            Ok(crate::config::Config::builder().host("").build()?)
+        }
+        fn read() -> Result<String> {
+            Ok(std::fs::read_to_string("/nonexistent/ai_messenger.toml")?)
+        }
+
+        let error = parse().unwrap_err();
+        assert!(matches!(error, Error::ConfigParse(_)));
+        assert!(error.source().unwrap().is::<toml::de::Error>());
+
+        let error = build().unwrap_err();
+        assert!(matches!(
+            error,
+            Error::InvalidConfig(ConfigBuildError::Empty(ref field)) if field == "server.host"
+        ));
+        assert!(error.source().unwrap().is::<ConfigBuildError>());
+
+        let error = read().unwrap_err();
+        assert!(matches!(error, Error::Io(ref e) if e.kind() == std::io::ErrorKind::NotFound));
+        assert!(error.source().unwrap().is::<std::io::Error>());
+    }
+
+    #[test]
+    fn test_adapter_load_error_has_source() {
+        let error = Error::AdapterLoad(ServiceError::InitializationFailed("no module".to_string()));
+
+        assert_eq!(
+            error.to_string(),
+            "Failed to load adapter: Adapter initialization failed: no module"
+        );
+        assert!(error.source().unwrap().is::<ServiceError>());
+    }
+}
@@ -1,5 +1,5 @@
 /// Library initialization functions for ai_messenger.
-use crate::library::error::Result;
+use crate::library::error::{Error, Result};
 
 /// Initialize ai_messenger library without touching global logging state.
 ///
@@ -51,6 +51,7 @@ pub fn init() -> Result<()> {
 /// ```
 pub fn init_with_logging(level: &str) -> Result<()> {
     // This is for apps that embed ai_messenger as their main component
-    crate::utils::logger::init_logging(level)?;
+    crate::utils::logger::init_logging(level)
+        .map_err(|e| Error::Config(format!("Failed to initialize logging: {:#}", e)))?;
     init()
 }
@@ -9,15 +9,18 @@
 /// use ai_messenger::prelude::*;
 ///
 /// // Now you have access to:
-/// // - Config, ServerConfig
+/// // - Config, ConfigBuilder, ServerConfig
 /// // - Error, Result
 /// // - init, init_with_logging
+/// // - Messenger, ChatResponse
+/// // - Message, Role, Conversation, Usage
 /// // - tracing macros (debug, info, warn, error, trace)
 ///
 /// let config = Config::default();
 /// info!("Starting with config: {:?}", config);
 /// ```
 // Core configuration types
+pub use crate::config::builder::ConfigBuilder;
 pub use crate::config::schema::{Config, ServerConfig};
 
 // Error handling
@@ -26,9 +29,11 @@ pub use crate::library::error::{Error, Result};
 // Initialization functions
 pub use crate::library::init::{init, init_with_logging};
 
+// High-level API
+pub use crate::library::api::Messenger;
+
+// Core domain types
+pub use crate::library::types::{ChatResponse, Conversation, Message, Role, Usage};
+
 // Re-export tracing for convenience when building on top of ai_messenger
 pub use tracing::{debug, error, info, trace, warn};
-
-// TODO: Add more as we implement core types
-// pub use crate::library::types::{Message, Conversation, Sender, Recipient};
-// pub use crate::library::types::{AIAdapter, StorageAdapter, CryptoAdapter};
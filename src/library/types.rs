@@ -1,12 +1,176 @@
 //! Core types for building adapters and integrations.
+//!
+//! These are the types library users work with; they mirror the shapes of
+//! the WIT interface and the HTTP API, so values can be passed between them
+//! without conversion surprises.
 
-// TODO: These will be implemented when we build the server layer
-// Re-export domain types for public API
-// pub use crate::domain::{Message, Conversation, Sender, Recipient};
+use crate::adapter::traits::ChatMessage;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
-// TODO: Re-export adapter interfaces
-// pub use crate::adapters::{AIAdapter, StorageAdapter, CryptoAdapter};
+// Shared with the adapter and storage layers rather than duplicated
+pub use crate::adapter::traits::Usage;
+pub use crate::routes::v1::conversations::model::{Conversation, ConversationMessage};
 
-// Placeholder documentation for future types
-#[doc(hidden)]
-pub struct _PlaceholderForFutureTypes;
+/// Role of a message in a conversation (the WIT `role` variant)
+///
+/// Serialized as the plain provider string, e.g. `"user"`. Unknown strings
+/// are kept as `Role::Other`, so every role round-trips.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    Function,
+    Tool,
+    /// For roles not covered by standard types
+    Other(String),
+}
+
+impl Role {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Function => "function",
+            Role::Tool => "tool",
+            Role::Other(other) => other,
+        }
+    }
+}
+
+impl From<&str> for Role {
+    fn from(role: &str) -> Self {
+        match role {
+            "system" => Role::System,
+            "user" => Role::User,
+            "assistant" => Role::Assistant,
+            "function" => Role::Function,
+            "tool" => Role::Tool,
+            other => Role::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for Role {
+    fn from(role: String) -> Self {
+        Role::from(role.as_str())
+    }
+}
+
+impl From<Role> for String {
+    fn from(role: Role) -> Self {
+        role.as_str().to_string()
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Single message in a conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+}
+
+impl Message {
+    /// Message with the given role
+    pub fn new(role: Role, content: impl Into<String>) -> Self {
+        Message {
+            role,
+            content: content.into(),
+        }
+    }
+
+    /// System prompt message
+    pub fn system(content: impl Into<String>) -> Self {
+        Message::new(Role::System, content)
+    }
+
+    /// Message from the user
+    pub fn user(content: impl Into<String>) -> Self {
+        Message::new(Role::User, content)
+    }
+
+    /// Reply from the assistant
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Message::new(Role::Assistant, content)
+    }
+}
+
+impl From<Message> for ChatMessage {
+    fn from(message: Message) -> Self {
+        ChatMessage {
+            role: message.role.into(),
+            content: message.content,
+            parts: None,
+        }
+    }
+}
+
+impl From<ChatMessage> for Message {
+    fn from(message: ChatMessage) -> Self {
+        Message {
+            role: message.role.into(),
+            content: message.content,
+        }
+    }
+}
+
+/// Reply to a message sent with [`Messenger::send_message`](crate::Messenger::send_message)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatResponse {
+    /// Generated reply
+    pub message: Message,
+    /// Provider that generated the reply
+    pub model: String,
+    /// Token usage, if the provider reported it
+    pub usage: Option<Usage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_round_trips() {
+        for role in [
+            Role::System,
+            Role::User,
+            Role::Assistant,
+            Role::Function,
+            Role::Tool,
+            Role::Other("critic".to_string()),
+        ] {
+            let json = serde_json::to_string(&role).unwrap();
+            assert_eq!(json, format!("\"{}\"", role));
+            assert_eq!(serde_json::from_str::<Role>(&json).unwrap(), role);
+        }
+    }
+
+    #[test]
+    fn test_message_serialization() {
+        let message = Message::user("Hello");
+
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"role": "user", "content": "Hello"})
+        );
+        assert_eq!(serde_json::from_value::<Message>(json).unwrap(), message);
+    }
+
+    #[test]
+    fn test_message_to_chat_message() {
+        let chat_message = ChatMessage::from(Message::assistant("Hi"));
+
+        assert_eq!(chat_message.role, "assistant");
+        assert_eq!(Message::from(chat_message), Message::assistant("Hi"));
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 
+mod adapter;
 mod cli;
 mod config;
 mod routes;
@@ -14,12 +15,27 @@ async fn main() -> Result<()> {
         Some(("serve", sub_m)) => {
             cli::commands::serve::run(sub_m).await?;
         }
+        Some(("adapter", sub_m)) => {
+            cli::commands::adapter::run(sub_m).await?;
+        }
         Some(("cache", sub_m)) => {
             cli::commands::cache::run(sub_m).await?;
         }
+        Some(("chat", sub_m)) => {
+            cli::commands::chat::run(sub_m).await?;
+        }
+        Some(("config", sub_m)) => {
+            cli::commands::config::run(sub_m).await?;
+        }
         Some(("data", sub_m)) => {
             cli::commands::data::run(sub_m).await?;
         }
+        Some(("doctor", sub_m)) => {
+            cli::commands::doctor::run(sub_m).await?;
+        }
+        Some(("usage", sub_m)) => {
+            cli::commands::usage::run(sub_m).await?;
+        }
         Some(("help", sub_m)) => {
             // Handle help command
             if let Some(cmd_name) = sub_m.get_one::<String>("command") {
@@ -29,14 +45,34 @@ async fn main() -> Result<()> {
                         let mut serve_cmd = cli::commands::serve::command();
                         serve_cmd.print_help()?;
                     }
+                    "adapter" => {
+                        let mut adapter_cmd = cli::commands::adapter::command();
+                        adapter_cmd.print_help()?;
+                    }
                     "cache" => {
                         let mut cache_cmd = cli::commands::cache::command();
                         cache_cmd.print_help()?;
                     }
+                    "chat" => {
+                        let mut chat_cmd = cli::commands::chat::command();
+                        chat_cmd.print_help()?;
+                    }
+                    "config" => {
+                        let mut config_cmd = cli::commands::config::command();
+                        config_cmd.print_help()?;
+                    }
                     "data" => {
                         let mut data_cmd = cli::commands::data::command();
                         data_cmd.print_help()?;
                     }
+                    "doctor" => {
+                        let mut doctor_cmd = cli::commands::doctor::command();
+                        doctor_cmd.print_help()?;
+                    }
+                    "usage" => {
+                        let mut usage_cmd = cli::commands::usage::command();
+                        usage_cmd.print_help()?;
+                    }
                     "help" => {
                         let mut app = cli::build();
                         let help_cmd = app.find_subcommand_mut("help").unwrap();
@@ -0,0 +1,375 @@
+use axum::{
+    extract::rejection::JsonRejection,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+
+use crate::adapter::traits::ServiceError;
+
+impl ServiceError {
+    /// HTTP status reported when a request fails with this error
+    ///
+    /// Problems with the provider behind an adapter are gateway errors;
+    /// problems with the adapter or its configuration are our own.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ServiceError::ExecutionError(_)
+            | ServiceError::InvalidResponse(_)
+            | ServiceError::ResponseTooLarge { .. }
+            | ServiceError::WasmTrap { .. } => StatusCode::BAD_GATEWAY,
+            ServiceError::InitializationFailed(_) | ServiceError::InvalidConfig(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            ServiceError::NotImplemented(_) => StatusCode::NOT_IMPLEMENTED,
+            ServiceError::Overloaded { .. } | ServiceError::ServiceUnavailable(_) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ServiceError::ProviderError { status, .. } => provider_status(*status),
+            ServiceError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    /// Machine-readable error type for response bodies
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            ServiceError::ExecutionError(_) | ServiceError::WasmTrap { .. } => "adapter_error",
+            ServiceError::InitializationFailed(_) | ServiceError::InvalidConfig(_) => {
+                "internal_error"
+            }
+            ServiceError::InvalidResponse(_) => "invalid_response",
+            ServiceError::NotImplemented(_) => "not_implemented",
+            ServiceError::Overloaded { .. } => "overloaded",
+            ServiceError::ProviderError { .. } => "provider_error",
+            ServiceError::ResponseTooLarge { .. } => "response_too_large",
+            ServiceError::ServiceUnavailable(_) => "service_unavailable",
+            ServiceError::Timeout(_) => "timeout",
+        }
+    }
+
+    /// Seconds clients should wait before retrying (for a `Retry-After` header)
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            ServiceError::Overloaded { retry_after_secs } => Some(*retry_after_secs),
+            ServiceError::ProviderError {
+                retry_after_secs, ..
+            } => *retry_after_secs,
+            _ => None,
+        }
+    }
+}
+
+/// Our status for an error status of the provider
+///
+/// Rejections of the request itself are client errors, so callers can fix
+/// them (an unknown model is a 404). The provider refusing our credentials
+/// or failing itself is a gateway error; the client can't do anything
+/// about either.
+fn provider_status(status: u16) -> StatusCode {
+    match status {
+        401 | 403 | 407 => StatusCode::BAD_GATEWAY,
+        408 => StatusCode::GATEWAY_TIMEOUT,
+        404 | 413 | 422 | 429 => StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_REQUEST),
+        400..=499 => StatusCode::BAD_REQUEST,
+        _ => StatusCode::BAD_GATEWAY,
+    }
+}
+
+/// Lets handlers return adapter errors with `?`
+///
+/// The body has the shape of `fallback::error_response` plus an
+/// `error_type`. Internal errors are logged but not described to the
+/// client, since their messages can contain configuration details.
+impl IntoResponse for ServiceError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        let error = if status == StatusCode::INTERNAL_SERVER_ERROR {
+            tracing::error!("Adapter error: {}", self);
+            "Internal server error".to_string()
+        } else {
+            tracing::warn!("Adapter error: {}", self);
+            self.to_string()
+        };
+
+        let mut response = (
+            status,
+            Json(json!({
+                "success": false,
+                "error": error,
+                "error_type": self.error_type()
+            })),
+        )
+            .into_response();
+
+        if let Some(retry_after_secs) = self.retry_after_secs() {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+
+        response
+    }
+}
+
+/// A request body that couldn't be read as the JSON a handler expects
+///
+/// Malformed JSON is a 400 whose `detail` tells where parsing failed, as
+/// is a body that doesn't fit the request type (e.g. a missing field or a
+/// negative count); a missing or different `Content-Type` is a 415.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonBodyError {
+    pub status: StatusCode,
+    pub error_type: &'static str,
+    pub error: String,
+    pub detail: Option<String>,
+}
+
+impl JsonBodyError {
+    /// Describe `rejection`, or hand it back if it isn't about the body
+    pub fn from_rejection(rejection: JsonRejection) -> Result<Self, JsonRejection> {
+        let error = match rejection {
+            JsonRejection::JsonDataError(e) => JsonBodyError {
+                status: StatusCode::BAD_REQUEST,
+                error_type: "invalid_request",
+                error: e.body_text(),
+                detail: None,
+            },
+            JsonRejection::JsonSyntaxError(e) => JsonBodyError {
+                status: StatusCode::BAD_REQUEST,
+                error_type: "invalid_json",
+                error: "invalid JSON".to_string(),
+                detail: Some(innermost_cause(&e)),
+            },
+            JsonRejection::MissingJsonContentType(_) => JsonBodyError {
+                status: StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                error_type: "unsupported_media_type",
+                error: "Expected a request with Content-Type: application/json".to_string(),
+                detail: None,
+            },
+            // Chunked bodies only hit the size limit while being read
+            rejection if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE => JsonBodyError {
+                status: StatusCode::PAYLOAD_TOO_LARGE,
+                error_type: "payload_too_large",
+                error: rejection.body_text(),
+                detail: None,
+            },
+            rejection => return Err(rejection),
+        };
+        Ok(error)
+    }
+}
+
+impl IntoResponse for JsonBodyError {
+    fn into_response(self) -> Response {
+        let mut body = json!({
+            "success": false,
+            "error": self.error,
+            "error_type": self.error_type
+        });
+        if let Some(detail) = self.detail {
+            body["detail"] = json!(detail);
+        }
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Response for a JSON body a handler couldn't read
+///
+/// For handlers taking `Result<Json<T>, JsonRejection>`, so that clients get
+/// JSON errors instead of axum's plain-text rejections.
+pub fn json_rejection_response(rejection: JsonRejection) -> Response {
+    match JsonBodyError::from_rejection(rejection) {
+        Ok(error) => error.into_response(),
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+/// Log a storage error and map it to a 500 response
+///
+/// For handlers answering with a bare status code; the error isn't
+/// described to the client.
+pub fn storage_error(error: ServiceError) -> StatusCode {
+    tracing::error!("Storage error: {}", error);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Message of the error at the end of `error`'s source chain
+///
+/// For JSON syntax errors, that's serde's, e.g. "EOF while parsing a string
+/// at line 1 column 12".
+fn innermost_cause(error: &(dyn std::error::Error + 'static)) -> String {
+    let mut cause = error;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    cause.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    fn errors() -> Vec<(ServiceError, StatusCode)> {
+        vec![
+            (
+                ServiceError::ExecutionError("bad output".to_string()),
+                StatusCode::BAD_GATEWAY,
+            ),
+            (
+                ServiceError::WasmTrap {
+                    function: "parse-response".to_string(),
+                    remaining_fuel: Some(0),
+                    trap: wasmtime::Trap::OutOfFuel,
+                },
+                StatusCode::BAD_GATEWAY,
+            ),
+            (
+                ServiceError::InitializationFailed("no module".to_string()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+            (
+                ServiceError::InvalidConfig("missing api_key".to_string()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+            (
+                ServiceError::NotImplemented("`prepare-request` can't be called".to_string()),
+                StatusCode::NOT_IMPLEMENTED,
+            ),
+            (
+                ServiceError::Overloaded {
+                    retry_after_secs: 3,
+                },
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (
+                ServiceError::ProviderError {
+                    status: 404,
+                    message: "model 'llama9' not found".to_string(),
+                    retry_after_secs: None,
+                },
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                ServiceError::ProviderError {
+                    status: 400,
+                    message: "context length exceeded".to_string(),
+                    retry_after_secs: None,
+                },
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                ServiceError::ProviderError {
+                    status: 401,
+                    message: "invalid api key".to_string(),
+                    retry_after_secs: None,
+                },
+                StatusCode::BAD_GATEWAY,
+            ),
+            (
+                ServiceError::ProviderError {
+                    status: 500,
+                    message: "internal error".to_string(),
+                    retry_after_secs: None,
+                },
+                StatusCode::BAD_GATEWAY,
+            ),
+            (
+                ServiceError::InvalidResponse("not valid UTF-8".to_string()),
+                StatusCode::BAD_GATEWAY,
+            ),
+            (
+                ServiceError::ResponseTooLarge { limit: 1024 },
+                StatusCode::BAD_GATEWAY,
+            ),
+            (
+                ServiceError::ServiceUnavailable("down".to_string()),
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (
+                ServiceError::Timeout("slow".to_string()),
+                StatusCode::GATEWAY_TIMEOUT,
+            ),
+        ]
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_status_code_per_variant() {
+        for (error, status) in errors() {
+            assert_eq!(error.status_code(), status, "{}", error);
+
+            let error_type = error.error_type();
+            let response = error.into_response();
+            assert_eq!(response.status(), status);
+
+            let body = body_json(response).await;
+            assert_eq!(body["success"], false);
+            assert_eq!(body["error_type"], error_type);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_internal_errors_not_described() {
+        let response =
+            ServiceError::InvalidConfig("api_key sk-123 rejected".to_string()).into_response();
+
+        let body = body_json(response).await;
+        assert_eq!(body["error"], "Internal server error");
+    }
+
+    #[tokio::test]
+    async fn test_gateway_errors_described() {
+        let response = ServiceError::Timeout("no reply in 30s".to_string()).into_response();
+
+        let body = body_json(response).await;
+        assert_eq!(body["error"], "Request timed out: no reply in 30s");
+    }
+
+    #[tokio::test]
+    async fn test_provider_errors_described() {
+        let response = ServiceError::ProviderError {
+            status: 404,
+            message: "model 'llama9' not found".to_string(),
+            retry_after_secs: None,
+        }
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = body_json(response).await;
+        assert_eq!(body["error_type"], "provider_error");
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .contains("model 'llama9' not found")
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_provider_sets_retry_after() {
+        let response = ServiceError::ProviderError {
+            status: 429,
+            message: "slow down".to_string(),
+            retry_after_secs: Some(20),
+        }
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "20");
+    }
+
+    #[test]
+    fn test_overloaded_sets_retry_after() {
+        let response = ServiceError::Overloaded {
+            retry_after_secs: 3,
+        }
+        .into_response();
+
+        assert_eq!(response.headers()[header::RETRY_AFTER], "3");
+    }
+}
@@ -0,0 +1,91 @@
+use axum::{
+    extract::OriginalUri,
+    http::{HeaderValue, Method, StatusCode, header},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+
+/// Fallback for unmatched routes
+pub async fn not_found(OriginalUri(uri): OriginalUri) -> Response {
+    route_error_response(
+        StatusCode::NOT_FOUND,
+        "NOT_FOUND",
+        "No route matches this path",
+        uri.path(),
+    )
+}
+
+/// Handle axum's empty 405 responses for routes that exist
+///
+/// Axum 0.7 has no router-wide method-not-allowed fallback, so this runs as
+/// response middleware. `OPTIONS` requests get a `204` listing the allowed
+/// methods; other methods get a JSON 405. The `Allow` header is kept.
+pub async fn method_not_allowed(
+    method: Method,
+    OriginalUri(uri): OriginalUri,
+    response: Response,
+) -> Response {
+    if response.status() != StatusCode::METHOD_NOT_ALLOWED
+        || response.headers().contains_key(header::CONTENT_TYPE)
+    {
+        return response;
+    }
+
+    let (mut parts, _) = response.into_parts();
+
+    if method == Method::OPTIONS {
+        let allow = parts
+            .headers
+            .get(header::ALLOW)
+            .and_then(|allow| allow.to_str().ok())
+            .filter(|allow| !allow.is_empty())
+            .map(|allow| format!("{},OPTIONS", allow))
+            .unwrap_or_else(|| "OPTIONS".to_string());
+
+        parts.status = StatusCode::NO_CONTENT;
+        if let Ok(allow) = HeaderValue::from_str(&allow) {
+            parts.headers.insert(header::ALLOW, allow);
+        }
+
+        return Response::from_parts(parts, Default::default());
+    }
+
+    let (json_parts, body) = route_error_response(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "METHOD_NOT_ALLOWED",
+        &format!("Method {} is not allowed for this path", method),
+        uri.path(),
+    )
+    .into_parts();
+
+    parts.headers.extend(json_parts.headers);
+
+    Response::from_parts(parts, body)
+}
+
+/// JSON error response for requests that don't reach a route handler
+pub fn route_error_response(status: StatusCode, code: &str, message: &str, path: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "error": {
+                "code": code,
+                "message": message,
+                "path": path
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// JSON error response in the shape used by all API errors
+pub fn error_response(status: StatusCode, error: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "success": false,
+            "error": error
+        })),
+    )
+        .into_response()
+}
@@ -1,11 +1,61 @@
-use axum::{http::StatusCode, response::Json};
-use serde_json::{Value, json};
+use crate::adapter::traits::AdapterService;
+use crate::server::AppState;
+use axum::response::Json;
+use serde_json::{Map, Value, json};
+use tokio::sync::RwLock;
 
 /// Health check endpoint - always available at /
-pub async fn health_check() -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({
+///
+/// Doubles as the root document, with `links` to the mounted API versions
+/// and the endpoints of the newest one, and the capabilities of the loaded
+/// adapters by service.
+pub async fn health_check(state: AppState, links: Map<String, Value>) -> Json<Value> {
+    Json(json!({
         "status": "ok",
         "message": "AI Messenger is running",
-        "version": env!("CARGO_PKG_VERSION")
-    })))
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "capabilities": adapter_capabilities(&state),
+        "links": links
+    }))
+}
+
+/// Capabilities of each loaded adapter, keyed by service
+///
+/// Adapters are locked while they generate, which is when load balancers
+/// probe this endpoint, so a locked adapter is reported as `"busy"`
+/// instead of waiting for it.
+fn adapter_capabilities(state: &AppState) -> Map<String, Value> {
+    let mut capabilities = Map::new();
+    if let Some(crypto) = &state.crypto {
+        insert_capabilities(&mut capabilities, "crypto", crypto);
+    }
+    if let Some(image) = &state.image {
+        insert_capabilities(&mut capabilities, "image", image);
+    }
+    if let Some(llm) = &state.llm {
+        insert_capabilities(&mut capabilities, "llm", llm);
+    }
+    if let Some(storage) = &state.storage {
+        insert_capabilities(&mut capabilities, "storage", storage);
+    }
+    if let Some(stt) = &state.stt {
+        insert_capabilities(&mut capabilities, "stt", stt);
+    }
+    if let Some(tts) = &state.tts {
+        insert_capabilities(&mut capabilities, "tts", tts);
+    }
+    capabilities
+}
+
+fn insert_capabilities<A: AdapterService + ?Sized>(
+    map: &mut Map<String, Value>,
+    service: &str,
+    adapter: &RwLock<A>,
+) {
+    let capabilities = match adapter.try_read() {
+        Ok(adapter) => json!(adapter.capabilities()),
+        Err(_) => json!("busy"),
+    };
+    map.insert(service.to_string(), capabilities);
 }
@@ -0,0 +1,349 @@
+use crate::adapter::balancer::EndpointBalancer;
+use crate::adapter::breaker::CircuitBreaker;
+use crate::adapter::limiter::ConcurrencyLimiter;
+use crate::server::AppState;
+use crate::server::moderation::ModerationDecision;
+use axum::{extract::State, http::header, response::IntoResponse};
+use std::fmt::Write;
+
+/// Content type of the Prometheus text exposition format
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Metrics in the Prometheus text format - always available at /metrics
+///
+/// Reports the circuit breakers, concurrency limiters and endpoint
+/// balancers of the loaded adapters; adapters without one aren't listed.
+/// With `[moderation]`, its decisions are counted too.
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let breakers: Vec<_> = state
+        .llm_breaker
+        .iter()
+        .map(|breaker| ("llm", breaker.as_ref()))
+        .collect();
+    let limiters: Vec<_> = state
+        .llm_limiter
+        .iter()
+        .map(|limiter| ("llm", limiter.as_ref()))
+        .collect();
+    let balancers: Vec<_> = state
+        .llm_balancer
+        .iter()
+        .map(|balancer| ("llm", balancer.as_ref()))
+        .collect();
+
+    let mut body = render_breakers(&breakers);
+    body.push_str(&render_limiters(&limiters));
+    body.push_str(&render_balancers(&balancers));
+    if let Some(moderation) = &state.moderation {
+        body.push_str(&render_moderation(&moderation.decision_counts()));
+    }
+
+    ([(header::CONTENT_TYPE, METRICS_CONTENT_TYPE)], body)
+}
+
+/// State and failure count of each `(service, breaker)`
+fn render_breakers(breakers: &[(&str, &CircuitBreaker)]) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "# HELP ai_messenger_circuit_breaker_state Circuit breaker state (0 closed, 1 half-open, 2 open)"
+    );
+    let _ = writeln!(out, "# TYPE ai_messenger_circuit_breaker_state gauge");
+    for (service, breaker) in breakers {
+        let _ = writeln!(
+            out,
+            "ai_messenger_circuit_breaker_state{{{}}} {}",
+            labels(service, breaker.provider()),
+            breaker.state().as_metric()
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP ai_messenger_circuit_breaker_consecutive_failures Provider failures since the last success"
+    );
+    let _ = writeln!(
+        out,
+        "# TYPE ai_messenger_circuit_breaker_consecutive_failures gauge"
+    );
+    for (service, breaker) in breakers {
+        let _ = writeln!(
+            out,
+            "ai_messenger_circuit_breaker_consecutive_failures{{{}}} {}",
+            labels(service, breaker.provider()),
+            breaker.consecutive_failures()
+        );
+    }
+
+    out
+}
+
+/// Calls in flight and queued at each `(service, limiter)`
+fn render_limiters(limiters: &[(&str, &ConcurrencyLimiter)]) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "# HELP ai_messenger_adapter_in_flight Provider calls holding a concurrency slot"
+    );
+    let _ = writeln!(out, "# TYPE ai_messenger_adapter_in_flight gauge");
+    for (service, limiter) in limiters {
+        let _ = writeln!(
+            out,
+            "ai_messenger_adapter_in_flight{{{}}} {}",
+            labels(service, limiter.provider()),
+            limiter.in_flight()
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP ai_messenger_adapter_queued Provider calls waiting for a concurrency slot"
+    );
+    let _ = writeln!(out, "# TYPE ai_messenger_adapter_queued gauge");
+    for (service, limiter) in limiters {
+        let _ = writeln!(
+            out,
+            "ai_messenger_adapter_queued{{{}}} {}",
+            labels(service, limiter.provider()),
+            limiter.queued()
+        );
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP ai_messenger_adapter_max_concurrent Configured limit of concurrent provider calls"
+    );
+    let _ = writeln!(out, "# TYPE ai_messenger_adapter_max_concurrent gauge");
+    for (service, limiter) in limiters {
+        let _ = writeln!(
+            out,
+            "ai_messenger_adapter_max_concurrent{{{}}} {}",
+            labels(service, limiter.provider()),
+            limiter.max_concurrent()
+        );
+    }
+
+    out
+}
+
+/// Requests in flight to each endpoint of each `(service, balancer)`
+fn render_balancers(balancers: &[(&str, &EndpointBalancer)]) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "# HELP ai_messenger_endpoint_in_flight Requests in flight to a provider endpoint"
+    );
+    let _ = writeln!(out, "# TYPE ai_messenger_endpoint_in_flight gauge");
+    for (service, balancer) in balancers {
+        for (endpoint, in_flight) in balancer.in_flight() {
+            let _ = writeln!(
+                out,
+                "ai_messenger_endpoint_in_flight{{{},endpoint=\"{}\"}} {}",
+                labels(service, balancer.provider()),
+                escape_label(endpoint),
+                in_flight
+            );
+        }
+    }
+
+    out
+}
+
+/// How often each moderation rule matched, by direction and action
+fn render_moderation(counts: &[(ModerationDecision, u64)]) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "# HELP ai_messenger_moderation_decisions_total Moderation rule matches by direction and action"
+    );
+    let _ = writeln!(
+        out,
+        "# TYPE ai_messenger_moderation_decisions_total counter"
+    );
+    for (decision, count) in counts {
+        let _ = writeln!(
+            out,
+            "ai_messenger_moderation_decisions_total{{direction=\"{}\",action=\"{}\",rule=\"{}\"}} {}",
+            decision.direction.as_str(),
+            decision.action.as_str(),
+            escape_label(&decision.rule),
+            count
+        );
+    }
+
+    out
+}
+
+fn labels(service: &str, provider: &str) -> String {
+    format!(
+        "service=\"{}\",provider=\"{}\"",
+        escape_label(service),
+        escape_label(provider)
+    )
+}
+
+/// Escape a label value as the text format requires
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::traits::ServiceError;
+    use crate::config::schema::{
+        BalanceStrategy, CircuitBreakerConfig, EndpointConfig, ModerationAction, ModerationConfig,
+        ModerationDirection, ModerationRule,
+    };
+    use crate::server::moderation::Moderation;
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use axum::routing::get;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn get_metrics(state: AppState) -> (StatusCode, String) {
+        let response = Router::new()
+            .route("/metrics", get(metrics))
+            .with_state(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let status = response.status();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            METRICS_CONTENT_TYPE
+        );
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_metrics_report_breaker_state() {
+        let breaker = Arc::new(CircuitBreaker::new("ollama", 1, Duration::from_secs(60)));
+        let _ = breaker
+            .call(async { Err::<(), _>(ServiceError::Timeout("no reply".to_string())) })
+            .await;
+        let state = AppState {
+            llm_breaker: Some(breaker),
+            ..AppState::default()
+        };
+
+        let (status, body) = get_metrics(state).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains(
+            "ai_messenger_circuit_breaker_state{service=\"llm\",provider=\"ollama\"} 2\n"
+        ));
+        assert!(body.contains(
+            "ai_messenger_circuit_breaker_consecutive_failures{service=\"llm\",provider=\"ollama\"} 1\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_report_limiter_and_endpoint_load() {
+        let limiter = Arc::new(ConcurrencyLimiter::new("ollama", 2, 4));
+        let _permit = limiter.acquire().await.unwrap();
+        let endpoints = [EndpointConfig {
+            base_url: "http://gpu-1:11434".to_string(),
+            weight: 1,
+        }];
+        let balancer = EndpointBalancer::new(
+            "ollama",
+            &endpoints,
+            BalanceStrategy::LeastInFlight,
+            &CircuitBreakerConfig::default(),
+        )
+        .unwrap()
+        .map(Arc::new);
+        let _lease = balancer.as_ref().unwrap().pick();
+        let state = AppState {
+            llm_balancer: balancer.clone(),
+            llm_limiter: Some(limiter),
+            ..AppState::default()
+        };
+
+        let (status, body) = get_metrics(state).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(
+            body.contains(
+                "ai_messenger_adapter_in_flight{service=\"llm\",provider=\"ollama\"} 1\n"
+            )
+        );
+        assert!(
+            body.contains("ai_messenger_adapter_queued{service=\"llm\",provider=\"ollama\"} 0\n")
+        );
+        assert!(body.contains(
+            "ai_messenger_adapter_max_concurrent{service=\"llm\",provider=\"ollama\"} 2\n"
+        ));
+        assert!(body.contains(
+            "ai_messenger_endpoint_in_flight{service=\"llm\",provider=\"ollama\",endpoint=\"http://gpu-1:11434\"} 1\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_without_breakers() {
+        let (status, body) = get_metrics(AppState::default()).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("# TYPE ai_messenger_circuit_breaker_state gauge"));
+        assert!(!body.contains("service="));
+        assert!(!body.contains("ai_messenger_moderation"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_moderation_decisions() {
+        let config = ModerationConfig {
+            adapter: None,
+            rules: vec![ModerationRule {
+                action: ModerationAction::Flag,
+                direction: ModerationDirection::Both,
+                id: "secrets".to_string(),
+                keywords: vec!["secret".to_string()],
+                pattern: None,
+            }],
+        };
+        let moderation = Moderation::from_config(&config).unwrap().unwrap();
+        for _ in 0..2 {
+            moderation
+                .review(ModerationDirection::Input, "a secret", None)
+                .await
+                .unwrap();
+        }
+        let state = AppState {
+            moderation: Some(moderation),
+            ..AppState::default()
+        };
+
+        let (_, body) = get_metrics(state).await;
+
+        assert!(body.contains("# TYPE ai_messenger_moderation_decisions_total counter"));
+        assert!(body.contains(
+            "ai_messenger_moderation_decisions_total{direction=\"input\",action=\"flag\",rule=\"secrets\"} 2\n"
+        ));
+        // The matched text never shows up
+        assert!(!body.contains("a secret"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
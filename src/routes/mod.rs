@@ -1,2 +1,9 @@
+pub mod error;
+pub mod fallback;
 pub mod health;
+pub mod metrics;
 pub mod v1;
+pub mod versions;
+
+#[cfg(test)]
+pub mod test_support;
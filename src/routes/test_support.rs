@@ -0,0 +1,116 @@
+//! Helpers shared by route tests
+
+use crate::adapter::manifest::AdapterManifest;
+use crate::adapter::traits::{
+    AdapterService, Capabilities, ChatMessage, GenerationOptions, LlmAdapter, ModelInfo,
+    ServiceError,
+};
+use async_trait::async_trait;
+
+pub use crate::adapter::services::memory::MemoryStorage;
+
+/// LLM adapter replying with whatever its closure returns
+pub struct FnLlm<F> {
+    /// Whether it accepts image parts, like an adapter with the `images` capability
+    pub images: bool,
+    /// Manifest it reports, like a WASM adapter's
+    pub manifest: Option<AdapterManifest>,
+    pub provider: &'static str,
+    pub reply: F,
+}
+
+impl<F> FnLlm<F>
+where
+    F: Fn(&[ChatMessage]) -> String + Send + Sync + 'static,
+{
+    pub fn new(provider: &'static str, reply: F) -> Self {
+        FnLlm {
+            images: false,
+            manifest: None,
+            provider,
+            reply,
+        }
+    }
+
+    /// Accept image parts in messages
+    pub fn with_images(self) -> Self {
+        FnLlm {
+            images: true,
+            ..self
+        }
+    }
+
+    /// Report `manifest` as the adapter's manifest
+    pub fn with_manifest(self, manifest: AdapterManifest) -> Self {
+        FnLlm {
+            manifest: Some(manifest),
+            ..self
+        }
+    }
+}
+
+#[async_trait]
+impl<F> AdapterService for FnLlm<F>
+where
+    F: Fn(&[ChatMessage]) -> String + Send + Sync + 'static,
+{
+    fn service_name(&self) -> &'static str {
+        "llm"
+    }
+
+    fn provider_name(&self) -> &str {
+        self.provider
+    }
+
+    fn version(&self) -> &str {
+        "test"
+    }
+
+    fn manifest(&self) -> Option<&AdapterManifest> {
+        self.manifest.as_ref()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let declared = self
+            .manifest
+            .as_ref()
+            .map(Capabilities::from_manifest)
+            .unwrap_or_default();
+
+        Capabilities {
+            images: self.images || declared.images,
+            ..declared
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<F> LlmAdapter for FnLlm<F>
+where
+    F: Fn(&[ChatMessage]) -> String + Send + Sync + 'static,
+{
+    async fn send_message(
+        &mut self,
+        messages: &[ChatMessage],
+        _options: &GenerationOptions,
+    ) -> Result<String, ServiceError> {
+        Ok((self.reply)(messages))
+    }
+
+    async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+        Ok(ModelInfo {
+            name: self.provider.to_string(),
+            version: "test".to_string(),
+            context_length: None,
+            parameters: None,
+        })
+    }
+}
@@ -0,0 +1,278 @@
+use crate::adapter::manifest::{AdapterManifest, ConfigField};
+use crate::adapter::services::AdapterLoadFailure;
+use crate::adapter::traits::{AdapterService, Capabilities};
+use crate::server::AppState;
+use axum::{Json, extract::State};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tokio::sync::RwLock;
+
+/// Configured adapters, loaded or not
+#[derive(Debug, Serialize)]
+pub struct AdapterList {
+    pub adapters: Vec<AdapterInfo>,
+}
+
+/// What a loaded adapter declares about itself, or why it isn't loaded
+#[derive(Debug, Serialize)]
+pub struct AdapterInfo {
+    /// Capability names the manifest declares, known or not
+    pub capabilities: Vec<String>,
+    /// Keys accepted in `[adapters.<service>.config]`, for config forms
+    /// (null if the adapter doesn't declare them)
+    pub config_schema: Option<BTreeMap<String, ConfigField>>,
+    /// Why the adapter failed to load (only for status `failed`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<AdapterLoadFailure>,
+    /// Provider name (null while the adapter is busy)
+    pub provider: Option<String>,
+    pub ready: bool,
+    pub required_config: Vec<String>,
+    pub service: String,
+    /// `ready`, `not ready`, `busy` or `failed`
+    pub status: &'static str,
+    /// Known capabilities the adapter supports
+    pub supports: Capabilities,
+    /// Adapter version (null while the adapter is busy)
+    pub version: Option<String>,
+}
+
+impl AdapterInfo {
+    fn of<A: AdapterService + ?Sized>(adapter: &A) -> Self {
+        let AdapterManifest {
+            capabilities,
+            config_schema,
+            required_config,
+        } = adapter.manifest().cloned().unwrap_or_default();
+
+        let ready = adapter.is_ready();
+        AdapterInfo {
+            capabilities,
+            config_schema,
+            failure: None,
+            provider: Some(adapter.provider_name().to_string()),
+            ready,
+            required_config,
+            service: adapter.service_name().to_string(),
+            status: if ready { "ready" } else { "not ready" },
+            supports: adapter.capabilities(),
+            version: Some(adapter.version().to_string()),
+        }
+    }
+
+    /// Info of `adapter`, or a `busy` entry if a request holds its lock
+    fn of_locked<A: AdapterService + ?Sized>(service: &str, adapter: &RwLock<A>) -> Self {
+        match adapter.try_read() {
+            Ok(adapter) => AdapterInfo::of(&*adapter),
+            Err(_) => AdapterInfo {
+                capabilities: Vec::new(),
+                config_schema: None,
+                failure: None,
+                provider: None,
+                ready: true,
+                required_config: Vec::new(),
+                service: service.to_string(),
+                status: "busy",
+                supports: Capabilities::default(),
+                version: None,
+            },
+        }
+    }
+
+    fn failed(service: &str, failure: &AdapterLoadFailure) -> Self {
+        AdapterInfo {
+            capabilities: Vec::new(),
+            config_schema: None,
+            failure: Some(failure.clone()),
+            provider: Some(failure.provider.clone()),
+            ready: false,
+            required_config: Vec::new(),
+            service: service.to_string(),
+            status: "failed",
+            supports: Capabilities::default(),
+            version: Some(failure.version.clone()),
+        }
+    }
+}
+
+/// List the loaded adapters with their manifests, then those that failed to load
+///
+/// Native and built-in adapters have no manifest, so they declare nothing.
+/// An adapter locked by a running request is listed as `busy` rather than
+/// waited for.
+pub async fn list_adapters(State(state): State<AppState>) -> Json<AdapterList> {
+    let mut adapters = Vec::new();
+    if let Some(crypto) = &state.crypto {
+        adapters.push(AdapterInfo::of_locked("crypto", crypto));
+    }
+    if let Some(image) = &state.image {
+        adapters.push(AdapterInfo::of_locked("image", image));
+    }
+    if let Some(llm) = &state.llm {
+        adapters.push(AdapterInfo::of_locked("llm", llm));
+    }
+    if let Some(storage) = &state.storage {
+        adapters.push(AdapterInfo::of_locked("storage", storage));
+    }
+    if let Some(stt) = &state.stt {
+        adapters.push(AdapterInfo::of_locked("stt", stt));
+    }
+    if let Some(tts) = &state.tts {
+        adapters.push(AdapterInfo::of_locked("tts", tts));
+    }
+    adapters.extend(
+        state
+            .failed_adapters
+            .iter()
+            .map(|(service, failure)| AdapterInfo::failed(service, failure)),
+    );
+
+    Json(AdapterList { adapters })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::{FnLlm, MemoryStorage};
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use axum::routing::get;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_list_adapters_with_config_schema() {
+        let manifest: AdapterManifest = toml::from_str(
+            r#"
+capabilities = ["images"]
+
+[config_schema.base_url]
+type = "string"
+description = "URL of the provider's API"
+"#,
+        )
+        .unwrap();
+        let state = AppState {
+            storage: Some(Arc::new(RwLock::new(MemoryStorage::default()))),
+            ..AppState::with_llm(FnLlm::new("ollama", |_| String::new()).with_manifest(manifest))
+        };
+
+        let response = Router::new()
+            .route("/v1/adapters", get(list_adapters))
+            .with_state(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/adapters")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            body["adapters"][0],
+            json!({
+                "capabilities": ["images"],
+                "config_schema": {
+                    "base_url": {
+                        "description": "URL of the provider's API",
+                        "type": "string"
+                    }
+                },
+                "provider": "ollama",
+                "ready": true,
+                "required_config": [],
+                "service": "llm",
+                "status": "ready",
+                "supports": {
+                    "embeddings": false,
+                    "images": true,
+                    "moderation": false,
+                    "streaming": false,
+                    "tools": false
+                },
+                "version": "test"
+            })
+        );
+        assert_eq!(body["adapters"][1]["service"], "storage");
+        assert!(body["adapters"][1]["config_schema"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_list_adapters_reports_failures() {
+        let failure = AdapterLoadFailure {
+            at: "2024-01-01T00:00:00Z".parse().unwrap(),
+            attempted_path: Some("/data/adapters/llm/openai/1.0.0/adapter.wasm".into()),
+            error: "Adapter module not found".to_string(),
+            provider: "openai".to_string(),
+            version: "1.0.0".to_string(),
+        };
+        let state = AppState {
+            failed_adapters: Arc::new(BTreeMap::from([("llm".to_string(), failure)])),
+            ..AppState::with_storage(MemoryStorage::default())
+        };
+
+        let response = Router::new()
+            .route("/v1/adapters", get(list_adapters))
+            .with_state(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/adapters")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["adapters"][0]["status"], "ready");
+        assert!(body["adapters"][0].get("failure").is_none());
+        let failed = &body["adapters"][1];
+        assert_eq!(failed["service"], "llm");
+        assert_eq!(failed["provider"], "openai");
+        assert_eq!(failed["status"], "failed");
+        assert_eq!(failed["ready"], false);
+        assert_eq!(
+            failed["failure"],
+            json!({
+                "at": "2024-01-01T00:00:00Z",
+                "attempted_path": "/data/adapters/llm/openai/1.0.0/adapter.wasm",
+                "error": "Adapter module not found",
+                "provider": "openai",
+                "version": "1.0.0"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_adapters_reports_busy_adapters() {
+        let state = AppState::with_llm(FnLlm::new("ollama", |_| String::new()));
+        let llm = state.llm.clone().unwrap();
+        let _generating = llm.write().await;
+
+        let response = Router::new()
+            .route("/v1/adapters", get(list_adapters))
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/adapters")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["adapters"][0]["service"], "llm");
+        assert_eq!(body["adapters"][0]["status"], "busy");
+        assert!(body["adapters"][0]["provider"].is_null());
+    }
+}
@@ -0,0 +1,9 @@
+pub mod list;
+
+use crate::server::AppState;
+use axum::{Router, routing::get};
+
+/// Build the adapters router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", get(list::list_adapters))
+}
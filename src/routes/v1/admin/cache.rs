@@ -0,0 +1,203 @@
+use crate::routes::fallback::error_response;
+use crate::server::AppState;
+use crate::utils::cache::{self, CleanOptions, CleanReport};
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+/// Held while cleaning, so overlapping requests don't walk the same files
+static CLEANING: Mutex<()> = Mutex::const_new(());
+
+/// Query parameters of `DELETE /v1/admin/cache`
+#[derive(Debug, Deserialize)]
+pub struct CleanQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Only remove files older than this, e.g. `7d`
+    pub older_than: Option<String>,
+}
+
+/// Result of cleaning the cache directory
+#[derive(Debug, Serialize)]
+pub struct CleanResponse {
+    pub dry_run: bool,
+    pub freed_bytes: u64,
+    /// Removed files, relative to the cache directory
+    pub removed: Vec<String>,
+}
+
+/// Remove our files from the cache directory, like `ai_messenger cache clean`
+///
+/// Only served when API keys are configured.
+pub async fn clean_cache(
+    State(state): State<AppState>,
+    Query(query): Query<CleanQuery>,
+) -> Response {
+    if let Err(response) = super::require_auth(&state) {
+        return response;
+    }
+    let Some(cache_dir) = state.cache_dir.clone() else {
+        return error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No cache directory configured",
+        );
+    };
+    let older_than = match query
+        .older_than
+        .as_deref()
+        .map(cache::parse_age)
+        .transpose()
+    {
+        Ok(older_than) => older_than,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+    let options = CleanOptions {
+        dry_run: query.dry_run,
+        older_than,
+    };
+
+    let _cleaning = CLEANING.lock().await;
+    let result = tokio::task::spawn_blocking(move || cache::clean(&cache_dir, &options)).await;
+
+    match result {
+        Ok(Ok(report)) => {
+            if !options.dry_run {
+                tracing::info!(
+                    "Cleaned cache: removed {} files, freed {} bytes",
+                    report.removed.len(),
+                    report.freed_bytes
+                );
+            }
+            Json(clean_response(report, options.dry_run)).into_response()
+        }
+        Ok(Err(e)) => {
+            tracing::error!("{}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+        }
+        Err(e) => {
+            tracing::error!("Cache cleaning panicked: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to clean cache")
+        }
+    }
+}
+
+fn clean_response(report: CleanReport, dry_run: bool) -> CleanResponse {
+    CleanResponse {
+        dry_run,
+        freed_bytes: report.freed_bytes,
+        removed: report
+            .removed
+            .iter()
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn delete(state: AppState, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = super::super::router()
+            .with_state(state)
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    fn state_with_cache() -> (tempfile::TempDir, AppState) {
+        let cache_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(cache_dir.path().join("tmp")).unwrap();
+        std::fs::write(cache_dir.path().join("tmp/upload.part"), b"12345").unwrap();
+        std::fs::write(cache_dir.path().join("keep.txt"), b"mine").unwrap();
+
+        let state = AppState {
+            cache_dir: Some(cache_dir.path().to_path_buf()),
+            ..authenticated()
+        };
+        (cache_dir, state)
+    }
+
+    /// State of a server requiring API keys
+    fn authenticated() -> AppState {
+        let auth_config = crate::config::schema::AuthConfig {
+            exempt: crate::config::defaults::default_auth_exempt(),
+            keys: vec!["secret".to_string()],
+            keys_file: None,
+        };
+        AppState {
+            auth: Some(std::sync::Arc::new(
+                crate::server::auth::ApiKeys::from_config(&auth_config, None).unwrap(),
+            )),
+            ..AppState::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clean_cache() {
+        let (cache_dir, state) = state_with_cache();
+
+        let (status, dry_run) = delete(state.clone(), "/cache?dry_run=true").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(dry_run["removed"], serde_json::json!(["tmp/upload.part"]));
+        assert!(cache_dir.path().join("tmp/upload.part").exists());
+
+        let (status, body) = delete(state, "/cache").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["freed_bytes"], 5);
+        assert!(!cache_dir.path().join("tmp/upload.part").exists());
+        assert!(cache_dir.path().join("keep.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_clean_cache_older_than() {
+        let (cache_dir, state) = state_with_cache();
+
+        let (status, body) = delete(state.clone(), "/cache?older_than=1d").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["removed"], serde_json::json!([]));
+        assert!(cache_dir.path().join("tmp/upload.part").exists());
+
+        let (status, _) = delete(state, "/cache?older_than=soon").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_clean_cache_needs_cache_dir() {
+        let (status, _) = delete(authenticated(), "/cache").await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_clean_cache_disabled_without_auth() {
+        let (cache_dir, state) = state_with_cache();
+        let state = AppState {
+            auth: None,
+            ..state
+        };
+
+        let (status, body) = delete(state, "/cache").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(body["error"].as_str().unwrap().contains("[server.auth]"));
+        assert!(cache_dir.path().join("tmp/upload.part").exists());
+    }
+}
@@ -0,0 +1,25 @@
+pub mod cache;
+
+use crate::routes::fallback::error_response;
+use crate::server::AppState;
+use axum::{Router, http::StatusCode, response::Response, routing::delete};
+
+/// Build the admin router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/cache", delete(cache::clean_cache))
+}
+
+/// Refuse admin requests unless the server requires API keys
+///
+/// Without `[server.auth]`, anyone who can reach the server could call
+/// them, so they're disabled.
+#[allow(clippy::result_large_err)] // Handlers return the refusal as it is
+fn require_auth(state: &AppState) -> Result<(), Response> {
+    match state.auth {
+        Some(_) => Ok(()),
+        None => Err(error_response(
+            StatusCode::FORBIDDEN,
+            "Admin endpoints are disabled: they need API keys under [server.auth]",
+        )),
+    }
+}
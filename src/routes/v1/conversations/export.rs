@@ -0,0 +1,265 @@
+use super::model::{Conversation, is_valid_conversation_id, load_conversation};
+use super::render;
+use crate::routes::error::storage_error;
+use crate::server::AppState;
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::convert::Infallible;
+
+/// Output format of a conversation export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// The stored conversation document
+    #[default]
+    Json,
+    /// Transcript with a `###` header per message
+    Markdown,
+    /// Plain text transcript
+    Txt,
+}
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Txt => "text/plain; charset=utf-8",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Txt => "txt",
+        }
+    }
+}
+
+/// Query parameters of the export endpoint
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// Output format (default: json)
+    #[serde(default)]
+    pub format: ExportFormat,
+
+    /// Whether to keep provider, model and usage information (default: true)
+    #[serde(default = "include_metadata_default")]
+    pub include_metadata: bool,
+}
+
+fn include_metadata_default() -> bool {
+    true
+}
+
+/// Download a stored conversation as an attachment
+///
+/// Markdown and text exports are streamed message by message, so large
+/// conversations aren't rendered into a single string first.
+pub async fn export_conversation(
+    State(state): State<AppState>,
+    Path(conversation_id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, StatusCode> {
+    if !is_valid_conversation_id(&conversation_id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let storage = state
+        .storage
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let conversation = match load_conversation(storage, &conversation_id).await {
+        Ok(Some(conversation)) => conversation,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => return Err(storage_error(e)),
+    };
+
+    let conversation = if query.include_metadata {
+        conversation
+    } else {
+        conversation.without_metadata()
+    };
+
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        render::filename(&conversation, query.format.extension())
+    );
+    let disposition =
+        HeaderValue::from_str(&disposition).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let body = render_body(conversation, query.format)?;
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(query.format.content_type()),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Render the response body in the requested format
+fn render_body(conversation: Conversation, format: ExportFormat) -> Result<Body, StatusCode> {
+    match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(&conversation)
+            .map(Body::from)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR),
+        ExportFormat::Markdown => Ok(stream_body(render::markdown(conversation))),
+        ExportFormat::Txt => Ok(stream_body(render::text(conversation))),
+    }
+}
+
+/// Stream rendered chunks as the response body
+fn stream_body(chunks: impl Iterator<Item = String> + Send + 'static) -> Body {
+    Body::from_stream(futures::stream::iter(chunks.map(Ok::<_, Infallible>)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::MemoryStorage;
+    use crate::routes::v1::conversations::model::{ConversationMessage, conversation_key};
+    use crate::routes::v1::message::response::Usage;
+    use axum::Router;
+    use axum::body::to_bytes;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn conversation() -> Conversation {
+        Conversation {
+            id: "abc-123".to_string(),
+            title: Some("Trip planning".to_string()),
+            created_at: Some("2025-01-02T03:04:05Z".to_string()),
+            provider: Some("ollama".to_string()),
+            messages: vec![
+                ConversationMessage {
+                    role: "user".to_string(),
+                    content: "Where should I go?".to_string(),
+                    timestamp: Some("2025-01-02T03:04:05Z".to_string()),
+                    model: None,
+                    usage: None,
+                    moderation: Vec::new(),
+                },
+                ConversationMessage {
+                    role: "assistant".to_string(),
+                    content: "Lisbon.".to_string(),
+                    timestamp: Some("2025-01-02T03:04:07Z".to_string()),
+                    model: Some("llama3.2".to_string()),
+                    usage: Some(Usage {
+                        prompt_tokens: 5,
+                        completion_tokens: 2,
+                        total_tokens: 7,
+                    }),
+                    moderation: Vec::new(),
+                },
+            ],
+            ..Conversation::new("abc-123")
+        }
+    }
+
+    fn app() -> Router {
+        let mut storage = MemoryStorage::default();
+        storage.entries.insert(
+            conversation_key("abc-123"),
+            serde_json::to_vec(&conversation()).unwrap(),
+        );
+
+        super::super::router().with_state(AppState::with_storage(storage))
+    }
+
+    async fn export(uri: &str) -> (StatusCode, header::HeaderMap, String) {
+        let response = app()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, headers, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_export_json() {
+        let (status, headers, body) = export("/abc-123/export").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+        assert_eq!(
+            headers[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"trip-planning.json\""
+        );
+
+        let exported: Conversation = serde_json::from_str(&body).unwrap();
+        assert_eq!(exported, conversation());
+    }
+
+    #[tokio::test]
+    async fn test_export_markdown() {
+        let (status, headers, body) = export("/abc-123/export?format=markdown").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            headers[header::CONTENT_TYPE],
+            "text/markdown; charset=utf-8"
+        );
+        assert_eq!(
+            headers[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"trip-planning.md\""
+        );
+        assert!(body.starts_with("# Trip planning\n"));
+        assert!(body.contains("### Assistant · 2025-01-02T03:04:07Z · llama3.2\n\nLisbon.\n"));
+    }
+
+    #[tokio::test]
+    async fn test_export_txt() {
+        let (status, headers, body) = export("/abc-123/export?format=txt").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+        assert_eq!(
+            headers[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"trip-planning.txt\""
+        );
+        assert!(body.contains("[2025-01-02T03:04:05Z] User:\nWhere should I go?\n"));
+    }
+
+    #[tokio::test]
+    async fn test_export_without_metadata() {
+        let (status, _, body) = export("/abc-123/export?include_metadata=false").await;
+
+        assert_eq!(status, StatusCode::OK);
+        let exported: Conversation = serde_json::from_str(&body).unwrap();
+        assert_eq!(exported, conversation().without_metadata());
+        assert!(!body.contains("llama3.2"));
+        assert!(!body.contains("total_tokens"));
+    }
+
+    #[tokio::test]
+    async fn test_export_missing_conversation() {
+        let (status, _, _) = export("/missing/export").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_export_rejects_invalid_input() {
+        let (status, _, _) = export("/abc-123/export?format=pdf").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _, _) = export("/..%2Fsecret/export").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,167 @@
+use super::model::{CONVERSATION_KEY_PREFIX, is_valid_conversation_id};
+use crate::adapter::traits::KeyPage;
+use crate::routes::error::storage_error;
+use crate::server::AppState;
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+/// Conversations listed when the request sets no `limit`
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Most conversations listed per page
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Query parameters selecting a page of conversations
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Page of conversation IDs
+#[derive(Debug, Serialize)]
+pub struct ConversationPage {
+    pub conversations: Vec<String>,
+    /// Cursor for the next page (null on the last page)
+    pub next_cursor: Option<String>,
+}
+
+/// List stored conversation IDs in order, a page at a time
+pub async fn list_conversations(
+    State(state): State<AppState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<ConversationPage>, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if query
+        .cursor
+        .as_deref()
+        .is_some_and(|cursor| KeyPage::decode_cursor(cursor).is_err())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let storage = state
+        .storage
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let page = storage
+        .read()
+        .await
+        .list_keys_paginated(
+            Some(CONVERSATION_KEY_PREFIX),
+            query.cursor.as_deref(),
+            limit,
+        )
+        .await
+        .map_err(storage_error)?;
+
+    let conversations = page
+        .keys
+        .iter()
+        .filter_map(|key| key.strip_prefix(CONVERSATION_KEY_PREFIX))
+        .filter(|id| is_valid_conversation_id(id))
+        .map(str::to_string)
+        .collect();
+
+    Ok(Json(ConversationPage {
+        conversations,
+        next_cursor: page.next_cursor,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::MemoryStorage;
+    use crate::routes::v1::conversations::model::conversation_key;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn state(count: usize) -> AppState {
+        let mut storage = MemoryStorage::default();
+        for i in 0..count {
+            storage
+                .entries
+                .insert(conversation_key(&format!("chat-{:02}", i)), b"{}".to_vec());
+        }
+        storage
+            .entries
+            .insert("sender/default/profile".to_string(), b"{}".to_vec());
+
+        AppState::with_storage(storage)
+    }
+
+    async fn get(state: &AppState, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = super::super::router()
+            .with_state(state.clone())
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn test_list_conversations_pages_through_all() {
+        let state = state(5);
+
+        let (status, first) = get(&state, "/?limit=2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            first["conversations"],
+            serde_json::json!(["chat-00", "chat-01"])
+        );
+
+        let cursor = first["next_cursor"].as_str().unwrap();
+        let (_, second) = get(&state, &format!("/?limit=2&cursor={}", cursor)).await;
+        assert_eq!(
+            second["conversations"],
+            serde_json::json!(["chat-02", "chat-03"])
+        );
+
+        let cursor = second["next_cursor"].as_str().unwrap();
+        let (_, last) = get(&state, &format!("/?limit=2&cursor={}", cursor)).await;
+        assert_eq!(last["conversations"], serde_json::json!(["chat-04"]));
+        assert!(last["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_list_conversations_default_limit() {
+        let (status, body) = get(&state(3), "/").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["conversations"].as_array().unwrap().len(), 3);
+        assert!(body["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_list_conversations_rejects_bad_parameters() {
+        let state = state(1);
+
+        assert_eq!(get(&state, "/?limit=0").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(get(&state, "/?limit=501").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(get(&state, "/?cursor=zz").await.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_list_conversations_needs_storage() {
+        assert_eq!(
+            get(&AppState::default(), "/").await.0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
@@ -0,0 +1,17 @@
+pub mod export;
+pub mod list;
+pub mod model;
+pub mod render;
+pub mod search;
+pub mod show;
+
+use crate::server::AppState;
+use axum::{Router, routing::get};
+
+/// Build the conversations router
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list::list_conversations))
+        .route("/:conversation_id", get(show::get_conversation))
+        .route("/:conversation_id/export", get(export::export_conversation))
+}
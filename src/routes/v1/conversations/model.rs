@@ -0,0 +1,337 @@
+use crate::adapter::traits::{ServiceError, StorageAdapter};
+use crate::routes::v1::message::response::Usage;
+use crate::server::moderation::ModerationDecision;
+use crate::server::state::SharedStorage;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+
+/// Conversation document persisted via the storage adapter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    /// Conversation ID (also part of the storage key)
+    pub id: String,
+
+    /// Human-readable title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// RFC 3339 timestamp of the first message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+
+    /// Provider that generated the assistant messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Messages in chronological order
+    #[serde(default)]
+    pub messages: Vec<ConversationMessage>,
+
+    /// Summary of the earliest messages, sent to the LLM in their place
+    /// with the `summary` memory strategy (the messages are kept)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ConversationSummary>,
+
+    /// Tokens this conversation may use, overriding the global limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<u64>,
+
+    /// Token totals over all exchanges (absent before the first exchange)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenTotals>,
+}
+
+/// Accumulated token usage of a conversation
+///
+/// Counts are `u64` so long conversations can't overflow the per-reply
+/// `u32` counts they are summed from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenTotals {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl TokenTotals {
+    /// Add the usage of one reply
+    pub fn add(&mut self, usage: &Usage) {
+        self.prompt_tokens += u64::from(usage.prompt_tokens);
+        self.completion_tokens += u64::from(usage.completion_tokens);
+        self.total_tokens += u64::from(usage.total_tokens);
+    }
+}
+
+/// A conversation has used up its token budget
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetExceeded {
+    pub budget: u64,
+    pub used: u64,
+}
+
+/// Rolling summary of the start of a conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    /// Summary written by the LLM
+    pub content: String,
+    /// Messages from the start of the conversation it covers
+    pub messages: usize,
+    /// RFC 3339 timestamp of when it was written
+    pub updated_at: String,
+}
+
+/// Single message of a stored conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    pub role: String,
+    pub content: String,
+
+    /// RFC 3339 timestamp of when the message was sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+
+    /// Model that generated the message (assistant messages only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Token usage reported for the message (assistant messages only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+
+    /// Moderation rules that flagged or redacted the message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moderation: Vec<ModerationDecision>,
+}
+
+impl Conversation {
+    /// Empty conversation created now
+    pub fn new(id: &str) -> Self {
+        Conversation {
+            id: id.to_string(),
+            title: None,
+            created_at: Some(Utc::now().to_rfc3339()),
+            provider: None,
+            messages: Vec::new(),
+            summary: None,
+            token_budget: None,
+            usage: None,
+        }
+    }
+
+    /// Check that the conversation hasn't used up its token budget
+    ///
+    /// The conversation's own budget takes precedence over `global_budget`.
+    /// A conversation that has reached its budget exactly is exhausted.
+    pub fn check_budget(&self, global_budget: Option<u64>) -> Result<(), BudgetExceeded> {
+        let used = self.total_tokens();
+        match self.token_budget.or(global_budget) {
+            Some(budget) if used >= budget => Err(BudgetExceeded { budget, used }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Tokens used by all exchanges so far
+    pub fn total_tokens(&self) -> u64 {
+        self.usage.as_ref().map_or(0, |usage| usage.total_tokens)
+    }
+
+    /// Messages from the start covered by the summary (0 without one)
+    pub fn summarized_messages(&self) -> usize {
+        self.summary.as_ref().map_or(0, |summary| summary.messages)
+    }
+
+    /// Append the messages of one exchange and account for their usage
+    pub fn record_exchange(&mut self, messages: Vec<ConversationMessage>, provider: &str) {
+        let totals = self.usage.get_or_insert_with(TokenTotals::default);
+        for usage in messages.iter().filter_map(|message| message.usage.as_ref()) {
+            totals.add(usage);
+        }
+        self.messages.extend(messages);
+        self.provider = Some(provider.to_string());
+    }
+
+    /// Drop provider, model and usage information, keeping content and timestamps
+    pub fn without_metadata(mut self) -> Self {
+        self.provider = None;
+        self.usage = None;
+        for message in &mut self.messages {
+            message.model = None;
+            message.usage = None;
+        }
+        self
+    }
+}
+
+/// Load a conversation from storage
+///
+/// Returns `Ok(None)` if no conversation has been stored under this ID.
+pub async fn load_conversation(
+    storage: &SharedStorage,
+    conversation_id: &str,
+) -> Result<Option<Conversation>, ServiceError> {
+    read_conversation(&*storage.read().await, conversation_id).await
+}
+
+/// Append one exchange to a conversation, creating it if needed
+///
+/// The conversation is re-read and written back under the storage write
+/// lock, so the messages and the usage totals land in a single write and
+/// concurrent exchanges can't lose each other's updates.
+#[allow(dead_code)] // Used in tests and by embedders without token budgets
+pub async fn append_exchange(
+    storage: &SharedStorage,
+    conversation_id: &str,
+    messages: Vec<ConversationMessage>,
+    provider: &str,
+) -> Result<Conversation, ServiceError> {
+    let appended = append_exchange_if(storage, conversation_id, messages, provider, |_| {
+        Ok::<(), Infallible>(())
+    })
+    .await?;
+    match appended {
+        Ok(conversation) => Ok(conversation),
+        Err(never) => match never {},
+    }
+}
+
+/// `append_exchange`, unless the conversation has used up its token budget
+///
+/// The budget is checked under the same write lock as the append, so
+/// concurrent exchanges that all passed an earlier check can't overspend:
+/// once one of them reaches the budget, the others aren't stored.
+pub async fn append_exchange_within_budget(
+    storage: &SharedStorage,
+    conversation_id: &str,
+    messages: Vec<ConversationMessage>,
+    provider: &str,
+    global_budget: Option<u64>,
+) -> Result<Result<Conversation, BudgetExceeded>, ServiceError> {
+    append_exchange_if(
+        storage,
+        conversation_id,
+        messages,
+        provider,
+        |conversation| conversation.check_budget(global_budget),
+    )
+    .await
+}
+
+/// Append one exchange if `check` accepts the stored conversation
+async fn append_exchange_if<E>(
+    storage: &SharedStorage,
+    conversation_id: &str,
+    messages: Vec<ConversationMessage>,
+    provider: &str,
+    check: impl FnOnce(&Conversation) -> Result<(), E>,
+) -> Result<Result<Conversation, E>, ServiceError> {
+    let mut storage = storage.write().await;
+
+    let mut conversation = read_conversation(&*storage, conversation_id)
+        .await?
+        .unwrap_or_else(|| Conversation::new(conversation_id));
+    if let Err(rejected) = check(&conversation) {
+        return Ok(Err(rejected));
+    }
+    conversation.record_exchange(messages, provider);
+
+    let data = serde_json::to_vec(&conversation)
+        .map_err(|e| ServiceError::ExecutionError(format!("Failed to encode conversation: {e}")))?;
+    storage
+        .store(&conversation_key(conversation_id), &data)
+        .await?;
+
+    Ok(Ok(conversation))
+}
+
+/// Title a stored conversation unless it already has a title
+///
+/// Re-read under the storage write lock like `append_exchange`. Returns
+/// whether the title was set; a conversation that was deleted meanwhile
+/// isn't recreated.
+pub async fn set_title_if_missing(
+    storage: &SharedStorage,
+    conversation_id: &str,
+    title: &str,
+) -> Result<bool, ServiceError> {
+    let mut storage = storage.write().await;
+
+    let Some(mut conversation) = read_conversation(&*storage, conversation_id).await? else {
+        return Ok(false);
+    };
+    if conversation.title.is_some() {
+        return Ok(false);
+    }
+    conversation.title = Some(title.to_string());
+
+    let data = serde_json::to_vec(&conversation)
+        .map_err(|e| ServiceError::ExecutionError(format!("Failed to encode conversation: {e}")))?;
+    storage
+        .store(&conversation_key(conversation_id), &data)
+        .await?;
+
+    Ok(true)
+}
+
+/// Store `summary` unless the conversation has one covering as many messages
+///
+/// Re-read under the storage write lock like `append_exchange`. Returns
+/// whether it was stored; a conversation that was deleted meanwhile isn't
+/// recreated.
+pub async fn set_summary(
+    storage: &SharedStorage,
+    conversation_id: &str,
+    summary: ConversationSummary,
+) -> Result<bool, ServiceError> {
+    let mut storage = storage.write().await;
+
+    let Some(mut conversation) = read_conversation(&*storage, conversation_id).await? else {
+        return Ok(false);
+    };
+    if summary.messages > conversation.messages.len()
+        || conversation.summarized_messages() >= summary.messages
+    {
+        return Ok(false);
+    }
+    conversation.summary = Some(summary);
+
+    let data = serde_json::to_vec(&conversation)
+        .map_err(|e| ServiceError::ExecutionError(format!("Failed to encode conversation: {e}")))?;
+    storage
+        .store(&conversation_key(conversation_id), &data)
+        .await?;
+
+    Ok(true)
+}
+
+async fn read_conversation(
+    storage: &dyn StorageAdapter,
+    conversation_id: &str,
+) -> Result<Option<Conversation>, ServiceError> {
+    let key = conversation_key(conversation_id);
+
+    if !storage.exists(&key).await? {
+        return Ok(None);
+    }
+
+    let data = storage.retrieve(&key).await?;
+    let conversation = serde_json::from_slice(&data)
+        .map_err(|e| ServiceError::ExecutionError(format!("Corrupt conversation: {e}")))?;
+
+    Ok(Some(conversation))
+}
+
+/// Prefix of the storage keys holding conversations
+pub const CONVERSATION_KEY_PREFIX: &str = "conversation/";
+
+/// Check that a conversation ID is safe to use as part of a storage key
+pub fn is_valid_conversation_id(conversation_id: &str) -> bool {
+    !conversation_id.is_empty()
+        && conversation_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Storage key for a conversation
+pub fn conversation_key(conversation_id: &str) -> String {
+    format!("{}{}", CONVERSATION_KEY_PREFIX, conversation_id)
+}
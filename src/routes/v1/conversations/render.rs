@@ -0,0 +1,260 @@
+use super::model::{Conversation, ConversationMessage};
+
+/// Maximum length of the title part of an export filename
+const MAX_FILENAME_STEM_LEN: usize = 64;
+
+/// Render a conversation as a Markdown transcript, one chunk per message
+///
+/// Each message gets a `###` header with its role, timestamp and model.
+/// Message content is copied verbatim, except that an unclosed code fence
+/// is closed so it can't swallow the following messages.
+pub fn markdown(conversation: Conversation) -> impl Iterator<Item = String> + Send + 'static {
+    let mut header = format!("# {}\n", title(&conversation));
+    if let Some(created_at) = &conversation.created_at {
+        header.push_str(&format!("\n_Created {}_\n", created_at));
+    }
+
+    std::iter::once(header).chain(conversation.messages.into_iter().map(markdown_message))
+}
+
+/// Render a conversation as plain text, one chunk per message
+pub fn text(conversation: Conversation) -> impl Iterator<Item = String> + Send + 'static {
+    let mut header = format!("{}\n", title(&conversation));
+    if let Some(created_at) = &conversation.created_at {
+        header.push_str(&format!("Created {}\n", created_at));
+    }
+
+    std::iter::once(header).chain(conversation.messages.into_iter().map(text_message))
+}
+
+/// Attachment filename derived from the title, falling back to the ID
+pub fn filename(conversation: &Conversation, extension: &str) -> String {
+    let slug = conversation
+        .title
+        .as_deref()
+        .map(slugify)
+        .filter(|slug| !slug.is_empty())
+        .unwrap_or_else(|| conversation.id.clone());
+
+    format!("{}.{}", slug, extension)
+}
+
+fn markdown_message(message: ConversationMessage) -> String {
+    let mut section = format!("\n### {}", role_label(&message.role));
+    for detail in [&message.timestamp, &message.model].into_iter().flatten() {
+        section.push_str(" · ");
+        section.push_str(detail);
+    }
+
+    section.push_str("\n\n");
+    section.push_str(message.content.trim_end());
+    if has_unclosed_fence(&message.content) {
+        section.push_str("\n```");
+    }
+    section.push('\n');
+
+    section
+}
+
+fn text_message(message: ConversationMessage) -> String {
+    let mut section = String::from("\n");
+    if let Some(timestamp) = &message.timestamp {
+        section.push_str(&format!("[{}] ", timestamp));
+    }
+    section.push_str(&role_label(&message.role));
+    if let Some(model) = &message.model {
+        section.push_str(&format!(" ({})", model));
+    }
+
+    section.push_str(":\n");
+    section.push_str(message.content.trim_end());
+    section.push('\n');
+
+    section
+}
+
+/// Title shown at the top of an export
+fn title(conversation: &Conversation) -> String {
+    match conversation.title.as_deref().map(str::trim) {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => format!("Conversation {}", conversation.id),
+    }
+}
+
+/// Capitalize a role for display (`user` becomes `User`)
+fn role_label(role: &str) -> String {
+    let mut chars = role.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Unknown".to_string(),
+    }
+}
+
+/// Check whether content opens more ``` fences than it closes
+fn has_unclosed_fence(content: &str) -> bool {
+    let fences = content
+        .lines()
+        .filter(|line| line.trim_start().starts_with("```"))
+        .count();
+
+    fences % 2 == 1
+}
+
+/// Lowercase ASCII slug safe for use in a filename and a header value
+fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.truncate(MAX_FILENAME_STEM_LEN);
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::v1::message::response::Usage;
+
+    fn message(role: &str, content: &str) -> ConversationMessage {
+        ConversationMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: None,
+            model: None,
+            usage: None,
+            moderation: Vec::new(),
+        }
+    }
+
+    fn fixture() -> Conversation {
+        Conversation {
+            id: "conv-1".to_string(),
+            title: Some("Grüße & Rust: async 🦀".to_string()),
+            created_at: Some("2025-01-02T03:04:05Z".to_string()),
+            provider: Some("ollama".to_string()),
+            messages: vec![
+                ConversationMessage {
+                    timestamp: Some("2025-01-02T03:04:05Z".to_string()),
+                    ..message("user", "Wie schreibe ich eine Schleife? 日本語もOK")
+                },
+                ConversationMessage {
+                    timestamp: Some("2025-01-02T03:04:09Z".to_string()),
+                    model: Some("llama3.2".to_string()),
+                    usage: Some(Usage {
+                        prompt_tokens: 12,
+                        completion_tokens: 30,
+                        total_tokens: 42,
+                    }),
+                    ..message(
+                        "assistant",
+                        "So:\n\n```rust\nfor i in 0..3 {\n    println!(\"{i}\");\n}\n```\n",
+                    )
+                },
+            ],
+            ..Conversation::new("conv-1")
+        }
+    }
+
+    fn render(chunks: impl Iterator<Item = String>) -> String {
+        chunks.collect()
+    }
+
+    #[test]
+    fn test_markdown_transcript() {
+        let output = render(markdown(fixture()));
+
+        assert!(output.starts_with("# Grüße & Rust: async 🦀\n"));
+        assert!(output.contains("_Created 2025-01-02T03:04:05Z_"));
+        assert!(output.contains("### User · 2025-01-02T03:04:05Z\n\nWie schreibe ich"));
+        assert!(output.contains("日本語もOK"));
+        assert!(output.contains("### Assistant · 2025-01-02T03:04:09Z · llama3.2\n"));
+        // Code blocks are kept intact
+        assert!(output.contains("```rust\nfor i in 0..3 {\n    println!(\"{i}\");\n}\n```\n"));
+    }
+
+    #[test]
+    fn test_markdown_one_chunk_per_message() {
+        assert_eq!(markdown(fixture()).count(), 3);
+    }
+
+    #[test]
+    fn test_markdown_closes_unterminated_fence() {
+        let mut conversation = fixture();
+        conversation.messages = vec![
+            message("assistant", "```python\nprint('cut off"),
+            message("user", "Continue"),
+        ];
+
+        let output = render(markdown(conversation));
+
+        assert!(output.contains("print('cut off\n```\n\n### User"));
+        assert!(!has_unclosed_fence(&output));
+    }
+
+    #[test]
+    fn test_text_transcript() {
+        let output = render(text(fixture()));
+
+        assert!(output.starts_with("Grüße & Rust: async 🦀\nCreated 2025-01-02T03:04:05Z\n"));
+        assert!(output.contains("\n[2025-01-02T03:04:05Z] User:\nWie schreibe ich"));
+        assert!(output.contains("\n[2025-01-02T03:04:09Z] Assistant (llama3.2):\nSo:\n"));
+    }
+
+    #[test]
+    fn test_long_messages_are_not_truncated() {
+        let long = "lorem ipsum ".repeat(50_000);
+        let mut conversation = fixture();
+        conversation.messages = vec![message("user", &long)];
+
+        assert!(render(markdown(conversation.clone())).contains(long.trim_end()));
+        assert!(render(text(conversation)).contains(long.trim_end()));
+    }
+
+    #[test]
+    fn test_without_metadata_strips_provider_info() {
+        let output = render(markdown(fixture().without_metadata()));
+
+        assert!(!output.contains("llama3.2"));
+        assert!(output.contains("### Assistant · 2025-01-02T03:04:09Z\n"));
+    }
+
+    #[test]
+    fn test_untitled_conversation() {
+        let mut conversation = fixture();
+        conversation.title = None;
+        conversation.messages.clear();
+
+        assert_eq!(
+            render(text(conversation.clone())).lines().next(),
+            Some("Conversation conv-1")
+        );
+        assert_eq!(filename(&conversation, "md"), "conv-1.md");
+    }
+
+    #[test]
+    fn test_filename_from_title() {
+        assert_eq!(filename(&fixture(), "md"), "gr-e-rust-async.md");
+
+        let mut conversation = fixture();
+        conversation.title = Some("🦀🦀".to_string());
+        assert_eq!(filename(&conversation, "txt"), "conv-1.txt");
+
+        conversation.title = Some("x".repeat(200));
+        assert_eq!(
+            filename(&conversation, "json").len(),
+            MAX_FILENAME_STEM_LEN + 5
+        );
+    }
+
+    #[test]
+    fn test_role_label() {
+        assert_eq!(role_label("user"), "User");
+        assert_eq!(role_label("tool"), "Tool");
+        assert_eq!(role_label(""), "Unknown");
+    }
+}
@@ -0,0 +1,527 @@
+use super::model::{
+    CONVERSATION_KEY_PREFIX, Conversation, ConversationMessage, is_valid_conversation_id,
+};
+use crate::config::defaults::DEFAULT_MAX_SEARCH_SCANNED;
+use crate::routes::error::storage_error;
+use crate::server::AppState;
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Matches returned when the request sets no `limit`
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Most matches returned per search
+pub const MAX_SEARCH_LIMIT: usize = 100;
+
+/// Characters of context shown on each side of the first match
+pub const SNIPPET_CONTEXT_CHARS: usize = 60;
+
+/// Conversation keys listed at a time while scanning
+const SCAN_PAGE_SIZE: usize = 100;
+
+/// Query parameters of a conversation search
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    /// Only messages sent after this RFC 3339 time
+    pub after: Option<DateTime<FixedOffset>>,
+    /// Only messages sent before this RFC 3339 time
+    pub before: Option<DateTime<FixedOffset>>,
+    pub limit: Option<usize>,
+    /// Terms that must all occur in a message, separated by spaces
+    pub q: String,
+    /// Only messages with this role, e.g. "user"
+    pub role: Option<String>,
+}
+
+/// Messages matching a search
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    pub results: Vec<SearchMatch>,
+    /// Conversations read
+    pub scanned: usize,
+    /// Whether the search stopped at `limit` or the scan limit, so more
+    /// matches may exist
+    pub truncated: bool,
+}
+
+/// Message containing every search term
+#[derive(Debug, Serialize)]
+pub struct SearchMatch {
+    pub conversation_id: String,
+    /// RFC 3339 timestamp of the conversation's first message
+    pub created_at: Option<String>,
+    /// Position of the message in the conversation
+    pub message_index: usize,
+    pub role: String,
+    /// Text around the first match, with matched terms in `**`
+    pub snippet: String,
+    /// RFC 3339 timestamp of the message
+    pub timestamp: Option<String>,
+}
+
+/// Find stored messages containing all terms of `q`
+///
+/// Conversations are read one page of keys at a time, so memory use
+/// doesn't grow with the store. The storage lock is only held for each
+/// read, so writes aren't blocked for the length of a scan. At most `[limits] max_search_scanned`
+/// conversations are read per request; corrupt ones are skipped.
+pub async fn search_conversations(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchResults>, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if limit == 0 || limit > MAX_SEARCH_LIMIT {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let filter = SearchFilter::new(&query).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let storage = state
+        .storage
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    let max_scanned = state
+        .max_search_scanned
+        .unwrap_or(DEFAULT_MAX_SEARCH_SCANNED);
+
+    let mut found = SearchResults {
+        results: Vec::new(),
+        scanned: 0,
+        truncated: false,
+    };
+    let mut cursor = None;
+    loop {
+        let page = storage
+            .read()
+            .await
+            .list_keys_paginated(
+                Some(CONVERSATION_KEY_PREFIX),
+                cursor.as_deref(),
+                SCAN_PAGE_SIZE,
+            )
+            .await
+            .map_err(storage_error)?;
+
+        for key in &page.keys {
+            let Some(conversation_id) = key
+                .strip_prefix(CONVERSATION_KEY_PREFIX)
+                .filter(|id| is_valid_conversation_id(id))
+            else {
+                continue;
+            };
+            if found.scanned == max_scanned || found.results.len() == limit {
+                found.truncated = true;
+                return Ok(Json(found));
+            }
+            found.scanned += 1;
+
+            let data = storage
+                .read()
+                .await
+                .retrieve(key)
+                .await
+                .map_err(storage_error)?;
+            let conversation: Conversation = match serde_json::from_slice(&data) {
+                Ok(conversation) => conversation,
+                Err(e) => {
+                    tracing::warn!(
+                        "Search skipped corrupt conversation {}: {}",
+                        conversation_id,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            found.results.extend(filter.matches(&conversation));
+            if found.results.len() > limit {
+                found.results.truncate(limit);
+                found.truncated = true;
+                return Ok(Json(found));
+            }
+        }
+
+        match page.next_cursor {
+            Some(next_cursor) => cursor = Some(next_cursor),
+            None => return Ok(Json(found)),
+        }
+    }
+}
+
+/// Which messages a search selects
+struct SearchFilter {
+    after: Option<DateTime<FixedOffset>>,
+    before: Option<DateTime<FixedOffset>>,
+    role: Option<String>,
+    /// Lowercased terms
+    terms: Vec<Vec<char>>,
+}
+
+impl SearchFilter {
+    /// Filter for `query` (None if it has no terms)
+    fn new(query: &SearchQuery) -> Option<Self> {
+        let terms: Vec<Vec<char>> = query
+            .q
+            .split_whitespace()
+            .map(|term| term.chars().map(fold_case).collect())
+            .collect();
+
+        (!terms.is_empty()).then(|| SearchFilter {
+            after: query.after,
+            before: query.before,
+            role: query.role.clone(),
+            terms,
+        })
+    }
+
+    /// Matching messages of `conversation`, in order
+    fn matches(&self, conversation: &Conversation) -> Vec<SearchMatch> {
+        conversation
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| self.selects(message))
+            .filter_map(|(message_index, message)| {
+                Some(SearchMatch {
+                    conversation_id: conversation.id.clone(),
+                    created_at: conversation.created_at.clone(),
+                    message_index,
+                    role: message.role.clone(),
+                    snippet: self.snippet(&message.content)?,
+                    timestamp: message.timestamp.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the role and time of `message` are within the filter
+    ///
+    /// Messages without a valid timestamp are outside any time window.
+    fn selects(&self, message: &ConversationMessage) -> bool {
+        if self
+            .role
+            .as_deref()
+            .is_some_and(|role| !role.eq_ignore_ascii_case(&message.role))
+        {
+            return false;
+        }
+        if self.after.is_none() && self.before.is_none() {
+            return true;
+        }
+
+        let Some(sent) = message
+            .timestamp
+            .as_deref()
+            .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+        else {
+            return false;
+        };
+        self.after.is_none_or(|after| sent > after)
+            && self.before.is_none_or(|before| sent < before)
+    }
+
+    /// Snippet around the first match, if every term occurs in `content`
+    ///
+    /// Matches within the snippet are wrapped in `**`; cut-off text is
+    /// marked with `…`.
+    fn snippet(&self, content: &str) -> Option<String> {
+        let chars: Vec<char> = content.chars().collect();
+        let folded: Vec<char> = chars.iter().copied().map(fold_case).collect();
+
+        let mut matches = Vec::new();
+        for term in &self.terms {
+            let term_matches = find_all(&folded, term);
+            if term_matches.is_empty() {
+                return None;
+            }
+            matches.extend(term_matches);
+        }
+        matches.sort_by_key(|range| range.start);
+
+        let start = matches[0].start.saturating_sub(SNIPPET_CONTEXT_CHARS);
+        let end = (matches[0].end + SNIPPET_CONTEXT_CHARS).min(chars.len());
+
+        let mut snippet = String::new();
+        if start > 0 {
+            snippet.push('…');
+        }
+        let mut position = start;
+        for range in matches {
+            // Overlaps with an earlier match or lies past the snippet
+            let (match_start, match_end) = (range.start.max(position), range.end.min(end));
+            if match_start >= match_end {
+                continue;
+            }
+            snippet.extend(&chars[position..match_start]);
+            snippet.push_str("**");
+            snippet.extend(&chars[match_start..match_end]);
+            snippet.push_str("**");
+            position = match_end;
+        }
+        snippet.extend(&chars[position..end]);
+        if end < chars.len() {
+            snippet.push('…');
+        }
+
+        Some(snippet)
+    }
+}
+
+/// Lowercase `c`, keeping one character so positions stay comparable
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Positions of all occurrences of `needle` in `haystack`
+fn find_all(haystack: &[char], needle: &[char]) -> Vec<Range<usize>> {
+    haystack
+        .windows(needle.len())
+        .enumerate()
+        .filter(|(_, window)| *window == needle)
+        .map(|(start, _)| start..start + needle.len())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::MemoryStorage;
+    use crate::routes::v1::conversations::model::conversation_key;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    /// 30 conversations, one a day from 2025-01-01; every fifth is about Lisbon
+    fn state(max_search_scanned: Option<usize>) -> AppState {
+        let mut storage = MemoryStorage::default();
+        for i in 0..30 {
+            let id = format!("chat-{:02}", i);
+            let (question, answer) = if i % 5 == 0 {
+                (
+                    "Planning a trip to Lisbon, any tips?",
+                    "Lisbon is lovely in spring.",
+                )
+            } else {
+                ("How do I sort a vector in Rust?", "Use sort_unstable.")
+            };
+            let day = format!("2025-01-{:02}", i + 1);
+            let message = |role: &str, content: &str, time: &str| ConversationMessage {
+                role: role.to_string(),
+                content: content.to_string(),
+                timestamp: Some(format!("{}T{}Z", day, time)),
+                model: None,
+                usage: None,
+                moderation: Vec::new(),
+            };
+            let conversation = Conversation {
+                created_at: Some(format!("{}T10:00:00Z", day)),
+                messages: vec![
+                    message("user", question, "10:00:00"),
+                    message("assistant", answer, "10:00:05"),
+                ],
+                ..Conversation::new(&id)
+            };
+            storage.entries.insert(
+                conversation_key(&id),
+                serde_json::to_vec(&conversation).unwrap(),
+            );
+        }
+        storage
+            .entries
+            .insert(conversation_key("chat-99"), b"not json".to_vec());
+        storage
+            .entries
+            .insert("sender/default/profile".to_string(), b"{}".to_vec());
+
+        AppState {
+            max_search_scanned,
+            ..AppState::with_storage(storage)
+        }
+    }
+
+    async fn search(state: &AppState, query: &str) -> (StatusCode, serde_json::Value) {
+        let response = crate::routes::v1::search::router()
+            .with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/conversations?{}", query))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+
+        (status, body)
+    }
+
+    /// `conversation_id:message_index` of each result
+    fn found(body: &serde_json::Value) -> Vec<String> {
+        body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| format!("{}:{}", result["conversation_id"], result["message_index"]))
+            .map(|found| found.replace('"', ""))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_search_matches_all_terms_ignoring_case() {
+        let (status, body) = search(&state(None), "q=LISBON+trip").await;
+
+        assert_eq!(status, StatusCode::OK);
+        // Answers mention Lisbon but not the trip
+        assert_eq!(
+            found(&body),
+            [
+                "chat-00:0",
+                "chat-05:0",
+                "chat-10:0",
+                "chat-15:0",
+                "chat-20:0",
+                "chat-25:0"
+            ]
+        );
+        assert_eq!(body["scanned"], 31);
+        assert_eq!(body["truncated"], false);
+
+        let first = &body["results"][0];
+        assert_eq!(
+            first["snippet"],
+            "Planning a **trip** to **Lisbon**, any tips?"
+        );
+        assert_eq!(first["role"], "user");
+        assert_eq!(first["timestamp"], "2025-01-01T10:00:00Z");
+        assert_eq!(first["created_at"], "2025-01-01T10:00:00Z");
+    }
+
+    #[tokio::test]
+    async fn test_search_filters_by_role() {
+        let (status, body) = search(&state(None), "q=lisbon&role=assistant").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(found(&body).len(), 6);
+        assert!(found(&body).iter().all(|found| found.ends_with(":1")));
+    }
+
+    #[tokio::test]
+    async fn test_search_filters_by_date() {
+        let (status, body) = search(
+            &state(None),
+            "q=lisbon&after=2025-01-06T00:00:00Z&before=2025-01-16T00:00:00Z",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            found(&body),
+            ["chat-05:0", "chat-05:1", "chat-10:0", "chat-10:1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_stops_at_max_scanned() {
+        let (status, body) = search(&state(Some(10)), "q=lisbon").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            found(&body),
+            ["chat-00:0", "chat-00:1", "chat-05:0", "chat-05:1"]
+        );
+        assert_eq!(body["scanned"], 10);
+        assert_eq!(body["truncated"], true);
+    }
+
+    #[tokio::test]
+    async fn test_search_stops_at_limit() {
+        let (status, body) = search(&state(None), "q=lisbon&limit=3").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(found(&body), ["chat-00:0", "chat-00:1", "chat-05:0"]);
+        assert_eq!(body["truncated"], true);
+    }
+
+    #[tokio::test]
+    async fn test_search_rejects_bad_parameters() {
+        let state = state(None);
+
+        for query in [
+            "",
+            "q=+",
+            "q=rust&limit=0",
+            "q=rust&limit=101",
+            "q=rust&after=yesterday",
+        ] {
+            assert_eq!(
+                search(&state, query).await.0,
+                StatusCode::BAD_REQUEST,
+                "{}",
+                query
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_doesnt_shadow_a_conversation_named_search() {
+        let mut storage = MemoryStorage::default();
+        storage.entries.insert(
+            conversation_key("search"),
+            serde_json::to_vec(&Conversation::new("search")).unwrap(),
+        );
+        let state = AppState::with_storage(storage);
+
+        let response = super::super::router()
+            .with_state(state)
+            .oneshot(
+                Request::builder()
+                    .uri("/search")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["id"], "search");
+    }
+
+    #[tokio::test]
+    async fn test_search_needs_storage() {
+        assert_eq!(
+            search(&AppState::default(), "q=rust").await.0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[test]
+    fn test_snippet_is_cut_around_first_match() {
+        let query = SearchQuery {
+            after: None,
+            before: None,
+            limit: None,
+            q: "needle".to_string(),
+            role: None,
+        };
+        let filter = SearchFilter::new(&query).unwrap();
+        let content = format!("{}Needle{}needle", "a".repeat(100), "b".repeat(100));
+
+        let snippet = filter.snippet(&content).unwrap();
+
+        assert_eq!(
+            snippet,
+            format!("…{}**Needle**{}…", "a".repeat(60), "b".repeat(60))
+        );
+        assert_eq!(filter.snippet("no match here"), None);
+    }
+}
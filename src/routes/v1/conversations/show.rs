@@ -0,0 +1,95 @@
+use super::model::{Conversation, is_valid_conversation_id, load_conversation};
+use crate::routes::error::storage_error;
+use crate::server::AppState;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+
+/// Get a stored conversation, including its accumulated token usage
+pub async fn get_conversation(
+    State(state): State<AppState>,
+    Path(conversation_id): Path<String>,
+) -> Result<Json<Conversation>, StatusCode> {
+    if !is_valid_conversation_id(&conversation_id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let storage = state
+        .storage
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    match load_conversation(storage, &conversation_id).await {
+        Ok(Some(conversation)) => Ok(Json(conversation)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => Err(storage_error(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::MemoryStorage;
+    use crate::routes::v1::conversations::model::{ConversationMessage, conversation_key};
+    use crate::routes::v1::message::response::Usage;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    async fn get(uri: &str) -> (StatusCode, serde_json::Value) {
+        let mut conversation = Conversation::new("abc-123");
+        conversation.record_exchange(
+            vec![ConversationMessage {
+                role: "assistant".to_string(),
+                content: "Lisbon.".to_string(),
+                timestamp: None,
+                model: Some("llama3.2".to_string()),
+                usage: Some(Usage {
+                    prompt_tokens: 5,
+                    completion_tokens: 2,
+                    total_tokens: 7,
+                }),
+                moderation: Vec::new(),
+            }],
+            "ollama",
+        );
+
+        let mut storage = MemoryStorage::default();
+        storage.entries.insert(
+            conversation_key("abc-123"),
+            serde_json::to_vec(&conversation).unwrap(),
+        );
+
+        let response = super::super::router()
+            .with_state(AppState::with_storage(storage))
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn test_get_conversation_includes_usage() {
+        let (status, body) = get("/abc-123").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], "abc-123");
+        assert_eq!(body["messages"][0]["content"], "Lisbon.");
+        assert_eq!(body["usage"]["prompt_tokens"], 5);
+        assert_eq!(body["usage"]["completion_tokens"], 2);
+        assert_eq!(body["usage"]["total_tokens"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_get_unknown_conversation() {
+        assert_eq!(get("/missing").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(get("/bad%20id").await.0, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::adapter::traits::ImageSize;
+use crate::routes::error::json_rejection_response;
+use crate::routes::fallback::error_response;
+use crate::server::AppState;
+use axum::{
+    Json,
+    extract::{State, rejection::JsonRejection},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+/// Longest prompt accepted, in characters
+pub const MAX_IMAGE_PROMPT_CHARS: usize = 4000;
+
+/// Smallest and largest width or height accepted, in pixels
+pub const IMAGE_SIDE_RANGE: std::ops::RangeInclusive<u32> = 64..=4096;
+
+/// Body of an image generation request
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImageGenerationRequest {
+    /// Description of the image
+    pub prompt: String,
+    /// `<width>x<height>` in pixels (default: 1024x1024)
+    #[serde(default)]
+    pub size: Option<String>,
+}
+
+impl ImageGenerationRequest {
+    /// The trimmed prompt and the parsed size, checked against the limits
+    #[allow(clippy::result_large_err)] // Only built for rejected requests
+    fn normalize(&self) -> Result<(&str, ImageSize), Response> {
+        let prompt = self.prompt.trim();
+        if prompt.is_empty() {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "prompt must not be empty",
+            ));
+        }
+        if prompt.chars().count() > MAX_IMAGE_PROMPT_CHARS {
+            return Err(error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                &format!("prompt is limited to {} characters", MAX_IMAGE_PROMPT_CHARS),
+            ));
+        }
+
+        let size = match &self.size {
+            Some(size) => size
+                .parse::<ImageSize>()
+                .map_err(|e| error_response(StatusCode::BAD_REQUEST, &e))?,
+            None => ImageSize::default(),
+        };
+        if !IMAGE_SIDE_RANGE.contains(&size.width) || !IMAGE_SIDE_RANGE.contains(&size.height) {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                &format!(
+                    "Image size {} out of range: width and height must be {} to {} pixels",
+                    size,
+                    IMAGE_SIDE_RANGE.start(),
+                    IMAGE_SIDE_RANGE.end()
+                ),
+            ));
+        }
+
+        Ok((prompt, size))
+    }
+}
+
+/// Generate an image with the image adapter
+///
+/// The image is returned as it came from the adapter, with its MIME type as
+/// `Content-Type`.
+pub async fn generate_image(
+    State(state): State<AppState>,
+    request: Result<Json<ImageGenerationRequest>, JsonRejection>,
+) -> Result<Response, Response> {
+    let Json(request) = request.map_err(json_rejection_response)?;
+    let (prompt, size) = request.normalize()?;
+
+    let image = state.image.as_ref().ok_or_else(|| {
+        error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No image adapter is configured",
+        )
+    })?;
+    let generated = image
+        .write()
+        .await
+        .generate(prompt, size)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let content_type = Some(&generated.mime_type)
+        .filter(|mime_type| mime_type.starts_with("image/") && !generated.data.is_empty())
+        .and_then(|mime_type| HeaderValue::from_str(mime_type).ok())
+        .ok_or_else(|| {
+            tracing::error!(
+                "Image adapter returned {} bytes of {:?}",
+                generated.data.len(),
+                generated.mime_type
+            );
+            error_response(
+                StatusCode::BAD_GATEWAY,
+                "Invalid image from the image adapter",
+            )
+        })?;
+
+    Ok(([(header::CONTENT_TYPE, content_type)], generated.data).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::traits::{AdapterService, GeneratedImage, ImageAdapter, ServiceError};
+    use async_trait::async_trait;
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    /// Image adapter recording what it's asked for and answering with `image`
+    struct RecordingImage {
+        image: GeneratedImage,
+        received: Arc<Mutex<Option<(String, ImageSize)>>>,
+    }
+
+    impl RecordingImage {
+        fn new(data: &[u8], mime_type: &str) -> Self {
+            RecordingImage {
+                image: GeneratedImage {
+                    data: data.to_vec(),
+                    mime_type: mime_type.to_string(),
+                },
+                received: Arc::default(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AdapterService for RecordingImage {
+        fn service_name(&self) -> &'static str {
+            "image"
+        }
+
+        fn provider_name(&self) -> &str {
+            "recording"
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ImageAdapter for RecordingImage {
+        async fn generate(
+            &mut self,
+            prompt: &str,
+            size: ImageSize,
+        ) -> Result<GeneratedImage, ServiceError> {
+            if prompt == "fail" {
+                return Err(ServiceError::ProviderError {
+                    status: 500,
+                    message: "generation failed".to_string(),
+                    retry_after_secs: None,
+                });
+            }
+            *self.received.lock().unwrap() = Some((prompt.to_string(), size));
+            Ok(self.image.clone())
+        }
+    }
+
+    fn app(state: AppState) -> Router {
+        super::super::router().with_state(state)
+    }
+
+    fn image_request(body: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/generations")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn normalize(body: &str) -> Result<(String, ImageSize), StatusCode> {
+        let request: ImageGenerationRequest = serde_json::from_str(body).unwrap();
+        request
+            .normalize()
+            .map(|(prompt, size)| (prompt.to_string(), size))
+            .map_err(|response| response.status())
+    }
+
+    #[test]
+    fn test_request_normalization() {
+        let size = |width, height| ImageSize { width, height };
+
+        assert_eq!(
+            normalize(r#"{"prompt":"  A red fox \n"}"#),
+            Ok(("A red fox".to_string(), size(1024, 1024)))
+        );
+        assert_eq!(
+            normalize(r#"{"prompt":"A red fox","size":" 512X768 "}"#),
+            Ok(("A red fox".to_string(), size(512, 768)))
+        );
+        assert_eq!(
+            normalize(r#"{"prompt":"A red fox","size":"64x4096"}"#),
+            Ok(("A red fox".to_string(), size(64, 4096)))
+        );
+
+        let long_prompt = format!(
+            r#"{{"prompt":"{}"}}"#,
+            "a".repeat(MAX_IMAGE_PROMPT_CHARS + 1)
+        );
+        for (body, status) in [
+            (r#"{"prompt":"  "}"#, StatusCode::BAD_REQUEST),
+            (long_prompt.as_str(), StatusCode::PAYLOAD_TOO_LARGE),
+            (
+                r#"{"prompt":"fox","size":"large"}"#,
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                r#"{"prompt":"fox","size":"1024x"}"#,
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                r#"{"prompt":"fox","size":"0x512"}"#,
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                r#"{"prompt":"fox","size":"32x512"}"#,
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                r#"{"prompt":"fox","size":"512x8192"}"#,
+                StatusCode::BAD_REQUEST,
+            ),
+        ] {
+            assert_eq!(normalize(body), Err(status), "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_image_generation_returns_image() {
+        let image = RecordingImage::new(b"\x89PNG image", "image/png");
+        let received = image.received.clone();
+
+        let response = app(AppState::with_image(image))
+            .oneshot(image_request(
+                r#"{"prompt":" A red fox ","size":"512x512"}"#,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "image/png");
+        let data = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&data[..], b"\x89PNG image");
+        assert_eq!(
+            *received.lock().unwrap(),
+            Some((
+                "A red fox".to_string(),
+                ImageSize {
+                    width: 512,
+                    height: 512
+                }
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_image_generation_rejects_invalid_requests() {
+        for (body, status) in [
+            (r#"{"prompt":""}"#, StatusCode::BAD_REQUEST),
+            (r#"{"size":"512x512"}"#, StatusCode::BAD_REQUEST),
+            (r#"{"prompt":"fox","n":2}"#, StatusCode::BAD_REQUEST),
+            (r#"{"prompt":"fox""#, StatusCode::BAD_REQUEST),
+        ] {
+            let image = RecordingImage::new(b"png", "image/png");
+            let received = image.received.clone();
+            let response = app(AppState::with_image(image))
+                .oneshot(image_request(body))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), status, "{}", body);
+            assert!(received.lock().unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_image_generation_errors() {
+        async fn status_and_body(state: AppState, prompt: &str) -> (StatusCode, serde_json::Value) {
+            let response = app(state)
+                .oneshot(image_request(&format!(r#"{{"prompt":"{prompt}"}}"#)))
+                .await
+                .unwrap();
+            let status = response.status();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice(&bytes).unwrap())
+        }
+
+        // Without an image adapter
+        let (status, _) = status_and_body(AppState::default(), "fox").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        // The adapter failing
+        let state = AppState::with_image(RecordingImage::new(b"png", "image/png"));
+        let (status, body) = status_and_body(state, "fail").await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert_eq!(body["error_type"], "provider_error");
+
+        // The adapter answering with something that isn't an image
+        for (data, mime_type) in [
+            (&b"{}"[..], "application/json"),
+            (b"", "image/png"),
+            (b"png", "image/png\n"),
+        ] {
+            let state = AppState::with_image(RecordingImage::new(data, mime_type));
+            let (status, _) = status_and_body(state, "fox").await;
+            assert_eq!(status, StatusCode::BAD_GATEWAY, "{:?}", mime_type);
+        }
+    }
+}
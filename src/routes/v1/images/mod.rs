@@ -0,0 +1,9 @@
+pub mod generate;
+
+use crate::server::AppState;
+use axum::{Router, routing::post};
+
+/// Build the images router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/generations", post(generate::generate_image))
+}
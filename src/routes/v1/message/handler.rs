@@ -1,40 +1,2179 @@
 use axum::{
-    extract::{Json, Path},
-    http::StatusCode,
+    extract::{Json, Path, State, rejection::JsonRejection},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
     response::{IntoResponse, Json as ResponseJson, Response},
 };
 use chrono::Utc;
+use std::time::Instant;
+use tokio::sync::mpsc;
 
 use super::{
-    request::{Message, MessageRequest},
-    response::{MessageResponse, Usage},
+    request::{Message, MessageContent, MessageRequest},
+    response::{MessageErrorResponse, MessageResponse, StreamEnd, StreamEvent, Usage},
+    stream::{StreamFormat, events, stream_response},
 };
+use crate::adapter::manifest::{CAPABILITY_IMAGES, CAPABILITY_STREAMING};
+use crate::adapter::traits::{
+    ChatMessage, Completion, ContentPart, Finish, GenerationOptions, LlmAdapter, ServiceError,
+};
+use crate::config::defaults::{DEFAULT_MAX_IMAGE_BYTES, DEFAULT_STREAM_BUFFER};
+use crate::config::schema::ModerationDirection;
+use crate::routes::error::JsonBodyError;
+use crate::routes::v1::conversations::model::{
+    BudgetExceeded, Conversation, ConversationMessage, append_exchange_within_budget,
+    is_valid_conversation_id, load_conversation,
+};
+use crate::routes::v1::sender::profile::{
+    DEFAULT_SENDER_ID, SenderProfile, is_valid_sender_id, load_profile,
+};
+use crate::server::jobs::webhook::{self, MESSAGE_COMPLETED, MessageCompleted, WEBHOOK_JOB};
+use crate::server::jobs::{JobQueue, summary, title};
+use crate::server::memory::{self, needs_summary};
+use crate::server::moderation::{Moderation, ModerationDecision};
+use crate::server::usage_log::{UsageLog, UsageRecord, UsageStatus};
+use crate::server::{AppState, cancellation::cancellable, state::SharedStorage};
+
+/// Reply used when no LLM adapter is loaded
+const PLACEHOLDER_REPLY: &str =
+    "This is a placeholder response. The message handler is not yet implemented.";
+
+/// Model reported alongside `PLACEHOLDER_REPLY`
+const PLACEHOLDER_MODEL: &str = "placeholder-model";
+
+/// Header carrying the client's ID for a request
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Header reporting the WASM fuel the reply took, with `[server.debug] expose_fuel`
+pub const FUEL_HEADER: &str = "x-adapter-fuel-consumed";
+
+/// Model recorded in the usage log when the adapter uses the provider default
+const DEFAULT_MODEL: &str = "default";
+
+/// Error type of messages and replies blocked by `[moderation]`
+const CONTENT_POLICY_ERROR: &str = "content_policy";
 
-/// Placeholder handler for sending messages to recipients
+/// Handler for sending messages to recipients
+///
+/// The work runs inside `cancellable`, so a client disconnect drops any
+/// in-flight adapter call instead of waiting for it to finish.
 pub async fn send_message(
-    Path(_recipient_id): Path<String>,
-    Json(_request): Json<MessageRequest>,
-) -> Result<Response, StatusCode> {
-    // Create a placeholder response message
-    let response_message = Message {
+    State(state): State<AppState>,
+    Path(recipient_id): Path<String>,
+    headers: HeaderMap,
+    request: Result<Json<MessageRequest>, JsonRejection>,
+) -> Result<Response, Response> {
+    let Json(request) = request.map_err(rejection_response)?;
+    let call = LlmCall::new(&headers, &request, recipient_id);
+    // Stored exchanges need the whole reply and its usage up front, and
+    // moderated replies must be checked before any of them is sent
+    let buffered = request.conversation_id.is_some()
+        || state
+            .moderation
+            .as_ref()
+            .is_some_and(Moderation::checks_output);
+    let stream_format = (request.stream && !buffered)
+        .then(|| StreamFormat::from_accept(&headers))
+        .flatten();
+
+    cancellable(
+        "send_message",
+        process_message(state, request, call, stream_format),
+    )
+    .await
+}
+
+/// Report request bodies that aren't JSON or don't fit `MessageRequest`
+///
+/// Out-of-range values such as a negative `max_completion_tokens` fail
+/// deserialization, and should be rejected like any other invalid option.
+/// The body has the message endpoint's error shape, see `JsonBodyError`.
+fn rejection_response(rejection: JsonRejection) -> Response {
+    match JsonBodyError::from_rejection(rejection) {
+        Ok(error) => {
+            let body = MessageErrorResponse {
+                detail: error.detail,
+                ..error_body(error.error_type, error.error)
+            };
+            (error.status, ResponseJson(body)).into_response()
+        }
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+/// What the usage log and webhooks record about the request behind an LLM call
+struct LlmCall {
+    conversation_id: Option<String>,
+    recipient: String,
+    /// The client's `x-request-id`, or a generated one
+    request_id: String,
+    sender: Option<String>,
+}
+
+impl LlmCall {
+    fn new(headers: &HeaderMap, request: &MessageRequest, recipient: String) -> Self {
+        let request_id = headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        LlmCall {
+            conversation_id: request.conversation_id.clone(),
+            recipient,
+            request_id,
+            sender: request.sender.clone(),
+        }
+    }
+
+    /// Add the outcome of a call to `llm` started at `started` to the usage log
+    fn record(
+        &self,
+        usage_log: Option<&UsageLog>,
+        llm: &dyn LlmAdapter,
+        started: Instant,
+        outcome: Result<Option<&Usage>, &ServiceError>,
+    ) {
+        let Some(usage_log) = usage_log else {
+            return;
+        };
+
+        let usage = outcome.ok().flatten();
+        usage_log.record(UsageRecord {
+            timestamp: Utc::now(),
+            request_id: self.request_id.clone(),
+            provider: llm.provider_name().to_string(),
+            model: llm.model().unwrap_or(DEFAULT_MODEL).to_string(),
+            prompt_tokens: usage.map_or(0, |usage| usage.prompt_tokens),
+            completion_tokens: usage.map_or(0, |usage| usage.completion_tokens),
+            total_tokens: usage.map_or(0, |usage| usage.total_tokens),
+            latency_ms: started.elapsed().as_millis() as u64,
+            status: match outcome {
+                Ok(_) => UsageStatus::Ok,
+                Err(_) => UsageStatus::Failed,
+            },
+            error: outcome.err().map(ToString::to_string),
+            conversation_id: self.conversation_id.clone(),
+            sender: self.sender.clone(),
+        });
+    }
+
+    /// Report the completed `reply` to `[server.webhooks]`, if configured
+    ///
+    /// Delivery runs as a background job, so it never holds up the response.
+    fn notify(
+        &self,
+        jobs: Option<&JobQueue>,
+        model: &str,
+        reply: &str,
+        finish: &Finish,
+        usage: Option<&Usage>,
+    ) {
+        let Some(jobs) = jobs.filter(|jobs| jobs.handles(WEBHOOK_JOB)) else {
+            return;
+        };
+
+        webhook::enqueue(
+            jobs,
+            &MessageCompleted {
+                event: MESSAGE_COMPLETED,
+                recipient: self.recipient.clone(),
+                conversation_id: self.conversation_id.clone(),
+                request_id: self.request_id.clone(),
+                model: model.to_string(),
+                reply: reply.to_string(),
+                finish_reason: finish.reason.clone(),
+                usage: usage.cloned(),
+                timestamp: Utc::now().to_rfc3339(),
+            },
+        );
+    }
+}
+
+/// Resolve the sender and generate the response message
+///
+/// With a `stream_format` the reply is streamed, otherwise it is buffered
+/// into a single JSON response. With a `conversation_id` the exchange is
+/// stored once the reply is complete. `[moderation]` checks the user's
+/// messages before the LLM is called and its reply before it's returned.
+async fn process_message(
+    state: AppState,
+    mut request: MessageRequest,
+    call: LlmCall,
+    stream_format: Option<StreamFormat>,
+) -> Result<Response, Response> {
+    let options = request.options.unwrap_or_default();
+    if let Err(e) = options.validate() {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            e,
+        ));
+    }
+    if stream_format.is_some() {
+        check_streaming(&state).await?;
+    }
+    let parameters = options.with_defaults(&state.generation_defaults);
+    check_content(&state, &request.messages).await?;
+
+    let stored = match &request.conversation_id {
+        Some(conversation_id) => Some(check_token_budget(&state, conversation_id).await?),
+        None => None,
+    };
+    let input_moderation = moderate_messages(&state, &mut request.messages).await?;
+
+    let profile = resolve_sender_profile(&state, request.sender.as_deref())
+        .await
+        .map_err(IntoResponse::into_response)?;
+    let exchange = request.conversation_id.is_some().then(|| {
+        let timestamp = Utc::now().to_rfc3339();
+        request
+            .messages
+            .iter()
+            .zip(&input_moderation)
+            .map(|(message, moderation)| ConversationMessage {
+                moderation: moderation.clone(),
+                ..stored_message(message, &timestamp)
+            })
+            .collect::<Vec<_>>()
+    });
+    // With `[conversations.memory]`, the stored history goes first
+    let (history, memory) = match (&state.memory, &stored) {
+        (Some(config), Some(stored)) => {
+            let (history, report) = memory::history(config, stored);
+            (history, Some(report))
+        }
+        _ => (Vec::new(), None),
+    };
+    let conversation = build_conversation(
+        profile.as_ref(),
+        history.into_iter().chain(request.messages).collect(),
+    );
+    let mut moderation = input_moderation.concat();
+
+    tracing::debug!("Prepared conversation with {} messages", conversation.len());
+
+    if let Some(format) = stream_format {
+        return Ok(stream_reply(
+            &state,
+            conversation,
+            parameters,
+            call,
+            format,
+            moderation,
+        ));
+    }
+
+    let (completion, model, fuel) =
+        generate_reply(&state, conversation, &parameters, &call).await?;
+    let (reply, reply_moderation) = moderate_reply(&state, completion.content).await?;
+    moderation.extend(reply_moderation.iter().cloned());
+    let message = Message {
         role: "assistant".to_string(),
-        content: "This is a placeholder response. The message handler is not yet implemented."
-            .to_string(),
+        content: reply.as_str().into(),
     };
+    let usage = completion.usage.unwrap_or_else(placeholder_usage);
+    let timestamp = Utc::now().to_rfc3339();
+
+    if let (Some(conversation_id), Some(mut exchange)) = (&request.conversation_id, exchange) {
+        exchange.push(ConversationMessage {
+            model: Some(model.clone()),
+            usage: Some(usage.clone()),
+            moderation: reply_moderation,
+            ..stored_message(&message, &timestamp)
+        });
+        record_exchange(&state, conversation_id, exchange, &model).await?;
+    }
+    call.notify(
+        state.jobs.as_ref(),
+        &model,
+        &reply,
+        &completion.finish,
+        Some(&usage),
+    );
 
     let response = MessageResponse {
         success: true,
-        message: response_message,
-        model: "placeholder-model".to_string(),
-        finish_reason: Some("stop".to_string()),
-        usage: Some(Usage {
-            prompt_tokens: 0,
-            completion_tokens: 0,
-            total_tokens: 0,
-        }),
-        timestamp: Utc::now().to_rfc3339(),
+        message,
+        model,
+        finish_reason: completion.finish.reason,
+        stop_sequence: completion.finish.stop_sequence,
+        moderation,
+        memory,
+        usage: Some(usage),
+        parameters,
+        timestamp,
     };
 
     // Return JSON response
-    Ok(ResponseJson(response).into_response())
+    let mut response = ResponseJson(response).into_response();
+    if let Some(fuel) = fuel {
+        response
+            .headers_mut()
+            .insert(FUEL_HEADER, HeaderValue::from(fuel));
+    }
+    Ok(response)
+}
+
+/// Reject malformed content parts, and images the adapter can't take
+///
+/// Without an LLM adapter, the placeholder reply ignores images anyway.
+async fn check_content(state: &AppState, messages: &[Message]) -> Result<(), Response> {
+    let max_image_bytes = state.max_image_bytes.unwrap_or(DEFAULT_MAX_IMAGE_BYTES);
+    for message in messages {
+        if let Err(e) = message.content.validate(max_image_bytes) {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                "invalid_request",
+                e,
+            ));
+        }
+    }
+
+    if !messages.iter().any(|message| message.content.has_images()) {
+        return Ok(());
+    }
+    let Some(llm) = &state.llm else {
+        return Ok(());
+    };
+    let llm = llm.read().await;
+    if llm.supports_images() {
+        return Ok(());
+    }
+    Err(error_response(
+        StatusCode::BAD_REQUEST,
+        "unsupported_content",
+        format!(
+            "The {} adapter doesn't accept images; its manifest must declare \"{}\"",
+            llm.provider_name(),
+            CAPABILITY_IMAGES
+        ),
+    ))
+}
+
+/// Reject a streamed request if the LLM adapter can't stream
+///
+/// Checked before anything is generated, so the client gets a 400 instead
+/// of a stream that fails or arrives in one piece at the end.
+async fn check_streaming(state: &AppState) -> Result<(), Response> {
+    let Some(llm) = &state.llm else {
+        return Ok(());
+    };
+    let llm = llm.read().await;
+    if llm.capabilities().streaming {
+        return Ok(());
+    }
+    Err(error_response(
+        StatusCode::BAD_REQUEST,
+        "unsupported_request",
+        format!(
+            "The {} adapter doesn't stream replies; its manifest must declare \"{}\". \
+             Send the request without `stream` for a single response",
+            llm.provider_name(),
+            CAPABILITY_STREAMING
+        ),
+    ))
+}
+
+/// Apply `[moderation]` to the user's messages, redacting them in place
+///
+/// Returns the decisions for each message. A blocking rule rejects the
+/// request with a 422 before the LLM is called.
+async fn moderate_messages(
+    state: &AppState,
+    messages: &mut [Message],
+) -> Result<Vec<Vec<ModerationDecision>>, Response> {
+    let mut decisions = vec![Vec::new(); messages.len()];
+    let Some(moderation) = &state.moderation else {
+        return Ok(decisions);
+    };
+
+    for (message, decisions) in messages.iter_mut().zip(&mut decisions) {
+        if message.role != "user" {
+            continue;
+        }
+        let texts: Vec<&mut String> = match &mut message.content {
+            MessageContent::Text(text) => vec![text],
+            MessageContent::Parts(parts) => parts
+                .iter_mut()
+                .filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text),
+                    _ => None,
+                })
+                .collect(),
+        };
+
+        for text in texts {
+            let review = moderation
+                .review(ModerationDirection::Input, text, state.llm.as_ref())
+                .await
+                .map_err(llm_error_response)?;
+            if let Some(blocked) = review.blocked() {
+                return Err(policy_response("Message", blocked));
+            }
+            *text = review.content;
+            decisions.extend(review.decisions);
+        }
+    }
+
+    Ok(decisions)
+}
+
+/// Apply `[moderation]` to the LLM's reply before it's returned or stored
+async fn moderate_reply(
+    state: &AppState,
+    reply: String,
+) -> Result<(String, Vec<ModerationDecision>), Response> {
+    let Some(moderation) = &state.moderation else {
+        return Ok((reply, Vec::new()));
+    };
+
+    let review = moderation
+        .review(ModerationDirection::Output, &reply, state.llm.as_ref())
+        .await
+        .map_err(llm_error_response)?;
+    if let Some(blocked) = review.blocked() {
+        return Err(policy_response("Reply", blocked));
+    }
+    Ok((review.content, review.decisions))
+}
+
+/// 422 for a message or reply blocked by `decision`
+///
+/// The response names the rule, never the text it matched.
+fn policy_response(what: &str, decision: &ModerationDecision) -> Response {
+    error_response(
+        StatusCode::UNPROCESSABLE_ENTITY,
+        CONTENT_POLICY_ERROR,
+        format!("{} blocked by moderation rule '{}'", what, decision.rule),
+    )
+}
+
+/// Usage reported until adapters return token counts
+fn placeholder_usage() -> Usage {
+    Usage {
+        prompt_tokens: 0,
+        completion_tokens: 0,
+        total_tokens: 0,
+    }
+}
+
+/// Convert the conversation into adapter messages
+fn chat_messages(conversation: Vec<Message>) -> Vec<ChatMessage> {
+    conversation
+        .into_iter()
+        .map(|message| ChatMessage {
+            content: message.content.text(),
+            parts: message.content.parts().map(<[_]>::to_vec),
+            role: message.role,
+        })
+        .collect()
+}
+
+/// Stream the reply to the client as `format` events
+///
+/// Generation runs in its own task feeding a bounded channel, so a client
+/// reading slower than the adapter produces holds generation back once
+/// `[server] stream_buffer` chunks are waiting. Once the status line is
+/// sent, adapter failures are reported as a final `error` event.
+fn stream_reply(
+    state: &AppState,
+    conversation: Vec<Message>,
+    parameters: GenerationOptions,
+    call: LlmCall,
+    format: StreamFormat,
+    moderation: Vec<ModerationDecision>,
+) -> Response {
+    let buffer = state.stream_buffer.unwrap_or(DEFAULT_STREAM_BUFFER).max(1);
+    let (chunks, receiver) = mpsc::channel(buffer);
+    let messages = chat_messages(conversation);
+    let llm = state.llm.clone();
+    let options = parameters.clone();
+    let usage_log = state.usage_log.clone();
+    let webhooks = state.jobs.clone().filter(|jobs| jobs.handles(WEBHOOK_JOB));
+
+    let generation = tokio::spawn(async move {
+        let Some(llm) = llm else {
+            let _ = chunks.send(PLACEHOLDER_REPLY.to_string()).await;
+            let finish = Finish::stop();
+            call.notify(
+                webhooks.as_ref(),
+                PLACEHOLDER_MODEL,
+                PLACEHOLDER_REPLY,
+                &finish,
+                None,
+            );
+            return Ok::<_, ServiceError>((PLACEHOLDER_MODEL.to_string(), finish));
+        };
+
+        let mut llm = llm.write().await;
+        let started = Instant::now();
+        let (result, reply) = match &webhooks {
+            Some(_) => stream_collecting(&mut *llm, &messages, &options, chunks).await,
+            None => (llm.stream_message(&messages, &options, chunks).await, None),
+        };
+        // Streams don't report usage yet, so only the call itself is recorded
+        call.record(
+            usage_log.as_ref(),
+            &*llm,
+            started,
+            result.as_ref().map(|_| None),
+        );
+        let finish = result?;
+        let model = llm.provider_name().to_string();
+        if let Some(reply) = reply {
+            call.notify(webhooks.as_ref(), &model, &reply, &finish, None);
+        }
+
+        Ok((model, finish))
+    });
+
+    let finish = async move {
+        match generation.await {
+            Ok(Ok((model, finish))) => StreamEvent::Done(StreamEnd {
+                model,
+                finish_reason: finish.reason,
+                stop_sequence: finish.stop_sequence,
+                moderation,
+                usage: Some(placeholder_usage()),
+                parameters,
+                timestamp: Utc::now().to_rfc3339(),
+            }),
+            Ok(Err(error)) => {
+                log_llm_error("LLM stream failed", &error);
+                StreamEvent::Error(error_body(error.error_type(), error.to_string()))
+            }
+            Err(error) => {
+                tracing::error!("LLM stream task failed: {}", error);
+                StreamEvent::Error(error_body("internal_error", "Reply generation failed"))
+            }
+        }
+    };
+
+    stream_response(format, events(receiver, finish))
+}
+
+/// Stream the reply of `llm` to `chunks`, also collecting its text
+///
+/// Webhooks report the whole reply, so it's put together on the way. The
+/// text is None if the client went away before the reply was complete.
+async fn stream_collecting(
+    llm: &mut dyn LlmAdapter,
+    messages: &[ChatMessage],
+    options: &GenerationOptions,
+    chunks: mpsc::Sender<String>,
+) -> (Result<Finish, ServiceError>, Option<String>) {
+    let (tee, mut collected) = mpsc::channel::<String>(1);
+    let forward = async move {
+        let mut reply = String::new();
+        while let Some(chunk) = collected.recv().await {
+            reply.push_str(&chunk);
+            // Dropping `collected` stops the adapter like a client disconnect
+            if chunks.send(chunk).await.is_err() {
+                return None;
+            }
+        }
+        Some(reply)
+    };
+
+    tokio::join!(llm.stream_message(messages, options, tee), forward)
+}
+
+/// Send the conversation to the LLM adapter, returning the reply and model name
+///
+/// Without a loaded LLM adapter a placeholder reply is returned. Calls to
+/// the adapter are added to the usage log. The fuel the adapter used is
+/// only returned with `[server.debug] expose_fuel`.
+async fn generate_reply(
+    state: &AppState,
+    conversation: Vec<Message>,
+    parameters: &GenerationOptions,
+    call: &LlmCall,
+) -> Result<(Completion, String, Option<u64>), Response> {
+    let Some(llm) = &state.llm else {
+        let completion = Completion {
+            content: PLACEHOLDER_REPLY.to_string(),
+            finish: Finish::stop(),
+            usage: None,
+        };
+        return Ok((completion, PLACEHOLDER_MODEL.to_string(), None));
+    };
+
+    let messages = chat_messages(conversation);
+
+    let mut llm = llm.write().await;
+    let started = Instant::now();
+    let result = llm.complete(&messages, parameters).await;
+    call.record(
+        state.usage_log.as_ref(),
+        &*llm,
+        started,
+        result.as_ref().map(|completion| completion.usage.as_ref()),
+    );
+    let completion = result.map_err(llm_error_response)?;
+    // Read under the same lock, so it's the fuel of this call
+    let fuel = llm.fuel_consumed().filter(|_| state.expose_fuel);
+
+    Ok((completion, llm.provider_name().to_string(), fuel))
+}
+
+/// Reject the request if the conversation has used up its token budget
+///
+/// Conversations that don't exist yet are checked against the global budget.
+/// Returns the stored conversation, empty if it doesn't exist yet.
+async fn check_token_budget(
+    state: &AppState,
+    conversation_id: &str,
+) -> Result<Conversation, Response> {
+    if !is_valid_conversation_id(conversation_id) {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            format!("Invalid conversation ID: {}", conversation_id),
+        ));
+    }
+
+    let storage = conversation_storage(state)?;
+    let conversation = load_conversation(storage, conversation_id)
+        .await
+        .map_err(|e| conversation_error_response(conversation_id, e))?
+        .unwrap_or_else(|| Conversation::new(conversation_id));
+
+    conversation
+        .check_budget(state.token_budget)
+        .map(|()| conversation)
+        .map_err(|exceeded| budget_exceeded_response(conversation_id, &exceeded))
+}
+
+fn budget_exceeded_response(conversation_id: &str, exceeded: &BudgetExceeded) -> Response {
+    error_response(
+        StatusCode::FORBIDDEN,
+        "token_budget_exceeded",
+        format!(
+            "Conversation {} has used {} of its {} token budget",
+            conversation_id, exceeded.used, exceeded.budget
+        ),
+    )
+}
+
+/// Append the exchange to the stored conversation in a single write
+///
+/// The budget is checked again under the write, in case concurrent
+/// requests used it up since `check_token_budget`; the exchange is then
+/// rejected without being stored.
+async fn record_exchange(
+    state: &AppState,
+    conversation_id: &str,
+    exchange: Vec<ConversationMessage>,
+    provider: &str,
+) -> Result<(), Response> {
+    let storage = conversation_storage(state)?;
+    let exchange_len = exchange.len();
+    let conversation = append_exchange_within_budget(
+        storage,
+        conversation_id,
+        exchange,
+        provider,
+        state.token_budget,
+    )
+    .await
+    .map_err(|e| conversation_error_response(conversation_id, e))?
+    .map_err(|exceeded| budget_exceeded_response(conversation_id, &exceeded))?;
+
+    tracing::debug!(
+        "Conversation {} has used {} tokens",
+        conversation_id,
+        conversation.total_tokens()
+    );
+
+    // Title new conversations once their first exchange is stored
+    if let Some(jobs) = &state.jobs
+        && conversation.title.is_none()
+        && conversation.messages.len() == exchange_len
+    {
+        title::enqueue(jobs, conversation_id);
+    }
+    // Summarize older messages once they've grown past the trigger
+    if let (Some(jobs), Some(memory)) = (&state.jobs, &state.memory)
+        && needs_summary(memory, &conversation)
+    {
+        summary::enqueue(jobs, conversation_id);
+    }
+    Ok(())
+}
+
+/// Storage adapter holding conversations
+#[allow(clippy::result_large_err)] // The error is what the handler answers with
+fn conversation_storage(state: &AppState) -> Result<&SharedStorage, Response> {
+    state.storage.as_ref().ok_or_else(|| {
+        error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "service_unavailable",
+            "Conversations need a storage adapter",
+        )
+    })
+}
+
+/// Map a conversation storage failure to an HTTP error response
+fn conversation_error_response(conversation_id: &str, error: ServiceError) -> Response {
+    tracing::error!(
+        "Conversation storage failed for '{}': {}",
+        conversation_id,
+        error
+    );
+    error_response(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "storage_error",
+        "Failed to access the conversation",
+    )
+}
+
+/// Request or reply message as stored in a conversation
+///
+/// Only the text of multi-part messages is stored, not their images.
+fn stored_message(message: &Message, timestamp: &str) -> ConversationMessage {
+    ConversationMessage {
+        role: message.role.clone(),
+        content: message.content.text(),
+        timestamp: Some(timestamp.to_string()),
+        model: None,
+        usage: None,
+        moderation: Vec::new(),
+    }
+}
+
+/// Map an LLM adapter failure to an HTTP error response
+///
+/// The status and error type come from `ServiceError`; the body keeps the
+/// message endpoint's shape.
+fn llm_error_response(error: ServiceError) -> Response {
+    log_llm_error("LLM request failed", &error);
+
+    let mut response = error_response(error.status_code(), error.error_type(), error.to_string());
+    if let Some(retry_after_secs) = error.retry_after_secs() {
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    }
+    response
+}
+
+/// Log an LLM adapter failure, with the fuel left when the adapter trapped
+fn log_llm_error(context: &str, error: &ServiceError) {
+    match error {
+        ServiceError::WasmTrap {
+            function,
+            remaining_fuel,
+            trap,
+        } => tracing::error!(
+            "{}: adapter trapped in `{}`: {} (fuel left: {})",
+            context,
+            function,
+            trap,
+            remaining_fuel.map_or("unknown".to_string(), |fuel| fuel.to_string())
+        ),
+        _ => tracing::error!("{}: {}", context, error),
+    }
+}
+
+/// Build the JSON body of a message endpoint error
+fn error_body(error_type: &str, error: impl Into<String>) -> MessageErrorResponse {
+    MessageErrorResponse {
+        success: false,
+        error: error.into(),
+        detail: None,
+        error_type: error_type.to_string(),
+        timestamp: Utc::now().to_rfc3339(),
+    }
+}
+
+/// Build a JSON error response for the message endpoint
+fn error_response(status: StatusCode, error_type: &str, error: impl Into<String>) -> Response {
+    (status, ResponseJson(error_body(error_type, error))).into_response()
+}
+
+/// Look up the sender profile selected by the request
+///
+/// An explicitly requested sender must exist; the default sender is optional.
+async fn resolve_sender_profile(
+    state: &AppState,
+    sender: Option<&str>,
+) -> Result<Option<SenderProfile>, StatusCode> {
+    let sender_id = sender.unwrap_or(DEFAULT_SENDER_ID);
+
+    if !is_valid_sender_id(sender_id) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let Some(storage) = &state.storage else {
+        // Without storage there are no profiles to apply
+        return if sender.is_some() {
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        } else {
+            Ok(None)
+        };
+    };
+
+    let profile = load_profile(storage, sender_id).await.map_err(|e| {
+        tracing::error!("Failed to load sender profile '{}': {}", sender_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if profile.is_none() && sender.is_some() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(profile)
+}
+
+/// Prepend the sender's system prompt (if any) to the conversation
+fn build_conversation(profile: Option<&SenderProfile>, messages: Vec<Message>) -> Vec<Message> {
+    let system_prompt = profile
+        .and_then(|profile| profile.system_prompt.as_deref())
+        .filter(|prompt| !prompt.trim().is_empty());
+
+    match system_prompt {
+        Some(prompt) => {
+            let mut conversation = Vec::with_capacity(messages.len() + 1);
+            conversation.push(Message {
+                role: "system".to_string(),
+                content: prompt.into(),
+            });
+            conversation.extend(messages);
+            conversation
+        }
+        None => messages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::traits::{
+        AdapterService, Capabilities, FinishReason, LlmAdapter, ModelInfo,
+    };
+    use crate::config::schema::{ModerationAction, ModerationConfig, ModerationRule};
+    use crate::routes::test_support::{FnLlm, MemoryStorage};
+    use crate::routes::v1::conversations::model::{append_exchange, conversation_key};
+    use crate::server::jobs::{JobOptions, SystemClock};
+    use async_trait::async_trait;
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    /// LLM adapter recording the options it was called with
+    #[derive(Clone, Default)]
+    struct RecordingLlm {
+        received: Arc<Mutex<Option<GenerationOptions>>>,
+    }
+
+    #[async_trait]
+    impl AdapterService for RecordingLlm {
+        fn service_name(&self) -> &'static str {
+            "llm"
+        }
+
+        fn provider_name(&self) -> &str {
+            "recording"
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl LlmAdapter for RecordingLlm {
+        async fn send_message(
+            &mut self,
+            messages: &[ChatMessage],
+            options: &GenerationOptions,
+        ) -> Result<String, ServiceError> {
+            *self.received.lock().unwrap() = Some(options.clone());
+            Ok(format!("echo: {}", messages.last().unwrap().content))
+        }
+
+        /// Reports the same usage for every reply
+        async fn complete(
+            &mut self,
+            messages: &[ChatMessage],
+            options: &GenerationOptions,
+        ) -> Result<Completion, ServiceError> {
+            Ok(Completion {
+                content: self.send_message(messages, options).await?,
+                finish: Finish::stop(),
+                usage: Some(Usage {
+                    prompt_tokens: 3,
+                    completion_tokens: 2,
+                    total_tokens: 5,
+                }),
+            })
+        }
+
+        /// Reports the same fuel for every reply, like a WASM adapter
+        fn fuel_consumed(&self) -> Option<u64> {
+            Some(1234)
+        }
+
+        async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+            Ok(ModelInfo {
+                name: "recording".to_string(),
+                version: "test".to_string(),
+                context_length: None,
+                parameters: None,
+            })
+        }
+    }
+
+    /// LLM adapter streaming fixed chunks, optionally failing afterwards
+    #[derive(Clone)]
+    struct ChunkedLlm {
+        chunks: Vec<&'static str>,
+        fail: bool,
+        /// How it reports its replies ended
+        finish: Finish,
+        /// Chunks the response has accepted so far
+        sent: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl AdapterService for ChunkedLlm {
+        fn service_name(&self) -> &'static str {
+            "llm"
+        }
+
+        fn provider_name(&self) -> &str {
+            "chunked"
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                streaming: true,
+                ..Capabilities::default()
+            }
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl LlmAdapter for ChunkedLlm {
+        async fn send_message(
+            &mut self,
+            _messages: &[ChatMessage],
+            _options: &GenerationOptions,
+        ) -> Result<String, ServiceError> {
+            Ok(self.chunks.concat())
+        }
+
+        async fn complete(
+            &mut self,
+            messages: &[ChatMessage],
+            options: &GenerationOptions,
+        ) -> Result<Completion, ServiceError> {
+            Ok(Completion {
+                content: self.send_message(messages, options).await?,
+                finish: self.finish.clone(),
+                usage: None,
+            })
+        }
+
+        async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+            Ok(ModelInfo {
+                name: "chunked".to_string(),
+                version: "test".to_string(),
+                context_length: None,
+                parameters: None,
+            })
+        }
+
+        async fn stream_message(
+            &mut self,
+            _messages: &[ChatMessage],
+            _options: &GenerationOptions,
+            chunks: mpsc::Sender<String>,
+        ) -> Result<Finish, ServiceError> {
+            for chunk in &self.chunks {
+                if chunks.send(chunk.to_string()).await.is_ok() {
+                    self.sent.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+
+            if self.fail {
+                return Err(ServiceError::ExecutionError("connection reset".to_string()));
+            }
+            Ok(self.finish.clone())
+        }
+    }
+
+    fn chunked(fail: bool) -> AppState {
+        chunked_with(Finish::stop(), fail)
+    }
+
+    fn chunked_with(finish: Finish, fail: bool) -> AppState {
+        AppState::with_llm(ChunkedLlm {
+            chunks: vec!["Hel", "lo"],
+            fail,
+            finish,
+            sent: Arc::default(),
+        })
+    }
+
+    fn stream_request(accept: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/assistant")
+            .header("content-type", "application/json")
+            .header("accept", accept)
+            .body(Body::from(
+                r#"{"messages":[{"role":"user","content":"Hi"}],"stream":true}"#,
+            ))
+            .unwrap()
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    async fn ndjson_lines(response: Response) -> Vec<serde_json::Value> {
+        body_text(response)
+            .await
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn app(state: AppState) -> Router {
+        super::super::router().with_state(state)
+    }
+
+    fn message_request(body: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/assistant")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn user_message(content: &str) -> Message {
+        Message {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+
+    /// State with a usage-reporting LLM and in-memory conversation storage
+    fn conversation_state(storage: MemoryStorage, token_budget: Option<u64>) -> AppState {
+        AppState {
+            storage: Some(Arc::new(tokio::sync::RwLock::new(storage))),
+            token_budget,
+            ..AppState::with_llm(RecordingLlm::default())
+        }
+    }
+
+    async fn send_to_conversation(state: &AppState, content: &str) -> Response {
+        let body = serde_json::json!({
+            "conversation_id": "chat-1",
+            "messages": [{"role": "user", "content": content}],
+        });
+
+        app(state.clone())
+            .oneshot(message_request(&body.to_string()))
+            .await
+            .unwrap()
+    }
+
+    async fn stored_conversation(state: &AppState) -> Conversation {
+        load_conversation(state.storage.as_ref().unwrap(), "chat-1")
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    fn profile(system_prompt: Option<&str>) -> SenderProfile {
+        SenderProfile {
+            default_model: None,
+            display_name: "Test".to_string(),
+            system_prompt: system_prompt.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_build_conversation_prepends_system_prompt() {
+        let profile = profile(Some("You are terse."));
+        let conversation = build_conversation(Some(&profile), vec![user_message("Hi")]);
+
+        assert_eq!(conversation.len(), 2);
+        assert_eq!(conversation[0].role, "system");
+        assert_eq!(conversation[0].content.text(), "You are terse.");
+        assert_eq!(conversation[1].role, "user");
+    }
+
+    #[test]
+    fn test_build_conversation_without_profile() {
+        let conversation = build_conversation(None, vec![user_message("Hi")]);
+
+        assert_eq!(conversation.len(), 1);
+        assert_eq!(conversation[0].role, "user");
+    }
+
+    #[test]
+    fn test_build_conversation_ignores_blank_prompt() {
+        let profile = profile(Some("   "));
+        let conversation = build_conversation(Some(&profile), vec![user_message("Hi")]);
+
+        assert_eq!(conversation.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_sender_profile_without_storage() {
+        let state = AppState::default();
+
+        // Default sender is optional
+        assert_eq!(resolve_sender_profile(&state, None).await, Ok(None));
+
+        // Explicit sender needs storage
+        assert_eq!(
+            resolve_sender_profile(&state, Some("work")).await,
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        );
+
+        // Unsafe sender IDs are rejected
+        assert_eq!(
+            resolve_sender_profile(&state, Some("../x")).await,
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fuel_header_only_with_expose_fuel() {
+        for expose_fuel in [false, true] {
+            let state = AppState {
+                expose_fuel,
+                ..AppState::with_llm(RecordingLlm::default())
+            };
+
+            let response = app(state)
+                .oneshot(message_request(
+                    r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+                ))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+            let fuel = response.headers().get(FUEL_HEADER);
+            if expose_fuel {
+                assert_eq!(fuel.unwrap(), "1234");
+            } else {
+                assert!(fuel.is_none());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_config_defaults_reach_adapter() {
+        let llm = RecordingLlm::default();
+        let mut state = AppState::with_llm(llm.clone());
+        state.generation_defaults = GenerationOptions {
+            max_tokens: Some(256),
+            temperature: Some(0.5),
+            ..GenerationOptions::default()
+        };
+
+        let response = app(state)
+            .oneshot(message_request(
+                r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let received = llm.received.lock().unwrap().clone().unwrap();
+        assert_eq!(received.temperature, Some(0.5));
+        assert_eq!(received.max_tokens, Some(256));
+
+        let body = body_json(response).await;
+        assert_eq!(body["message"]["content"], "echo: Hi");
+        assert_eq!(body["model"], "recording");
+        assert_eq!(body["parameters"]["temperature"], 0.5);
+        assert_eq!(body["parameters"]["max_tokens"], 256);
+        assert!(body["parameters"].get("seed").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_request_options_override_defaults() {
+        let llm = RecordingLlm::default();
+        let mut state = AppState::with_llm(llm.clone());
+        state.generation_defaults = GenerationOptions {
+            max_tokens: Some(256),
+            temperature: Some(0.5),
+            ..GenerationOptions::default()
+        };
+
+        let response = app(state)
+            .oneshot(message_request(
+                r#"{"messages":[{"role":"user","content":"Hi"}],
+                    "options":{"temperature":1.5,"seed":42,"stop":["\n\n"]}}"#,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let received = llm.received.lock().unwrap().clone().unwrap();
+        assert_eq!(
+            received,
+            GenerationOptions {
+                max_tokens: Some(256),
+                seed: Some(42),
+                stop: Some(vec!["\n\n".to_string()]),
+                temperature: Some(1.5),
+                top_p: None,
+            }
+        );
+
+        let body = body_json(response).await;
+        assert_eq!(body["parameters"]["seed"], 42);
+        assert_eq!(body["parameters"]["temperature"], 1.5);
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_options_rejected() {
+        for options in [
+            r#"{"temperature":3.0}"#,
+            r#"{"temperature":-0.1}"#,
+            r#"{"top_p":1.5}"#,
+            r#"{"max_tokens":0}"#,
+            r#"{"stop":["a","b","c","d","e"]}"#,
+        ] {
+            let llm = RecordingLlm::default();
+            let response = app(AppState::with_llm(llm.clone()))
+                .oneshot(message_request(&format!(
+                    r#"{{"messages":[{{"role":"user","content":"Hi"}}],"options":{}}}"#,
+                    options
+                )))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", options);
+            assert!(llm.received.lock().unwrap().is_none());
+
+            let body = body_json(response).await;
+            assert_eq!(body["success"], false);
+            assert_eq!(body["error_type"], "invalid_request");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_rejected_with_location() {
+        for (body, location) in [
+            (
+                r#"{"messages":[{"role":"user","content":"Hi"}"#,
+                "at line 1 column",
+            ),
+            ("{\n  \"messages\": [,]\n}", "at line 2 column"),
+        ] {
+            let llm = RecordingLlm::default();
+            let response = app(AppState::with_llm(llm.clone()))
+                .oneshot(message_request(body))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", body);
+            assert!(llm.received.lock().unwrap().is_none());
+
+            let body = body_json(response).await;
+            assert_eq!(body["success"], false);
+            assert_eq!(body["error"], "invalid JSON");
+            assert_eq!(body["error_type"], "invalid_json");
+            let detail = body["detail"].as_str().unwrap();
+            assert!(detail.contains(location), "{}", detail);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_content_type_required() {
+        for content_type in [None, Some("text/plain")] {
+            let mut request = Request::builder().method("POST").uri("/assistant");
+            if let Some(content_type) = content_type {
+                request = request.header("content-type", content_type);
+            }
+            let request = request
+                .body(Body::from(
+                    r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+                ))
+                .unwrap();
+
+            let response = app(AppState::default()).oneshot(request).await.unwrap();
+
+            assert_eq!(
+                response.status(),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "{:?}",
+                content_type
+            );
+            let body = body_json(response).await;
+            assert_eq!(body["error_type"], "unsupported_media_type");
+            assert!(body.get("detail").is_none());
+        }
+    }
+
+    const IMAGE_REQUEST: &str = r#"{"messages":[{"role":"user","content":[
+        {"type":"text","text":"What's this?"},
+        {"type":"image","data":"iVBORw==","mime":"image/png"}
+    ]}]}"#;
+
+    /// LLM replying with the number of parts of the last message
+    fn parts_llm() -> FnLlm<impl Fn(&[ChatMessage]) -> String + Send + Sync + 'static> {
+        FnLlm::new("vision", |messages: &[ChatMessage]| {
+            let last = messages.last().unwrap();
+            let parts = last.parts.as_ref().map_or(0, Vec::len);
+            format!("{}: {} parts", last.content, parts)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_images_reach_capable_adapter() {
+        let response = app(AppState::with_llm(parts_llm().with_images()))
+            .oneshot(message_request(IMAGE_REQUEST))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(body["message"]["content"], "What's this?: 2 parts");
+    }
+
+    #[tokio::test]
+    async fn test_images_rejected_without_capability() {
+        let response = app(AppState::with_llm(parts_llm()))
+            .oneshot(message_request(IMAGE_REQUEST))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = body_json(response).await;
+        assert_eq!(body["error_type"], "unsupported_content");
+        assert!(body["error"].as_str().unwrap().contains("\"images\""));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_image_rejected() {
+        let state = AppState {
+            max_image_bytes: Some(3),
+            ..AppState::with_llm(parts_llm().with_images())
+        };
+
+        let response = app(state)
+            .oneshot(message_request(IMAGE_REQUEST))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = body_json(response).await;
+        assert_eq!(body["error_type"], "invalid_request");
+    }
+
+    #[tokio::test]
+    async fn test_max_completion_tokens_alias() {
+        let llm = RecordingLlm::default();
+        let response = app(AppState::with_llm(llm.clone()))
+            .oneshot(message_request(
+                r#"{"messages":[{"role":"user","content":"Hi"}],
+                    "options":{"max_completion_tokens":64}}"#,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let received = llm.received.lock().unwrap().clone().unwrap();
+        assert_eq!(received.max_tokens, Some(64));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_options_rejected() {
+        for options in [
+            r#"{"max_completion_tokens":-1}"#,
+            r#"{"max_tokens":-1}"#,
+            r#"{"temperature":"hot"}"#,
+            r#"{"unknown":1}"#,
+        ] {
+            let response = app(AppState::with_llm(RecordingLlm::default()))
+                .oneshot(message_request(&format!(
+                    r#"{{"messages":[{{"role":"user","content":"Hi"}}],"options":{}}}"#,
+                    options
+                )))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", options);
+            let body = body_json(response).await;
+            assert_eq!(body["error_type"], "invalid_request");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_options_at_bounds_accepted() {
+        let response = app(AppState::with_llm(RecordingLlm::default()))
+            .oneshot(message_request(
+                r#"{"messages":[{"role":"user","content":"Hi"}],
+                    "options":{"temperature":2.0,"top_p":0.0,"stop":["a","b","c","d"]}}"#,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_stream_ndjson() {
+        let response = app(chunked(false))
+            .oneshot(stream_request("application/x-ndjson"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+
+        let lines = ndjson_lines(response).await;
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["type"], "chunk");
+        assert_eq!(lines[0]["sequence"], 0);
+        assert_eq!(lines[0]["content"], "Hel");
+        assert_eq!(lines[1]["sequence"], 1);
+        assert_eq!(lines[1]["content"], "lo");
+        assert_eq!(lines[2]["type"], "done");
+        assert_eq!(lines[2]["finish_reason"], "stop");
+        assert_eq!(lines[2]["model"], "chunked");
+        assert_eq!(lines[2]["usage"]["total_tokens"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_finish_reason_from_adapter() {
+        let cut_off = Finish {
+            reason: Some(FinishReason::Length),
+            stop_sequence: None,
+        };
+        let response = app(chunked_with(cut_off, false))
+            .oneshot(message_request(
+                r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+            ))
+            .await
+            .unwrap();
+
+        let body = body_json(response).await;
+        assert_eq!(body["finish_reason"], "length");
+        assert!(body.get("stop_sequence").is_none());
+
+        // Unknown reasons are null rather than a guess
+        let response = app(chunked_with(Finish::default(), false))
+            .oneshot(message_request(
+                r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+            ))
+            .await
+            .unwrap();
+        let body = body_json(response).await;
+        assert!(body["finish_reason"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_stream_reports_finish_reason_and_stop_sequence() {
+        let stopped = Finish {
+            reason: Some(FinishReason::Stop),
+            stop_sequence: Some("END".to_string()),
+        };
+        let response = app(chunked_with(stopped, false))
+            .oneshot(stream_request("application/x-ndjson"))
+            .await
+            .unwrap();
+
+        let lines = ndjson_lines(response).await;
+        assert_eq!(lines[2]["type"], "done");
+        assert_eq!(lines[2]["finish_reason"], "stop");
+        assert_eq!(lines[2]["stop_sequence"], "END");
+
+        let filtered = Finish {
+            reason: Some(FinishReason::ContentFilter),
+            stop_sequence: None,
+        };
+        let response = app(chunked_with(filtered, false))
+            .oneshot(stream_request("application/x-ndjson"))
+            .await
+            .unwrap();
+        let lines = ndjson_lines(response).await;
+        assert_eq!(lines[2]["finish_reason"], "content_filter");
+    }
+
+    #[tokio::test]
+    async fn test_stream_ndjson_reports_adapter_failure() {
+        let response = app(chunked(true))
+            .oneshot(stream_request("application/x-ndjson"))
+            .await
+            .unwrap();
+
+        let lines = ndjson_lines(response).await;
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1]["content"], "lo");
+        assert_eq!(lines[2]["type"], "error");
+        assert_eq!(lines[2]["error_type"], "adapter_error");
+        assert_eq!(lines[2]["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_slow_client_throttles_generation() {
+        let sent = Arc::new(AtomicUsize::new(0));
+        let state = AppState {
+            stream_buffer: Some(2),
+            ..AppState::with_llm(ChunkedLlm {
+                chunks: vec!["x"; 20],
+                fail: false,
+                finish: Finish::stop(),
+                sent: sent.clone(),
+            })
+        };
+        let response = app(state)
+            .oneshot(stream_request("application/x-ndjson"))
+            .await
+            .unwrap();
+        let mut body = response.into_body().into_data_stream();
+
+        // Nothing read yet: generation stops once the buffer is full
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+
+        // Every chunk read makes room for one more
+        for _ in 0..5 {
+            body.next().await.unwrap().unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(sent.load(Ordering::SeqCst), 7);
+
+        // Nothing is lost on the way
+        let mut lines = 5;
+        while let Some(frame) = body.next().await {
+            lines += frame.unwrap().iter().filter(|&&byte| byte == b'\n').count();
+        }
+        assert_eq!(lines, 21);
+        assert_eq!(sent.load(Ordering::SeqCst), 20);
+    }
+
+    #[tokio::test]
+    async fn test_stream_sse() {
+        let response = app(chunked(false))
+            .oneshot(stream_request("text/event-stream"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+
+        let body = body_text(response).await;
+        let events: Vec<&str> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("event: "))
+            .collect();
+        assert_eq!(events, ["chunk", "chunk", "done"]);
+        assert!(body.contains(r#""sequence":1"#));
+    }
+
+    #[tokio::test]
+    async fn test_stream_rejected_without_streaming_capability() {
+        let response = app(AppState::with_llm(RecordingLlm::default()))
+            .oneshot(stream_request("application/x-ndjson"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = body_json(response).await;
+        assert_eq!(body["error_type"], "unsupported_request");
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .contains("must declare \"streaming\"")
+        );
+
+        // Without a streaming `Accept` the reply is buffered, so it's fine
+        let response = app(AppState::with_llm(RecordingLlm::default()))
+            .oneshot(stream_request("application/json"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_stream_without_streaming_accept_is_buffered() {
+        let response = app(chunked(false))
+            .oneshot(stream_request("application/json"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(body["message"]["content"], "Hello");
+        assert_eq!(body["finish_reason"], "stop");
+    }
+
+    #[tokio::test]
+    async fn test_streaming_accept_without_stream_flag_is_buffered() {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/assistant")
+            .header("content-type", "application/json")
+            .header("accept", "application/x-ndjson")
+            .body(Body::from(
+                r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+            ))
+            .unwrap();
+
+        let response = app(chunked(false)).oneshot(request).await.unwrap();
+
+        let body = body_json(response).await;
+        assert_eq!(body["message"]["content"], "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_conversation_accumulates_usage() {
+        let state = conversation_state(MemoryStorage::default(), None);
+
+        for content in ["Hi", "Again"] {
+            let response = send_to_conversation(&state, content).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let conversation = stored_conversation(&state).await;
+        let roles: Vec<&str> = conversation
+            .messages
+            .iter()
+            .map(|message| message.role.as_str())
+            .collect();
+        assert_eq!(roles, ["user", "assistant", "user", "assistant"]);
+        assert_eq!(conversation.messages[3].content, "echo: Again");
+        assert_eq!(conversation.provider.as_deref(), Some("recording"));
+
+        let usage = conversation.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 6);
+        assert_eq!(usage.completion_tokens, 4);
+        assert_eq!(usage.total_tokens, 10);
+    }
+
+    #[tokio::test]
+    async fn test_conversation_memory_sends_summary_and_window() {
+        use crate::config::schema::{ConversationMemoryConfig, MemoryStrategy};
+        use crate::routes::v1::conversations::model::{ConversationSummary, set_summary};
+
+        let storage: SharedStorage = Arc::new(tokio::sync::RwLock::new(MemoryStorage::default()));
+        let history: Vec<_> = ["one", "two", "three", "four"]
+            .iter()
+            .zip(["user", "assistant"].iter().cycle())
+            .map(|(content, role)| ConversationMessage {
+                role: role.to_string(),
+                ..stored_message(&user_message(content), "2024-01-01T00:00:00+00:00")
+            })
+            .collect();
+        append_exchange(&storage, "chat-1", history.clone(), "echo")
+            .await
+            .unwrap();
+        set_summary(
+            &storage,
+            "chat-1",
+            ConversationSummary {
+                content: "They counted.".to_string(),
+                messages: 2,
+                updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let seen = prompts.clone();
+        let llm = FnLlm::new("echo", move |messages: &[ChatMessage]| {
+            seen.lock().unwrap().push(
+                messages
+                    .iter()
+                    .map(|message| format!("{}: {}", message.role, message.content))
+                    .collect::<Vec<_>>(),
+            );
+            "five".to_string()
+        });
+        // Record the summary jobs instead of running them
+        let (state, summarized) = with_webhooks(AppState {
+            memory: Some(ConversationMemoryConfig {
+                strategy: MemoryStrategy::Summary,
+                summary_trigger_tokens: 1,
+                window_messages: 2,
+                ..ConversationMemoryConfig::default()
+            }),
+            storage: Some(storage.clone()),
+            ..AppState::with_llm(llm)
+        });
+        let seen = summarized.clone();
+        state
+            .jobs
+            .as_ref()
+            .unwrap()
+            .register(summary::SUMMARY_JOB, move |job| {
+                seen.lock().unwrap().push(job.payload);
+                async { Ok(()) }
+            });
+
+        let response = send_to_conversation(&state, "Go on").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(
+            body["memory"],
+            serde_json::json!({
+                "strategy": "summary",
+                "messages": 2,
+                "omitted": 2,
+                "summarized": true,
+            })
+        );
+
+        // The summary stands in for the first two messages
+        assert_eq!(
+            prompts.lock().unwrap()[0],
+            [
+                "system: Summary of the earlier conversation:\nThey counted.",
+                "user: three",
+                "assistant: four",
+                "user: Go on",
+            ]
+        );
+
+        // The stored history is complete, and grew past the window again
+        let conversation = stored_conversation(&state).await;
+        assert_eq!(conversation.messages[..4], history[..]);
+        assert_eq!(conversation.messages.len(), 6);
+        assert_eq!(conversation.summarized_messages(), 2);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while summarized.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("a summary job should be enqueued");
+        assert_eq!(
+            summarized.lock().unwrap()[0],
+            serde_json::json!({"conversation_id": "chat-1"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_llm_calls_recorded_in_usage_log() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = crate::config::schema::UsageLogConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let state = AppState {
+            usage_log: UsageLog::start(&config, temp_dir.path()).unwrap(),
+            ..conversation_state(MemoryStorage::default(), None)
+        };
+
+        let body = serde_json::json!({
+            "conversation_id": "chat-1",
+            "messages": [{"role": "user", "content": "Hi"}],
+        });
+        let mut request = message_request(&body.to_string());
+        request
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, HeaderValue::from_static("req-42"));
+        let response = app(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let ledger = config.ledger_path(temp_dir.path());
+        let mut line = String::new();
+        for _ in 0..200 {
+            line = std::fs::read_to_string(&ledger).unwrap_or_default();
+            if !line.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let record: UsageRecord = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(record.request_id, "req-42");
+        assert_eq!(record.provider, "recording");
+        assert_eq!(record.model, DEFAULT_MODEL);
+        assert_eq!(record.total_tokens, 5);
+        assert_eq!(record.status, UsageStatus::Ok);
+        assert_eq!(record.conversation_id.as_deref(), Some("chat-1"));
+    }
+
+    #[tokio::test]
+    async fn test_conversation_rejected_at_budget() {
+        let state = conversation_state(MemoryStorage::default(), Some(10));
+
+        // 5 tokens per exchange: the second one reaches the budget exactly
+        for content in ["Hi", "Again"] {
+            let response = send_to_conversation(&state, content).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = send_to_conversation(&state, "Once more").await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = body_json(response).await;
+        assert_eq!(body["error_type"], "token_budget_exceeded");
+        assert_eq!(
+            body["error"],
+            "Conversation chat-1 has used 10 of its 10 token budget"
+        );
+
+        // The rejected exchange isn't stored
+        let conversation = stored_conversation(&state).await;
+        assert_eq!(conversation.messages.len(), 4);
+        assert_eq!(conversation.total_tokens(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_exchange_not_stored_once_budget_used_concurrently() {
+        let state = conversation_state(MemoryStorage::default(), Some(5));
+        let exchange = |content: &str| {
+            vec![ConversationMessage {
+                usage: Some(Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 4,
+                    total_tokens: 5,
+                }),
+                ..stored_message(&user_message(content), "2025-01-02T03:04:05Z")
+            }]
+        };
+
+        // Both requests passed the check before either was recorded
+        assert!(check_token_budget(&state, "chat-1").await.is_ok());
+        assert!(check_token_budget(&state, "chat-1").await.is_ok());
+        let first = record_exchange(&state, "chat-1", exchange("First"), "recording").await;
+        assert!(first.is_ok());
+        let response = record_exchange(&state, "chat-1", exchange("Second"), "recording")
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let conversation = stored_conversation(&state).await;
+        assert_eq!(conversation.messages.len(), 1);
+        assert_eq!(conversation.total_tokens(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_conversation_budget_overrides_global() {
+        let mut conversation = Conversation::new("chat-1");
+        conversation.token_budget = Some(5);
+        conversation.record_exchange(
+            vec![ConversationMessage {
+                usage: Some(Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 4,
+                    total_tokens: 5,
+                }),
+                ..stored_message(&user_message("Hi"), "2025-01-02T03:04:05Z")
+            }],
+            "recording",
+        );
+
+        let mut storage = MemoryStorage::default();
+        storage.entries.insert(
+            conversation_key("chat-1"),
+            serde_json::to_vec(&conversation).unwrap(),
+        );
+        let state = conversation_state(storage, Some(1000));
+
+        let response = send_to_conversation(&state, "Hi").await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = body_json(response).await;
+        assert_eq!(
+            body["error"],
+            "Conversation chat-1 has used 5 of its 5 token budget"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conversation_needs_storage() {
+        let state = AppState::with_llm(RecordingLlm::default());
+
+        let response = send_to_conversation(&state, "Hi").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    /// State with one `secrets` moderation rule and an LLM appending "and a secret"
+    ///
+    /// The returned counter tells how often the LLM was called.
+    fn moderated(
+        action: ModerationAction,
+        direction: ModerationDirection,
+    ) -> (AppState, Arc<AtomicUsize>) {
+        let config = ModerationConfig {
+            adapter: None,
+            rules: vec![ModerationRule {
+                action,
+                direction,
+                id: "secrets".to_string(),
+                keywords: vec!["secret".to_string()],
+                pattern: None,
+            }],
+        };
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let llm = FnLlm::new("echo", move |messages| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            format!("{} and a secret", messages.last().unwrap().content)
+        });
+        let state = AppState {
+            moderation: Moderation::from_config(&config).unwrap(),
+            ..AppState::with_llm(llm)
+        };
+
+        (state, calls)
+    }
+
+    async fn send_moderated(state: &AppState, content: &str) -> Response {
+        let body = serde_json::json!({"messages": [{"role": "user", "content": content}]});
+        app(state.clone())
+            .oneshot(message_request(&body.to_string()))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_moderation_disabled_by_default() {
+        let (state, _) = moderated(ModerationAction::Block, ModerationDirection::Both);
+        let state = AppState {
+            moderation: None,
+            ..state
+        };
+
+        let response = send_moderated(&state, "my secret").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(body["message"]["content"], "my secret and a secret");
+        assert!(body.get("moderation").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_moderation_blocks_messages_before_the_llm() {
+        let (state, calls) = moderated(ModerationAction::Block, ModerationDirection::Input);
+
+        let response = send_moderated(&state, "my secret").await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = body_json(response).await;
+        assert_eq!(body["error_type"], "content_policy");
+        assert_eq!(
+            body["error"],
+            "Message blocked by moderation rule 'secrets'"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // Replies aren't checked by input rules
+        let response = send_moderated(&state, "hello").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_moderation_blocks_replies() {
+        let (state, calls) = moderated(ModerationAction::Block, ModerationDirection::Output);
+
+        let response = send_moderated(&state, "hello").await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = body_json(response).await;
+        assert_eq!(body["error_type"], "content_policy");
+        assert_eq!(body["error"], "Reply blocked by moderation rule 'secrets'");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_moderation_flags_both_directions() {
+        let (state, _) = moderated(ModerationAction::Flag, ModerationDirection::Both);
+
+        let response = send_moderated(&state, "my secret").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        assert_eq!(body["message"]["content"], "my secret and a secret");
+        assert_eq!(
+            body["moderation"],
+            serde_json::json!([
+                {"action": "flag", "direction": "input", "rule": "secrets"},
+                {"action": "flag", "direction": "output", "rule": "secrets"},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_moderation_redacts_both_directions() {
+        let (state, _) = moderated(ModerationAction::Redact, ModerationDirection::Both);
+
+        let response = send_moderated(&state, "my secret").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_json(response).await;
+        // The LLM only saw the redacted message
+        assert_eq!(body["message"]["content"], "my [REDACTED] and a [REDACTED]");
+        assert_eq!(body["moderation"][0]["action"], "redact");
+        assert_eq!(body["moderation"][1]["direction"], "output");
+    }
+
+    #[tokio::test]
+    async fn test_moderation_is_stored_with_the_conversation() {
+        let (state, _) = moderated(ModerationAction::Flag, ModerationDirection::Both);
+        let state = AppState {
+            storage: Some(Arc::new(tokio::sync::RwLock::new(MemoryStorage::default()))),
+            ..state
+        };
+
+        let response = send_to_conversation(&state, "my secret").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let conversation = stored_conversation(&state).await;
+        assert_eq!(conversation.messages.len(), 2);
+        assert_eq!(conversation.messages[0].content, "my secret");
+        assert_eq!(
+            conversation.messages[0].moderation[0].direction,
+            ModerationDirection::Input
+        );
+        assert_eq!(
+            conversation.messages[1].moderation[0].direction,
+            ModerationDirection::Output
+        );
+    }
+
+    #[tokio::test]
+    async fn test_moderated_replies_are_not_streamed() {
+        let (state, _) = moderated(ModerationAction::Redact, ModerationDirection::Output);
+
+        let response = app(state)
+            .oneshot(stream_request("application/x-ndjson"))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body = body_json(response).await;
+        assert_eq!(body["message"]["content"], "Hi and a [REDACTED]");
+    }
+
+    /// `state` with a job queue recording the payloads of webhook jobs
+    fn with_webhooks(state: AppState) -> (AppState, Arc<Mutex<Vec<serde_json::Value>>>) {
+        let storage: SharedStorage = Arc::new(tokio::sync::RwLock::new(MemoryStorage::default()));
+        let options = JobOptions {
+            concurrency: 1,
+            max_attempts: 1,
+            poll_interval: Duration::from_millis(10),
+            retry_base: Duration::from_millis(10),
+            retry_max: Duration::from_millis(10),
+        };
+        let queue = JobQueue::new(storage, options, Arc::new(SystemClock));
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let seen = delivered.clone();
+        queue.register(WEBHOOK_JOB, move |job| {
+            seen.lock().unwrap().push(job.payload);
+            async { Ok(()) }
+        });
+        queue.start();
+
+        let state = AppState {
+            jobs: Some(queue),
+            ..state
+        };
+        (state, delivered)
+    }
+
+    async fn wait_for_webhook(delivered: &Mutex<Vec<serde_json::Value>>) -> serde_json::Value {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(payload) = delivered.lock().unwrap().first() {
+                    return payload.clone();
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("a webhook should be queued")
+    }
+
+    #[tokio::test]
+    async fn test_completed_message_fires_webhook() {
+        let (state, delivered) = with_webhooks(AppState::with_llm(RecordingLlm::default()));
+
+        // The queue's worker stops once the last state holding it is dropped
+        let response = app(state.clone())
+            .oneshot(message_request(
+                r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let payload = wait_for_webhook(&delivered).await;
+        assert_eq!(payload["event"], "message.completed");
+        assert_eq!(payload["recipient"], "assistant");
+        assert_eq!(payload["model"], "recording");
+        assert_eq!(payload["reply"], "echo: Hi");
+        assert_eq!(payload["finish_reason"], "stop");
+        assert_eq!(payload["usage"]["total_tokens"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_message_fires_webhook_with_whole_reply() {
+        let (state, delivered) = with_webhooks(chunked(false));
+
+        let response = app(state.clone())
+            .oneshot(stream_request("application/x-ndjson"))
+            .await
+            .unwrap();
+        let lines = ndjson_lines(response).await;
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2]["type"], "done");
+
+        let payload = wait_for_webhook(&delivered).await;
+        assert_eq!(payload["recipient"], "assistant");
+        assert_eq!(payload["reply"], "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_failed_message_fires_no_webhook() {
+        let (state, delivered) = with_webhooks(chunked(true));
+
+        let response = app(state.clone())
+            .oneshot(stream_request("application/x-ndjson"))
+            .await
+            .unwrap();
+        let lines = ndjson_lines(response).await;
+        assert_eq!(lines[2]["type"], "error");
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(delivered.lock().unwrap().is_empty());
+        assert!(state.jobs.unwrap().jobs().await.unwrap().is_empty());
+    }
 }
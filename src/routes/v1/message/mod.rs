@@ -1,12 +1,14 @@
+use crate::server::AppState;
 use axum::{Router, routing::post};
 
 mod handler;
-mod request;
-mod response;
+pub mod request;
+pub mod response;
+mod stream;
 
-pub use handler::send_message;
+pub use handler::{FUEL_HEADER, send_message};
 
 /// Build the message router
-pub fn router() -> Router {
+pub fn router() -> Router<AppState> {
     Router::new().route("/:recipient_id", post(send_message))
 }
@@ -1,10 +1,116 @@
+use crate::adapter::traits::{ContentPart, GenerationOptions};
 use serde::{Deserialize, Serialize};
 
 /// Message in the conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub role: String,
-    pub content: String,
+    pub content: MessageContent,
+}
+
+/// Content of a message: plain text, or an array of text and image parts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl MessageContent {
+    /// Text of the message; the text parts, one per line, for multi-part content
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// Parts of multi-part content (None for plain text)
+    pub fn parts(&self) -> Option<&[ContentPart]> {
+        match self {
+            MessageContent::Text(_) => None,
+            MessageContent::Parts(parts) => Some(parts),
+        }
+    }
+
+    /// Whether the content has an image part
+    pub fn has_images(&self) -> bool {
+        self.parts()
+            .is_some_and(|parts| parts.iter().any(ContentPart::is_image))
+    }
+
+    /// Check that parts are well-formed and images at most `max_image_bytes`
+    pub fn validate(&self, max_image_bytes: usize) -> Result<(), String> {
+        let Some(parts) = self.parts() else {
+            return Ok(());
+        };
+        if parts.is_empty() {
+            return Err("Message content must have at least one part".to_string());
+        }
+
+        for part in parts {
+            match part {
+                ContentPart::Text { .. } => {}
+                ContentPart::Image { data, mime } => {
+                    if !mime.starts_with("image/") {
+                        return Err(format!("Invalid image mime type '{}'", mime));
+                    }
+                    let size = base64_decoded_len(data)
+                        .ok_or_else(|| "Image data must be base64".to_string())?;
+                    if size > max_image_bytes {
+                        return Err(format!(
+                            "Image of {} bytes exceeds the limit of {} bytes",
+                            size, max_image_bytes
+                        ));
+                    }
+                }
+                ContentPart::ImageUrl { url } => {
+                    if !(url.starts_with("https://") || url.starts_with("http://")) {
+                        return Err(format!("Image URL '{}' must be http or https", url));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+/// Size of standard base64 `data` once decoded (None if it isn't base64)
+///
+/// Padding is optional, but must be at the end.
+fn base64_decoded_len(data: &str) -> Option<usize> {
+    let unpadded = data.trim_end_matches('=');
+    let padding = data.len() - unpadded.len();
+    let valid = unpadded
+        .bytes()
+        .all(|byte| byte.is_ascii_alphanumeric() || byte == b'+' || byte == b'/');
+    if !valid || padding > 2 || unpadded.len() % 4 == 1 {
+        return None;
+    }
+    if padding > 0 && !data.len().is_multiple_of(4) {
+        return None;
+    }
+    Some(unpadded.len() * 3 / 4)
 }
 
 /// Request body for sending messages
@@ -12,7 +118,6 @@ pub struct Message {
 pub struct MessageRequest {
     /// Optional sender ID - falls back to default if not provided
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[allow(dead_code)] // TODO: implement sender logic
     pub sender: Option<String>,
 
     /// Optional group ID - falls back to default if not provided
@@ -20,12 +125,77 @@ pub struct MessageRequest {
     #[allow(dead_code)] // TODO: implement group logic
     pub group: Option<String>,
 
+    /// Optional stored conversation to continue
+    ///
+    /// The exchange is appended to it and counted against its token budget.
+    /// Such requests are never streamed.
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+
     /// Array of messages in the conversation
-    #[allow(dead_code)] // TODO: implement message processing
     pub messages: Vec<Message>,
 
+    /// Optional sampling parameters - unset values fall back to config defaults
+    #[serde(default)]
+    pub options: Option<GenerationOptions>,
+
     /// Whether to stream the response (default: false)
+    ///
+    /// The format follows the `Accept` header: NDJSON or SSE, falling back to
+    /// a single JSON response.
     #[serde(default)]
-    #[allow(dead_code)] // TODO: implement streaming
     pub stream: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_content_round_trip() {
+        let text = r#"{"role":"user","content":"Hi"}"#;
+        let message: Message = serde_json::from_str(text).unwrap();
+        assert_eq!(message.content, MessageContent::from("Hi"));
+        assert_eq!(serde_json::to_string(&message).unwrap(), text);
+
+        let parts = r#"{"role":"user","content":[{"type":"text","text":"What's this?"},{"type":"image","data":"iVBORw==","mime":"image/png"},{"type":"image_url","url":"https://example.com/cat.png"}]}"#;
+        let message: Message = serde_json::from_str(parts).unwrap();
+        assert_eq!(message.content.parts().unwrap().len(), 3);
+        assert_eq!(message.content.text(), "What's this?");
+        assert!(message.content.has_images());
+        assert_eq!(serde_json::to_string(&message).unwrap(), parts);
+    }
+
+    #[test]
+    fn test_message_content_validation() {
+        let image = |data: &str, mime: &str| {
+            MessageContent::Parts(vec![ContentPart::Image {
+                data: data.to_string(),
+                mime: mime.to_string(),
+            }])
+        };
+
+        assert!(MessageContent::from("Hi").validate(0).is_ok());
+        assert!(image("iVBORw==", "image/png").validate(4).is_ok());
+        assert!(image("iVBORw", "image/png").validate(4).is_ok());
+        // 4 bytes decoded
+        assert!(
+            image("iVBORw==", "image/png")
+                .validate(3)
+                .unwrap_err()
+                .contains("exceeds the limit")
+        );
+        assert!(image("not base64!", "image/png").validate(100).is_err());
+        assert!(image("iVBORw=", "image/png").validate(100).is_err());
+        assert!(image("iVBORw==", "text/plain").validate(100).is_err());
+        assert!(MessageContent::Parts(vec![]).validate(100).is_err());
+
+        let url = |url: &str| {
+            MessageContent::Parts(vec![ContentPart::ImageUrl {
+                url: url.to_string(),
+            }])
+        };
+        assert!(url("https://example.com/cat.png").validate(0).is_ok());
+        assert!(url("file:///etc/passwd").validate(0).is_err());
+    }
+}
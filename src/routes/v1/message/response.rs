@@ -1,5 +1,11 @@
 use super::request::Message;
-use serde::{Deserialize, Serialize};
+use crate::adapter::traits::{FinishReason, GenerationOptions};
+use crate::server::memory::MemoryReport;
+use crate::server::moderation::ModerationDecision;
+use serde::Serialize;
+
+/// Usage statistics from AI provider
+pub use crate::adapter::traits::Usage;
 
 /// Successful message response
 #[derive(Debug, Serialize)]
@@ -7,25 +13,80 @@ pub struct MessageResponse {
     pub success: bool,
     pub message: Message,
     pub model: String,
-    pub finish_reason: Option<String>,
+    /// Why generation ended, as the adapter reported it (null if it didn't say)
+    pub finish_reason: Option<FinishReason>,
+    /// Stop sequence that ended generation, if the provider reported it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequence: Option<String>,
+    /// Moderation rules that flagged or redacted the messages or the reply
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub moderation: Vec<ModerationDecision>,
+    /// Stored history sent ahead of the messages, with `[conversations.memory]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryReport>,
     pub usage: Option<Usage>,
+    /// Effective sampling parameters, for reproducing the response
+    pub parameters: GenerationOptions,
     pub timestamp: String,
 }
 
 /// Error response for message endpoint
 #[derive(Debug, Serialize)]
-#[allow(dead_code)] // TODO: implement proper HTTP error responses
 pub struct MessageErrorResponse {
     pub success: bool,
     pub error: String,
+    /// What exactly was wrong, e.g. where a body failed to parse
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
     pub error_type: String,
     pub timestamp: String,
 }
 
-/// Usage statistics from AI provider
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Usage {
-    pub prompt_tokens: u32,
-    pub completion_tokens: u32,
-    pub total_tokens: u32,
+/// One piece of a streamed reply
+#[derive(Debug, Serialize)]
+pub struct StreamChunk {
+    /// Position of the chunk in the stream, starting at 0
+    pub sequence: u64,
+    pub content: String,
+}
+
+/// Final event of a successful stream
+#[derive(Debug, Serialize)]
+pub struct StreamEnd {
+    pub model: String,
+    /// Why generation ended, as the adapter reported it (null if it didn't say)
+    pub finish_reason: Option<FinishReason>,
+    /// Stop sequence that ended generation, if the provider reported it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequence: Option<String>,
+    /// Moderation rules that flagged or redacted the messages or the reply
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub moderation: Vec<ModerationDecision>,
+    pub usage: Option<Usage>,
+    /// Effective sampling parameters, for reproducing the response
+    pub parameters: GenerationOptions,
+    pub timestamp: String,
+}
+
+/// Event of a streamed message response
+///
+/// A stream is any number of `chunk` events followed by exactly one `done`
+/// or `error` event.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Chunk(StreamChunk),
+    Done(StreamEnd),
+    Error(MessageErrorResponse),
+}
+
+impl StreamEvent {
+    /// Event name, matching the serialized `type` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamEvent::Chunk(_) => "chunk",
+            StreamEvent::Done(_) => "done",
+            StreamEvent::Error(_) => "error",
+        }
+    }
 }
@@ -0,0 +1,175 @@
+use super::response::{StreamChunk, StreamEvent};
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, header},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, Sse},
+    },
+};
+use futures::{Stream, StreamExt, stream};
+use std::future::Future;
+use tokio::sync::mpsc;
+
+/// Media type of newline-delimited JSON responses
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Media type of server-sent event responses
+pub const SSE_CONTENT_TYPE: &str = "text/event-stream";
+
+/// Wire format of a streamed message response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// One JSON event per line
+    Ndjson,
+    /// Server-sent events with JSON data
+    Sse,
+}
+
+impl StreamFormat {
+    /// Pick the streaming format from the `Accept` header
+    ///
+    /// The first streaming media type listed wins, ignoring quality values.
+    /// `None` means the client gets a single buffered JSON response.
+    pub fn from_accept(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .find_map(|media_range| {
+                let media_type = media_range.split(';').next().unwrap_or_default().trim();
+
+                if media_type.eq_ignore_ascii_case(NDJSON_CONTENT_TYPE) {
+                    Some(StreamFormat::Ndjson)
+                } else if media_type.eq_ignore_ascii_case(SSE_CONTENT_TYPE) {
+                    Some(StreamFormat::Sse)
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+/// Number the adapter's chunks and append the event produced by `finish`
+///
+/// `finish` is only polled once the adapter has closed `chunks`, so it can
+/// wait for the generation result to build the terminal `done` or `error`.
+pub fn events<F>(
+    chunks: mpsc::Receiver<String>,
+    finish: F,
+) -> impl Stream<Item = StreamEvent> + Send + 'static
+where
+    F: Future<Output = StreamEvent> + Send + 'static,
+{
+    let chunk_events = stream::unfold((chunks, 0), |(mut chunks, sequence)| async move {
+        let content = chunks.recv().await?;
+        let event = StreamEvent::Chunk(StreamChunk { content, sequence });
+
+        Some((event, (chunks, sequence + 1)))
+    });
+
+    chunk_events.chain(stream::once(finish))
+}
+
+/// Build the HTTP response delivering `events` in `format`
+///
+/// Dropping the response body (client disconnect) drops the chunk receiver,
+/// which cancels the adapter's stream.
+pub fn stream_response<S>(format: StreamFormat, events: S) -> Response
+where
+    S: Stream<Item = StreamEvent> + Send + 'static,
+{
+    match format {
+        StreamFormat::Ndjson => {
+            let lines = events.map(|event| {
+                let mut line = serde_json::to_vec(&event)?;
+                line.push(b'\n');
+                Ok::<_, serde_json::Error>(line)
+            });
+
+            (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(NDJSON_CONTENT_TYPE),
+                )],
+                Body::from_stream(lines),
+            )
+                .into_response()
+        }
+        StreamFormat::Sse => {
+            let events = events.map(|event| Event::default().event(event.name()).json_data(&event));
+
+            Sse::new(events).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::defaults::DEFAULT_STREAM_BUFFER;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_from_accept() {
+        assert_eq!(
+            StreamFormat::from_accept(&accept("application/x-ndjson")),
+            Some(StreamFormat::Ndjson)
+        );
+        assert_eq!(
+            StreamFormat::from_accept(&accept("text/event-stream")),
+            Some(StreamFormat::Sse)
+        );
+        assert_eq!(
+            StreamFormat::from_accept(&accept("Application/X-NDJSON; charset=utf-8")),
+            Some(StreamFormat::Ndjson)
+        );
+    }
+
+    #[test]
+    fn test_from_accept_first_streaming_type_wins() {
+        assert_eq!(
+            StreamFormat::from_accept(&accept(
+                "application/json, text/event-stream, application/x-ndjson"
+            )),
+            Some(StreamFormat::Sse)
+        );
+    }
+
+    #[test]
+    fn test_from_accept_without_streaming_type() {
+        assert_eq!(StreamFormat::from_accept(&HeaderMap::new()), None);
+        assert_eq!(StreamFormat::from_accept(&accept("application/json")), None);
+        assert_eq!(StreamFormat::from_accept(&accept("*/*")), None);
+    }
+
+    #[tokio::test]
+    async fn test_events_numbers_chunks_and_ends_with_finish() {
+        let (tx, rx) = mpsc::channel(DEFAULT_STREAM_BUFFER);
+        tx.send("Hel".to_string()).await.unwrap();
+        tx.send("lo".to_string()).await.unwrap();
+        drop(tx);
+
+        let events: Vec<serde_json::Value> = events(rx, async {
+            StreamEvent::Chunk(StreamChunk {
+                content: "end".to_string(),
+                sequence: 99,
+            })
+        })
+        .map(|event| serde_json::to_value(&event).unwrap())
+        .collect()
+        .await;
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["sequence"], 0);
+        assert_eq!(events[0]["content"], "Hel");
+        assert_eq!(events[1]["sequence"], 1);
+        assert_eq!(events[2]["sequence"], 99);
+    }
+}
@@ -1,11 +1,38 @@
+pub mod adapters;
+pub mod admin;
+pub mod conversations;
+pub mod images;
 pub mod message;
+pub mod search;
 pub mod sender;
+pub mod speech;
+pub mod transcriptions;
 
+use super::versions::Capabilities;
+use crate::server::AppState;
 use axum::Router;
 
+/// What v1 offers, see `routes::versions`
+pub const CAPABILITIES: Capabilities = Capabilities {
+    version: "v1",
+    message_prefix: Some("/message/"),
+    links: &[
+        ("conversations", "/conversations/{conversation_id}/export"),
+        ("message", "/message/{recipient_id}"),
+        ("sender", "/sender"),
+    ],
+};
+
 /// Build the v1 API router
-pub fn router() -> Router {
+pub fn router() -> Router<AppState> {
     Router::new()
+        .nest("/adapters", adapters::router())
+        .nest("/admin", admin::router())
+        .nest("/conversations", conversations::router())
+        .nest("/images", images::router())
         .nest("/sender", sender::router())
         .nest("/message", message::router())
+        .nest("/search", search::router())
+        .nest("/speech", speech::router())
+        .nest("/transcriptions", transcriptions::router())
 }
@@ -0,0 +1,11 @@
+use super::conversations::search::search_conversations;
+use crate::server::AppState;
+use axum::{Router, routing::get};
+
+/// Build the search router
+///
+/// Searches live outside the routes they search, so no conversation id can
+/// shadow them.
+pub fn router() -> Router<AppState> {
+    Router::new().route("/conversations", get(search_conversations))
+}
@@ -1,8 +1,9 @@
-mod profile;
+pub mod profile;
 
+use crate::server::AppState;
 use axum::Router;
 
 /// Build the sender router
-pub fn router() -> Router {
+pub fn router() -> Router<AppState> {
     Router::new().nest("/profile", profile::router())
 }
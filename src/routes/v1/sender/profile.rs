@@ -1,17 +1,350 @@
-use axum::{Router, http::StatusCode, response::Json, routing::get};
-use serde_json::{Value, json};
+use crate::adapter::traits::ServiceError;
+use crate::routes::error::storage_error;
+use crate::server::{AppState, state::SharedStorage};
+use axum::{
+    Router,
+    extract::{Json, Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+
+/// Sender ID used when a request doesn't specify one
+pub const DEFAULT_SENDER_ID: &str = "default";
+
+/// Sender profile persisted via the storage adapter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SenderProfile {
+    /// Model to use when a request doesn't specify one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+
+    /// Human-readable name of the sender
+    pub display_name: String,
+
+    /// System prompt prepended to every conversation of this sender
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+}
+
+/// Query parameters selecting a sender profile
+#[derive(Debug, Deserialize)]
+pub struct ProfileQuery {
+    /// Sender ID - falls back to default if not provided
+    pub id: Option<String>,
+}
 
 /// Build the sender profile router
-pub fn router() -> Router {
-    Router::new().route("/", get(get_profile))
-}
-
-/// Get sender profile - dummy implementation
-async fn get_profile() -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({
-        "id": "sender",
-        "name": "Default Sender",
-        "status": "active",
-        "message": "This is a dummy sender profile endpoint"
-    })))
+pub fn router() -> Router<AppState> {
+    Router::new().route(
+        "/",
+        get(get_profile).put(put_profile).delete(delete_profile),
+    )
+}
+
+/// Get sender profile
+async fn get_profile(
+    State(state): State<AppState>,
+    Query(query): Query<ProfileQuery>,
+) -> Result<Json<SenderProfile>, StatusCode> {
+    let storage = require_storage(&state)?;
+    let sender_id = resolve_sender_id(&query)?;
+
+    match load_profile(storage, sender_id).await {
+        Ok(Some(profile)) => Ok(Json(profile)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => Err(storage_error(e)),
+    }
+}
+
+/// Create or replace sender profile
+async fn put_profile(
+    State(state): State<AppState>,
+    Query(query): Query<ProfileQuery>,
+    Json(profile): Json<SenderProfile>,
+) -> Result<Json<SenderProfile>, StatusCode> {
+    let storage = require_storage(&state)?;
+    let sender_id = resolve_sender_id(&query)?;
+
+    if profile.display_name.trim().is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let data = serde_json::to_vec(&profile).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    storage
+        .write()
+        .await
+        .store(&profile_key(sender_id), &data)
+        .await
+        .map_err(storage_error)?;
+
+    Ok(Json(profile))
+}
+
+/// Delete sender profile
+async fn delete_profile(
+    State(state): State<AppState>,
+    Query(query): Query<ProfileQuery>,
+) -> Result<StatusCode, StatusCode> {
+    let storage = require_storage(&state)?;
+    let sender_id = resolve_sender_id(&query)?;
+    let key = profile_key(sender_id);
+
+    let mut storage = storage.write().await;
+
+    if !storage.exists(&key).await.map_err(storage_error)? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    storage.delete(&key).await.map_err(storage_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Load a sender profile from storage
+///
+/// Returns `Ok(None)` if no profile has been stored for this sender.
+pub async fn load_profile(
+    storage: &SharedStorage,
+    sender_id: &str,
+) -> Result<Option<SenderProfile>, ServiceError> {
+    let key = profile_key(sender_id);
+    let storage = storage.read().await;
+
+    if !storage.exists(&key).await? {
+        return Ok(None);
+    }
+
+    let data = storage.retrieve(&key).await?;
+    let profile = serde_json::from_slice(&data)
+        .map_err(|e| ServiceError::ExecutionError(format!("Corrupt sender profile: {e}")))?;
+
+    Ok(Some(profile))
+}
+
+/// Check that a sender ID is safe to use as part of a storage key
+pub fn is_valid_sender_id(sender_id: &str) -> bool {
+    !sender_id.is_empty()
+        && sender_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Storage key for a sender profile
+fn profile_key(sender_id: &str) -> String {
+    format!("sender/{}/profile", sender_id)
+}
+
+/// Resolve the sender ID from the query, rejecting unsafe values
+fn resolve_sender_id(query: &ProfileQuery) -> Result<&str, StatusCode> {
+    let sender_id = query.id.as_deref().unwrap_or(DEFAULT_SENDER_ID);
+
+    if is_valid_sender_id(sender_id) {
+        Ok(sender_id)
+    } else {
+        Err(StatusCode::BAD_REQUEST)
+    }
+}
+
+/// Get the storage adapter or report that persistence is unavailable
+fn require_storage(state: &AppState) -> Result<&SharedStorage, StatusCode> {
+    state
+        .storage
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::MemoryStorage;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn app(state: AppState) -> Router {
+        router().with_state(state)
+    }
+
+    fn request(method: &str, uri: &str, body: Option<&str>) -> Request<Body> {
+        let builder = Request::builder().method(method).uri(uri);
+
+        match body {
+            Some(body) => builder
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        }
+    }
+
+    async fn body_json(response: axum::response::Response) -> serde_json::Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_profile_crud() {
+        let state = AppState::with_storage(MemoryStorage::default());
+
+        // Read before create
+        let response = app(state.clone())
+            .oneshot(request("GET", "/", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Create
+        let response = app(state.clone())
+            .oneshot(request(
+                "PUT",
+                "/",
+                Some(r#"{"display_name":"Alice","system_prompt":"Be brief."}"#),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Read
+        let response = app(state.clone())
+            .oneshot(request("GET", "/", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body_json(response).await;
+        assert_eq!(json["display_name"], "Alice");
+        assert_eq!(json["system_prompt"], "Be brief.");
+        assert!(json.get("default_model").is_none());
+
+        // Update
+        let response = app(state.clone())
+            .oneshot(request(
+                "PUT",
+                "/",
+                Some(r#"{"display_name":"Alice","default_model":"llama3.2"}"#),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app(state.clone())
+            .oneshot(request("GET", "/", None))
+            .await
+            .unwrap();
+        let json = body_json(response).await;
+        assert_eq!(json["default_model"], "llama3.2");
+        assert!(json.get("system_prompt").is_none());
+
+        // Delete
+        let response = app(state.clone())
+            .oneshot(request("DELETE", "/", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = app(state.clone())
+            .oneshot(request("GET", "/", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Delete again
+        let response = app(state)
+            .oneshot(request("DELETE", "/", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_profiles_are_keyed_by_sender_id() {
+        let state = AppState::with_storage(MemoryStorage::default());
+
+        app(state.clone())
+            .oneshot(request(
+                "PUT",
+                "/?id=work",
+                Some(r#"{"display_name":"Work"}"#),
+            ))
+            .await
+            .unwrap();
+
+        let response = app(state.clone())
+            .oneshot(request("GET", "/?id=work", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app(state).oneshot(request("GET", "/", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_sender_id_rejected() {
+        let state = AppState::with_storage(MemoryStorage::default());
+
+        let response = app(state)
+            .oneshot(request("GET", "/?id=..%2Fsecret", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_empty_display_name_rejected() {
+        let state = AppState::with_storage(MemoryStorage::default());
+
+        let response = app(state)
+            .oneshot(request("PUT", "/", Some(r#"{"display_name":"  "}"#)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_without_storage_is_unavailable() {
+        let response = app(AppState::default())
+            .oneshot(request("GET", "/", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_load_profile() {
+        let state = AppState::with_storage(MemoryStorage::default());
+        let storage = state.storage.as_ref().unwrap();
+
+        assert_eq!(load_profile(storage, "default").await.unwrap(), None);
+
+        let profile = SenderProfile {
+            default_model: None,
+            display_name: "Bob".to_string(),
+            system_prompt: Some("Hi".to_string()),
+        };
+        storage
+            .write()
+            .await
+            .store(
+                &profile_key("default"),
+                &serde_json::to_vec(&profile).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            load_profile(storage, "default").await.unwrap(),
+            Some(profile)
+        );
+    }
+
+    #[test]
+    fn test_is_valid_sender_id() {
+        assert!(is_valid_sender_id("default"));
+        assert!(is_valid_sender_id("work-profile_2"));
+        assert!(!is_valid_sender_id(""));
+        assert!(!is_valid_sender_id("../etc"));
+        assert!(!is_valid_sender_id("a/b"));
+    }
 }
@@ -0,0 +1,9 @@
+pub mod synthesize;
+
+use crate::server::AppState;
+use axum::{Router, routing::post};
+
+/// Build the speech router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", post(synthesize::synthesize_speech))
+}
@@ -0,0 +1,377 @@
+use crate::adapter::traits::{ServiceError, Speech};
+use crate::config::defaults::DEFAULT_STREAM_BUFFER;
+use crate::routes::error::json_rejection_response;
+use crate::routes::fallback::error_response;
+use crate::server::AppState;
+use axum::{
+    Json,
+    body::Body,
+    extract::{State, rejection::JsonRejection},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Longest text spoken in one request, in characters
+pub const MAX_SPEECH_CHARS: usize = 4096;
+
+/// Body of a speech request
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpeechRequest {
+    /// Text to speak
+    pub text: String,
+    /// Voice to speak it in (provider-specific, default: the provider's)
+    #[serde(default)]
+    pub voice: Option<String>,
+}
+
+/// Speak a text with the TTS adapter
+///
+/// The audio is streamed back as the adapter produces it, in its encoding,
+/// with its MIME type as `Content-Type`. Synthesis runs in its own task
+/// feeding a bounded channel, like message streams; a client that goes
+/// away drops the channel, which stops the adapter.
+pub async fn synthesize_speech(
+    State(state): State<AppState>,
+    request: Result<Json<SpeechRequest>, JsonRejection>,
+) -> Result<Response, Response> {
+    let Json(request) = request.map_err(json_rejection_response)?;
+    if request.text.trim().is_empty() {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "text must not be empty",
+        ));
+    }
+    if request.text.chars().count() > MAX_SPEECH_CHARS {
+        return Err(error_response(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            &format!("text is limited to {} characters", MAX_SPEECH_CHARS),
+        ));
+    }
+
+    let tts = state.tts.clone().ok_or_else(|| {
+        error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No TTS adapter is configured",
+        )
+    })?;
+    let buffer = state.stream_buffer.unwrap_or(DEFAULT_STREAM_BUFFER).max(1);
+    let (chunks, mut receiver) = mpsc::channel(buffer);
+    let synthesis = tokio::spawn(async move {
+        tts.write()
+            .await
+            .stream_speech(&request.text, request.voice.as_deref(), chunks)
+            .await
+    });
+
+    // Nothing is sent until the first chunk tells the audio's MIME type
+    let Some(first) = receiver.recv().await else {
+        return Err(match synthesis.await {
+            Ok(Err(error)) => error.into_response(),
+            Ok(Ok(())) => invalid_audio("no audio"),
+            Err(error) => {
+                tracing::error!("TTS task failed: {}", error);
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, "Speech synthesis failed")
+            }
+        });
+    };
+    let content_type =
+        HeaderValue::from_str(&first.mime_type).map_err(|_| invalid_audio(&first.mime_type))?;
+
+    Ok((
+        [(header::CONTENT_TYPE, content_type)],
+        stream_audio(first, receiver, synthesis),
+    )
+        .into_response())
+}
+
+/// Error response for a TTS adapter answering with something unusable
+fn invalid_audio(answer: &str) -> Response {
+    tracing::error!("TTS adapter returned invalid audio: {:?}", answer);
+    error_response(
+        StatusCode::BAD_GATEWAY,
+        "Invalid audio from the TTS adapter",
+    )
+}
+
+/// Stream `first` and the chunks still to come as the response body
+///
+/// If synthesis fails after the status line went out, the body is cut off
+/// with an error instead of ending like a complete clip.
+fn stream_audio(
+    first: Speech,
+    receiver: mpsc::Receiver<Speech>,
+    synthesis: JoinHandle<Result<(), ServiceError>>,
+) -> Body {
+    let rest = stream::unfold(
+        (receiver, Some(synthesis)),
+        |(mut receiver, synthesis)| async move {
+            if let Some(speech) = receiver.recv().await {
+                return Some((Ok(speech.audio), (receiver, synthesis)));
+            }
+            let error = match synthesis?.await {
+                Ok(Ok(())) => return None,
+                Ok(Err(error)) => error.to_string(),
+                Err(error) => error.to_string(),
+            };
+            tracing::error!("Speech synthesis failed while streaming: {}", error);
+            Some((Err(std::io::Error::other(error)), (receiver, None)))
+        },
+    );
+
+    Body::from_stream(stream::once(async move { Ok(first.audio) }).chain(rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::services::silence::SilenceTts;
+    use crate::adapter::traits::{AdapterService, ServiceError, Speech, TtsAdapter};
+    use async_trait::async_trait;
+    use axum::Router;
+    use axum::body::to_bytes;
+    use axum::http::Request;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    /// TTS adapter recording the voice it's asked for
+    #[derive(Default)]
+    struct RecordingTts {
+        voice: Arc<Mutex<Option<String>>>,
+    }
+
+    #[async_trait]
+    impl AdapterService for RecordingTts {
+        fn service_name(&self) -> &'static str {
+            "tts"
+        }
+
+        fn provider_name(&self) -> &str {
+            "recording"
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl TtsAdapter for RecordingTts {
+        async fn synthesize(
+            &mut self,
+            text: &str,
+            voice: Option<&str>,
+        ) -> Result<Speech, ServiceError> {
+            if text == "fail" {
+                return Err(ServiceError::ProviderError {
+                    status: 500,
+                    message: "synthesis failed".to_string(),
+                    retry_after_secs: None,
+                });
+            }
+            *self.voice.lock().unwrap() = voice.map(str::to_string);
+            Ok(Speech {
+                audio: vec![7; 20_000],
+                mime_type: "audio/mpeg".to_string(),
+            })
+        }
+    }
+
+    fn app(state: AppState) -> Router {
+        super::super::router().with_state(state)
+    }
+
+    fn speech_request(body: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_speech_streams_audio() {
+        let tts = RecordingTts::default();
+        let voice = tts.voice.clone();
+
+        let response = app(AppState::with_tts(tts))
+            .oneshot(speech_request(r#"{"text":"Hello","voice":"alloy"}"#))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "audio/mpeg");
+        let audio = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(audio, vec![7; 20_000]);
+        assert_eq!(voice.lock().unwrap().as_deref(), Some("alloy"));
+    }
+
+    /// TTS adapter streaming `chunks` clips, then failing if `fail` is set
+    struct ChunkedTts {
+        chunks: usize,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl AdapterService for ChunkedTts {
+        fn service_name(&self) -> &'static str {
+            "tts"
+        }
+
+        fn provider_name(&self) -> &str {
+            "chunked"
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl TtsAdapter for ChunkedTts {
+        async fn synthesize(
+            &mut self,
+            _text: &str,
+            _voice: Option<&str>,
+        ) -> Result<Speech, ServiceError> {
+            unreachable!("the endpoint streams")
+        }
+
+        async fn stream_speech(
+            &mut self,
+            _text: &str,
+            _voice: Option<&str>,
+            chunks: mpsc::Sender<Speech>,
+        ) -> Result<(), ServiceError> {
+            for index in 0..self.chunks {
+                let speech = Speech {
+                    audio: vec![index as u8; 4],
+                    mime_type: "audio/ogg".to_string(),
+                };
+                if chunks.send(speech).await.is_err() {
+                    return Ok(());
+                }
+            }
+            if self.fail {
+                return Err(ServiceError::Timeout("provider stalled".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_speech_streams_adapter_chunks() {
+        let tts = ChunkedTts {
+            chunks: 3,
+            fail: false,
+        };
+        let response = app(AppState::with_tts(tts))
+            .oneshot(speech_request(r#"{"text":"Hello"}"#))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "audio/ogg");
+        let audio = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&audio[..], &[0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_speech_failing_mid_stream_cuts_the_body() {
+        let tts = ChunkedTts {
+            chunks: 2,
+            fail: true,
+        };
+        let response = app(AppState::with_tts(tts))
+            .oneshot(speech_request(r#"{"text":"Hello"}"#))
+            .await
+            .unwrap();
+
+        // The status line went out with the first chunk
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(to_bytes(response.into_body(), usize::MAX).await.is_err());
+
+        // Failing before any audio is an ordinary error response
+        let tts = ChunkedTts {
+            chunks: 0,
+            fail: true,
+        };
+        let response = app(AppState::with_tts(tts))
+            .oneshot(speech_request(r#"{"text":"Hello"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_speech_with_built_in_stub() {
+        let response = app(AppState::with_tts(SilenceTts))
+            .oneshot(speech_request(r#"{"text":"Hi"}"#))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "audio/wav");
+        let audio = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&audio[..4], b"RIFF");
+    }
+
+    #[tokio::test]
+    async fn test_speech_rejects_invalid_requests() {
+        let long_text = format!(r#"{{"text":"{}"}}"#, "a".repeat(MAX_SPEECH_CHARS + 1));
+        for (body, status) in [
+            (r#"{"text":"  "}"#, StatusCode::BAD_REQUEST),
+            (long_text.as_str(), StatusCode::PAYLOAD_TOO_LARGE),
+            (r#"{"voice":"alloy"}"#, StatusCode::BAD_REQUEST),
+            (r#"{"text":"Hello""#, StatusCode::BAD_REQUEST),
+        ] {
+            let response = app(AppState::with_tts(RecordingTts::default()))
+                .oneshot(speech_request(body))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), status, "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_speech_errors() {
+        // Without a TTS adapter
+        let response = app(AppState::default())
+            .oneshot(speech_request(r#"{"text":"Hello"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // The adapter failing
+        let response = app(AppState::with_tts(RecordingTts::default()))
+            .oneshot(speech_request(r#"{"text":"fail"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error_type"], "provider_error");
+    }
+}
@@ -0,0 +1,9 @@
+pub mod transcribe;
+
+use crate::server::AppState;
+use axum::{Router, routing::post};
+
+/// Build the transcriptions router
+pub fn router() -> Router<AppState> {
+    Router::new().route("/", post(transcribe::transcribe_audio))
+}
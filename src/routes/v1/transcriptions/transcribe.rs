@@ -0,0 +1,353 @@
+use crate::config::defaults::DEFAULT_MAX_AUDIO_BYTES;
+use crate::routes::fallback::error_response;
+use crate::server::AppState;
+use axum::{
+    Json,
+    extract::{Multipart, State, multipart::Field},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+/// Multipart field carrying the audio
+const FILE_FIELD: &str = "file";
+
+/// Audio encodings accepted for transcription
+pub const AUDIO_MIME_TYPES: [&str; 11] = [
+    "audio/flac",
+    "audio/m4a",
+    "audio/mp4",
+    "audio/mpeg",
+    "audio/ogg",
+    "audio/wav",
+    "audio/wave",
+    "audio/webm",
+    "audio/x-flac",
+    "audio/x-m4a",
+    "audio/x-wav",
+];
+
+/// Body of a transcription response
+#[derive(Debug, Serialize)]
+pub struct TranscriptionResponse {
+    pub text: String,
+}
+
+/// Transcribe an uploaded audio file with the STT adapter
+///
+/// The audio is the `file` field of a `multipart/form-data` body, with its
+/// encoding as the field's `Content-Type`. Files larger than `[limits]
+/// max_audio_bytes` are rejected without reading them to the end.
+pub async fn transcribe_audio(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<Json<TranscriptionResponse>, Response> {
+    let max_bytes = state.max_audio_bytes.unwrap_or(DEFAULT_MAX_AUDIO_BYTES);
+    let mut upload = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(invalid_upload)? {
+        match field.name() {
+            Some(FILE_FIELD) if upload.is_none() => {
+                let mime_type = audio_mime_type(&field)?;
+                upload = Some((read_audio(field, max_bytes).await?, mime_type));
+            }
+            Some(FILE_FIELD) => {
+                return Err(error_response(
+                    StatusCode::BAD_REQUEST,
+                    "Only one file can be transcribed per request",
+                ));
+            }
+            name => {
+                return Err(error_response(
+                    StatusCode::BAD_REQUEST,
+                    &format!("Unknown field {:?}", name.unwrap_or_default()),
+                ));
+            }
+        }
+    }
+
+    let Some((audio, mime_type)) = upload else {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "The audio must be uploaded as the `file` field",
+        ));
+    };
+    if audio.is_empty() {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "The audio file is empty",
+        ));
+    }
+
+    let stt = state.stt.as_ref().ok_or_else(|| {
+        error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No STT adapter is configured",
+        )
+    })?;
+    let text = stt
+        .write()
+        .await
+        .transcribe(&audio, &mime_type)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    Ok(Json(TranscriptionResponse { text }))
+}
+
+/// Encoding of the uploaded audio, without parameters such as `codecs`
+#[allow(clippy::result_large_err)] // Only built for rejected uploads
+fn audio_mime_type(field: &Field<'_>) -> Result<String, Response> {
+    let Some(content_type) = field.content_type() else {
+        return Err(error_response(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "The audio file needs a Content-Type",
+        ));
+    };
+    let mime_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if !AUDIO_MIME_TYPES.contains(&mime_type.as_str()) {
+        return Err(error_response(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            &format!(
+                "Unsupported audio type {:?}, expected one of: {}",
+                content_type,
+                AUDIO_MIME_TYPES.join(", ")
+            ),
+        ));
+    }
+
+    Ok(mime_type)
+}
+
+/// Read the audio of `field`, stopping once it's over `max_bytes`
+async fn read_audio(mut field: Field<'_>, max_bytes: usize) -> Result<Vec<u8>, Response> {
+    let mut audio = Vec::new();
+    while let Some(chunk) = field.chunk().await.map_err(invalid_upload)? {
+        if audio.len() + chunk.len() > max_bytes {
+            return Err(error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                &format!("Audio files are limited to {} bytes", max_bytes),
+            ));
+        }
+        audio.extend_from_slice(&chunk);
+    }
+
+    Ok(audio)
+}
+
+fn invalid_upload(error: axum::extract::multipart::MultipartError) -> Response {
+    error_response(error.status(), &error.body_text())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::traits::{AdapterService, ServiceError, SttAdapter};
+    use async_trait::async_trait;
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    const BOUNDARY: &str = "transcription-test";
+
+    /// STT adapter recording the audio it's asked to transcribe
+    #[derive(Default)]
+    struct RecordingStt {
+        received: Arc<Mutex<Option<(usize, String)>>>,
+    }
+
+    #[async_trait]
+    impl AdapterService for RecordingStt {
+        fn service_name(&self) -> &'static str {
+            "stt"
+        }
+
+        fn provider_name(&self) -> &str {
+            "recording"
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl SttAdapter for RecordingStt {
+        async fn transcribe(
+            &mut self,
+            audio: &[u8],
+            mime_type: &str,
+        ) -> Result<String, ServiceError> {
+            if audio == b"fail" {
+                return Err(ServiceError::ProviderError {
+                    status: 500,
+                    message: "transcription failed".to_string(),
+                    retry_after_secs: None,
+                });
+            }
+            *self.received.lock().unwrap() = Some((audio.len(), mime_type.to_string()));
+            Ok("Hello".to_string())
+        }
+    }
+
+    fn app(state: AppState) -> Router {
+        super::super::router().with_state(state)
+    }
+
+    /// One multipart part as `(name, content type, content)`
+    type Part<'a> = (&'a str, Option<&'a str>, &'a [u8]);
+
+    /// Multipart request with one part per `Part`
+    fn upload_request(parts: &[Part]) -> Request<Body> {
+        let mut body = Vec::new();
+        for (name, content_type, content) in parts {
+            body.extend_from_slice(format!("--{BOUNDARY}\r\n").as_bytes());
+            body.extend_from_slice(
+                format!("Content-Disposition: form-data; name=\"{name}\"; filename=\"audio\"\r\n")
+                    .as_bytes(),
+            );
+            if let Some(content_type) = content_type {
+                body.extend_from_slice(format!("Content-Type: {content_type}\r\n").as_bytes());
+            }
+            body.extend_from_slice(b"\r\n");
+            body.extend_from_slice(content);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+
+        Request::builder()
+            .method("POST")
+            .uri("/")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transcription_returns_text() {
+        let stt = RecordingStt::default();
+        let received = stt.received.clone();
+
+        let response = app(AppState::with_stt(stt))
+            .oneshot(upload_request(&[(
+                "file",
+                Some("Audio/Ogg; codecs=opus"),
+                b"OggS audio",
+            )]))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            body_json(response).await,
+            serde_json::json!({"text": "Hello"})
+        );
+        assert_eq!(
+            *received.lock().unwrap(),
+            Some((10, "audio/ogg".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transcription_rejects_invalid_uploads() {
+        let wav = Some("audio/wav");
+        let cases: [(&[Part], StatusCode); 6] = [
+            (&[], StatusCode::BAD_REQUEST),
+            (&[("file", wav, b"")], StatusCode::BAD_REQUEST),
+            (&[("model", None, b"whisper-1")], StatusCode::BAD_REQUEST),
+            (
+                &[("file", wav, b"one"), ("file", wav, b"two")],
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                &[("file", Some("video/mp4"), b"audio")],
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ),
+            (
+                &[("file", None, b"audio")],
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ),
+        ];
+        for (parts, status) in cases {
+            let response = app(AppState::with_stt(RecordingStt::default()))
+                .oneshot(upload_request(parts))
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), status, "{:?}", parts);
+        }
+
+        // Not a multipart body at all
+        let request = Request::builder()
+            .method("POST")
+            .uri("/")
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = app(AppState::with_stt(RecordingStt::default()))
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_transcription_enforces_max_audio_bytes() {
+        let state = || AppState {
+            max_audio_bytes: Some(4),
+            ..AppState::with_stt(RecordingStt::default())
+        };
+
+        let response = app(state())
+            .oneshot(upload_request(&[("file", Some("audio/wav"), b"12345")]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = app(state())
+            .oneshot(upload_request(&[("file", Some("audio/wav"), b"1234")]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_transcription_errors() {
+        let upload = || upload_request(&[("file", Some("audio/mpeg"), b"fail")]);
+
+        // Without an STT adapter
+        let response = app(AppState::default()).oneshot(upload()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // The adapter failing
+        let response = app(AppState::with_stt(RecordingStt::default()))
+            .oneshot(upload())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(body_json(response).await["error_type"], "provider_error");
+    }
+}
@@ -0,0 +1,294 @@
+//! API versions served side by side under the base path
+//!
+//! Each version module exposes a `router` factory and its `CAPABILITIES`;
+//! `API_VERSIONS` lists the ones this build implements. Which of them are
+//! mounted, and whether they're deprecated, comes from `[server] api_versions`.
+
+use crate::config::schema::ApiVersionConfig;
+use crate::server::AppState;
+use anyhow::{Context, Result};
+use axum::{
+    Router,
+    http::{HeaderName, HeaderValue, header},
+    response::{Json, Response},
+};
+use chrono::NaiveDate;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use super::v1;
+
+/// Header marking responses of a deprecated version
+pub const DEPRECATION_HEADER: &str = "deprecation";
+
+/// Header with the date a version goes away
+pub const SUNSET_HEADER: &str = "sunset";
+
+/// What an API version offers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Path segment it's mounted at, e.g. "v1"
+    pub version: &'static str,
+    /// Prefix of its message routes below the version path (e.g. "/message/"),
+    /// which get the message timeout and idempotency keys
+    pub message_prefix: Option<&'static str>,
+    /// Endpoints linked from the root document, relative to the version path
+    pub links: &'static [(&'static str, &'static str)],
+}
+
+/// An API version this build implements
+#[derive(Clone, Copy)]
+pub struct ApiVersion {
+    pub capabilities: Capabilities,
+    /// Builds its routes
+    pub router: fn() -> Router<AppState>,
+}
+
+/// Versions this build implements
+pub const API_VERSIONS: [ApiVersion; 1] = [ApiVersion {
+    capabilities: v1::CAPABILITIES,
+    router: v1::router,
+}];
+
+/// A version to mount, with its lifecycle
+#[derive(Clone)]
+pub struct MountedVersion {
+    pub api: ApiVersion,
+    /// Responses get `Deprecation` headers
+    pub deprecated: bool,
+    /// Date it goes away, announced in `Sunset` headers
+    pub sunset: Option<NaiveDate>,
+}
+
+impl MountedVersion {
+    /// `api`, neither deprecated nor sunset
+    pub fn stable(api: ApiVersion) -> Self {
+        MountedVersion {
+            api,
+            deprecated: false,
+            sunset: None,
+        }
+    }
+
+    /// Name of the version, e.g. "v1"
+    pub fn name(&self) -> &'static str {
+        self.api.capabilities.version
+    }
+
+    /// "deprecated" or "stable"
+    pub fn status(&self) -> &'static str {
+        if self.deprecated {
+            "deprecated"
+        } else {
+            "stable"
+        }
+    }
+
+    /// Headers announcing the version's deprecation and sunset
+    ///
+    /// Deprecated versions link to the discovery document at `versions_path`.
+    pub fn lifecycle_headers(&self, versions_path: &str) -> Vec<(HeaderName, HeaderValue)> {
+        let mut headers = Vec::new();
+        if self.deprecated {
+            headers.push((
+                HeaderName::from_static(DEPRECATION_HEADER),
+                HeaderValue::from_static("true"),
+            ));
+            let link = format!("<{}>; rel=\"deprecation\"", versions_path);
+            if let Ok(link) = HeaderValue::from_str(&link) {
+                headers.push((header::LINK, link));
+            }
+        }
+        if let Some(sunset) = self.sunset {
+            // An HTTP-date, the start of the sunset day in UTC
+            let date = sunset
+                .and_time(chrono::NaiveTime::MIN)
+                .and_utc()
+                .format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string();
+            if let Ok(date) = HeaderValue::from_str(&date) {
+                headers.push((HeaderName::from_static(SUNSET_HEADER), date));
+            }
+        }
+        headers
+    }
+}
+
+/// The versions the router mounts, in `[server] api_versions` order
+#[derive(Clone)]
+pub struct ApiVersions(Arc<[MountedVersion]>);
+
+impl Default for ApiVersions {
+    /// Every version this build implements, as stable
+    fn default() -> Self {
+        ApiVersions::new(
+            API_VERSIONS
+                .iter()
+                .copied()
+                .map(MountedVersion::stable)
+                .collect(),
+        )
+    }
+}
+
+impl ApiVersions {
+    pub fn new(versions: Vec<MountedVersion>) -> Self {
+        ApiVersions(versions.into())
+    }
+
+    /// Look up the versions listed in `config`
+    ///
+    /// Versions this build doesn't implement, duplicates, an empty list and
+    /// sunset dates that aren't YYYY-MM-DD are errors.
+    pub fn from_config(config: &[ApiVersionConfig]) -> Result<Self> {
+        if config.is_empty() {
+            anyhow::bail!("[server] api_versions must list at least one version");
+        }
+
+        let mut versions: Vec<MountedVersion> = Vec::with_capacity(config.len());
+        for entry in config {
+            let Some(api) = API_VERSIONS
+                .iter()
+                .find(|api| api.capabilities.version == entry.version)
+            else {
+                let known: Vec<_> = API_VERSIONS
+                    .iter()
+                    .map(|api| api.capabilities.version)
+                    .collect();
+                anyhow::bail!(
+                    "Unknown API version '{}' (available: {})",
+                    entry.version,
+                    known.join(", ")
+                );
+            };
+            if versions
+                .iter()
+                .any(|version| version.name() == entry.version)
+            {
+                anyhow::bail!("API version '{}' is listed twice", entry.version);
+            }
+            let sunset = entry
+                .sunset
+                .as_deref()
+                .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+                .transpose()
+                .with_context(|| {
+                    format!(
+                        "Invalid sunset of API version '{}', expected YYYY-MM-DD",
+                        entry.version
+                    )
+                })?;
+
+            versions.push(MountedVersion {
+                api: *api,
+                deprecated: entry.deprecated,
+                sunset,
+            });
+        }
+
+        Ok(ApiVersions::new(versions))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MountedVersion> {
+        self.0.iter()
+    }
+}
+
+/// Discovery document listing each mounted version at its path
+///
+/// `versions` pairs each version with the path it's mounted at.
+pub fn versions_document(versions: &[(MountedVersion, String)]) -> Value {
+    let versions: Vec<Value> = versions
+        .iter()
+        .map(|(version, path)| {
+            let mut entry = json!({
+                "version": version.name(),
+                "path": path,
+                "status": version.status(),
+            });
+            if let Some(sunset) = version.sunset {
+                entry["sunset"] = json!(sunset.format("%Y-%m-%d").to_string());
+            }
+            entry
+        })
+        .collect();
+
+    json!({ "versions": versions })
+}
+
+/// Version discovery endpoint - at /versions under the base path
+pub async fn list_versions(document: Value) -> Json<Value> {
+    Json(document)
+}
+
+/// Add `headers` to a response of a deprecated or sunset version
+pub async fn add_lifecycle_headers(
+    headers: Arc<Vec<(HeaderName, HeaderValue)>>,
+    mut response: Response,
+) -> Response {
+    for (name, value) in headers.iter() {
+        response.headers_mut().insert(name.clone(), value.clone());
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(version: &str, sunset: Option<&str>) -> ApiVersionConfig {
+        ApiVersionConfig {
+            deprecated: true,
+            sunset: sunset.map(str::to_string),
+            version: version.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_from_config() {
+        let versions = ApiVersions::from_config(&[entry("v1", Some("2027-01-01"))]).unwrap();
+        let v1 = versions.iter().next().unwrap();
+        assert_eq!(v1.name(), "v1");
+        assert_eq!(v1.status(), "deprecated");
+        assert_eq!(v1.sunset, NaiveDate::from_ymd_opt(2027, 1, 1));
+    }
+
+    #[test]
+    fn test_from_config_rejects_invalid_lists() {
+        for (config, error) in [
+            (Vec::new(), "at least one version"),
+            (
+                vec![entry("v9", None)],
+                "Unknown API version 'v9' (available: v1)",
+            ),
+            (vec![entry("v1", None), entry("v1", None)], "listed twice"),
+            (vec![entry("v1", Some("soon"))], "Invalid sunset"),
+        ] {
+            let result = ApiVersions::from_config(&config);
+            let message = format!("{:#}", result.err().unwrap());
+            assert!(message.contains(error), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_lifecycle_headers() {
+        let stable = MountedVersion::stable(API_VERSIONS[0]);
+        assert!(stable.lifecycle_headers("/versions").is_empty());
+
+        let sunset = MountedVersion {
+            deprecated: true,
+            sunset: NaiveDate::from_ymd_opt(2027, 1, 1),
+            ..stable
+        };
+        let headers = sunset.lifecycle_headers("/api/versions");
+        let value = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header == name)
+                .map(|(_, value)| value.to_str().unwrap())
+        };
+        assert_eq!(value("deprecation"), Some("true"));
+        assert_eq!(value("sunset"), Some("Fri, 01 Jan 2027 00:00:00 GMT"));
+        assert_eq!(value("link"), Some("</api/versions>; rel=\"deprecation\""));
+    }
+}
@@ -0,0 +1,276 @@
+use crate::config::schema::{AccessLogConfig, AccessLogMode};
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{Request, State},
+    http::{HeaderMap, header},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Tracing target of access log events (e.g. `RUST_LOG=access=debug`)
+pub const ACCESS_LOG_TARGET: &str = "access";
+
+/// Bodies larger than this are neither buffered nor logged
+pub const MAX_LOGGED_BODY_BYTES: usize = 16 * 1024;
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// State of the access log middleware
+#[derive(Debug, Clone)]
+pub struct AccessLog {
+    bodies: bool,
+    mode: AccessLogMode,
+    /// Lowercased header and JSON field names to redact
+    redact: Arc<[String]>,
+}
+
+impl AccessLog {
+    /// Read the settings from `[server.access_log]`
+    pub fn from_config(config: &AccessLogConfig) -> Self {
+        AccessLog {
+            bodies: config.bodies,
+            mode: config.mode,
+            redact: config
+                .redact
+                .iter()
+                .map(|name| name.to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Whether the middleware needs to run at all
+    pub fn is_enabled(&self) -> bool {
+        self.mode != AccessLogMode::Off
+    }
+
+    fn is_redacted(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.redact.contains(&name)
+    }
+
+    /// Headers as "name: value" pairs, with redacted values replaced
+    fn format_headers(&self, headers: &HeaderMap) -> String {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_redacted(name.as_str()) {
+                    REDACTED
+                } else {
+                    value.to_str().unwrap_or("<binary>")
+                };
+                format!("{}: {}", name, value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Body for the log, with redacted JSON fields replaced
+    ///
+    /// Only JSON can be redacted reliably, so other bodies are summarized.
+    fn format_body(&self, body: &[u8]) -> String {
+        match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(mut json) => {
+                self.redact_json(&mut json);
+                json.to_string()
+            }
+            Err(_) => format!("<{} bytes, not JSON>", body.len()),
+        }
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(fields) => {
+                for (name, field) in fields.iter_mut() {
+                    if self.is_redacted(name) {
+                        *field = serde_json::Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(field);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for item in items {
+                    self.redact_json(item);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for AccessLog {
+    fn default() -> Self {
+        AccessLog::from_config(&AccessLogConfig::default())
+    }
+}
+
+/// Log method, path, status and latency of every request
+///
+/// In verbose mode headers are logged too, and with `bodies` enabled the
+/// JSON bodies, both at debug level. Bodies are only buffered when their
+/// size is known and small, so streamed responses pass through untouched.
+pub async fn log_requests(State(log): State<AccessLog>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let started = Instant::now();
+
+    if log.mode == AccessLogMode::Verbose {
+        tracing::debug!(
+            target: ACCESS_LOG_TARGET,
+            "{} {} request headers: {}",
+            method,
+            path,
+            log.format_headers(request.headers())
+        );
+    }
+
+    let request = if log.bodies {
+        let length = request
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        let (parts, body) = request.into_parts();
+        let body = log_body(&log, body, length, &format!("{} {} request", method, path)).await;
+        Request::from_parts(parts, body)
+    } else {
+        request
+    };
+
+    let response = next.run(request).await;
+    let status = response.status();
+
+    tracing::info!(
+        target: ACCESS_LOG_TARGET,
+        method = %method,
+        path = %path,
+        status = status.as_u16(),
+        latency_ms = started.elapsed().as_millis() as u64,
+        "{} {} {} in {:?}",
+        method,
+        path,
+        status.as_u16(),
+        started.elapsed()
+    );
+
+    if log.mode == AccessLogMode::Verbose {
+        tracing::debug!(
+            target: ACCESS_LOG_TARGET,
+            "{} {} response headers: {}",
+            method,
+            path,
+            log.format_headers(response.headers())
+        );
+    }
+
+    if log.bodies {
+        let (parts, body) = response.into_parts();
+        let length = body.size_hint().exact();
+        let body = log_body(&log, body, length, &format!("{} {} response", method, path)).await;
+        Response::from_parts(parts, body)
+    } else {
+        response
+    }
+}
+
+/// Log `body` if it's small enough to buffer, and return it for sending on
+async fn log_body(log: &AccessLog, body: Body, length: Option<u64>, label: &str) -> Body {
+    match length {
+        Some(0) => body,
+        Some(length) if length <= MAX_LOGGED_BODY_BYTES as u64 => {
+            match axum::body::to_bytes(body, MAX_LOGGED_BODY_BYTES).await {
+                Ok(bytes) => {
+                    tracing::debug!(
+                        target: ACCESS_LOG_TARGET,
+                        "{} body: {}",
+                        label,
+                        log.format_body(&bytes)
+                    );
+                    Body::from(bytes)
+                }
+                Err(e) => {
+                    tracing::debug!(target: ACCESS_LOG_TARGET, "{} body unreadable: {}", label, e);
+                    Body::from(Bytes::new())
+                }
+            }
+        }
+        _ => {
+            tracing::debug!(
+                target: ACCESS_LOG_TARGET,
+                "{} body not logged (streamed or larger than {} bytes)",
+                label,
+                MAX_LOGGED_BODY_BYTES
+            );
+            body
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn verbose_with_bodies() -> AccessLog {
+        AccessLog::from_config(&AccessLogConfig {
+            bodies: true,
+            mode: AccessLogMode::Verbose,
+            ..AccessLogConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_default_is_disabled() {
+        assert!(!AccessLog::default().is_enabled());
+        assert!(verbose_with_bodies().is_enabled());
+    }
+
+    #[test]
+    fn test_headers_redacted() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer secret"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+
+        let formatted = verbose_with_bodies().format_headers(&headers);
+
+        assert!(formatted.contains("authorization: [REDACTED]"));
+        assert!(formatted.contains("content-type: application/json"));
+        assert!(!formatted.contains("secret"));
+    }
+
+    #[test]
+    fn test_json_body_redacted_at_any_depth() {
+        let log = AccessLog::from_config(&AccessLogConfig {
+            redact: vec!["API_KEY".to_string()],
+            ..AccessLogConfig::default()
+        });
+
+        let body = br#"{"api_key":"sk-1","options":[{"Api_Key":"sk-2","model":"llama3.2"}]}"#;
+        let formatted = log.format_body(body);
+
+        assert!(!formatted.contains("sk-1"));
+        assert!(!formatted.contains("sk-2"));
+        assert!(formatted.contains("llama3.2"));
+    }
+
+    #[test]
+    fn test_non_json_body_summarized() {
+        assert_eq!(
+            verbose_with_bodies().format_body(b"password=hunter2"),
+            "<16 bytes, not JSON>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_logged_body_is_passed_on() {
+        let log = verbose_with_bodies();
+
+        let body = log_body(&log, Body::from("{\"a\":1}"), Some(7), "test").await;
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+
+        assert_eq!(&bytes[..], b"{\"a\":1}");
+    }
+}
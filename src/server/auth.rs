@@ -0,0 +1,252 @@
+use crate::config::schema::AuthConfig;
+use crate::routes::fallback::error_response;
+use anyhow::{Context, Result, bail};
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Caller of an authenticated request, added to its extensions
+///
+/// Tells API keys apart without holding them, e.g. to keep one client's
+/// idempotent responses from another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ApiKeyId(u64);
+
+impl ApiKeyId {
+    fn of(key: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        ApiKeyId(hasher.finish())
+    }
+}
+
+/// Accepted API keys and the paths that don't need one
+#[derive(Debug)]
+pub struct ApiKeys {
+    exempt: Vec<String>,
+    keys: Vec<String>,
+}
+
+impl ApiKeys {
+    /// Build the key set from config, reading `keys_file` if set
+    ///
+    /// Fails if no keys are configured at all, so an auth block can never
+    /// silently lock everyone out or leave the API open.
+    pub fn from_config(config: &AuthConfig, config_dir: Option<&Path>) -> Result<Self> {
+        let mut keys: Vec<String> = config
+            .keys
+            .iter()
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+            .collect();
+
+        if let Some(keys_file) = &config.keys_file {
+            let path = crate::config::expand_required_path(keys_file, config_dir);
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read API keys file: {}", path.display()))?;
+
+            keys.extend(parse_keys_file(&content));
+        }
+
+        if keys.is_empty() {
+            bail!("[server.auth] is configured but no API keys were provided");
+        }
+
+        Ok(ApiKeys {
+            exempt: config.exempt.clone(),
+            keys,
+        })
+    }
+
+    /// Check whether a request path is exempt from authentication
+    fn is_exempt(&self, path: &str) -> bool {
+        self.exempt.iter().any(|exempt| exempt == path)
+    }
+
+    /// Check a presented key against all accepted keys in constant time
+    fn accepts(&self, presented: &str) -> bool {
+        // Compare against every key so timing doesn't reveal which one matched
+        self.keys.iter().fold(false, |matched, key| {
+            matched | constant_time_eq(key.as_bytes(), presented.as_bytes())
+        })
+    }
+}
+
+/// Middleware rejecting requests without a valid `Authorization: Bearer` key
+///
+/// Accepted requests carry the `ApiKeyId` of their key.
+pub async fn require_api_key(
+    State(keys): State<Arc<ApiKeys>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if keys.is_exempt(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+
+    match presented {
+        Some(key) if keys.accepts(key) => {
+            let id = ApiKeyId::of(key);
+            request.extensions_mut().insert(id);
+            next.run(request).await
+        }
+        _ => {
+            let mut response = error_response(StatusCode::UNAUTHORIZED, "unauthorized");
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            response
+        }
+    }
+}
+
+/// Parse a keys file: one key per line, blank lines and `#` comments ignored
+fn parse_keys_file(content: &str) -> impl Iterator<Item = String> + '_ {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+}
+
+/// Compare two byte strings without short-circuiting on the first difference
+///
+/// Only the length of `expected` can leak through timing.
+fn constant_time_eq(expected: &[u8], presented: &[u8]) -> bool {
+    let mut diff = expected.len() ^ presented.len();
+
+    for (i, byte) in expected.iter().enumerate() {
+        let other = presented.get(i).copied().unwrap_or(0);
+        diff |= usize::from(byte ^ other);
+    }
+
+    diff == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::Request, middleware, routing::get};
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    fn auth_config(keys: &[&str]) -> AuthConfig {
+        AuthConfig {
+            exempt: crate::config::defaults::default_auth_exempt(),
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+            keys_file: None,
+        }
+    }
+
+    fn app(keys: ApiKeys) -> Router {
+        Router::new()
+            .route("/", get(|| async { "health" }))
+            .route("/v1/message", get(|| async { "message" }))
+            .layer(middleware::from_fn_with_state(
+                Arc::new(keys),
+                require_api_key,
+            ))
+    }
+
+    async fn status_for(app: Router, uri: &str, authorization: Option<&str>) -> StatusCode {
+        let mut request = Request::builder().uri(uri);
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+
+        app.oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_require_api_key() {
+        let keys = ApiKeys::from_config(&auth_config(&["secret"]), None).unwrap();
+        let app = app(keys);
+
+        assert_eq!(
+            status_for(app.clone(), "/v1/message", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status_for(app.clone(), "/v1/message", Some("Bearer wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status_for(app.clone(), "/v1/message", Some("secret")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status_for(app.clone(), "/v1/message", Some("Bearer secret")).await,
+            StatusCode::OK
+        );
+
+        // Health check is exempt by default
+        assert_eq!(status_for(app, "/", None).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_health_can_require_key() {
+        let mut config = auth_config(&["secret"]);
+        config.exempt.clear();
+        let app = app(ApiKeys::from_config(&config, None).unwrap());
+
+        assert_eq!(status_for(app, "/", None).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_keys_file_relative_to_config_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("keys.txt"),
+            "# team keys\nfirst\n\n  second  \n",
+        )
+        .unwrap();
+
+        let mut config = auth_config(&["inline"]);
+        config.keys_file = Some("keys.txt".into());
+        let keys = ApiKeys::from_config(&config, Some(temp_dir.path())).unwrap();
+
+        assert!(keys.accepts("inline"));
+        assert!(keys.accepts("first"));
+        assert!(keys.accepts("second"));
+        assert!(!keys.accepts("# team keys"));
+    }
+
+    #[test]
+    fn test_missing_keys_file_is_an_error() {
+        let mut config = auth_config(&["inline"]);
+        config.keys_file = Some("/nonexistent/keys.txt".into());
+
+        assert!(ApiKeys::from_config(&config, None).is_err());
+    }
+
+    #[test]
+    fn test_no_keys_is_an_error() {
+        assert!(ApiKeys::from_config(&auth_config(&[]), None).is_err());
+        assert!(ApiKeys::from_config(&auth_config(&["  "]), None).is_err());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"secret", b"secre"));
+        assert!(!constant_time_eq(b"", b"x"));
+    }
+}
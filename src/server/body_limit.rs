@@ -0,0 +1,70 @@
+use crate::config::defaults::DEFAULT_MAX_BODY_BYTES;
+use crate::config::schema::ServerConfig;
+use crate::routes::fallback::route_error_response;
+use axum::{
+    extract::{DefaultBodyLimit, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+
+/// Largest request body the server accepts, in bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyLimit(pub usize);
+
+impl BodyLimit {
+    /// Read the limit from `[server]`
+    pub fn from_config(config: &ServerConfig) -> Self {
+        BodyLimit(config.max_body_bytes)
+    }
+
+    /// Layer capping how much of a body the extractors buffer
+    ///
+    /// This also covers chunked bodies, which have no `Content-Length`
+    /// for `reject_oversized` to check.
+    pub fn layer(self) -> DefaultBodyLimit {
+        DefaultBodyLimit::max(self.0)
+    }
+}
+
+impl Default for BodyLimit {
+    fn default() -> Self {
+        BodyLimit(DEFAULT_MAX_BODY_BYTES)
+    }
+}
+
+/// Answer with a 413 if the declared body is larger than the limit
+///
+/// Runs before routing, so the body is never read and the handler never
+/// called. Responses, including streamed ones, aren't limited.
+pub async fn reject_oversized(
+    State(limit): State<BodyLimit>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+
+    match length {
+        Some(length) if length > limit.0 as u64 => {
+            let path = request.uri().path().to_string();
+            tracing::warn!(
+                "Rejecting {} byte request body for {} (limit: {} bytes)",
+                length,
+                path,
+                limit.0
+            );
+
+            route_error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "PAYLOAD_TOO_LARGE",
+                &format!("Request body exceeds {} bytes", limit.0),
+                &path,
+            )
+        }
+        _ => next.run(request).await,
+    }
+}
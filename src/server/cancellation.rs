@@ -0,0 +1,134 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of operations abandoned because the client went away
+static CANCELLED_OPERATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Run `future` to completion, recording a cancellation if it is dropped early
+///
+/// Hyper drops the handler future as soon as it sees the client close the
+/// connection. Any upstream work owned by `future` (adapter calls, HTTP
+/// requests, limiter permits) is dropped with it, so a disconnect aborts
+/// that work instead of letting it run to completion unobserved.
+pub async fn cancellable<F: Future>(operation: &'static str, future: F) -> F::Output {
+    let mut guard = CancellationGuard {
+        completed: false,
+        operation,
+    };
+
+    let output = future.await;
+    guard.completed = true;
+
+    output
+}
+
+/// Total number of operations cancelled by client disconnects
+#[allow(dead_code)] // Read by tests and future metrics endpoints
+pub fn cancelled_operations() -> u64 {
+    CANCELLED_OPERATIONS.load(Ordering::Relaxed)
+}
+
+/// Records a cancellation when dropped before the operation completed
+struct CancellationGuard {
+    completed: bool,
+    operation: &'static str,
+}
+
+impl Drop for CancellationGuard {
+    fn drop(&mut self) {
+        if !self.completed {
+            CANCELLED_OPERATIONS.fetch_add(1, Ordering::Relaxed);
+            tracing::info!("{} cancelled: client disconnected", self.operation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::get};
+    use std::io::{Read, Write};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancellable_returns_output() {
+        assert_eq!(cancellable("test", async { 42 }).await, 42);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_operation_is_cancelled() {
+        let before = cancelled_operations();
+
+        let future = cancellable("test", std::future::pending::<()>());
+        let result = tokio::time::timeout(Duration::from_millis(10), future).await;
+
+        assert!(result.is_err());
+        assert!(cancelled_operations() > before);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_client_disconnect_closes_upstream_connection() {
+        // Slow upstream: accepts a connection and never answers
+        let upstream = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let upstream_addr = upstream.local_addr().unwrap();
+        let (accepted_tx, accepted_rx) = mpsc::channel();
+        let (closed_tx, closed_rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (mut socket, _) = upstream.accept().unwrap();
+            socket
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            accepted_tx.send(()).unwrap();
+
+            // EOF means the server dropped its side of the connection
+            let mut buf = [0u8; 1];
+            closed_tx
+                .send(matches!(socket.read(&mut buf), Ok(0)))
+                .unwrap();
+        });
+
+        let app = Router::new().route(
+            "/slow",
+            get(move || {
+                cancellable("slow", async move {
+                    let upstream = tokio::net::TcpStream::connect(upstream_addr).await.unwrap();
+                    // Upstream never writes, so this waits until the future is dropped
+                    let _ = upstream.readable().await;
+                    "unreachable"
+                })
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let before = cancelled_operations();
+
+        // Client sends a request, waits for the upstream call, then gives up
+        tokio::task::spawn_blocking(move || {
+            let mut client = std::net::TcpStream::connect(addr).unwrap();
+            client
+                .write_all(b"GET /slow HTTP/1.1\r\nhost: localhost\r\n\r\n")
+                .unwrap();
+            accepted_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            drop(client);
+
+            let closed = closed_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert!(closed, "upstream connection should be closed promptly");
+        })
+        .await
+        .unwrap();
+
+        // The guard counts the cancellation just after the upstream closes
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while cancelled_operations() <= before {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the cancellation should be recorded");
+    }
+}
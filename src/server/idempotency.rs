@@ -0,0 +1,553 @@
+use crate::config::schema::IdempotencyConfig;
+use crate::routes::fallback::error_response;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use super::auth::ApiKeyId;
+use super::body_limit::BodyLimit;
+
+/// Request header naming a retry-safe request
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Response header marking a replayed response
+pub const IDEMPOTENCY_REPLAYED_HEADER: &str = "idempotency-replayed";
+
+/// Longest idempotency key accepted
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Response stored for replaying
+#[derive(Debug, Clone)]
+struct StoredResponse {
+    body: Bytes,
+    headers: HeaderMap,
+    status: StatusCode,
+}
+
+impl StoredResponse {
+    fn replay(&self) -> Response {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        response.headers_mut().insert(
+            HeaderName::from_static(IDEMPOTENCY_REPLAYED_HEADER),
+            HeaderValue::from_static("true"),
+        );
+        response
+    }
+}
+
+#[derive(Debug)]
+enum EntryState {
+    /// The first request is still running; the receiver wakes when it ends
+    Pending(watch::Receiver<()>),
+    Done {
+        expires: Instant,
+        response: StoredResponse,
+    },
+}
+
+#[derive(Debug)]
+struct Entry {
+    body_hash: u64,
+    /// Tick of the last use, for evicting the least recently used entry
+    last_used: u64,
+    state: EntryState,
+}
+
+/// Idempotency key of a request, scoped to the API key that sent it
+type CacheKey = (Option<ApiKeyId>, String);
+
+#[derive(Debug, Default)]
+struct Entries {
+    entries: HashMap<CacheKey, Entry>,
+    tick: u64,
+}
+
+/// What to do with a request carrying an idempotency key
+enum Begin {
+    /// First request with the key: run it and complete the guard
+    Execute(PendingGuard),
+    /// The cache is full of running requests: run it without caching
+    Bypass,
+    /// Same key and body as an earlier request
+    Replay(Response),
+    /// Same key as an earlier request, but a different body
+    Conflict,
+    /// Same key and body as a running request: wait for it to end
+    Wait(watch::Receiver<()>),
+}
+
+/// Responses to requests with an `Idempotency-Key`, for replaying retries
+///
+/// Entries are keyed by the idempotency key and the caller's API key, so
+/// clients can't replay each other's responses, and remember a hash of the
+/// request body. At most `max_entries` are kept; expired entries go first,
+/// then the least recently used ones.
+#[derive(Debug, Clone)]
+pub struct IdempotencyCache {
+    entries: Arc<Mutex<Entries>>,
+    max_entries: usize,
+    ttl: Duration,
+}
+
+impl IdempotencyCache {
+    /// Read the settings from `[server.idempotency]`
+    pub fn from_config(config: &IdempotencyConfig) -> Self {
+        IdempotencyCache {
+            entries: Arc::default(),
+            max_entries: config.max_entries,
+            ttl: Duration::from_secs(config.ttl_secs),
+        }
+    }
+
+    /// Whether requests with an idempotency key are cached at all
+    pub fn is_enabled(&self) -> bool {
+        self.max_entries > 0 && !self.ttl.is_zero()
+    }
+
+    fn begin(&self, key: &CacheKey, body_hash: u64) -> Begin {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.tick += 1;
+        let tick = entries.tick;
+        let now = Instant::now();
+
+        if let Some(entry) = entries.entries.get_mut(key) {
+            let expired = matches!(entry.state, EntryState::Done { expires, .. } if expires <= now);
+            if !expired {
+                if entry.body_hash != body_hash {
+                    return Begin::Conflict;
+                }
+                entry.last_used = tick;
+                return match &entry.state {
+                    EntryState::Pending(finished) => Begin::Wait(finished.clone()),
+                    EntryState::Done { response, .. } => Begin::Replay(response.replay()),
+                };
+            }
+            entries.entries.remove(key);
+        }
+
+        if entries.entries.len() >= self.max_entries && !entries.evict(now) {
+            return Begin::Bypass;
+        }
+
+        let (finished, receiver) = watch::channel(());
+        entries.entries.insert(
+            key.clone(),
+            Entry {
+                body_hash,
+                last_used: tick,
+                state: EntryState::Pending(receiver),
+            },
+        );
+
+        Begin::Execute(PendingGuard {
+            cache: self.clone(),
+            completed: false,
+            _finished: finished,
+            key: key.clone(),
+        })
+    }
+}
+
+impl Entries {
+    /// Make room for one entry, returning false if every entry is running
+    fn evict(&mut self, now: Instant) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(
+            |_, entry| !matches!(entry.state, EntryState::Done { expires, .. } if expires <= now),
+        );
+        if self.entries.len() < before {
+            return true;
+        }
+
+        let oldest = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| matches!(entry.state, EntryState::Done { .. }))
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        match oldest {
+            Some(key) => {
+                self.entries.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for IdempotencyCache {
+    fn default() -> Self {
+        IdempotencyCache::from_config(&IdempotencyConfig::default())
+    }
+}
+
+/// Pending entry of a running request
+///
+/// Dropping it without `complete`, e.g. when the request is cancelled or
+/// failed, forgets the key so a retry runs again. Either way, requests
+/// waiting on it wake up.
+struct PendingGuard {
+    cache: IdempotencyCache,
+    completed: bool,
+    _finished: watch::Sender<()>,
+    key: CacheKey,
+}
+
+impl PendingGuard {
+    fn complete(mut self, response: StoredResponse) {
+        let mut entries = self.cache.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.entries.get_mut(&self.key) {
+            entry.state = EntryState::Done {
+                expires: Instant::now() + self.cache.ttl,
+                response,
+            };
+        }
+        self.completed = true;
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let mut entries = self.cache.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if matches!(
+            entries.entries.get(&self.key),
+            Some(Entry {
+                state: EntryState::Pending(_),
+                ..
+            })
+        ) {
+            entries.entries.remove(&self.key);
+        }
+    }
+}
+
+/// State of the idempotency middleware
+#[derive(Clone)]
+pub struct IdempotentMessages {
+    pub body_limit: BodyLimit,
+    pub cache: IdempotencyCache,
+    /// Path prefixes of the message routes, one per API version (e.g. "/v1/message/")
+    pub message_prefixes: Vec<String>,
+}
+
+/// Replay responses to message requests retried with the same `Idempotency-Key`
+///
+/// The first request with a key runs normally and its response is stored;
+/// a retry with the same key and body gets that response again, marked
+/// with `Idempotency-Replayed: true`. A retry arriving while the first is
+/// still running waits for it. Reusing a key with a different body is a
+/// 422. Server errors aren't stored, so those requests can be retried.
+/// Streamed replies can't be replayed, so streaming requests with a key
+/// are rejected.
+pub async fn replay_messages(
+    State(idempotency): State<IdempotentMessages>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER).cloned() else {
+        return next.run(request).await;
+    };
+    if request.method() != Method::POST
+        || !idempotency
+            .message_prefixes
+            .iter()
+            .any(|prefix| request.uri().path().starts_with(prefix))
+        || !idempotency.cache.is_enabled()
+    {
+        return next.run(request).await;
+    }
+
+    let Some(key) = key
+        .to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN)
+        .map(str::to_string)
+    else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            &format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                MAX_IDEMPOTENCY_KEY_LEN
+            ),
+        );
+    };
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, idempotency.body_limit.0).await else {
+        return error_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large");
+    };
+    if is_streaming(&body) {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Idempotency-Key is not supported for streaming requests",
+        );
+    }
+
+    let mut hasher = DefaultHasher::new();
+    // The path selects the recipient, so it's part of the request too
+    parts.uri.path().hash(&mut hasher);
+    body.hash(&mut hasher);
+    let body_hash = hasher.finish();
+    let cache_key = (parts.extensions.get::<ApiKeyId>().copied(), key.clone());
+
+    loop {
+        match idempotency.cache.begin(&cache_key, body_hash) {
+            Begin::Execute(guard) => {
+                let request = Request::from_parts(parts, Body::from(body));
+                let response = next.run(request).await;
+                return store(response, guard).await;
+            }
+            Begin::Bypass => {
+                tracing::warn!("Idempotency cache is full, not caching request {}", key);
+                let request = Request::from_parts(parts, Body::from(body));
+                return next.run(request).await;
+            }
+            Begin::Replay(response) => {
+                tracing::debug!("Replaying response for idempotency key {}", key);
+                return response;
+            }
+            Begin::Conflict => {
+                return error_response(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "Idempotency-Key was already used for a different request",
+                );
+            }
+            Begin::Wait(mut finished) => {
+                // Resolves once the first request completes or is dropped
+                let _ = finished.changed().await;
+            }
+        }
+    }
+}
+
+/// Buffer `response`, storing it under the guard's key unless it's a server error
+async fn store(response: Response, guard: PendingGuard) -> Response {
+    if response.status().is_server_error() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for replaying: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to send response");
+        }
+    };
+
+    guard.complete(StoredResponse {
+        body: body.clone(),
+        headers: parts.headers.clone(),
+        status: parts.status,
+    });
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Whether a message request body asks for a streamed reply
+fn is_streaming(body: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|request| request.get("stream")?.as_bool())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, middleware, routing::post};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    /// Message route counting its calls, replying with the call number
+    fn app(cache: IdempotencyCache, delay: Duration) -> (Router, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler = {
+            let calls = calls.clone();
+            move || {
+                let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    tokio::time::sleep(delay).await;
+                    format!("reply {}", call)
+                }
+            }
+        };
+
+        let app = Router::new()
+            .route("/v1/message/:recipient_id", post(handler))
+            .layer(middleware::from_fn_with_state(
+                IdempotentMessages {
+                    body_limit: BodyLimit::default(),
+                    cache,
+                    message_prefixes: vec!["/v1/message/".to_string()],
+                },
+                replay_messages,
+            ));
+        (app, calls)
+    }
+
+    fn cache(max_entries: usize, ttl: Duration) -> IdempotencyCache {
+        IdempotencyCache {
+            entries: Arc::default(),
+            max_entries,
+            ttl,
+        }
+    }
+
+    fn request(key: Option<&str>, body: &str) -> Request {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/v1/message/assistant")
+            .header("content-type", "application/json");
+        if let Some(key) = key {
+            builder = builder.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn send(app: &Router, key: Option<&str>, body: &str) -> (StatusCode, bool, String) {
+        let response = app.clone().oneshot(request(key, body)).await.unwrap();
+        let status = response.status();
+        let replayed = response.headers().contains_key(IDEMPOTENCY_REPLAYED_HEADER);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (status, replayed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    const BODY: &str = r#"{"messages":[{"role":"user","content":"Hi"}]}"#;
+
+    #[tokio::test]
+    async fn test_retry_replays_response() {
+        let (app, calls) = app(IdempotencyCache::default(), Duration::ZERO);
+
+        let first = send(&app, Some("key-1"), BODY).await;
+        let retry = send(&app, Some("key-1"), BODY).await;
+
+        assert_eq!(first, (StatusCode::OK, false, "reply 1".to_string()));
+        assert_eq!(retry, (StatusCode::OK, true, "reply 1".to_string()));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Without a key every request runs
+        send(&app, None, BODY).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_keys_scoped_per_api_key() {
+        let auth_config = crate::config::schema::AuthConfig {
+            exempt: Vec::new(),
+            keys: vec!["client-a".to_string(), "client-b".to_string()],
+            keys_file: None,
+        };
+        let keys = super::super::auth::ApiKeys::from_config(&auth_config, None).unwrap();
+        let (app, calls) = app(IdempotencyCache::default(), Duration::ZERO);
+        let app = app.layer(middleware::from_fn_with_state(
+            Arc::new(keys),
+            super::super::auth::require_api_key,
+        ));
+        let send_as = |api_key: &str| {
+            let mut request = request(Some("key-1"), BODY);
+            request.headers_mut().insert(
+                axum::http::header::AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {api_key}")).unwrap(),
+            );
+            app.clone().oneshot(request)
+        };
+
+        send_as("client-a").await.unwrap();
+        let other = send_as("client-b").await.unwrap();
+        assert!(!other.headers().contains_key(IDEMPOTENCY_REPLAYED_HEADER));
+        let retry = send_as("client-a").await.unwrap();
+        assert!(retry.headers().contains_key(IDEMPOTENCY_REPLAYED_HEADER));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_with_different_body() {
+        let (app, calls) = app(IdempotencyCache::default(), Duration::ZERO);
+
+        send(&app, Some("key-1"), BODY).await;
+        let (status, _, _) = send(&app, Some("key-1"), r#"{"messages":[]}"#).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_entries_expire() {
+        let (app, calls) = app(cache(10, Duration::from_millis(50)), Duration::ZERO);
+
+        send(&app, Some("key-1"), BODY).await;
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let (_, replayed, body) = send(&app, Some("key-1"), BODY).await;
+
+        assert!(!replayed);
+        assert_eq!(body, "reply 2");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_duplicate_waits_for_first() {
+        let (app, calls) = app(IdempotencyCache::default(), Duration::from_millis(100));
+
+        let (first, second) = tokio::join!(
+            send(&app, Some("key-1"), BODY),
+            send(&app, Some("key-1"), BODY)
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(first.2, "reply 1");
+        assert_eq!(second.2, "reply 1");
+        // Exactly one of them ran, the other was replayed
+        assert!(first.1 != second.1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_is_bounded() {
+        let cache = cache(2, Duration::from_secs(60));
+        let (app, calls) = app(cache.clone(), Duration::ZERO);
+
+        for key in ["a", "b", "c"] {
+            send(&app, Some(key), BODY).await;
+        }
+        assert_eq!(cache.entries.lock().unwrap().entries.len(), 2);
+
+        // "a" was the least recently used and is gone
+        let (_, replayed, _) = send(&app, Some("a"), BODY).await;
+        assert!(!replayed);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_requests_and_bad_keys_rejected() {
+        let (app, calls) = app(IdempotencyCache::default(), Duration::ZERO);
+
+        let streaming = r#"{"messages":[],"stream":true}"#;
+        assert_eq!(
+            send(&app, Some("key-1"), streaming).await.0,
+            StatusCode::BAD_REQUEST
+        );
+        let long_key = "k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1);
+        assert_eq!(
+            send(&app, Some(&long_key), BODY).await.0,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,18 @@
+//! Background jobs that survive restarts
+//!
+//! Jobs are records in the storage adapter under `queue/`. A worker polls
+//! for due ones and runs them with the handler registered for their type;
+//! a job is done when its handler succeeds and the record is deleted. A
+//! failed job is retried with exponential backoff until it has run
+//! `max_attempts` times. A job that was running when the process died is
+//! still stored and runs again after the restart, so handlers must cope
+//! with running more than once.
+
+mod queue;
+pub mod summary;
+pub mod title;
+pub mod webhook;
+
+#[allow(unused_imports)] // Library API for embedders; tests use the options and clock
+pub use queue::{Clock, JOB_KEY_PREFIX, JobHandler, JobOptions, SystemClock};
+pub use queue::{Job, JobQueue};
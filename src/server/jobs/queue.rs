@@ -0,0 +1,658 @@
+//! Queue of stored jobs and the worker running them
+//!
+//! See the parent module for how jobs are stored and retried.
+
+use crate::adapter::traits::ServiceError;
+use crate::config::schema::JobsConfig;
+use crate::server::state::SharedStorage;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// Prefix of the storage keys holding jobs
+pub const JOB_KEY_PREFIX: &str = "queue/";
+
+/// A stored job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    /// Runs that failed so far
+    pub attempts: u32,
+    /// Unique and ordered by enqueue time (also part of the storage key)
+    pub id: String,
+    /// Handler to run the job with
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Why the last run failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub next_run_at: DateTime<Utc>,
+    pub payload: serde_json::Value,
+}
+
+/// Runs the jobs of one type
+pub type JobHandler = Arc<dyn Fn(Job) -> BoxFuture<'static, anyhow::Result<()>> + Send + Sync>;
+
+/// Source of the current time, so tests can move it forward
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// How the queue runs jobs
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobOptions {
+    pub concurrency: usize,
+    pub max_attempts: u32,
+    pub poll_interval: Duration,
+    pub retry_base: Duration,
+    pub retry_max: Duration,
+}
+
+impl JobOptions {
+    pub fn from_config(config: &JobsConfig) -> Self {
+        JobOptions {
+            concurrency: config.concurrency.max(1),
+            max_attempts: config.max_attempts.max(1),
+            poll_interval: Duration::from_secs(config.poll_interval_secs.max(1)),
+            retry_base: Duration::from_secs(config.retry_base_secs),
+            retry_max: Duration::from_secs(config.retry_max_secs),
+        }
+    }
+
+    /// Wait before the run following the `attempts`th failure
+    fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.retry_base.saturating_mul(factor).min(self.retry_max)
+    }
+}
+
+/// Handle for enqueueing jobs and registering their handlers
+///
+/// Clones share one queue. The worker started with `start` stops once
+/// every handle is dropped.
+#[derive(Clone)]
+pub struct JobQueue {
+    inner: Arc<QueueInner>,
+}
+
+struct QueueInner {
+    clock: Arc<dyn Clock>,
+    handlers: RwLock<HashMap<String, JobHandler>>,
+    /// IDs of the jobs running now
+    in_flight: Mutex<HashSet<String>>,
+    options: JobOptions,
+    storage: SharedStorage,
+    /// Wakes the worker when a job is enqueued or a slot frees up
+    wake: Arc<Notify>,
+}
+
+impl JobQueue {
+    /// Queue keeping its jobs in `storage`
+    pub fn new(storage: SharedStorage, options: JobOptions, clock: Arc<dyn Clock>) -> Self {
+        JobQueue {
+            inner: Arc::new(QueueInner {
+                clock,
+                handlers: RwLock::new(HashMap::new()),
+                in_flight: Mutex::new(HashSet::new()),
+                options,
+                storage,
+                wake: Arc::new(Notify::new()),
+            }),
+        }
+    }
+
+    /// Queue configured in `[server.jobs]`, if enabled, with its worker started
+    ///
+    /// `register` runs before the worker starts, so stored jobs don't run
+    /// before their handlers are known.
+    pub fn start_from_config(
+        config: &JobsConfig,
+        storage: Option<&SharedStorage>,
+        register: impl FnOnce(&JobQueue),
+    ) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let Some(storage) = storage else {
+            tracing::warn!("Background jobs disabled: they need a storage adapter");
+            return None;
+        };
+
+        let queue = JobQueue::new(
+            storage.clone(),
+            JobOptions::from_config(config),
+            Arc::new(SystemClock),
+        );
+        register(&queue);
+        queue.start();
+        Some(queue)
+    }
+
+    /// Run jobs of type `kind` with `handler`, replacing any earlier handler
+    pub fn register<F, Fut>(&self, kind: &str, handler: F)
+    where
+        F: Fn(Job) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let handler: JobHandler = Arc::new(move |job| Box::pin(handler(job)));
+        self.inner
+            .handlers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(kind.to_string(), handler);
+    }
+
+    /// Stop running jobs of type `kind`
+    ///
+    /// Stored jobs of that type fail until a handler is registered again.
+    pub fn unregister(&self, kind: &str) {
+        self.inner
+            .handlers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(kind);
+    }
+
+    /// Whether jobs of type `kind` have a handler
+    pub fn handles(&self, kind: &str) -> bool {
+        self.handler(kind).is_some()
+    }
+
+    /// Store a job without waiting, returning its ID
+    ///
+    /// For request handlers: the job is written in the background and a
+    /// failure to store it is only logged.
+    pub fn enqueue(&self, kind: &str, payload: serde_json::Value) -> String {
+        let job = self.new_job(kind, payload);
+        let id = job.id.clone();
+        let queue = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = queue.save(&job).await {
+                tracing::error!("Failed to enqueue {} job {}: {}", job.kind, job.id, e);
+            }
+        });
+        id
+    }
+
+    /// Store a job, returning its ID once it's stored
+    #[allow(dead_code)] // Used in tests and by embedders
+    pub async fn push(
+        &self,
+        kind: &str,
+        payload: serde_json::Value,
+    ) -> Result<String, ServiceError> {
+        let job = self.new_job(kind, payload);
+        self.save(&job).await?;
+        Ok(job.id)
+    }
+
+    /// Stored jobs, due or not, in the order they're run
+    pub async fn jobs(&self) -> Result<Vec<Job>, ServiceError> {
+        let storage = self.inner.storage.read().await;
+        let mut jobs = Vec::new();
+        for key in storage.list_keys(Some(JOB_KEY_PREFIX)).await? {
+            // Deleted since it was listed
+            let Ok(data) = storage.retrieve(&key).await else {
+                continue;
+            };
+            match serde_json::from_slice::<Job>(&data) {
+                Ok(job) => jobs.push(job),
+                Err(e) => tracing::warn!("Skipping corrupt job {}: {}", key, e),
+            }
+        }
+
+        jobs.sort_by(|a, b| (a.next_run_at, &a.id).cmp(&(b.next_run_at, &b.id)));
+        Ok(jobs)
+    }
+
+    /// Spawn the worker running due jobs
+    ///
+    /// It checks for due jobs every `poll_interval`, and right away when a
+    /// job is enqueued or finishes.
+    pub fn start(&self) {
+        let queue: Weak<QueueInner> = Arc::downgrade(&self.inner);
+        let wake = self.inner.wake.clone();
+        let poll_interval = self.inner.options.poll_interval;
+
+        tokio::spawn(async move {
+            loop {
+                {
+                    let Some(inner) = queue.upgrade() else {
+                        break;
+                    };
+                    JobQueue { inner }.run_due().await;
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(poll_interval) => {}
+                    _ = wake.notified() => {}
+                }
+            }
+        });
+    }
+
+    /// Start the due jobs that fit into the free slots
+    async fn run_due(&self) -> Vec<JoinHandle<()>> {
+        let jobs = match self.jobs().await {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                tracing::error!("Failed to list jobs: {}", e);
+                return Vec::new();
+            }
+        };
+
+        self.claim_due(jobs)
+            .await
+            .into_iter()
+            .map(|job| {
+                let queue = self.clone();
+                tokio::spawn(async move { queue.run(job).await })
+            })
+            .collect()
+    }
+
+    /// Claim the due jobs of a listing that fit into the free slots
+    ///
+    /// A job can finish and leave `in_flight` after `jobs` was listed, with
+    /// a new `next_run_at` or deleted. So each claimed job is read again
+    /// once it's claimed, and nothing else can change it, and only kept if
+    /// it's still due.
+    async fn claim_due(&self, jobs: Vec<Job>) -> Vec<Job> {
+        let now = self.inner.clock.now();
+        let claimed: Vec<Job> = {
+            let mut in_flight = self.in_flight();
+            let free = self
+                .inner
+                .options
+                .concurrency
+                .saturating_sub(in_flight.len());
+            let due: Vec<Job> = jobs
+                .into_iter()
+                .filter(|job| job.next_run_at <= now && !in_flight.contains(&job.id))
+                .take(free)
+                .collect();
+            for job in &due {
+                in_flight.insert(job.id.clone());
+            }
+            due
+        };
+
+        let mut due = Vec::new();
+        for job in claimed {
+            match self.load(&job.id).await {
+                Some(job) if job.next_run_at <= now => due.push(job),
+                _ => {
+                    self.in_flight().remove(&job.id);
+                }
+            }
+        }
+        due
+    }
+
+    /// Run `job` and delete it, or schedule its retry
+    async fn run(&self, mut job: Job) {
+        let result = match self.handler(&job.kind) {
+            // A panicking handler fails the job instead of the worker
+            Some(handler) => match tokio::spawn(handler(job.clone())).await {
+                Ok(result) => result,
+                Err(e) => Err(anyhow::anyhow!("Job handler panicked: {}", e)),
+            },
+            None => Err(anyhow::anyhow!(
+                "No handler for jobs of type '{}'",
+                job.kind
+            )),
+        };
+
+        let saved = match result {
+            Ok(()) => self.delete(&job.id).await,
+            Err(e) => {
+                job.attempts += 1;
+                if job.attempts >= self.inner.options.max_attempts {
+                    tracing::error!(
+                        "Giving up on {} job {} after {} attempts: {:#}",
+                        job.kind,
+                        job.id,
+                        job.attempts,
+                        e
+                    );
+                    self.delete(&job.id).await
+                } else {
+                    let delay = self.inner.options.backoff(job.attempts);
+                    tracing::warn!(
+                        "{} job {} failed (attempt {}), retrying in {}s: {:#}",
+                        job.kind,
+                        job.id,
+                        job.attempts,
+                        delay.as_secs(),
+                        e
+                    );
+                    job.last_error = Some(format!("{:#}", e));
+                    job.next_run_at = later(self.inner.clock.now(), delay);
+                    self.save(&job).await
+                }
+            }
+        };
+        if let Err(e) = saved {
+            tracing::error!("Failed to update {} job {}: {}", job.kind, job.id, e);
+        }
+
+        self.in_flight().remove(&job.id);
+        self.inner.wake.notify_one();
+    }
+
+    fn new_job(&self, kind: &str, payload: serde_json::Value) -> Job {
+        let now = self.inner.clock.now();
+        Job {
+            attempts: 0,
+            id: format!(
+                "{:013}-{}",
+                now.timestamp_millis(),
+                uuid::Uuid::new_v4().simple()
+            ),
+            kind: kind.to_string(),
+            last_error: None,
+            next_run_at: now,
+            payload,
+        }
+    }
+
+    async fn save(&self, job: &Job) -> Result<(), ServiceError> {
+        let data = serde_json::to_vec(job)
+            .map_err(|e| ServiceError::ExecutionError(format!("Failed to encode job: {e}")))?;
+        self.inner
+            .storage
+            .write()
+            .await
+            .store(&job_key(&job.id), &data)
+            .await?;
+        self.inner.wake.notify_one();
+        Ok(())
+    }
+
+    /// The stored job `id`, if it's still there
+    async fn load(&self, id: &str) -> Option<Job> {
+        let data = self
+            .inner
+            .storage
+            .read()
+            .await
+            .retrieve(&job_key(id))
+            .await
+            .ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), ServiceError> {
+        self.inner.storage.write().await.delete(&job_key(id)).await
+    }
+
+    fn handler(&self, kind: &str) -> Option<JobHandler> {
+        self.inner
+            .handlers
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(kind)
+            .cloned()
+    }
+
+    fn in_flight(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.inner
+            .in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// `delay` after `time`, or the end of time for absurd delays
+fn later(time: DateTime<Utc>, delay: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(delay)
+        .ok()
+        .and_then(|delay| time.checked_add_signed(delay))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+fn job_key(id: &str) -> String {
+    format!("{}{}", JOB_KEY_PREFIX, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::MemoryStorage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::Semaphore;
+
+    /// Clock that only moves when told to
+    struct ManualClock(Mutex<DateTime<Utc>>);
+
+    impl ManualClock {
+        fn new() -> Arc<Self> {
+            Arc::new(ManualClock(Mutex::new(Utc::now())))
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += chrono::Duration::from_std(duration).unwrap();
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn options() -> JobOptions {
+        JobOptions {
+            concurrency: 2,
+            max_attempts: 3,
+            poll_interval: Duration::from_millis(10),
+            retry_base: Duration::from_secs(10),
+            retry_max: Duration::from_secs(15),
+        }
+    }
+
+    fn storage() -> SharedStorage {
+        Arc::new(tokio::sync::RwLock::new(MemoryStorage::default()))
+    }
+
+    async fn finish(handles: Vec<JoinHandle<()>>) -> usize {
+        let count = handles.len();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        count
+    }
+
+    #[tokio::test]
+    async fn test_job_runs_and_is_deleted() {
+        let queue = JobQueue::new(storage(), options(), ManualClock::new());
+        let payloads = Arc::new(Mutex::new(Vec::new()));
+        let seen = payloads.clone();
+        queue.register("echo", move |job| {
+            seen.lock().unwrap().push(job.payload);
+            async { Ok(()) }
+        });
+
+        queue
+            .push("echo", serde_json::json!({ "n": 1 }))
+            .await
+            .unwrap();
+        assert_eq!(queue.jobs().await.unwrap().len(), 1);
+
+        assert_eq!(finish(queue.run_due().await).await, 1);
+        assert_eq!(
+            *payloads.lock().unwrap(),
+            vec![serde_json::json!({ "n": 1 })]
+        );
+        assert!(queue.jobs().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failing_job_backs_off_then_gives_up() {
+        let clock = ManualClock::new();
+        let queue = JobQueue::new(storage(), options(), clock.clone());
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = runs.clone();
+        queue.register("flaky", move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Err(anyhow::anyhow!("provider down")) }
+        });
+        queue.push("flaky", serde_json::Value::Null).await.unwrap();
+
+        finish(queue.run_due().await).await;
+        let job = &queue.jobs().await.unwrap()[0];
+        assert_eq!(job.attempts, 1);
+        assert_eq!(job.last_error.as_deref(), Some("provider down"));
+        assert_eq!(job.next_run_at, clock.now() + chrono::Duration::seconds(10));
+
+        // Not due before its backoff is over
+        clock.advance(Duration::from_secs(9));
+        assert_eq!(finish(queue.run_due().await).await, 0);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(finish(queue.run_due().await).await, 1);
+        let job = &queue.jobs().await.unwrap()[0];
+        assert_eq!(job.attempts, 2);
+        // Doubled, but capped at retry_max
+        assert_eq!(job.next_run_at, clock.now() + chrono::Duration::seconds(15));
+
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(finish(queue.run_due().await).await, 1);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert!(queue.jobs().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit() {
+        let queue = JobQueue::new(storage(), options(), ManualClock::new());
+        let gate = Arc::new(Semaphore::new(0));
+        let running = Arc::new(AtomicUsize::new(0));
+        let (gate_in, running_in) = (gate.clone(), running.clone());
+        queue.register("slow", move |_| {
+            let (gate, running) = (gate_in.clone(), running_in.clone());
+            async move {
+                running.fetch_add(1, Ordering::SeqCst);
+                gate.acquire().await.unwrap().forget();
+                Ok(())
+            }
+        });
+        for _ in 0..3 {
+            queue.push("slow", serde_json::Value::Null).await.unwrap();
+        }
+
+        let first = queue.run_due().await;
+        assert_eq!(first.len(), 2);
+        // Both slots are taken
+        assert!(queue.run_due().await.is_empty());
+
+        gate.add_permits(2);
+        finish(first).await;
+        assert_eq!(queue.jobs().await.unwrap().len(), 1);
+
+        gate.add_permits(1);
+        assert_eq!(finish(queue.run_due().await).await, 1);
+        assert_eq!(running.load(Ordering::SeqCst), 3);
+        assert!(queue.jobs().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stale_listing_does_not_rerun_jobs() {
+        let clock = ManualClock::new();
+        let queue = JobQueue::new(storage(), options(), clock.clone());
+        queue.push("flaky", serde_json::Value::Null).await.unwrap();
+        queue.push("done", serde_json::Value::Null).await.unwrap();
+        let listed = queue.jobs().await.unwrap();
+
+        // Both ran after the listing: one was rescheduled, one finished
+        let mut rescheduled = listed[0].clone();
+        rescheduled.next_run_at = clock.now() + chrono::Duration::seconds(10);
+        queue.save(&rescheduled).await.unwrap();
+        queue.delete(&listed[1].id).await.unwrap();
+
+        assert!(queue.claim_due(listed).await.is_empty());
+        assert!(queue.in_flight().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_jobs_survive_restart() {
+        let storage = storage();
+        let clock = ManualClock::new();
+
+        // Enqueued, then the process "dies" before running it
+        let queue = JobQueue::new(storage.clone(), options(), clock.clone());
+        let id = queue.push("echo", serde_json::json!("hi")).await.unwrap();
+        drop(queue);
+
+        let queue = JobQueue::new(storage, options(), clock);
+        let ran = Arc::new(Mutex::new(None));
+        let seen = ran.clone();
+        queue.register("echo", move |job| {
+            *seen.lock().unwrap() = Some(job.id);
+            async { Ok(()) }
+        });
+
+        assert_eq!(finish(queue.run_due().await).await, 1);
+        assert_eq!(ran.lock().unwrap().as_deref(), Some(id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_job_without_handler_fails() {
+        let queue = JobQueue::new(storage(), options(), ManualClock::new());
+        queue
+            .push("unknown", serde_json::Value::Null)
+            .await
+            .unwrap();
+
+        finish(queue.run_due().await).await;
+
+        let job = &queue.jobs().await.unwrap()[0];
+        assert_eq!(job.attempts, 1);
+        assert!(job.last_error.as_ref().unwrap().contains("No handler"));
+    }
+
+    #[tokio::test]
+    async fn test_worker_runs_enqueued_jobs() {
+        let queue = JobQueue::new(storage(), options(), Arc::new(SystemClock));
+        let done = Arc::new(Notify::new());
+        let notify = done.clone();
+        queue.register("ping", move |_| {
+            notify.notify_one();
+            async { Ok(()) }
+        });
+        queue.start();
+
+        queue.enqueue("ping", serde_json::Value::Null);
+
+        tokio::time::timeout(Duration::from_secs(5), done.notified())
+            .await
+            .expect("the worker should run the job");
+    }
+
+    #[test]
+    fn test_backoff() {
+        let options = JobOptions {
+            retry_max: Duration::from_secs(100),
+            ..options()
+        };
+
+        assert_eq!(options.backoff(1), Duration::from_secs(10));
+        assert_eq!(options.backoff(2), Duration::from_secs(20));
+        assert_eq!(options.backoff(3), Duration::from_secs(40));
+        assert_eq!(options.backoff(10), Duration::from_secs(100));
+        assert_eq!(options.backoff(u32::MAX), Duration::from_secs(100));
+    }
+}
@@ -0,0 +1,189 @@
+//! Summarizing long conversations with the LLM
+
+use super::{Job, JobQueue};
+use crate::adapter::traits::{ChatMessage, GenerationOptions};
+use crate::routes::v1::conversations::model::{
+    ConversationSummary, load_conversation, set_summary,
+};
+use crate::server::state::{SharedLlm, SharedStorage};
+use anyhow::Context;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// Type of the jobs summarizing a conversation
+pub const SUMMARY_JOB: &str = "conversation_summary";
+
+/// Instruction sent with the messages to summarize
+const SUMMARY_PROMPT: &str = "Summarize the conversation below so it can be continued without it. \
+                              Keep names, facts, decisions and open questions. \
+                              Reply with the summary only.";
+
+/// Payload of a summary job
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SummaryJob {
+    pub conversation_id: String,
+}
+
+/// Summarize conversations with `llm` when `enqueue` asks for it, leaving
+/// out the `window_messages` most recent messages
+pub fn register(queue: &JobQueue, llm: SharedLlm, storage: SharedStorage, window_messages: usize) {
+    queue.register(SUMMARY_JOB, move |job| {
+        let (llm, storage) = (llm.clone(), storage.clone());
+        async move { summarize(&llm, &storage, window_messages, job).await }
+    });
+}
+
+/// Ask for a new summary of `conversation_id`, if conversations are summarized
+pub fn enqueue(queue: &JobQueue, conversation_id: &str) {
+    if !queue.handles(SUMMARY_JOB) {
+        return;
+    }
+    let payload = SummaryJob {
+        conversation_id: conversation_id.to_string(),
+    };
+    queue.enqueue(
+        SUMMARY_JOB,
+        serde_json::to_value(payload).expect("summary jobs serialize to JSON"),
+    );
+}
+
+async fn summarize(
+    llm: &SharedLlm,
+    storage: &SharedStorage,
+    window_messages: usize,
+    job: Job,
+) -> anyhow::Result<()> {
+    let SummaryJob { conversation_id } =
+        serde_json::from_value(job.payload).context("Invalid summary job")?;
+
+    // Deleted, or summarized by an earlier job, since the job was enqueued
+    let Some(conversation) = load_conversation(storage, &conversation_id).await? else {
+        return Ok(());
+    };
+    let covered = conversation.summarized_messages();
+    let end = conversation.messages.len().saturating_sub(window_messages);
+    if end <= covered {
+        return Ok(());
+    }
+
+    // The previous summary stands in for the messages it covers
+    let mut transcript = Vec::new();
+    if let Some(summary) = &conversation.summary {
+        transcript.push(format!("Summary so far: {}", summary.content));
+    }
+    transcript.extend(
+        conversation.messages[covered..end]
+            .iter()
+            .map(|message| format!("{}: {}", message.role, message.content)),
+    );
+    let messages = [
+        ChatMessage {
+            role: "system".to_string(),
+            content: SUMMARY_PROMPT.to_string(),
+            parts: None,
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: transcript.join("\n\n"),
+            parts: None,
+        },
+    ];
+
+    let reply = llm
+        .write()
+        .await
+        .send_message(&messages, &GenerationOptions::default())
+        .await?;
+    let content = reply.trim();
+    anyhow::ensure!(!content.is_empty(), "The LLM replied with an empty summary");
+
+    let summary = ConversationSummary {
+        content: content.to_string(),
+        messages: end,
+        updated_at: Utc::now().to_rfc3339(),
+    };
+    if set_summary(storage, &conversation_id, summary).await? {
+        tracing::debug!(
+            "Summarized the first {} messages of conversation {}",
+            end,
+            conversation_id
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::{FnLlm, MemoryStorage};
+    use crate::routes::v1::conversations::model::{ConversationMessage, append_exchange};
+    use crate::server::jobs::{JobOptions, SystemClock};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+
+    fn message(role: &str, content: &str) -> ConversationMessage {
+        ConversationMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: None,
+            model: None,
+            usage: None,
+            moderation: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_summary_job_keeps_raw_messages() {
+        let storage: SharedStorage = Arc::new(RwLock::new(MemoryStorage::default()));
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let seen = prompts.clone();
+        let llm: SharedLlm = Arc::new(RwLock::new(FnLlm::new("echo", move |messages| {
+            seen.lock().unwrap().push(messages[1].content.clone());
+            " They planned a trip to Rome. ".to_string()
+        })));
+        let exchange = vec![
+            message("user", "Plan three days in Rome"),
+            message("assistant", "Day one: the Colosseum"),
+            message("user", "And day two?"),
+        ];
+        append_exchange(&storage, "trip", exchange.clone(), "echo")
+            .await
+            .unwrap();
+
+        let options = JobOptions {
+            concurrency: 1,
+            max_attempts: 3,
+            poll_interval: Duration::from_millis(10),
+            retry_base: Duration::from_millis(10),
+            retry_max: Duration::from_millis(10),
+        };
+        let queue = JobQueue::new(storage.clone(), options, Arc::new(SystemClock));
+        register(&queue, llm, storage.clone(), 1);
+        queue.start();
+
+        enqueue(&queue, "trip");
+        enqueue(&queue, "trip");
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !queue.jobs().await.unwrap().is_empty() || prompts.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the summary jobs should finish");
+
+        let conversation = load_conversation(&storage, "trip").await.unwrap().unwrap();
+        let summary = conversation.summary.unwrap();
+        assert_eq!(summary.content, "They planned a trip to Rome.");
+        assert_eq!(summary.messages, 2);
+        // The summarized messages are still stored
+        assert_eq!(conversation.messages, exchange);
+        // The second job found them summarized and didn't ask again
+        let prompts = prompts.lock().unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(
+            prompts[0],
+            "user: Plan three days in Rome\n\nassistant: Day one: the Colosseum"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Titling new conversations with the LLM
+
+use super::{Job, JobQueue};
+use crate::adapter::traits::{ChatMessage, GenerationOptions};
+use crate::routes::v1::conversations::model::{load_conversation, set_title_if_missing};
+use crate::server::state::{SharedLlm, SharedStorage};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// Type of the jobs titling a conversation
+pub const TITLE_JOB: &str = "conversation_title";
+
+/// Instruction sent with the start of the conversation
+const TITLE_PROMPT: &str = "Write a title of at most six words for the conversation below. \
+                            Reply with the title only, without quotes.";
+
+/// Messages from the start of the conversation the title is based on
+const TITLE_CONTEXT_MESSAGES: usize = 4;
+
+/// Characters of each message shown to the LLM
+const TITLE_CONTEXT_CHARS: usize = 500;
+
+/// Longest title stored; longer replies are cut
+const MAX_TITLE_CHARS: usize = 80;
+
+/// Payload of a title job
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TitleJob {
+    pub conversation_id: String,
+}
+
+/// Title conversations with `llm` when `enqueue` asks for it
+pub fn register(queue: &JobQueue, llm: SharedLlm, storage: SharedStorage) {
+    queue.register(TITLE_JOB, move |job| {
+        let (llm, storage) = (llm.clone(), storage.clone());
+        async move { generate_title(&llm, &storage, job).await }
+    });
+}
+
+/// Ask for a title for `conversation_id`, if titles are generated
+pub fn enqueue(queue: &JobQueue, conversation_id: &str) {
+    if !queue.handles(TITLE_JOB) {
+        return;
+    }
+    let payload = TitleJob {
+        conversation_id: conversation_id.to_string(),
+    };
+    queue.enqueue(
+        TITLE_JOB,
+        serde_json::to_value(payload).expect("title jobs serialize to JSON"),
+    );
+}
+
+async fn generate_title(llm: &SharedLlm, storage: &SharedStorage, job: Job) -> anyhow::Result<()> {
+    let TitleJob { conversation_id } =
+        serde_json::from_value(job.payload).context("Invalid title job")?;
+
+    // Deleted or already titled since the job was enqueued
+    let Some(conversation) = load_conversation(storage, &conversation_id).await? else {
+        return Ok(());
+    };
+    if conversation.title.is_some() || conversation.messages.is_empty() {
+        return Ok(());
+    }
+
+    let transcript = conversation
+        .messages
+        .iter()
+        .take(TITLE_CONTEXT_MESSAGES)
+        .map(|message| {
+            let content: String = message.content.chars().take(TITLE_CONTEXT_CHARS).collect();
+            format!("{}: {}", message.role, content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let messages = [
+        ChatMessage {
+            role: "system".to_string(),
+            content: TITLE_PROMPT.to_string(),
+            parts: None,
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: transcript,
+            parts: None,
+        },
+    ];
+
+    let reply = llm
+        .write()
+        .await
+        .send_message(&messages, &GenerationOptions::default())
+        .await?;
+    let title = clean_title(&reply).context("The LLM replied with an empty title")?;
+
+    if set_title_if_missing(storage, &conversation_id, &title).await? {
+        tracing::debug!("Titled conversation {}: {}", conversation_id, title);
+    }
+    Ok(())
+}
+
+/// First line of `reply` without surrounding quotes, cut to `MAX_TITLE_CHARS`
+fn clean_title(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let title = line
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '“' | '”' | '*' | '#'))
+        .trim()
+        .trim_end_matches('.');
+    if title.is_empty() {
+        return None;
+    }
+
+    Some(title.chars().take(MAX_TITLE_CHARS).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::{FnLlm, MemoryStorage};
+    use crate::routes::v1::conversations::model::{ConversationMessage, append_exchange};
+    use crate::server::jobs::{JobOptions, SystemClock};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+
+    fn message(role: &str, content: &str) -> ConversationMessage {
+        ConversationMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            timestamp: None,
+            model: None,
+            usage: None,
+            moderation: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_clean_title() {
+        assert_eq!(
+            clean_title("\n  \"Planning a Trip to Rome.\"\nSure!").as_deref(),
+            Some("Planning a Trip to Rome")
+        );
+        assert_eq!(
+            clean_title("**Rust lifetimes**").as_deref(),
+            Some("Rust lifetimes")
+        );
+        assert_eq!(clean_title(" \"\" \n").as_deref(), None);
+        assert_eq!(
+            clean_title(&"a".repeat(200)).unwrap().len(),
+            MAX_TITLE_CHARS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_title_job_titles_conversation_once() {
+        let storage: SharedStorage = Arc::new(RwLock::new(MemoryStorage::default()));
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let seen = prompts.clone();
+        let llm: SharedLlm = Arc::new(RwLock::new(FnLlm::new("echo", move |messages| {
+            seen.lock().unwrap().push(messages[1].content.clone());
+            "\"Trip to Rome\"".to_string()
+        })));
+        append_exchange(
+            &storage,
+            "trip",
+            vec![
+                message("user", "Plan three days in Rome"),
+                message("assistant", "Day one: the Colosseum"),
+            ],
+            "echo",
+        )
+        .await
+        .unwrap();
+
+        let options = JobOptions {
+            concurrency: 1,
+            max_attempts: 3,
+            poll_interval: Duration::from_millis(10),
+            retry_base: Duration::from_millis(10),
+            retry_max: Duration::from_millis(10),
+        };
+        let queue = JobQueue::new(storage.clone(), options, Arc::new(SystemClock));
+        register(&queue, llm, storage.clone());
+        queue.start();
+
+        enqueue(&queue, "trip");
+        enqueue(&queue, "trip");
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !queue.jobs().await.unwrap().is_empty() || prompts.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the title jobs should finish");
+
+        let conversation = load_conversation(&storage, "trip").await.unwrap().unwrap();
+        assert_eq!(conversation.title.as_deref(), Some("Trip to Rome"));
+        // The second job found the title and didn't ask again
+        let prompts = prompts.lock().unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(
+            prompts[0],
+            "user: Plan three days in Rome\n\nassistant: Day one: the Colosseum"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Reporting completed messages to the `[server.webhooks]` URL
+
+use super::{Job, JobQueue};
+use crate::adapter::traits::{FinishReason, Usage};
+use crate::config::schema::WebhooksConfig;
+use anyhow::Context;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+/// Type of the jobs delivering a webhook
+pub const WEBHOOK_JOB: &str = "webhook";
+
+/// Event reported when a reply is complete
+pub const MESSAGE_COMPLETED: &str = "message.completed";
+
+/// Header with the ID of the delivery, the same on every retry
+pub const DELIVERY_HEADER: &str = "x-ai-messenger-delivery";
+
+/// Header with the event, e.g. "message.completed"
+pub const EVENT_HEADER: &str = "x-ai-messenger-event";
+
+/// Header with `sha256=<hex HMAC of the body>`, if a secret is configured
+pub const SIGNATURE_HEADER: &str = "x-ai-messenger-signature";
+
+/// Body of a `message.completed` webhook
+#[derive(Debug, Serialize)]
+pub struct MessageCompleted {
+    pub event: &'static str,
+    /// Recipient the message was sent to
+    pub recipient: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    /// The client's `x-request-id`, or the one generated for the request
+    pub request_id: String,
+    /// Provider that generated the reply
+    pub model: String,
+    /// Text of the reply
+    pub reply: String,
+    pub finish_reason: Option<FinishReason>,
+    pub usage: Option<Usage>,
+    pub timestamp: String,
+}
+
+/// POST the payload of webhook jobs to `config.url`
+///
+/// A delivery fails, and is retried by the queue, unless the receiver
+/// answers with a 2xx status.
+pub fn register(queue: &JobQueue, client: reqwest::Client, config: WebhooksConfig) {
+    queue.register(WEBHOOK_JOB, move |job| {
+        let (client, config) = (client.clone(), config.clone());
+        async move { deliver(&client, &config, job).await }
+    });
+}
+
+/// Report `event`, if webhooks are configured
+pub fn enqueue(queue: &JobQueue, event: &MessageCompleted) {
+    if !queue.handles(WEBHOOK_JOB) {
+        return;
+    }
+    queue.enqueue(
+        WEBHOOK_JOB,
+        serde_json::to_value(event).expect("webhook events serialize to JSON"),
+    );
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    config: &WebhooksConfig,
+    job: Job,
+) -> anyhow::Result<()> {
+    let event = job
+        .payload
+        .get("event")
+        .and_then(|event| event.as_str())
+        .unwrap_or(MESSAGE_COMPLETED)
+        .to_string();
+    let body = serde_json::to_vec(&job.payload).context("Invalid webhook job")?;
+
+    let mut request = client
+        .post(&config.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(DELIVERY_HEADER, &job.id)
+        .header(EVENT_HEADER, &event);
+    if let Some(secret) = &config.secret {
+        request = request.header(SIGNATURE_HEADER, signature(secret, &body));
+    }
+
+    let response = request
+        .body(body)
+        .send()
+        .await
+        .with_context(|| format!("Webhook delivery {} failed", job.id))?;
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("Webhook delivery {} got status {}", job.id, status);
+    }
+
+    tracing::debug!("Delivered {} webhook {}", event, job.id);
+    Ok(())
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body` keyed with `secret`
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    format!("sha256={}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::MemoryStorage;
+    use crate::server::jobs::{JobOptions, SystemClock};
+    use crate::server::state::SharedStorage;
+    use axum::Router;
+    use axum::body::Bytes;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+
+    /// Deliveries a mock receiver got, with their headers
+    type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+    /// Local receiver failing the first `failures` deliveries with a 500
+    async fn mock_receiver(failures: usize) -> (String, Received) {
+        let received = Received::default();
+        let seen = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| {
+                let seen = seen.clone();
+                async move {
+                    let mut seen = seen.lock().unwrap();
+                    seen.push((headers, body));
+                    if seen.len() <= failures {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::NO_CONTENT
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (url, received)
+    }
+
+    fn event() -> MessageCompleted {
+        MessageCompleted {
+            event: MESSAGE_COMPLETED,
+            recipient: "assistant".to_string(),
+            conversation_id: Some("chat-1".to_string()),
+            request_id: "req-1".to_string(),
+            model: "echo".to_string(),
+            reply: "Hello!".to_string(),
+            finish_reason: Some(FinishReason::Stop),
+            usage: Some(Usage {
+                prompt_tokens: 3,
+                completion_tokens: 2,
+                total_tokens: 5,
+            }),
+            timestamp: "2025-01-02T03:04:05Z".to_string(),
+        }
+    }
+
+    fn queue(url: String, secret: Option<&str>) -> JobQueue {
+        let storage: SharedStorage = Arc::new(RwLock::new(MemoryStorage::default()));
+        let options = JobOptions {
+            concurrency: 1,
+            max_attempts: 3,
+            poll_interval: Duration::from_millis(10),
+            retry_base: Duration::from_millis(10),
+            retry_max: Duration::from_millis(10),
+        };
+        let queue = JobQueue::new(storage, options, Arc::new(SystemClock));
+        let config = WebhooksConfig {
+            secret: secret.map(str::to_string),
+            url,
+        };
+        register(&queue, reqwest::Client::new(), config);
+        queue.start();
+        queue
+    }
+
+    async fn wait_for_deliveries(queue: &JobQueue, received: &Received, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !queue.jobs().await.unwrap().is_empty() || received.lock().unwrap().len() < count
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the webhook should be delivered");
+    }
+
+    #[test]
+    fn test_signature() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delivers_signed_event() {
+        let (url, received) = mock_receiver(0).await;
+        let queue = queue(url, Some("s3cret"));
+
+        enqueue(&queue, &event());
+        wait_for_deliveries(&queue, &received, 1).await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        assert_eq!(headers[EVENT_HEADER], MESSAGE_COMPLETED);
+        assert_eq!(headers["content-type"], "application/json");
+        assert_eq!(
+            headers[SIGNATURE_HEADER],
+            signature("s3cret", body).as_str()
+        );
+        let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(body["recipient"], "assistant");
+        assert_eq!(body["reply"], "Hello!");
+        assert_eq!(body["finish_reason"], "stop");
+        assert_eq!(body["usage"]["total_tokens"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_failed_deliveries_are_retried() {
+        let (url, received) = mock_receiver(2).await;
+        let queue = queue(url, None);
+
+        enqueue(&queue, &event());
+        wait_for_deliveries(&queue, &received, 3).await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        // Retries are the same delivery
+        let delivery = &received[0].0[DELIVERY_HEADER];
+        assert!(
+            received
+                .iter()
+                .all(|(headers, _)| headers[DELIVERY_HEADER] == delivery)
+        );
+        assert!(received[0].0.get(SIGNATURE_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_nothing_is_queued_without_webhooks() {
+        let storage: SharedStorage = Arc::new(RwLock::new(MemoryStorage::default()));
+        let options = JobOptions::from_config(&Default::default());
+        let queue = JobQueue::new(storage, options, Arc::new(SystemClock));
+
+        enqueue(&queue, &event());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(queue.jobs().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+//! Stored history sent with new messages of a conversation
+//!
+//! With `[conversations.memory]`, the messages of a stored conversation go
+//! to the LLM ahead of each new message. The `window` strategy sends the
+//! most recent ones; `summary` has the LLM condense older messages in the
+//! background (see `jobs::summary`) and sends that summary in their place.
+//! Either way, the stored messages themselves are kept as they are.
+
+use crate::config::schema::{ConversationMemoryConfig, MemoryStrategy};
+use crate::routes::v1::conversations::model::Conversation;
+use crate::routes::v1::message::request::Message;
+use serde::Serialize;
+
+/// Characters counted as one token when estimating the size of messages
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Start of the system note carrying the summary
+const SUMMARY_NOTE: &str = "Summary of the earlier conversation:";
+
+/// How much of the stored history was sent with a message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryReport {
+    pub strategy: MemoryStrategy,
+    /// Stored messages sent
+    pub messages: usize,
+    /// Stored messages not sent, left out or summarized
+    pub omitted: usize,
+    /// Whether a summary was sent in place of the omitted messages
+    pub summarized: bool,
+}
+
+/// Stored history to send ahead of the messages of a request
+///
+/// At most the `window_messages` most recent messages are sent. With
+/// `summary`, the summary goes ahead of them as a system note, and only
+/// messages it doesn't cover are sent. Without a summary yet (jobs are
+/// off, or summarizing keeps failing), that's the window alone.
+pub fn history(
+    config: &ConversationMemoryConfig,
+    conversation: &Conversation,
+) -> (Vec<Message>, MemoryReport) {
+    let stored = &conversation.messages;
+    let window_start = stored.len().saturating_sub(config.window_messages);
+    let (start, summary) = match (config.strategy, &conversation.summary) {
+        (MemoryStrategy::Summary, Some(summary)) => (
+            summary.messages.clamp(window_start, stored.len()),
+            Some(summary),
+        ),
+        _ => (window_start, None),
+    };
+
+    let mut messages = Vec::with_capacity(stored.len() - start + 1);
+    if let Some(summary) = summary {
+        messages.push(Message {
+            role: "system".to_string(),
+            content: format!("{}\n{}", SUMMARY_NOTE, summary.content).into(),
+        });
+    }
+    messages.extend(stored[start..].iter().map(|message| Message {
+        role: message.role.clone(),
+        content: message.content.as_str().into(),
+    }));
+
+    let report = MemoryReport {
+        strategy: config.strategy,
+        messages: stored.len() - start,
+        omitted: start,
+        summarized: summary.is_some(),
+    };
+    (messages, report)
+}
+
+/// Whether the messages the summary doesn't cover have grown enough to
+/// summarize the older ones
+///
+/// The `window_messages` most recent messages are never summarized.
+pub fn needs_summary(config: &ConversationMemoryConfig, conversation: &Conversation) -> bool {
+    if config.strategy != MemoryStrategy::Summary {
+        return false;
+    }
+    let unsummarized = &conversation.messages[conversation
+        .summarized_messages()
+        .min(conversation.messages.len())..];
+    if unsummarized.len() <= config.window_messages {
+        return false;
+    }
+
+    let tokens: u64 = unsummarized
+        .iter()
+        .map(|message| estimate_tokens(&message.content))
+        .sum();
+    tokens >= config.summary_trigger_tokens
+}
+
+/// Rough token count of `text`, about four characters per token
+pub fn estimate_tokens(text: &str) -> u64 {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::v1::conversations::model::{ConversationMessage, ConversationSummary};
+
+    fn conversation(messages: usize) -> Conversation {
+        let mut conversation = Conversation::new("chat-1");
+        conversation.messages = (0..messages)
+            .map(|i| ConversationMessage {
+                role: if i % 2 == 0 { "user" } else { "assistant" }.to_string(),
+                content: format!("message {i:02}"),
+                timestamp: None,
+                model: None,
+                usage: None,
+                moderation: Vec::new(),
+            })
+            .collect();
+        conversation
+    }
+
+    fn contents(messages: &[Message]) -> Vec<String> {
+        messages
+            .iter()
+            .map(|message| message.content.text())
+            .collect()
+    }
+
+    #[test]
+    fn test_window_sends_most_recent_messages() {
+        let config = ConversationMemoryConfig {
+            window_messages: 2,
+            ..ConversationMemoryConfig::default()
+        };
+
+        let (messages, report) = history(&config, &conversation(5));
+        assert_eq!(contents(&messages), ["message 03", "message 04"]);
+        assert_eq!(messages[0].role, "assistant");
+        assert_eq!(
+            report,
+            MemoryReport {
+                strategy: MemoryStrategy::Window,
+                messages: 2,
+                omitted: 3,
+                summarized: false,
+            }
+        );
+
+        let (messages, report) = history(&config, &conversation(1));
+        assert_eq!(contents(&messages), ["message 00"]);
+        assert_eq!(report.omitted, 0);
+    }
+
+    #[test]
+    fn test_summary_replaces_covered_messages() {
+        let config = ConversationMemoryConfig {
+            strategy: MemoryStrategy::Summary,
+            window_messages: 2,
+            ..ConversationMemoryConfig::default()
+        };
+        let mut conversation = conversation(5);
+
+        // Nothing summarized yet: the window is sent
+        let (messages, report) = history(&config, &conversation);
+        assert_eq!(contents(&messages), ["message 03", "message 04"]);
+        assert!(!report.summarized);
+
+        conversation.summary = Some(ConversationSummary {
+            content: "They counted to two.".to_string(),
+            messages: 3,
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+        });
+        let (messages, report) = history(&config, &conversation);
+        assert_eq!(messages[0].role, "system");
+        assert_eq!(
+            contents(&messages),
+            [
+                "Summary of the earlier conversation:\nThey counted to two.",
+                "message 03",
+                "message 04"
+            ]
+        );
+        assert_eq!(
+            report,
+            MemoryReport {
+                strategy: MemoryStrategy::Summary,
+                messages: 2,
+                omitted: 3,
+                summarized: true,
+            }
+        );
+
+        // Messages since an old summary are capped at the window too
+        let mut grown = conversation.clone();
+        grown
+            .messages
+            .extend(self::conversation(9).messages.split_off(5));
+        let (messages, report) = history(&config, &grown);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(contents(&messages)[1..], ["message 07", "message 08"]);
+        assert_eq!(report.omitted, 7);
+        assert!(report.summarized);
+    }
+
+    #[test]
+    fn test_needs_summary_at_threshold() {
+        // Each message is 10 characters, 3 estimated tokens
+        let config = ConversationMemoryConfig {
+            strategy: MemoryStrategy::Summary,
+            summary_trigger_tokens: 12,
+            window_messages: 2,
+            ..ConversationMemoryConfig::default()
+        };
+
+        assert!(!needs_summary(&config, &conversation(3)));
+        assert!(needs_summary(&config, &conversation(4)));
+
+        // Only messages the summary doesn't cover count
+        let mut covered = conversation(5);
+        covered.summary = Some(ConversationSummary {
+            content: "Earlier".to_string(),
+            messages: 2,
+            updated_at: "2024-01-01T00:00:00+00:00".to_string(),
+        });
+        assert!(!needs_summary(&config, &covered));
+
+        // Not past the window, however long the messages are
+        let config = ConversationMemoryConfig {
+            summary_trigger_tokens: 1,
+            window_messages: 4,
+            ..config
+        };
+        assert!(!needs_summary(&config, &conversation(4)));
+
+        let window = ConversationMemoryConfig {
+            strategy: MemoryStrategy::Window,
+            ..config
+        };
+        assert!(!needs_summary(&window, &conversation(50)));
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+}
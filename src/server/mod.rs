@@ -1,4 +1,18 @@
-mod router;
+pub mod access_log;
+pub mod auth;
+pub mod body_limit;
+pub mod cancellation;
+pub mod idempotency;
+pub mod jobs;
+pub mod memory;
+pub mod moderation;
+pub mod reload;
+pub mod router;
+pub mod runtime_info;
 pub mod startup;
+pub mod state;
+pub mod timeout;
+pub mod usage_log;
 
 pub use startup::start;
+pub use state::AppState;
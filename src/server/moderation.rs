@@ -0,0 +1,402 @@
+use super::access_log::REDACTED;
+use crate::adapter::services::SharedLlm;
+use crate::adapter::traits::ServiceError;
+use crate::config::schema::{
+    ModerationAction, ModerationAdapterConfig, ModerationConfig, ModerationDirection,
+    ModerationRule,
+};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Prefix of the rule reported for content the LLM adapter flagged
+pub const ADAPTER_RULE_PREFIX: &str = "adapter:";
+
+/// Moderation rule that matched a message or reply
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ModerationDecision {
+    pub action: ModerationAction,
+    /// `input` for user messages, `output` for the LLM's reply
+    pub direction: ModerationDirection,
+    /// ID of the rule, or `adapter:<category>` if the LLM adapter flagged it
+    pub rule: String,
+}
+
+/// Outcome of moderating one piece of text
+#[derive(Debug, Default)]
+pub struct Review {
+    /// The text, with the matches of `redact` rules replaced
+    pub content: String,
+    /// Rules that matched, in config order, the adapter's verdict last
+    pub decisions: Vec<ModerationDecision>,
+}
+
+impl Review {
+    /// Decision blocking the text, if any
+    pub fn blocked(&self) -> Option<&ModerationDecision> {
+        self.decisions
+            .iter()
+            .find(|decision| decision.action == ModerationAction::Block)
+    }
+}
+
+/// Rule with its keywords and pattern compiled into one expression
+#[derive(Debug)]
+struct Rule {
+    action: ModerationAction,
+    direction: ModerationDirection,
+    id: String,
+    matcher: Regex,
+}
+
+impl Rule {
+    fn compile(rule: &ModerationRule) -> Result<Self> {
+        if rule.id.trim().is_empty() {
+            anyhow::bail!("Moderation rules need an id");
+        }
+
+        let mut alternatives = Vec::new();
+        if let Some(pattern) = &rule.pattern {
+            // Check it alone, so errors point at the pattern
+            Regex::new(pattern)
+                .with_context(|| format!("Invalid pattern of moderation rule '{}'", rule.id))?;
+            alternatives.push(format!("(?:{})", pattern));
+        }
+        let keywords: Vec<_> = rule
+            .keywords
+            .iter()
+            .map(|keyword| keyword.trim())
+            .filter(|keyword| !keyword.is_empty())
+            .map(regex::escape)
+            .collect();
+        if !keywords.is_empty() {
+            alternatives.push(format!(r"(?i:\b(?:{})\b)", keywords.join("|")));
+        }
+        if alternatives.is_empty() {
+            anyhow::bail!("Moderation rule '{}' needs keywords or a pattern", rule.id);
+        }
+
+        Ok(Rule {
+            action: rule.action,
+            direction: rule.direction,
+            id: rule.id.clone(),
+            matcher: Regex::new(&alternatives.join("|"))?,
+        })
+    }
+}
+
+/// The `[moderation]` policy, checking messages before they reach the LLM
+/// and replies before they're returned or stored
+#[derive(Debug, Clone)]
+pub struct Moderation {
+    /// Whether and how to ask the LLM adapter (None to only apply the rules)
+    adapter: Option<ModerationAdapterConfig>,
+    /// Decisions taken since startup, for metrics
+    counts: Arc<Mutex<BTreeMap<ModerationDecision, u64>>>,
+    rules: Arc<[Rule]>,
+}
+
+impl Moderation {
+    /// Compile the rules of `config` (None if there's nothing to check)
+    pub fn from_config(config: &ModerationConfig) -> Result<Option<Self>> {
+        if config.rules.is_empty() && config.adapter.is_none() {
+            return Ok(None);
+        }
+
+        let rules = config
+            .rules
+            .iter()
+            .map(Rule::compile)
+            .collect::<Result<_>>()?;
+
+        Ok(Some(Moderation {
+            adapter: config.adapter.clone(),
+            counts: Arc::default(),
+            rules,
+        }))
+    }
+
+    /// Whether replies are checked, so they must be complete before they're sent
+    pub fn checks_output(&self) -> bool {
+        let output = ModerationDirection::Output;
+        self.rules.iter().any(|rule| rule.direction.covers(output))
+            || self
+                .adapter
+                .as_ref()
+                .is_some_and(|adapter| adapter.direction.covers(output))
+    }
+
+    /// Check `content` going in `direction` (`input` or `output`)
+    ///
+    /// Rules are applied in order, each seeing the text as redacted by the
+    /// previous ones; a blocking rule ends the check. With `[moderation.adapter]`
+    /// the text is then sent to `llm`, if one is loaded.
+    pub async fn review(
+        &self,
+        direction: ModerationDirection,
+        content: &str,
+        llm: Option<&SharedLlm>,
+    ) -> Result<Review, ServiceError> {
+        let mut review = Review {
+            content: content.to_string(),
+            decisions: Vec::new(),
+        };
+
+        let rules = self
+            .rules
+            .iter()
+            .filter(|rule| rule.direction.covers(direction));
+        for rule in rules {
+            let Some(matched) = rule.matcher.find(&review.content) else {
+                continue;
+            };
+            tracing::debug!(
+                "Moderation rule '{}' matched {:?}",
+                rule.id,
+                matched.as_str()
+            );
+
+            if rule.action == ModerationAction::Redact {
+                review.content = rule
+                    .matcher
+                    .replace_all(&review.content, REDACTED)
+                    .into_owned();
+            }
+            review
+                .decisions
+                .push(self.decide(rule.action, direction, &rule.id));
+            if rule.action == ModerationAction::Block {
+                return Ok(review);
+            }
+        }
+
+        if let Some(adapter) = &self.adapter
+            && adapter.direction.covers(direction)
+            && let Some(llm) = llm
+        {
+            let category = llm.write().await.moderate(&review.content).await?;
+            if let Some(category) = category {
+                if adapter.action == ModerationAction::Redact {
+                    review.content = REDACTED.to_string();
+                }
+                let rule = format!("{}{}", ADAPTER_RULE_PREFIX, category);
+                review
+                    .decisions
+                    .push(self.decide(adapter.action, direction, &rule));
+            }
+        }
+
+        Ok(review)
+    }
+
+    /// Log and count a decision
+    ///
+    /// Only the rule is logged at info level, never the text it matched.
+    fn decide(
+        &self,
+        action: ModerationAction,
+        direction: ModerationDirection,
+        rule: &str,
+    ) -> ModerationDecision {
+        tracing::info!(
+            "Moderation rule '{}' matched {} content ({})",
+            rule,
+            direction.as_str(),
+            action.as_str()
+        );
+
+        let decision = ModerationDecision {
+            action,
+            direction,
+            rule: rule.to_string(),
+        };
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        *counts.entry(decision.clone()).or_default() += 1;
+        decision
+    }
+
+    /// Decisions taken so far, with how often each was taken
+    pub fn decision_counts(&self) -> Vec<(ModerationDecision, u64)> {
+        let counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        counts
+            .iter()
+            .map(|(decision, count)| (decision.clone(), *count))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::FnLlm;
+    use tokio::sync::RwLock;
+
+    fn rule(id: &str, action: ModerationAction, direction: ModerationDirection) -> ModerationRule {
+        ModerationRule {
+            action,
+            direction,
+            id: id.to_string(),
+            keywords: vec!["secret".to_string(), "top-secret".to_string()],
+            pattern: None,
+        }
+    }
+
+    fn moderation(rules: Vec<ModerationRule>) -> Moderation {
+        Moderation::from_config(&ModerationConfig {
+            adapter: None,
+            rules,
+        })
+        .unwrap()
+        .unwrap()
+    }
+
+    fn moderation_with_input_only() -> Moderation {
+        moderation(vec![rule(
+            "requests",
+            ModerationAction::Block,
+            ModerationDirection::Input,
+        )])
+    }
+
+    async fn input(moderation: &Moderation, content: &str) -> Review {
+        moderation
+            .review(ModerationDirection::Input, content, None)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        assert!(
+            Moderation::from_config(&ModerationConfig::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let mut bad_pattern = rule("bad", ModerationAction::Block, ModerationDirection::Both);
+        bad_pattern.pattern = Some("(".to_string());
+        let error = Moderation::from_config(&ModerationConfig {
+            adapter: None,
+            rules: vec![bad_pattern],
+        })
+        .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Invalid pattern of moderation rule 'bad'")
+        );
+
+        let mut empty = rule("empty", ModerationAction::Block, ModerationDirection::Both);
+        empty.keywords = vec![" ".to_string()];
+        let error = Moderation::from_config(&ModerationConfig {
+            adapter: None,
+            rules: vec![empty],
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("needs keywords or a pattern"));
+    }
+
+    #[tokio::test]
+    async fn test_keywords_match_whole_words_ignoring_case() {
+        let moderation = moderation(vec![rule(
+            "secrets",
+            ModerationAction::Flag,
+            ModerationDirection::Both,
+        )]);
+
+        let review = input(&moderation, "That's a SECRET.").await;
+        assert_eq!(review.decisions.len(), 1);
+        assert_eq!(review.decisions[0].rule, "secrets");
+        assert_eq!(review.decisions[0].direction, ModerationDirection::Input);
+        assert_eq!(review.content, "That's a SECRET.");
+
+        assert!(input(&moderation, "Secretary").await.decisions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_redact_replaces_matches() {
+        let mut cards = rule("cards", ModerationAction::Redact, ModerationDirection::Both);
+        cards.keywords.clear();
+        cards.pattern = Some(r"\d{4}-\d{4}".to_string());
+        let moderation = moderation(vec![cards]);
+
+        let review = input(&moderation, "Use 1234-5678 or 8765-4321").await;
+        assert_eq!(review.content, "Use [REDACTED] or [REDACTED]");
+        assert!(review.blocked().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_block_ends_the_check() {
+        let moderation = moderation(vec![
+            rule(
+                "blocked",
+                ModerationAction::Block,
+                ModerationDirection::Both,
+            ),
+            rule("flagged", ModerationAction::Flag, ModerationDirection::Both),
+        ]);
+
+        let review = input(&moderation, "secret").await;
+        assert_eq!(review.blocked().unwrap().rule, "blocked");
+        assert_eq!(review.decisions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rules_apply_to_their_direction() {
+        let moderation = moderation(vec![rule(
+            "replies",
+            ModerationAction::Block,
+            ModerationDirection::Output,
+        )]);
+        assert!(moderation.checks_output());
+
+        assert!(input(&moderation, "secret").await.blocked().is_none());
+        let review = moderation
+            .review(ModerationDirection::Output, "secret", None)
+            .await
+            .unwrap();
+        assert!(review.blocked().is_some());
+
+        let moderation = moderation_with_input_only();
+        assert!(!moderation.checks_output());
+    }
+
+    #[tokio::test]
+    async fn test_decisions_are_counted() {
+        let moderation = moderation_with_input_only();
+        input(&moderation, "secret").await;
+        input(&moderation, "top-secret").await;
+        input(&moderation, "public").await;
+
+        let counts = moderation.decision_counts();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[0].0.rule, "requests");
+        assert_eq!(counts[0].1, 2);
+    }
+
+    #[tokio::test]
+    async fn test_adapter_needs_moderation_capability() {
+        let moderation = Moderation::from_config(&ModerationConfig {
+            adapter: Some(ModerationAdapterConfig::default()),
+            rules: Vec::new(),
+        })
+        .unwrap()
+        .unwrap();
+        let llm: SharedLlm = Arc::new(RwLock::new(FnLlm::new("echo", |_| String::new())));
+
+        let error = moderation
+            .review(ModerationDirection::Input, "hello", Some(&llm))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("doesn't support moderation"));
+
+        // Without an LLM adapter there's nothing to ask
+        let review = input(&moderation, "hello").await;
+        assert!(review.decisions.is_empty());
+    }
+}
@@ -0,0 +1,390 @@
+use super::router::normalize_base_path;
+use super::startup::{app_with_state, create_storage_dirs, make_ephemeral};
+use super::state::AppState;
+use crate::config::{Config, discovery::load_from_file};
+use anyhow::{Context, Result};
+use axum::{Router, extract::Request};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+use tower::ServiceExt;
+
+/// Quiet period after the last change before the config is reloaded
+///
+/// Editors often save in several writes (truncate, write, rename), and
+/// reloading after the first one would read a partial file.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Router that forwards each request to the latest router sent on `routers`
+///
+/// Requests already in flight finish on the router they started on.
+pub fn reloadable(routers: watch::Receiver<Router>) -> Router {
+    let service = tower::service_fn(move |request: Request| {
+        let router = routers.borrow().clone();
+        router.oneshot(request)
+    });
+
+    Router::new().fallback_service(service)
+}
+
+/// The config being served, with the adapters loaded for it
+pub struct ServedConfig {
+    pub config: Config,
+    /// Data directory the adapters were loaded from
+    pub data_dir: PathBuf,
+    /// Whether reloaded configs are made ephemeral too
+    pub ephemeral: bool,
+    pub state: AppState,
+}
+
+/// Running config watcher, see `watch_config`
+pub struct ConfigWatcher {
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+    watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+    /// Stop watching and wait for a reload in progress to finish
+    pub async fn stop(self) {
+        drop(self.watcher);
+        let _ = self.shutdown.send(());
+        let _ = self.task.await;
+    }
+}
+
+/// Watch the config file and send a rebuilt router after each change
+///
+/// The parent directory is watched, so editors that replace the file
+/// instead of writing it in place are noticed too. Adapters whose config
+/// didn't change are kept, see `AppState::reconcile`.
+pub fn watch_config(
+    path: PathBuf,
+    served: ServedConfig,
+    create_dirs: bool,
+    routers: watch::Sender<Router>,
+) -> Result<ConfigWatcher> {
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+    let file_name = path.file_name().map(ToOwned::to_owned);
+
+    // One pending change is enough, further events are coalesced into it
+    let (changes, receiver) = mpsc::channel(1);
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event)
+                if !event.kind.is_access()
+                    && event
+                        .paths
+                        .iter()
+                        .any(|changed| changed.file_name() == file_name.as_deref()) =>
+            {
+                let _ = changes.try_send(());
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Config watcher error: {}", e),
+        })
+        .context("Failed to create config watcher")?;
+
+    watcher
+        .watch(&directory, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", directory.display()))?;
+
+    let (shutdown, shutdown_receiver) = oneshot::channel();
+    let task = tokio::spawn(reload_on_change(
+        path,
+        served,
+        create_dirs,
+        Changes {
+            changes: receiver,
+            shutdown: shutdown_receiver,
+        },
+        routers,
+    ));
+
+    Ok(ConfigWatcher {
+        shutdown,
+        task,
+        watcher,
+    })
+}
+
+/// Debounced change notifications, until the watcher is stopped
+struct Changes {
+    changes: mpsc::Receiver<()>,
+    shutdown: oneshot::Receiver<()>,
+}
+
+impl Changes {
+    /// Wait for a change and for the file to be quiet again afterwards
+    ///
+    /// Returns false once the watcher is stopped.
+    async fn next(&mut self) -> bool {
+        tokio::select! {
+            change = self.changes.recv() => {
+                if change.is_none() {
+                    return false;
+                }
+            }
+            _ = &mut self.shutdown => return false,
+        }
+
+        loop {
+            tokio::select! {
+                change = tokio::time::timeout(DEBOUNCE, self.changes.recv()) => match change {
+                    Ok(Some(())) => {}
+                    Ok(None) => return false,
+                    Err(_) => return true,
+                },
+                _ = &mut self.shutdown => return false,
+            }
+        }
+    }
+}
+
+/// Rebuild the router whenever a debounced change arrives
+///
+/// A config that fails to load, validate or load its adapters is logged
+/// and skipped, so the server keeps running with the last good one.
+/// Adapters the new config replaced are shut down once it's in use.
+async fn reload_on_change(
+    path: PathBuf,
+    mut served: ServedConfig,
+    create_dirs: bool,
+    mut changes: Changes,
+    routers: watch::Sender<Router>,
+) {
+    while changes.next().await {
+        match reload(&path, &served, create_dirs).await {
+            Ok((reloaded, router)) => {
+                routers.send_modify(|current| *current = router);
+                served.state.shutdown_replaced(&reloaded.state).await;
+                served = reloaded;
+                tracing::info!("Reloaded config from {}", path.display());
+            }
+            Err(e) => tracing::error!(
+                "Keeping previous config, failed to reload {}: {:#}",
+                path.display(),
+                e
+            ),
+        }
+    }
+}
+
+/// Load and check the changed config, reconcile adapters and build a router
+async fn reload(
+    path: &Path,
+    previous: &ServedConfig,
+    create_dirs: bool,
+) -> Result<(ServedConfig, Router)> {
+    // The profile applied at startup applies to the changed file too
+    let (mut config, config_dir) = load_from_file(path, previous.config.profile.as_deref())?;
+    crate::config::builder::check(&config).context("Invalid config")?;
+    keep_restart_settings(&previous.config, &mut config);
+    if previous.ephemeral {
+        make_ephemeral(&mut config);
+    }
+
+    let data_dir = crate::config::data_dir(&config, Some(&config_dir));
+    if create_dirs {
+        create_storage_dirs(&config, Some(&config_dir))?;
+    }
+    let state = previous
+        .state
+        .reconcile(&previous.config, &previous.data_dir, &config, &data_dir)
+        .await
+        .context("Failed to load adapters")?;
+
+    let router = app_with_state(&config, Some(&config_dir), state.clone())?;
+    let served = ServedConfig {
+        config,
+        data_dir,
+        ephemeral: previous.ephemeral,
+        state,
+    };
+    Ok((served, router))
+}
+
+/// Keep the settings that only take effect after a restart
+///
+/// The listener, routes and shared HTTP client are set up once, so changes
+/// to them are logged instead of applied.
+fn keep_restart_settings(previous: &Config, config: &mut Config) {
+    let (old, new) = (&previous.server, &mut config.server);
+    let changed = [
+        ("server.host", old.host != new.host),
+        ("server.port", old.port != new.port),
+        (
+            "server.base_path",
+            normalize_base_path(&old.base_path) != normalize_base_path(&new.base_path),
+        ),
+        ("http", previous.http != config.http),
+    ];
+    for (setting, _) in changed.iter().filter(|(_, changed)| *changed) {
+        tracing::warn!("{} changed, restart required to apply it", setting);
+    }
+
+    new.host = old.host.clone();
+    new.port = old.port;
+    new.base_path = old.base_path.clone();
+    config.http = previous.http.clone();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use tempfile::TempDir;
+
+    fn text_router(text: &'static str) -> Router {
+        Router::new().route("/", get(move || async move { text }))
+    }
+
+    async fn get_text(app: Router) -> String {
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reloadable_uses_latest_router() {
+        let (routers, receiver) = watch::channel(text_router("first"));
+        let app = reloadable(receiver);
+
+        assert_eq!(get_text(app.clone()).await, "first");
+
+        routers.send(text_router("second")).unwrap();
+        assert_eq!(get_text(app).await, "second");
+    }
+
+    /// Served state of the default config, with data in `temp_dir`
+    fn served_default(temp_dir: &TempDir) -> ServedConfig {
+        ServedConfig {
+            config: Config::default(),
+            data_dir: temp_dir.path().join("data"),
+            ephemeral: false,
+            state: AppState::default(),
+        }
+    }
+
+    /// Write a config file in `temp_dir` with `extra` and the data directory
+    fn write_config(temp_dir: &TempDir, extra: &str) -> PathBuf {
+        std::fs::create_dir_all(temp_dir.path().join("data")).unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            format!(
+                "{}\n\n[storage]\ndata_dir = {:?}\n",
+                extra,
+                temp_dir.path().join("data")
+            ),
+        )
+        .unwrap();
+
+        path
+    }
+
+    #[tokio::test]
+    async fn test_reload_rejects_invalid_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(&path, "[server\nport = ").unwrap();
+
+        assert!(
+            reload(&path, &served_default(&temp_dir), false)
+                .await
+                .is_err()
+        );
+
+        // Parses, but fails validation
+        let path = write_config(&temp_dir, "[server]\nhost = \"\"");
+        let Err(error) = reload(&path, &served_default(&temp_dir), false).await else {
+            panic!("an invalid host should be rejected");
+        };
+        assert!(format!("{:#}", error).contains("server.host must not be empty"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_rejects_adapter_that_does_not_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config(&temp_dir, "[adapters.storage]\nprovider = \"missing\"");
+
+        let Err(error) = reload(&path, &served_default(&temp_dir), false).await else {
+            panic!("a missing adapter should be rejected");
+        };
+        assert!(format!("{:#}", error).contains("Failed to load adapters"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_keeps_restart_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config(
+            &temp_dir,
+            "[http]\npool_max_idle_per_host = 1\n\n[server]\nbase_path = \"api\"\nport = 9999",
+        );
+
+        let (served, router) = reload(&path, &served_default(&temp_dir), false)
+            .await
+            .unwrap();
+        assert_eq!(served.config.server.base_path, "");
+        assert_eq!(served.config.server.port, Config::default().server.port);
+        assert_eq!(served.config.http, Config::default().http);
+
+        let response = router
+            .oneshot(Request::builder().uri("/api").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_reload_applies_adapter_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config(&temp_dir, "[adapters.storage]\nprovider = \"sqlite\"");
+
+        let (served, _) = reload(&path, &served_default(&temp_dir), false)
+            .await
+            .unwrap();
+
+        let storage = served.state.storage.expect("storage should be loaded");
+        assert_eq!(storage.read().await.provider_name(), "sqlite");
+    }
+
+    #[tokio::test]
+    async fn test_watcher_reloads_changed_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_config(&temp_dir, "");
+
+        let (routers, mut receiver) = watch::channel(text_router("initial"));
+        let watcher =
+            watch_config(path.clone(), served_default(&temp_dir), false, routers).unwrap();
+
+        write_config(
+            &temp_dir,
+            "[adapters.storage]\nprovider = \"sqlite\"\nconfig = { path = \"watched.sqlite3\" }",
+        );
+        tokio::time::timeout(Duration::from_secs(10), receiver.changed())
+            .await
+            .expect("config should be reloaded")
+            .unwrap();
+
+        // The new storage adapter was opened, without restarting anything
+        assert!(temp_dir.path().join("data/watched.sqlite3").exists());
+
+        tokio::time::timeout(Duration::from_secs(5), watcher.stop())
+            .await
+            .expect("watcher should stop");
+    }
+}
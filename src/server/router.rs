@@ -1,17 +1,729 @@
-use crate::routes;
-use axum::Router;
+use super::{
+    access_log, auth, body_limit,
+    idempotency::{self, IdempotentMessages},
+    state::AppState,
+    timeout::{self, RouteTimeouts},
+};
+use crate::routes::{
+    self,
+    versions::{self, MountedVersion},
+};
+use axum::{Router, extract::State, middleware, response::Response, routing::get};
+use serde_json::{Map, Value, json};
+use std::sync::Arc;
 
 /// Build the main application router
-pub fn build_router(base_path: &str) -> Router {
+pub fn build_router(base_path: &str, state: AppState) -> Router {
+    // If base_path is empty, mount each API version directly at /{version}
+    // If base_path is set (e.g., "api"), mount it at /{base_path}/{version}
+    let base_path = normalize_base_path(base_path);
+    let prefix = if base_path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", base_path)
+    };
+    let versions_path = format!("{}/versions", prefix);
+    let mounted: Vec<(MountedVersion, String)> = state
+        .api_versions
+        .iter()
+        .map(|version| (version.clone(), format!("{}/{}", prefix, version.name())))
+        .collect();
+
+    let root = get({
+        let links = root_links(&mounted, &versions_path);
+        move |State(state): State<AppState>| routes::health::health_check(state, links.clone())
+    });
+    let versions = get({
+        let document = versions::versions_document(&mounted);
+        move || versions::list_versions(document.clone())
+    });
+
+    // Health endpoint / root document and metrics (always unversioned at root)
+    let app = Router::new()
+        .route("/", root.clone())
+        .route("/metrics", get(routes::metrics::metrics))
+        .route(&versions_path, versions);
+
+    // Also serve the root document at the base path itself
+    let mut app = if base_path.is_empty() {
+        app
+    } else {
+        app.route(&prefix, root.clone())
+            .route(&format!("{}/", prefix), root)
+    };
+
+    let mut message_prefixes = Vec::new();
+    for (version, path) in &mounted {
+        let mut routes = (version.api.router)();
+        let headers = version.lifecycle_headers(&versions_path);
+        if !headers.is_empty() {
+            let headers = Arc::new(headers);
+            routes = routes.layer(middleware::map_response(move |response: Response| {
+                versions::add_lifecycle_headers(headers.clone(), response)
+            }));
+        }
+        app = app.nest(path, routes);
+
+        if let Some(message_prefix) = version.api.capabilities.message_prefix {
+            message_prefixes.push(format!("{}{}", path, message_prefix));
+        }
+    }
+
+    // Router layers see a 405 before axum adds its `Allow` header, so this
+    // one wraps the finished routes as a whole
     let app = Router::new()
-        // Health endpoint (always unversioned at root)
-        .route("/", axum::routing::get(routes::health::health_check));
+        .fallback_service(
+            app.fallback(routes::fallback::not_found)
+                .with_state(state.clone()),
+        )
+        .layer(middleware::map_response(
+            routes::fallback::method_not_allowed,
+        ));
+
+    // Retried message requests get the first response again; inside the
+    // timeout so requests waiting on a duplicate are limited too
+    let app = app.layer(middleware::from_fn_with_state(
+        IdempotentMessages {
+            body_limit: state.body_limit,
+            cache: state.idempotency.clone(),
+            message_prefixes: message_prefixes.clone(),
+        },
+        idempotency::replay_messages,
+    ));
+
+    // Handlers that don't respond in time get a 504
+    let app = app.layer(middleware::from_fn_with_state(
+        RouteTimeouts {
+            message_prefixes,
+            state: state.clone(),
+        },
+        timeout::enforce_timeout,
+    ));
+
+    // Oversized bodies get a 413 before any handler reads them
+    let app = app
+        .layer(state.body_limit.layer())
+        .layer(middleware::from_fn_with_state(
+            state.body_limit,
+            body_limit::reject_oversized,
+        ));
+
+    // Authentication wraps everything, including fallbacks
+    let app = match state.auth.clone() {
+        Some(keys) => app.layer(middleware::from_fn_with_state(keys, auth::require_api_key)),
+        None => app,
+    };
 
-    // If base_path is empty, mount v1 directly at /v1
-    // If base_path is set (e.g., "api"), mount v1 at /{base_path}/v1
-    if base_path.is_empty() {
-        app.nest("/v1", routes::v1::router())
+    // The access log sees every request, including rejected ones
+    if state.access_log.is_enabled() {
+        app.layer(middleware::from_fn_with_state(
+            state.access_log.clone(),
+            access_log::log_requests,
+        ))
     } else {
-        app.nest(&format!("/{}/v1", base_path), routes::v1::router())
+        app
+    }
+}
+
+/// Links of the root document: each mounted version, the discovery
+/// document, and the endpoints of the last version listed
+fn root_links(mounted: &[(MountedVersion, String)], versions_path: &str) -> Map<String, Value> {
+    let mut links = Map::new();
+    for (version, path) in mounted {
+        links.insert(version.name().to_string(), json!(path));
+    }
+    if let Some((latest, path)) = mounted.last() {
+        for (name, endpoint) in latest.api.capabilities.links {
+            links.insert(name.to_string(), json!(format!("{}{}", path, endpoint)));
+        }
+    }
+    links.insert("versions".to_string(), json!(versions_path));
+    links
+}
+
+/// Strip leading and trailing slashes so "api", "/api" and "/api/" are equivalent
+pub fn normalize_base_path(base_path: &str) -> &str {
+    base_path.trim().trim_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::traits::{
+        AdapterService, ChatMessage, Finish, GenerationOptions, LlmAdapter, ModelInfo, ServiceError,
+    };
+    use crate::config::schema::{AccessLogConfig, AccessLogMode};
+    use crate::routes::versions::{ApiVersion, ApiVersions, Capabilities};
+    use crate::server::access_log::AccessLog;
+    use crate::server::body_limit::BodyLimit;
+    use crate::server::timeout::RequestTimeouts;
+    use async_trait::async_trait;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use tower::ServiceExt;
+
+    /// LLM adapter that takes `delay` to reply, streaming in two halves
+    struct SlowLlm {
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl AdapterService for SlowLlm {
+        fn service_name(&self) -> &'static str {
+            "llm"
+        }
+
+        fn provider_name(&self) -> &str {
+            "slow"
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn capabilities(&self) -> crate::adapter::traits::Capabilities {
+            crate::adapter::traits::Capabilities {
+                streaming: true,
+                ..Default::default()
+            }
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl LlmAdapter for SlowLlm {
+        async fn send_message(
+            &mut self,
+            _messages: &[ChatMessage],
+            _options: &GenerationOptions,
+        ) -> Result<String, ServiceError> {
+            tokio::time::sleep(self.delay).await;
+            Ok("slow reply".to_string())
+        }
+
+        async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+            Err(ServiceError::ServiceUnavailable(
+                "no model info".to_string(),
+            ))
+        }
+
+        async fn stream_message(
+            &mut self,
+            _messages: &[ChatMessage],
+            _options: &GenerationOptions,
+            chunks: mpsc::Sender<String>,
+        ) -> Result<Finish, ServiceError> {
+            let _ = chunks.send("slow".to_string()).await;
+            tokio::time::sleep(self.delay).await;
+            let _ = chunks.send(" reply".to_string()).await;
+            Ok(Finish::stop())
+        }
+    }
+
+    /// Router whose LLM takes 200ms, with the given time limits
+    fn slow_app(default: Duration, message: Duration) -> Router {
+        let state = AppState {
+            request_timeouts: RequestTimeouts { default, message },
+            ..AppState::with_llm(SlowLlm {
+                delay: Duration::from_millis(200),
+            })
+        };
+
+        build_router("", state)
+    }
+
+    fn message_request(stream: bool) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/v1/message/alice")
+            .header("content-type", "application/json")
+            .header("accept", "application/x-ndjson")
+            .body(Body::from(format!(
+                r#"{{"messages":[{{"role":"user","content":"Hi"}}],"stream":{}}}"#,
+                stream
+            )))
+            .unwrap()
+    }
+
+    async fn json_for(app: Router, method: &str, uri: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    async fn status_for(app: Router, method: &str, uri: &str) -> StatusCode {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"messages":[]}"#))
+            .unwrap();
+
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[test]
+    fn test_normalize_base_path() {
+        assert_eq!(normalize_base_path(""), "");
+        assert_eq!(normalize_base_path("/"), "");
+        assert_eq!(normalize_base_path("api"), "api");
+        assert_eq!(normalize_base_path("/api"), "api");
+        assert_eq!(normalize_base_path("/api/"), "api");
+        assert_eq!(normalize_base_path("/api/internal/"), "api/internal");
+    }
+
+    #[tokio::test]
+    async fn test_router_without_base_path() {
+        let app = build_router("", AppState::default());
+
+        assert_eq!(status_for(app.clone(), "GET", "/").await, StatusCode::OK);
+        assert_eq!(
+            status_for(app, "POST", "/v1/message/alice").await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_router_with_base_path() {
+        for base_path in ["api", "/api", "/api/"] {
+            let app = build_router(base_path, AppState::default());
+
+            assert_eq!(
+                status_for(app.clone(), "POST", "/api/v1/message/alice").await,
+                StatusCode::OK,
+                "prefixed path should match for base_path {:?}",
+                base_path
+            );
+            assert_eq!(
+                status_for(app.clone(), "POST", "/v1/message/alice").await,
+                StatusCode::NOT_FOUND,
+                "unprefixed path should 404 for base_path {:?}",
+                base_path
+            );
+
+            // Health stays at the root
+            assert_eq!(status_for(app, "GET", "/").await, StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_path_returns_json_404() {
+        let app = build_router("", AppState::default());
+
+        let (status, json) = json_for(app, "GET", "/v1/unknown").await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["error"]["code"], "NOT_FOUND");
+        assert_eq!(json["error"]["path"], "/v1/unknown");
+        assert!(json["error"]["message"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_path_with_base_path_reports_full_path() {
+        let app = build_router("api", AppState::default());
+
+        let (status, json) = json_for(app, "GET", "/api/v1/unknown").await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["error"]["code"], "NOT_FOUND");
+        assert_eq!(json["error"]["path"], "/api/v1/unknown");
+    }
+
+    #[tokio::test]
+    async fn test_wrong_method_returns_json_405() {
+        let app = build_router("", AppState::default());
+
+        let request = Request::builder()
+            .method("GET")
+            .uri("/v1/message/alice")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()["allow"], "POST");
+
+        let (status, json) = json_for(app, "GET", "/v1/message/alice").await;
+
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(json["error"]["code"], "METHOD_NOT_ALLOWED");
+        assert_eq!(json["error"]["path"], "/v1/message/alice");
+        assert!(json["error"]["message"].as_str().unwrap().contains("GET"));
+    }
+
+    #[tokio::test]
+    async fn test_options_lists_allowed_methods() {
+        let app = build_router("", AppState::default());
+
+        let request = Request::builder()
+            .method("OPTIONS")
+            .uri("/v1/message/alice")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers()["allow"], "POST,OPTIONS");
+
+        // Unknown paths still 404
+        assert_eq!(
+            status_for(app, "OPTIONS", "/v1/unknown").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_root_document() {
+        let app = build_router("", AppState::default());
+
+        let (status, json) = json_for(app, "GET", "/").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["name"], env!("CARGO_PKG_NAME"));
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["links"]["v1"], "/v1");
+        assert_eq!(json["links"]["message"], "/v1/message/{recipient_id}");
+    }
+
+    #[tokio::test]
+    async fn test_root_document_lists_adapter_capabilities() {
+        let app = build_router(
+            "",
+            AppState::with_llm(SlowLlm {
+                delay: Duration::ZERO,
+            }),
+        );
+
+        let (_, json) = json_for(app, "GET", "/").await;
+
+        assert_eq!(json["capabilities"]["llm"]["streaming"], true);
+        assert_eq!(json["capabilities"]["llm"]["images"], false);
+        assert!(json["capabilities"].get("storage").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_root_document_does_not_wait_for_busy_adapters() {
+        let state = AppState::with_llm(SlowLlm {
+            delay: Duration::ZERO,
+        });
+        let llm = state.llm.clone().unwrap();
+        let _generating = llm.write().await;
+
+        let (status, json) = json_for(build_router("", state), "GET", "/").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["capabilities"]["llm"], "busy");
+    }
+
+    #[tokio::test]
+    async fn test_root_document_at_base_path() {
+        let app = build_router("/api/", AppState::default());
+
+        for uri in ["/", "/api", "/api/"] {
+            let (status, json) = json_for(app.clone(), "GET", uri).await;
+
+            assert_eq!(status, StatusCode::OK, "{}", uri);
+            assert_eq!(json["links"]["v1"], "/api/v1");
+            assert_eq!(json["links"]["versions"], "/api/versions");
+        }
+    }
+
+    /// Stub version `name` whose /ping answers with its name
+    fn stub_version(name: &'static str, router: fn() -> Router<AppState>) -> ApiVersion {
+        ApiVersion {
+            capabilities: Capabilities {
+                version: name,
+                message_prefix: None,
+                links: &[("ping", "/ping")],
+            },
+            router,
+        }
+    }
+
+    /// Old "v1" stub, deprecated with a sunset, and a stable "v2" stub
+    fn two_versions() -> AppState {
+        let v1 = stub_version("v1", || {
+            Router::new().route("/ping", get(|| async { "v1" }))
+        });
+        let v2 = stub_version("v2", || {
+            Router::new().route("/ping", get(|| async { "v2" }))
+        });
+
+        AppState {
+            api_versions: ApiVersions::new(vec![
+                MountedVersion {
+                    deprecated: true,
+                    sunset: chrono::NaiveDate::from_ymd_opt(2027, 1, 1),
+                    ..MountedVersion::stable(v1)
+                },
+                MountedVersion::stable(v2),
+            ]),
+            ..AppState::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_versions_are_mounted_side_by_side() {
+        let app = build_router("api", two_versions());
+
+        for version in ["v1", "v2"] {
+            let request = Request::builder()
+                .uri(format!("/api/{}/ping", version))
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert_eq!(body, version);
+        }
+
+        // The root document links the newest version's endpoints
+        let (_, json) = json_for(app, "GET", "/").await;
+        assert_eq!(json["links"]["v1"], "/api/v1");
+        assert_eq!(json["links"]["v2"], "/api/v2");
+        assert_eq!(json["links"]["ping"], "/api/v2/ping");
+    }
+
+    #[tokio::test]
+    async fn test_versions_discovery_document() {
+        let app = build_router("api", two_versions());
+
+        let (status, json) = json_for(app, "GET", "/api/versions").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "versions": [
+                    {
+                        "version": "v1",
+                        "path": "/api/v1",
+                        "status": "deprecated",
+                        "sunset": "2027-01-01"
+                    },
+                    { "version": "v2", "path": "/api/v2", "status": "stable" }
+                ]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_version_gets_lifecycle_headers() {
+        let app = build_router("", two_versions());
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app.clone().oneshot(get("/v1/ping")).await.unwrap();
+        assert_eq!(response.headers()["deprecation"], "true");
+        assert_eq!(
+            response.headers()["sunset"],
+            "Fri, 01 Jan 2027 00:00:00 GMT"
+        );
+        assert_eq!(
+            response.headers()["link"],
+            "</versions>; rel=\"deprecation\""
+        );
+
+        let response = app.oneshot(get("/v2/ping")).await.unwrap();
+        assert!(response.headers().get("deprecation").is_none());
+        assert!(response.headers().get("sunset").is_none());
+    }
+
+    /// Message request with a body of `size` bytes
+    fn oversized_request(size: usize, content_length: bool) -> Request<Body> {
+        let content = "x".repeat(size);
+        let body = format!(
+            r#"{{"messages":[{{"role":"user","content":"{}"}}]}}"#,
+            content
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/message/alice")
+            .header("content-type", "application/json");
+        let request = if content_length {
+            request.header("content-length", body.len())
+        } else {
+            request
+        };
+
+        request.body(Body::from(body)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_returns_413() {
+        let state = AppState {
+            body_limit: BodyLimit(64),
+            ..AppState::with_llm(SlowLlm {
+                delay: Duration::from_secs(60),
+            })
+        };
+        let app = build_router("", state);
+
+        // The handler would wait for the slow LLM, so a quick 413 means it never ran
+        let response = tokio::time::timeout(
+            Duration::from_secs(5),
+            app.oneshot(oversized_request(128, true)),
+        )
+        .await
+        .expect("request should be rejected before the handler runs")
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["error"]["code"], "PAYLOAD_TOO_LARGE");
+        assert_eq!(json["error"]["path"], "/v1/message/alice");
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_without_content_length_returns_413() {
+        let state = AppState {
+            body_limit: BodyLimit(64),
+            ..AppState::default()
+        };
+        let app = build_router("", state);
+
+        let response = app.oneshot(oversized_request(128, false)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_body_within_limit_is_accepted() {
+        let state = AppState {
+            body_limit: BodyLimit(1024),
+            ..AppState::default()
+        };
+        let app = build_router("", state);
+
+        let response = app.oneshot(oversized_request(128, true)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_router_with_auth() {
+        let auth_config = crate::config::schema::AuthConfig {
+            exempt: crate::config::defaults::default_auth_exempt(),
+            keys: vec!["secret".to_string()],
+            keys_file: None,
+        };
+        let state = AppState {
+            auth: Some(std::sync::Arc::new(
+                auth::ApiKeys::from_config(&auth_config, None).unwrap(),
+            )),
+            ..AppState::default()
+        };
+        let app = build_router("", state);
+
+        assert_eq!(status_for(app.clone(), "GET", "/").await, StatusCode::OK);
+        assert_eq!(
+            status_for(app.clone(), "POST", "/v1/message/alice").await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/message/alice")
+            .header("authorization", "Bearer secret")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"messages":[]}"#))
+            .unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_times_out() {
+        let limit = Duration::from_millis(50);
+        let response = slow_app(limit, limit)
+            .oneshot(message_request(false))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["error"]["code"], "GATEWAY_TIMEOUT");
+        assert_eq!(json["error"]["path"], "/v1/message/alice");
+    }
+
+    #[tokio::test]
+    async fn test_message_route_has_own_timeout() {
+        let app = slow_app(Duration::from_millis(50), Duration::from_secs(5));
+
+        let response = app.oneshot(message_request(false)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_response_is_not_cut_off() {
+        let limit = Duration::from_millis(50);
+        let response = slow_app(limit, limit)
+            .oneshot(message_request(true))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The body takes longer than the limit but arrives complete
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(body.contains(r#""content":" reply""#));
+        assert!(body.contains(r#""type":"done""#));
+    }
+
+    #[tokio::test]
+    async fn test_access_log_passes_bodies_through() {
+        let state = AppState {
+            access_log: AccessLog::from_config(&AccessLogConfig {
+                bodies: true,
+                mode: AccessLogMode::Verbose,
+                ..AccessLogConfig::default()
+            }),
+            ..AppState::with_llm(SlowLlm {
+                delay: Duration::from_millis(1),
+            })
+        };
+        let app = build_router("", state);
+
+        let body = r#"{"messages":[{"role":"user","content":"Hi"}]}"#;
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/message/alice")
+            .header("content-type", "application/json")
+            .header("content-length", body.len())
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["message"]["content"], "slow reply");
     }
 }
@@ -0,0 +1,558 @@
+use super::AppState;
+use super::startup::ServerStartupConfig;
+use crate::adapter::services::is_built_in;
+use crate::config::Config;
+use crate::config::schema::ServiceAdapterConfig;
+use crate::config::secrets::resolve_secrets;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Replacement for redacted adapter settings
+const REDACTED: &str = "[REDACTED]";
+
+/// Endings of adapter setting names that hold secrets, like `api_key`
+///
+/// Names are compared lowercased and without separators, so `apiKey`,
+/// `api-key` and `apikey` match too. A bare `key` has to be a word of its
+/// own, which keeps names like `monkey` visible. Settings named in
+/// `[server.access_log] redact` are redacted as well.
+const SECRET_NAME_ENDINGS: &[&str] = &["apikey", "password", "secret", "token"];
+
+/// Parameters a server runs with, after the config and overrides are applied
+///
+/// Printed by `serve --print-config json` and `config show`, so both
+/// describe the server the same way. Secrets are never included.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedRuntimeInfo {
+    pub adapters: Vec<ResolvedAdapter>,
+    /// Whether requests need an API key (the keys themselves aren't shown)
+    pub auth_enabled: bool,
+    pub base_path: String,
+    pub cache_dir: PathBuf,
+    /// Directory of the config file (None when using built-in defaults)
+    pub config_dir: Option<PathBuf>,
+    pub data_dir: PathBuf,
+    pub ephemeral: bool,
+    pub host: String,
+    pub limits: ResolvedLimits,
+    pub port: u16,
+    /// Profile of the config file that was applied, if any
+    pub profile: Option<String>,
+    pub version: String,
+}
+
+/// A configured adapter
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedAdapter {
+    /// Provider-specific settings, with secrets redacted
+    pub config: serde_json::Value,
+    /// Whether the adapter loaded and is ready (None if loading wasn't tried)
+    pub loaded: Option<bool>,
+    pub max_concurrent: Option<usize>,
+    pub max_queued: Option<usize>,
+    pub max_response_bytes: usize,
+    /// WASM module to load (None for built-in providers)
+    pub module_path: Option<PathBuf>,
+    pub pool_size: Option<usize>,
+    pub provider: String,
+    pub service: String,
+    /// Configured version, e.g. "latest"; `module_path` shows what it resolved to
+    pub version: String,
+}
+
+/// Size and time limits on requests
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedLimits {
+    pub max_audio_bytes: usize,
+    pub max_body_bytes: usize,
+    pub max_image_bytes: usize,
+    pub max_search_scanned: usize,
+    pub max_tokens_per_conversation: Option<u64>,
+    pub message_timeout_secs: u64,
+    pub request_timeout_secs: u64,
+}
+
+impl ResolvedRuntimeInfo {
+    /// Parameters of a server started from `config` without overrides
+    pub fn from_config(config: &Config, config_dir: Option<&Path>) -> Self {
+        let data_dir = crate::config::data_dir(config, config_dir);
+        let redact = &config.server.access_log.redact;
+
+        let mut adapters: Vec<_> = config
+            .adapters
+            .services
+            .iter()
+            .map(|(service, adapter)| resolved_adapter(service, adapter, &data_dir, redact))
+            .collect();
+        adapters.sort_by(|a, b| a.service.cmp(&b.service));
+
+        ResolvedRuntimeInfo {
+            adapters,
+            auth_enabled: config.server.auth.is_some(),
+            base_path: config.server.base_path.clone(),
+            cache_dir: crate::config::cache_dir(config, config_dir),
+            config_dir: config_dir.map(Path::to_path_buf),
+            data_dir,
+            ephemeral: false,
+            host: config.server.host.clone(),
+            limits: ResolvedLimits {
+                max_audio_bytes: config.limits.max_audio_bytes,
+                max_body_bytes: config.server.max_body_bytes,
+                max_image_bytes: config.limits.max_image_bytes,
+                max_search_scanned: config.limits.max_search_scanned,
+                max_tokens_per_conversation: config.limits.max_tokens_per_conversation,
+                message_timeout_secs: config.server.message_timeout_secs,
+                request_timeout_secs: config.server.request_timeout_secs,
+            },
+            port: config.server.port,
+            profile: config.profile.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Parameters `startup_config` resolves to, including command line overrides
+    pub fn from_startup_config(startup_config: &ServerStartupConfig) -> Self {
+        ResolvedRuntimeInfo {
+            ephemeral: startup_config.ephemeral,
+            host: startup_config.host.clone(),
+            port: startup_config.port,
+            ..ResolvedRuntimeInfo::from_config(
+                &startup_config.config,
+                startup_config.config_dir.as_deref(),
+            )
+        }
+    }
+
+    /// Record which of the adapters `state` loaded
+    pub async fn with_loaded_adapters(mut self, state: &AppState) -> Self {
+        let crypto_ready = match &state.crypto {
+            Some(crypto) => crypto.read().await.is_ready(),
+            None => false,
+        };
+        let image_ready = match &state.image {
+            Some(image) => image.read().await.is_ready(),
+            None => false,
+        };
+        let llm_ready = match &state.llm {
+            Some(llm) => llm.read().await.is_ready(),
+            None => false,
+        };
+        let storage_ready = match &state.storage {
+            Some(storage) => storage.read().await.is_ready(),
+            None => false,
+        };
+        let stt_ready = match &state.stt {
+            Some(stt) => stt.read().await.is_ready(),
+            None => false,
+        };
+        let tts_ready = match &state.tts {
+            Some(tts) => tts.read().await.is_ready(),
+            None => false,
+        };
+
+        for adapter in &mut self.adapters {
+            adapter.loaded = match adapter.service.as_str() {
+                "crypto" => Some(crypto_ready),
+                "image" => Some(image_ready),
+                "llm" => Some(llm_ready),
+                "storage" => Some(storage_ready),
+                "stt" => Some(stt_ready),
+                "tts" => Some(tts_ready),
+                // Other services aren't served yet
+                _ => Some(false),
+            };
+        }
+        self
+    }
+
+    /// Configured adapters that aren't loaded and ready
+    pub fn failed_adapters(&self) -> Vec<&ResolvedAdapter> {
+        self.adapters
+            .iter()
+            .filter(|adapter| adapter.loaded == Some(false))
+            .collect()
+    }
+}
+
+fn resolved_adapter(
+    service: &str,
+    adapter: &ServiceAdapterConfig,
+    data_dir: &Path,
+    redact: &[String],
+) -> ResolvedAdapter {
+    let built_in = is_built_in(service, &adapter.provider);
+    let mut config = adapter
+        .config_as_json()
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    redact_secrets(&mut config, redact);
+
+    ResolvedAdapter {
+        config,
+        loaded: None,
+        max_concurrent: adapter.max_concurrent,
+        max_queued: adapter.max_queued,
+        max_response_bytes: adapter.http.max_response_bytes,
+        module_path: (!built_in).then(|| adapter.module_path(data_dir, service)),
+        pool_size: adapter.pool_size,
+        provider: adapter.provider.clone(),
+        service: service.to_string(),
+        version: adapter.version.clone(),
+    }
+}
+
+/// `config` as TOML, for `serve --print-config` and `config show --format toml`
+///
+/// Adapter settings that look like secrets, the API keys of `[server.auth]`
+/// and the secret of `[server.webhooks]` are redacted like in `ResolvedRuntimeInfo`. With
+/// `show_secrets`, they're shown instead, with `${ENV:..}` and `${FILE:..}`
+/// placeholders resolved to what the adapters would get. The profile
+/// applied, if any, is named in a comment.
+pub fn config_toml(config: &Config, show_secrets: bool) -> anyhow::Result<String> {
+    let mut config = config.clone();
+    let redact = config.server.access_log.redact.clone();
+
+    for (service, adapter) in &mut config.adapters.services {
+        let fallback = adapter
+            .fallback
+            .iter_mut()
+            .map(|fallback| &mut fallback.config);
+        for settings in std::iter::once(&mut adapter.config).chain(fallback) {
+            if show_secrets {
+                *settings = resolve_secrets(settings, service)?;
+            } else {
+                redact_toml_secrets(settings, &redact);
+            }
+        }
+    }
+    if !show_secrets && let Some(auth) = &mut config.server.auth {
+        for key in &mut auth.keys {
+            *key = REDACTED.to_string();
+        }
+    }
+    if !show_secrets
+        && let Some(secret) = config
+            .server
+            .webhooks
+            .as_mut()
+            .and_then(|webhooks| webhooks.secret.as_mut())
+    {
+        *secret = REDACTED.to_string();
+    }
+
+    let toml = toml::to_string_pretty(&config)?;
+    Ok(match &config.profile {
+        Some(profile) => format!("# Profile: {}\n{}", profile, toml),
+        None => toml,
+    })
+}
+
+/// `redact_secrets` for adapter settings still in TOML
+fn redact_toml_secrets(value: &mut toml::Value, redact: &[String]) {
+    match value {
+        toml::Value::Table(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if is_secret(name, redact) {
+                    *field = toml::Value::String(REDACTED.to_string());
+                } else {
+                    redact_toml_secrets(field, redact);
+                }
+            }
+        }
+        toml::Value::Array(items) => {
+            for item in items {
+                redact_toml_secrets(item, redact);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replace the values of settings that look like secrets, at any depth
+fn redact_secrets(value: &mut serde_json::Value, redact: &[String]) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if is_secret(name, redact) {
+                    *field = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(field, redact);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact_secrets(item, redact);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_secret(name: &str, redact: &[String]) -> bool {
+    let normalized = normalize_name(name);
+    redact
+        .iter()
+        .any(|redacted| normalize_name(redacted) == normalized)
+        || last_word(name).eq_ignore_ascii_case("key")
+        || SECRET_NAME_ENDINGS
+            .iter()
+            .any(|ending| normalized.ends_with(ending))
+}
+
+/// `name` lowercased, without separators like `_` and `-`
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Last word of `name`, split at separators and camelCase humps
+fn last_word(name: &str) -> &str {
+    let word = name
+        .rsplit(|c: char| !c.is_ascii_alphanumeric())
+        .next()
+        .unwrap_or(name);
+    // Only ASCII is left, so every byte is a char
+    let bytes = word.as_bytes();
+    let start = (1..bytes.len())
+        .rev()
+        .find(|&i| bytes[i].is_ascii_uppercase() && bytes[i - 1].is_ascii_lowercase())
+        .unwrap_or(0);
+    &word[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::FnLlm;
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.server.port = 9000;
+        config.adapters.services.insert(
+            "llm".to_string(),
+            ServiceAdapterConfig::new("openai")
+                .with_setting("api_key", "sk-secret")
+                .with_setting("base_url", "https://api.openai.com")
+                .with_setting("max_tokens", 512),
+        );
+        config
+            .adapters
+            .services
+            .insert("storage".to_string(), ServiceAdapterConfig::new("sqlite"));
+        config
+    }
+
+    #[test]
+    fn test_json_keys() {
+        let info = ResolvedRuntimeInfo::from_config(&config(), None);
+        let json = serde_json::to_value(&info).unwrap();
+
+        let keys: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        assert_eq!(
+            keys,
+            [
+                "adapters",
+                "auth_enabled",
+                "base_path",
+                "cache_dir",
+                "config_dir",
+                "data_dir",
+                "ephemeral",
+                "host",
+                "limits",
+                "port",
+                "profile",
+                "version"
+            ]
+        );
+        assert_eq!(json["port"], 9000);
+        assert_eq!(json["limits"]["max_body_bytes"], 4 * 1024 * 1024);
+
+        let llm = &json["adapters"][0];
+        assert_eq!(llm["service"], "llm");
+        assert_eq!(llm["provider"], "openai");
+        assert!(
+            llm["module_path"]
+                .as_str()
+                .unwrap()
+                .ends_with("adapter.wasm")
+        );
+        assert_eq!(llm["loaded"], serde_json::Value::Null);
+
+        // Built-in providers have no module
+        let storage = &json["adapters"][1];
+        assert_eq!(storage["service"], "storage");
+        assert_eq!(storage["module_path"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let mut config = config();
+        config.server.auth = Some(crate::config::schema::AuthConfig {
+            exempt: Vec::new(),
+            keys: vec!["server-key".to_string()],
+            keys_file: None,
+        });
+        let llm = config.adapters.services.get_mut("llm").unwrap();
+        *llm = llm
+            .clone()
+            .with_setting("custom_header", "hidden")
+            .with_setting(
+                "nested",
+                toml::Value::Table(toml::Table::from_iter([(
+                    "client_secret".to_string(),
+                    toml::Value::String("also-hidden".to_string()),
+                )])),
+            );
+        config
+            .server
+            .access_log
+            .redact
+            .push("custom_header".to_string());
+
+        let info = ResolvedRuntimeInfo::from_config(&config, None);
+        let json = serde_json::to_string(&info).unwrap();
+
+        for secret in ["sk-secret", "server-key", "hidden"] {
+            assert!(!json.contains(secret), "{} leaked", secret);
+        }
+        let llm_config = &info.adapters[0].config;
+        assert_eq!(llm_config["api_key"], REDACTED);
+        assert_eq!(llm_config["nested"]["client_secret"], REDACTED);
+        // Names merely containing a secret word stay visible
+        assert_eq!(llm_config["max_tokens"], 512);
+        assert_eq!(llm_config["base_url"], "https://api.openai.com");
+        assert!(info.auth_enabled);
+    }
+
+    #[test]
+    fn test_secret_names_ignore_case_and_separators() {
+        let redact = ["Custom-Header".to_string()];
+        for name in [
+            "api_key",
+            "api-key",
+            "apikey",
+            "apiKey",
+            "API_KEY",
+            "ApiKey",
+            "key",
+            "orgKey",
+            "access-token",
+            "clientSecret",
+            "custom_header",
+        ] {
+            assert!(is_secret(name, &redact), "{} not redacted", name);
+        }
+        for name in [
+            "monkey",
+            "keyboard",
+            "max_tokens",
+            "base_url",
+            "turkey_count",
+        ] {
+            assert!(!is_secret(name, &redact), "{} redacted", name);
+        }
+    }
+
+    #[test]
+    fn test_config_toml_redacts_secrets() {
+        let mut config = config();
+        config.server.auth = Some(crate::config::schema::AuthConfig {
+            exempt: Vec::new(),
+            keys: vec!["server-key".to_string()],
+            keys_file: None,
+        });
+        config.server.webhooks = Some(crate::config::schema::WebhooksConfig {
+            secret: Some("hook-secret".to_string()),
+            url: "https://example.com/hook".to_string(),
+        });
+        let llm = config.adapters.services.get_mut("llm").unwrap();
+        llm.fallback
+            .push(ServiceAdapterConfig::new("ollama").with_setting("token", "fallback-token"));
+
+        let toml = config_toml(&config, false).unwrap();
+        for secret in ["sk-secret", "server-key", "fallback-token", "hook-secret"] {
+            assert!(!toml.contains(secret), "{} leaked", secret);
+        }
+
+        assert!(!toml.starts_with("# Profile"));
+        config.profile = Some("prod".to_string());
+        let toml = config_toml(&config, false).unwrap();
+        assert!(toml.starts_with("# Profile: prod\n"));
+
+        // What's printed is the config itself, so it parses back
+        let printed: Config = toml::from_str(&toml).unwrap();
+        assert_eq!(printed.server.port, 9000);
+        let llm = printed.adapters.get_service("llm").unwrap();
+        assert_eq!(llm.config["api_key"].as_str(), Some(REDACTED));
+        assert_eq!(
+            llm.config["base_url"].as_str(),
+            Some("https://api.openai.com")
+        );
+        assert_eq!(llm.fallback[0].config["token"].as_str(), Some(REDACTED));
+        assert_eq!(printed.server.auth.unwrap().keys, [REDACTED]);
+        let webhooks = printed.server.webhooks.unwrap();
+        assert_eq!(webhooks.secret.as_deref(), Some(REDACTED));
+        assert_eq!(webhooks.url, "https://example.com/hook");
+    }
+
+    #[test]
+    fn test_config_toml_shows_secrets() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let key_file = temp_dir.path().join("org_key");
+        std::fs::write(&key_file, "org-resolved").unwrap();
+        let mut config = config();
+        let llm = config.adapters.services.get_mut("llm").unwrap();
+        *llm = llm
+            .clone()
+            .with_setting("org_key", format!("${{FILE:{}}}", key_file.display()));
+
+        let toml = config_toml(&config, true).unwrap();
+        assert!(toml.contains("sk-secret"));
+        assert!(toml.contains("org-resolved"));
+
+        std::fs::remove_file(&key_file).unwrap();
+        // Secrets that can't be resolved are an error, not silently left out
+        assert!(config_toml(&config, true).is_err());
+        assert!(config_toml(&config, false).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_with_loaded_adapters() {
+        let state = AppState::with_llm(FnLlm::new("echo", |_| String::new()));
+        let startup_config = ServerStartupConfig {
+            check: false,
+            config: config(),
+            config_dir: None,
+            create_dirs: false,
+            ephemeral: true,
+            host: "0.0.0.0".to_string(),
+            log_level: "info".to_string(),
+            port: 3000,
+            print_config: true,
+            quiet: false,
+            watch_file: None,
+        };
+
+        let info = ResolvedRuntimeInfo::from_startup_config(&startup_config)
+            .with_loaded_adapters(&state)
+            .await;
+
+        // Command line overrides win over the config
+        assert!(info.ephemeral);
+        assert_eq!(info.host, "0.0.0.0");
+        assert_eq!(info.port, 3000);
+        assert_eq!(info.adapters[0].loaded, Some(true));
+        assert_eq!(info.adapters[1].loaded, Some(false));
+        let failed: Vec<_> = info
+            .failed_adapters()
+            .iter()
+            .map(|adapter| adapter.service.as_str())
+            .collect();
+        assert_eq!(failed, ["storage"]);
+    }
+}
@@ -1,31 +1,179 @@
-use super::router;
+use super::runtime_info::ResolvedRuntimeInfo;
+use super::{auth::ApiKeys, moderation::Moderation, reload, router, state::AppState};
+use crate::adapter::AdapterRegistry;
+use crate::adapter::http;
+use crate::adapter::services::memory::MEMORY_PROVIDER;
 use crate::config::Config;
+use crate::config::schema::ServiceAdapterConfig;
+use crate::routes::versions::ApiVersions;
 use anyhow::Result;
-use std::path::PathBuf;
+use axum::Router;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::watch;
 
 /// Server startup configuration
 #[derive(Debug)]
 pub struct ServerStartupConfig {
+    /// Load the config and adapters, then exit instead of serving
+    pub check: bool,
     pub config: Config,
     pub config_dir: Option<PathBuf>,
+    /// Create missing data and cache directories before loading adapters
+    pub create_dirs: bool,
+    /// Keep everything in memory, see `make_ephemeral`
+    pub ephemeral: bool,
     pub host: String,
     pub log_level: String,
     pub port: u16,
+    /// Print `ResolvedRuntimeInfo` as JSON before binding
+    pub print_config: bool,
+    /// Don't show the startup banner
+    pub quiet: bool,
+    /// Config file to reload on change (None disables watching)
+    pub watch_file: Option<PathBuf>,
 }
 
 /// Start the server with the given configuration
-pub async fn start(startup_config: ServerStartupConfig) -> Result<()> {
-    let base_path = &startup_config.config.server.base_path;
+pub async fn start(mut startup_config: ServerStartupConfig) -> Result<()> {
+    if startup_config.ephemeral {
+        make_ephemeral(&mut startup_config.config);
+        startup_config.create_dirs = false;
+        tracing::warn!(
+            "Running in ephemeral mode: conversations, sender profiles and usage \
+             are kept in memory only and lost when the server stops"
+        );
+    }
+    if startup_config.check {
+        // Checking must not change anything on disk
+        startup_config.create_dirs = false;
+    }
+
+    let config = &startup_config.config;
+    let config_dir = startup_config.config_dir.as_deref();
+    let state = load_state(config, config_dir, startup_config.create_dirs).await?;
+    if report_runtime_info(&startup_config, &state).await? {
+        return Ok(());
+    }
+    let app = app_with_state(config, config_dir, state.clone())?;
+
+    // Serve a replaceable router when watching, keeping the watcher alive
+    let (app, watcher) = match &startup_config.watch_file {
+        Some(path) => {
+            let (routers, receiver) = watch::channel(app);
+            let served = reload::ServedConfig {
+                config: config.clone(),
+                data_dir: crate::config::data_dir(config, config_dir),
+                ephemeral: startup_config.ephemeral,
+                state,
+            };
+            let watcher =
+                reload::watch_config(path.clone(), served, startup_config.create_dirs, routers)?;
+            tracing::info!("Watching {} for changes", path.display());
+            (reload::reloadable(receiver), Some(watcher))
+        }
+        None => (app, None),
+    };
+
+    let result = serve(&startup_config, app).await;
+
+    if let Some(watcher) = watcher {
+        watcher.stop().await;
+    }
+    result
+}
+
+/// Start the server with adapters from `registry` instead of the config
+///
+/// Lets embedders serve native adapters registered with
+/// `AdapterRegistry::register_llm_adapter` and friends. The rest of the
+/// config applies as usual; the adapters are shut down when the server
+/// stops.
+#[allow(dead_code)] // Used when embedding with a custom adapter registry
+pub async fn start_with_registry(
+    mut startup_config: ServerStartupConfig,
+    mut registry: AdapterRegistry,
+) -> Result<()> {
+    if startup_config.ephemeral {
+        // The registered storage adapter stays; only config-driven writes stop
+        make_ephemeral(&mut startup_config.config);
+    }
+    if let Some(path) = &startup_config.watch_file {
+        // A reload would replace the registered adapters with configured ones
+        tracing::warn!(
+            "Not watching {}: config reloading needs adapters loaded from the config",
+            path.display()
+        );
+    }
+
+    // The adapters are shut down however serving ends
+    let result: Result<()> = async {
+        registry.check_default_providers(&startup_config.config)?;
+        let state = AppState::from_registry(&startup_config.config, &registry);
+        if report_runtime_info(&startup_config, &state).await? {
+            return Ok(());
+        }
+        let app = app_with_state(
+            &startup_config.config,
+            startup_config.config_dir.as_deref(),
+            state,
+        )?;
+        serve(&startup_config, app).await
+    }
+    .await;
+
+    registry.shutdown().await?;
+    result
+}
+
+/// Print the resolved runtime parameters with --print-config, check them with --check
+///
+/// Returns whether to exit instead of serving. A check fails if a
+/// configured adapter didn't load.
+async fn report_runtime_info(
+    startup_config: &ServerStartupConfig,
+    state: &AppState,
+) -> Result<bool> {
+    if !startup_config.print_config && !startup_config.check {
+        return Ok(false);
+    }
+
+    let info = ResolvedRuntimeInfo::from_startup_config(startup_config)
+        .with_loaded_adapters(state)
+        .await;
+    if startup_config.print_config {
+        // One line, so it's easy to pick out of the log output
+        println!("{}", serde_json::to_string(&info)?);
+    }
+    if !startup_config.check {
+        return Ok(false);
+    }
 
-    // Build the router
-    let app = router::build_router(base_path);
+    let failed: Vec<_> = info
+        .failed_adapters()
+        .iter()
+        .map(|adapter| format!("{} ({})", adapter.service, adapter.provider))
+        .collect();
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "Config check failed, adapters not loaded: {}",
+            failed.join(", ")
+        );
+    }
+    tracing::info!("Config check passed");
+    Ok(true)
+}
+
+/// Bind to the configured address and serve `app`
+async fn serve(startup_config: &ServerStartupConfig, app: Router) -> Result<()> {
+    let base_path = router::normalize_base_path(&startup_config.config.server.base_path);
 
     // Create listener
     let addr = format!("{}:{}", startup_config.host, startup_config.port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
 
-    // Show startup messages based on log level
-    show_startup_messages(&startup_config, &addr, base_path);
+    show_startup_messages(startup_config, &addr, base_path);
 
     // Start the server
     axum::serve(listener, app).await?;
@@ -33,55 +181,342 @@ pub async fn start(startup_config: ServerStartupConfig) -> Result<()> {
     Ok(())
 }
 
-/// Display startup messages based on log level
-fn show_startup_messages(startup_config: &ServerStartupConfig, addr: &str, base_path: &str) {
-    match startup_config.log_level.as_str() {
-        "silent" => {
-            // Only show critical startup info
-        }
-        "error" | "warn" | "info" => {
-            print_basic_startup_info(addr, base_path);
-        }
-        "debug" => {
-            print_debug_startup_info(startup_config, addr, base_path);
-            print_basic_startup_info(addr, base_path);
+/// Load adapters for `config` and build the application router
+#[allow(dead_code)] // Library API for embedders; `start` keeps the state for reloads
+pub async fn build_app(
+    config: &Config,
+    config_dir: Option<&Path>,
+    create_dirs: bool,
+) -> Result<Router> {
+    let state = load_state(config, config_dir, create_dirs).await?;
+    app_with_state(config, config_dir, state)
+}
+
+/// Change `config` so that the server writes nothing to disk
+///
+/// Storage switches to the built-in memory provider and the usage log is
+/// turned off. Creating data directories is up to the caller; log files
+/// are still written if configured, since the operator asked for them.
+pub(crate) fn make_ephemeral(config: &mut Config) {
+    config.adapters.services.insert(
+        "storage".to_string(),
+        ServiceAdapterConfig::new(MEMORY_PROVIDER),
+    );
+    config.storage.create_dirs = Some(false);
+    config.usage_log.enabled = false;
+}
+
+/// Create the data directories if asked to and load adapters for `config`
+async fn load_state(
+    config: &Config,
+    config_dir: Option<&Path>,
+    create_dirs: bool,
+) -> Result<AppState> {
+    let data_dir = crate::config::data_dir(config, config_dir);
+    if create_dirs {
+        create_storage_dirs(config, config_dir)?;
+    }
+    http::configure_shared_client(&config.http)?;
+
+    Ok(AppState::from_config(config, &data_dir).await?)
+}
+
+/// Create the data directory, its `adapters/` subtree and the cache directory
+///
+/// Missing parents are created too, so adapters can rely on the
+/// directories existing on a fresh machine.
+pub(super) fn create_storage_dirs(config: &Config, config_dir: Option<&Path>) -> Result<()> {
+    crate::config::creation::create_data_dirs(crate::config::data_dir(config, config_dir))?;
+    crate::config::creation::create_cache_dir(crate::config::cache_dir(config, config_dir))?;
+    Ok(())
+}
+
+/// Build the application router with adapters from `registry`
+#[allow(dead_code)] // Used when embedding with a custom adapter registry
+pub fn build_app_with_registry(
+    config: &Config,
+    config_dir: Option<&Path>,
+    registry: &AdapterRegistry,
+) -> Result<Router> {
+    app_with_state(
+        config,
+        config_dir,
+        AppState::from_registry(config, registry),
+    )
+}
+
+/// Enable authentication and moderation on `state`, set its cache directory
+/// and API versions and build the router
+pub(super) fn app_with_state(
+    config: &Config,
+    config_dir: Option<&Path>,
+    mut state: AppState,
+) -> Result<Router> {
+    let base_path = router::normalize_base_path(&config.server.base_path);
+    state.cache_dir = Some(crate::config::cache_dir(config, config_dir));
+    state.api_versions = ApiVersions::from_config(&config.server.api_versions)?;
+
+    // Refuse to start with a broken auth setup rather than serve an open API
+    if let Some(auth_config) = &config.server.auth {
+        let keys = ApiKeys::from_config(auth_config, config_dir)?;
+        tracing::info!("API key authentication enabled");
+        state.auth = Some(Arc::new(keys));
+    }
+    // Same for a content policy that doesn't compile
+    state.moderation = Moderation::from_config(&config.moderation)?;
+    if state.moderation.is_some() {
+        tracing::info!("Moderation enabled");
+    }
+    if state.expose_fuel {
+        tracing::warn!(
+            "Adapter fuel is exposed in {} headers; turn off [server.debug] expose_fuel in production",
+            crate::routes::v1::message::FUEL_HEADER
+        );
+    }
+
+    Ok(router::build_router(base_path, state))
+}
+
+/// How the startup information is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StartupOutput {
+    /// Human-friendly banner on stdout, for interactive terminals
+    Banner,
+    /// Structured `tracing` event, for captured logs
+    Log,
+    /// Nothing, with --quiet or the silent log level
+    None,
+}
+
+impl StartupOutput {
+    fn choose(quiet: bool, log_level: &str, is_terminal: bool) -> Self {
+        if quiet || log_level == "silent" {
+            StartupOutput::None
+        } else if is_terminal {
+            StartupOutput::Banner
+        } else {
+            StartupOutput::Log
         }
-        _ => {
-            // Fallback to info level
-            println!("Server running on http://{}", addr);
+    }
+}
+
+/// Show the server URL, endpoints and config directory
+///
+/// Printed as a banner when stdout is a terminal, and logged at info level
+/// otherwise, so piped output only contains log lines.
+fn show_startup_messages(startup_config: &ServerStartupConfig, addr: &str, base_path: &str) {
+    let output = StartupOutput::choose(
+        startup_config.quiet,
+        &startup_config.log_level,
+        std::io::stdout().is_terminal(),
+    );
+
+    match output {
+        StartupOutput::Banner => {
+            for line in startup_banner(startup_config, addr, base_path) {
+                println!("{}", line);
+            }
         }
+        StartupOutput::Log => tracing::info!(
+            base_path = %if base_path.is_empty() { "/" } else { base_path },
+            config_dir = %config_dir_display(startup_config),
+            endpoints = %endpoints_url(addr, base_path),
+            "Server running on http://{}",
+            addr
+        ),
+        StartupOutput::None => {}
     }
 }
 
-/// Print basic server startup information
-fn print_basic_startup_info(addr: &str, base_path: &str) {
-    println!("Server running on http://{}", addr);
-    print_api_endpoints(addr, base_path);
+/// Lines of the startup banner; debug level adds the base path and config directory
+fn startup_banner(
+    startup_config: &ServerStartupConfig,
+    addr: &str,
+    base_path: &str,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    if startup_config.log_level == "debug" {
+        lines.push(format!(
+            "Starting server on {} (base_path: '{}')",
+            addr,
+            if base_path.is_empty() { "/" } else { base_path }
+        ));
+        lines.push(format!(
+            "Config directory: {}",
+            config_dir_display(startup_config)
+        ));
+    }
+    lines.push(format!("Server running on http://{}", addr));
+    lines.push(format!(
+        "API endpoints available at: {}",
+        endpoints_url(addr, base_path)
+    ));
+    lines
 }
 
-/// Print debug-specific startup information
-fn print_debug_startup_info(startup_config: &ServerStartupConfig, addr: &str, base_path: &str) {
-    println!(
-        "Starting server on {} (base_path: '{}')",
-        addr,
-        if base_path.is_empty() { "/" } else { base_path }
-    );
+fn config_dir_display(startup_config: &ServerStartupConfig) -> String {
+    match &startup_config.config_dir {
+        Some(config_dir) => config_dir.display().to_string(),
+        None => "<none> (using defaults)".to_string(),
+    }
+}
 
-    if let Some(config_dir) = &startup_config.config_dir {
-        println!("Config directory: {}", config_dir.display());
+/// URL pattern of the API endpoints
+fn endpoints_url(addr: &str, base_path: &str) -> String {
+    if base_path.is_empty() {
+        format!("http://{}/v1/*", addr)
     } else {
-        println!("Config directory: <none> (using defaults)");
+        format!("http://{}/{}/v1/*", addr, base_path)
     }
 }
 
-/// Print API endpoint information
-fn print_api_endpoints(addr: &str, base_path: &str) {
-    if !base_path.is_empty() {
-        println!(
-            "API endpoints available at: http://{}/{}/v1/*",
-            addr, base_path
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::test_support::{FnLlm, MemoryStorage};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_build_app_with_registry_serves_registered_adapter() {
+        let mut registry = AdapterRegistry::new().await.unwrap();
+        registry.register_llm_adapter(
+            "echo",
+            FnLlm::new("echo", |messages| {
+                format!("echo: {}", messages.last().unwrap().content)
+            }),
         );
-    } else {
-        println!("API endpoints available at: http://{}/v1/*", addr);
+        registry.register_storage_adapter("memory", MemoryStorage::default());
+
+        let app = build_app_with_registry(&Config::default(), None, &registry).unwrap();
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/message/alice")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+            ))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["message"]["content"], "echo: Hi");
+        assert_eq!(body["model"], "echo");
+    }
+
+    fn startup_config(log_level: &str, config_dir: Option<&str>) -> ServerStartupConfig {
+        ServerStartupConfig {
+            check: false,
+            config: Config::default(),
+            config_dir: config_dir.map(PathBuf::from),
+            create_dirs: false,
+            ephemeral: false,
+            host: "127.0.0.1".to_string(),
+            log_level: log_level.to_string(),
+            port: 8080,
+            print_config: false,
+            quiet: false,
+            watch_file: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_runtime_info() {
+        let state = AppState::with_llm(FnLlm::new("echo", |_| String::new()));
+
+        // Without --print-config or --check, startup goes on as before
+        let serving = startup_config("info", None);
+        assert!(!report_runtime_info(&serving, &state).await.unwrap());
+
+        let check = ServerStartupConfig {
+            check: true,
+            ..startup_config("info", None)
+        };
+        assert!(report_runtime_info(&check, &state).await.unwrap());
+        // The default config has an LLM adapter, which didn't load here
+        assert!(
+            report_runtime_info(&check, &AppState::default())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_state_creates_storage_dirs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.data_dir = Some(temp_dir.path().join("fresh").join("data"));
+        config.storage.cache_dir = Some(temp_dir.path().join("fresh").join("cache"));
+
+        load_state(&config, None, false).await.unwrap();
+        assert!(!temp_dir.path().join("fresh").exists());
+
+        load_state(&config, None, true).await.unwrap();
+        assert!(temp_dir.path().join("fresh/data/adapters").is_dir());
+        assert!(temp_dir.path().join("fresh/cache").is_dir());
+    }
+
+    #[test]
+    fn test_make_ephemeral() {
+        let mut config = Config::default();
+        config.usage_log.enabled = true;
+
+        make_ephemeral(&mut config);
+
+        assert_eq!(
+            config.adapters.services["storage"].provider,
+            MEMORY_PROVIDER
+        );
+        assert_eq!(config.storage.create_dirs, Some(false));
+        assert!(!config.usage_log.enabled);
+    }
+
+    #[test]
+    fn test_startup_output_choice() {
+        assert_eq!(
+            StartupOutput::choose(false, "info", true),
+            StartupOutput::Banner
+        );
+        assert_eq!(
+            StartupOutput::choose(false, "info", false),
+            StartupOutput::Log
+        );
+        assert_eq!(
+            StartupOutput::choose(true, "debug", true),
+            StartupOutput::None
+        );
+        assert_eq!(
+            StartupOutput::choose(false, "silent", false),
+            StartupOutput::None
+        );
+    }
+
+    #[test]
+    fn test_startup_banner() {
+        let banner = startup_banner(&startup_config("info", None), "127.0.0.1:8080", "api");
+        assert_eq!(
+            banner,
+            [
+                "Server running on http://127.0.0.1:8080",
+                "API endpoints available at: http://127.0.0.1:8080/api/v1/*"
+            ]
+        );
+
+        let banner = startup_banner(
+            &startup_config("debug", Some("/etc/ai_messenger")),
+            "127.0.0.1:8080",
+            "",
+        );
+        assert_eq!(banner.len(), 4);
+        assert_eq!(
+            banner[0],
+            "Starting server on 127.0.0.1:8080 (base_path: '/')"
+        );
+        assert_eq!(banner[1], "Config directory: /etc/ai_messenger");
     }
 }
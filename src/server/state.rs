@@ -0,0 +1,1223 @@
+use super::access_log::AccessLog;
+use super::auth::ApiKeys;
+use super::body_limit::BodyLimit;
+use super::idempotency::IdempotencyCache;
+use super::jobs::summary::SUMMARY_JOB;
+use super::jobs::title::TITLE_JOB;
+use super::jobs::webhook::WEBHOOK_JOB;
+use super::jobs::{self, JobQueue};
+use super::moderation::Moderation;
+use super::timeout::RequestTimeouts;
+use super::usage_log::UsageLog;
+use crate::adapter::balancer::EndpointBalancer;
+use crate::adapter::breaker::CircuitBreaker;
+use crate::adapter::encryption::{EncryptedValues, storage_crypto};
+use crate::adapter::http;
+use crate::adapter::keys::EncodedKeys;
+use crate::adapter::limiter::ConcurrencyLimiter;
+use crate::adapter::runtime::WasmRuntime;
+use crate::adapter::services::aes::{AesGcmCrypto, check_crypto_provider};
+use crate::adapter::services::fallback::FallbackLlm;
+use crate::adapter::services::image::ImageAdapterWrapper;
+use crate::adapter::services::llm::LlmAdapterWrapper;
+use crate::adapter::services::memory::{MEMORY_PROVIDER, MemoryStorage};
+use crate::adapter::services::mock::{MOCK_PROVIDER, MockLlm};
+use crate::adapter::services::silence::{SILENCE_PROVIDER, SilenceTts};
+use crate::adapter::services::sqlite::{SQLITE_PROVIDER, SqliteStorage};
+use crate::adapter::services::storage::StorageAdapterWrapper;
+use crate::adapter::services::stt::SttAdapterWrapper;
+use crate::adapter::services::tts::TtsAdapterWrapper;
+use crate::adapter::services::{AdapterLoadFailure, AdapterLoadOptions, AdapterRegistry};
+use crate::adapter::traits::{
+    AdapterService, GenerationOptions, ImageAdapter, LlmAdapter, ServiceError, StorageAdapter,
+    SttAdapter, TtsAdapter,
+};
+use crate::config::Config;
+use crate::config::schema::{ConversationMemoryConfig, MemoryStrategy};
+use crate::routes::versions::ApiVersions;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub use crate::adapter::services::{
+    SharedCrypto, SharedImage, SharedLlm, SharedStorage, SharedStt, SharedTts,
+};
+
+/// Shared application state available to all route handlers
+#[derive(Clone, Default)]
+pub struct AppState {
+    /// Request/response logging settings
+    pub access_log: AccessLog,
+    /// API versions mounted by the router
+    pub api_versions: ApiVersions,
+    /// Accepted API keys (None if authentication is disabled)
+    pub auth: Option<Arc<ApiKeys>>,
+    /// Largest request body accepted
+    pub body_limit: BodyLimit,
+    /// Cache directory cleaned by `DELETE /v1/admin/cache` (None if not served from a config)
+    pub cache_dir: Option<PathBuf>,
+    /// Crypto adapter encrypting stored values (None if no crypto adapter is configured)
+    pub crypto: Option<SharedCrypto>,
+    /// Report the adapter fuel of replies in a header (`[server.debug] expose_fuel`)
+    pub expose_fuel: bool,
+    /// Configured adapters that failed to load, by service
+    pub failed_adapters: Arc<BTreeMap<String, AdapterLoadFailure>>,
+    /// Sampling parameters applied when a request doesn't set them
+    pub generation_defaults: GenerationOptions,
+    /// Responses to message requests with an idempotency key
+    pub idempotency: IdempotencyCache,
+    /// Image adapter for `/v1/images/generations` (None if no image adapter is configured)
+    pub image: Option<SharedImage>,
+    /// Background job queue (None if `[server.jobs]` is disabled or there's no storage)
+    pub jobs: Option<JobQueue>,
+    /// LLM adapter for generating replies (None if it failed to load)
+    pub llm: Option<SharedLlm>,
+    /// Endpoint balancer of the LLM adapter, readable without waiting for its lock
+    pub llm_balancer: Option<Arc<EndpointBalancer>>,
+    /// Circuit breaker of the LLM adapter, readable without waiting for its lock
+    pub llm_breaker: Option<Arc<CircuitBreaker>>,
+    /// Concurrency limiter of the LLM adapter, readable without waiting for its lock
+    pub llm_limiter: Option<Arc<ConcurrencyLimiter>>,
+    /// Largest audio file accepted for transcription (None uses the default)
+    pub max_audio_bytes: Option<usize>,
+    /// Largest decoded image accepted in message content (None uses the default)
+    pub max_image_bytes: Option<usize>,
+    /// Conversations a search reads at most (None uses the default)
+    pub max_search_scanned: Option<usize>,
+    /// Stored history sent with conversation messages (None if `[conversations.memory]` is off)
+    pub memory: Option<ConversationMemoryConfig>,
+    /// Policy checks on messages and replies (None if `[moderation]` is off)
+    pub moderation: Option<Moderation>,
+    /// How long handlers may take to start a response
+    pub request_timeouts: RequestTimeouts,
+    /// Storage adapter for persistence (None if no storage adapter is configured)
+    pub storage: Option<SharedStorage>,
+    /// Chunks buffered for a streaming client (None uses the default)
+    pub stream_buffer: Option<usize>,
+    /// STT adapter for `/v1/transcriptions` (None if no STT adapter is configured)
+    pub stt: Option<SharedStt>,
+    /// Tokens a conversation may use (None if conversations are unlimited)
+    pub token_budget: Option<u64>,
+    /// TTS adapter for `/v1/speech` (None if no TTS adapter is configured)
+    pub tts: Option<SharedTts>,
+    /// Ledger of LLM calls (None if `[usage_log]` is disabled)
+    pub usage_log: Option<UsageLog>,
+}
+
+impl AppState {
+    /// Build application state from configuration
+    ///
+    /// Adapters that fail to load are logged, recorded in `failed_adapters`
+    /// and left unset, so the server can still start and serve endpoints
+    /// that don't depend on them. With `[adapters] fail_fast`, the first
+    /// failure is returned instead.
+    pub async fn from_config(config: &Config, data_dir: &Path) -> Result<Self, ServiceError> {
+        let mut failed = BTreeMap::new();
+        let crypto =
+            load_configured("crypto", config, data_dir, &mut failed, load_crypto(config)).await?;
+        let image = load_configured(
+            "image",
+            config,
+            data_dir,
+            &mut failed,
+            load_image(config, data_dir),
+        )
+        .await?;
+        let llm = load_configured(
+            "llm",
+            config,
+            data_dir,
+            &mut failed,
+            load_llm(config, data_dir),
+        )
+        .await?;
+        let storage = load_configured(
+            "storage",
+            config,
+            data_dir,
+            &mut failed,
+            load_storage(config, data_dir, crypto.as_ref()),
+        )
+        .await?;
+        let stt = load_configured(
+            "stt",
+            config,
+            data_dir,
+            &mut failed,
+            load_stt(config, data_dir),
+        )
+        .await?;
+        let tts = load_configured(
+            "tts",
+            config,
+            data_dir,
+            &mut failed,
+            load_tts(config, data_dir),
+        )
+        .await?;
+
+        let usage_log = match UsageLog::start(&config.usage_log, data_dir) {
+            Ok(usage_log) => usage_log,
+            Err(e) => {
+                tracing::warn!("Usage log unavailable: {:#}", e);
+                None
+            }
+        };
+
+        let summary_llm = load_summary_llm(config, data_dir, llm.as_ref()).await;
+        let jobs = JobQueue::start_from_config(&config.server.jobs, storage.as_ref(), |queue| {
+            register_jobs(
+                queue,
+                config,
+                llm.as_ref(),
+                summary_llm.as_ref(),
+                storage.as_ref(),
+            );
+        });
+        if config.server.webhooks.is_some() && jobs.is_none() {
+            tracing::warn!("Webhooks disabled: they're delivered as background jobs");
+        }
+        if summary_llm.is_some() && jobs.is_none() {
+            tracing::warn!("Conversation summaries disabled: they're written as background jobs");
+        }
+
+        Ok(AppState {
+            crypto,
+            failed_adapters: Arc::new(failed),
+            image,
+            jobs,
+            stt,
+            tts,
+            usage_log,
+            ..AppState::with_adapters(config, llm, storage)
+        })
+    }
+
+    /// Build application state from configuration, using adapters from `registry`
+    ///
+    /// Nothing is loaded: the adapters are whatever the embedder registered
+    /// or loaded into the registry, see `AdapterRegistry::llm_adapter_for`.
+    /// No usage log is written; set `usage_log` to record one.
+    #[allow(dead_code)] // Used when embedding with a custom adapter registry
+    pub fn from_registry(config: &Config, registry: &AdapterRegistry) -> Self {
+        let failed_adapters = registry
+            .failed_adapters()
+            .iter()
+            .map(|(service, failure)| (service.clone(), failure.clone()))
+            .collect();
+
+        AppState {
+            crypto: registry.crypto_adapter_for(config).cloned(),
+            failed_adapters: Arc::new(failed_adapters),
+            image: registry.image_adapter_for(config).cloned(),
+            stt: registry.stt_adapter_for(config).cloned(),
+            tts: registry.tts_adapter_for(config).cloned(),
+            ..AppState::with_adapters(
+                config,
+                registry.llm_adapter_for(config).cloned(),
+                registry.storage_adapter_for(config).cloned(),
+            )
+        }
+    }
+
+    /// State for `config`, reusing the adapters of `self` that didn't change
+    ///
+    /// `previous` and `previous_data_dir` describe the config `self` was
+    /// built from. Adapters are reloaded when their `[adapters.*]` entry or
+    /// the data directory changed, and dropped when their entry was removed;
+    /// unchanged ones are kept as they are, even if they failed to load
+    /// (their `failed_adapters` entries are kept too).
+    /// Storage is also reloaded when `[storage] encrypt` or, with encryption
+    /// on, the crypto adapter changed.
+    /// Unlike `from_config`, a changed adapter that fails to load is an
+    /// error, so a broken config can be rejected as a whole. The usage log
+    /// keeps writing where it did until restart, the job queue keeps its
+    /// storage but runs jobs with the reloaded adapters and settings, and
+    /// stored idempotent responses are kept unless `[server.idempotency]`
+    /// changed.
+    pub async fn reconcile(
+        &self,
+        previous: &Config,
+        previous_data_dir: &Path,
+        config: &Config,
+        data_dir: &Path,
+    ) -> Result<AppState, ServiceError> {
+        let unchanged = |service: &str| {
+            previous_data_dir == data_dir
+                && previous.adapters.get_service(service) == config.adapters.get_service(service)
+        };
+
+        log_adapter_change("crypto", previous, config);
+        let crypto = match config.adapters.get_service("crypto") {
+            None => None,
+            Some(_) if unchanged("crypto") => self.crypto.clone(),
+            Some(_) => Some(load_crypto(config).await?),
+        };
+
+        log_adapter_change("image", previous, config);
+        let image = match config.adapters.get_service("image") {
+            None => None,
+            Some(_) if unchanged("image") => self.image.clone(),
+            Some(_) => Some(load_image(config, data_dir).await?),
+        };
+
+        log_adapter_change("llm", previous, config);
+        let llm = match config.adapters.get_service("llm") {
+            None => None,
+            Some(_) if unchanged("llm") => self.llm.clone(),
+            Some(_) => Some(load_llm(config, data_dir).await?),
+        };
+
+        log_adapter_change("storage", previous, config);
+        // Stored values are encrypted with whatever crypto adapter storage was loaded with
+        let same_crypto = !config.storage.encrypt
+            || match (&self.crypto, &crypto) {
+                (Some(old), Some(new)) => Arc::ptr_eq(old, new),
+                _ => false,
+            };
+        let storage = match config.adapters.get_service("storage") {
+            None => None,
+            Some(_)
+                if unchanged("storage")
+                    && previous.storage.encrypt == config.storage.encrypt
+                    && same_crypto =>
+            {
+                self.storage.clone()
+            }
+            Some(_) => Some(load_storage(config, data_dir, crypto.as_ref()).await?),
+        };
+
+        log_adapter_change("stt", previous, config);
+        let stt = match config.adapters.get_service("stt") {
+            None => None,
+            Some(_) if unchanged("stt") => self.stt.clone(),
+            Some(_) => Some(load_stt(config, data_dir).await?),
+        };
+
+        log_adapter_change("tts", previous, config);
+        let tts = match config.adapters.get_service("tts") {
+            None => None,
+            Some(_) if unchanged("tts") => self.tts.clone(),
+            Some(_) => Some(load_tts(config, data_dir).await?),
+        };
+
+        let failed_adapters = self
+            .failed_adapters
+            .iter()
+            .filter(|(service, _)| {
+                config.adapters.get_service(service).is_some() && unchanged(service)
+            })
+            .map(|(service, failure)| (service.clone(), failure.clone()))
+            .collect();
+
+        if let Some(jobs) = &self.jobs {
+            let summary_llm = load_summary_llm(config, data_dir, llm.as_ref()).await;
+            register_jobs(
+                jobs,
+                config,
+                llm.as_ref(),
+                summary_llm.as_ref(),
+                storage.as_ref(),
+            );
+        }
+
+        let state = AppState::with_adapters(config, llm, storage);
+        // A reused adapter may be busy; its breaker, limiter and balancer didn't change
+        let monitored = match (&self.llm, &state.llm) {
+            (Some(old), Some(new)) if Arc::ptr_eq(old, new) => self,
+            _ => &state,
+        };
+        let llm_balancer = monitored.llm_balancer.clone();
+        let llm_breaker = monitored.llm_breaker.clone();
+        let llm_limiter = monitored.llm_limiter.clone();
+        Ok(AppState {
+            failed_adapters: Arc::new(failed_adapters),
+            llm_balancer,
+            llm_breaker,
+            llm_limiter,
+            idempotency: if previous.server.idempotency == config.server.idempotency {
+                self.idempotency.clone()
+            } else {
+                state.idempotency
+            },
+            crypto,
+            image,
+            jobs: self.jobs.clone(),
+            stt,
+            tts,
+            usage_log: self.usage_log.clone(),
+            ..state
+        })
+    }
+
+    /// Shut down the adapters of `self` that `next` doesn't use anymore
+    ///
+    /// For after a reload swapped `next` in. Shutting an adapter down waits
+    /// for its lock, so requests still using it finish first.
+    pub async fn shutdown_replaced(&self, next: &AppState) {
+        shutdown_if_replaced("crypto", &self.crypto, &next.crypto).await;
+        shutdown_if_replaced("image", &self.image, &next.image).await;
+        shutdown_if_replaced("llm", &self.llm, &next.llm).await;
+        shutdown_if_replaced("storage", &self.storage, &next.storage).await;
+        shutdown_if_replaced("stt", &self.stt, &next.stt).await;
+        shutdown_if_replaced("tts", &self.tts, &next.tts).await;
+    }
+
+    fn with_adapters(
+        config: &Config,
+        llm: Option<SharedLlm>,
+        storage: Option<SharedStorage>,
+    ) -> Self {
+        // Nothing holds the lock of a freshly loaded adapter yet
+        let (llm_balancer, llm_breaker, llm_limiter) = llm
+            .as_ref()
+            .and_then(|llm| llm.try_read().ok())
+            .map(|llm| {
+                (
+                    llm.endpoint_balancer().cloned(),
+                    llm.circuit_breaker().cloned(),
+                    llm.concurrency_limiter().cloned(),
+                )
+            })
+            .unwrap_or_default();
+
+        AppState {
+            access_log: AccessLog::from_config(&config.server.access_log),
+            api_versions: ApiVersions::default(),
+            auth: None,
+            body_limit: BodyLimit::from_config(&config.server),
+            cache_dir: None,
+            crypto: None,
+            expose_fuel: config.server.debug.expose_fuel,
+            failed_adapters: Arc::default(),
+            generation_defaults: generation_defaults(config),
+            idempotency: IdempotencyCache::from_config(&config.server.idempotency),
+            image: None,
+            jobs: None,
+            llm,
+            llm_balancer,
+            llm_breaker,
+            llm_limiter,
+            max_audio_bytes: Some(config.limits.max_audio_bytes),
+            max_image_bytes: Some(config.limits.max_image_bytes),
+            max_search_scanned: Some(config.limits.max_search_scanned),
+            memory: config.conversations.memory.clone(),
+            moderation: None,
+            request_timeouts: RequestTimeouts::from_config(&config.server),
+            storage,
+            stream_buffer: Some(config.server.stream_buffer),
+            stt: None,
+            token_budget: config.limits.max_tokens_per_conversation,
+            tts: None,
+            usage_log: None,
+        }
+    }
+
+    /// Create state with the given storage adapter
+    #[allow(dead_code)] // Used in tests and when embedding with custom adapters
+    pub fn with_storage<S: StorageAdapter + 'static>(storage: S) -> Self {
+        AppState {
+            storage: Some(Arc::new(RwLock::new(storage))),
+            ..AppState::default()
+        }
+    }
+
+    /// Create state with the given LLM adapter
+    #[allow(dead_code)] // Used in tests and when embedding with custom adapters
+    pub fn with_llm<L: LlmAdapter + 'static>(llm: L) -> Self {
+        AppState {
+            llm_balancer: llm.endpoint_balancer().cloned(),
+            llm_breaker: llm.circuit_breaker().cloned(),
+            llm_limiter: llm.concurrency_limiter().cloned(),
+            llm: Some(Arc::new(RwLock::new(llm))),
+            ..AppState::default()
+        }
+    }
+
+    /// Create state with the given image adapter
+    #[allow(dead_code)] // Used in tests and when embedding with custom adapters
+    pub fn with_image<I: ImageAdapter + 'static>(image: I) -> Self {
+        AppState {
+            image: Some(Arc::new(RwLock::new(image))),
+            ..AppState::default()
+        }
+    }
+
+    /// Create state with the given STT adapter
+    #[allow(dead_code)] // Used in tests and when embedding with custom adapters
+    pub fn with_stt<T: SttAdapter + 'static>(stt: T) -> Self {
+        AppState {
+            stt: Some(Arc::new(RwLock::new(stt))),
+            ..AppState::default()
+        }
+    }
+
+    /// Create state with the given TTS adapter
+    #[allow(dead_code)] // Used in tests and when embedding with custom adapters
+    pub fn with_tts<T: TtsAdapter + 'static>(tts: T) -> Self {
+        AppState {
+            tts: Some(Arc::new(RwLock::new(tts))),
+            ..AppState::default()
+        }
+    }
+}
+
+/// Log how the adapter for `service` changed between two configs
+fn log_adapter_change(service: &str, previous: &Config, config: &Config) {
+    match (
+        previous.adapters.get_service(service),
+        config.adapters.get_service(service),
+    ) {
+        (None, Some(added)) => tracing::info!(
+            "Adding {} adapter {}@{}",
+            service,
+            added.provider,
+            added.version
+        ),
+        (Some(removed), None) => tracing::info!(
+            "Removing {} adapter {}@{}",
+            service,
+            removed.provider,
+            removed.version
+        ),
+        (Some(old), Some(new)) if old != new => tracing::info!(
+            "Updating {} adapter {}@{} to {}@{}",
+            service,
+            old.provider,
+            old.version,
+            new.provider,
+            new.version
+        ),
+        _ => {}
+    }
+}
+
+/// Shut down `old` unless it is `new`
+///
+/// A failure is logged: the adapter is out of use either way.
+async fn shutdown_if_replaced<A: AdapterService + ?Sized>(
+    service: &str,
+    old: &Option<Arc<RwLock<A>>>,
+    new: &Option<Arc<RwLock<A>>>,
+) {
+    let Some(old) = old else {
+        return;
+    };
+    if new.as_ref().is_some_and(|new| Arc::ptr_eq(old, new)) {
+        return;
+    }
+    let mut adapter = old.write().await;
+    if let Err(e) = adapter.shutdown().await {
+        tracing::warn!(
+            "Failed to shut down replaced {} adapter {}: {}",
+            service,
+            adapter.provider_name(),
+            e
+        );
+    }
+}
+
+/// Read sampling defaults from `[adapters.llm.config.defaults]`
+///
+/// Invalid defaults are logged and ignored rather than applied to every request.
+pub(crate) fn generation_defaults(config: &Config) -> GenerationOptions {
+    let Some(defaults) = config
+        .adapters
+        .get_service("llm")
+        .and_then(|llm| llm.config.get("defaults"))
+    else {
+        return GenerationOptions::default();
+    };
+
+    let defaults = match defaults.clone().try_into::<GenerationOptions>() {
+        Ok(defaults) => defaults,
+        Err(e) => {
+            tracing::warn!("Ignoring invalid [adapters.llm.config.defaults]: {}", e);
+            return GenerationOptions::default();
+        }
+    };
+
+    match defaults.validate() {
+        Ok(()) => defaults,
+        Err(e) => {
+            tracing::warn!("Ignoring invalid [adapters.llm.config.defaults]: {}", e);
+            GenerationOptions::default()
+        }
+    }
+}
+
+/// Load the adapter configured for `service` with `load`, if there's one
+///
+/// A failure is logged and recorded in `failed`, or returned with
+/// `[adapters] fail_fast`.
+async fn load_configured<T>(
+    service: &str,
+    config: &Config,
+    data_dir: &Path,
+    failed: &mut BTreeMap<String, AdapterLoadFailure>,
+    load: impl Future<Output = Result<T, ServiceError>>,
+) -> Result<Option<T>, ServiceError> {
+    let Some(service_config) = config.adapters.get_service(service) else {
+        return Ok(None);
+    };
+
+    match load.await {
+        Ok(adapter) => Ok(Some(adapter)),
+        Err(e) if config.adapters.fail_fast => Err(e),
+        Err(e) => {
+            tracing::warn!(
+                "{} adapter '{}' unavailable: {}",
+                service,
+                service_config.provider,
+                e
+            );
+            let failure = AdapterLoadFailure::new(service, service_config, data_dir, &e);
+            failed.insert(service.to_string(), failure);
+            Ok(None)
+        }
+    }
+}
+
+/// Load the configured LLM adapter, with its fallback chain, into its own WASM runtime
+///
+/// The built-in `mock` provider on its own needs no runtime.
+async fn load_llm(config: &Config, data_dir: &Path) -> Result<SharedLlm, ServiceError> {
+    let llm_config = config
+        .adapters
+        .get_service("llm")
+        .ok_or_else(|| ServiceError::InvalidConfig("No LLM adapter configured".to_string()))?;
+
+    if llm_config.provider == MOCK_PROVIDER && llm_config.fallback.is_empty() {
+        return Ok(Arc::new(RwLock::new(MockLlm::from_config(llm_config)?)));
+    }
+
+    let runtime = Arc::new(RwLock::new(WasmRuntime::new()?));
+    let http_client = http::shared_client()?;
+    if !llm_config.fallback.is_empty() {
+        let chain = FallbackLlm::load(
+            &runtime,
+            &http_client,
+            llm_config,
+            data_dir,
+            &config.adapters,
+        )
+        .await?;
+        return Ok(Arc::new(RwLock::new(chain)));
+    }
+    let adapter = LlmAdapterWrapper::new(
+        &runtime,
+        &http_client,
+        llm_config,
+        data_dir,
+        "llm",
+        AdapterLoadOptions::from_config(&config.adapters),
+    )
+    .await?;
+
+    Ok(Arc::new(RwLock::new(adapter)))
+}
+
+/// Register the job handlers `config` asks for, and drop the others
+///
+/// Run again when the config is reloaded, so jobs use the reloaded
+/// adapters and settings, and stop being enqueued once they're turned off.
+fn register_jobs(
+    queue: &JobQueue,
+    config: &Config,
+    llm: Option<&SharedLlm>,
+    summary_llm: Option<&SharedLlm>,
+    storage: Option<&SharedStorage>,
+) {
+    match (config.server.generate_titles, llm, storage) {
+        (true, Some(llm), Some(storage)) => {
+            jobs::title::register(queue, llm.clone(), storage.clone());
+        }
+        _ => queue.unregister(TITLE_JOB),
+    }
+    match (&config.conversations.memory, summary_llm, storage) {
+        (Some(memory), Some(llm), Some(storage)) => {
+            jobs::summary::register(queue, llm.clone(), storage.clone(), memory.window_messages)
+        }
+        _ => queue.unregister(SUMMARY_JOB),
+    }
+    match config
+        .server
+        .webhooks
+        .as_ref()
+        .map(|webhooks| (webhooks, http::shared_client()))
+    {
+        Some((webhooks, Ok(client))) => jobs::webhook::register(queue, client, webhooks.clone()),
+        Some((_, Err(e))) => {
+            tracing::warn!("Webhooks unavailable: {}", e);
+            queue.unregister(WEBHOOK_JOB);
+        }
+        None => queue.unregister(WEBHOOK_JOB),
+    }
+}
+
+/// LLM adapter writing conversation summaries (None unless the memory
+/// strategy is `summary`)
+///
+/// That's `llm` unless `summary_provider` names another provider of
+/// `[adapters.llm]` or its fallback chain, which is then loaded on its own
+/// into its own WASM runtime. If it can't be, summaries fall back to `llm`.
+async fn load_summary_llm(
+    config: &Config,
+    data_dir: &Path,
+    llm: Option<&SharedLlm>,
+) -> Option<SharedLlm> {
+    let memory = config
+        .conversations
+        .memory
+        .as_ref()
+        .filter(|memory| memory.strategy == MemoryStrategy::Summary)?;
+    let llm_config = config.adapters.get_service("llm")?;
+    let Some(provider) = memory
+        .summary_provider
+        .as_deref()
+        .filter(|provider| *provider != llm_config.provider)
+    else {
+        return llm.cloned();
+    };
+    let Some(entry) = llm_config
+        .fallback
+        .iter()
+        .find(|entry| entry.provider == provider)
+    else {
+        tracing::warn!(
+            "Summary provider '{}' isn't in [adapters.llm] or its fallback, using '{}'",
+            provider,
+            llm_config.provider
+        );
+        return llm.cloned();
+    };
+
+    let loaded = async {
+        if entry.provider == MOCK_PROVIDER {
+            let adapter: SharedLlm = Arc::new(RwLock::new(MockLlm::from_config(entry)?));
+            return Ok(adapter);
+        }
+        let runtime = Arc::new(RwLock::new(WasmRuntime::new()?));
+        let adapter = LlmAdapterWrapper::new(
+            &runtime,
+            &http::shared_client()?,
+            entry,
+            data_dir,
+            "llm",
+            AdapterLoadOptions::from_config(&config.adapters),
+        )
+        .await?;
+        Ok::<SharedLlm, ServiceError>(Arc::new(RwLock::new(adapter)))
+    };
+    match loaded.await {
+        Ok(adapter) => Some(adapter),
+        Err(e) => {
+            tracing::warn!("Summary provider '{}' unavailable: {}", provider, e);
+            llm.cloned()
+        }
+    }
+}
+
+/// Load the configured storage adapter, encoding keys with `KeyCodec`
+///
+/// With `[storage] encrypt`, values are encrypted with `crypto`, the loaded
+/// crypto adapter; without one, storage fails to load.
+async fn load_storage(
+    config: &Config,
+    data_dir: &Path,
+    crypto: Option<&SharedCrypto>,
+) -> Result<SharedStorage, ServiceError> {
+    let storage = EncodedKeys::new(load_raw_storage(config, data_dir).await?);
+    let storage: SharedStorage = match storage_crypto(config, crypto)? {
+        Some(crypto) => Arc::new(RwLock::new(EncryptedValues::new(Box::new(storage), crypto))),
+        None => Arc::new(RwLock::new(storage)),
+    };
+    Ok(storage)
+}
+
+/// Load the configured storage adapter into its own WASM runtime
+///
+/// The `sqlite` and `memory` providers are built into the host and need no
+/// WASM module. Keys are passed through as is, which only key migration should rely on.
+pub(crate) async fn load_raw_storage(
+    config: &Config,
+    data_dir: &Path,
+) -> Result<Box<dyn StorageAdapter>, ServiceError> {
+    let storage_config = config
+        .adapters
+        .get_service("storage")
+        .ok_or_else(|| ServiceError::InvalidConfig("No storage adapter configured".to_string()))?;
+
+    if storage_config.provider == MEMORY_PROVIDER {
+        return Ok(Box::new(MemoryStorage::default()));
+    }
+    if storage_config.provider == SQLITE_PROVIDER {
+        let storage = SqliteStorage::from_config(storage_config, data_dir).await?;
+        return Ok(Box::new(storage));
+    }
+
+    let runtime = Arc::new(RwLock::new(WasmRuntime::new()?));
+    let adapter = StorageAdapterWrapper::new(
+        &runtime,
+        storage_config,
+        data_dir,
+        "storage",
+        AdapterLoadOptions::from_config(&config.adapters),
+    )
+    .await?;
+
+    Ok(Box::new(adapter))
+}
+
+/// Load the configured crypto adapter
+///
+/// Only the built-in `aes-gcm` provider can encrypt yet, see `check_crypto_provider`.
+async fn load_crypto(config: &Config) -> Result<SharedCrypto, ServiceError> {
+    let crypto_config = config
+        .adapters
+        .get_service("crypto")
+        .ok_or_else(|| ServiceError::InvalidConfig("No crypto adapter configured".to_string()))?;
+
+    check_crypto_provider(crypto_config)?;
+    Ok(Arc::new(RwLock::new(AesGcmCrypto::from_config(
+        crypto_config,
+    )?)))
+}
+
+/// Load the configured image adapter into its own WASM runtime
+async fn load_image(config: &Config, data_dir: &Path) -> Result<SharedImage, ServiceError> {
+    let image_config = config
+        .adapters
+        .get_service("image")
+        .ok_or_else(|| ServiceError::InvalidConfig("No image adapter configured".to_string()))?;
+
+    let runtime = Arc::new(RwLock::new(WasmRuntime::new()?));
+    let adapter = ImageAdapterWrapper::new(
+        &runtime,
+        image_config,
+        data_dir,
+        "image",
+        AdapterLoadOptions::from_config(&config.adapters),
+    )
+    .await?;
+
+    Ok(Arc::new(RwLock::new(adapter)))
+}
+
+/// Load the configured STT adapter into its own WASM runtime
+async fn load_stt(config: &Config, data_dir: &Path) -> Result<SharedStt, ServiceError> {
+    let stt_config = config
+        .adapters
+        .get_service("stt")
+        .ok_or_else(|| ServiceError::InvalidConfig("No STT adapter configured".to_string()))?;
+
+    let runtime = Arc::new(RwLock::new(WasmRuntime::new()?));
+    let adapter = SttAdapterWrapper::new(
+        &runtime,
+        stt_config,
+        data_dir,
+        "stt",
+        AdapterLoadOptions::from_config(&config.adapters),
+    )
+    .await?;
+
+    Ok(Arc::new(RwLock::new(adapter)))
+}
+
+/// Load the configured TTS adapter into its own WASM runtime
+///
+/// The `silence` provider is built into the host and needs no WASM module.
+async fn load_tts(config: &Config, data_dir: &Path) -> Result<SharedTts, ServiceError> {
+    let tts_config = config
+        .adapters
+        .get_service("tts")
+        .ok_or_else(|| ServiceError::InvalidConfig("No TTS adapter configured".to_string()))?;
+
+    if tts_config.provider == SILENCE_PROVIDER {
+        return Ok(Arc::new(RwLock::new(SilenceTts)));
+    }
+
+    let runtime = Arc::new(RwLock::new(WasmRuntime::new()?));
+    let adapter = TtsAdapterWrapper::new(
+        &runtime,
+        tts_config,
+        data_dir,
+        "tts",
+        AdapterLoadOptions::from_config(&config.adapters),
+    )
+    .await?;
+
+    Ok(Arc::new(RwLock::new(adapter)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_defaults_from_config() {
+        let config: Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "ollama"
+
+[adapters.llm.config.defaults]
+temperature = 0.5
+max_tokens = 256
+stop = ["END"]
+"#,
+        )
+        .unwrap();
+
+        let defaults = generation_defaults(&config);
+
+        assert_eq!(defaults.temperature, Some(0.5));
+        assert_eq!(defaults.max_tokens, Some(256));
+        assert_eq!(defaults.stop, Some(vec!["END".to_string()]));
+        assert_eq!(defaults.top_p, None);
+    }
+
+    #[test]
+    fn test_invalid_generation_defaults_ignored() {
+        for defaults in ["temperature = 5.0", "unknown = 1", "max_tokens = \"many\""] {
+            let config: Config = toml::from_str(&format!(
+                "[adapters.llm]\nprovider = \"ollama\"\n\n[adapters.llm.config.defaults]\n{}\n",
+                defaults
+            ))
+            .unwrap();
+
+            assert_eq!(generation_defaults(&config), GenerationOptions::default());
+        }
+    }
+
+    #[test]
+    fn test_generation_defaults_without_config() {
+        assert_eq!(
+            generation_defaults(&Config::default()),
+            GenerationOptions::default()
+        );
+    }
+
+    fn storage_config(storage: &str) -> Config {
+        toml::from_str(&format!("[adapters.storage]\n{}\n", storage)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_adds_updates_and_removes_adapters() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let empty = Config {
+            adapters: crate::config::schema::AdapterConfig {
+                services: Default::default(),
+                fail_fast: false,
+                strict_config: Default::default(),
+                strict_manifest: true,
+            },
+            ..Config::default()
+        };
+        let sqlite = storage_config("provider = \"sqlite\"");
+
+        // Added
+        let state = AppState::default();
+        let added = state
+            .reconcile(&empty, data_dir, &sqlite, data_dir)
+            .await
+            .unwrap();
+        let storage = added.storage.clone().unwrap();
+        assert_eq!(storage.read().await.provider_name(), "sqlite");
+
+        // Unchanged adapters are kept
+        let kept = added
+            .reconcile(&sqlite, data_dir, &sqlite, data_dir)
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&storage, kept.storage.as_ref().unwrap()));
+
+        // Changed settings reload the adapter
+        let moved = storage_config("provider = \"sqlite\"\nconfig = { path = \"other.sqlite3\" }");
+        let updated = kept
+            .reconcile(&sqlite, data_dir, &moved, data_dir)
+            .await
+            .unwrap();
+        assert!(!Arc::ptr_eq(&storage, updated.storage.as_ref().unwrap()));
+        assert!(data_dir.join("other.sqlite3").exists());
+
+        // Removed
+        let removed = updated
+            .reconcile(&moved, data_dir, &empty, data_dir)
+            .await
+            .unwrap();
+        assert!(removed.storage.is_none());
+    }
+
+    /// TTS adapter counting its shutdowns
+    struct CountedTts(Arc<std::sync::atomic::AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl AdapterService for CountedTts {
+        fn service_name(&self) -> &'static str {
+            "tts"
+        }
+
+        fn provider_name(&self) -> &str {
+            "counted"
+        }
+
+        fn version(&self) -> &str {
+            "test"
+        }
+
+        fn is_ready(&self) -> bool {
+            true
+        }
+
+        async fn shutdown(&mut self) -> Result<(), ServiceError> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TtsAdapter for CountedTts {
+        async fn synthesize(
+            &mut self,
+            _text: &str,
+            _voice: Option<&str>,
+        ) -> Result<crate::adapter::traits::Speech, ServiceError> {
+            Err(ServiceError::NotImplemented("synthesize".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_replaced_keeps_reused_adapters() {
+        let shutdowns = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let previous = AppState::with_tts(CountedTts(shutdowns.clone()));
+
+        let kept = AppState {
+            tts: previous.tts.clone(),
+            ..AppState::default()
+        };
+        previous.shutdown_replaced(&kept).await;
+        assert_eq!(shutdowns.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let silence: Config = toml::from_str("[adapters.tts]\nprovider = \"silence\"\n").unwrap();
+        let replaced = previous
+            .reconcile(
+                &Config::default(),
+                temp_dir.path(),
+                &silence,
+                temp_dir.path(),
+            )
+            .await
+            .unwrap();
+        previous.shutdown_replaced(&replaced).await;
+        assert_eq!(shutdowns.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_loads_built_in_tts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let silence: Config = toml::from_str("[adapters.tts]\nprovider = \"silence\"\n").unwrap();
+
+        let state = AppState::default()
+            .reconcile(
+                &Config::default(),
+                temp_dir.path(),
+                &silence,
+                temp_dir.path(),
+            )
+            .await
+            .unwrap();
+
+        let tts = state.tts.unwrap();
+        assert_eq!(tts.read().await.provider_name(), SILENCE_PROVIDER);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_reloads_storage_when_encryption_changes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let adapters = r#"
+[adapters.crypto]
+provider = "aes-gcm"
+
+[adapters.crypto.config]
+key = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+
+[adapters.storage]
+provider = "sqlite"
+"#;
+        let plain: Config = toml::from_str(adapters).unwrap();
+        let encrypted: Config =
+            toml::from_str(&format!("[storage]\nencrypt = true\n{}", adapters)).unwrap();
+
+        let state = AppState::from_config(&plain, data_dir).await.unwrap();
+        assert!(state.failed_adapters.is_empty());
+        let storage = state.storage.clone().unwrap();
+
+        let reloaded = state
+            .reconcile(&plain, data_dir, &encrypted, data_dir)
+            .await
+            .unwrap();
+        let encrypting = reloaded.storage.clone().unwrap();
+        assert!(!Arc::ptr_eq(&storage, &encrypting));
+        encrypting
+            .write()
+            .await
+            .store("conversation/a", b"hallo")
+            .await
+            .unwrap();
+
+        // Stored through the crypto adapter, so plain storage can't read it back as is
+        let value = storage
+            .read()
+            .await
+            .retrieve("conversation/a")
+            .await
+            .unwrap();
+        assert_ne!(value, b"hallo");
+        assert_eq!(
+            encrypting
+                .read()
+                .await
+                .retrieve("conversation/a")
+                .await
+                .unwrap(),
+            b"hallo"
+        );
+
+        // A changed crypto adapter reloads encrypting storage too
+        let mut other_key = encrypted.clone();
+        other_key
+            .adapters
+            .services
+            .get_mut("crypto")
+            .unwrap()
+            .config = toml::from_str(
+            "key = \"1f1e1d1c1b1a191817161514131211100f0e0d0c0b0a09080706050403020100\"",
+        )
+        .unwrap();
+        let rekeyed = reloaded
+            .reconcile(&encrypted, data_dir, &other_key, data_dir)
+            .await
+            .unwrap();
+        assert!(!Arc::ptr_eq(&encrypting, rekeyed.storage.as_ref().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_registers_jobs_again() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let adapters = r#"
+[adapters.llm]
+provider = "mock"
+
+[adapters.storage]
+provider = "memory"
+
+[usage_log]
+enabled = false
+"#;
+        let window: Config = toml::from_str(adapters).unwrap();
+        let summary: Config = toml::from_str(&format!(
+            "[conversations.memory]\nstrategy = \"summary\"\n{}",
+            adapters
+        ))
+        .unwrap();
+
+        let state = AppState::from_config(&window, data_dir).await.unwrap();
+        let jobs = state.jobs.clone().unwrap();
+        assert!(!jobs.handles(SUMMARY_JOB));
+
+        let summarizing = state
+            .reconcile(&window, data_dir, &summary, data_dir)
+            .await
+            .unwrap();
+        assert!(jobs.handles(SUMMARY_JOB));
+
+        summarizing
+            .reconcile(&summary, data_dir, &window, data_dir)
+            .await
+            .unwrap();
+        assert!(!jobs.handles(SUMMARY_JOB));
+    }
+
+    #[tokio::test]
+    async fn test_from_config_records_failed_adapters() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config: Config = toml::from_str(
+            r#"
+[adapters.llm]
+provider = "openai"
+version = "1.0.0"
+
+[adapters.storage]
+provider = "memory"
+
+[usage_log]
+enabled = false
+"#,
+        )
+        .unwrap();
+
+        let state = AppState::from_config(&config, temp_dir.path())
+            .await
+            .unwrap();
+        assert!(state.llm.is_none());
+        let storage = state.storage.clone().unwrap();
+        storage.write().await.store("key", b"data").await.unwrap();
+        assert!(storage.read().await.exists("key").await.unwrap());
+
+        let failure = &state.failed_adapters["llm"];
+        assert_eq!(failure.provider, "openai");
+        assert_eq!(
+            failure.attempted_path,
+            Some(
+                temp_dir
+                    .path()
+                    .join("adapters/llm/openai/1.0.0/adapter.wasm")
+            )
+        );
+        assert!(!failure.error.is_empty());
+
+        // Kept while the service is unchanged, dropped once it's removed
+        let kept = state
+            .reconcile(&config, temp_dir.path(), &config, temp_dir.path())
+            .await
+            .unwrap();
+        assert!(kept.failed_adapters.contains_key("llm"));
+        let without_llm = storage_config("provider = \"memory\"");
+        let removed = kept
+            .reconcile(&config, temp_dir.path(), &without_llm, temp_dir.path())
+            .await
+            .unwrap();
+        assert!(removed.failed_adapters.is_empty());
+
+        config.adapters.fail_fast = true;
+        assert!(
+            AppState::from_config(&config, temp_dir.path())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_fails_when_adapter_does_not_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let missing = storage_config("provider = \"missing\"");
+
+        let result = AppState::default()
+            .reconcile(
+                &Config::default(),
+                temp_dir.path(),
+                &missing,
+                temp_dir.path(),
+            )
+            .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use super::state::AppState;
+use crate::config::defaults::{DEFAULT_MESSAGE_TIMEOUT_SECS, DEFAULT_REQUEST_TIMEOUT_SECS};
+use crate::config::schema::ServerConfig;
+use crate::routes::fallback::route_error_response;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use std::time::{Duration, Instant};
+
+/// How long handlers may take to produce a response
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestTimeouts {
+    /// Limit for all routes without their own
+    pub default: Duration,
+    /// Limit for /v1/message, where LLM calls legitimately take a while
+    pub message: Duration,
+}
+
+impl RequestTimeouts {
+    /// Read the limits from `[server]`
+    pub fn from_config(config: &ServerConfig) -> Self {
+        RequestTimeouts {
+            default: Duration::from_secs(config.request_timeout_secs),
+            message: Duration::from_secs(config.message_timeout_secs),
+        }
+    }
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        RequestTimeouts {
+            default: Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS),
+            message: Duration::from_secs(DEFAULT_MESSAGE_TIMEOUT_SECS),
+        }
+    }
+}
+
+/// State of the timeout middleware
+#[derive(Clone)]
+pub struct RouteTimeouts {
+    /// Path prefixes of the message routes, one per API version (e.g. "/api/v1/message/")
+    pub message_prefixes: Vec<String>,
+    pub state: AppState,
+}
+
+/// Answer with a 504 if the handler doesn't produce a response in time
+///
+/// Only the time until the response head is limited. Streamed bodies
+/// (NDJSON, SSE) start right away and then run for as long as they need.
+/// The handler future is dropped on timeout, which aborts its upstream work.
+pub async fn enforce_timeout(
+    State(routes): State<RouteTimeouts>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let is_message = routes
+        .message_prefixes
+        .iter()
+        .any(|prefix| path.starts_with(prefix));
+    let timeouts = routes.state.request_timeouts;
+    let limit = if is_message {
+        timeouts.message
+    } else {
+        timeouts.default
+    };
+    let started = Instant::now();
+
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            let provider = is_message.then(|| provider_name(&routes.state)).flatten();
+            tracing::warn!(
+                "Request to {} timed out after {:?} (provider: {})",
+                path,
+                started.elapsed(),
+                provider.as_deref().unwrap_or("unknown")
+            );
+
+            route_error_response(
+                StatusCode::GATEWAY_TIMEOUT,
+                "GATEWAY_TIMEOUT",
+                &format!("No response within {:?}", limit),
+                &path,
+            )
+        }
+    }
+}
+
+/// Provider of the LLM adapter, unless another request still holds its lock
+fn provider_name(state: &AppState) -> Option<String> {
+    let llm = state.llm.as_ref()?.try_read().ok()?;
+    Some(llm.provider_name().to_string())
+}
@@ -0,0 +1,413 @@
+use crate::config::schema::UsageLogConfig;
+use crate::utils::log_file::{SizeRotatingFile, backup_path};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::mpsc;
+
+/// Records buffered for the writer; when it falls behind, new ones are dropped
+pub const USAGE_LOG_BUFFER: usize = 1024;
+
+/// Outcome of an LLM call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageStatus {
+    Ok,
+    Failed,
+}
+
+/// One line of the usage ledger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub request_id: String,
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+    pub latency_ms: u64,
+    pub status: UsageStatus,
+    /// Why the call failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+}
+
+/// Handle for appending to the usage ledger
+///
+/// Records go through a bounded channel to a writer on a blocking thread,
+/// so a slow disk never holds up request handling: when the buffer is
+/// full, records are dropped and counted instead. The writer stops once
+/// every handle is dropped.
+#[derive(Debug, Clone)]
+pub struct UsageLog {
+    dropped: Arc<AtomicU64>,
+    include_failed: bool,
+    records: mpsc::Sender<UsageRecord>,
+}
+
+impl UsageLog {
+    /// Start writing the ledger configured in `[usage_log]`, if enabled
+    pub fn start(config: &UsageLogConfig, data_dir: &Path) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let path = config.ledger_path(data_dir);
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).with_context(|| {
+                format!(
+                    "Failed to create usage log directory: {}",
+                    directory.display()
+                )
+            })?;
+        }
+        let file = SizeRotatingFile::open(
+            &path,
+            config.max_size_mb.saturating_mul(1024 * 1024),
+            config.max_files,
+        )
+        .with_context(|| format!("Failed to open usage log: {}", path.display()))?
+        .backup_before_extension();
+
+        tracing::info!("Recording LLM usage in {}", path.display());
+        Ok(Some(UsageLog::with_writer(
+            file,
+            USAGE_LOG_BUFFER,
+            config.include_failed,
+        )))
+    }
+
+    /// Write records to `writer`, buffering up to `capacity` of them
+    pub fn with_writer<W: Write + Send + 'static>(
+        mut writer: W,
+        capacity: usize,
+        include_failed: bool,
+    ) -> Self {
+        let (records, mut receiver) = mpsc::channel::<UsageRecord>(capacity);
+
+        tokio::task::spawn_blocking(move || {
+            while let Some(record) = receiver.blocking_recv() {
+                let result = serde_json::to_vec(&record)
+                    .map_err(io::Error::from)
+                    .and_then(|mut line| {
+                        line.push(b'\n');
+                        writer.write_all(&line)?;
+                        writer.flush()
+                    });
+                if let Err(e) = result {
+                    tracing::error!("Failed to write usage record: {}", e);
+                }
+            }
+        });
+
+        UsageLog {
+            dropped: Arc::new(AtomicU64::new(0)),
+            include_failed,
+            records,
+        }
+    }
+
+    /// Queue `record` for the ledger without waiting
+    pub fn record(&self, record: UsageRecord) {
+        if record.status == UsageStatus::Failed && !self.include_failed {
+            return;
+        }
+
+        if self.records.try_send(record).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            // Warn on the first drop and then occasionally, not for every record
+            if dropped == 1 || dropped.is_multiple_of(1000) {
+                tracing::warn!(
+                    "Usage log is falling behind, {} records dropped so far",
+                    dropped
+                );
+            }
+        }
+    }
+
+    /// Records dropped because the writer fell behind
+    #[allow(dead_code)] // Used in tests and by embedders monitoring the ledger
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Token totals for one provider and model
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub calls: u64,
+    pub completion_tokens: u64,
+    pub failed: u64,
+    pub prompt_tokens: u64,
+    pub total_tokens: u64,
+}
+
+/// Usage per (provider, model), sorted
+pub type UsageReport = BTreeMap<(String, String), UsageTotals>;
+
+/// Ledger files of `[usage_log]`, the current one and its backups
+pub fn ledger_files(config: &UsageLogConfig, data_dir: &Path) -> Vec<PathBuf> {
+    let path = config.ledger_path(data_dir);
+    let backups = (1..=config.max_files).map(|index| backup_path(&path, index, true));
+
+    std::iter::once(path.clone())
+        .chain(backups)
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// Sum up the records in `files` from `from` to `to` (inclusive, UTC dates)
+///
+/// Lines that aren't usage records are skipped with a warning, so a line
+/// cut short by a crash doesn't spoil the report.
+pub fn summarize(
+    files: &[PathBuf],
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<UsageReport> {
+    let mut report = UsageReport::new();
+
+    for path in files {
+        let file =
+            fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: UsageRecord = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(e) => {
+                    tracing::warn!("Skipping {}:{}: {}", path.display(), number + 1, e);
+                    continue;
+                }
+            };
+
+            let date = record.timestamp.date_naive();
+            if from.is_some_and(|from| date < from) || to.is_some_and(|to| date > to) {
+                continue;
+            }
+
+            let totals = report.entry((record.provider, record.model)).or_default();
+            totals.calls += 1;
+            totals.prompt_tokens += u64::from(record.prompt_tokens);
+            totals.completion_tokens += u64::from(record.completion_tokens);
+            totals.total_tokens += u64::from(record.total_tokens);
+            if record.status == UsageStatus::Failed {
+                totals.failed += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    fn record(timestamp: &str, provider: &str, model: &str, tokens: u32) -> UsageRecord {
+        UsageRecord {
+            timestamp: timestamp.parse().unwrap(),
+            request_id: "req-1".to_string(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt_tokens: tokens,
+            completion_tokens: tokens,
+            total_tokens: tokens * 2,
+            latency_ms: 12,
+            status: UsageStatus::Ok,
+            error: None,
+            conversation_id: None,
+            sender: None,
+        }
+    }
+
+    /// Writer that takes `delay` per write, like a slow disk
+    #[derive(Clone, Default)]
+    struct SlowWriter {
+        delay: Duration,
+        lines: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl Write for SlowWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            std::thread::sleep(self.delay);
+            self.lines.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn wait_for_lines(writer: &SlowWriter, count: usize) -> Vec<UsageRecord> {
+        for _ in 0..200 {
+            let lines = writer.lines.lock().unwrap().clone();
+            let records: Vec<UsageRecord> = String::from_utf8(lines)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            if records.len() >= count {
+                return records;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {} usage records", count);
+    }
+
+    #[tokio::test]
+    async fn test_records_written_as_json_lines() {
+        let writer = SlowWriter::default();
+        let log = UsageLog::with_writer(writer.clone(), 8, true);
+
+        let mut failed = record("2025-01-02T10:00:00Z", "ollama", "llama3.2", 0);
+        failed.status = UsageStatus::Failed;
+        failed.error = Some("timeout".to_string());
+        log.record(record("2025-01-02T09:00:00Z", "ollama", "llama3.2", 5));
+        log.record(failed.clone());
+
+        let records = wait_for_lines(&writer, 2).await;
+        assert_eq!(records[1], failed);
+        assert_eq!(records[0].total_tokens, 10);
+    }
+
+    #[tokio::test]
+    async fn test_failed_calls_skipped_unless_included() {
+        let writer = SlowWriter::default();
+        let log = UsageLog::with_writer(writer.clone(), 8, false);
+
+        let mut failed = record("2025-01-02T10:00:00Z", "ollama", "llama3.2", 0);
+        failed.status = UsageStatus::Failed;
+        log.record(failed);
+        log.record(record("2025-01-02T11:00:00Z", "ollama", "llama3.2", 1));
+
+        let records = wait_for_lines(&writer, 1).await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].status, UsageStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn test_slow_disk_does_not_block_recording() {
+        let writer = SlowWriter {
+            delay: Duration::from_millis(50),
+            ..SlowWriter::default()
+        };
+        let log = UsageLog::with_writer(writer.clone(), 4, true);
+
+        let started = Instant::now();
+        for _ in 0..20 {
+            log.record(record("2025-01-02T10:00:00Z", "ollama", "llama3.2", 1));
+        }
+
+        // 20 writes would take a second; recording must not wait for them
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert!(log.dropped() > 0);
+        assert!(log.dropped() < 20);
+    }
+
+    #[tokio::test]
+    async fn test_start_rotates_ledger() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = UsageLogConfig {
+            enabled: true,
+            max_files: 2,
+            max_size_mb: 0,
+            ..UsageLogConfig::default()
+        };
+
+        let log = UsageLog::start(&config, temp_dir.path()).unwrap().unwrap();
+        for tokens in 1..=4 {
+            log.record(record("2025-01-02T10:00:00Z", "ollama", "llama3.2", tokens));
+        }
+        drop(log);
+
+        // With a zero size limit every record starts a new file
+        let usage_dir = temp_dir.path().join("usage");
+        for _ in 0..200 {
+            if fs::read_to_string(usage_dir.join("ledger.jsonl"))
+                .is_ok_and(|current| current.contains("\"prompt_tokens\":4"))
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let read = |name: &str| fs::read_to_string(usage_dir.join(name)).unwrap();
+        assert!(read("ledger.jsonl").contains("\"prompt_tokens\":4"));
+        assert!(read("ledger.1.jsonl").contains("\"prompt_tokens\":3"));
+        assert!(read("ledger.2.jsonl").contains("\"prompt_tokens\":2"));
+        assert!(!usage_dir.join("ledger.3.jsonl").exists());
+
+        assert_eq!(ledger_files(&config, temp_dir.path()).len(), 3);
+    }
+
+    #[test]
+    fn test_start_disabled() {
+        let temp_dir = TempDir::new().unwrap();
+
+        assert!(
+            UsageLog::start(&UsageLogConfig::default(), temp_dir.path())
+                .unwrap()
+                .is_none()
+        );
+        assert!(!temp_dir.path().join("usage").exists());
+    }
+
+    #[test]
+    fn test_summarize_aggregates_by_provider_and_model() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut failed = record("2025-01-03T10:00:00Z", "openai", "gpt-4o", 0);
+        failed.status = UsageStatus::Failed;
+        let lines = [
+            record("2025-01-01T23:59:59Z", "ollama", "llama3.2", 100),
+            record("2025-01-02T00:00:00Z", "ollama", "llama3.2", 5),
+            record("2025-01-03T12:00:00Z", "ollama", "llama3.2", 7),
+            record("2025-01-03T12:00:00Z", "openai", "gpt-4o", 3),
+            failed,
+            record("2025-01-04T00:00:00Z", "ollama", "llama3.2", 100),
+        ]
+        .iter()
+        .map(|record| serde_json::to_string(record).unwrap())
+        .collect::<Vec<_>>();
+
+        let current = temp_dir.path().join("ledger.jsonl");
+        let backup = temp_dir.path().join("ledger.1.jsonl");
+        fs::write(&backup, lines[..3].join("\n") + "\n").unwrap();
+        fs::write(&current, lines[3..].join("\n") + "\n{\"truncated\n").unwrap();
+
+        let report = summarize(
+            &[current, backup],
+            Some(NaiveDate::from_ymd_opt(2025, 1, 2).unwrap()),
+            Some(NaiveDate::from_ymd_opt(2025, 1, 3).unwrap()),
+        )
+        .unwrap();
+
+        assert_eq!(report.len(), 2);
+        let ollama = &report[&("ollama".to_string(), "llama3.2".to_string())];
+        assert_eq!(ollama.calls, 2);
+        assert_eq!(ollama.prompt_tokens, 12);
+        assert_eq!(ollama.total_tokens, 24);
+        let openai = &report[&("openai".to_string(), "gpt-4o".to_string())];
+        assert_eq!(openai.calls, 2);
+        assert_eq!(openai.failed, 1);
+        assert_eq!(openai.total_tokens, 6);
+    }
+}
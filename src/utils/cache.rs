@@ -0,0 +1,408 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// File marking a cache directory whose whole content is ours
+pub const CACHE_MARKER_FILE: &str = ".ai_messenger-cache";
+
+/// Subdirectories of the cache directory we create and may delete from
+pub const CACHE_SUBDIRS: &[&str] = &["components", "responses", "tmp"];
+
+/// Errors while cleaning the cache directory
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("Refusing to clean {path}: {reason}")]
+    Unsafe { path: PathBuf, reason: &'static str },
+    #[error("Failed to clean {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// What to remove from the cache directory
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanOptions {
+    /// Only report what would be removed
+    pub dry_run: bool,
+    /// Only remove files last modified longer ago than this
+    pub older_than: Option<Duration>,
+}
+
+/// Files removed (or, for a dry run, to be removed) by `clean`
+#[derive(Debug, Default)]
+pub struct CleanReport {
+    pub freed_bytes: u64,
+    /// Removed files, relative to the cache directory
+    pub removed: Vec<PathBuf>,
+}
+
+/// Remove our files from `cache_dir`
+///
+/// Only the subdirectories in `CACHE_SUBDIRS` are touched, or everything
+/// if the directory has a `CACHE_MARKER_FILE`. Directories emptied along
+/// the way are removed too, except the subdirectories themselves. A
+/// missing cache directory has nothing to clean.
+pub fn clean(cache_dir: &Path, options: &CleanOptions) -> Result<CleanReport, CacheError> {
+    let mut report = CleanReport::default();
+    if !cache_dir.exists() {
+        return Ok(report);
+    }
+    let cache_dir = check_cache_dir(cache_dir)?;
+
+    let cutoff = options
+        .older_than
+        .and_then(|age| SystemTime::now().checked_sub(age));
+    let owns_everything = cache_dir.join(CACHE_MARKER_FILE).is_file();
+
+    for entry in read_dir(&cache_dir)? {
+        let name = entry.file_name();
+        let owned = if owns_everything {
+            name != CACHE_MARKER_FILE
+        } else {
+            CACHE_SUBDIRS.iter().any(|subdir| name == *subdir)
+        };
+        if owned {
+            clean_entry(
+                &cache_dir,
+                &entry.path(),
+                cutoff,
+                options.dry_run,
+                &mut report,
+            )?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Remove everything in `cache_dir` except its `CACHE_MARKER_FILE`
+///
+/// Unlike `clean`, files we didn't create are removed as well, so callers
+/// should confirm first. The cache directory itself is kept.
+pub fn clear(cache_dir: &Path) -> Result<CleanReport, CacheError> {
+    let mut report = CleanReport::default();
+    if !cache_dir.exists() {
+        return Ok(report);
+    }
+    let cache_dir = check_cache_dir(cache_dir)?;
+
+    for entry in read_dir(&cache_dir)? {
+        if entry.file_name() == CACHE_MARKER_FILE {
+            continue;
+        }
+        let path = entry.path();
+        clean_entry(&cache_dir, &path, None, false, &mut report)?;
+        // `clean_entry` keeps the directories directly in the cache directory
+        if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_dir()) {
+            fs::remove_dir(&path).map_err(|source| CacheError::Io { path, source })?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Canonical `cache_dir`, unless it's a directory we must never clean
+///
+/// That's the filesystem root and the home directory or any of its
+/// ancestors, which a typo in `storage.cache_dir` can easily point at.
+pub fn check_cache_dir(cache_dir: &Path) -> Result<PathBuf, CacheError> {
+    let canonical = cache_dir.canonicalize().map_err(|source| CacheError::Io {
+        path: cache_dir.to_path_buf(),
+        source,
+    })?;
+    let unsafe_dir = |reason| CacheError::Unsafe {
+        path: canonical.clone(),
+        reason,
+    };
+
+    if canonical.parent().is_none() {
+        return Err(unsafe_dir("it is the filesystem root"));
+    }
+    if dirs::home_dir()
+        .and_then(|home| home.canonicalize().ok())
+        .is_some_and(|home| home.starts_with(&canonical))
+    {
+        return Err(unsafe_dir("it contains the home directory"));
+    }
+
+    Ok(canonical)
+}
+
+/// Remove `path` if it's an old enough file, or its old files if it's a directory
+///
+/// Returns whether `path` is gone (or would be, for a dry run).
+fn clean_entry(
+    cache_dir: &Path,
+    path: &Path,
+    cutoff: Option<SystemTime>,
+    dry_run: bool,
+    report: &mut CleanReport,
+) -> Result<bool, CacheError> {
+    let io_error = |source| CacheError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    // Symlinks are removed themselves, never followed
+    let metadata = fs::symlink_metadata(path).map_err(io_error)?;
+
+    if metadata.is_dir() {
+        let mut empty = true;
+        for entry in read_dir(path)? {
+            empty &= clean_entry(cache_dir, &entry.path(), cutoff, dry_run, report)?;
+        }
+        let is_subdir = path.parent() == Some(cache_dir);
+        if empty && !is_subdir && !dry_run {
+            fs::remove_dir(path).map_err(io_error)?;
+        }
+        return Ok(empty);
+    }
+
+    let old_enough = match (cutoff, metadata.modified()) {
+        (Some(cutoff), Ok(modified)) => modified < cutoff,
+        (Some(_), Err(_)) => false,
+        (None, _) => true,
+    };
+    if !old_enough {
+        return Ok(false);
+    }
+
+    if !dry_run {
+        fs::remove_file(path).map_err(io_error)?;
+    }
+    report.freed_bytes += metadata.len();
+    report
+        .removed
+        .push(path.strip_prefix(cache_dir).unwrap_or(path).to_path_buf());
+    Ok(true)
+}
+
+/// Entries of `dir`, sorted by name
+fn read_dir(dir: &Path) -> Result<Vec<fs::DirEntry>, CacheError> {
+    let io_error = |source| CacheError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+    let mut entries = fs::read_dir(dir)
+        .map_err(io_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io_error)?;
+    entries.sort_by_key(|entry| entry.file_name());
+    Ok(entries)
+}
+
+/// Parse an age like `30s`, `15m`, `12h`, `7d` or `2w`
+pub fn parse_age(age: &str) -> Result<Duration, String> {
+    let age = age.trim();
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (count, unit) = age.split_at(split);
+
+    let count: u64 = count
+        .parse()
+        .map_err(|_| format!("Invalid age '{}': expected e.g. 7d", age))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Invalid age '{}': unit must be s, m, h, d or w",
+                age
+            ));
+        }
+    };
+
+    Ok(Duration::from_secs(count.saturating_mul(seconds)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cache tree with an old and a new file in `components`, plus a foreign file
+    fn cache_tree() -> tempfile::TempDir {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let root = cache_dir.path();
+        fs::create_dir_all(root.join("components/nested")).unwrap();
+        fs::create_dir_all(root.join("responses")).unwrap();
+
+        write(
+            root,
+            "components/nested/old.bin",
+            100,
+            Duration::from_secs(10 * 86400),
+        );
+        write(root, "components/new.bin", 20, Duration::ZERO);
+        write(
+            root,
+            "responses/old.json",
+            5,
+            Duration::from_secs(8 * 86400),
+        );
+        write(root, "notes.txt", 7, Duration::from_secs(30 * 86400));
+        cache_dir
+    }
+
+    /// Write `size` bytes to `name`, last modified `age` ago
+    fn write(root: &Path, name: &str, size: usize, age: Duration) {
+        let path = root.join(name);
+        fs::write(&path, vec![0u8; size]).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+    }
+
+    fn removed(report: &CleanReport) -> Vec<String> {
+        report
+            .removed
+            .iter()
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_only_touches_our_subdirectories() {
+        let cache_dir = cache_tree();
+
+        let report = clean(cache_dir.path(), &CleanOptions::default()).unwrap();
+
+        assert_eq!(
+            removed(&report),
+            [
+                "components/nested/old.bin",
+                "components/new.bin",
+                "responses/old.json"
+            ]
+        );
+        assert_eq!(report.freed_bytes, 125);
+        assert!(cache_dir.path().join("notes.txt").exists());
+        // Emptied nested directories go, our subdirectories stay
+        assert!(!cache_dir.path().join("components/nested").exists());
+        assert!(cache_dir.path().join("components").is_dir());
+    }
+
+    #[test]
+    fn test_clean_older_than() {
+        let cache_dir = cache_tree();
+        let options = CleanOptions {
+            older_than: Some(Duration::from_secs(9 * 86400)),
+            ..CleanOptions::default()
+        };
+
+        let report = clean(cache_dir.path(), &options).unwrap();
+
+        assert_eq!(removed(&report), ["components/nested/old.bin"]);
+        assert_eq!(report.freed_bytes, 100);
+        assert!(cache_dir.path().join("components/new.bin").exists());
+        assert!(cache_dir.path().join("responses/old.json").exists());
+    }
+
+    #[test]
+    fn test_clean_dry_run_removes_nothing() {
+        let cache_dir = cache_tree();
+        let options = CleanOptions {
+            dry_run: true,
+            ..CleanOptions::default()
+        };
+
+        let report = clean(cache_dir.path(), &options).unwrap();
+
+        assert_eq!(report.removed.len(), 3);
+        assert_eq!(report.freed_bytes, 125);
+        assert!(cache_dir.path().join("components/nested/old.bin").exists());
+        assert!(cache_dir.path().join("responses/old.json").exists());
+    }
+
+    #[test]
+    fn test_clean_marked_directory_removes_everything_but_marker() {
+        let cache_dir = cache_tree();
+        fs::write(cache_dir.path().join(CACHE_MARKER_FILE), b"").unwrap();
+
+        let report = clean(cache_dir.path(), &CleanOptions::default()).unwrap();
+
+        assert_eq!(report.removed.len(), 4);
+        assert!(!cache_dir.path().join("notes.txt").exists());
+        assert!(cache_dir.path().join(CACHE_MARKER_FILE).exists());
+    }
+
+    #[test]
+    fn test_clean_refuses_root_and_home() {
+        let root = Path::new(if cfg!(windows) { r"C:\" } else { "/" });
+        assert!(matches!(
+            clean(root, &CleanOptions::default()),
+            Err(CacheError::Unsafe { .. })
+        ));
+
+        if let Some(home) = dirs::home_dir().filter(|home| home.exists()) {
+            assert!(matches!(
+                check_cache_dir(&home),
+                Err(CacheError::Unsafe { .. })
+            ));
+            if let Some(parent) = home.parent() {
+                assert!(matches!(
+                    check_cache_dir(parent),
+                    Err(CacheError::Unsafe { .. })
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_clear_removes_everything_but_marker() {
+        let cache_dir = cache_tree();
+        fs::write(cache_dir.path().join(CACHE_MARKER_FILE), b"").unwrap();
+
+        let report = clear(cache_dir.path()).unwrap();
+
+        assert_eq!(
+            removed(&report),
+            [
+                "components/nested/old.bin",
+                "components/new.bin",
+                "notes.txt",
+                "responses/old.json"
+            ]
+        );
+        assert_eq!(report.freed_bytes, 132);
+        let left: Vec<_> = fs::read_dir(cache_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(left, [CACHE_MARKER_FILE]);
+    }
+
+    #[test]
+    fn test_clear_refuses_root_and_missing_directory() {
+        let root = Path::new(if cfg!(windows) { r"C:\" } else { "/" });
+        assert!(matches!(clear(root), Err(CacheError::Unsafe { .. })));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let report = clear(&temp_dir.path().join("cache")).unwrap();
+        assert!(report.removed.is_empty());
+    }
+
+    #[test]
+    fn test_clean_missing_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let report = clean(&temp_dir.path().join("cache"), &CleanOptions::default()).unwrap();
+
+        assert!(report.removed.is_empty());
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_age("15m"), Ok(Duration::from_secs(900)));
+        assert_eq!(parse_age("12h"), Ok(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_age("7d"), Ok(Duration::from_secs(7 * 86400)));
+        assert_eq!(parse_age("2w"), Ok(Duration::from_secs(14 * 86400)));
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("7y").is_err());
+        assert!(parse_age("-1d").is_err());
+    }
+}
@@ -0,0 +1,317 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+
+/// When a log file is rotated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    /// New file every day, suffixed with the date
+    #[default]
+    Daily,
+    /// New file every hour, suffixed with the date and hour
+    Hourly,
+    /// Single file that grows without bound
+    Never,
+    /// Rotate once the file reaches a size limit (app.log → app.log.1)
+    Size,
+}
+
+/// Log file written alongside the console output
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFile {
+    /// Level or `EnvFilter` directive for this file
+    pub level: String,
+    /// Rotated files kept besides the current one
+    pub max_files: usize,
+    /// Size limit for `LogRotation::Size`
+    pub max_size_bytes: u64,
+    /// Path of the current log file
+    pub path: PathBuf,
+    pub rotation: LogRotation,
+}
+
+impl LogFile {
+    /// Open the file for writing, creating its directory if missing
+    pub fn make_writer(&self) -> Result<BoxMakeWriter> {
+        let directory = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        fs::create_dir_all(directory)
+            .with_context(|| format!("Failed to create log directory: {}", directory.display()))?;
+
+        let file_name = self
+            .path
+            .file_name()
+            .with_context(|| format!("Log file path has no file name: {}", self.path.display()))?;
+
+        let rotation = match self.rotation {
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Never => Rotation::NEVER,
+            LogRotation::Size => {
+                let file = SizeRotatingFile::open(&self.path, self.max_size_bytes, self.max_files)
+                    .with_context(|| format!("Failed to open log file: {}", self.path.display()))?;
+                return Ok(BoxMakeWriter::new(Mutex::new(file)));
+            }
+        };
+
+        let mut builder = RollingFileAppender::builder()
+            .rotation(rotation)
+            .filename_prefix(file_name.to_string_lossy());
+        if self.rotation != LogRotation::Never {
+            // The appender keeps the current file plus `max_log_files - 1`
+            builder = builder.max_log_files(self.max_files + 1);
+        }
+
+        let appender = builder
+            .build(directory)
+            .with_context(|| format!("Failed to open log file: {}", self.path.display()))?;
+
+        Ok(BoxMakeWriter::new(appender))
+    }
+}
+
+/// Log file rotated by size, keeping numbered backups (app.log.1 is newest)
+pub struct SizeRotatingFile {
+    /// Number backups before the extension (ledger.1.jsonl)
+    backup_before_extension: bool,
+    file: File,
+    max_files: usize,
+    max_size: u64,
+    path: PathBuf,
+    size: u64,
+}
+
+impl SizeRotatingFile {
+    /// Open `path` for appending, continuing from its current size
+    pub fn open(path: &Path, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = open_append(path)?;
+        let size = file.metadata()?.len();
+
+        Ok(SizeRotatingFile {
+            backup_before_extension: false,
+            file,
+            max_files,
+            max_size,
+            path: path.to_path_buf(),
+            size,
+        })
+    }
+
+    /// Name backups ledger.1.jsonl instead of ledger.jsonl.1
+    ///
+    /// Keeps the extension, so backups are still recognized by their type.
+    pub fn backup_before_extension(mut self) -> Self {
+        self.backup_before_extension = true;
+        self
+    }
+
+    /// Path of the `index`th backup
+    fn backup_path(&self, index: usize) -> PathBuf {
+        backup_path(&self.path, index, self.backup_before_extension)
+    }
+
+    /// Shift backups up by one and start a new current file
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                ignore_not_found(fs::rename(
+                    self.backup_path(index),
+                    self.backup_path(index + 1),
+                ))?;
+            }
+            fs::rename(&self.path, self.backup_path(1))?;
+            self.file = open_append(&self.path)?;
+        }
+
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotatingFile {
+    /// Writes one formatted event, so rotation never splits a line
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Path of the `index`th backup of `path`
+pub fn backup_path(path: &Path, index: usize, before_extension: bool) -> PathBuf {
+    match (before_extension, path.file_stem(), path.extension()) {
+        (true, Some(stem), Some(extension)) => {
+            let mut name = stem.to_os_string();
+            name.push(format!(".{}.", index));
+            name.push(extension);
+            path.with_file_name(name)
+        }
+        _ => {
+            let mut path = path.to_path_buf().into_os_string();
+            path.push(format!(".{}", index));
+            PathBuf::from(path)
+        }
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn ignore_not_found(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn log_file(path: PathBuf, rotation: LogRotation) -> LogFile {
+        LogFile {
+            level: "info".to_string(),
+            max_files: 2,
+            max_size_bytes: 16,
+            path,
+            rotation,
+        }
+    }
+
+    #[test]
+    fn test_size_rotation_keeps_max_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.log");
+        let mut file = SizeRotatingFile::open(&path, 16, 2).unwrap();
+
+        for line in [
+            "first line 1\n",
+            "second line\n",
+            "third line\n",
+            "fourth\n",
+        ] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("app.log.1")).unwrap(),
+            "third line\n"
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("app.log.2")).unwrap(),
+            "second line\n"
+        );
+        assert!(!temp_dir.path().join("app.log.3").exists());
+    }
+
+    #[test]
+    fn test_size_rotation_continues_existing_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.log");
+        fs::write(&path, "0123456789abcdef").unwrap();
+
+        let mut file = SizeRotatingFile::open(&path, 16, 1).unwrap();
+        file.write_all(b"next\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "next\n");
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("app.log.1")).unwrap(),
+            "0123456789abcdef"
+        );
+    }
+
+    #[test]
+    fn test_size_rotation_backup_before_extension() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("ledger.jsonl");
+        let mut file = SizeRotatingFile::open(&path, 8, 2)
+            .unwrap()
+            .backup_before_extension();
+
+        file.write_all(b"{\"a\":1}\n").unwrap();
+        file.write_all(b"{\"b\":2}\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"b\":2}\n");
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("ledger.1.jsonl")).unwrap(),
+            "{\"a\":1}\n"
+        );
+        assert!(!temp_dir.path().join("ledger.jsonl.1").exists());
+    }
+
+    #[test]
+    fn test_size_rotation_without_backups_truncates() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.log");
+        let mut file = SizeRotatingFile::open(&path, 8, 0).unwrap();
+
+        file.write_all(b"1234567\n").unwrap();
+        file.write_all(b"abc\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abc\n");
+        assert!(!temp_dir.path().join("app.log.1").exists());
+    }
+
+    #[test]
+    fn test_make_writer_creates_directory() {
+        let temp_dir = TempDir::new().unwrap();
+
+        for rotation in [
+            LogRotation::Daily,
+            LogRotation::Hourly,
+            LogRotation::Never,
+            LogRotation::Size,
+        ] {
+            let directory = temp_dir.path().join(format!("{:?}", rotation)).join("logs");
+            let file = log_file(directory.join("app.log"), rotation);
+
+            assert!(file.make_writer().is_ok(), "{:?}", rotation);
+            assert!(directory.is_dir(), "{:?}", rotation);
+        }
+    }
+
+    #[test]
+    fn test_rotation_from_toml() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            rotation: LogRotation,
+        }
+
+        for (value, rotation) in [
+            ("daily", LogRotation::Daily),
+            ("hourly", LogRotation::Hourly),
+            ("never", LogRotation::Never),
+            ("size", LogRotation::Size),
+        ] {
+            let wrapper: Wrapper = toml::from_str(&format!("rotation = \"{}\"", value)).unwrap();
+            assert_eq!(wrapper.rotation, rotation);
+        }
+
+        assert!(toml::from_str::<Wrapper>("rotation = \"weekly\"").is_err());
+    }
+}
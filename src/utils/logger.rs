@@ -1,9 +1,36 @@
-use anyhow::Result;
+use super::log_file::LogFile;
+use anyhow::{Result, bail};
+use std::str::FromStr;
 use std::sync::Once;
-use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, Registry, fmt, prelude::*};
 
 static INIT: Once = Once::new();
 
+/// Output format of log lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Compact human-readable lines
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log collectors like Loki or ELK
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => bail!("Unknown log format '{}' (expected pretty or json)", s),
+        }
+    }
+}
+
 /// Helper to create EnvFilter with fallback logic
 fn get_env_filter(level: &str) -> EnvFilter {
     EnvFilter::try_from_default_env()
@@ -11,23 +38,94 @@ fn get_env_filter(level: &str) -> EnvFilter {
         .unwrap_or_else(|_| EnvFilter::new("info"))
 }
 
+/// Build the output layer for a log format, writing to `writer`
+///
+/// `ansi` enables terminal colors, which only make sense on the console.
+fn fmt_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Pretty => fmt::layer()
+            .with_writer(writer)
+            .with_ansi(ansi)
+            .with_target(false) // Don't show module path (cleaner output)
+            .with_level(true) // Show log level
+            .compact() // Compact format
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .with_writer(writer)
+            .with_ansi(ansi)
+            .json()
+            .with_target(true)
+            .with_level(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    }
+}
+
+/// Build the stdout layer, filtered by `level` unless `RUST_LOG` is set
+fn console_layer(level: &str, format: LogFormat) -> Box<dyn Layer<Registry> + Send + Sync> {
+    // Create filter from level string
+    let filter = get_env_filter(level);
+
+    fmt_layer(format, std::io::stdout, true)
+        .with_filter(filter)
+        .boxed()
+}
+
+/// Console-only subscriber for logging before `init_logging_with_files`
+///
+/// Meant for `tracing::subscriber::with_default` while loading the config
+/// that decides which log files to open.
+pub fn console_subscriber(level: &str, format: LogFormat) -> impl Subscriber + Send + Sync {
+    tracing_subscriber::registry().with(console_layer(level, format))
+}
+
+/// Build one output layer per log file, each filtered to its own level
+fn file_layers(
+    format: LogFormat,
+    files: &[LogFile],
+) -> Result<Vec<Box<dyn Layer<Registry> + Send + Sync>>> {
+    files
+        .iter()
+        .map(|file| {
+            let filter = EnvFilter::try_new(&file.level).unwrap_or_else(|_| EnvFilter::new("info"));
+
+            Ok(fmt_layer(format, file.make_writer()?, false)
+                .with_filter(filter)
+                .boxed())
+        })
+        .collect()
+}
+
 /// Initialize tracing/logging system with the specified log level
 /// Safe to call multiple times - will only initialize once
 pub fn init_logging(level: &str) -> Result<()> {
+    init_logging_with_format(level, LogFormat::Pretty)
+}
+
+/// Initialize tracing/logging with the specified log level and output format
+/// Safe to call multiple times - only the first call takes effect
+pub fn init_logging_with_format(level: &str, format: LogFormat) -> Result<()> {
+    init_logging_with_files(level, format, &[])
+}
+
+/// Initialize tracing/logging to the console and to log files
+///
+/// Each file has its own level, independent of the console `level`. Files
+/// are opened (and their directories created) before anything is installed,
+/// so on error logging stays uninitialized and the caller can fall back.
+/// Safe to call multiple times - only the first call takes effect
+pub fn init_logging_with_files(level: &str, format: LogFormat, files: &[LogFile]) -> Result<()> {
+    let mut layers = file_layers(format, files)?;
+
     INIT.call_once(|| {
-        // Create filter from level string
-        let filter = get_env_filter(level);
-
-        // Set up console logging with clean format
-        let _ = tracing_subscriber::registry()
-            .with(
-                fmt::layer()
-                    .with_target(false) // Don't show module path (cleaner output)
-                    .with_level(true) // Show log level
-                    .compact(), // Compact format
-            )
-            .with(filter)
-            .try_init(); // Use try_init to avoid panic on multiple calls
+        layers.push(console_layer(level, format));
+
+        let _ = tracing_subscriber::registry().with(layers).try_init(); // Use try_init to avoid panic on multiple calls
     });
 
     Ok(())
@@ -82,6 +180,126 @@ mod tests {
         // All should succeed without panic due to Once::call_once
     }
 
+    #[test]
+    fn test_repeated_init_with_formats() {
+        // Only the first call installs a subscriber; later ones must not panic
+        assert!(init_logging_with_format("info", LogFormat::Json).is_ok());
+        assert!(init_logging_with_format("debug", LogFormat::Pretty).is_ok());
+        assert!(init_logging_with_format("info", LogFormat::Json).is_ok());
+    }
+
+    #[test]
+    fn test_log_format_from_str() {
+        assert_eq!("pretty".parse::<LogFormat>().unwrap(), LogFormat::Pretty);
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    /// Log one event inside a span with `format` and return the output
+    fn capture(format: LogFormat) -> String {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber =
+            tracing_subscriber::registry().with(fmt_layer(format, move || writer.clone(), false));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", method = "POST");
+            let _entered = span.enter();
+            tracing::info!(status = 200, "Request handled");
+        });
+
+        String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_json_format_is_structured() {
+        let output = capture(LogFormat::Json);
+        let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+
+        assert!(line["timestamp"].is_string());
+        assert_eq!(line["level"], "INFO");
+        assert!(line["target"].as_str().unwrap().contains("logger"));
+        assert_eq!(line["fields"]["message"], "Request handled");
+        assert_eq!(line["fields"]["status"], 200);
+        assert_eq!(line["span"]["name"], "request");
+        assert_eq!(line["span"]["method"], "POST");
+        assert_eq!(line["spans"][0]["name"], "request");
+    }
+
+    #[test]
+    fn test_pretty_format_is_not_json() {
+        let output = capture(LogFormat::Pretty);
+
+        assert!(output.contains("Request handled"));
+        assert!(serde_json::from_str::<serde_json::Value>(output.trim()).is_err());
+    }
+
+    #[test]
+    fn test_file_layers_use_their_own_levels() {
+        use crate::utils::log_file::LogRotation;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log_file = |name: &str, level: &str| LogFile {
+            level: level.to_string(),
+            max_files: 1,
+            max_size_bytes: 1024,
+            path: temp_dir.path().join("logs").join(name),
+            rotation: LogRotation::Never,
+        };
+        let files = [log_file("app.log", "debug"), log_file("error.log", "error")];
+
+        let subscriber =
+            tracing_subscriber::registry().with(file_layers(LogFormat::Pretty, &files).unwrap());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("debug event");
+            tracing::error!("error event");
+        });
+
+        let app_log = std::fs::read_to_string(&files[0].path).unwrap();
+        let error_log = std::fs::read_to_string(&files[1].path).unwrap();
+
+        assert!(app_log.contains("debug event"));
+        assert!(app_log.contains("error event"));
+        assert!(!error_log.contains("debug event"));
+        assert!(error_log.contains("error event"));
+
+        // No terminal escape codes in files
+        assert!(!app_log.contains('\u{1b}'));
+    }
+
+    #[test]
+    fn test_unwritable_log_file_is_an_error() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let blocker = temp_dir.path().join("not_a_dir");
+        std::fs::write(&blocker, "").unwrap();
+
+        let files = [LogFile {
+            level: "info".to_string(),
+            max_files: 1,
+            max_size_bytes: 1024,
+            path: blocker.join("app.log"),
+            rotation: Default::default(),
+        }];
+
+        assert!(init_logging_with_files("info", LogFormat::Pretty, &files).is_err());
+    }
+
     #[test]
     fn test_logging_graceful_fallback() {
         // Test that invalid log levels fall back to "info" gracefully
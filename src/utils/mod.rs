@@ -1,3 +1,5 @@
+pub mod cache;
+pub mod log_file;
 pub mod logger;
 
 pub use logger::*;
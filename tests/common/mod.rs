@@ -0,0 +1,274 @@
+//! End-to-end test harness: the real router served over TCP, with an LLM
+//! adapter talking HTTP to an in-process mock provider.
+
+#![allow(dead_code)] // Each test binary uses a different part of the harness
+
+use ai_messenger::adapter::traits::{
+    AdapterService, ChatMessage, Completion, Finish, GenerationOptions, LlmAdapter, ModelInfo,
+    ServiceError, Usage,
+};
+use ai_messenger::server::AppState;
+use ai_messenger::server::router::build_router;
+use async_trait::async_trait;
+use axum::{Json, Router, extract::State, http::StatusCode, response::IntoResponse, routing::post};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Canned behavior of the mock provider's `/api/chat` endpoint
+#[derive(Debug, Clone)]
+pub enum MockBehavior {
+    /// Reply with `content` and the given token counts
+    Reply {
+        content: String,
+        prompt_tokens: u32,
+        completion_tokens: u32,
+    },
+    /// Fail with HTTP 500
+    ServerError,
+    /// Reply 200 with a body that isn't valid JSON
+    Malformed,
+    /// Wait before replying with `content`
+    Slow { content: String, delay: Duration },
+}
+
+impl MockBehavior {
+    /// Successful reply with some token usage
+    pub fn reply(content: &str) -> Self {
+        MockBehavior::Reply {
+            content: content.to_string(),
+            prompt_tokens: 12,
+            completion_tokens: 30,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct MockState {
+    behavior: MockBehavior,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+/// In-process stand-in for an Ollama server
+pub struct MockProvider {
+    /// Base URL, e.g. `http://127.0.0.1:12345`
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl MockProvider {
+    /// Start a mock provider on a random local port
+    pub async fn start(behavior: MockBehavior) -> Self {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let state = MockState {
+            behavior,
+            requests: requests.clone(),
+        };
+        let app = Router::new()
+            .route("/api/chat", post(mock_chat))
+            .with_state(state);
+
+        MockProvider {
+            base_url: serve(app).await,
+            requests,
+        }
+    }
+
+    /// JSON bodies of all chat requests received so far
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn mock_chat(State(state): State<MockState>, Json(body): Json<Value>) -> impl IntoResponse {
+    let model = body["model"].clone();
+    state.requests.lock().unwrap().push(body);
+
+    let reply = |content: &str, prompt_tokens: u32, completion_tokens: u32| {
+        Json(json!({
+            "model": model,
+            "created_at": "2025-01-01T00:00:00Z",
+            "message": { "role": "assistant", "content": content },
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": prompt_tokens,
+            "eval_count": completion_tokens,
+        }))
+        .into_response()
+    };
+
+    match state.behavior {
+        MockBehavior::Reply {
+            content,
+            prompt_tokens,
+            completion_tokens,
+        } => reply(&content, prompt_tokens, completion_tokens),
+        MockBehavior::ServerError => {
+            (StatusCode::INTERNAL_SERVER_ERROR, "model crashed").into_response()
+        }
+        MockBehavior::Malformed => (StatusCode::OK, "{\"message\": ").into_response(),
+        MockBehavior::Slow { content, delay } => {
+            tokio::time::sleep(delay).await;
+            reply(&content, 0, 0)
+        }
+    }
+}
+
+/// Native LLM adapter speaking the Ollama chat API over HTTP
+///
+/// Stands in for the WASM adapter so tests don't need a module on disk.
+pub struct OllamaTestAdapter {
+    base_url: String,
+    client: reqwest::Client,
+    model: String,
+}
+
+impl OllamaTestAdapter {
+    /// Adapter for the provider at `base_url`, giving up after `timeout`
+    pub fn new(base_url: &str, timeout: Duration) -> Self {
+        OllamaTestAdapter {
+            base_url: base_url.to_string(),
+            client: reqwest::Client::builder().timeout(timeout).build().unwrap(),
+            model: "llama3.2".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl AdapterService for OllamaTestAdapter {
+    fn service_name(&self) -> &'static str {
+        "llm"
+    }
+
+    fn provider_name(&self) -> &str {
+        "ollama"
+    }
+
+    fn version(&self) -> &str {
+        "test"
+    }
+
+    fn is_ready(&self) -> bool {
+        true
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ServiceError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LlmAdapter for OllamaTestAdapter {
+    async fn send_message(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<String, ServiceError> {
+        Ok(self.complete(messages, options).await?.content)
+    }
+
+    /// Reply with the token counts Ollama reports
+    async fn complete(
+        &mut self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> Result<Completion, ServiceError> {
+        let body = json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": false,
+            "options": {
+                "temperature": options.temperature,
+                "top_p": options.top_p,
+                "num_predict": options.max_tokens,
+                "stop": options.stop,
+                "seed": options.seed,
+            },
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    ServiceError::Timeout(format!("Provider request timed out: {e}"))
+                } else {
+                    ServiceError::ExecutionError(format!("Provider request failed: {e}"))
+                }
+            })?;
+
+        if !response.status().is_success() {
+            return Err(ServiceError::ServiceUnavailable(format!(
+                "Provider returned {}",
+                response.status()
+            )));
+        }
+
+        let reply: Value = response.json().await.map_err(|e| {
+            ServiceError::ExecutionError(format!("Malformed provider response: {e}"))
+        })?;
+
+        let content = reply["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| {
+                ServiceError::ExecutionError("Provider response has no content".to_string())
+            })?;
+        let prompt_tokens = reply["prompt_eval_count"].as_u64().unwrap_or(0) as u32;
+        let completion_tokens = reply["eval_count"].as_u64().unwrap_or(0) as u32;
+
+        Ok(Completion {
+            content,
+            finish: Finish::stop(),
+            usage: Some(Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            }),
+        })
+    }
+
+    async fn get_model_info(&self) -> Result<ModelInfo, ServiceError> {
+        Ok(ModelInfo {
+            name: self.model.clone(),
+            version: "test".to_string(),
+            context_length: None,
+            parameters: None,
+        })
+    }
+}
+
+/// Serve the application router for `state` and return its base URL
+pub async fn spawn_app(state: AppState) -> String {
+    serve(build_router("", state)).await
+}
+
+/// Serve a router on a random local port and return its base URL
+async fn serve(app: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    format!("http://{}", addr)
+}
+
+/// POST a JSON body and return the status and parsed JSON response
+pub async fn post_json(url: &str, body: Value) -> (StatusCode, Value) {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+    let body = response.json().await.unwrap_or(Value::Null);
+
+    (status, body)
+}
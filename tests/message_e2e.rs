@@ -0,0 +1,124 @@
+//! POST /v1/message through the router, an adapter and a mock provider
+
+mod common;
+
+use ai_messenger::server::AppState;
+use axum::http::StatusCode;
+use common::{MockBehavior, MockProvider, OllamaTestAdapter, post_json, spawn_app};
+use serde_json::json;
+use std::time::Duration;
+
+const ADAPTER_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Serve the app with an adapter pointing at `provider`
+async fn app_for(provider: &MockProvider) -> String {
+    let adapter = OllamaTestAdapter::new(&provider.base_url, ADAPTER_TIMEOUT);
+    spawn_app(AppState::with_llm(adapter)).await
+}
+
+fn hello() -> serde_json::Value {
+    json!({ "messages": [{ "role": "user", "content": "Hello" }] })
+}
+
+#[tokio::test]
+async fn test_message_success() {
+    let provider = MockProvider::start(MockBehavior::reply("Hi there!")).await;
+    let app = app_for(&provider).await;
+
+    let (status, body) = post_json(&format!("{}/v1/message/assistant", app), hello()).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["success"], true);
+    assert_eq!(body["message"]["role"], "assistant");
+    assert_eq!(body["message"]["content"], "Hi there!");
+    assert_eq!(body["model"], "ollama");
+    assert_eq!(body["usage"]["prompt_tokens"], 12);
+    assert_eq!(body["usage"]["completion_tokens"], 30);
+    assert_eq!(body["usage"]["total_tokens"], 42);
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["messages"][0]["content"], "Hello");
+    assert_eq!(requests[0]["stream"], false);
+}
+
+#[tokio::test]
+async fn test_message_options_reach_provider() {
+    let provider = MockProvider::start(MockBehavior::reply("ok")).await;
+    let app = app_for(&provider).await;
+
+    let mut request = hello();
+    request["options"] = json!({ "temperature": 0.25, "max_tokens": 64, "seed": 7 });
+    let (status, body) = post_json(&format!("{}/v1/message/assistant", app), request).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["parameters"]["seed"], 7);
+
+    let options = &provider.requests()[0]["options"];
+    assert_eq!(options["temperature"], 0.25);
+    assert_eq!(options["num_predict"], 64);
+    assert_eq!(options["seed"], 7);
+}
+
+#[tokio::test]
+async fn test_message_provider_error() {
+    let provider = MockProvider::start(MockBehavior::ServerError).await;
+    let app = app_for(&provider).await;
+
+    let (status, body) = post_json(&format!("{}/v1/message/assistant", app), hello()).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["success"], false);
+    assert_eq!(body["error_type"], "service_unavailable");
+}
+
+#[tokio::test]
+async fn test_message_malformed_provider_response() {
+    let provider = MockProvider::start(MockBehavior::Malformed).await;
+    let app = app_for(&provider).await;
+
+    let (status, body) = post_json(&format!("{}/v1/message/assistant", app), hello()).await;
+
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(body["error_type"], "adapter_error");
+}
+
+#[tokio::test]
+async fn test_message_slow_provider() {
+    // Within the adapter timeout the reply arrives
+    let provider = MockProvider::start(MockBehavior::Slow {
+        content: "finally".to_string(),
+        delay: Duration::from_millis(50),
+    })
+    .await;
+    let app = app_for(&provider).await;
+
+    let (status, body) = post_json(&format!("{}/v1/message/assistant", app), hello()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["message"]["content"], "finally");
+
+    // Beyond it the request fails instead of hanging
+    let provider = MockProvider::start(MockBehavior::Slow {
+        content: "too late".to_string(),
+        delay: ADAPTER_TIMEOUT * 4,
+    })
+    .await;
+    let app = app_for(&provider).await;
+
+    let (status, body) = post_json(&format!("{}/v1/message/assistant", app), hello()).await;
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(body["error_type"], "timeout");
+}
+
+#[tokio::test]
+async fn test_message_invalid_options_skip_provider() {
+    let provider = MockProvider::start(MockBehavior::reply("unused")).await;
+    let app = app_for(&provider).await;
+
+    let mut request = hello();
+    request["options"] = json!({ "temperature": 9.0 });
+    let (status, _) = post_json(&format!("{}/v1/message/assistant", app), request).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(provider.requests().is_empty());
+}
@@ -0,0 +1,113 @@
+//! POST /v1/message through the router and the built-in `mock` LLM provider,
+//! loaded from config like any other adapter
+
+mod common;
+
+use ai_messenger::config::Config;
+use ai_messenger::server::AppState;
+use axum::http::StatusCode;
+use common::{post_json, spawn_app};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// Serve the app with `[adapters.llm]` set to the mock and `mock_config`
+/// as its `[adapters.llm.config]`
+async fn app_with_mock(mock_config: &str) -> (String, tempfile::TempDir) {
+    let data_dir = tempfile::TempDir::new().unwrap();
+    let config: Config = toml::from_str(&format!(
+        r#"
+[adapters.llm]
+provider = "mock"
+
+[adapters.llm.config]
+{mock_config}
+
+[adapters.storage]
+provider = "memory"
+
+[usage_log]
+enabled = false
+"#
+    ))
+    .unwrap();
+
+    let mut state = AppState::from_config(&config, data_dir.path())
+        .await
+        .unwrap();
+    state.request_timeouts.message = Duration::from_millis(200);
+    (spawn_app(state).await, data_dir)
+}
+
+fn hello() -> Value {
+    json!({ "messages": [{ "role": "user", "content": "Hello" }] })
+}
+
+#[tokio::test]
+async fn test_mock_scripted_replies() {
+    let (app, _data_dir) = app_with_mock(r#"responses = ["Hi there!"]"#).await;
+    let url = format!("{}/v1/message/assistant", app);
+
+    let (status, body) = post_json(&url, hello()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["message"]["content"], "Hi there!");
+    assert_eq!(body["model"], "mock");
+
+    // The script is used up: the user message is echoed
+    let (status, body) = post_json(&url, hello()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["message"]["content"], "Hello");
+}
+
+#[tokio::test]
+async fn test_mock_streamed_reply() {
+    let (app, _data_dir) = app_with_mock(concat!(
+        "chunk_latency_ms = 5\n",
+        r#"responses = [{ chunks = ["Hi", " there", "!"] }]"#
+    ))
+    .await;
+
+    let mut request = hello();
+    request["stream"] = json!(true);
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/message/assistant", app))
+        .header("accept", "application/x-ndjson")
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response.text().await.unwrap();
+    let events: Vec<Value> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let chunks: Vec<_> = events
+        .iter()
+        .filter(|event| event["type"] == "chunk")
+        .map(|event| event["content"].as_str().unwrap())
+        .collect();
+    assert_eq!(chunks, ["Hi", " there", "!"]);
+    assert_eq!(events.last().unwrap()["type"], "done");
+}
+
+#[tokio::test]
+async fn test_mock_scripted_failure() {
+    let (app, _data_dir) = app_with_mock(
+        r#"responses = [{ error = "rate limited", status = 429, retry_after_secs = 3 }]"#,
+    )
+    .await;
+
+    let (status, body) = post_json(&format!("{}/v1/message/assistant", app), hello()).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["success"], false);
+    assert_eq!(body["error_type"], "provider_error");
+}
+
+#[tokio::test]
+async fn test_mock_latency_beyond_timeout() {
+    let (app, _data_dir) = app_with_mock("latency_ms = 2000").await;
+
+    let (status, _) = post_json(&format!("{}/v1/message/assistant", app), hello()).await;
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+}